@@ -0,0 +1,102 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Benchmarks for the `FromWire`/`ToWire` hot path.
+//!
+//! Each sample message is serialized into a `Cursor` and parsed back out
+//! through a `BumpArena`; throughput is reported in bytes per second of
+//! encoded message.
+//!
+//! Run with `cargo bench --features bench`.
+
+use criterion::black_box;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::Criterion;
+use criterion::Throughput;
+
+use manticore::io::Cursor;
+use manticore::mem::Arena as _;
+use manticore::mem::BumpArena;
+use manticore::protocol::cerberus::device_id::DeviceIdResponse;
+use manticore::protocol::cerberus::device_id::DeviceIdentifier;
+use manticore::protocol::cerberus::get_cert::GetCertResponse;
+use manticore::protocol::cerberus::DeviceId;
+use manticore::protocol::cerberus::GetCert;
+use manticore::protocol::wire::FromWire as _;
+use manticore::protocol::wire::ToWire as _;
+use manticore::protocol::Command;
+use manticore::protocol::Resp;
+
+/// The size of the certificate window in the large sample; this is a typical
+/// chunk size for reading a certificate chain.
+const CERT_WINDOW: usize = 1024;
+
+/// Benchmarks serializing and parsing `msg`, a response to the command `C`,
+/// under the group `name`.
+fn bench_response<C>(c: &mut Criterion, name: &str, msg: Resp<'static, C>)
+where
+    C: for<'a> Command<'a>,
+{
+    let mut buf = vec![0; 4096];
+    let len = {
+        let mut cursor = Cursor::new(&mut buf);
+        msg.to_wire(&mut cursor).unwrap();
+        cursor.consumed_len()
+    };
+    let bytes = buf[..len].to_vec();
+    let mut arena = BumpArena::new(vec![0; 4096]);
+
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Bytes(len as u64));
+
+    group.bench_function("to_wire", |b| {
+        b.iter(|| {
+            let mut cursor = Cursor::new(&mut buf);
+            black_box(&msg).to_wire(&mut cursor).unwrap();
+            black_box(cursor.consumed_len());
+        })
+    });
+
+    group.bench_function("from_wire", |b| {
+        b.iter(|| {
+            let mut r = black_box(&bytes[..]);
+            let msg = Resp::<C>::from_wire(&mut r, &arena).unwrap();
+            black_box(msg);
+            arena.reset();
+        })
+    });
+
+    group.finish();
+}
+
+fn device_id(c: &mut Criterion) {
+    bench_response::<DeviceId>(
+        c,
+        "device_id",
+        DeviceIdResponse {
+            id: DeviceIdentifier {
+                vendor_id: 0x1414,
+                device_id: 0x0001,
+                subsys_vendor_id: 0x1414,
+                subsys_id: 0x0002,
+            },
+        },
+    );
+}
+
+fn get_cert(c: &mut Criterion) {
+    bench_response::<GetCert>(
+        c,
+        "get_cert",
+        GetCertResponse {
+            slot: 0,
+            cert_number: 1,
+            data: &[0xa5; CERT_WINDOW],
+        },
+    );
+}
+
+criterion_group!(benches, device_id, get_cert);
+criterion_main!(benches);
@@ -25,9 +25,32 @@ pub mod support;
 
 #[cfg(test)]
 mod tests {
+    mod busy;
+    mod cfm;
     mod challenge;
+    mod client;
+    mod compression;
     mod device_queries;
+    mod event_log;
+    mod framing;
+    mod from_stream;
+    mod fw_verify;
+    mod host_state;
+    mod key_type;
+    mod message_type;
+    mod payload;
+    mod protocol_version;
+    mod rate_limit;
+    mod read_budget;
+    mod reset_config;
+    mod serve_n;
+    mod shutdown;
     mod spdm_device_queries;
+    mod staging;
+    mod streaming;
+    mod trailing;
+    mod unseal;
+    mod vendor;
 }
 
 /// End-to-end tests for Manticore.
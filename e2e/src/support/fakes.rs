@@ -9,6 +9,16 @@ use std::convert::TryInto as _;
 use std::time::Duration;
 use std::time::Instant;
 
+use manticore::crypto::hash;
+use manticore::crypto::ring;
+use manticore::crypto::sig;
+use manticore::fail;
+use manticore::protocol::cerberus::get_host_state::HostResetState;
+use manticore::protocol::cerberus::get_log::LogType;
+use manticore::protocol::cerberus::reset_config::ResetKind;
+use manticore::protocol::cerberus::unseal::Policy;
+use manticore::Result;
+
 /// A fake `Identity` that returns fixed values.
 pub struct Identity {
     firmware_version: Vec<u8>,
@@ -82,3 +92,317 @@ impl manticore::hardware::Reset for Reset {
         self.startup_time.elapsed()
     }
 }
+
+/// A fake hash `Engine` that reports itself as busy a fixed number of times
+/// before delegating to a real engine.
+pub struct BusyHasher<E> {
+    engine: E,
+    busy_count: u32,
+}
+
+impl<E> BusyHasher<E> {
+    /// Creates a new `BusyHasher`, which will reject the first `busy_count`
+    /// operations it is asked to start.
+    pub fn new(engine: E, busy_count: u32) -> Self {
+        Self { engine, busy_count }
+    }
+}
+
+impl<E: hash::Engine> hash::Engine for BusyHasher<E> {
+    fn supports(&mut self, algo: hash::Algo) -> bool {
+        self.engine.supports(algo)
+    }
+
+    fn start_raw(
+        &mut self,
+        algo: hash::Algo,
+        key: Option<&[u8]>,
+    ) -> Result<(), hash::Error> {
+        if self.busy_count > 0 {
+            self.busy_count -= 1;
+            return Err(fail!(hash::Error::Busy));
+        }
+        self.engine.start_raw(algo, key)
+    }
+
+    fn write_raw(&mut self, data: &[u8]) -> Result<(), hash::Error> {
+        self.engine.write_raw(data)
+    }
+
+    fn finish_raw(&mut self, out: &mut [u8]) -> Result<(), hash::Error> {
+        self.engine.finish_raw(out)
+    }
+
+    fn compare_raw(&mut self, expected: &[u8]) -> Result<(), hash::Error> {
+        self.engine.compare_raw(expected)
+    }
+}
+
+/// A fake `VendorHandler` that echoes back the payload of every message it
+/// receives.
+pub struct EchoVendor {
+    vendor_id: u16,
+}
+
+impl EchoVendor {
+    /// Creates a new `EchoVendor` for messages with the given vendor ID.
+    pub fn new(vendor_id: u16) -> Self {
+        Self { vendor_id }
+    }
+}
+
+impl manticore::server::VendorHandler for EchoVendor {
+    fn vendor_id(&self) -> u16 {
+        self.vendor_id
+    }
+
+    fn handle(
+        &mut self,
+        _: u8,
+        payload: &[u8],
+        out: &mut dyn manticore::io::Write,
+    ) -> Result<(), manticore::protocol::cerberus::Error> {
+        out.write_all(payload)?;
+        Ok(())
+    }
+}
+
+/// A fake `ConfigReset` that only supports factory-default resets, which it
+/// merely counts.
+pub struct ConfigReset {
+    authority: ring::rsa::Verify256,
+    resets: u32,
+}
+
+impl ConfigReset {
+    /// Creates a new `ConfigReset` whose resets are authorized by
+    /// `authority`.
+    pub fn new(authority: ring::rsa::Verify256) -> Self {
+        Self {
+            authority,
+            resets: 0,
+        }
+    }
+}
+
+impl manticore::server::ConfigReset for ConfigReset {
+    fn authority(&mut self, kind: ResetKind) -> Option<&mut dyn sig::Verify> {
+        match kind {
+            ResetKind::FactoryDefault => Some(&mut self.authority),
+            _ => None,
+        }
+    }
+
+    fn reset(
+        &mut self,
+        kind: ResetKind,
+    ) -> Result<(), manticore::protocol::cerberus::Error> {
+        self.resets += 1;
+        log::info!("performed {:?} reset #{}", kind, self.resets);
+        Ok(())
+    }
+}
+
+/// A fake `HostControl` that records the state it was last set to.
+pub struct HostControl {
+    state: HostResetState,
+    authority: Option<ring::rsa::Verify256>,
+}
+
+impl HostControl {
+    /// Creates a new `HostControl` for a running host, whose state may be
+    /// changed with the authorization of `authority`.
+    pub fn new(authority: Option<ring::rsa::Verify256>) -> Self {
+        Self {
+            state: HostResetState::HostRunning,
+            authority,
+        }
+    }
+}
+
+impl manticore::server::HostControl for HostControl {
+    fn state(&self) -> HostResetState {
+        self.state
+    }
+
+    fn authority(&mut self) -> Option<&mut dyn sig::Verify> {
+        self.authority.as_mut().map(|a| a as &mut dyn sig::Verify)
+    }
+
+    fn assert_reset(
+        &mut self,
+    ) -> Result<(), manticore::protocol::cerberus::Error> {
+        log::info!("holding host in reset");
+        self.state = HostResetState::HostInReset;
+        Ok(())
+    }
+
+    fn deassert_reset(
+        &mut self,
+    ) -> Result<(), manticore::protocol::cerberus::Error> {
+        log::info!("releasing host from reset");
+        self.state = HostResetState::HostRunning;
+        Ok(())
+    }
+}
+
+/// A fake `ManifestStore` that keeps everything in RAM.
+#[derive(Default)]
+pub struct Manifests {
+    receiving: Option<Vec<u8>>,
+    staged: Option<Vec<u8>>,
+    active: Option<Vec<u8>>,
+}
+
+impl Manifests {
+    /// The largest manifest this store will accept.
+    pub const MAX_LEN: usize = 4096;
+
+    /// Creates a new, empty `Manifests`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl manticore::server::ManifestStore for Manifests {
+    fn prepare(
+        &mut self,
+        len: usize,
+    ) -> Result<(), manticore::protocol::cerberus::Error> {
+        self.staged = None;
+        self.receiving = None;
+        if len > Self::MAX_LEN {
+            return Err(fail!(
+                manticore::protocol::cerberus::Error::ResourceLimit
+            ));
+        }
+        self.receiving = Some(vec![0; len]);
+        Ok(())
+    }
+
+    fn receiving(&mut self) -> Option<&mut [u8]> {
+        self.receiving.as_deref_mut()
+    }
+
+    fn stage(&mut self) -> Result<(), manticore::protocol::cerberus::Error> {
+        self.staged = self.receiving.take();
+        Ok(())
+    }
+
+    fn staged(&self) -> Option<&[u8]> {
+        self.staged.as_deref()
+    }
+
+    fn activate(&mut self) -> Result<(), manticore::protocol::cerberus::Error> {
+        match self.staged.take() {
+            Some(staged) => {
+                log::info!("activated a {}-byte manifest", staged.len());
+                self.active = Some(staged);
+                Ok(())
+            }
+            None => {
+                Err(fail!(manticore::protocol::cerberus::Error::OutOfRange))
+            }
+        }
+    }
+
+    fn active(&self) -> Option<&[u8]> {
+        self.active.as_deref()
+    }
+}
+
+/// A fake `LogStore` with an attestation log and a debug log.
+///
+/// Only the debug log may be cleared, and only if there is an authority for
+/// it.
+pub struct Logs<'a> {
+    attestation: manticore::server::TcgEventLog<'a>,
+    debug: Vec<u8>,
+    authority: Option<ring::rsa::Verify256>,
+}
+
+impl<'a> Logs<'a> {
+    /// Creates a new `Logs` with the given contents, whose debug log may be
+    /// cleared with the authorization of `authority`.
+    pub fn new(
+        attestation: manticore::server::TcgEventLog<'a>,
+        debug: Vec<u8>,
+        authority: Option<ring::rsa::Verify256>,
+    ) -> Self {
+        Self {
+            attestation,
+            debug,
+            authority,
+        }
+    }
+}
+
+impl manticore::server::LogStore for Logs<'_> {
+    fn read(&self, log: LogType) -> Option<&[u8]> {
+        match log {
+            LogType::Debug => Some(&self.debug),
+            _ => self.attestation.read(log),
+        }
+    }
+
+    fn clear(
+        &mut self,
+        log: LogType,
+    ) -> Result<(), manticore::protocol::cerberus::Error> {
+        match log {
+            LogType::Debug => {
+                self.debug.clear();
+                Ok(())
+            }
+            _ => self.attestation.clear(log),
+        }
+    }
+
+    fn authority(&mut self, log: LogType) -> Option<&mut dyn sig::Verify> {
+        match log {
+            LogType::Debug => {
+                self.authority.as_mut().map(|a| a as &mut dyn sig::Verify)
+            }
+            _ => None,
+        }
+    }
+
+    fn pcr(&self, index: usize) -> Option<&[u8; 32]> {
+        self.attestation.pcr(index)
+    }
+}
+
+/// A fake `Unsealer`, whose "sealed" blobs are the little-endian PCR mask of
+/// the policy they are sealed to, followed by the key in the clear.
+pub struct Unsealer;
+
+impl Unsealer {
+    /// Seals `key` to `policy`, in the format this `Unsealer` expects.
+    pub fn seal(policy: &Policy, key: &[u8]) -> Vec<u8> {
+        let mut blob = policy.pcr_mask.to_le_bytes().to_vec();
+        blob.extend_from_slice(key);
+        blob
+    }
+}
+
+impl manticore::server::Unsealer for Unsealer {
+    fn max_key_len(&self) -> usize {
+        64
+    }
+
+    fn unseal(
+        &mut self,
+        policy: &Policy,
+        blob: &[u8],
+        key: &mut [u8],
+    ) -> Result<usize, manticore::protocol::cerberus::Error> {
+        use manticore::protocol::cerberus::Error;
+        if blob.len() < 4 || blob[..4] != policy.pcr_mask.to_le_bytes() {
+            return Err(fail!(Error::Unauthorized));
+        }
+        let sealed = &blob[4..];
+        let out = key.get_mut(..sealed.len()).ok_or(Error::ResourceLimit)?;
+        out.copy_from_slice(sealed);
+        Ok(sealed.len())
+    }
+}
@@ -18,6 +18,8 @@ use std::time::Duration;
 use manticore::cert;
 use manticore::cert::CertFormat;
 use manticore::crypto::ring;
+use manticore::crypto::sig;
+use manticore::hardware::clock::SystemClock;
 use manticore::mem::Arena;
 use manticore::mem::BumpArena;
 use manticore::net;
@@ -25,12 +27,19 @@ use manticore::protocol;
 use manticore::protocol::cerberus;
 use manticore::protocol::cerberus::capabilities;
 use manticore::protocol::cerberus::device_id::DeviceIdentifier;
+use manticore::protocol::cerberus::get_device_state;
 use manticore::protocol::spdm;
 use manticore::server;
+use manticore::server::nonce::RecentNonces;
 use manticore::server::pa_rot::PaRot;
+use manticore::server::rate_limit;
+use manticore::server::rate_limit::TokenBuckets;
+use manticore::session;
 use manticore::session::ring::Session;
 use manticore::Result;
 
+use enumflags2::BitFlags;
+
 use crate::support::fakes;
 use crate::support::tcp;
 use crate::support::tcp::TcpHostPort;
@@ -60,6 +69,36 @@ pub struct Options {
     /// (unused by the transport).
     pub max_packet_size: u16,
 
+    /// Whether to advertise, and accept, compressed payloads.
+    pub compression: bool,
+
+    /// Whether to advertise, and send, streamed replies without a length
+    /// prefix.
+    pub streaming: bool,
+
+    /// The protocol versions to accept during capabilities negotiation.
+    pub protocol_versions: Vec<capabilities::ProtocolVersion>,
+
+    /// Whether to sign timestamps into `Challenge` responses, when asked to.
+    pub signed_timestamps: bool,
+
+    /// Whether Cerberus-over-TCP frames carry the [`tcp::MAGIC`] marker.
+    pub magic: bool,
+
+    /// Whether Cerberus-over-TCP frames carry an MCTP message type.
+    pub message_type: bool,
+
+    /// Limits on how long a client may take to send a request.
+    pub read_budget: tcp::ReadBudget,
+
+    /// The largest payload a request header may declare; see
+    /// [`TcpHostPort::set_payload_ceiling()`].
+    pub payload_ceiling: usize,
+
+    /// The number of Cerberus requests to serve before exiting; `None` serves
+    /// requests forever.
+    pub max_requests: Option<usize>,
+
     /// The timeout to report for a non-cryptographic operation
     /// (unused other than for capabilities requests).
     pub regular_timeout: Duration,
@@ -79,8 +118,92 @@ pub struct Options {
     /// The keypair to use with the certificate chain.
     pub alias_keypair: Option<KeyPairFormat>,
 
+    /// A PKCS#8-encoded ECDSA P-256 keypair, for `Challenge`s that ask to be
+    /// signed with an ECDSA key.
+    ///
+    /// `Challenge`s that ask for an RSA key are signed with
+    /// [`Options::alias_keypair`].
+    pub ecdsa_alias_keypair: Option<Vec<u8>>,
+
     /// The contents of PMR #0.
     pub pmr0: Vec<u8>,
+
+    /// Events to record in the attestation log, as `(pcr, event_type, data)`
+    /// triples.
+    pub attestation_events: Vec<(u32, u32, Vec<u8>)>,
+
+    /// The initial contents of the debug log.
+    pub debug_log: Vec<u8>,
+
+    /// The keypair whose public half authorizes clearing the debug log.
+    ///
+    /// If `None`, the debug log cannot be cleared.
+    pub log_authority: Option<KeyPairFormat>,
+
+    /// The number of hashing operations to reject as busy, before the RoT
+    /// starts to succeed at them.
+    pub hash_busy_count: u32,
+
+    /// Vendor IDs to accept `VendorCommand`s for; each one is handled by a
+    /// [`fakes::EchoVendor`].
+    pub vendor_ids: Vec<u16>,
+
+    /// A command type to rate-limit, and how many requests for it to serve.
+    ///
+    /// The limit is only refilled once an hour, so that tests see a fixed
+    /// budget.
+    pub rate_limit: Option<(cerberus::CommandType, u32)>,
+
+    /// How long a session may remain active after its key exchange.
+    pub session_lifetime: Option<Duration>,
+
+    /// The runtime state to report from `GetDeviceState`.
+    pub device_state: get_device_state::State,
+
+    /// The keypair whose public half authorizes `ResetConfig` requests.
+    ///
+    /// If `None`, `ResetConfig` is unsupported.
+    pub reset_authority: Option<KeyPairFormat>,
+
+    /// The keypair whose public half PFMs must be signed with.
+    ///
+    /// If `None`, PFM updates are unsupported.
+    pub pfm_authority: Option<KeyPairFormat>,
+
+    /// The keypair whose public half CFMs must be signed with.
+    ///
+    /// If `None`, CFM updates are unsupported.
+    pub cfm_authority: Option<KeyPairFormat>,
+
+    /// The keypair whose public half PCDs must be signed with.
+    ///
+    /// If `None`, PCD updates are unsupported.
+    pub pcd_authority: Option<KeyPairFormat>,
+
+    /// Whether to support `Unseal`, with a [`fakes::Unsealer`].
+    pub unseal: bool,
+
+    /// Whether to support `GetHostState` and `SetHostState`, with a
+    /// [`fakes::HostControl`].
+    pub host_control: bool,
+
+    /// The keypair whose public half authorizes `SetHostState`.
+    ///
+    /// If `None`, the host's state can only be read.
+    pub host_authority: Option<KeyPairFormat>,
+
+    /// How many firmware verifications may be in progress at once.
+    ///
+    /// If zero, `BeginVerify` and friends are unsupported.
+    pub verify_slots: usize,
+
+    /// How long a firmware verification may go without a chunk before it is
+    /// abandoned.
+    pub verify_idle_timeout: Duration,
+
+    /// Whether to reject requests with unknown trailing fields, rather than
+    /// skipping over them.
+    pub strict_parsing: bool,
 }
 
 /// See [`Options::protocol`].
@@ -108,6 +231,15 @@ impl Default for Options {
             resets_since_power_on: 5,
             max_message_size: 1024,
             max_packet_size: 256,
+            compression: false,
+            streaming: false,
+            protocol_versions: vec![capabilities::ProtocolVersion::CURRENT],
+            signed_timestamps: false,
+            magic: false,
+            message_type: false,
+            read_budget: Default::default(),
+            payload_ceiling: tcp::DEFAULT_PAYLOAD_CEILING,
+            max_requests: None,
             regular_timeout: Duration::from_millis(30),
             crypto_timeout: Duration::from_millis(200),
             device_id: DeviceIdentifier {
@@ -119,7 +251,93 @@ impl Default for Options {
             cert_chain: vec![],
             cert_format: CertFormat::RiotX509,
             alias_keypair: None,
+            ecdsa_alias_keypair: None,
             pmr0: b"<pmr0 unspecified>".to_vec(),
+            attestation_events: vec![],
+            debug_log: vec![],
+            log_authority: None,
+            hash_busy_count: 0,
+            vendor_ids: vec![],
+            rate_limit: None,
+            session_lifetime: None,
+            device_state: get_device_state::State::Running,
+            reset_authority: None,
+            pfm_authority: None,
+            cfm_authority: None,
+            pcd_authority: None,
+            unseal: false,
+            host_control: false,
+            host_authority: None,
+            verify_slots: 0,
+            verify_idle_timeout: Duration::from_secs(10),
+            strict_parsing: false,
+        }
+    }
+}
+
+/// Options for the capabilities a test client advertises; see
+/// [`ClientOptions::capabilities()`].
+///
+/// By default, the client is an RSA-only host that supports neither
+/// compression nor streaming, and speaks the current protocol version.
+#[derive(Copy, Clone, Debug)]
+pub struct ClientOptions {
+    /// Whether the client supports compressed messages.
+    pub compression: bool,
+    /// Whether the client supports streamed replies.
+    pub streaming: bool,
+    /// Whether the client supports ECDSA over P-256, alongside RSA.
+    pub ecdsa: bool,
+    /// The hash algorithms the client supports.
+    pub hash_algos: capabilities::HashAlgos,
+    /// The protocol version the client speaks.
+    pub protocol_version: capabilities::ProtocolVersion,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            compression: false,
+            streaming: false,
+            ecdsa: false,
+            hash_algos: capabilities::HashAlgos::empty(),
+            protocol_version: capabilities::ProtocolVersion::CURRENT,
+        }
+    }
+}
+
+impl ClientOptions {
+    /// Builds the capabilities described by these options.
+    pub fn capabilities(&self) -> capabilities::Capabilities {
+        use capabilities::*;
+        Capabilities {
+            networking: Networking {
+                max_message_size: 1024,
+                max_packet_size: 256,
+                mode: RotMode::Platform,
+                roles: BusRole::Host.into(),
+                has_compression: self.compression,
+                has_streaming: self.streaming,
+            },
+            security: BitFlags::empty(),
+            has_pfm_support: false,
+            has_policy_support: false,
+            has_firmware_protection: false,
+            crypto: Crypto {
+                has_ecdsa: self.ecdsa,
+                has_ecc: self.ecdsa,
+                has_rsa: true,
+                has_aes: false,
+                ecc_strength: if self.ecdsa {
+                    EccKeyStrength::Bits256.into()
+                } else {
+                    BitFlags::empty()
+                },
+                rsa_strength: RsaKeyStrength::Bits2048.into(),
+                aes_strength: BitFlags::empty(),
+                hash_algos: self.hash_algos,
+            },
+            protocol_version: self.protocol_version,
         }
     }
 }
@@ -211,6 +429,11 @@ impl Virtual {
             }
         }
     }
+    /// Returns the TCP port this virtual RoT is listening on.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
     /// Sends `req` to this virtal RoT, using Cerberus-over-TCP.
     ///
     /// Blocks until a response comes back.
@@ -228,6 +451,81 @@ impl Virtual {
         tcp::send_cerberus::<Cmd>(self.port, req, arena)
     }
 
+    /// Sends `req` to this virtal RoT, using Cerberus-over-TCP with the given
+    /// options.
+    ///
+    /// Blocks until a response comes back.
+    pub fn send_cerberus_with<'a, Cmd>(
+        &self,
+        req: Cmd::Req,
+        arena: &'a dyn Arena,
+        opts: tcp::SendOptions,
+    ) -> Result<
+        Result<Cmd::Resp, protocol::Error<'a, Cmd>>,
+        server::Error<net::CerberusHeader>,
+    >
+    where
+        Cmd: protocol::Command<'a, CommandType = cerberus::CommandType>,
+    {
+        tcp::send_cerberus_with::<Cmd>(self.port, req, arena, opts)
+    }
+
+    /// Sends `req` to this virtual RoT, using Cerberus-over-TCP, encrypted
+    /// under `session`.
+    ///
+    /// Blocks until a response comes back.
+    pub fn send_cerberus_encrypted<'a, Cmd>(
+        &self,
+        req: Cmd::Req,
+        arena: &'a dyn Arena,
+        session: &mut dyn session::Session,
+    ) -> Result<
+        Result<Cmd::Resp, protocol::Error<'a, Cmd>>,
+        server::Error<net::CerberusHeader>,
+    >
+    where
+        Cmd: protocol::Command<'a, CommandType = cerberus::CommandType>,
+    {
+        tcp::send_cerberus_encrypted::<Cmd>(self.port, req, arena, session)
+    }
+
+    /// Sends a vendor-defined message to this virtual RoT, using
+    /// Cerberus-over-TCP.
+    ///
+    /// Blocks until a response comes back.
+    pub fn send_vendor<'a>(
+        &self,
+        vendor_id: u16,
+        subcommand: u8,
+        payload: &'a [u8],
+        arena: &'a dyn Arena,
+    ) -> Result<
+        Result<
+            protocol::Resp<'a, cerberus::VendorCommand>,
+            protocol::Error<'a, cerberus::VendorCommand>,
+        >,
+        server::Error<net::CerberusHeader>,
+    > {
+        self.send_cerberus::<cerberus::VendorCommand>(
+            cerberus::vendor_command::VendorCommandRequest {
+                vendor_id,
+                subcommand,
+                payload,
+            },
+            arena,
+        )
+    }
+
+    /// Opens a persistent Cerberus-over-TCP connection to this virtual RoT,
+    /// advertising `capabilities` during negotiation.
+    pub fn connect(
+        &self,
+        capabilities: capabilities::Capabilities,
+    ) -> tcp::Client {
+        tcp::Client::connect(self.port, capabilities)
+            .expect("failed to connect to virtual RoT")
+    }
+
     /// Sends `req` to this virtal RoT, using SPDM-over-TCP.
     ///
     /// Blocks until a response comes back.
@@ -246,7 +544,36 @@ impl Virtual {
     }
 }
 
+/// Builds the attestation log described by `opts` in `buf`.
+pub fn build_event_log<'a>(
+    opts: &Options,
+    buf: &'a mut [u8],
+) -> server::TcgEventLog<'a> {
+    let mut hasher = ring::hash::Engine::new();
+    let mut log = server::TcgEventLog::new(buf).unwrap();
+    for (pcr, event_type, data) in &opts.attestation_events {
+        log.append(
+            &mut hasher,
+            *pcr,
+            server::event_log::EventType(*event_type),
+            data,
+        )
+        .unwrap();
+    }
+    log
+}
+
+/// The signals that make a virtual RoT exit cleanly, such as `SIGINT` when it
+/// is run by hand and interrupted with Ctrl-C.
+#[cfg(unix)]
+const SHUTDOWN_SIGNALS: &[i32] =
+    &[signal_hook::consts::SIGINT, signal_hook::consts::SIGTERM];
+#[cfg(not(unix))]
+const SHUTDOWN_SIGNALS: &[i32] = &[];
+
 /// Starts a server loop for serving PA-RoT requests, as described by `opts`.
+///
+/// The loop runs until the process receives one of [`SHUTDOWN_SIGNALS`].
 pub fn serve(opts: Options) -> ! {
     log::info!("configuring server...");
     let networking = capabilities::Networking {
@@ -254,6 +581,8 @@ pub fn serve(opts: Options) -> ! {
         max_packet_size: opts.max_packet_size,
         mode: capabilities::RotMode::Platform,
         roles: capabilities::BusRole::Host.into(),
+        has_compression: opts.compression,
+        has_streaming: opts.streaming,
     };
 
     let timeouts = capabilities::Timeouts {
@@ -269,8 +598,10 @@ pub fn serve(opts: Options) -> ! {
         &opts.unique_device_identity,
     );
     let reset = fakes::Reset::new(opts.resets_since_power_on);
+    let clock = SystemClock::new();
 
-    let mut hasher = ring::hash::Engine::new();
+    let mut hasher =
+        fakes::BusyHasher::new(ring::hash::Engine::new(), opts.hash_busy_count);
     let mut csrng = ring::csrng::Csrng::new();
     let mut ciphers = ring::sig::Ciphers::new();
 
@@ -287,6 +618,54 @@ pub fn serve(opts: Options) -> ! {
             }
         }
     });
+    let mut rsa_signer = opts.alias_keypair.as_ref().map(|kp| match kp {
+        KeyPairFormat::RsaPkcs8(pk8) => {
+            ring::rsa::Sign256::from_pkcs8(pk8).unwrap()
+        }
+    });
+    let mut ecdsa_signer = opts.ecdsa_alias_keypair.as_ref().map(|pk8| {
+        match ring::ecdsa::SignP256::with_der_encoding_from_pkcs8(pk8) {
+            Ok(ecdsa) => ecdsa,
+            Err(e) => {
+                log::error!("could not parse ECDSA alias keypair: {:?}", e);
+                std::process::exit(1);
+            }
+        }
+    });
+    let mut config_reset = opts.reset_authority.as_ref().map(|kp| match kp {
+        KeyPairFormat::RsaPkcs8(pk8) => {
+            match ring::rsa::Sign256::from_pkcs8(pk8) {
+                Ok(rsa) => fakes::ConfigReset::new(rsa.verifier()),
+                Err(e) => {
+                    log::error!("could not parse reset keypair: {:?}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    });
+    let authority = |kp: &Option<KeyPairFormat>, name: &str| {
+        kp.as_ref().map(|kp| match kp {
+            KeyPairFormat::RsaPkcs8(pk8) => {
+                match ring::rsa::Sign256::from_pkcs8(pk8) {
+                    Ok(rsa) => rsa.verifier(),
+                    Err(e) => {
+                        log::error!(
+                            "could not parse {} keypair: {:?}",
+                            name,
+                            e
+                        );
+                        std::process::exit(1);
+                    }
+                }
+            }
+        })
+    };
+    let mut pfm_authority = authority(&opts.pfm_authority, "PFM");
+    let mut pfm_store = pfm_authority.as_ref().map(|_| fakes::Manifests::new());
+    let mut cfm_authority = authority(&opts.cfm_authority, "CFM");
+    let mut cfm_store = cfm_authority.as_ref().map(|_| fakes::Manifests::new());
+    let mut pcd_authority = authority(&opts.pcd_authority, "PCD");
+    let mut pcd_store = pcd_authority.as_ref().map(|_| fakes::Manifests::new());
     let mut trust_chain = cert::SimpleChain::<8>::parse(
         &trust_chain_bytes,
         opts.cert_format,
@@ -294,22 +673,113 @@ pub fn serve(opts: Options) -> ! {
         signer.as_mut().map(|s| s as _),
     )
     .unwrap();
+    if let Some(rsa) = &mut rsa_signer {
+        trust_chain = trust_chain.with_signer_for(sig::AnySigner::Rsa(rsa));
+    }
+    if let Some(ecdsa) = &mut ecdsa_signer {
+        trust_chain = trust_chain.with_signer_for(sig::AnySigner::Ecdsa(ecdsa));
+    }
     let mut session = Session::new();
+    let mut nonces = RecentNonces::<16>::new();
+    let mut log_buf = vec![0; 4096];
+    let log_authority = authority(&opts.log_authority, "log");
+    let mut logs = fakes::Logs::new(
+        build_event_log(&opts, &mut log_buf),
+        opts.debug_log.clone(),
+        log_authority,
+    );
+
+    let mut limiter = opts.rate_limit.map(|(command, burst)| {
+        TokenBuckets::new([rate_limit::Limit {
+            command,
+            burst,
+            period: Duration::from_secs(3600),
+        }])
+    });
+
+    let mut vendors = opts
+        .vendor_ids
+        .iter()
+        .map(|&id| fakes::EchoVendor::new(id))
+        .collect::<Vec<_>>();
+    let mut vendor = vendors
+        .iter_mut()
+        .map(|v| v as &mut dyn server::VendorHandler)
+        .collect::<Vec<_>>();
+
+    let mut unsealer = if opts.unseal {
+        Some(fakes::Unsealer)
+    } else {
+        None
+    };
+
+    let host_authority = authority(&opts.host_authority, "host");
+    let mut host_control = if opts.host_control {
+        Some(fakes::HostControl::new(host_authority))
+    } else {
+        None
+    };
+
+    let mut verify_engines = (0..opts.verify_slots)
+        .map(|_| ring::hash::Engine::new())
+        .collect::<Vec<_>>();
+    let mut verify_slots = verify_engines
+        .iter_mut()
+        .map(|e| server::fw_verify::Slot::new(e))
+        .collect::<Vec<_>>();
+    let fw_verify = if opts.verify_slots > 0 {
+        Some(server::Verifications::new(
+            &mut verify_slots,
+            opts.verify_idle_timeout,
+        ))
+    } else {
+        None
+    };
 
     let mut server = PaRot::new(manticore::server::pa_rot::Options {
         identity: &identity,
         reset: &reset,
+        clock: &clock,
+        signed_timestamps: opts.signed_timestamps,
         hasher: &mut hasher,
         csrng: &mut csrng,
         ciphers: &mut ciphers,
         trust_chain: &mut trust_chain,
         session: &mut session,
+        session_lifetime: opts.session_lifetime,
+        nonces: &mut nonces,
+        vendor: &mut vendor,
+        rate_limiter: limiter.as_mut().map(|l| l as _),
+        timer: None,
         pmr0: &opts.pmr0,
+        logs: &mut logs,
+        device_state: &opts.device_state,
+        config_reset: config_reset.as_mut().map(|c| c as _),
+        host_control: host_control.as_mut().map(|h| h as _),
+        pfm_store: pfm_store.as_mut().map(|s| s as _),
+        pfm_authority: pfm_authority.as_mut().map(|a| a as _),
+        cfm_store: cfm_store.as_mut().map(|s| s as _),
+        cfm_authority: cfm_authority.as_mut().map(|a| a as _),
+        pcd_store: pcd_store.as_mut().map(|s| s as _),
+        pcd_authority: pcd_authority.as_mut().map(|a| a as _),
+        unsealer: unsealer.as_mut().map(|u| u as _),
+        fw_verify,
         device_id: opts.device_id,
         networking,
+        protocol_versions: &opts.protocol_versions,
         timeouts,
+        parse_options: if opts.strict_parsing {
+            server::ParseOptions::STRICT
+        } else {
+            server::ParseOptions::default()
+        },
     });
 
+    if let Err(e) = server.check_rng_health() {
+        log::error!("random number generator is unhealthy: {:?}", e);
+        std::process::exit(1);
+    }
+
     match opts.protocol {
         Protocol::Cerberus => {
             let mut host = match TcpHostPort::<net::CerberusHeader>::bind() {
@@ -319,6 +789,23 @@ pub fn serve(opts: Options) -> ! {
                     std::process::exit(1);
                 }
             };
+            if opts.compression {
+                host.enable_compression();
+            }
+            if opts.streaming {
+                host.enable_streaming();
+            }
+            if opts.magic {
+                host.enable_magic();
+            }
+            if opts.message_type {
+                host.enable_message_type();
+            }
+            host.set_read_budget(opts.read_budget);
+            host.set_payload_ceiling(opts.payload_ceiling);
+            if let Err(e) = host.shutdown_on_signals(SHUTDOWN_SIGNALS) {
+                log::warn!("could not catch signals: {:?}", e);
+            }
             let port = host.port();
             log::info!("bound to port {}", port);
 
@@ -327,10 +814,25 @@ pub fn serve(opts: Options) -> ! {
 
             let mut arena = BumpArena::new(vec![0; 1024]);
 
+            if let Some(n) = opts.max_requests {
+                log::info!("serving {} requests", n);
+                if let Err(e) = server.serve_n(&mut host, &mut arena, n) {
+                    log::error!("failed to serve {} requests: {}", n, e);
+                    std::process::exit(1);
+                }
+                log::info!("served {} requests; exiting", n);
+                std::process::exit(0);
+            }
+
             log::info!("entering server loop");
             loop {
-                if let Err(e) = server.process_request(&mut host, &arena) {
-                    log::error!("failed to process request: {:?}", e);
+                match server.process_request(&mut host, &arena) {
+                    Err(e) if is_shutdown(&e) => {
+                        log::info!("shutting down");
+                        std::process::exit(0);
+                    }
+                    Err(e) => log::error!("failed to process request: {}", e),
+                    Ok(()) => {}
                 }
                 arena.reset();
             }
@@ -343,6 +845,9 @@ pub fn serve(opts: Options) -> ! {
                     std::process::exit(1);
                 }
             };
+            if let Err(e) = host.shutdown_on_signals(SHUTDOWN_SIGNALS) {
+                log::warn!("could not catch signals: {:?}", e);
+            }
             let port = host.port();
             log::info!("bound to port {}", port);
 
@@ -353,11 +858,21 @@ pub fn serve(opts: Options) -> ! {
 
             log::info!("entering server loop");
             loop {
-                if let Err(e) = server.process_spdm_request(&mut host, &arena) {
-                    log::error!("failed to process request: {:?}", e);
+                match server.process_spdm_request(&mut host, &arena) {
+                    Err(e) if is_shutdown(&e) => {
+                        log::info!("shutting down");
+                        std::process::exit(0);
+                    }
+                    Err(e) => log::error!("failed to process request: {}", e),
+                    Ok(()) => {}
                 }
                 arena.reset();
             }
         }
     }
 }
+
+/// Returns whether `e` indicates that the server's host port was shut down.
+fn is_shutdown<H: net::Header>(e: &manticore::Error<server::Error<H>>) -> bool {
+    matches!(e.as_ref(), server::Error::Network(net::Error::Shutdown))
+}
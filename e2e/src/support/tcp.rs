@@ -25,31 +25,171 @@
 //! Here, `total_len` includes the four bytes of the header, and the two bytes
 //! that follow are the leading version and command bytes of a generic SPDM
 //! message.
+//!
+//! Cerberus-over-TCP additionally supports compressing the payload with
+//! [`manticore::io::rle`], once both sides have advertised the
+//! `has_compression` capability. A compressed payload is signaled by setting
+//! the most significant bit of `payload_len`; the remaining bits then give
+//! the length of the *compressed* payload.
+//!
+//! Once both sides have also advertised the `has_streaming` capability, a
+//! Cerberus-over-TCP reply whose payload length is implied by its command type
+//! (see [`Header::implied_len()`]) may leave its length out, so that it can be
+//! written out as it is built rather than buffered first. A request asks for
+//! this by setting [`STREAM_BIT`] in its `payload_len`; a reply that takes it
+//! up sets the same bit, and leaves the rest of `payload_len` zero. Compressed
+//! and integrity-checked replies always carry a length.
+//!
+//! A connection may carry any number of request/response pairs; the server
+//! waits for the next request on a connection until the client closes it, or
+//! another client connects.
+//!
+//! Optionally, every header may be preceded by the two-byte [`MAGIC`] frame
+//! marker, which lets a reader resynchronize after a framing error.
+//!
+//! A [`TcpHostPort`] may also be given a [`ReadBudget`], which bounds how long
+//! a peer may take to send a request, so that a peer trickling bytes in cannot
+//! tie up the server indefinitely.
+//!
+//! Also optionally, every header may be preceded by a one-byte MCTP
+//! [`net::MessageType`], emulating an endpoint shared between several
+//! protocols, such as Cerberus and PLDM. A port only accepts messages tagged
+//! with the message type of the protocol it speaks. If both options are
+//! enabled, the message type follows the frame marker.
+//!
+//! When message types are enabled, a message may also set the
+//! [`integrity::IC_BIT`] in its message type, in which case its payload ends
+//! in an integrity-check tag over the rest of the (possibly compressed)
+//! payload. The tag counts towards the header's payload length. A port
+//! replies with a tag if and only if the request carried one.
+//!
+//! A request encrypted under the current session, as described in
+//! [`manticore::session`], sets [`CRYPT_BIT`] in its `payload_len`. The
+//! payload is passed to the server as-is, which decrypts it itself. Replies are
+//! never marked as encrypted.
+//!
+//! The same framing is used in the other direction by [`TcpClientPort`], which
+//! lets a device originate requests of its own.
 
 use std::any::type_name;
+use std::io::ErrorKind;
 use std::io::Read as _;
 use std::io::Write as _;
+use std::net::SocketAddr;
 use std::net::TcpListener;
 use std::net::TcpStream;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
+use manticore::crypto::csrng::Csrng as _;
+use manticore::crypto::ring;
+use manticore::hardware::clock;
+use manticore::hardware::clock::Clock;
+use manticore::hardware::clock::SystemClock;
+use manticore::hardware::clock::Ticks;
 use manticore::io;
+use manticore::io::rle;
+use manticore::io::Read as _;
 use manticore::mem::Arena;
+use manticore::mem::ArenaExt as _;
+use manticore::mem::OutOfMemory;
 use manticore::net;
+use manticore::net::client::ClientPort;
+use manticore::net::client::ClientResponse;
 use manticore::net::host::HostPort;
 use manticore::net::host::HostRequest;
 use manticore::net::host::HostResponse;
 use manticore::protocol;
 use manticore::protocol::cerberus;
+use manticore::protocol::cerberus::capabilities;
+use manticore::protocol::cerberus::capabilities::Capabilities;
+use manticore::protocol::cerberus::capabilities::HashAlgos;
+use manticore::protocol::cerberus::capabilities::ProtocolVersion;
+use manticore::protocol::cerberus::device_id::DeviceIdentifier;
+use manticore::protocol::integrity;
 use manticore::protocol::spdm;
+use manticore::protocol::wire;
 use manticore::protocol::wire::FromWire;
 use manticore::protocol::wire::ToWire;
 use manticore::protocol::wire::WireEnum;
 use manticore::protocol::Command;
 use manticore::protocol::Message;
+use manticore::protocol::Req;
+use manticore::protocol::Resp;
 use manticore::server;
+use manticore::session;
+use manticore::session::Session;
 use manticore::Result;
 use manticore::{check, fail};
 
+use crate::support::rot;
+
+/// Options for [`send_cerberus_with()`].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SendOptions {
+    /// Whether to compress the request payload.
+    ///
+    /// The caller is responsible for checking that the RoT has negotiated the
+    /// `has_compression` capability beforehand.
+    pub compress: bool,
+
+    /// Whether to prefix each frame with the [`MAGIC`] frame-boundary marker,
+    /// and to expect one in the response.
+    ///
+    /// The RoT must have been configured with
+    /// [`TcpHostPort::enable_magic()`].
+    pub magic: bool,
+
+    /// Whether to prefix each frame with its MCTP message-type byte, and to
+    /// expect one in the response.
+    ///
+    /// The RoT must have been configured with
+    /// [`TcpHostPort::enable_message_type()`].
+    pub message_type: bool,
+
+    /// Whether to append an integrity-check tag to each frame, and to require
+    /// one in the response.
+    ///
+    /// This has no effect unless `message_type` is also set.
+    pub integrity: bool,
+
+    /// Whether to ask the RoT to stream replies whose length is implied by
+    /// their command type, rather than buffering them to compute a length.
+    ///
+    /// The caller is responsible for checking that the RoT has negotiated the
+    /// `has_streaming` capability beforehand.
+    pub streaming: bool,
+
+    /// Whether to mark the request as encrypted, by setting [`CRYPT_BIT`].
+    ///
+    /// This does not encrypt the payload itself; see
+    /// [`send_cerberus_encrypted()`] for that.
+    pub encrypted: bool,
+
+    /// How to handle a RoT that reports that it is busy.
+    pub retry: Retry,
+}
+
+/// A policy for retrying requests that a RoT rejects with
+/// [`cerberus::Error::Busy`].
+///
+/// Requests that fail with any other error are never retried.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Retry {
+    /// The maximum number of times to retry a request; zero disables
+    /// retrying altogether.
+    pub attempts: u32,
+
+    /// The delay before the first retry. Each subsequent retry waits twice as
+    /// long as the one before it.
+    pub delay: Duration,
+}
+
 /// Sends `req` to a virtual RoT listening on `localhost:{port}`, using
 /// Cerberus-over-TCP.
 ///
@@ -65,30 +205,367 @@ pub fn send_cerberus<
     Result<Cmd::Resp, protocol::Error<'a, Cmd>>,
     server::Error<net::CerberusHeader>,
 > {
+    send_cerberus_with::<Cmd>(port, req, arena, SendOptions::default())
+}
+
+/// Like [`send_cerberus()`], but with additional options.
+///
+/// Blocks until a response comes back, which includes any retries specified
+/// by `opts`.
+pub fn send_cerberus_with<
+    'a,
+    Cmd: Command<'a, CommandType = cerberus::CommandType>,
+>(
+    port: u16,
+    req: Cmd::Req,
+    arena: &'a dyn Arena,
+    opts: SendOptions,
+) -> Result<
+    Result<Cmd::Resp, protocol::Error<'a, Cmd>>,
+    server::Error<net::CerberusHeader>,
+> {
+    let mut delay = opts.retry.delay;
+    let mut attempt = 0;
+    loop {
+        let (result, busy) =
+            send_cerberus_once::<Cmd>(port, &req, arena, opts)?;
+        if !busy || attempt >= opts.retry.attempts {
+            return Ok(result);
+        }
+
+        attempt += 1;
+        log::info!(
+            "RoT is busy; retry {} of {} in {:?}",
+            attempt,
+            opts.retry.attempts,
+            delay
+        );
+        thread::sleep(delay);
+        delay *= 2;
+    }
+}
+
+/// A persistent Cerberus-over-TCP client.
+///
+/// Unlike [`send_cerberus()`], which opens a fresh connection for every
+/// request, a `Client` keeps one connection open for its entire lifetime.
+///
+/// The first call to [`Client::command()`] negotiates capabilities with the
+/// RoT; the result is cached and used to frame all subsequent requests:
+/// requests longer than the negotiated maximum message size are rejected
+/// before being sent, payloads are compressed if both sides support it, and
+/// replies are streamed if both sides support that.
+pub struct Client {
+    conn: TcpStream,
+    capabilities: Capabilities,
+    negotiated: Option<Capabilities>,
+}
+
+impl Client {
+    /// Connects to a virtual RoT listening on `localhost:{port}`.
+    ///
+    /// `capabilities` are the capabilities this client advertises during
+    /// negotiation.
+    pub fn connect(
+        port: u16,
+        capabilities: Capabilities,
+    ) -> Result<Self, net::Error> {
+        Ok(Self {
+            conn: connect(port)?,
+            capabilities,
+            negotiated: None,
+        })
+    }
+
+    /// Returns the capabilities negotiated with the RoT, negotiating them
+    /// first if this has not been done yet.
+    ///
+    /// If the RoT rejects negotiation, [`net::Error::Disconnected`] is
+    /// returned, since the connection is no longer usable.
+    pub fn capabilities(
+        &mut self,
+    ) -> Result<&Capabilities, server::Error<net::CerberusHeader>> {
+        if self.negotiated.is_none() {
+            let req = Req::<cerberus::DeviceCapabilities> {
+                capabilities: self.capabilities,
+            };
+            let (result, _) = exchange_cerberus::<cerberus::DeviceCapabilities>(
+                &mut self.conn,
+                &req,
+                &OutOfMemory,
+                SendOptions::default(),
+                None,
+            )?;
+            let resp = result.map_err(|e| {
+                log::error!("capability negotiation failed: {:?}", e);
+                fail!(net::Error::Disconnected)
+            })?;
+            self.negotiated = Some(resp.capabilities);
+        }
+        Ok(self.negotiated.as_ref().unwrap())
+    }
+
+    /// Returns the protocol version negotiated with the RoT, negotiating
+    /// capabilities first if this has not been done yet.
+    pub fn protocol_version(
+        &mut self,
+    ) -> Result<ProtocolVersion, server::Error<net::CerberusHeader>> {
+        Ok(self.capabilities()?.protocol_version)
+    }
+
+    /// Returns the hash algorithms that both this client and the RoT
+    /// support, negotiating capabilities first if this has not been done yet.
+    pub fn hash_algos(
+        &mut self,
+    ) -> Result<HashAlgos, server::Error<net::CerberusHeader>> {
+        let theirs = self.capabilities()?.crypto.hash_algos;
+        Ok(theirs.intersection(self.capabilities.crypto.hash_algos))
+    }
+
+    /// Sends `req` to the RoT over this client's connection.
+    ///
+    /// Blocks until a response comes back.
+    pub fn command<
+        'a,
+        Cmd: Command<'a, CommandType = cerberus::CommandType>,
+    >(
+        &mut self,
+        req: Cmd::Req,
+        arena: &'a dyn Arena,
+    ) -> Result<
+        Result<Cmd::Resp, protocol::Error<'a, Cmd>>,
+        server::Error<net::CerberusHeader>,
+    > {
+        let theirs = self.capabilities()?.networking;
+        let ours = self.capabilities.networking;
+        let max_len = theirs.max_message_size.min(ours.max_message_size);
+        let opts = SendOptions {
+            compress: theirs.has_compression && ours.has_compression,
+            streaming: theirs.has_streaming && ours.has_streaming,
+            ..Default::default()
+        };
+
+        let (result, _) = exchange_cerberus::<Cmd>(
+            &mut self.conn,
+            &req,
+            arena,
+            opts,
+            Some(max_len as usize),
+        )?;
+        Ok(result)
+    }
+}
+
+/// Performs a single Cerberus-over-TCP request.
+///
+/// In addition to the result, returns whether the RoT reported that it was
+/// busy.
+#[allow(clippy::type_complexity)]
+fn send_cerberus_once<
+    'a,
+    Cmd: Command<'a, CommandType = cerberus::CommandType>,
+>(
+    port: u16,
+    req: &Cmd::Req,
+    arena: &'a dyn Arena,
+    opts: SendOptions,
+) -> Result<
+    (Result<Cmd::Resp, protocol::Error<'a, Cmd>>, bool),
+    server::Error<net::CerberusHeader>,
+> {
+    let mut conn = connect(port)?;
+    exchange_cerberus::<Cmd>(&mut conn, req, arena, opts, None)
+}
+
+/// Opens a connection to a virtual RoT listening on `localhost:{port}`.
+fn connect(port: u16) -> Result<TcpStream, net::Error> {
     log::info!("connecting to 127.0.0.1:{}", port);
-    let mut conn = TcpStream::connect(("127.0.0.1", port)).map_err(|e| {
+    TcpStream::connect(("127.0.0.1", port)).map_err(|e| {
         log::error!("{}", e);
-        net::Error::Io(io::Error::Internal)
-    })?;
+        fail!(net::Error::Io(io::Error::Internal))
+    })
+}
+
+/// Sends `req` over `conn` and waits for a response.
+///
+/// `opts.retry` is ignored. If `max_len` is set, requests whose (uncompressed) payload is longer than
+/// it are rejected before anything is sent.
+///
+/// In addition to the result, returns whether the RoT reported that it was
+/// busy.
+#[allow(clippy::type_complexity)]
+fn exchange_cerberus<
+    'a,
+    Cmd: Command<'a, CommandType = cerberus::CommandType>,
+>(
+    conn: &mut TcpStream,
+    req: &Cmd::Req,
+    arena: &'a dyn Arena,
+    opts: SendOptions,
+    max_len: Option<usize>,
+) -> Result<
+    (Result<Cmd::Resp, protocol::Error<'a, Cmd>>, bool),
+    server::Error<net::CerberusHeader>,
+> {
     let mut writer = Writer::new(net::CerberusHeader {
         command: <Cmd::Req as Message>::TYPE,
     });
+    writer.compress = opts.compress;
+    writer.magic = opts.magic;
+    writer.message_type = opts.message_type;
+    writer.integrity = opts.integrity;
+    writer.streamed = opts.streaming;
+    writer.encrypted = opts.encrypted;
     log::info!("serializing {}", type_name::<Cmd::Req>());
     req.to_wire(&mut writer)?;
+    if let Some(max_len) = max_len {
+        if writer.buf.len() > max_len {
+            log::error!(
+                "request is {} bytes, but the negotiated limit is {}",
+                writer.buf.len(),
+                max_len
+            );
+            return Err(
+                fail!(net::Error::Io(io::Error::BufferExhausted)).into()
+            );
+        }
+    }
+    writer.finish(&mut *conn)?;
+    receive_cerberus::<Cmd>(conn, arena, opts)
+}
+
+/// Sends `req` to a virtual RoT listening on `localhost:{port}`, using
+/// Cerberus-over-TCP, encrypted under `session`.
+///
+/// `session` must be the host end of a session already established with the
+/// RoT. Blocks until a response comes back; the response itself is not
+/// decrypted.
+pub fn send_cerberus_encrypted<
+    'a,
+    Cmd: Command<'a, CommandType = cerberus::CommandType>,
+>(
+    port: u16,
+    req: Cmd::Req,
+    arena: &'a dyn Arena,
+    session: &mut dyn Session,
+) -> Result<
+    Result<Cmd::Resp, protocol::Error<'a, Cmd>>,
+    server::Error<net::CerberusHeader>,
+> {
+    let mut writer = Writer::new(net::CerberusHeader {
+        command: <Cmd::Req as Message>::TYPE,
+    });
+    writer.encrypted = true;
+    log::info!("serializing {}", type_name::<Cmd::Req>());
+    req.to_wire(&mut writer)?;
+
+    let len = writer.buf.len();
+    writer
+        .buf
+        .resize(len + session::AES_TAG_LEN + session::AES_IV_LEN, 0);
+    let mut iv = [0; session::AES_IV_LEN];
+    ring::csrng::Csrng::new().fill(&mut iv).map_err(|e| {
+        log::error!("could not generate an IV: {:?}", e);
+        net::Error::Io(io::Error::Internal)
+    })?;
+    session::seal_message(session, &iv, &mut writer.buf, len).map_err(|e| {
+        log::error!("could not encrypt request: {:?}", e);
+        net::Error::Io(io::Error::Internal)
+    })?;
+
+    let mut conn = connect(port)?;
     writer.finish(&mut conn)?;
+    let (result, _) =
+        receive_cerberus::<Cmd>(&mut conn, arena, SendOptions::default())?;
+    Ok(result)
+}
 
+/// Waits for the response to a request for `Cmd` sent over `conn`, framed
+/// according to `opts`.
+///
+/// In addition to the result, returns whether the RoT reported that it was
+/// busy.
+#[allow(clippy::type_complexity)]
+fn receive_cerberus<
+    'a,
+    Cmd: Command<'a, CommandType = cerberus::CommandType>,
+>(
+    conn: &mut TcpStream,
+    arena: &'a dyn Arena,
+    opts: SendOptions,
+) -> Result<
+    (Result<Cmd::Resp, protocol::Error<'a, Cmd>>, bool),
+    server::Error<net::CerberusHeader>,
+> {
     log::info!("waiting for response");
-    let (header, len) = net::CerberusHeader::from_tcp(&mut conn)?;
-    let mut r = TcpReader { tcp: conn, len };
+    let (header, frame) = read_header::<net::CerberusHeader, _>(
+        &mut *conn,
+        opts.magic,
+        opts.message_type,
+        usize::MAX,
+    )?;
+    let frame = reply_frame(header, frame)?;
+    let conn = conn.try_clone().map_err(|e| {
+        log::error!("{}", e);
+        net::Error::Io(io::Error::Internal)
+    })?;
+    let mut r = TcpReader::new(conn, frame)?;
+    decode_cerberus::<Cmd>(header, &mut r, arena)
+}
+
+/// Parses the payload of a Cerberus-over-TCP response to `Cmd` out of `r`,
+/// according to `header`.
+///
+/// In addition to the result, returns whether the RoT reported that it was
+/// busy.
+#[allow(clippy::type_complexity)]
+fn decode_cerberus<
+    'a,
+    Cmd: Command<'a, CommandType = cerberus::CommandType>,
+>(
+    header: net::CerberusHeader,
+    r: &mut TcpReader,
+    arena: &'a dyn Arena,
+) -> Result<
+    (Result<Cmd::Resp, protocol::Error<'a, Cmd>>, bool),
+    server::Error<net::CerberusHeader>,
+> {
+    if header.command == cerberus::CommandType::Error {
+        // Buffer the error, so that its completion code can be inspected
+        // regardless of what response and error types `Cmd` uses.
+        let payload = arena
+            .alloc_slice::<u8>(r.remaining_data())
+            .map_err(|e| wire::Error::from(e.into_inner()))?;
+        r.read_bytes(payload)
+            .map_err(|e| wire::Error::from(e.into_inner()))?;
+        let payload: &'a [u8] = payload;
+
+        let raw = cerberus::RawError::from_wire(&mut { payload }, arena)?;
+        // Only a successful completion code may stand in for a response that
+        // happens to share the error command type, such as an `Ack`.
+        if raw.is_success()
+            && <Cmd::Resp as Message>::TYPE == cerberus::CommandType::Error
+        {
+            log::info!("deserializing {}", type_name::<Cmd::Resp>());
+            return Ok((
+                Ok(FromWire::from_wire(&mut { payload }, arena)?),
+                false,
+            ));
+        }
+        let busy =
+            raw.completion_code() == Some(cerberus::CompletionCode::Busy);
 
-    if header.command == <Cmd::Resp as Message>::TYPE {
-        log::info!("deserializing {}", type_name::<Cmd::Resp>());
-        Ok(Ok(FromWire::from_wire(&mut r, arena)?))
-    } else if header.command == cerberus::CommandType::Error {
         log::info!("deserializing {}", type_name::<protocol::Error<'a, Cmd>>());
-        Ok(Err(fail!(FromWire::from_wire(&mut r, arena)?)))
+        let err = FromWire::from_wire(&mut { payload }, arena)?;
+        Ok((Err(fail!(err)), busy))
+    } else if header.command == <Cmd::Resp as Message>::TYPE {
+        log::info!("deserializing {}", type_name::<Cmd::Resp>());
+        Ok((Ok(FromWire::from_wire(r, arena)?), false))
     } else {
-        Err(net::Error::BadHeader.into())
+        Err(fail!(server::Error::UnexpectedResponse {
+            expected: <Cmd::Resp as Message>::TYPE,
+            got: header.command,
+        }))
     }
 }
 
@@ -104,11 +581,7 @@ pub fn send_spdm<'a, Cmd: Command<'a, CommandType = spdm::CommandType>>(
     Result<Cmd::Resp, protocol::Error<'a, Cmd>>,
     server::Error<net::SpdmHeader>,
 > {
-    log::info!("connecting to 127.0.0.1:{}", port);
-    let mut conn = TcpStream::connect(("127.0.0.1", port)).map_err(|e| {
-        log::error!("{}", e);
-        net::Error::Io(io::Error::Internal)
-    })?;
+    let mut conn = connect(port)?;
     let mut writer = Writer::new(net::SpdmHeader {
         command: <Cmd::Req as Message>::TYPE,
         is_request: false,
@@ -119,8 +592,8 @@ pub fn send_spdm<'a, Cmd: Command<'a, CommandType = spdm::CommandType>>(
     writer.finish(&mut conn)?;
 
     log::info!("waiting for response");
-    let (header, len) = net::SpdmHeader::from_tcp(&mut conn)?;
-    let mut r = TcpReader { tcp: conn, len };
+    let (header, frame) = net::SpdmHeader::from_tcp(&mut conn)?;
+    let mut r = TcpReader::new(conn, frame)?;
 
     if header.command == <Cmd::Resp as Message>::TYPE {
         log::info!("deserializing {}", type_name::<Cmd::Resp>());
@@ -129,15 +602,153 @@ pub fn send_spdm<'a, Cmd: Command<'a, CommandType = spdm::CommandType>>(
         log::info!("deserializing {}", type_name::<protocol::Error<'a, Cmd>>());
         Ok(Err(fail!(FromWire::from_wire(&mut r, arena)?)))
     } else {
-        Err(net::Error::BadHeader.into())
+        Err(fail!(server::Error::UnexpectedResponse {
+            expected: <Cmd::Resp as Message>::TYPE,
+            got: header.command,
+        }))
     }
 }
 
+/// Serializes `msg` into a standalone X-over-TCP frame with the given
+/// `header`, byte-for-byte as it would be sent with `opts`.
+///
+/// `opts.retry` is ignored. This is useful for building test fixtures without
+/// a live connection.
+pub fn to_vec<H: Header>(
+    msg: &impl ToWire,
+    header: H,
+    opts: SendOptions,
+) -> Result<Vec<u8>, server::Error<H>> {
+    let mut writer = Writer::new(header);
+    writer.compress = opts.compress;
+    writer.magic = opts.magic;
+    writer.message_type = opts.message_type;
+    writer.integrity = opts.integrity;
+    writer.streamed = opts.streaming;
+    writer.encrypted = opts.encrypted;
+    msg.to_wire(&mut writer)?;
+
+    let mut bytes = Vec::new();
+    writer.finish(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Parses a standalone X-over-TCP frame, such as one produced by [`to_vec()`],
+/// into its header and a message of type `M`.
+///
+/// `opts.compress` is ignored, since compression is signaled in the header.
+/// `bytes` must contain exactly one frame.
+pub fn from_bytes<'a, H: Header, M: FromWire<'a>>(
+    bytes: &[u8],
+    arena: &'a dyn Arena,
+    opts: SendOptions,
+) -> Result<(H, M), server::Error<H>> {
+    let (header, mut r) = split_frame::<H>(bytes, opts, false)?;
+    let msg = M::from_wire(&mut r, arena)?;
+    check!(
+        r.remaining_data() == 0,
+        server::Error::ReqTooLong(r.remaining_data())
+    );
+    Ok((header, msg))
+}
+
+/// Parses a standalone Cerberus-over-TCP frame containing a response to
+/// `Cmd`, exactly as [`send_cerberus_with()`] would have decoded it off of a
+/// connection.
+///
+/// This is useful for replaying captured traffic. Like [`from_bytes()`],
+/// `bytes` must contain exactly one frame, and the response must use up its
+/// entire payload.
+pub fn response_from_bytes<
+    'a,
+    Cmd: Command<'a, CommandType = cerberus::CommandType>,
+>(
+    bytes: &[u8],
+    arena: &'a dyn Arena,
+    opts: SendOptions,
+) -> Result<
+    Result<Cmd::Resp, protocol::Error<'a, Cmd>>,
+    server::Error<net::CerberusHeader>,
+> {
+    let (header, mut r) =
+        split_frame::<net::CerberusHeader>(bytes, opts, true)?;
+    let (resp, _) = decode_cerberus::<Cmd>(header, &mut r, arena)?;
+    check!(
+        r.remaining_data() == 0,
+        server::Error::ReqTooLong(r.remaining_data())
+    );
+    Ok(resp)
+}
+
+/// Reads the header off of a standalone frame, returning it along with a
+/// reader for its payload.
+///
+/// If `reply` is set, the frame may be a streamed reply. Fails if `bytes` does
+/// not contain exactly one frame.
+fn split_frame<H: Header>(
+    mut bytes: &[u8],
+    opts: SendOptions,
+    reply: bool,
+) -> Result<(H, TcpReader), server::Error<H>> {
+    let (header, mut frame) = read_header::<H, _>(
+        &mut bytes,
+        opts.magic,
+        opts.message_type,
+        usize::MAX,
+    )?;
+    if reply {
+        frame = reply_frame(header, frame)?;
+    }
+    if bytes.len() != frame.len {
+        log::error!(
+            "frame declares {} payload bytes, but {} follow the header",
+            frame.len,
+            bytes.len()
+        );
+        return Err(fail!(net::Error::BadHeader).into());
+    }
+
+    let r = TcpReader::new(std::io::Cursor::new(bytes.to_vec()), frame)?;
+    Ok((header, r))
+}
+
 /// Helper struct for exposing a TCP stream as a Manticore reader.
 struct TcpReader {
-    tcp: TcpStream,
+    tcp: Box<dyn std::io::Read>,
     len: usize,
 }
+
+impl TcpReader {
+    /// Creates a reader for the payload described by `frame`, checking its
+    /// integrity and decompressing it first if necessary.
+    fn new(
+        mut tcp: impl std::io::Read + 'static,
+        frame: Frame,
+    ) -> Result<Self, net::Error> {
+        if frame.integrity {
+            let payload = read_checked(&mut tcp, frame.len)?;
+            let frame = Frame {
+                len: payload.len(),
+                integrity: false,
+                ..frame
+            };
+            return Self::new(std::io::Cursor::new(payload), frame);
+        }
+        if !frame.compressed {
+            return Ok(Self {
+                tcp: Box::new(tcp),
+                len: frame.len,
+            });
+        }
+
+        let payload = inflate(&mut tcp, frame.len)?;
+        Ok(Self {
+            len: payload.len(),
+            tcp: Box::new(std::io::Cursor::new(payload)),
+        })
+    }
+}
+
 impl io::Read for TcpReader {
     fn read_bytes(&mut self, out: &mut [u8]) -> Result<(), io::Error> {
         let Self { tcp, len } = self;
@@ -159,23 +770,223 @@ impl io::Read for TcpReader {
 #[allow(unsafe_code)]
 unsafe impl io::ReadZero<'_> for TcpReader {}
 
+/// Reads a compressed payload of `len` bytes from `r`, and decompresses it.
+fn inflate(
+    mut r: impl std::io::Read,
+    len: usize,
+) -> Result<Vec<u8>, net::Error> {
+    let mut compressed = vec![0; len];
+    r.read_exact(&mut compressed).map_err(|e| {
+        log::error!("{}", e);
+        net::Error::Io(io::Error::Internal)
+    })?;
+
+    let mut payload = Vec::with_capacity(rle::decompressed_len(&compressed)?);
+    rle::decompress(&compressed, io::write::StdWrite(&mut payload))?;
+    log::info!("decompressed {} bytes into {}", len, payload.len());
+    Ok(payload)
+}
+
+/// Reads a payload of `len` bytes from `r` that ends in an integrity-check
+/// tag, and checks it.
+///
+/// Returns the payload, without the tag.
+fn read_checked(
+    mut r: impl std::io::Read,
+    len: usize,
+) -> Result<Vec<u8>, net::Error> {
+    let mut payload = vec![0; len];
+    r.read_exact(&mut payload).map_err(|e| {
+        log::error!("{}", e);
+        net::Error::Io(io::Error::Internal)
+    })?;
+
+    let (body, tag) = integrity::split_tag(&payload).ok_or_else(|| {
+        log::error!("payload of {} bytes is too short for a tag", len);
+        net::Error::BadHeader
+    })?;
+    if !integrity::verify(body, &tag) {
+        log::error!("integrity check failed: got tag {:02x?}", tag);
+        return Err(fail!(net::Error::BadIntegrity));
+    }
+    payload.truncate(len - integrity::TAG_LEN);
+    Ok(payload)
+}
+
+/// A frame-boundary marker, optionally sent before every X-over-TCP header.
+///
+/// This marker is not part of any specification, and is off by default. When
+/// enabled, a reader that encounters a bad header skips ahead to the next
+/// marker, rather than interpreting every subsequent byte as garbage. This
+/// allows recovering from a desynchronized stream, such as one with a
+/// partially-consumed payload.
+///
+/// Every Cerberus command byte is well-formed, since unknown commands are
+/// answered as unsupported, so a Cerberus header after a marker is only bad
+/// if it declares more payload than the reader accepts. Garbage that happens
+/// to declare a plausible length is answered with an error instead of being
+/// skipped, and the reader resynchronizes on the marker after its payload.
+pub const MAGIC: [u8; 2] = [0xce, 0x7b];
+
+/// Reads a header off of the wire; if `magic` is set, first scans for
+/// [`MAGIC`], and skips over any bad headers that follow a marker.
+///
+/// A header that declares more than `ceiling` bytes of payload counts as bad.
+///
+/// If `message_type` is set, the header must be preceded by `H`'s message
+/// type, which also determines whether the payload carries an integrity
+/// check.
+fn read_header<H: Header, R: std::io::Read>(
+    mut r: R,
+    magic: bool,
+    message_type: bool,
+    ceiling: usize,
+) -> Result<(H, Frame), net::Error> {
+    let read_tagged = |r: &mut R| {
+        let mut integrity = false;
+        if message_type {
+            let mut byte = [0u8];
+            r.read_exact(&mut byte).map_err(|e| {
+                log::error!("{}", e);
+                net::Error::Io(io::Error::Internal)
+            })?;
+            integrity = net::check_message_type::<H>(byte[0])?;
+        }
+        let (header, frame) = H::from_tcp(r)?;
+        if frame.len > ceiling {
+            log::error!(
+                "header declares {} payload bytes, over the ceiling of {}",
+                frame.len,
+                ceiling
+            );
+            return Err(fail!(net::Error::BadHeader));
+        }
+        Ok((header, Frame { integrity, ..frame }))
+    };
+
+    if !magic {
+        return read_tagged(&mut r);
+    }
+
+    loop {
+        let mut window = [0u8; 2];
+        let mut skipped = 0usize;
+        loop {
+            let mut byte = [0u8];
+            r.read_exact(&mut byte).map_err(|e| {
+                log::error!("{}", e);
+                net::Error::Io(io::Error::Internal)
+            })?;
+            window = [window[1], byte[0]];
+            skipped += 1;
+            if skipped >= MAGIC.len() && window == MAGIC {
+                break;
+            }
+        }
+        if skipped > MAGIC.len() {
+            log::warn!("skipped {} bytes to resync", skipped - MAGIC.len());
+        }
+
+        match read_tagged(&mut r) {
+            Ok(header) => return Ok(header),
+            Err(e) if e.into_inner() == net::Error::BadHeader => {
+                log::warn!("bad header after frame marker; resyncing");
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Framing information for an X-over-TCP payload, parsed out of its header.
+#[derive(Copy, Clone, Debug)]
+pub struct Frame {
+    /// The number of payload bytes on the wire.
+    pub len: usize,
+    /// Whether the payload is compressed with [`manticore::io::rle`].
+    pub compressed: bool,
+    /// Whether the payload ends in an integrity-check tag.
+    pub integrity: bool,
+    /// Whether the header set [`STREAM_BIT`].
+    ///
+    /// In a request, this means that the peer accepts a streamed reply; in a
+    /// reply, that the payload's length is implied by its command type, rather
+    /// than given by `len`.
+    pub streamed: bool,
+    /// Whether the header set [`CRYPT_BIT`].
+    pub encrypted: bool,
+}
+
+/// Resolves the framing of a reply, which may have been streamed: a streamed
+/// reply takes the length implied by its command type.
+fn reply_frame<H: Header>(
+    header: H,
+    frame: Frame,
+) -> Result<Frame, net::Error> {
+    if !frame.streamed {
+        return Ok(frame);
+    }
+    match header.implied_len() {
+        Some(len)
+            if frame.len == 0 && !frame.compressed && !frame.integrity =>
+        {
+            Ok(Frame {
+                len,
+                streamed: false,
+                ..frame
+            })
+        }
+        _ => {
+            log::error!("bad streamed reply: {:?}, {:?}", header, frame);
+            Err(fail!(net::Error::BadHeader))
+        }
+    }
+}
+
 /// A header for a X-over-TCP protocol.
 pub trait Header: net::Header {
-    /// Reads a header and a length for the rest of the message off of the wire.
-    fn from_tcp(r: impl std::io::Read) -> Result<(Self, usize), net::Error>;
+    /// Reads a header and framing for the rest of the message off of the
+    /// wire.
+    fn from_tcp(r: impl std::io::Read) -> Result<(Self, Frame), net::Error>;
 
     /// Writes the given header, and buffered output message, to the wire.
+    ///
+    /// If `compressed` is set, `msg` has already been compressed, and the
+    /// header must indicate as much. If `streamed` is set, the header must set
+    /// [`STREAM_BIT`], and if `encrypted` is set, [`CRYPT_BIT`].
     fn to_tcp(
         self,
         msg: &[u8],
+        compressed: bool,
+        streamed: bool,
+        encrypted: bool,
         w: impl std::io::Write,
     ) -> Result<(), net::Error>;
+
+    /// Returns the payload length of every reply with this header, if its
+    /// command type fixes one.
+    ///
+    /// Only these replies may be streamed.
+    fn implied_len(&self) -> Option<usize> {
+        None
+    }
 }
 
+/// The bit of a Cerberus-over-TCP `payload_len` that marks the payload as
+/// compressed.
+const COMPRESSED_BIT: u16 = 1 << 15;
+
+/// The bit of a Cerberus-over-TCP `payload_len` that asks for a streamed
+/// reply, in a request, or marks a reply as streamed.
+pub const STREAM_BIT: u16 = 1 << 14;
+
+/// The bit of a Cerberus-over-TCP `payload_len` that marks the payload as
+/// encrypted under the current session.
+pub const CRYPT_BIT: u16 = 1 << 13;
+
 impl Header for net::CerberusHeader {
     fn from_tcp(
         mut r: impl std::io::Read,
-    ) -> Result<(Self, usize), net::Error> {
+    ) -> Result<(Self, Frame), net::Error> {
         let mut header_bytes = [0u8; 3];
         r.read_exact(&mut header_bytes).map_err(|e| {
             log::error!("{}", e);
@@ -183,23 +994,49 @@ impl Header for net::CerberusHeader {
         })?;
         let [cmd_byte, len_lo, len_hi] = header_bytes;
 
+        // Any command byte is well-formed framing: unknown ones come out as
+        // `CommandType::Error`, so that the server can answer them as
+        // unsupported instead of dropping the connection.
+        if cerberus::CommandType::from_wire_value(cmd_byte).is_none() {
+            log::warn!("unknown command byte: {:#04x}", cmd_byte);
+        }
         let header = Self {
-            command: cerberus::CommandType::from_wire_value(cmd_byte)
-                .ok_or_else(|| {
-                    log::error!("bad command byte: {}", cmd_byte);
-                    net::Error::BadHeader
-                })?,
+            command: cerberus::CommandType::from(cmd_byte),
         };
         let len = u16::from_le_bytes([len_lo, len_hi]);
-        Ok((header, len as usize))
+        let frame = Frame {
+            len: (len & !(COMPRESSED_BIT | STREAM_BIT | CRYPT_BIT)) as usize,
+            compressed: len & COMPRESSED_BIT != 0,
+            integrity: false,
+            streamed: len & STREAM_BIT != 0,
+            encrypted: len & CRYPT_BIT != 0,
+        };
+        Ok((header, frame))
     }
 
     fn to_tcp(
         self,
         msg: &[u8],
+        compressed: bool,
+        streamed: bool,
+        encrypted: bool,
         mut w: impl std::io::Write,
     ) -> Result<(), net::Error> {
-        let [len_lo, len_hi] = (msg.len() as u16).to_le_bytes();
+        let mut len = msg.len() as u16;
+        check!(
+            len & (COMPRESSED_BIT | STREAM_BIT | CRYPT_BIT) == 0,
+            net::Error::BadHeader
+        );
+        if compressed {
+            len |= COMPRESSED_BIT;
+        }
+        if streamed {
+            len |= STREAM_BIT;
+        }
+        if encrypted {
+            len |= CRYPT_BIT;
+        }
+        let [len_lo, len_hi] = len.to_le_bytes();
         w.write_all(&[self.command.to_wire_value(), len_lo, len_hi])
             .map_err(|e| {
                 log::error!("{}", e);
@@ -211,12 +1048,60 @@ impl Header for net::CerberusHeader {
         })?;
         Ok(())
     }
+
+    fn implied_len(&self) -> Option<usize> {
+        use cerberus::CommandType as Ty;
+        // These replies consist only of fixed-size fields, so every one of
+        // them is as long as the arbitrary one below.
+        let len = match self.command {
+            Ty::Error => cerberus::RawError {
+                code: 0,
+                data: [0; 4],
+            }
+            .wire_len(),
+            Ty::FirmwareVersion => {
+                Resp::<cerberus::FirmwareVersion> { version: &[0; 32] }
+                    .wire_len()
+            }
+            Ty::DeviceCapabilities => Resp::<cerberus::DeviceCapabilities> {
+                capabilities: rot::ClientOptions::default().capabilities(),
+                timeouts: capabilities::Timeouts {
+                    regular: Duration::from_secs(0),
+                    crypto: Duration::from_secs(0),
+                },
+            }
+            .wire_len(),
+            Ty::DeviceId => Resp::<cerberus::DeviceId> {
+                id: DeviceIdentifier {
+                    vendor_id: 0,
+                    device_id: 0,
+                    subsys_vendor_id: 0,
+                    subsys_id: 0,
+                },
+            }
+            .wire_len(),
+            Ty::ResetCounter => {
+                Resp::<cerberus::ResetCounter> { count: 0 }.wire_len()
+            }
+            Ty::DeviceUptime => Resp::<cerberus::DeviceUptime> {
+                uptime: Duration::from_secs(0),
+            }
+            .wire_len(),
+            Ty::RequestCounter => Resp::<cerberus::RequestCounter> {
+                ok_count: 0,
+                err_count: 0,
+            }
+            .wire_len(),
+            _ => return None,
+        };
+        len.ok()
+    }
 }
 
 impl Header for net::SpdmHeader {
     fn from_tcp(
         mut r: impl std::io::Read,
-    ) -> Result<(Self, usize), net::Error> {
+    ) -> Result<(Self, Frame), net::Error> {
         let mut header_bytes = [0u8; 4];
         r.read_exact(&mut header_bytes).map_err(|e| {
             log::error!("{}", e);
@@ -238,14 +1123,36 @@ impl Header for net::SpdmHeader {
             is_request: cmd_byte & 0x80 != 0,
             version: version.into(),
         };
-        Ok((header, len as usize))
+        let frame = Frame {
+            len: len as usize,
+            compressed: false,
+            integrity: false,
+            streamed: false,
+            encrypted: false,
+        };
+        Ok((header, frame))
     }
 
     fn to_tcp(
         self,
         msg: &[u8],
+        compressed: bool,
+        streamed: bool,
+        encrypted: bool,
         mut w: impl std::io::Write,
     ) -> Result<(), net::Error> {
+        if compressed {
+            log::error!("SPDM-over-TCP does not support compression");
+            return Err(fail!(net::Error::BadHeader));
+        }
+        if streamed {
+            log::error!("SPDM-over-TCP does not support streaming");
+            return Err(fail!(net::Error::BadHeader));
+        }
+        if encrypted {
+            log::error!("SPDM-over-TCP does not support encryption");
+            return Err(fail!(net::Error::BadHeader));
+        }
         let [len_lo, len_hi] = (msg.len() as u16 + 4).to_le_bytes();
         let cmd_byte =
             ((self.is_request as u8) << 7) | self.command.to_wire_value();
@@ -266,13 +1173,30 @@ impl Header for net::SpdmHeader {
 
 /// A helper for constructing X-over-TCP messages, for `X in [Cerberus, Spdm]`.
 ///
-/// Because an X-over-TCP header requires a length prefix for the payload,
-/// we need to buffer the entire reply before writing the header.
+/// Because an X-over-TCP header usually requires a length prefix for the
+/// payload, we need to buffer the entire reply before writing the header,
+/// unless the reply is [streamed](Writer::stream_to).
 ///
 /// This type implements [`manticore::io::Write`].
 struct Writer<H> {
     header: H,
     buf: Vec<u8>,
+    /// Whether to compress the buffered data before writing it out.
+    compress: bool,
+    /// Whether to write [`MAGIC`] before the header.
+    magic: bool,
+    /// Whether to write the MCTP message type before the header.
+    message_type: bool,
+    /// Whether to set the integrity-check bit in the message type, and
+    /// append a tag to the payload.
+    integrity: bool,
+    /// Whether to set [`STREAM_BIT`] in the header.
+    streamed: bool,
+    /// Whether to set [`CRYPT_BIT`] in the header.
+    encrypted: bool,
+    /// If the header has already been sent, the stream that the payload is
+    /// written straight through to, along with how many bytes of it remain.
+    direct: Option<(Box<dyn std::io::Write + Send>, usize)>,
 }
 
 impl<H: Header> Writer<H> {
@@ -281,23 +1205,231 @@ impl<H: Header> Writer<H> {
         Self {
             header,
             buf: Vec::new(),
+            compress: false,
+            magic: false,
+            message_type: false,
+            integrity: false,
+            streamed: false,
+            encrypted: false,
+            direct: None,
         }
     }
 
+    /// Sends the header to `w` right away, so that the payload can be
+    /// written straight through to it rather than buffered.
+    ///
+    /// The header must imply the length of the payload, which may be neither
+    /// compressed nor integrity-checked.
+    pub fn stream_to(
+        &mut self,
+        mut w: impl std::io::Write + Send + 'static,
+    ) -> Result<(), net::Error> {
+        let len = match self.header.implied_len() {
+            Some(len) if !self.compress && !self.integrity => len,
+            _ => {
+                log::error!("cannot stream a reply to {:?}", self.header);
+                return Err(fail!(net::Error::BadHeader));
+            }
+        };
+        check!(
+            self.buf.is_empty() && self.direct.is_none(),
+            net::Error::OutOfOrder
+        );
+
+        self.write_prefix(&mut w)?;
+        self.header.to_tcp(&[], false, true, false, &mut w)?;
+        self.direct = Some((Box::new(w), len));
+        Ok(())
+    }
+
     /// Flushes the buffered data to the given [`std::io::Write`] (usually, a
     /// [`TcpStream`]).
-    pub fn finish(self, w: impl std::io::Write) -> Result<(), net::Error> {
-        self.header.to_tcp(&self.buf, w)
+    ///
+    /// If the payload was streamed, `w` is ignored, and this instead checks
+    /// that the whole payload was written.
+    pub fn finish(
+        mut self,
+        mut w: impl std::io::Write,
+    ) -> Result<(), net::Error> {
+        if let Some((direct, remaining)) = &mut self.direct {
+            if *remaining != 0 {
+                log::error!("streamed payload is {} bytes short", remaining);
+                return Err(fail!(net::Error::Io(io::Error::BufferExhausted)));
+            }
+            return direct.flush().map_err(|e| {
+                log::error!("{}", e);
+                fail!(net::Error::Io(io::Error::Internal))
+            });
+        }
+
+        self.write_prefix(&mut w)?;
+        let integrity = self.message_type && self.integrity;
+        let mut payload = if self.compress {
+            let mut compressed = Vec::new();
+            rle::compress(&self.buf, io::write::StdWrite(&mut compressed))?;
+            log::info!(
+                "compressed {} bytes into {}",
+                self.buf.len(),
+                compressed.len()
+            );
+            compressed
+        } else {
+            self.buf
+        };
+        if integrity {
+            let tag = integrity::compute(&payload);
+            payload.extend_from_slice(&tag);
+        }
+        self.header.to_tcp(
+            &payload,
+            self.compress,
+            self.streamed,
+            self.encrypted,
+            w,
+        )
+    }
+
+    /// Writes whatever precedes the header: the frame marker and message
+    /// type, if enabled.
+    fn write_prefix(
+        &self,
+        mut w: impl std::io::Write,
+    ) -> Result<(), net::Error> {
+        if self.magic {
+            w.write_all(&MAGIC).map_err(|e| {
+                log::error!("{}", e);
+                io::Error::BufferExhausted
+            })?;
+        }
+        if self.message_type {
+            let mut byte = H::MESSAGE_TYPE.to_wire_value();
+            if self.integrity {
+                byte |= integrity::IC_BIT;
+            }
+            w.write_all(&[byte]).map_err(|e| {
+                log::error!("{}", e);
+                io::Error::BufferExhausted
+            })?;
+        }
+        Ok(())
     }
 }
 
 impl<H> io::Write for Writer<H> {
-    fn write_bytes(&mut self, buf: &[u8]) -> Result<(), io::Error> {
-        self.buf.extend_from_slice(buf);
+    fn write_bytes(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        let (w, remaining) = match &mut self.direct {
+            Some((w, remaining)) => (w, remaining),
+            None => {
+                self.buf.extend_from_slice(buf);
+                return Ok(buf.len());
+            }
+        };
+
+        check!(buf.len() <= *remaining, io::Error::BufferExhausted);
+        w.write_all(buf).map_err(|e| {
+            log::error!("{}", e);
+            io::Error::Internal
+        })?;
+        *remaining -= buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        if let Some((w, _)) = &mut self.direct {
+            w.flush().map_err(|e| {
+                log::error!("{}", e);
+                io::Error::Internal
+            })?;
+        }
         Ok(())
     }
 }
 
+/// An X-over-TCP implementation of [`ClientPort`].
+///
+/// This type lets a device send requests to a peer listening on `localhost`,
+/// such as another device's [`TcpHostPort`], using exactly the framing that a
+/// `TcpHostPort` expects. Like [`Client`], it keeps one connection open for
+/// its entire lifetime.
+pub struct TcpClientPort<H = net::CerberusHeader> {
+    conn: TcpStream,
+    opts: SendOptions,
+    response: Option<TcpClientResponse<H>>,
+}
+
+/// The [`ClientResponse`] half of a [`TcpClientPort`].
+struct TcpClientResponse<H> {
+    header: H,
+    r: TcpReader,
+}
+
+impl<H> TcpClientPort<H> {
+    /// Connects to a peer listening on `localhost:{port}`.
+    ///
+    /// `opts` determines how each request is framed, and how each response is
+    /// expected to be; `opts.retry` is ignored.
+    pub fn connect(port: u16, opts: SendOptions) -> Result<Self, net::Error> {
+        Ok(Self {
+            conn: connect(port)?,
+            opts,
+            response: None,
+        })
+    }
+}
+
+impl<'resp, H: Header + 'resp> ClientPort<'resp, H> for TcpClientPort<H> {
+    fn send(
+        &mut self,
+        header: H,
+        payload: &[u8],
+    ) -> Result<&mut dyn ClientResponse<'resp, H>, net::Error> {
+        // Skip past whatever the caller left unread of the previous
+        // response, so that its tail is not mistaken for the next header.
+        if let Some(mut prev) = self.response.take() {
+            let mut rest = vec![0; prev.r.remaining_data()];
+            prev.r.read_bytes(&mut rest)?;
+        }
+
+        let mut writer = Writer::new(header);
+        writer.compress = self.opts.compress;
+        writer.magic = self.opts.magic;
+        writer.message_type = self.opts.message_type;
+        writer.integrity = self.opts.integrity;
+        writer.streamed = self.opts.streaming;
+        writer.encrypted = self.opts.encrypted;
+        writer.buf.extend_from_slice(payload);
+        writer.finish(&mut self.conn)?;
+
+        log::info!("waiting for response");
+        let (header, frame) = read_header::<H, _>(
+            &mut self.conn,
+            self.opts.magic,
+            self.opts.message_type,
+            usize::MAX,
+        )?;
+        let frame = reply_frame(header, frame)?;
+        let conn = self.conn.try_clone().map_err(|e| {
+            log::error!("{}", e);
+            net::Error::Io(io::Error::Internal)
+        })?;
+        let r = TcpReader::new(conn, frame)?;
+        self.response = Some(TcpClientResponse { header, r });
+        Ok(self.response.as_mut().unwrap())
+    }
+}
+
+impl<'resp, H: Header + 'resp> ClientResponse<'resp, H>
+    for TcpClientResponse<H>
+{
+    fn header(&self) -> Result<H, net::Error> {
+        Ok(self.header)
+    }
+
+    fn payload(&mut self) -> Result<&mut dyn io::ReadZero<'resp>, net::Error> {
+        Ok(&mut self.r)
+    }
+}
+
 /// A Cerberus-over-TCP implementation of [`HostPort`].
 ///
 /// This type can be used to drive a Manticore server using a TCP port bound to
@@ -305,6 +1437,160 @@ impl<H> io::Write for Writer<H> {
 /// implement [`HostPort`] for their own transport.
 pub struct TcpHostPort<H = net::CerberusHeader>(Inner<H>);
 
+/// A handle for shutting down a [`TcpHostPort`], possibly from another
+/// thread.
+///
+/// See [`TcpHostPort::shutdown_handle()`].
+#[derive(Clone, Debug)]
+pub struct ShutdownHandle {
+    flag: Arc<AtomicBool>,
+    // The listener to wake up, if the port has one.
+    addr: Option<SocketAddr>,
+}
+
+impl ShutdownHandle {
+    /// Shuts down the associated port.
+    ///
+    /// The current call to `receive()`, if the port is blocked in one, and all
+    /// future calls will return [`net::Error::Shutdown`].
+    pub fn shutdown(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+
+        // Wake up a blocked `accept()` by connecting to the listener; the
+        // port notices the flag and drops this connection immediately.
+        let addr = match self.addr {
+            Some(addr) => addr,
+            None => return,
+        };
+        if let Err(e) = TcpStream::connect(addr) {
+            log::warn!("failed to wake up listener: {}", e);
+        }
+    }
+
+    /// Returns whether [`ShutdownHandle::shutdown()`] has been called on this
+    /// handle or any of its clones.
+    pub fn is_shutdown(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+    }
+}
+
+/// The default for [`TcpHostPort::set_payload_ceiling()`].
+///
+/// This is well above any message size a Manticore server negotiates, but
+/// keeps a hostile header from tying the port up waiting for tens of kilobytes
+/// that may never arrive.
+pub const DEFAULT_PAYLOAD_CEILING: usize = 8192;
+
+/// How often a port waiting on an idle connection checks for shutdown.
+const IDLE_POLL: Duration = Duration::from_millis(50);
+
+/// Limits on how long a [`TcpHostPort`] waits for a request to arrive.
+///
+/// Both limits are measured from when the port starts waiting on a
+/// connection for a request, and either may be disabled independently. If a
+/// request is not fully received within budget, `receive()` drops the
+/// connection and returns [`net::Error::Timeout`].
+///
+/// While a budget is set, the port reads each request's payload in full
+/// before handing it to the server.
+#[derive(Copy, Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct ReadBudget {
+    /// The maximum time to receive a whole request.
+    pub per_message: Option<Duration>,
+
+    /// The minimum average rate, in bytes per second, at which a request must
+    /// arrive.
+    ///
+    /// The peer is allowed [`RATE_GRACE`] of slack on top of this, to account
+    /// for connection setup and small requests.
+    pub min_rate: Option<u32>,
+}
+
+/// Slack given to a peer on top of [`ReadBudget::min_rate`].
+pub const RATE_GRACE: Duration = Duration::from_millis(250);
+
+impl ReadBudget {
+    /// Returns whether either limit is set.
+    fn is_enabled(&self) -> bool {
+        self.per_message.is_some() || self.min_rate.is_some()
+    }
+
+    /// Returns the time by which the next byte must arrive, given that the
+    /// peer started sending at `start`, and has sent `read` bytes so far.
+    fn deadline(&self, start: Ticks, read: usize) -> Option<Ticks> {
+        let per_message = self
+            .per_message
+            .map(|d| start.saturating_add(clock::ticks(d)));
+        let rate = self.min_rate.map(|rate| {
+            let secs = read as f64 / rate.max(1) as f64;
+            let slack = RATE_GRACE + Duration::from_secs_f64(secs);
+            start.saturating_add(clock::ticks(slack))
+        });
+        match (per_message, rate) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+/// A [`std::io::Read`] over a [`TcpStream`] that enforces a [`ReadBudget`].
+struct Budgeted<'a> {
+    stream: &'a TcpStream,
+    budget: ReadBudget,
+    clock: &'a dyn Clock,
+    start: Ticks,
+    read: usize,
+    expired: bool,
+}
+
+impl<'a> Budgeted<'a> {
+    fn new(
+        stream: &'a TcpStream,
+        budget: ReadBudget,
+        clock: &'a dyn Clock,
+    ) -> Self {
+        Self {
+            stream,
+            budget,
+            clock,
+            start: clock.now(),
+            read: 0,
+            expired: false,
+        }
+    }
+}
+
+impl std::io::Read for Budgeted<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if let Some(deadline) = self.budget.deadline(self.start, self.read) {
+            let now = self.clock.now();
+            if now >= deadline {
+                self.expired = true;
+                return Err(ErrorKind::TimedOut.into());
+            }
+            let timeout = Duration::from_micros(deadline - now);
+            self.stream.set_read_timeout(Some(timeout))?;
+        }
+
+        match self.stream.read(buf) {
+            Ok(n) => {
+                self.read += n;
+                Ok(n)
+            }
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::WouldBlock | ErrorKind::TimedOut
+                ) =>
+            {
+                self.expired = true;
+                Err(e)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
 /// The "inner" state of the `HostPort`. This type is intended to carry the state
 /// and functionality for an in-process request/response flow, without making it
 /// accessible to outside callers except through the associated [`manticore::net`]
@@ -316,12 +1602,52 @@ pub struct TcpHostPort<H = net::CerberusHeader>(Inner<H>);
 /// though users may only move from one trait implementation to the other by calling
 /// methods like `reply()` and `payload()`.
 struct Inner<H> {
-    listener: TcpListener,
+    // The listener to accept connections from, unless this port was created
+    // from a single, already-accepted connection.
+    listener: Option<TcpListener>,
+    // The address of `listener`, or the local end of the connection.
+    local_addr: SocketAddr,
+    // Set once this port has been shut down.
+    shutdown: Arc<AtomicBool>,
+    // Whether this port is willing to exchange compressed payloads.
+    compression: bool,
+    // Whether this port is willing to stream replies.
+    streaming: bool,
+    // Whether frames are prefixed with `MAGIC`.
+    magic: bool,
+    // Whether frames are prefixed with an MCTP message type.
+    message_type: bool,
+    // Limits on how long a request may take to arrive.
+    budget: ReadBudget,
+    // The time source for `budget`.
+    clock: Arc<dyn Clock + Send + Sync>,
+    // The largest payload length a request header may declare.
+    payload_ceiling: usize,
     // State for `HostRequest`: a parsed header, the length of the payload, and
     // a stream to read it from.
     stream: Option<(H, usize, TcpStream)>,
+    // If the request was compressed, or received under a budget, the full
+    // (decompressed) payload, which is read from instead of the stream.
+    buffered: Option<std::io::Cursor<Vec<u8>>>,
+    // Whether the request was compressed.
+    compressed: bool,
+    // Whether the request carried an integrity-check tag.
+    integrity: bool,
+    // Whether the request asked for a streamed reply.
+    stream_reply: bool,
+    // Whether the request was marked as encrypted.
+    encrypted: bool,
+    // Whether the peer sent more bytes before being replied to.
+    interleaved: bool,
+    // A connection whose last request has been fully handled, which may be
+    // reused for the next request if the peer keeps it open.
+    idle: Option<TcpStream>,
     // State for `HostResponse`: a `Writer` to dump the response bytes into.
     output_buffer: Option<Writer<H>>,
+    // Signal handlers registered by `shutdown_on_signals()`, which are
+    // removed when this port is dropped.
+    #[cfg(unix)]
+    signals: Vec<signal_hook::SigId>,
 }
 
 impl<H> TcpHostPort<H> {
@@ -331,16 +1657,199 @@ impl<H> TcpHostPort<H> {
             log::error!("{}", e);
             net::Error::Io(io::Error::Internal)
         })?;
-        Ok(Self(Inner {
+        let local_addr = listener.local_addr().map_err(|e| {
+            log::error!("{}", e);
+            net::Error::Io(io::Error::Internal)
+        })?;
+        Ok(Self::new(Some(listener), local_addr, None))
+    }
+
+    /// Creates a `TcpHostPort` that serves requests from a single connection
+    /// that has already been accepted elsewhere.
+    ///
+    /// The port serves as many requests as the peer sends on `stream`, like it
+    /// would for any connection it keeps open. Once the peer hangs up, or a
+    /// request is not read in full, `receive()` returns
+    /// [`net::Error::Disconnected`] rather than waiting for a new connection.
+    pub fn from_stream(stream: TcpStream) -> Result<Self, net::Error> {
+        let local_addr = stream.local_addr().map_err(|e| {
+            log::error!("{}", e);
+            net::Error::Io(io::Error::Internal)
+        })?;
+        Ok(Self::new(None, local_addr, Some(stream)))
+    }
+
+    fn new(
+        listener: Option<TcpListener>,
+        local_addr: SocketAddr,
+        idle: Option<TcpStream>,
+    ) -> Self {
+        Self(Inner {
             listener,
+            local_addr,
+            shutdown: Arc::new(AtomicBool::new(false)),
+            compression: false,
+            streaming: false,
+            magic: false,
+            message_type: false,
+            budget: ReadBudget::default(),
+            clock: Arc::new(SystemClock::new()),
+            payload_ceiling: DEFAULT_PAYLOAD_CEILING,
             stream: None,
+            buffered: None,
+            compressed: false,
+            integrity: false,
+            stream_reply: false,
+            encrypted: false,
+            interleaved: false,
+            idle,
             output_buffer: None,
-        }))
+            #[cfg(unix)]
+            signals: Vec::new(),
+        })
+    }
+
+    /// Allows this port to accept compressed requests.
+    ///
+    /// Replies to compressed requests will themselves be compressed. This
+    /// should only be enabled if the server advertises the `has_compression`
+    /// capability.
+    pub fn enable_compression(&mut self) {
+        self.0.compression = true;
+    }
+
+    /// Streams replies whose length is implied by their command type
+    /// straight out to the peer, rather than buffering them, when the request
+    /// asks for it.
+    ///
+    /// Requests that do not ask, such as those from peers that have not
+    /// negotiated the `has_streaming` capability, are unaffected. This should
+    /// only be enabled if the server advertises that capability.
+    pub fn enable_streaming(&mut self) {
+        self.0.streaming = true;
+    }
+
+    /// Requires every request to be prefixed with [`MAGIC`], and prefixes
+    /// every reply with it.
+    ///
+    /// If a request has a malformed header, this port will skip ahead to
+    /// the next frame marker instead of failing on every subsequent request.
+    /// Peers must opt into this as well, such as with [`SendOptions::magic`].
+    pub fn enable_magic(&mut self) {
+        self.0.magic = true;
+    }
+
+    /// Requires every request to be prefixed with an MCTP message type, and
+    /// prefixes every reply with one.
+    ///
+    /// Requests tagged with a message type other than `H`'s are rejected with
+    /// [`net::Error::UnsupportedMessageType`], and their connection is dropped.
+    /// Peers must opt into this as well, such as with
+    /// [`SendOptions::message_type`].
+    pub fn enable_message_type(&mut self) {
+        self.0.message_type = true;
+    }
+
+    /// Sets limits on how long a peer may take to send a request.
+    ///
+    /// See [`ReadBudget`].
+    pub fn set_read_budget(&mut self, budget: ReadBudget) {
+        self.0.budget = budget;
+    }
+
+    /// Sets the time source used to enforce the read budget.
+    ///
+    /// Defaults to a [`SystemClock`].
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock + Send + Sync>) {
+        self.0.clock = clock;
+    }
+
+    /// Sets the largest payload length a request header may declare.
+    ///
+    /// Requests whose header declares a longer payload are rejected with
+    /// [`net::Error::BadHeader`] before any of the payload is read, or, if
+    /// [`MAGIC`] is enabled, skipped over as garbage. This is independent of
+    /// any negotiated maximum message size, and defaults to
+    /// [`DEFAULT_PAYLOAD_CEILING`].
+    pub fn set_payload_ceiling(&mut self, ceiling: usize) {
+        self.0.payload_ceiling = ceiling;
+    }
+
+    /// Returns a handle that can be used to shut this port down.
+    ///
+    /// The handle may be freely cloned and sent to other threads.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle {
+            flag: Arc::clone(&self.0.shutdown),
+            addr: self.0.listener.as_ref().map(|_| self.0.local_addr),
+        }
+    }
+
+    /// Shuts this port down as soon as the process receives one of
+    /// `signals`, such as `SIGINT` from Ctrl-C.
+    ///
+    /// Each signal's handler writes to a self-pipe, whose other end is watched
+    /// by a helper thread that calls [`ShutdownHandle::shutdown()`] on this
+    /// port's behalf. A `receive()` blocked in `accept()` therefore returns
+    /// [`net::Error::Shutdown`] immediately, rather than after the next
+    /// connection arrives. The handlers are removed, and the helper thread
+    /// exits, once this port is dropped.
+    ///
+    /// Signals are only caught on Unix. Elsewhere, this does nothing, and the
+    /// port can only be shut down through a [`ShutdownHandle`]; a port waiting
+    /// on an idle connection notices that within a short polling timeout, as
+    /// it always does.
+    #[cfg(unix)]
+    pub fn shutdown_on_signals(
+        &mut self,
+        signals: &[i32],
+    ) -> Result<(), net::Error> {
+        let io_err = |e: std::io::Error| {
+            log::error!("{}", e);
+            net::Error::Io(io::Error::Internal)
+        };
+
+        let (mut pipe, write) = UnixStream::pair().map_err(io_err)?;
+        for &signal in signals {
+            let write = write.try_clone().map_err(io_err)?;
+            let id = signal_hook::low_level::pipe::register(signal, write)
+                .map_err(io_err)?;
+            self.0.signals.push(id);
+        }
+        // Only the handlers may keep the pipe open; once they are gone, the
+        // helper thread sees EOF.
+        drop(write);
+
+        let handle = self.shutdown_handle();
+        thread::spawn(move || {
+            if let Ok(1) = pipe.read(&mut [0]) {
+                log::info!("caught signal; shutting down");
+                handle.shutdown();
+            }
+        });
+        Ok(())
+    }
+
+    /// Shuts this port down as soon as the process receives one of
+    /// `signals`.
+    ///
+    /// Signals are only caught on Unix, so this does nothing on this
+    /// platform; see the Unix version of this function.
+    #[cfg(not(unix))]
+    pub fn shutdown_on_signals(
+        &mut self,
+        _signals: &[i32],
+    ) -> Result<(), net::Error> {
+        log::warn!("signals are not caught on this platform");
+        Ok(())
     }
 
     /// Returns the TCP port this `HostPort` is bound to.
+    ///
+    /// For a port created with [`TcpHostPort::from_stream()`], this is the
+    /// local port of the connection.
     pub fn port(&self) -> u16 {
-        self.0.listener.local_addr().unwrap().port()
+        self.0.local_addr.port()
     }
 }
 
@@ -348,21 +1857,189 @@ impl<'req, H: Header + 'req> HostPort<'req, H> for TcpHostPort<H> {
     fn receive(&mut self) -> Result<&mut dyn HostRequest<'req, H>, net::Error> {
         let inner = &mut self.0;
         inner.stream = None;
+        inner.buffered = None;
+        inner.compressed = false;
+        inner.integrity = false;
+        inner.stream_reply = false;
+        inner.encrypted = false;
+        inner.interleaved = false;
+
+        check!(!inner.is_shutdown(), net::Error::Shutdown);
+
+        let next = match inner.idle.take() {
+            Some(stream) => inner.wait_for_request(stream)?,
+            None => None,
+        };
+        let mut stream = match next {
+            Some(stream) => stream,
+            None => {
+                let listener = match &inner.listener {
+                    Some(listener) => listener,
+                    None => {
+                        log::info!(
+                            "connection closed; no listener to fall back on"
+                        );
+                        return Err(fail!(net::Error::Disconnected));
+                    }
+                };
+                log::info!("blocking on listener");
+                let (stream, _) = listener.accept().map_err(|e| {
+                    log::error!("{}", e);
+                    net::Error::Io(io::Error::Internal)
+                })?;
+                check!(!inner.is_shutdown(), net::Error::Shutdown);
+                stream
+            }
+        };
+
+        let budget = inner.budget;
+        if !budget.is_enabled() {
+            let (header, len) = inner.read_request(&mut stream)?;
+            inner.stream = Some((header, len, stream));
+            return Ok(inner);
+        }
 
-        log::info!("blocking on listener");
-        let (mut stream, _) = inner.listener.accept().map_err(|e| {
+        let clock = Arc::clone(&inner.clock);
+        let mut r = Budgeted::new(&stream, budget, &*clock);
+        let result = inner.read_request(&mut r).and_then(|(header, len)| {
+            if inner.buffered.is_none() {
+                let mut payload = vec![0; len];
+                r.read_exact(&mut payload).map_err(|e| {
+                    log::error!("{}", e);
+                    net::Error::Io(io::Error::Internal)
+                })?;
+                inner.buffered = Some(std::io::Cursor::new(payload));
+            }
+            Ok((header, len))
+        });
+        if r.expired {
+            log::error!(
+                "request exceeded its read budget; dropping connection"
+            );
+            return Err(fail!(net::Error::Timeout));
+        }
+        let (header, len) = result?;
+
+        stream.set_read_timeout(None).map_err(|e| {
             log::error!("{}", e);
             net::Error::Io(io::Error::Internal)
         })?;
-
-        log::info!("parsing header");
-        let (header, len) = H::from_tcp(&mut stream)?;
         inner.stream = Some((header, len, stream));
 
         Ok(inner)
     }
 }
 
+impl<H: Header> Inner<H> {
+    /// Reads a request header from `r`, along with its payload, if it is
+    /// compressed or carries an integrity check.
+    ///
+    /// Returns the header and the (decompressed) length of the payload.
+    fn read_request(
+        &mut self,
+        mut r: impl std::io::Read,
+    ) -> Result<(H, usize), net::Error> {
+        log::info!("parsing header");
+        let (header, frame) = read_header::<H, _>(
+            &mut r,
+            self.magic,
+            self.message_type,
+            self.payload_ceiling,
+        )?;
+
+        let checked = if frame.integrity {
+            Some(read_checked(&mut r, frame.len)?)
+        } else {
+            None
+        };
+        self.integrity = frame.integrity;
+        self.stream_reply = frame.streamed;
+        self.encrypted = frame.encrypted;
+        if !frame.compressed {
+            return Ok(match checked {
+                Some(payload) => {
+                    let len = payload.len();
+                    self.buffered = Some(std::io::Cursor::new(payload));
+                    (header, len)
+                }
+                None => (header, frame.len),
+            });
+        }
+
+        if !self.compression {
+            log::error!("got compressed request, but compression is off");
+            return Err(fail!(net::Error::BadHeader));
+        }
+        let payload = match checked {
+            Some(compressed) => inflate(&compressed[..], compressed.len())?,
+            None => inflate(&mut r, frame.len)?,
+        };
+        let len = payload.len();
+        self.buffered = Some(std::io::Cursor::new(payload));
+        self.compressed = true;
+        Ok((header, len))
+    }
+}
+
+impl<H> Inner<H> {
+    fn is_shutdown(&self) -> bool {
+        self.shutdown.load(Ordering::SeqCst)
+    }
+
+    /// Waits for the peer on an idle connection to either send another
+    /// request or hang up, returning the connection to read the next request
+    /// from, if any.
+    ///
+    /// Clients that only send one request per connection hang up as soon as
+    /// they've read their reply. Clients that keep their connection open
+    /// without sending anything would otherwise lock every other peer out, so
+    /// the listener is polled as well; if another peer connects first, the
+    /// idle connection is dropped in favor of the new one.
+    fn wait_for_request(
+        &self,
+        stream: TcpStream,
+    ) -> Result<Option<TcpStream>, net::Error> {
+        let io_err = |e: std::io::Error| {
+            log::error!("{}", e);
+            net::Error::Io(io::Error::Internal)
+        };
+
+        stream.set_read_timeout(Some(IDLE_POLL)).map_err(io_err)?;
+        let ready = loop {
+            match stream.peek(&mut [0]) {
+                Ok(n) => break n > 0,
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        ErrorKind::WouldBlock | ErrorKind::TimedOut
+                    ) =>
+                {
+                    check!(!self.is_shutdown(), net::Error::Shutdown);
+                    let listener = match &self.listener {
+                        Some(listener) => listener,
+                        None => continue,
+                    };
+                    if let Some(new) = try_accept(listener)? {
+                        check!(!self.is_shutdown(), net::Error::Shutdown);
+                        log::info!(
+                            "new connection while idle; dropping idle \
+                             connection"
+                        );
+                        return Ok(Some(new));
+                    }
+                }
+                Err(_) => break false,
+            }
+        };
+        if !ready {
+            return Ok(None);
+        }
+        stream.set_read_timeout(None).map_err(io_err)?;
+        log::info!("reusing connection");
+        Ok(Some(stream))
+    }
+}
+
 impl<'req, H: Header + 'req> HostRequest<'req, H> for Inner<H> {
     fn header(&self) -> Result<H, net::Error> {
         if self.output_buffer.is_some() {
@@ -388,20 +2065,85 @@ impl<'req, H: Header + 'req> HostRequest<'req, H> for Inner<H> {
         Ok(self)
     }
 
+    fn payload_len(&self) -> Option<usize> {
+        if self.output_buffer.is_some() {
+            return None;
+        }
+        self.stream.as_ref().map(|(_, len, _)| *len)
+    }
+
+    fn payload_bytes(
+        &mut self,
+        arena: &'req dyn Arena,
+    ) -> Result<&'req [u8], net::Error> {
+        let len = HostRequest::<H>::payload_len(self).unwrap_or(0);
+        if len > self.payload_ceiling {
+            log::error!(
+                "payload of {} bytes is over the ceiling of {}",
+                len,
+                self.payload_ceiling
+            );
+            return Err(fail!(net::Error::BadHeader));
+        }
+
+        // Even a payload that was buffered whole, such as for a checksum, has
+        // to be copied: the buffer does not outlive the next `receive()`, but
+        // the returned slice must.
+        HostRequest::<H>::payload(self)?;
+        let bytes = arena
+            .alloc_slice::<u8>(len)
+            .map_err(|_| fail!(net::Error::Io(io::Error::BufferExhausted)))?;
+        io::Read::read_bytes(self, bytes)?;
+        Ok(bytes)
+    }
+
+    fn is_encrypted(&self) -> bool {
+        self.encrypted
+    }
+
     fn reply(
         &mut self,
         header: H,
     ) -> Result<&mut dyn HostResponse<'req>, net::Error> {
-        if self.stream.is_none() {
-            log::error!("payload() called out-of-order");
-            return Err(fail!(net::Error::Disconnected));
-        }
+        let (len, stream) = match &self.stream {
+            Some((_, len, stream)) => (*len, stream),
+            None => {
+                log::error!("payload() called out-of-order");
+                return Err(fail!(net::Error::Disconnected));
+            }
+        };
         if self.output_buffer.is_some() {
             log::error!("payload() called out-of-order");
             return Err(fail!(net::Error::OutOfOrder));
         }
 
-        self.output_buffer = Some(Writer::new(header));
+        // A well-behaved peer waits for this reply before sending anything
+        // else, so any bytes already waiting must be a request sent out of
+        // turn. This has to be checked before any of the reply goes out,
+        // since the peer may legitimately send its next request as soon as it
+        // has read it.
+        self.interleaved = len == 0 && has_pending_input(stream)?;
+
+        let mut writer = Writer::new(header);
+        writer.compress = self.compressed;
+        writer.magic = self.magic;
+        writer.message_type = self.message_type;
+        writer.integrity = self.integrity;
+        if self.streaming
+            && self.stream_reply
+            && !self.compressed
+            && !self.integrity
+            && header.implied_len().is_some()
+        {
+            log::info!("streaming reply");
+            let conn = stream.try_clone().map_err(|e| {
+                log::error!("{}", e);
+                net::Error::Io(io::Error::Internal)
+            })?;
+            writer.streamed = true;
+            writer.stream_to(conn)?;
+        }
+        self.output_buffer = Some(writer);
         Ok(self)
     }
 }
@@ -422,30 +2164,130 @@ impl<'req, H: Header + 'req> HostResponse<'req> for Inner<H> {
     fn finish(&mut self) -> Result<(), net::Error> {
         match self {
             Inner {
-                stream: Some((_, _, stream)),
+                stream: Some(_),
                 output_buffer: Some(_),
                 ..
             } => {
                 log::info!("sending reply");
-                self.output_buffer.take().unwrap().finish(&mut *stream)?;
+                let (_, len, mut stream) = self.stream.take().unwrap();
+                let interleaved = self.interleaved;
+
+                self.output_buffer.take().unwrap().finish(&mut stream)?;
                 stream.flush().map_err(|e| {
                     log::error!("{}", e);
                     net::Error::Io(io::Error::Internal)
                 })?;
-                self.stream = None;
-                self.output_buffer = None;
+                self.buffered = None;
+                self.compressed = false;
+                self.integrity = false;
+                self.stream_reply = false;
+
+                if interleaved {
+                    log::error!(
+                        "peer sent a request before reading the previous \
+                         reply; dropping connection"
+                    );
+                    // Closing a socket with unread data makes the peer see a
+                    // reset, which may discard the reply it has not read yet.
+                    discard_pending_input(&stream);
+                    return Err(fail!(net::Error::OutOfOrder));
+                }
+
+                // Only keep the connection around if the request was read in
+                // full; otherwise, the rest of it would be mistaken for the
+                // next request.
+                if len == 0 {
+                    self.idle = Some(stream);
+                }
                 Ok(())
             }
+            Inner {
+                stream: Some(_),
+                output_buffer: None,
+                ..
+            } => {
+                log::error!("finish() called before reply()");
+                Err(fail!(net::Error::NoResponseStarted))
+            }
             _ => Err(fail!(net::Error::Disconnected)),
         }
     }
 }
 
+/// Returns whether the peer has sent bytes on `stream` that have not been read
+/// yet, without blocking.
+fn has_pending_input(stream: &TcpStream) -> Result<bool, net::Error> {
+    let io_err = |e: std::io::Error| {
+        log::error!("{}", e);
+        net::Error::Io(io::Error::Internal)
+    };
+
+    stream.set_nonblocking(true).map_err(io_err)?;
+    let pending = match stream.peek(&mut [0]) {
+        Ok(n) => n > 0,
+        Err(e) if e.kind() == ErrorKind::WouldBlock => false,
+        Err(e) => return Err(fail!(io_err(e))),
+    };
+    stream.set_nonblocking(false).map_err(io_err)?;
+    Ok(pending)
+}
+
+/// Accepts a connection on `listener` if one is already waiting, without
+/// blocking.
+fn try_accept(listener: &TcpListener) -> Result<Option<TcpStream>, net::Error> {
+    let io_err = |e: std::io::Error| {
+        log::error!("{}", e);
+        net::Error::Io(io::Error::Internal)
+    };
+
+    listener.set_nonblocking(true).map_err(io_err)?;
+    let accepted = match listener.accept() {
+        Ok((stream, _)) => Ok(Some(stream)),
+        Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(None),
+        Err(e) => Err(e),
+    };
+    listener.set_nonblocking(false).map_err(io_err)?;
+    let accepted = accepted.map_err(|e| fail!(io_err(e)))?;
+    if let Some(stream) = &accepted {
+        // Some platforms hand out sockets that inherit the listener's
+        // non-blocking flag.
+        stream.set_nonblocking(false).map_err(io_err)?;
+    }
+    Ok(accepted)
+}
+
+/// Reads and throws away whatever the peer has already sent on `stream`,
+/// without blocking.
+fn discard_pending_input(mut stream: &TcpStream) {
+    if stream.set_nonblocking(true).is_err() {
+        return;
+    }
+    let mut scratch = [0; 256];
+    while let Ok(n) = stream.read(&mut scratch) {
+        if n == 0 {
+            break;
+        }
+    }
+}
+
+#[cfg(unix)]
+impl<H> Drop for Inner<H> {
+    fn drop(&mut self) {
+        for id in self.signals.drain(..) {
+            signal_hook::low_level::unregister(id);
+        }
+    }
+}
+
 impl<H> io::Read for Inner<H> {
     fn read_bytes(&mut self, out: &mut [u8]) -> Result<(), io::Error> {
         let (_, len, stream) =
             self.stream.as_mut().ok_or(io::Error::Internal)?;
         check!(*len >= out.len(), io::Error::BufferExhausted);
+        let stream: &mut dyn std::io::Read = match &mut self.buffered {
+            Some(payload) => payload,
+            None => stream,
+        };
         stream.read_exact(out).map_err(|e| {
             log::error!("{}", e);
             io::Error::Internal
@@ -0,0 +1,111 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for retrying requests against a busy RoT.
+
+use std::time::Duration;
+
+use manticore::cert::CertFormat;
+use manticore::mem::BumpArena;
+use manticore::protocol::cerberus::*;
+use manticore::protocol::Req;
+use manticore::protocol::Slot;
+use testutil::data::keys;
+use testutil::data::x509;
+
+use crate::support::rot;
+use crate::support::tcp;
+
+fn spawn(hash_busy_count: u32) -> rot::Virtual {
+    rot::Virtual::spawn(&rot::Options {
+        cert_chain: vec![
+            x509::CHAIN1.to_vec(),
+            x509::CHAIN2.to_vec(),
+            x509::CHAIN3.to_vec(),
+        ],
+        cert_format: CertFormat::RiotX509,
+        alias_keypair: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY3_RSA_KEYPAIR.to_vec(),
+        )),
+        hash_busy_count,
+        ..Default::default()
+    })
+}
+
+fn retry(attempts: u32) -> tcp::SendOptions {
+    tcp::SendOptions {
+        retry: tcp::Retry {
+            attempts,
+            delay: Duration::from_millis(1),
+        },
+        ..Default::default()
+    }
+}
+
+const DIGESTS: Req<'static, GetDigests> = Req::<GetDigests> {
+    slot: Slot::FIRST,
+    key_exchange: get_digests::KeyExchangeAlgo::None,
+};
+
+#[test]
+fn busy_without_retry() {
+    let virt = spawn(1);
+
+    let arena = BumpArena::new(vec![0; 1024]);
+    let err = virt
+        .send_cerberus::<GetDigests>(DIGESTS, &arena)
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.into_inner(), Error::Busy);
+}
+
+#[test]
+fn busy_with_retry() {
+    let virt = spawn(2);
+
+    let arena = BumpArena::new(vec![0; 1024]);
+    let resp = virt
+        .send_cerberus_with::<GetDigests>(DIGESTS, &arena, retry(3))
+        .unwrap()
+        .unwrap();
+    assert_eq!(resp.digests.len(), 3);
+}
+
+#[test]
+fn busy_retries_exhausted() {
+    let virt = spawn(5);
+
+    let arena = BumpArena::new(vec![0; 1024]);
+    let err = virt
+        .send_cerberus_with::<GetDigests>(DIGESTS, &arena, retry(2))
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.into_inner(), Error::Busy);
+}
+
+#[test]
+fn other_errors_not_retried() {
+    let virt = spawn(0);
+
+    let arena = BumpArena::new(vec![0; 1024]);
+    let err = virt
+        .send_cerberus_with::<GetDigests>(
+            Req::<GetDigests> {
+                slot: Slot::new(7).unwrap(),
+                ..DIGESTS
+            },
+            &arena,
+            retry(3),
+        )
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.into_inner(), Error::UnknownChain);
+
+    // Only the single failed request should have reached the RoT.
+    let resp = virt
+        .send_cerberus::<RequestCounter>(Req::<RequestCounter> {}, &arena)
+        .unwrap()
+        .unwrap();
+    assert_eq!(resp.ok_count, 1);
+}
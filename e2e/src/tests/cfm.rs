@@ -0,0 +1,211 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for delivering and activating CFMs.
+
+use manticore::crypto::hash;
+use manticore::crypto::hash::EngineExt as _;
+use manticore::crypto::ring;
+use manticore::manifest::owned;
+use manticore::manifest::ManifestType;
+use manticore::manifest::Metadata;
+use manticore::mem::BumpArena;
+use manticore::protocol::cerberus::*;
+use manticore::protocol::Req;
+use testutil::data::keys;
+
+use crate::support::fakes;
+use crate::support::rot;
+
+/// Builds a CFM for `platform_id`, signed with the given PKCS#8 keypair.
+fn signed_cfm(keypair: &[u8], platform_id: &[u8]) -> Vec<u8> {
+    signed_cfm_version(keypair, platform_id, 1)
+}
+
+/// Like [`signed_cfm()`], but with the given version ID.
+fn signed_cfm_version(
+    keypair: &[u8],
+    platform_id: &[u8],
+    version_id: u32,
+) -> Vec<u8> {
+    let cfm = owned::Cfm {
+        metadata: Metadata { version_id },
+        elements: vec![owned::Node {
+            element: owned::cfm::Element::PlatformId {
+                platform_id: platform_id.to_vec(),
+            },
+            children: vec![],
+            hashed: true,
+        }],
+    };
+    let mut signer = ring::rsa::Sign256::from_pkcs8(keypair).unwrap();
+    cfm.sign(
+        0x00,
+        hash::Algo::Sha256,
+        &mut ring::hash::Engine::new(),
+        &mut signer,
+    )
+    .unwrap()
+}
+
+/// Spawns a RoT that accepts CFMs signed with `KEY1`.
+fn spawn() -> rot::Virtual {
+    rot::Virtual::spawn(&rot::Options {
+        cfm_authority: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY1_RSA_KEYPAIR.to_vec(),
+        )),
+        ..Default::default()
+    })
+}
+
+fn prepare(virt: &rot::Virtual, total_len: usize) -> Result<(), Error> {
+    let arena = BumpArena::new([0; 64]);
+    virt.send_cerberus::<PrepareCfm>(
+        Req::<PrepareCfm> {
+            total_len: total_len as u32,
+        },
+        &arena,
+    )
+    .unwrap()
+    .map(drop)
+    .map_err(|e| e.into_inner())
+}
+
+/// Sends `cfm` to `virt` in several `UpdateCfm`s, stopping at the first
+/// error.
+fn update(virt: &rot::Virtual, cfm: &[u8]) -> Result<(), Error> {
+    let arena = BumpArena::new([0; 64]);
+    for (i, data) in cfm.chunks(100).enumerate() {
+        virt.send_cerberus::<UpdateCfm>(
+            Req::<UpdateCfm> {
+                offset: (i * 100) as u32,
+                data,
+            },
+            &arena,
+        )
+        .unwrap()
+        .map_err(|e| e.into_inner())?;
+    }
+    Ok(())
+}
+
+fn activate(virt: &rot::Virtual) -> Result<(), Error> {
+    let arena = BumpArena::new([0; 64]);
+    virt.send_cerberus::<ActivateCfm>(Req::<ActivateCfm> {}, &arena)
+        .unwrap()
+        .map(drop)
+        .map_err(|e| e.into_inner())
+}
+
+#[test]
+fn stage_and_activate() {
+    let virt = spawn();
+    let cfm = signed_cfm(keys::KEY1_RSA_KEYPAIR, b"my components");
+
+    // Nothing has been staged yet.
+    assert_eq!(activate(&virt), Err(Error::OutOfRange));
+
+    prepare(&virt, cfm.len()).unwrap();
+    update(&virt, &cfm).unwrap();
+    activate(&virt).unwrap();
+
+    // The staged CFM was consumed by activating it.
+    assert_eq!(activate(&virt), Err(Error::OutOfRange));
+
+    // A second update goes through the same lifecycle.
+    let cfm = signed_cfm(keys::KEY1_RSA_KEYPAIR, b"other components");
+    prepare(&virt, cfm.len()).unwrap();
+    update(&virt, &cfm).unwrap();
+    activate(&virt).unwrap();
+}
+
+#[test]
+fn rejects_bad_signature() {
+    let virt = spawn();
+
+    let cfm = signed_cfm(keys::KEY2_RSA_KEYPAIR, b"my components");
+    prepare(&virt, cfm.len()).unwrap();
+    assert_eq!(update(&virt, &cfm), Err(Error::Unauthorized));
+    assert_eq!(activate(&virt), Err(Error::OutOfRange));
+
+    let mut cfm = signed_cfm(keys::KEY1_RSA_KEYPAIR, b"my components");
+    *cfm.last_mut().unwrap() ^= 1;
+    prepare(&virt, cfm.len()).unwrap();
+    assert_eq!(update(&virt, &cfm), Err(Error::Unauthorized));
+    assert_eq!(activate(&virt), Err(Error::OutOfRange));
+}
+
+#[test]
+fn prepare_discards_staged() {
+    let virt = spawn();
+    let cfm = signed_cfm(keys::KEY1_RSA_KEYPAIR, b"my components");
+
+    prepare(&virt, cfm.len()).unwrap();
+    update(&virt, &cfm).unwrap();
+    prepare(&virt, cfm.len()).unwrap();
+    assert_eq!(activate(&virt), Err(Error::OutOfRange));
+}
+
+#[test]
+fn rejects_out_of_bounds_update() {
+    let virt = spawn();
+    let cfm = signed_cfm(keys::KEY1_RSA_KEYPAIR, b"my components");
+
+    // No `PrepareCfm` yet.
+    assert_eq!(update(&virt, &cfm), Err(Error::OutOfRange));
+
+    prepare(&virt, cfm.len() - 1).unwrap();
+    assert_eq!(update(&virt, &cfm), Err(Error::OutOfRange));
+
+    assert_eq!(
+        prepare(&virt, fakes::Manifests::MAX_LEN + 1),
+        Err(Error::ResourceLimit)
+    );
+}
+
+#[test]
+fn unsupported() {
+    let virt = rot::Virtual::spawn(&rot::Options::default());
+    assert_eq!(prepare(&virt, 16), Err(Error::UnsupportedCommand));
+    assert_eq!(activate(&virt), Err(Error::UnsupportedCommand));
+}
+
+/// Asks `virt` for the version ID and digest of its active manifest of type
+/// `manifest_type`.
+fn config_id(
+    virt: &rot::Virtual,
+    manifest_type: ManifestType,
+) -> Result<Option<(u32, [u8; 32])>, Error> {
+    let arena = BumpArena::new([0; 128]);
+    let resp = virt
+        .send_cerberus::<GetConfigIds>(
+            Req::<GetConfigIds> { manifest_type },
+            &arena,
+        )
+        .unwrap()
+        .map_err(|e| e.into_inner())?;
+    assert_eq!(resp.manifest_type, manifest_type);
+    Ok(resp.active.map(|id| (id.version_id, *id.digest)))
+}
+
+#[test]
+fn config_ids() {
+    let virt = spawn();
+    assert_eq!(config_id(&virt, ManifestType::Cfm), Ok(None));
+    assert_eq!(config_id(&virt, ManifestType::Pfm), Err(Error::OutOfRange));
+
+    let cfm = signed_cfm_version(keys::KEY1_RSA_KEYPAIR, b"components", 77);
+    let mut digest = [0; 32];
+    ring::hash::Engine::new()
+        .contiguous_hash(hash::Algo::Sha256, &cfm, &mut digest)
+        .unwrap();
+
+    // Staging alone does not change the active manifest.
+    prepare(&virt, cfm.len()).unwrap();
+    update(&virt, &cfm).unwrap();
+    assert_eq!(config_id(&virt, ManifestType::Cfm), Ok(None));
+
+    activate(&virt).unwrap();
+    assert_eq!(config_id(&virt, ManifestType::Cfm), Ok(Some((77, digest))));
+}
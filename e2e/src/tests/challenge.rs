@@ -4,6 +4,9 @@
 
 //! Tests for the identity challenge.
 
+use std::thread;
+use std::time::Duration;
+
 use manticore::cert;
 use manticore::cert::CertFormat;
 use manticore::cert::TrustChain as _;
@@ -17,14 +20,17 @@ use manticore::mem::Arena as _;
 use manticore::mem::BumpArena;
 use manticore::protocol::cerberus::*;
 use manticore::protocol::wire::ToWire;
+use manticore::protocol::Nonce;
 use manticore::protocol::Req;
 use manticore::protocol::Resp;
+use manticore::protocol::Slot;
 use manticore::session;
 use manticore::session::Session as _;
 use testutil::data::keys;
 use testutil::data::x509;
 
 use crate::support::rot;
+use crate::tests::event_log::clear_log;
 
 #[test]
 fn challenge() {
@@ -39,6 +45,10 @@ fn challenge() {
         alias_keypair: Some(rot::KeyPairFormat::RsaPkcs8(
             keys::KEY3_RSA_KEYPAIR.to_vec(),
         )),
+        debug_log: b"debug".to_vec(),
+        log_authority: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY1_RSA_KEYPAIR.to_vec(),
+        )),
         ..Default::default()
     });
 
@@ -46,7 +56,7 @@ fn challenge() {
     let resp = virt
         .send_cerberus::<GetDigests>(
             Req::<GetDigests> {
-                slot: 0,
+                slot: Slot::FIRST,
                 key_exchange: get_digests::KeyExchangeAlgo::Ecdh,
             },
             &arena,
@@ -71,7 +81,7 @@ fn challenge() {
             let resp = virt
                 .send_cerberus::<GetCert>(
                     Req::<GetCert> {
-                        slot: 0,
+                        slot: Slot::FIRST,
                         cert_number: i as u8,
                         offset: cert.len() as u16,
                         len: 256,
@@ -80,7 +90,7 @@ fn challenge() {
                 )
                 .unwrap()
                 .unwrap();
-            assert_eq!(resp.slot, 0);
+            assert_eq!(resp.slot, Slot::FIRST);
             assert_eq!(resp.cert_number, i as u8);
             cert.extend_from_slice(resp.data);
 
@@ -110,8 +120,10 @@ fn challenge() {
 
     // Issue a challenge.
     let req = Req::<Challenge> {
-        slot: 0,
-        nonce: &[99; 32],
+        slot: Slot::FIRST,
+        nonce: &Nonce([99; 32]),
+        want_timestamp: false,
+        key_type: None,
     };
     let resp = virt
         .send_cerberus::<Challenge>(req, &arena)
@@ -126,7 +138,7 @@ fn challenge() {
     ToWire::to_wire(&req, &mut cursor).unwrap();
     ToWire::to_wire(&resp.tbs, &mut cursor).unwrap();
 
-    let alias_cert = certs.cert(0, digests.len() - 1).unwrap();
+    let alias_cert = certs.cert(Slot::FIRST, digests.len() - 1).unwrap();
     let verifier = ciphers
         .verifier(sig::Algo::RsaPkcs1Sha256, alias_cert.subject_key())
         .unwrap();
@@ -136,7 +148,9 @@ fn challenge() {
         .unwrap();
 
     let mut session = session::ring::Session::new();
-    session.create_session(&[99; 32], resp.tbs.nonce).unwrap();
+    session
+        .create_session(&Nonce([99; 32]), resp.tbs.nonce)
+        .unwrap();
 
     let mut pk_req = vec![0; session.ephemeral_bytes()];
     let pk_len = session.begin_ecdh(&mut pk_req).unwrap();
@@ -165,4 +179,323 @@ fn challenge() {
     let mut hasher = h.new_hmac(hash::Algo::Sha256, hmac_key).unwrap();
     hasher.write(alias_cert.raw()).unwrap();
     hasher.expect(alias_hmac).unwrap();
+
+    // Now that there is a session, logs may be cleared.
+    arena.reset();
+    clear_log(&virt, get_log::LogType::Debug, keys::KEY1_RSA_KEYPAIR).unwrap();
+    let req = Req::<GetLog> {
+        log_type: get_log::LogType::Debug,
+        offset: 0,
+    };
+    let resp = virt.send_cerberus::<GetLog>(req, &arena).unwrap().unwrap();
+    assert!(resp.data.is_empty());
+}
+
+#[test]
+fn challenge_replay() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        cert_chain: vec![
+            x509::CHAIN1.to_vec(),
+            x509::CHAIN2.to_vec(),
+            x509::CHAIN3.to_vec(),
+        ],
+        cert_format: CertFormat::RiotX509,
+        alias_keypair: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY3_RSA_KEYPAIR.to_vec(),
+        )),
+        ..Default::default()
+    });
+
+    let mut arena = BumpArena::new(vec![0; 1024]);
+    let req = Req::<Challenge> {
+        slot: Slot::FIRST,
+        nonce: &Nonce([42; 32]),
+        want_timestamp: false,
+        key_type: None,
+    };
+    virt.send_cerberus::<Challenge>(req, &arena)
+        .unwrap()
+        .unwrap();
+    arena.reset();
+
+    // Replaying the same nonce must not produce a second signature.
+    let err = virt
+        .send_cerberus::<Challenge>(req, &arena)
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.into_inner(), Error::StaleNonce);
+}
+
+#[test]
+fn signed_timestamp() {
+    let opts = |signed_timestamps| rot::Options {
+        cert_chain: vec![
+            x509::CHAIN1.to_vec(),
+            x509::CHAIN2.to_vec(),
+            x509::CHAIN3.to_vec(),
+        ],
+        cert_format: CertFormat::RiotX509,
+        alias_keypair: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY3_RSA_KEYPAIR.to_vec(),
+        )),
+        signed_timestamps,
+        ..Default::default()
+    };
+    let mut verifier = ring::rsa::Sign256::from_pkcs8(keys::KEY3_RSA_KEYPAIR)
+        .unwrap()
+        .verifier();
+
+    let virt = rot::Virtual::spawn(&opts(true));
+    let arena = BumpArena::new(vec![0; 4096]);
+    let req = Req::<Challenge> {
+        slot: Slot::FIRST,
+        nonce: &Nonce([31; 32]),
+        want_timestamp: true,
+        key_type: None,
+    };
+    let first = virt
+        .send_cerberus::<Challenge>(req, &arena)
+        .unwrap()
+        .unwrap();
+    first.verify(&req, &mut verifier).unwrap();
+    let first_ticks = first.tbs.timestamp.unwrap();
+
+    let req = Req::<Challenge> {
+        slot: Slot::FIRST,
+        nonce: &Nonce([32; 32]),
+        want_timestamp: true,
+        key_type: None,
+    };
+    let second = virt
+        .send_cerberus::<Challenge>(req, &arena)
+        .unwrap()
+        .unwrap();
+    second.verify(&req, &mut verifier).unwrap();
+    assert!(second.tbs.timestamp.unwrap() >= first_ticks);
+
+    // Timestamps are only sent when asked for.
+    let req = Req::<Challenge> {
+        slot: Slot::FIRST,
+        nonce: &Nonce([33; 32]),
+        want_timestamp: false,
+        key_type: None,
+    };
+    let resp = virt
+        .send_cerberus::<Challenge>(req, &arena)
+        .unwrap()
+        .unwrap();
+    resp.verify(&req, &mut verifier).unwrap();
+    assert_eq!(resp.tbs.timestamp, None);
+
+    // A device that does not support timestamps ignores the request.
+    let virt = rot::Virtual::spawn(&opts(false));
+    let req = Req::<Challenge> {
+        slot: Slot::FIRST,
+        nonce: &Nonce([34; 32]),
+        want_timestamp: true,
+        key_type: None,
+    };
+    let resp = virt
+        .send_cerberus::<Challenge>(req, &arena)
+        .unwrap()
+        .unwrap();
+    resp.verify(&req, &mut verifier).unwrap();
+    assert_eq!(resp.tbs.timestamp, None);
+}
+
+#[test]
+fn cert_number_bounds() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        cert_chain: vec![
+            x509::CHAIN1.to_vec(),
+            x509::CHAIN2.to_vec(),
+            x509::CHAIN3.to_vec(),
+        ],
+        cert_format: CertFormat::RiotX509,
+        ..Default::default()
+    });
+
+    let arena = BumpArena::new(vec![0; 1024]);
+    let get_cert = |cert_number| {
+        virt.send_cerberus::<GetCert>(
+            Req::<GetCert> {
+                slot: Slot::FIRST,
+                cert_number,
+                offset: 0,
+                len: 16,
+            },
+            &arena,
+        )
+        .unwrap()
+        .unwrap_err()
+        .into_inner()
+    };
+
+    // Within the RoT's limit of eight certs, but past the end of its chain.
+    assert_eq!(get_cert(5), Error::UnknownChain);
+    // Past the limit altogether.
+    assert_eq!(get_cert(8), Error::OutOfRange);
+    assert_eq!(get_cert(255), Error::OutOfRange);
+}
+
+#[test]
+fn key_exchange_off_curve() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        cert_chain: vec![
+            x509::CHAIN1.to_vec(),
+            x509::CHAIN2.to_vec(),
+            x509::CHAIN3.to_vec(),
+        ],
+        cert_format: CertFormat::RiotX509,
+        alias_keypair: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY3_RSA_KEYPAIR.to_vec(),
+        )),
+        ..Default::default()
+    });
+
+    let mut arena = BumpArena::new(vec![0; 1024]);
+    virt.send_cerberus::<GetDigests>(
+        Req::<GetDigests> {
+            slot: Slot::FIRST,
+            key_exchange: get_digests::KeyExchangeAlgo::Ecdh,
+        },
+        &arena,
+    )
+    .unwrap()
+    .unwrap();
+    arena.reset();
+
+    let req = Req::<Challenge> {
+        slot: Slot::FIRST,
+        nonce: &Nonce([77; 32]),
+        want_timestamp: false,
+        key_type: None,
+    };
+    let resp = virt
+        .send_cerberus::<Challenge>(req, &arena)
+        .unwrap()
+        .unwrap();
+
+    let mut session = session::ring::Session::new();
+    session
+        .create_session(&Nonce([77; 32]), resp.tbs.nonce)
+        .unwrap();
+    let mut pk_req = vec![0; session.ephemeral_bytes()];
+    let pk_len = session.begin_ecdh(&mut pk_req).unwrap();
+    let pk_req = &mut pk_req[..pk_len];
+
+    // Perturbing the y-coordinate moves the point off the curve.
+    pk_req[pk_len - 1] ^= 1;
+    let req = Req::<KeyExchange>::SessionKey {
+        hmac_algorithm: hash::Algo::Sha256,
+        pk_req,
+    };
+    let err = virt
+        .send_cerberus::<KeyExchange>(req, &arena)
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.into_inner(), Error::InvalidPublicKey);
+}
+
+#[test]
+fn session_expires() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        cert_chain: vec![
+            x509::CHAIN1.to_vec(),
+            x509::CHAIN2.to_vec(),
+            x509::CHAIN3.to_vec(),
+        ],
+        cert_format: CertFormat::RiotX509,
+        alias_keypair: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY3_RSA_KEYPAIR.to_vec(),
+        )),
+        session_lifetime: Some(Duration::from_millis(500)),
+        ..Default::default()
+    });
+
+    let mut arena = BumpArena::new(vec![0; 1024]);
+    virt.send_cerberus::<GetDigests>(
+        Req::<GetDigests> {
+            slot: Slot::FIRST,
+            key_exchange: get_digests::KeyExchangeAlgo::Ecdh,
+        },
+        &arena,
+    )
+    .unwrap()
+    .unwrap();
+    arena.reset();
+
+    let req = Req::<Challenge> {
+        slot: Slot::FIRST,
+        nonce: &Nonce([55; 32]),
+        want_timestamp: false,
+        key_type: None,
+    };
+    let resp = virt
+        .send_cerberus::<Challenge>(req, &arena)
+        .unwrap()
+        .unwrap();
+
+    let mut session = session::ring::Session::new();
+    session
+        .create_session(&Nonce([55; 32]), resp.tbs.nonce)
+        .unwrap();
+    let mut pk_req = vec![0; session.ephemeral_bytes()];
+    let pk_len = session.begin_ecdh(&mut pk_req).unwrap();
+    let req = Req::<KeyExchange>::SessionKey {
+        hmac_algorithm: hash::Algo::Sha256,
+        pk_req: &pk_req[..pk_len],
+    };
+    virt.send_cerberus::<KeyExchange>(req, &arena)
+        .unwrap()
+        .unwrap();
+    arena.reset();
+
+    let req = Req::<ClearLog> {
+        log_type: get_log::LogType::Debug,
+        authorization: None,
+    };
+    virt.send_cerberus::<ClearLog>(req, &arena)
+        .unwrap()
+        .unwrap();
+    arena.reset();
+
+    // Once the lifetime is up, the session is gone.
+    thread::sleep(Duration::from_millis(600));
+    let err = virt
+        .send_cerberus::<ClearLog>(req, &arena)
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.into_inner(), Error::SessionRequired);
+}
+
+#[test]
+fn challenge_past_deadline() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        cert_chain: vec![
+            x509::CHAIN1.to_vec(),
+            x509::CHAIN2.to_vec(),
+            x509::CHAIN3.to_vec(),
+        ],
+        cert_format: CertFormat::RiotX509,
+        alias_keypair: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY3_RSA_KEYPAIR.to_vec(),
+        )),
+        // With no time at all to respond, every signature is overdue.
+        crypto_timeout: Duration::from_secs(0),
+        ..Default::default()
+    });
+
+    let arena = BumpArena::new(vec![0; 1024]);
+    let req = Req::<Challenge> {
+        slot: Slot::FIRST,
+        nonce: &Nonce([7; 32]),
+        want_timestamp: false,
+        key_type: None,
+    };
+    let err = virt
+        .send_cerberus::<Challenge>(req, &arena)
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.into_inner(), Error::Busy);
 }
@@ -0,0 +1,214 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for persistent Cerberus-over-TCP clients.
+
+use std::io::Read as _;
+use std::io::Write as _;
+use std::net::TcpStream;
+
+use manticore::crypto::hash;
+use manticore::io;
+use manticore::mem::BumpArena;
+use manticore::net;
+use manticore::net::client::ClientPort as _;
+use manticore::protocol::cerberus::capabilities::*;
+use manticore::protocol::cerberus::*;
+use manticore::protocol::wire::FromWire as _;
+use manticore::protocol::wire::ToWire as _;
+use manticore::protocol::Nonce;
+use manticore::protocol::Req;
+use manticore::protocol::Resp;
+use manticore::protocol::Slot;
+
+use crate::support::rot;
+use crate::support::tcp;
+
+fn client_capabilities() -> Capabilities {
+    rot::ClientOptions {
+        compression: true,
+        hash_algos: hash::Algo::Sha256.into(),
+        ..Default::default()
+    }
+    .capabilities()
+}
+
+#[test]
+fn negotiates_once() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        firmware_version: b"persistent".to_vec(),
+        ..Default::default()
+    });
+    let mut client = virt.connect(client_capabilities());
+
+    let arena = BumpArena::new([0; 64]);
+    for _ in 0..2 {
+        let resp = client
+            .command::<FirmwareVersion>(
+                Req::<FirmwareVersion> { index: 0 },
+                &arena,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(&resp.version[..10], b"persistent");
+    }
+    assert_eq!(
+        client.capabilities().unwrap().networking.max_message_size,
+        1024
+    );
+
+    // One negotiation plus two version queries.
+    let resp = client
+        .command::<RequestCounter>(Req::<RequestCounter> {}, &arena)
+        .unwrap()
+        .unwrap();
+    assert_eq!(resp.ok_count, 3);
+}
+
+#[test]
+fn negotiates_hash_algos() {
+    let virt = rot::Virtual::spawn(&rot::Options::default());
+    let mut client = virt.connect(client_capabilities());
+
+    let theirs = client.capabilities().unwrap().crypto.hash_algos;
+    assert!(theirs.contains(hash::Algo::Sha256));
+    assert!(theirs.contains(hash::Algo::Sha384));
+    assert_eq!(
+        client.hash_algos().unwrap().iter().collect::<Vec<_>>(),
+        &[hash::Algo::Sha256]
+    );
+}
+
+#[test]
+fn respects_max_message_size() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        max_message_size: 16,
+        ..Default::default()
+    });
+    let mut client = virt.connect(client_capabilities());
+
+    let arena = BumpArena::new([0; 64]);
+    let req = Req::<Challenge> {
+        slot: Slot::FIRST,
+        nonce: &Nonce([99; 32]),
+        want_timestamp: false,
+        key_type: None,
+    };
+    assert!(client.command::<Challenge>(req, &arena).is_err());
+
+    // The oversized request was never sent, so the connection is still good.
+    let resp = client
+        .command::<RequestCounter>(Req::<RequestCounter> {}, &arena)
+        .unwrap()
+        .unwrap();
+    assert_eq!(resp.ok_count, 1);
+}
+
+#[test]
+fn compresses_when_negotiated() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        compression: true,
+        vendor_firmware_versions: vec![(1, vec![0xff; 32])],
+        ..Default::default()
+    });
+    let mut client = virt.connect(client_capabilities());
+    assert!(client.capabilities().unwrap().networking.has_compression);
+
+    let arena = BumpArena::new([0; 64]);
+    let resp = client
+        .command::<FirmwareVersion>(Req::<FirmwareVersion> { index: 1 }, &arena)
+        .unwrap()
+        .unwrap();
+    assert_eq!(resp.version, &[0xff; 32]);
+}
+
+#[test]
+fn rejects_interleaved_requests() {
+    let virt = rot::Virtual::spawn(&rot::Options::default());
+
+    // Two `FirmwareVersion` requests, the second sent before the reply to
+    // the first has been read.
+    let mut conn = TcpStream::connect(("127.0.0.1", virt.port())).unwrap();
+    let request = [0x01, 0x01, 0x00, 0x00];
+    conn.write_all(&[request, request].concat()).unwrap();
+
+    // The first request is still answered...
+    let mut reply = [0; 3 + 32];
+    conn.read_exact(&mut reply).unwrap();
+    assert_eq!(reply[0], CommandType::FirmwareVersion as u8);
+
+    // ...but the connection is then dropped instead of serving the second.
+    let mut rest = Vec::new();
+    conn.read_to_end(&mut rest).unwrap();
+    assert!(rest.is_empty());
+
+    // The RoT is still happy to serve new connections.
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt.send_cerberus::<FirmwareVersion>(
+        Req::<FirmwareVersion> { index: 0 },
+        &arena,
+    );
+    assert!(resp.unwrap().is_ok());
+}
+
+#[test]
+fn idle_client_yields_to_new_peer() {
+    let virt = rot::Virtual::spawn(&rot::Options::default());
+
+    // After negotiating, this client keeps its connection open without
+    // sending anything else.
+    let mut idle = virt.connect(client_capabilities());
+    idle.capabilities().unwrap();
+
+    // A new peer still gets served, rather than waiting on the idle one.
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt.send_cerberus::<FirmwareVersion>(
+        Req::<FirmwareVersion> { index: 0 },
+        &arena,
+    );
+    assert!(resp.unwrap().is_ok());
+}
+
+#[test]
+fn client_port() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        firmware_version: b"client port".to_vec(),
+        ..Default::default()
+    });
+    let mut port =
+        tcp::TcpClientPort::connect(virt.port(), Default::default()).unwrap();
+
+    let arena = BumpArena::new([0; 64]);
+    for i in 0..2 {
+        let mut req = Vec::new();
+        Req::<FirmwareVersion> { index: 0 }
+            .to_wire(&mut io::write::StdWrite(&mut req))
+            .unwrap();
+        let resp = port
+            .send(
+                net::CerberusHeader {
+                    command: CommandType::FirmwareVersion,
+                },
+                &req,
+            )
+            .unwrap();
+        assert_eq!(
+            resp.header().unwrap().command,
+            CommandType::FirmwareVersion
+        );
+
+        // Only read part of the first response; the port should skip the
+        // rest before sending the next request.
+        let payload = resp.payload().unwrap();
+        if i == 0 {
+            let mut version = [0; 11];
+            payload.read_bytes(&mut version).unwrap();
+            assert_eq!(&version, b"client port");
+        } else {
+            let resp =
+                Resp::<FirmwareVersion>::from_wire(payload, &arena).unwrap();
+            assert_eq!(&resp.version[..11], b"client port");
+        }
+    }
+}
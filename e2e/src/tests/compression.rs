@@ -0,0 +1,76 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for compressed Cerberus-over-TCP payloads.
+
+use manticore::mem::BumpArena;
+use manticore::protocol::cerberus::capabilities::*;
+use manticore::protocol::cerberus::*;
+use manticore::protocol::Req;
+
+use crate::support::rot;
+use crate::support::tcp;
+
+fn client_capabilities() -> Capabilities {
+    rot::ClientOptions {
+        compression: true,
+        ..Default::default()
+    }
+    .capabilities()
+}
+
+#[test]
+fn negotiate() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        compression: true,
+        ..Default::default()
+    });
+
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt.send_cerberus::<DeviceCapabilities>(
+        Req::<DeviceCapabilities> {
+            capabilities: client_capabilities(),
+        },
+        &arena,
+    );
+    let caps = resp.unwrap().unwrap().capabilities;
+    assert!(caps.networking.has_compression);
+}
+
+#[test]
+fn negotiate_unsupported() {
+    let virt = rot::Virtual::spawn(&rot::Options::default());
+
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt.send_cerberus::<DeviceCapabilities>(
+        Req::<DeviceCapabilities> {
+            capabilities: client_capabilities(),
+        },
+        &arena,
+    );
+    let caps = resp.unwrap().unwrap().capabilities;
+    assert!(!caps.networking.has_compression);
+}
+
+#[test]
+fn compressed_round_trip() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        compression: true,
+        vendor_firmware_versions: vec![(1, vec![0xff; 32])],
+        ..Default::default()
+    });
+
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt.send_cerberus_with::<FirmwareVersion>(
+        Req::<FirmwareVersion> { index: 1 },
+        &arena,
+        tcp::SendOptions {
+            compress: true,
+            ..Default::default()
+        },
+    );
+
+    let version = resp.unwrap().unwrap().version;
+    assert_eq!(version, &[0xff; 32]);
+}
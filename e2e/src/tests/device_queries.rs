@@ -90,3 +90,19 @@ fn device_id() {
         }
     );
 }
+
+#[test]
+fn device_state() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        device_state: get_device_state::State::Recovery,
+        ..Default::default()
+    });
+
+    let arena = BumpArena::new([0; 64]);
+    let resp =
+        virt.send_cerberus::<GetDeviceState>(Req::<GetDeviceState> {}, &arena);
+    assert_eq!(
+        resp.unwrap().unwrap().state,
+        get_device_state::State::Recovery
+    );
+}
@@ -0,0 +1,211 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for reading and clearing logs.
+
+use std::convert::TryInto as _;
+
+use manticore::cert::CertFormat;
+use manticore::crypto::ring;
+use manticore::crypto::sig::Sign as _;
+use manticore::mem::Arena as _;
+use manticore::mem::BumpArena;
+use manticore::protocol::cerberus::clear_log::with_signed_message;
+use manticore::protocol::cerberus::get_log::LogType;
+use manticore::protocol::cerberus::reset_config::AuthToken;
+use manticore::protocol::cerberus::*;
+use manticore::protocol::Nonce;
+use manticore::protocol::Req;
+use testutil::data::keys;
+use testutil::data::x509;
+
+use crate::support::rot;
+use crate::tests::unseal::establish_session;
+
+/// Clears the log of type `log_type` on `virt`, authorizing it with a
+/// signature by the given PKCS#8 keypair.
+pub(super) fn clear_log(
+    virt: &rot::Virtual,
+    log_type: LogType,
+    keypair: &[u8],
+) -> Result<(), Error> {
+    let mut arena = BumpArena::new(vec![0; 1024]);
+    let resp = virt
+        .send_cerberus::<ClearLog>(
+            Req::<ClearLog> {
+                log_type,
+                authorization: None,
+            },
+            &arena,
+        )
+        .unwrap()
+        .map_err(|e| e.into_inner())?;
+    let nonce = Nonce(resp.nonce.try_into().unwrap());
+    arena.reset();
+
+    let mut signer = ring::rsa::Sign256::from_pkcs8(keypair).unwrap();
+    let mut signature = vec![0; signer.sig_bytes()];
+    let len = with_signed_message(log_type, &nonce, |msg| {
+        signer.sign(msg, &mut signature)
+    })
+    .unwrap();
+    signature.truncate(len);
+
+    let resp = virt
+        .send_cerberus::<ClearLog>(
+            Req::<ClearLog> {
+                log_type,
+                authorization: Some(AuthToken {
+                    nonce: &nonce,
+                    signature: &signature,
+                }),
+            },
+            &arena,
+        )
+        .unwrap();
+    match resp {
+        Ok(resp) => {
+            assert!(resp.nonce.is_empty());
+            Ok(())
+        }
+        Err(e) => Err(e.into_inner()),
+    }
+}
+
+/// Spawns a virtual RoT that a session can be established with, whose debug
+/// log may be cleared with `KEY1`.
+fn spawn_with_authority() -> rot::Virtual {
+    rot::Virtual::spawn(&rot::Options {
+        cert_chain: vec![
+            x509::CHAIN1.to_vec(),
+            x509::CHAIN2.to_vec(),
+            x509::CHAIN3.to_vec(),
+        ],
+        cert_format: CertFormat::RiotX509,
+        alias_keypair: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY3_RSA_KEYPAIR.to_vec(),
+        )),
+        debug_log: b"debug".to_vec(),
+        log_authority: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY1_RSA_KEYPAIR.to_vec(),
+        )),
+        ..Default::default()
+    })
+}
+
+/// Reads back the whole debug log of `virt`.
+fn debug_log(virt: &rot::Virtual) -> Vec<u8> {
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt.send_cerberus::<GetLog>(
+        Req::<GetLog> {
+            log_type: LogType::Debug,
+            offset: 0,
+        },
+        &arena,
+    );
+    resp.unwrap().unwrap().data.to_vec()
+}
+
+#[test]
+fn read_in_chunks() {
+    let opts = rot::Options {
+        max_message_size: 64,
+        attestation_events: vec![
+            (0, 0x07, b"crtm".to_vec()),
+            (1, 0x0d, b"bootloader v1.2.3".to_vec()),
+            (1, 0x04, vec![0; 4]),
+        ],
+        ..Default::default()
+    };
+    let virt = rot::Virtual::spawn(&opts);
+
+    let mut buf = vec![0; 4096];
+    let expected = rot::build_event_log(&opts, &mut buf);
+
+    let mut arena = BumpArena::new(vec![0; 256]);
+    let mut log = Vec::new();
+    loop {
+        let resp = virt.send_cerberus::<GetLog>(
+            Req::<GetLog> {
+                log_type: LogType::Attestation,
+                offset: log.len() as u32,
+            },
+            &arena,
+        );
+        let data = resp.unwrap().unwrap().data;
+        assert!(data.len() <= 64);
+        if data.is_empty() {
+            break;
+        }
+        log.extend_from_slice(data);
+        arena.reset();
+    }
+    assert_eq!(log, expected.as_bytes());
+}
+
+#[test]
+fn unknown_log() {
+    let virt = rot::Virtual::spawn(&rot::Options::default());
+
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt.send_cerberus::<GetLog>(
+        Req::<GetLog> {
+            log_type: LogType::Tamper,
+            offset: 0,
+        },
+        &arena,
+    );
+    assert_eq!(resp.unwrap().unwrap_err().into_inner(), Error::OutOfRange);
+}
+
+#[test]
+fn clear_requires_session() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        debug_log: b"debug".to_vec(),
+        ..Default::default()
+    });
+
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt.send_cerberus::<ClearLog>(
+        Req::<ClearLog> {
+            log_type: LogType::Debug,
+            authorization: None,
+        },
+        &arena,
+    );
+    assert_eq!(
+        resp.unwrap().unwrap_err().into_inner(),
+        Error::SessionRequired
+    );
+    assert_eq!(debug_log(&virt), b"debug");
+}
+
+#[test]
+fn clear_authorized() {
+    let virt = spawn_with_authority();
+    establish_session(&virt);
+
+    clear_log(&virt, LogType::Debug, keys::KEY1_RSA_KEYPAIR).unwrap();
+    assert!(debug_log(&virt).is_empty());
+}
+
+#[test]
+fn clear_unauthorized() {
+    let virt = spawn_with_authority();
+    establish_session(&virt);
+
+    // A session alone is not enough; the nonce must be signed by the log's
+    // authority.
+    assert_eq!(
+        clear_log(&virt, LogType::Debug, keys::KEY2_RSA_KEYPAIR),
+        Err(Error::Unauthorized)
+    );
+    assert_eq!(debug_log(&virt), b"debug");
+
+    // Logs without an authority cannot be cleared at all.
+    assert_eq!(
+        clear_log(&virt, LogType::Attestation, keys::KEY1_RSA_KEYPAIR),
+        Err(Error::OutOfRange)
+    );
+}
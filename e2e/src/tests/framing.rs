@@ -0,0 +1,241 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for Cerberus-over-TCP framing, including the optional frame marker.
+
+use std::io::Read as _;
+use std::io::Write as _;
+use std::net::TcpStream;
+use std::thread;
+
+use manticore::mem::BumpArena;
+use manticore::net;
+use manticore::net::host::HostPort as _;
+use manticore::protocol::cerberus::*;
+use manticore::protocol::Req;
+
+use crate::support::rot;
+use crate::support::tcp;
+use crate::support::tcp::TcpHostPort;
+
+#[test]
+fn magic_round_trip() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        magic: true,
+        firmware_version: b"magic".to_vec(),
+        ..Default::default()
+    });
+
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt.send_cerberus_with::<FirmwareVersion>(
+        Req::<FirmwareVersion> { index: 0 },
+        &arena,
+        tcp::SendOptions {
+            magic: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(&resp.unwrap().unwrap().version[..5], b"magic");
+}
+
+#[test]
+fn resync_after_garbage() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        magic: true,
+        payload_ceiling: 1024,
+        ..Default::default()
+    });
+
+    let mut conn = TcpStream::connect(("127.0.0.1", virt.port())).unwrap();
+    let mut bytes = vec![0x00, 0x12];
+    // A spurious marker, followed by a header declaring far more payload than
+    // the RoT accepts.
+    bytes.extend_from_slice(&tcp::MAGIC);
+    bytes.extend_from_slice(&[0xee, 0xff, 0x1f]);
+    // A well-formed `FirmwareVersion` request.
+    bytes.extend_from_slice(&tcp::MAGIC);
+    bytes.extend_from_slice(&[0x01, 0x01, 0x00, 0x00]);
+    conn.write_all(&bytes).unwrap();
+
+    let mut reply = [0; 5];
+    conn.read_exact(&mut reply).unwrap();
+    assert_eq!(reply[..2], tcp::MAGIC);
+    assert_eq!(reply[2], CommandType::FirmwareVersion as u8);
+}
+
+#[test]
+fn unknown_command() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        firmware_version: b"unknown".to_vec(),
+        ..Default::default()
+    });
+
+    // A well-framed request for a command that doesn't exist gets an error
+    // reply, not a dropped connection.
+    let mut conn = TcpStream::connect(("127.0.0.1", virt.port())).unwrap();
+    conn.write_all(&[0xee, 0x00, 0x00]).unwrap();
+    let mut reply = [0; 8];
+    conn.read_exact(&mut reply).unwrap();
+    assert_eq!(reply[0], CommandType::Error as u8);
+
+    let arena = BumpArena::new([0; 64]);
+    let resp = tcp::response_from_bytes::<FirmwareVersion>(
+        &reply,
+        &arena,
+        Default::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        resp.map_err(|e| e.into_inner()).unwrap_err(),
+        Error::UnsupportedCommand
+    );
+
+    // The connection is still good for known commands.
+    let req = tcp::to_vec(
+        &Req::<FirmwareVersion> { index: 0 },
+        net::CerberusHeader {
+            command: CommandType::FirmwareVersion,
+        },
+        Default::default(),
+    )
+    .unwrap();
+    conn.write_all(&req).unwrap();
+    let mut reply = [0; 3 + 32];
+    conn.read_exact(&mut reply).unwrap();
+    let resp = tcp::response_from_bytes::<FirmwareVersion>(
+        &reply,
+        &arena,
+        Default::default(),
+    )
+    .unwrap();
+    assert_eq!(&resp.unwrap().version[..7], b"unknown");
+}
+
+#[test]
+fn overlong_header() {
+    let mut port = TcpHostPort::<net::SpdmHeader>::bind().unwrap();
+    port.set_payload_ceiling(4096);
+    let addr = ("127.0.0.1", port.port());
+
+    // A `GetVersion` header claiming 60000 bytes, from a peer that then hangs
+    // up without sending any of them.
+    let client = thread::spawn(move || {
+        let mut conn = TcpStream::connect(addr).unwrap();
+        let [len_lo, len_hi] = 60000u16.to_le_bytes();
+        conn.write_all(&[len_lo, len_hi, 0x10, 0x84]).unwrap();
+    });
+    let result = port.receive().map(|_| ()).map_err(|e| e.into_inner());
+    client.join().unwrap();
+    assert_eq!(result, Err(net::Error::BadHeader));
+}
+
+#[test]
+fn standalone_frames() {
+    let header = net::CerberusHeader {
+        command: CommandType::FirmwareVersion,
+    };
+    let req = Req::<FirmwareVersion> { index: 7 };
+
+    let bytes = tcp::to_vec(&req, header, Default::default()).unwrap();
+    assert_eq!(bytes, &[0x01, 0x01, 0x00, 0x07]);
+
+    let magic = tcp::SendOptions {
+        magic: true,
+        ..Default::default()
+    };
+    let framed = tcp::to_vec(&req, header, magic).unwrap();
+    assert_eq!(framed[..2], tcp::MAGIC);
+    assert_eq!(framed[2..], bytes[..]);
+
+    let arena = BumpArena::new([0; 64]);
+    let (parsed_header, parsed): (net::CerberusHeader, Req<FirmwareVersion>) =
+        tcp::from_bytes(&framed, &arena, magic).unwrap();
+    assert_eq!(parsed_header, header);
+    assert_eq!(parsed, req);
+
+    // Trailing garbage is rejected.
+    let mut long = bytes;
+    long.push(0);
+    let err = tcp::from_bytes::<net::CerberusHeader, Req<FirmwareVersion>>(
+        &long,
+        &arena,
+        Default::default(),
+    )
+    .unwrap_err();
+    assert_eq!(err.into_inner(), net::Error::BadHeader.into());
+}
+
+#[test]
+fn replay_response() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        firmware_version: b"replayed".to_vec(),
+        ..Default::default()
+    });
+
+    // Capture a response off of the wire by hand.
+    let req = Req::<FirmwareVersion> { index: 0 };
+    let header = net::CerberusHeader {
+        command: CommandType::FirmwareVersion,
+    };
+    let mut conn = TcpStream::connect(("127.0.0.1", virt.port())).unwrap();
+    conn.write_all(&tcp::to_vec(&req, header, Default::default()).unwrap())
+        .unwrap();
+    let mut resp_header = [0; 3];
+    conn.read_exact(&mut resp_header).unwrap();
+    let len = u16::from_le_bytes([resp_header[1], resp_header[2]]);
+    let mut captured = resp_header.to_vec();
+    captured.resize(3 + len as usize, 0);
+    conn.read_exact(&mut captured[3..]).unwrap();
+
+    let arena = BumpArena::new([0; 128]);
+    let resp = tcp::response_from_bytes::<FirmwareVersion>(
+        &captured,
+        &arena,
+        Default::default(),
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(&resp.version[..8], b"replayed");
+
+    // An error response decodes as one.
+    let error = [0x7f, 0x05, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00];
+    let resp = tcp::response_from_bytes::<FirmwareVersion>(
+        &error,
+        &arena,
+        Default::default(),
+    )
+    .unwrap();
+    assert!(resp.is_err());
+
+    // Trailing bytes in the payload are rejected.
+    let mut long = captured;
+    long[1] += 1;
+    long.push(0);
+    let err = tcp::response_from_bytes::<FirmwareVersion>(
+        &long,
+        &arena,
+        Default::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err.into_inner(),
+        manticore::server::Error::ReqTooLong(1)
+    ));
+
+    // So is a reply of the wrong type.
+    let wrong = [CommandType::DeviceId as u8, 0x00, 0x00];
+    let err = tcp::response_from_bytes::<FirmwareVersion>(
+        &wrong,
+        &arena,
+        Default::default(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.into_inner(),
+        manticore::server::Error::UnexpectedResponse {
+            expected: CommandType::FirmwareVersion,
+            got: CommandType::DeviceId,
+        }
+    );
+}
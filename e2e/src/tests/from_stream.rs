@@ -0,0 +1,81 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for serving a single, already-accepted connection.
+
+use std::io::Read as _;
+use std::io::Write as _;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+use manticore::mem::BumpArena;
+use manticore::net;
+use manticore::net::host::HostPort as _;
+
+use crate::support::tcp::TcpHostPort;
+
+/// Accepts a single connection from `client`, which is run on another
+/// thread, and wraps it in a `TcpHostPort`.
+fn accept(
+    client: impl FnOnce(TcpStream) + Send + 'static,
+) -> (TcpHostPort, thread::JoinHandle<()>) {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let addr = listener.local_addr().unwrap();
+    let client =
+        thread::spawn(move || client(TcpStream::connect(addr).unwrap()));
+
+    let (stream, _) = listener.accept().unwrap();
+    let port = TcpHostPort::from_stream(stream).unwrap();
+    assert_eq!(port.port(), addr.port());
+    (port, client)
+}
+
+#[test]
+fn serves_one_connection() {
+    let (mut port, client) = accept(|mut conn| {
+        for i in 0..2 {
+            conn.write_all(&[0x01, 0x01, 0x00, i]).unwrap();
+            let mut reply = [0; 4];
+            conn.read_exact(&mut reply).unwrap();
+            assert_eq!(reply, [0x01, 0x01, 0x00, i]);
+        }
+    });
+
+    // Echo each request back to the client.
+    let arena = BumpArena::new([0; 64]);
+    for _ in 0..2 {
+        let req = port.receive().unwrap();
+        let header = req.header().unwrap();
+        let payload = req.payload_bytes(&arena).unwrap();
+        let reply = req.reply(header).unwrap();
+        reply.sink().unwrap().write_all(payload).unwrap();
+        reply.finish().unwrap();
+    }
+    client.join().unwrap();
+
+    // Once the client hangs up, there is no listener to fall back on.
+    let err = port.receive().err().unwrap();
+    assert_eq!(err.into_inner(), net::Error::Disconnected);
+}
+
+#[test]
+fn shutdown_while_waiting() {
+    let (mut port, client) = accept(|conn| {
+        thread::sleep(Duration::from_millis(300));
+        drop(conn);
+    });
+    let handle = port.shutdown_handle();
+
+    let server = thread::spawn(move || {
+        let err = port.receive().err().unwrap();
+        err.into_inner()
+    });
+
+    thread::sleep(Duration::from_millis(100));
+    handle.shutdown();
+    assert_eq!(server.join().unwrap(), net::Error::Shutdown);
+    client.join().unwrap();
+}
@@ -0,0 +1,123 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for verifying firmware images over several requests.
+
+use manticore::crypto::hash;
+use manticore::crypto::hash::EngineExt as _;
+use manticore::crypto::ring;
+use manticore::mem::Arena as _;
+use manticore::mem::BumpArena;
+use manticore::protocol::cerberus::*;
+use manticore::protocol::Req;
+
+use crate::support::rot;
+
+/// An image several chunks long.
+const IMAGE: &[u8] = &[0x5a; 1000];
+
+/// The size of each chunk `IMAGE` is sent in.
+const CHUNK: usize = 128;
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut digest = [0; 32];
+    ring::hash::Engine::new()
+        .contiguous_hash(hash::Algo::Sha256, data, &mut digest)
+        .unwrap();
+    digest
+}
+
+/// Sends `IMAGE` through a new verification against `expected`, returning
+/// the verdict.
+fn verify(virt: &rot::Virtual, expected: &[u8; 32]) -> bool {
+    let mut arena = BumpArena::new(vec![0; 1024]);
+    let handle = virt
+        .send_cerberus::<BeginVerify>(
+            Req::<BeginVerify> {
+                image_len: IMAGE.len() as u32,
+                expected,
+            },
+            &arena,
+        )
+        .unwrap()
+        .unwrap()
+        .handle;
+    arena.reset();
+
+    for (i, data) in IMAGE.chunks(CHUNK).enumerate() {
+        let req = Req::<VerifyChunk> {
+            handle,
+            offset: (i * CHUNK) as u32,
+            data,
+        };
+        virt.send_cerberus::<VerifyChunk>(req, &arena)
+            .unwrap()
+            .unwrap();
+        arena.reset();
+    }
+
+    virt.send_cerberus::<FinishVerify>(Req::<FinishVerify> { handle }, &arena)
+        .unwrap()
+        .unwrap()
+        .verified
+}
+
+#[test]
+fn matching_image() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        verify_slots: 1,
+        ..Default::default()
+    });
+    assert!(verify(&virt, &sha256(IMAGE)));
+}
+
+#[test]
+fn mismatched_image() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        verify_slots: 1,
+        ..Default::default()
+    });
+    assert!(!verify(&virt, &[0; 32]));
+
+    // The slot is free again for another verification.
+    assert!(verify(&virt, &sha256(IMAGE)));
+}
+
+#[test]
+fn slots_exhausted() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        verify_slots: 1,
+        ..Default::default()
+    });
+
+    let arena = BumpArena::new(vec![0; 1024]);
+    let req = Req::<BeginVerify> {
+        image_len: IMAGE.len() as u32,
+        expected: &[0; 32],
+    };
+    virt.send_cerberus::<BeginVerify>(req, &arena)
+        .unwrap()
+        .unwrap();
+    let err = virt
+        .send_cerberus::<BeginVerify>(req, &arena)
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.into_inner(), Error::ResourceLimit);
+}
+
+#[test]
+fn unsupported() {
+    let virt = rot::Virtual::spawn(&rot::Options::default());
+
+    let arena = BumpArena::new(vec![0; 1024]);
+    let req = Req::<BeginVerify> {
+        image_len: IMAGE.len() as u32,
+        expected: &[0; 32],
+    };
+    let err = virt
+        .send_cerberus::<BeginVerify>(req, &arena)
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.into_inner(), Error::UnsupportedCommand);
+}
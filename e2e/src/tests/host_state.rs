@@ -0,0 +1,158 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for reading and setting the host's reset state.
+
+use std::convert::TryInto as _;
+
+use manticore::cert::CertFormat;
+use manticore::crypto::ring;
+use manticore::crypto::sig::Sign as _;
+use manticore::mem::Arena as _;
+use manticore::mem::BumpArena;
+use manticore::protocol::cerberus::get_host_state::HostResetState;
+use manticore::protocol::cerberus::reset_config::AuthToken;
+use manticore::protocol::cerberus::set_host_state::with_signed_message;
+use manticore::protocol::cerberus::*;
+use manticore::protocol::Nonce;
+use manticore::protocol::Req;
+use testutil::data::keys;
+use testutil::data::x509;
+
+use crate::support::rot;
+use crate::tests::unseal::establish_session;
+
+fn spawn() -> rot::Virtual {
+    rot::Virtual::spawn(&rot::Options {
+        cert_chain: vec![
+            x509::CHAIN1.to_vec(),
+            x509::CHAIN2.to_vec(),
+            x509::CHAIN3.to_vec(),
+        ],
+        cert_format: CertFormat::RiotX509,
+        alias_keypair: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY3_RSA_KEYPAIR.to_vec(),
+        )),
+        host_control: true,
+        host_authority: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY1_RSA_KEYPAIR.to_vec(),
+        )),
+        ..Default::default()
+    })
+}
+
+fn get(virt: &rot::Virtual) -> Result<HostResetState, Error> {
+    let arena = BumpArena::new([0; 64]);
+    virt.send_cerberus::<GetHostState>(
+        Req::<GetHostState> { port_id: 0 },
+        &arena,
+    )
+    .unwrap()
+    .map(|resp| resp.host_reset_state)
+    .map_err(|e| e.into_inner())
+}
+
+/// Asks `virt` to set the host's state to `hold_in_reset`, authorizing it
+/// with a signature by the given PKCS#8 keypair over `signed_hold_in_reset`.
+fn set_signed(
+    virt: &rot::Virtual,
+    hold_in_reset: bool,
+    signed_hold_in_reset: bool,
+    keypair: &[u8],
+) -> Result<(), Error> {
+    let mut arena = BumpArena::new(vec![0; 1024]);
+    let resp = virt
+        .send_cerberus::<SetHostState>(
+            Req::<SetHostState> {
+                port_id: 0,
+                hold_in_reset,
+                authorization: None,
+            },
+            &arena,
+        )
+        .unwrap()
+        .map_err(|e| e.into_inner())?;
+    let nonce = Nonce(resp.nonce.try_into().unwrap());
+    arena.reset();
+
+    let mut signer = ring::rsa::Sign256::from_pkcs8(keypair).unwrap();
+    let mut signature = vec![0; signer.sig_bytes()];
+    let len = with_signed_message(0, signed_hold_in_reset, &nonce, |msg| {
+        signer.sign(msg, &mut signature)
+    })
+    .unwrap();
+    signature.truncate(len);
+
+    let resp = virt
+        .send_cerberus::<SetHostState>(
+            Req::<SetHostState> {
+                port_id: 0,
+                hold_in_reset,
+                authorization: Some(AuthToken {
+                    nonce: &nonce,
+                    signature: &signature,
+                }),
+            },
+            &arena,
+        )
+        .unwrap();
+    match resp {
+        Ok(resp) => {
+            assert!(resp.nonce.is_empty());
+            Ok(())
+        }
+        Err(e) => Err(e.into_inner()),
+    }
+}
+
+fn set(virt: &rot::Virtual, hold_in_reset: bool) -> Result<(), Error> {
+    set_signed(virt, hold_in_reset, hold_in_reset, keys::KEY1_RSA_KEYPAIR)
+}
+
+#[test]
+fn requires_session() {
+    let virt = spawn();
+    assert_eq!(get(&virt), Ok(HostResetState::HostRunning));
+    assert_eq!(set(&virt, true), Err(Error::SessionRequired));
+    assert_eq!(get(&virt), Ok(HostResetState::HostRunning));
+}
+
+#[test]
+fn toggle() {
+    let virt = spawn();
+    establish_session(&virt);
+
+    set(&virt, true).unwrap();
+    assert_eq!(get(&virt), Ok(HostResetState::HostInReset));
+    set(&virt, false).unwrap();
+    assert_eq!(get(&virt), Ok(HostResetState::HostRunning));
+}
+
+#[test]
+fn unauthorized() {
+    let virt = spawn();
+    establish_session(&virt);
+
+    // A session alone is not enough; the nonce must be signed by the host's
+    // authority.
+    assert_eq!(
+        set_signed(&virt, true, true, keys::KEY2_RSA_KEYPAIR),
+        Err(Error::Unauthorized)
+    );
+    assert_eq!(get(&virt), Ok(HostResetState::HostRunning));
+
+    // Nor may a signature for releasing the host be used to hold it.
+    assert_eq!(
+        set_signed(&virt, true, false, keys::KEY1_RSA_KEYPAIR),
+        Err(Error::Unauthorized)
+    );
+    assert_eq!(get(&virt), Ok(HostResetState::HostRunning));
+}
+
+#[test]
+fn unsupported() {
+    let virt = rot::Virtual::spawn(&rot::Options::default());
+    assert_eq!(get(&virt), Err(Error::UnsupportedCommand));
+    assert_eq!(set(&virt, true), Err(Error::UnsupportedCommand));
+}
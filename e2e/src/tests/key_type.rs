@@ -0,0 +1,119 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for picking which type of key signs a challenge.
+
+use manticore::cert::CertFormat;
+use manticore::crypto::ring;
+use manticore::crypto::sig;
+use manticore::mem::BumpArena;
+use manticore::protocol::cerberus::capabilities::*;
+use manticore::protocol::cerberus::*;
+use manticore::protocol::Nonce;
+use manticore::protocol::Req;
+use manticore::protocol::Slot;
+use testutil::data::keys;
+use testutil::data::x509;
+
+use crate::support::rot;
+
+fn client_capabilities() -> Capabilities {
+    rot::ClientOptions {
+        ecdsa: true,
+        ..Default::default()
+    }
+    .capabilities()
+}
+
+/// Spawns a RoT with an RSA key and, if `ecdsa` is set, an ECDSA key.
+fn spawn(ecdsa: bool) -> rot::Virtual {
+    rot::Virtual::spawn(&rot::Options {
+        cert_chain: vec![
+            x509::CHAIN1.to_vec(),
+            x509::CHAIN2.to_vec(),
+            x509::CHAIN3.to_vec(),
+        ],
+        cert_format: CertFormat::RiotX509,
+        alias_keypair: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY3_RSA_KEYPAIR.to_vec(),
+        )),
+        ecdsa_alias_keypair: if ecdsa {
+            Some(keys::KEY1_ECDSA_P256_KEYPAIR.to_vec())
+        } else {
+            None
+        },
+        ..Default::default()
+    })
+}
+
+#[test]
+fn each_key_type() {
+    let virt = spawn(true);
+    let arena = BumpArena::new(vec![0; 4096]);
+
+    let resp = virt
+        .send_cerberus::<DeviceCapabilities>(
+            Req::<DeviceCapabilities> {
+                capabilities: client_capabilities(),
+            },
+            &arena,
+        )
+        .unwrap()
+        .unwrap();
+    let crypto = resp.capabilities.crypto;
+
+    let mut rsa = ring::rsa::Sign256::from_pkcs8(keys::KEY3_RSA_KEYPAIR)
+        .unwrap()
+        .verifier();
+    let mut ecdsa = ring::ecdsa::VerifyP256::with_der_encoding(
+        *keys::KEY1_ECDSA_P256_X,
+        *keys::KEY1_ECDSA_P256_Y,
+    );
+    let mut cases: [(sig::KeyType, &mut dyn sig::Verify); 2] = [
+        (sig::KeyType::Rsa, &mut rsa),
+        (sig::KeyType::Ecdsa, &mut ecdsa),
+    ];
+    for (i, (key_type, verifier)) in cases.iter_mut().enumerate() {
+        assert!(key_type.is_negotiated(&crypto), "{:?}", key_type);
+
+        let req = Req::<Challenge> {
+            slot: Slot::FIRST,
+            nonce: &Nonce([i as u8; 32]),
+            want_timestamp: false,
+            key_type: Some(*key_type),
+        };
+        let resp = virt
+            .send_cerberus::<Challenge>(req, &arena)
+            .unwrap()
+            .unwrap();
+        resp.verify(&req, &mut **verifier).unwrap();
+    }
+}
+
+#[test]
+fn missing_key_type() {
+    let virt = spawn(false);
+    let arena = BumpArena::new(vec![0; 4096]);
+    let challenge = |key_type, nonce| {
+        virt.send_cerberus::<Challenge>(
+            Req::<Challenge> {
+                slot: Slot::FIRST,
+                nonce: &Nonce([nonce; 32]),
+                want_timestamp: false,
+                key_type,
+            },
+            &arena,
+        )
+        .unwrap()
+        .map(drop)
+        .map_err(|e| e.into_inner())
+    };
+
+    assert_eq!(
+        challenge(Some(sig::KeyType::Ecdsa), 1),
+        Err(Error::UnsupportedKeyType)
+    );
+    assert_eq!(challenge(Some(sig::KeyType::Rsa), 2), Ok(()));
+    assert_eq!(challenge(None, 3), Ok(()));
+}
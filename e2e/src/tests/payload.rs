@@ -0,0 +1,32 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for reading request payloads off of a `TcpHostPort`.
+
+use std::io::Write as _;
+use std::net::TcpStream;
+use std::thread;
+
+use manticore::mem::BumpArena;
+use manticore::net;
+use manticore::net::host::HostPort as _;
+
+use crate::support::tcp::TcpHostPort;
+
+#[test]
+fn payload_bytes() {
+    let arena = BumpArena::new([0; 64]);
+    let mut port = TcpHostPort::<net::CerberusHeader>::bind().unwrap();
+    let addr = ("127.0.0.1", port.port());
+
+    let client = thread::spawn(move || {
+        let mut conn = TcpStream::connect(addr).unwrap();
+        conn.write_all(&[0x01, 0x05, 0x00]).unwrap();
+        conn.write_all(b"hello").unwrap();
+    });
+    let req = port.receive().unwrap();
+    assert_eq!(req.payload_bytes(&arena).unwrap(), b"hello");
+    assert_eq!(req.payload_len(), Some(0));
+    client.join().unwrap();
+}
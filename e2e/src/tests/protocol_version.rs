@@ -0,0 +1,92 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for protocol version negotiation.
+
+use manticore::mem::BumpArena;
+use manticore::protocol::cerberus::capabilities::*;
+use manticore::protocol::cerberus::*;
+use manticore::protocol::Req;
+
+use crate::support::rot;
+
+fn client_capabilities(version: u8) -> Capabilities {
+    rot::ClientOptions {
+        protocol_version: ProtocolVersion::new(version).unwrap(),
+        ..Default::default()
+    }
+    .capabilities()
+}
+
+#[test]
+fn negotiate() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        protocol_versions: vec![
+            ProtocolVersion::CURRENT,
+            ProtocolVersion::new(1).unwrap(),
+        ],
+        ..Default::default()
+    });
+
+    let mut client = virt.connect(client_capabilities(0));
+    assert_eq!(client.protocol_version().unwrap(), ProtocolVersion::CURRENT);
+
+    let mut client = virt.connect(client_capabilities(1));
+    assert_eq!(client.protocol_version().unwrap().get(), 1);
+}
+
+/// Asks `virt` for its firmware version, without negotiating first.
+fn fw_version(virt: &rot::Virtual) -> Result<(), Error> {
+    let arena = BumpArena::new([0; 64]);
+    virt.send_cerberus::<FirmwareVersion>(
+        Req::<FirmwareVersion> { index: 0 },
+        &arena,
+    )
+    .unwrap()
+    .map(drop)
+    .map_err(|e| e.into_inner())
+}
+
+#[test]
+fn rejects_unknown_version() {
+    let virt = rot::Virtual::spawn(&rot::Options::default());
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt
+        .send_cerberus::<DeviceCapabilities>(
+            Req::<DeviceCapabilities> {
+                capabilities: client_capabilities(2),
+            },
+            &arena,
+        )
+        .unwrap();
+    assert_eq!(
+        resp.map(drop).map_err(|e| e.into_inner()),
+        Err(Error::UnsupportedVersion)
+    );
+    assert_eq!(fw_version(&virt), Err(Error::UnsupportedVersion));
+
+    // A client that is refused cannot go on to send other requests.
+    let mut client = virt.connect(client_capabilities(2));
+    assert!(client.protocol_version().is_err());
+
+    let mut client = virt.connect(client_capabilities(0));
+    assert_eq!(client.protocol_version().unwrap(), ProtocolVersion::CURRENT);
+    assert_eq!(fw_version(&virt), Ok(()));
+}
+
+#[test]
+fn requires_negotiation_without_current() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        protocol_versions: vec![ProtocolVersion::new(1).unwrap()],
+        ..Default::default()
+    });
+
+    // A peer that hasn't negotiated is assumed to speak the current version,
+    // which this device doesn't accept.
+    assert_eq!(fw_version(&virt), Err(Error::UnsupportedVersion));
+
+    let mut client = virt.connect(client_capabilities(1));
+    assert_eq!(client.protocol_version().unwrap().get(), 1);
+    assert_eq!(fw_version(&virt), Ok(()));
+}
@@ -0,0 +1,42 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for per-command rate limiting.
+
+use manticore::mem::BumpArena;
+use manticore::protocol::cerberus::*;
+use manticore::protocol::Req;
+
+use crate::support::rot;
+
+#[test]
+fn busy_when_exhausted() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        firmware_version: b"limited".to_vec(),
+        rate_limit: Some((CommandType::FirmwareVersion, 2)),
+        ..Default::default()
+    });
+
+    let arena = BumpArena::new(vec![0; 1024]);
+    for _ in 0..2 {
+        let resp = virt.send_cerberus::<FirmwareVersion>(
+            Req::<FirmwareVersion> { index: 0 },
+            &arena,
+        );
+        assert_eq!(&resp.unwrap().unwrap().version[..7], b"limited");
+    }
+
+    let err = virt
+        .send_cerberus::<FirmwareVersion>(
+            Req::<FirmwareVersion> { index: 0 },
+            &arena,
+        )
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.into_inner(), Error::Busy);
+
+    // Other commands are unaffected.
+    let resp = virt.send_cerberus::<DeviceId>(Req::<DeviceId> {}, &arena);
+    assert_eq!(resp.unwrap().unwrap().id.vendor_id, 1);
+}
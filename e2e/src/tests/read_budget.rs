@@ -0,0 +1,107 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for `TcpHostPort` read budgets.
+
+use std::io::Write as _;
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+use manticore::mem::BumpArena;
+use manticore::net;
+use manticore::net::host::HostPort as _;
+use manticore::protocol::cerberus::*;
+use manticore::protocol::Req;
+
+use crate::support::rot;
+use crate::support::tcp;
+use crate::support::tcp::TcpHostPort;
+
+/// Binds a port with `budget`, and returns the result of the first
+/// `receive()` on it, after `client` has been run against it.
+fn receive_with(
+    budget: tcp::ReadBudget,
+    client: impl FnOnce(TcpStream) + Send + 'static,
+) -> Result<(), net::Error> {
+    let mut port = TcpHostPort::<net::CerberusHeader>::bind().unwrap();
+    port.set_read_budget(budget);
+    let addr = ("127.0.0.1", port.port());
+
+    let client =
+        thread::spawn(move || client(TcpStream::connect(addr).unwrap()));
+    let result = port.receive().map(|_| ()).map_err(|e| e.into_inner());
+    client.join().unwrap();
+    result
+}
+
+/// The header of a `FirmwareVersion` request, whose payload is one byte.
+const HEADER: [u8; 3] = [0x01, 0x01, 0x00];
+
+#[test]
+fn per_message_deadline() {
+    let result = receive_with(
+        tcp::ReadBudget {
+            per_message: Some(Duration::from_millis(100)),
+            ..Default::default()
+        },
+        |mut conn| {
+            conn.write_all(&HEADER).unwrap();
+            thread::sleep(Duration::from_millis(300));
+            let _ = conn.write_all(&[0]);
+        },
+    );
+    assert_eq!(result, Err(net::Error::Timeout));
+}
+
+#[test]
+fn min_rate() {
+    let result = receive_with(
+        tcp::ReadBudget {
+            min_rate: Some(100),
+            ..Default::default()
+        },
+        |mut conn| {
+            for &byte in &HEADER {
+                let _ = conn.write_all(&[byte]);
+                thread::sleep(tcp::RATE_GRACE);
+            }
+        },
+    );
+    assert_eq!(result, Err(net::Error::Timeout));
+}
+
+#[test]
+fn within_budget() {
+    let result = receive_with(
+        tcp::ReadBudget {
+            per_message: Some(Duration::from_secs(5)),
+            min_rate: Some(100),
+        },
+        |mut conn| {
+            conn.write_all(&HEADER).unwrap();
+            conn.write_all(&[0]).unwrap();
+        },
+    );
+    assert_eq!(result, Ok(()));
+}
+
+#[test]
+fn serves_requests() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        read_budget: tcp::ReadBudget {
+            per_message: Some(Duration::from_secs(5)),
+            min_rate: Some(100),
+        },
+        firmware_version: b"budget".to_vec(),
+        ..Default::default()
+    });
+
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt.send_cerberus::<FirmwareVersion>(
+        Req::<FirmwareVersion> { index: 0 },
+        &arena,
+    );
+    assert_eq!(&resp.unwrap().unwrap().version[..6], b"budget");
+}
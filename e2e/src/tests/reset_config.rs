@@ -0,0 +1,136 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for authorized configuration resets.
+
+use std::convert::TryInto as _;
+
+use manticore::crypto::ring;
+use manticore::crypto::sig::Sign as _;
+use manticore::mem::BumpArena;
+use manticore::protocol::cerberus::reset_config::with_signed_message;
+use manticore::protocol::cerberus::reset_config::AuthToken;
+use manticore::protocol::cerberus::reset_config::ResetKind;
+use manticore::protocol::cerberus::*;
+use manticore::protocol::Nonce;
+use manticore::protocol::Req;
+use testutil::data::keys;
+
+use crate::support::rot;
+
+/// Asks `virt` for a nonce to authorize a reset of `kind` with.
+fn fresh_nonce(virt: &rot::Virtual, kind: ResetKind) -> Nonce {
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt
+        .send_cerberus::<ResetConfig>(
+            Req::<ResetConfig> {
+                kind,
+                authorization: None,
+            },
+            &arena,
+        )
+        .unwrap()
+        .unwrap();
+    Nonce(resp.nonce.try_into().unwrap())
+}
+
+/// Signs `nonce` for a reset of `kind` with the given PKCS#8 keypair.
+fn sign(keypair: &[u8], kind: ResetKind, nonce: &Nonce) -> Vec<u8> {
+    let mut signer = ring::rsa::Sign256::from_pkcs8(keypair).unwrap();
+    let mut sig = vec![0; signer.sig_bytes()];
+    let len =
+        with_signed_message(kind, nonce, |msg| signer.sign(msg, &mut sig))
+            .unwrap();
+    sig.truncate(len);
+    sig
+}
+
+/// Sends an authorized `ResetConfig` to `virt`.
+fn reset(
+    virt: &rot::Virtual,
+    kind: ResetKind,
+    nonce: &Nonce,
+    signature: &[u8],
+) -> Result<(), Error> {
+    let arena = BumpArena::new([0; 1024]);
+    let resp = virt
+        .send_cerberus::<ResetConfig>(
+            Req::<ResetConfig> {
+                kind,
+                authorization: Some(AuthToken { nonce, signature }),
+            },
+            &arena,
+        )
+        .unwrap();
+    match resp {
+        Ok(resp) => {
+            assert!(resp.nonce.is_empty());
+            Ok(())
+        }
+        Err(e) => Err(e.into_inner()),
+    }
+}
+
+#[test]
+fn authorized() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        reset_authority: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY1_RSA_KEYPAIR.to_vec(),
+        )),
+        ..Default::default()
+    });
+
+    let kind = ResetKind::FactoryDefault;
+    let nonce = fresh_nonce(&virt, kind);
+    let sig = sign(keys::KEY1_RSA_KEYPAIR, kind, &nonce);
+    reset(&virt, kind, &nonce, &sig).unwrap();
+
+    // The nonce cannot be replayed.
+    assert_eq!(reset(&virt, kind, &nonce, &sig), Err(Error::Unauthorized));
+}
+
+#[test]
+fn unauthorized() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        reset_authority: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY1_RSA_KEYPAIR.to_vec(),
+        )),
+        ..Default::default()
+    });
+    let kind = ResetKind::FactoryDefault;
+
+    // A signature by the wrong key is rejected, and burns the nonce.
+    let nonce = fresh_nonce(&virt, kind);
+    let bad = sign(keys::KEY2_RSA_KEYPAIR, kind, &nonce);
+    assert_eq!(reset(&virt, kind, &nonce, &bad), Err(Error::Unauthorized));
+    let good = sign(keys::KEY1_RSA_KEYPAIR, kind, &nonce);
+    assert_eq!(reset(&virt, kind, &nonce, &good), Err(Error::Unauthorized));
+
+    // So is a signature over a nonce the device never handed out.
+    let _ = fresh_nonce(&virt, kind);
+    let forged = Nonce([0x42; 32]);
+    let sig = sign(keys::KEY1_RSA_KEYPAIR, kind, &forged);
+    assert_eq!(reset(&virt, kind, &forged, &sig), Err(Error::Unauthorized));
+
+    // And a signature for a different kind of reset.
+    let nonce = fresh_nonce(&virt, kind);
+    let sig = sign(keys::KEY1_RSA_KEYPAIR, ResetKind::Bypass, &nonce);
+    assert_eq!(reset(&virt, kind, &nonce, &sig), Err(Error::Unauthorized));
+}
+
+#[test]
+fn unsupported() {
+    let virt = rot::Virtual::spawn(&rot::Options::default());
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt
+        .send_cerberus::<ResetConfig>(
+            Req::<ResetConfig> {
+                kind: ResetKind::FactoryDefault,
+                authorization: None,
+            },
+            &arena,
+        )
+        .unwrap();
+    assert_eq!(resp.unwrap_err().into_inner(), Error::UnsupportedCommand);
+}
@@ -0,0 +1,70 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for serving a bounded number of requests.
+
+use std::io::Write as _;
+use std::net::TcpStream;
+
+use manticore::mem::BumpArena;
+use manticore::protocol::cerberus::*;
+use manticore::protocol::Req;
+
+use crate::support::rot;
+
+#[test]
+fn exits_after_max_requests() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        max_requests: Some(2),
+        ..Default::default()
+    });
+
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt
+        .send_cerberus::<FirmwareVersion>(
+            Req::<FirmwareVersion> { index: 0 },
+            &arena,
+        )
+        .unwrap();
+    assert!(resp.is_ok());
+
+    // Requests that fail still count towards the limit.
+    let resp = virt
+        .send_cerberus::<FirmwareVersion>(
+            Req::<FirmwareVersion> { index: 9 },
+            &arena,
+        )
+        .unwrap();
+    assert!(resp.is_err());
+
+    // The server is gone now, so nothing answers a third request.
+    let resp = virt.send_cerberus::<FirmwareVersion>(
+        Req::<FirmwareVersion> { index: 0 },
+        &arena,
+    );
+    assert!(resp.is_err());
+}
+
+#[test]
+fn survives_truncated_request() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        max_requests: Some(2),
+        ..Default::default()
+    });
+
+    // A peer that hangs up partway through a header is counted as a request,
+    // but doesn't stop the server.
+    let mut conn = TcpStream::connect(("127.0.0.1", virt.port())).unwrap();
+    conn.write_all(&[0x01]).unwrap();
+    drop(conn);
+
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt
+        .send_cerberus::<FirmwareVersion>(
+            Req::<FirmwareVersion> { index: 0 },
+            &arena,
+        )
+        .unwrap();
+    assert!(resp.is_ok());
+}
@@ -0,0 +1,61 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for shutting down a `TcpHostPort`.
+
+use std::thread;
+use std::time::Duration;
+
+use manticore::net;
+use manticore::net::host::HostPort as _;
+
+use crate::support::tcp::TcpHostPort;
+
+#[test]
+fn shutdown_before_receive() {
+    let mut port = TcpHostPort::<net::CerberusHeader>::bind().unwrap();
+    let handle = port.shutdown_handle();
+    handle.shutdown();
+    assert!(handle.is_shutdown());
+
+    let err = port.receive().err().unwrap();
+    assert_eq!(err.into_inner(), net::Error::Shutdown);
+}
+
+#[test]
+fn shutdown_while_blocked() {
+    let mut port = TcpHostPort::<net::CerberusHeader>::bind().unwrap();
+    let handle = port.shutdown_handle();
+
+    let server = thread::spawn(move || {
+        let err = port.receive().err().unwrap();
+        err.into_inner()
+    });
+
+    // Give the server a chance to block in `accept()`.
+    thread::sleep(Duration::from_millis(100));
+    handle.shutdown();
+    assert_eq!(server.join().unwrap(), net::Error::Shutdown);
+}
+
+#[test]
+#[cfg(unix)]
+fn shutdown_on_signal() {
+    use signal_hook::consts::SIGUSR1;
+
+    let mut port = TcpHostPort::<net::CerberusHeader>::bind().unwrap();
+    port.shutdown_on_signals(&[SIGUSR1]).unwrap();
+    let handle = port.shutdown_handle();
+
+    let server = thread::spawn(move || {
+        let err = port.receive().err().unwrap();
+        err.into_inner()
+    });
+
+    // Give the server a chance to block in `accept()`.
+    thread::sleep(Duration::from_millis(100));
+    signal_hook::low_level::raise(SIGUSR1).unwrap();
+    assert_eq!(server.join().unwrap(), net::Error::Shutdown);
+    assert!(handle.is_shutdown());
+}
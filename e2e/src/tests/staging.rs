@@ -0,0 +1,296 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for delivering and activating PFMs and PCDs.
+//!
+//! These go through the same staging logic as CFMs, which are covered in
+//! more depth by the `cfm` tests.
+
+use manticore::crypto::hash;
+use manticore::crypto::ring;
+use manticore::manifest::owned;
+use manticore::manifest::ManifestType;
+use manticore::manifest::Metadata;
+use manticore::mem::BumpArena;
+use manticore::protocol::cerberus::*;
+use manticore::protocol::Req;
+use testutil::data::keys;
+
+use crate::support::rot;
+
+/// Builds an empty manifest with the given version ID, signed with the given
+/// PKCS#8 keypair.
+fn signed<E: owned::Element>(keypair: &[u8], version_id: u32) -> Vec<u8> {
+    let manifest = owned::Container::<E> {
+        metadata: Metadata { version_id },
+        elements: vec![],
+    };
+    let mut signer = ring::rsa::Sign256::from_pkcs8(keypair).unwrap();
+    manifest
+        .sign(
+            0x00,
+            hash::Algo::Sha256,
+            &mut ring::hash::Engine::new(),
+            &mut signer,
+        )
+        .unwrap()
+}
+
+/// Spawns a RoT that accepts PFMs and PCDs signed with `KEY1`.
+fn spawn() -> rot::Virtual {
+    let authority = || {
+        Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY1_RSA_KEYPAIR.to_vec(),
+        ))
+    };
+    rot::Virtual::spawn(&rot::Options {
+        pfm_authority: authority(),
+        pcd_authority: authority(),
+        ..Default::default()
+    })
+}
+
+/// Sends a `Prepare`, then `manifest` in `chunk`-byte `Update`s, stopping at
+/// the first error, then an `Activate`, for manifests of type `ty`.
+fn send(
+    virt: &rot::Virtual,
+    ty: ManifestType,
+    manifest: &[u8],
+    chunk: usize,
+) -> Result<(), Error> {
+    prepare(virt, ty, manifest.len())?;
+    for (i, data) in manifest.chunks(chunk).enumerate() {
+        update(virt, ty, i * chunk, data)?;
+    }
+    activate(virt, ty)
+}
+
+fn prepare(
+    virt: &rot::Virtual,
+    ty: ManifestType,
+    total_len: usize,
+) -> Result<(), Error> {
+    let arena = BumpArena::new([0; 64]);
+    let total_len = total_len as u32;
+    match ty {
+        ManifestType::Pfm => virt
+            .send_cerberus::<PreparePfm>(
+                Req::<PreparePfm> {
+                    port_id: 0,
+                    total_len,
+                },
+                &arena,
+            )
+            .unwrap()
+            .map(drop),
+        ManifestType::Pcd => virt
+            .send_cerberus::<PreparePcd>(
+                Req::<PreparePcd> { total_len },
+                &arena,
+            )
+            .unwrap()
+            .map(drop),
+        ManifestType::Cfm => unreachable!(),
+    }
+    .map_err(|e| e.into_inner())
+}
+
+fn update(
+    virt: &rot::Virtual,
+    ty: ManifestType,
+    offset: usize,
+    data: &[u8],
+) -> Result<(), Error> {
+    let arena = BumpArena::new([0; 64]);
+    let offset = offset as u32;
+    match ty {
+        ManifestType::Pfm => virt
+            .send_cerberus::<UpdatePfm>(
+                Req::<UpdatePfm> {
+                    port_id: 0,
+                    offset,
+                    data,
+                },
+                &arena,
+            )
+            .unwrap()
+            .map(drop),
+        ManifestType::Pcd => virt
+            .send_cerberus::<UpdatePcd>(
+                Req::<UpdatePcd> { offset, data },
+                &arena,
+            )
+            .unwrap()
+            .map(drop),
+        ManifestType::Cfm => unreachable!(),
+    }
+    .map_err(|e| e.into_inner())
+}
+
+fn activate(virt: &rot::Virtual, ty: ManifestType) -> Result<(), Error> {
+    let arena = BumpArena::new([0; 64]);
+    match ty {
+        ManifestType::Pfm => virt
+            .send_cerberus::<ActivatePfm>(
+                Req::<ActivatePfm> { port_id: 0 },
+                &arena,
+            )
+            .unwrap()
+            .map(drop),
+        ManifestType::Pcd => virt
+            .send_cerberus::<ActivatePcd>(Req::<ActivatePcd> {}, &arena)
+            .unwrap()
+            .map(drop),
+        ManifestType::Cfm => unreachable!(),
+    }
+    .map_err(|e| e.into_inner())
+}
+
+/// Asks `virt` for the version ID of its active manifest of type `ty`.
+fn version_id(virt: &rot::Virtual, ty: ManifestType) -> Option<u32> {
+    let arena = BumpArena::new([0; 128]);
+    let resp = virt
+        .send_cerberus::<GetConfigIds>(
+            Req::<GetConfigIds> { manifest_type: ty },
+            &arena,
+        )
+        .unwrap()
+        .unwrap();
+    resp.active.map(|id| id.version_id)
+}
+
+#[test]
+fn pfm() {
+    let virt = spawn();
+    let pfm = signed::<owned::pfm::Element>(keys::KEY1_RSA_KEYPAIR, 5);
+
+    assert_eq!(version_id(&virt, ManifestType::Pfm), None);
+    send(&virt, ManifestType::Pfm, &pfm, 32).unwrap();
+    assert_eq!(version_id(&virt, ManifestType::Pfm), Some(5));
+
+    // PCDs are stored separately.
+    assert_eq!(version_id(&virt, ManifestType::Pcd), None);
+}
+
+#[test]
+fn pcd() {
+    let virt = spawn();
+    let pcd = signed::<owned::pcd::Element>(keys::KEY1_RSA_KEYPAIR, 6);
+
+    assert_eq!(version_id(&virt, ManifestType::Pcd), None);
+    send(&virt, ManifestType::Pcd, &pcd, 32).unwrap();
+    assert_eq!(version_id(&virt, ManifestType::Pcd), Some(6));
+    assert_eq!(version_id(&virt, ManifestType::Pfm), None);
+}
+
+#[test]
+fn corrupted_final_chunk() {
+    let virt = spawn();
+    let manifests = vec![
+        (
+            ManifestType::Pfm,
+            signed::<owned::pfm::Element>(keys::KEY1_RSA_KEYPAIR, 1),
+        ),
+        (
+            ManifestType::Pcd,
+            signed::<owned::pcd::Element>(keys::KEY1_RSA_KEYPAIR, 1),
+        ),
+    ];
+    for (ty, mut manifest) in manifests {
+        *manifest.last_mut().unwrap() ^= 1;
+
+        // Only the final chunk is checked, so every chunk before it goes
+        // through.
+        prepare(&virt, ty, manifest.len()).unwrap();
+        let (head, tail) = manifest.split_at(manifest.len() - 16);
+        for (i, data) in head.chunks(32).enumerate() {
+            update(&virt, ty, i * 32, data).unwrap();
+        }
+        assert_eq!(
+            update(&virt, ty, head.len(), tail),
+            Err(Error::Unauthorized)
+        );
+        assert_eq!(activate(&virt, ty), Err(Error::OutOfRange));
+        assert_eq!(version_id(&virt, ty), None);
+    }
+}
+
+#[test]
+fn rejects_unprepared_and_overflowing_chunks() {
+    let virt = spawn();
+    let pfm = signed::<owned::pfm::Element>(keys::KEY1_RSA_KEYPAIR, 1);
+
+    // No `PreparePfm` yet.
+    assert_eq!(
+        update(&virt, ManifestType::Pfm, 0, &pfm),
+        Err(Error::OutOfRange)
+    );
+
+    // A chunk that runs past the prepared length is refused outright...
+    prepare(&virt, ManifestType::Pfm, pfm.len()).unwrap();
+    update(&virt, ManifestType::Pfm, 0, &pfm[..8]).unwrap();
+    assert_eq!(
+        update(&virt, ManifestType::Pfm, 8, &pfm),
+        Err(Error::OutOfRange)
+    );
+
+    // ...without disturbing the chunks before it.
+    update(&virt, ManifestType::Pfm, 8, &pfm[8..]).unwrap();
+    activate(&virt, ManifestType::Pfm).unwrap();
+
+    // Once a manifest is complete, further chunks need a new `PreparePfm`.
+    assert_eq!(
+        update(&virt, ManifestType::Pfm, 0, &pfm[..8]),
+        Err(Error::OutOfRange)
+    );
+}
+
+#[test]
+fn rejects_out_of_order_chunks() {
+    let virt = spawn();
+    let pfm = signed::<owned::pfm::Element>(keys::KEY1_RSA_KEYPAIR, 1);
+
+    prepare(&virt, ManifestType::Pfm, pfm.len()).unwrap();
+    update(&virt, ManifestType::Pfm, 0, &pfm[..8]).unwrap();
+
+    // Skipping ahead, or replaying a chunk that already arrived, is refused...
+    assert_eq!(
+        update(&virt, ManifestType::Pfm, 16, &pfm[16..24]),
+        Err(Error::OutOfRange)
+    );
+    assert_eq!(
+        update(&virt, ManifestType::Pfm, 0, &pfm[..8]),
+        Err(Error::OutOfRange)
+    );
+
+    // ...and leaves the transfer where it was.
+    update(&virt, ManifestType::Pfm, 8, &pfm[8..]).unwrap();
+    activate(&virt, ManifestType::Pfm).unwrap();
+    assert_eq!(version_id(&virt, ManifestType::Pfm), Some(1));
+}
+
+#[test]
+fn pfm_port() {
+    let virt = spawn();
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt
+        .send_cerberus::<PreparePfm>(
+            Req::<PreparePfm> {
+                port_id: 1,
+                total_len: 16,
+            },
+            &arena,
+        )
+        .unwrap();
+    assert_eq!(resp.map_err(|e| e.into_inner()), Err(Error::OutOfRange));
+}
+
+#[test]
+fn unsupported() {
+    let virt = rot::Virtual::spawn(&rot::Options::default());
+    for &ty in &[ManifestType::Pfm, ManifestType::Pcd] {
+        assert_eq!(prepare(&virt, ty, 16), Err(Error::UnsupportedCommand));
+        assert_eq!(activate(&virt, ty), Err(Error::UnsupportedCommand));
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for streamed Cerberus-over-TCP replies.
+
+use std::io::Read as _;
+use std::io::Write as _;
+use std::net::TcpStream;
+
+use manticore::mem::BumpArena;
+use manticore::net;
+use manticore::protocol::cerberus::capabilities::*;
+use manticore::protocol::cerberus::*;
+use manticore::protocol::Req;
+
+use crate::support::rot;
+use crate::support::tcp;
+
+fn client_capabilities() -> Capabilities {
+    rot::ClientOptions {
+        streaming: true,
+        ..Default::default()
+    }
+    .capabilities()
+}
+
+/// Sends `req` on a fresh connection, asking for a streamed reply, and
+/// returns the first `len` bytes of the reply.
+fn exchange_raw(port: u16, req: Vec<u8>, len: usize) -> Vec<u8> {
+    let mut conn = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    conn.write_all(&req).unwrap();
+    let mut reply = vec![0; len];
+    conn.read_exact(&mut reply).unwrap();
+    reply
+}
+
+#[test]
+fn negotiate() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        streaming: true,
+        ..Default::default()
+    });
+    let mut client = virt.connect(client_capabilities());
+    assert!(client.capabilities().unwrap().networking.has_streaming);
+
+    let virt = rot::Virtual::spawn(&rot::Options::default());
+    let mut client = virt.connect(client_capabilities());
+    assert!(!client.capabilities().unwrap().networking.has_streaming);
+}
+
+#[test]
+fn streamed_round_trip() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        streaming: true,
+        firmware_version: b"streamed".to_vec(),
+        ..Default::default()
+    });
+    let mut client = virt.connect(client_capabilities());
+
+    let arena = BumpArena::new([0; 128]);
+    for _ in 0..2 {
+        let resp = client
+            .command::<FirmwareVersion>(
+                Req::<FirmwareVersion> { index: 0 },
+                &arena,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(&resp.version[..8], b"streamed");
+    }
+
+    // Errors are streamed too.
+    let resp = client
+        .command::<FirmwareVersion>(Req::<FirmwareVersion> { index: 9 }, &arena)
+        .unwrap();
+    assert!(resp.is_err());
+}
+
+#[test]
+fn only_fixed_length_replies_are_streamed() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        streaming: true,
+        ..Default::default()
+    });
+    let opts = tcp::SendOptions {
+        streaming: true,
+        ..Default::default()
+    };
+
+    let req = tcp::to_vec(
+        &Req::<FirmwareVersion> { index: 0 },
+        net::CerberusHeader {
+            command: CommandType::FirmwareVersion,
+        },
+        opts,
+    )
+    .unwrap();
+    let reply = exchange_raw(virt.port(), req, 3 + 32);
+    let len = u16::from_le_bytes([reply[1], reply[2]]);
+    assert_eq!(len, tcp::STREAM_BIT);
+
+    let arena = BumpArena::new([0; 64]);
+    let resp =
+        tcp::response_from_bytes::<FirmwareVersion>(&reply, &arena, opts);
+    assert!(resp.unwrap().is_ok());
+
+    // A `DeviceInfo` reply has no fixed length, so it still carries one.
+    let req = tcp::to_vec(
+        &Req::<DeviceInfo> {
+            index: device_info::InfoIndex::UniqueChipIndex,
+        },
+        net::CerberusHeader {
+            command: CommandType::DeviceInfo,
+        },
+        opts,
+    )
+    .unwrap();
+    let reply = exchange_raw(virt.port(), req, 3);
+    let len = u16::from_le_bytes([reply[1], reply[2]]);
+    assert_eq!(len & tcp::STREAM_BIT, 0);
+    assert_ne!(len, 0);
+}
+
+#[test]
+fn unrequested() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        streaming: true,
+        firmware_version: b"buffered".to_vec(),
+        ..Default::default()
+    });
+
+    // Clients that don't ask for streaming still get length prefixes.
+    let req = tcp::to_vec(
+        &Req::<FirmwareVersion> { index: 0 },
+        net::CerberusHeader {
+            command: CommandType::FirmwareVersion,
+        },
+        tcp::SendOptions::default(),
+    )
+    .unwrap();
+    let reply = exchange_raw(virt.port(), req, 3);
+    assert_eq!(u16::from_le_bytes([reply[1], reply[2]]), 32);
+
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt.send_cerberus::<FirmwareVersion>(
+        Req::<FirmwareVersion> { index: 0 },
+        &arena,
+    );
+    assert_eq!(&resp.unwrap().unwrap().version[..8], b"buffered");
+}
@@ -0,0 +1,51 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for requests that carry fields the RoT does not know about.
+
+use std::io::Read as _;
+use std::io::Write as _;
+use std::net::TcpStream;
+
+use manticore::protocol::cerberus::*;
+
+use crate::support::rot;
+
+/// A `FirmwareVersion` request for index 0, followed by two bytes of fields
+/// from some future revision of the protocol.
+const LONG_REQUEST: [u8; 6] = [0x01, 0x03, 0x00, 0x00, 0xaa, 0xbb];
+
+#[test]
+fn lenient_by_default() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        firmware_version: b"lenient".to_vec(),
+        ..Default::default()
+    });
+
+    let mut conn = TcpStream::connect(("127.0.0.1", virt.port())).unwrap();
+    conn.write_all(&LONG_REQUEST).unwrap();
+
+    let mut header = [0; 3];
+    conn.read_exact(&mut header).unwrap();
+    assert_eq!(header[0], CommandType::FirmwareVersion as u8);
+    let mut version = vec![0; u16::from_le_bytes([header[1], header[2]]) as _];
+    conn.read_exact(&mut version).unwrap();
+    assert_eq!(&version[..7], b"lenient");
+}
+
+#[test]
+fn strict_rejects() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        strict_parsing: true,
+        ..Default::default()
+    });
+
+    let mut conn = TcpStream::connect(("127.0.0.1", virt.port())).unwrap();
+    conn.write_all(&LONG_REQUEST).unwrap();
+
+    // The RoT hangs up without replying; depending on timing, this may
+    // surface as a reset rather than a clean EOF.
+    let n = conn.read(&mut [0; 1]).unwrap_or(0);
+    assert_eq!(n, 0);
+}
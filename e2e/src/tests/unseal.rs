@@ -0,0 +1,220 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for releasing keys sealed to measurements.
+
+use manticore::cert::CertFormat;
+use manticore::crypto::hash;
+use manticore::mem::Arena as _;
+use manticore::mem::BumpArena;
+use manticore::protocol::cerberus::unseal::Policy;
+use manticore::protocol::cerberus::*;
+use manticore::protocol::Nonce;
+use manticore::protocol::Req;
+use manticore::protocol::Resp;
+use manticore::protocol::Slot;
+use manticore::session;
+use manticore::session::Session as _;
+use testutil::data::keys;
+use testutil::data::x509;
+
+use crate::support::fakes;
+use crate::support::rot;
+use crate::support::tcp;
+
+const KEY: &[u8] = b"the sealed key";
+
+fn options() -> rot::Options {
+    rot::Options {
+        cert_chain: vec![
+            x509::CHAIN1.to_vec(),
+            x509::CHAIN2.to_vec(),
+            x509::CHAIN3.to_vec(),
+        ],
+        cert_format: CertFormat::RiotX509,
+        alias_keypair: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY3_RSA_KEYPAIR.to_vec(),
+        )),
+        attestation_events: vec![
+            (0, 0x07, b"crtm".to_vec()),
+            (2, 0x0d, b"bootloader v1.2.3".to_vec()),
+        ],
+        unseal: true,
+        ..Default::default()
+    }
+}
+
+/// Returns the values of PCRs 0 and 2 that `opts` produces.
+fn measurements(opts: &rot::Options) -> [[u8; 32]; 2] {
+    let mut buf = vec![0; 4096];
+    let log = rot::build_event_log(opts, &mut buf);
+    [*log.pcr(0).unwrap(), *log.pcr(2).unwrap()]
+}
+
+/// Runs a challenge and key exchange against `virt`, which must have been
+/// spawned with a certificate chain and alias keypair, as [`options()`]
+/// does.
+///
+/// Returns the host's end of the session.
+pub(super) fn establish_session(virt: &rot::Virtual) -> session::ring::Session {
+    let mut arena = BumpArena::new(vec![0; 1024]);
+    virt.send_cerberus::<GetDigests>(
+        Req::<GetDigests> {
+            slot: Slot::FIRST,
+            key_exchange: get_digests::KeyExchangeAlgo::Ecdh,
+        },
+        &arena,
+    )
+    .unwrap()
+    .unwrap();
+    arena.reset();
+
+    let req = Req::<Challenge> {
+        slot: Slot::FIRST,
+        nonce: &Nonce([99; 32]),
+        want_timestamp: false,
+        key_type: None,
+    };
+    let resp = virt
+        .send_cerberus::<Challenge>(req, &arena)
+        .unwrap()
+        .unwrap();
+
+    let mut session = session::ring::Session::new();
+    session
+        .create_session(&Nonce([99; 32]), resp.tbs.nonce)
+        .unwrap();
+    let mut pk_req = vec![0; session.ephemeral_bytes()];
+    let pk_len = session.begin_ecdh(&mut pk_req).unwrap();
+    let req = Req::<KeyExchange>::SessionKey {
+        hmac_algorithm: hash::Algo::Sha256,
+        pk_req: &pk_req[..pk_len],
+    };
+    let resp = virt
+        .send_cerberus::<KeyExchange>(req, &arena)
+        .unwrap()
+        .unwrap();
+    let pk_resp = match resp {
+        Resp::<KeyExchange>::SessionKey { pk_resp, .. } => pk_resp,
+        _ => panic!("unexpected response: {:?}", resp),
+    };
+    session.finish_ecdh(hash::Algo::Sha256, pk_resp).unwrap();
+    session
+}
+
+#[test]
+fn matching_policy() {
+    let opts = options();
+    let virt = rot::Virtual::spawn(&opts);
+    let mut session = establish_session(&virt);
+
+    let expected = measurements(&opts);
+    let policy = Policy {
+        pcr_mask: 0b101,
+        expected: &expected,
+    };
+    let blob = fakes::Unsealer::seal(&policy, KEY);
+    let req = Req::<Unseal> {
+        policy,
+        blob: &blob,
+    };
+
+    let arena = BumpArena::new(vec![0; 1024]);
+    let resp = virt
+        .send_cerberus_encrypted::<Unseal>(req, &arena, &mut session)
+        .unwrap()
+        .unwrap();
+    // The key never appears in the clear.
+    assert!(!resp.key.windows(KEY.len()).any(|w| w == KEY));
+    let mut key = resp.key.to_vec();
+    let key = session::open_message(&mut session, &mut key).unwrap();
+    assert_eq!(key, KEY);
+}
+
+#[test]
+fn mismatched_policy() {
+    let opts = options();
+    let virt = rot::Virtual::spawn(&opts);
+    let mut session = establish_session(&virt);
+
+    let mut expected = measurements(&opts);
+    expected[1][0] ^= 1;
+    let policy = Policy {
+        pcr_mask: 0b101,
+        expected: &expected,
+    };
+    let blob = fakes::Unsealer::seal(&policy, KEY);
+    let req = Req::<Unseal> {
+        policy,
+        blob: &blob,
+    };
+
+    let arena = BumpArena::new(vec![0; 1024]);
+    let err = virt
+        .send_cerberus_encrypted::<Unseal>(req, &arena, &mut session)
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.into_inner(), Error::Unauthorized);
+}
+
+#[test]
+fn requires_encryption() {
+    let opts = options();
+    let virt = rot::Virtual::spawn(&opts);
+    establish_session(&virt);
+
+    let expected = measurements(&opts);
+    let policy = Policy {
+        pcr_mask: 0b101,
+        expected: &expected,
+    };
+    let blob = fakes::Unsealer::seal(&policy, KEY);
+    let req = Req::<Unseal> {
+        policy,
+        blob: &blob,
+    };
+
+    // Sent in the clear, even within a session.
+    let arena = BumpArena::new(vec![0; 1024]);
+    let err = virt
+        .send_cerberus::<Unseal>(req, &arena)
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.into_inner(), Error::Unauthorized);
+
+    // Marked as encrypted, but not actually encrypted.
+    let opts = tcp::SendOptions {
+        encrypted: true,
+        ..Default::default()
+    };
+    let err = virt
+        .send_cerberus_with::<Unseal>(req, &arena, opts)
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.into_inner(), Error::Unauthorized);
+}
+
+#[test]
+fn requires_session() {
+    let opts = options();
+    let virt = rot::Virtual::spawn(&opts);
+
+    let expected = measurements(&opts);
+    let policy = Policy {
+        pcr_mask: 0b101,
+        expected: &expected,
+    };
+    let blob = fakes::Unsealer::seal(&policy, KEY);
+    let req = Req::<Unseal> {
+        policy,
+        blob: &blob,
+    };
+
+    let arena = BumpArena::new(vec![0; 1024]);
+    let err = virt
+        .send_cerberus::<Unseal>(req, &arena)
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.into_inner(), Error::SessionRequired);
+}
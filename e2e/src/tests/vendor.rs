@@ -0,0 +1,42 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for vendor-defined messages.
+
+use manticore::mem::BumpArena;
+use manticore::protocol::cerberus::*;
+
+use crate::support::rot;
+
+#[test]
+fn echo() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        vendor_ids: vec![0x1414, 0x8086],
+        ..Default::default()
+    });
+
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt
+        .send_vendor(0x8086, 7, b"hello, vendor", &arena)
+        .unwrap()
+        .unwrap();
+    assert_eq!(resp.vendor_id, 0x8086);
+    assert_eq!(resp.subcommand, 7);
+    assert_eq!(resp.payload, b"hello, vendor");
+}
+
+#[test]
+fn unknown_vendor() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        vendor_ids: vec![0x1414],
+        ..Default::default()
+    });
+
+    let arena = BumpArena::new([0; 64]);
+    let err = virt
+        .send_vendor(0x8086, 7, b"hello, vendor", &arena)
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.into_inner(), Error::UnsupportedCommand);
+}
@@ -10,6 +10,7 @@
 
 use libfuzzer_sys::fuzz_target;
 
+use manticore::io::CheckedRead;
 use manticore::mem::BumpArena;
 use manticore::protocol::Command;
 use manticore::protocol::wire::FromWire;
@@ -19,7 +20,7 @@ use manticore::protocol::cerberus::DeviceCapabilities as C;
 fuzz_target!(|data: &[u8]| {
     let mut arena = vec![0; data.len()];
     let arena = BumpArena::new(&mut arena);
-    let mut data = data;
+    let mut data = CheckedRead::new(data);
     let _ = <C as Command<'_>>::Resp::from_wire(&mut data, &arena);
 });
 
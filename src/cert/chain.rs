@@ -9,12 +9,22 @@ use core::num::NonZeroUsize;
 
 use arrayvec::ArrayVec;
 
+use crate::cert::x509;
 use crate::cert::Cert;
 use crate::cert::CertFormat;
 use crate::cert::Error;
 use crate::crypto::sig;
+use crate::protocol::Slot;
 use crate::Result;
 
+/// The default limit on the number of certificates in a single slot's chain.
+///
+/// This leaves room for a root, a few intermediates, and the DeviceID and
+/// Alias certificates that a Cerberus device typically presents.
+///
+/// See [`TrustChain::max_chain_len()`].
+pub const MAX_CERTS_PER_SLOT: usize = 6;
+
 /// A trust chain collection.
 ///
 /// A trust chain consists of a sequence of certificates, starting with a
@@ -28,13 +38,13 @@ pub trait TrustChain {
     /// Gets the length of the `slot`th chain.
     ///
     /// Cannot be zero; returns `None` if this chain has no such slot.
-    fn chain_len(&self, slot: u8) -> Option<NonZeroUsize>;
+    fn chain_len(&self, slot: Slot) -> Option<NonZeroUsize>;
 
     /// Gets the `index`th cert of the `slot`th chain.
     ///
     /// Returns `None` if `index` is out of bounds or if there is no `slot`th
     /// chain. These cases can be distinguished by calling `chain_len()`.
-    fn cert(&self, slot: u8, index: usize) -> Option<&Cert>;
+    fn cert(&self, slot: Slot, index: usize) -> Option<&Cert>;
 
     /// Gets the signer for the `slot`th chain.
     ///
@@ -42,14 +52,94 @@ pub trait TrustChain {
     /// custody of the private key corresponding to a leaf certificate.
     ///
     /// Returns `None` if no such chain is present.
-    fn signer(&mut self, slot: u8) -> Option<&mut dyn sig::Sign>;
+    fn signer(&mut self, slot: Slot) -> Option<&mut dyn sig::Sign>;
+
+    /// Gets the signer for the `slot`th chain's key of type `key_type`.
+    ///
+    /// A device may hold keys of several types for the same leaf, such as
+    /// both an RSA and an ECDSA key, and let requesters pick which one signs
+    /// a `Challenge`. Returns `None` if no such chain is present, or if it has
+    /// no key of that type. The default implementation always returns `None`.
+    fn signer_for(
+        &mut self,
+        slot: Slot,
+        key_type: sig::KeyType,
+    ) -> Option<sig::AnySigner<'_>> {
+        let _ = (slot, key_type);
+        None
+    }
+
+    /// Returns the largest number of certificates any one chain may have.
+    ///
+    /// This bounds iteration over a chain, so that a malformed collection, or
+    /// a request with a crafted certificate number, cannot drive an unbounded
+    /// loop. Defaults to [`MAX_CERTS_PER_SLOT`].
+    fn max_chain_len(&self) -> usize {
+        MAX_CERTS_PER_SLOT
+    }
+
+    /// Like `chain_len()`, but fails with [`Error::ChainTooLong`] if the
+    /// chain is longer than `max_chain_len()`.
+    fn bounded_chain_len(
+        &self,
+        slot: Slot,
+    ) -> Result<Option<NonZeroUsize>, Error> {
+        let len = self.chain_len(slot);
+        check!(
+            len.map_or(true, |len| len.get() <= self.max_chain_len()),
+            Error::ChainTooLong
+        );
+        Ok(len)
+    }
+
+    /// Like `cert()`, but fails with [`Error::ChainTooLong`] if `index` is
+    /// not below `max_chain_len()`, without consulting the chain at all.
+    fn bounded_cert(
+        &self,
+        slot: Slot,
+        index: usize,
+    ) -> Result<Option<&Cert>, Error> {
+        check!(index < self.max_chain_len(), Error::ChainTooLong);
+        Ok(self.cert(slot, index))
+    }
 }
 impl dyn TrustChain {} // Ensure object-safe.
 
+/// Splits `chain`, a concatenation of DER-encoded X.509 certificates such as
+/// the ones read out of a device with `GetCert`, into its certificates.
+///
+/// Certificates are not parsed: only their outer `SEQUENCE` headers are read,
+/// to find where each one ends, so each must still be checked with
+/// [`Cert::parse()`] or [`SimpleChain::parse()`]. If `chain` ends partway
+/// through a certificate, or has something other than a certificate in it,
+/// the iterator yields an error and then stops.
+pub fn split_der_chain(
+    chain: &[u8],
+) -> impl Iterator<Item = Result<&[u8], Error>> + '_ {
+    let mut rest = chain;
+    core::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        match x509::split_first(rest) {
+            Ok((cert, tail)) => {
+                rest = tail;
+                Some(Ok(cert))
+            }
+            Err(e) => {
+                rest = &[];
+                Some(Err(fail!(e)))
+            }
+        }
+    })
+}
+
 /// A simple trust chain with only one slot.
 pub struct SimpleChain<'cert, const LEN: usize> {
     chain: ArrayVec<Cert<'cert>, LEN>,
     signer: Option<&'cert mut dyn sig::Sign>,
+    rsa_signer: Option<&'cert mut dyn sig::Sign>,
+    ecdsa_signer: Option<&'cert mut dyn sig::Sign>,
 }
 
 impl<const LEN: usize> fmt::Debug for SimpleChain<'_, LEN> {
@@ -95,31 +185,72 @@ impl<'cert, const LEN: usize> SimpleChain<'cert, LEN> {
             chain.push(cert);
         }
 
-        Ok(Self { chain, signer })
+        Ok(Self {
+            chain,
+            signer,
+            rsa_signer: None,
+            ecdsa_signer: None,
+        })
+    }
+
+    /// Adds `signer` as this chain's signer for its type of key, replacing
+    /// any added before.
+    ///
+    /// See [`TrustChain::signer_for()`].
+    pub fn with_signer_for(mut self, signer: sig::AnySigner<'cert>) -> Self {
+        match signer {
+            sig::AnySigner::Rsa(s) => self.rsa_signer = Some(s),
+            sig::AnySigner::Ecdsa(s) => self.ecdsa_signer = Some(s),
+        }
+        self
     }
 }
 
 impl<const LEN: usize> TrustChain for SimpleChain<'_, LEN> {
-    fn cert(&self, slot: u8, index: usize) -> Option<&Cert> {
-        if slot != 0 {
+    fn cert(&self, slot: Slot, index: usize) -> Option<&Cert> {
+        if slot.get() != 0 {
             return None;
         }
         self.chain.get(index)
     }
 
-    fn chain_len(&self, slot: u8) -> Option<NonZeroUsize> {
-        if slot != 0 {
+    fn chain_len(&self, slot: Slot) -> Option<NonZeroUsize> {
+        if slot.get() != 0 {
             return None;
         }
         NonZeroUsize::new(self.chain.len())
     }
 
-    fn signer(&mut self, slot: u8) -> Option<&mut dyn sig::Sign> {
-        match (&mut self.signer, slot) {
+    fn signer(&mut self, slot: Slot) -> Option<&mut dyn sig::Sign> {
+        match (&mut self.signer, slot.get()) {
             (Some(signer), 0) => Some(*signer),
             _ => None,
         }
     }
+
+    fn signer_for(
+        &mut self,
+        slot: Slot,
+        key_type: sig::KeyType,
+    ) -> Option<sig::AnySigner<'_>> {
+        if slot.get() != 0 {
+            return None;
+        }
+        match key_type {
+            sig::KeyType::Rsa => self
+                .rsa_signer
+                .as_mut()
+                .map(|s| sig::AnySigner::Rsa(&mut **s)),
+            sig::KeyType::Ecdsa => self
+                .ecdsa_signer
+                .as_mut()
+                .map(|s| sig::AnySigner::Ecdsa(&mut **s)),
+        }
+    }
+
+    fn max_chain_len(&self) -> usize {
+        LEN
+    }
 }
 
 #[cfg(all(test, not(miri)))] // TODO(#103)
@@ -132,6 +263,10 @@ mod test {
     use crate::crypto::ring;
     use crate::crypto::sig::PublicKeyParams;
 
+    fn slot(n: u8) -> Slot {
+        Slot::new(n).unwrap()
+    }
+
     #[test]
     fn x509_chain_parse() {
         let chain = SimpleChain::<3>::parse(
@@ -142,12 +277,63 @@ mod test {
         )
         .unwrap();
 
-        assert_eq!(chain.cert(0, 2).unwrap().raw(), x509::CHAIN3);
-        assert_eq!(chain.chain_len(0), NonZeroUsize::new(3));
+        assert_eq!(chain.cert(slot(0), 2).unwrap().raw(), x509::CHAIN3);
+        assert_eq!(chain.chain_len(slot(0)), NonZeroUsize::new(3));
+
+        assert!(chain.cert(slot(0), 3).is_none());
+        assert!(chain.cert(slot(1), 0).is_none());
+        assert!(chain.chain_len(slot(2)).is_none());
+    }
+
+    #[test]
+    fn signer_for() {
+        let (_, mut rsa) = ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let mut chain = SimpleChain::<3>::parse(
+            &[x509::CHAIN1, x509::CHAIN2, x509::CHAIN3],
+            CertFormat::RiotX509,
+            &mut ring::sig::Ciphers::new(),
+            None,
+        )
+        .unwrap()
+        .with_signer_for(sig::AnySigner::Rsa(&mut rsa));
+
+        let signer = chain.signer_for(slot(0), sig::KeyType::Rsa).unwrap();
+        assert_eq!(signer.key_type(), sig::KeyType::Rsa);
+        assert!(chain.signer_for(slot(0), sig::KeyType::Ecdsa).is_none());
+        assert!(chain.signer_for(slot(1), sig::KeyType::Rsa).is_none());
+        assert!(chain.signer(slot(0)).is_none());
+    }
 
-        assert!(chain.cert(0, 3).is_none());
-        assert!(chain.cert(1, 0).is_none());
-        assert!(chain.chain_len(2).is_none());
+    #[test]
+    fn bounded() {
+        let chain = SimpleChain::<3>::parse(
+            &[x509::CHAIN1, x509::CHAIN2, x509::CHAIN3],
+            CertFormat::RiotX509,
+            &mut ring::sig::Ciphers::new(),
+            None,
+        )
+        .unwrap();
+        assert!(chain.bounded_cert(slot(0), 2).unwrap().is_some());
+        assert!(chain.bounded_cert(slot(0), 3).is_err());
+
+        struct Huge;
+        impl TrustChain for Huge {
+            fn chain_len(&self, _: Slot) -> Option<NonZeroUsize> {
+                NonZeroUsize::new(usize::MAX)
+            }
+            fn cert(&self, _: Slot, _: usize) -> Option<&Cert> {
+                None
+            }
+            fn signer(&mut self, _: Slot) -> Option<&mut dyn sig::Sign> {
+                None
+            }
+        }
+        assert!(Huge.bounded_chain_len(slot(0)).is_err());
+        assert!(Huge.bounded_cert(slot(0), MAX_CERTS_PER_SLOT).is_err());
+        assert!(Huge
+            .bounded_cert(slot(0), MAX_CERTS_PER_SLOT - 1)
+            .unwrap()
+            .is_none());
     }
 
     #[test]
@@ -217,12 +403,12 @@ mod test {
         )
         .unwrap();
 
-        assert_eq!(chain.cert(0, 2).unwrap().raw(), data[2]);
-        assert_eq!(chain.chain_len(0), NonZeroUsize::new(3));
+        assert_eq!(chain.cert(slot(0), 2).unwrap().raw(), data[2]);
+        assert_eq!(chain.chain_len(slot(0)), NonZeroUsize::new(3));
 
-        assert!(chain.cert(0, 3).is_none());
-        assert!(chain.cert(1, 0).is_none());
-        assert!(chain.chain_len(2).is_none());
+        assert!(chain.cert(slot(0), 3).is_none());
+        assert!(chain.cert(slot(1), 0).is_none());
+        assert!(chain.chain_len(slot(2)).is_none());
     }
 
     #[test]
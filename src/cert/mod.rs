@@ -22,6 +22,11 @@ mod x509;
 mod chain;
 pub use chain::*;
 
+#[cfg(feature = "std")]
+pub mod pem;
+#[cfg(feature = "std")]
+pub use pem::PemCertStore;
+
 /// A certificate format understood by Manticore.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -203,3 +208,14 @@ impl<'cert> Cert<'cert> {
 /// comparisons.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Name<'cert>(&'cert [u8]);
+
+impl<'cert> Name<'cert> {
+    /// Returns the raw bytes of this name.
+    ///
+    /// For an X.509 certificate, these are the DER-encoded contents of the
+    /// name's `RDNSequence`, without the enclosing tag and length. For a CWT,
+    /// they are the name's UTF-8 string.
+    pub fn as_bytes(&self) -> &'cert [u8] {
+        self.0
+    }
+}
@@ -0,0 +1,306 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! PEM certificate bundles.
+//!
+//! Certificates are usually handed around as PEM, while Manticore only
+//! understands DER. [`PemCertStore`] splits a bundle of PEM `CERTIFICATE`
+//! blocks, such as the ones `openssl` produces, into DER, so that tests can
+//! use PEM fixtures as-is.
+
+use std::fmt;
+
+use crate::protocol::Slot;
+use crate::protocol::SLOTS;
+use crate::Result;
+
+/// An error returned while parsing a PEM bundle.
+///
+/// Line numbers start at one.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Error {
+    /// A `BEGIN` line was not followed by a matching `END` line.
+    Unterminated {
+        /// The line of the `BEGIN`.
+        line: usize,
+    },
+    /// An `END` line appeared outside of a block.
+    UnexpectedEnd {
+        /// The line of the `END`.
+        line: usize,
+    },
+    /// A block was not labeled `CERTIFICATE`, or its `END` line had a
+    /// different label than its `BEGIN` line.
+    BadLabel {
+        /// The line of the offending label.
+        line: usize,
+    },
+    /// A block's contents were not valid base64.
+    BadBase64 {
+        /// The line of the block's `BEGIN`.
+        line: usize,
+    },
+    /// A block was empty.
+    Empty {
+        /// The line of the block's `BEGIN`.
+        line: usize,
+    },
+    /// The bundle held more certificates than there are [`SLOTS`].
+    TooManyCerts,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unterminated { line } => {
+                write!(f, "line {}: block has no END line", line)
+            }
+            Self::UnexpectedEnd { line } => {
+                write!(f, "line {}: END line outside of a block", line)
+            }
+            Self::BadLabel { line } => {
+                write!(f, "line {}: expected a CERTIFICATE label", line)
+            }
+            Self::BadBase64 { line } => {
+                write!(f, "line {}: block is not valid base64", line)
+            }
+            Self::Empty { line } => write!(f, "line {}: block is empty", line),
+            Self::TooManyCerts => {
+                write!(f, "more than {} certificates", SLOTS.end() + 1)
+            }
+        }
+    }
+}
+
+/// A collection of DER-encoded certificates, parsed out of a PEM bundle.
+///
+/// The `n`th certificate in the bundle is stored in the `n`th [`Slot`].
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct PemCertStore {
+    certs: Vec<Vec<u8>>,
+}
+
+impl PemCertStore {
+    /// Parses `bundle`, a sequence of PEM `CERTIFICATE` blocks.
+    ///
+    /// Text outside of blocks, such as the `subject=` lines that `openssl`
+    /// prints, is ignored.
+    pub fn from_pem(bundle: &str) -> Result<Self, Error> {
+        let mut certs = Vec::new();
+        let mut lines = bundle.lines().map(str::trim).zip(1..);
+        while let Some((text, line)) = lines.next() {
+            check!(
+                delimiter(text, "END").is_none(),
+                Error::UnexpectedEnd { line }
+            );
+            let label = match delimiter(text, "BEGIN") {
+                Some(label) => label,
+                None => continue,
+            };
+            check!(label == LABEL, Error::BadLabel { line });
+
+            let begin = line;
+            let mut base64 = String::new();
+            loop {
+                let (text, line) = lines.next().ok_or_else(|| {
+                    fail!(Error::Unterminated { line: begin })
+                })?;
+                if let Some(label) = delimiter(text, "END") {
+                    check!(label == LABEL, Error::BadLabel { line });
+                    break;
+                }
+                check!(
+                    delimiter(text, "BEGIN").is_none(),
+                    Error::Unterminated { line: begin }
+                );
+                base64.push_str(text);
+            }
+
+            let der = decode_base64(&base64)
+                .ok_or_else(|| fail!(Error::BadBase64 { line: begin }))?;
+            check!(!der.is_empty(), Error::Empty { line: begin });
+            check!(certs.len() <= *SLOTS.end() as usize, Error::TooManyCerts);
+            certs.push(der);
+        }
+        Ok(Self { certs })
+    }
+
+    /// Returns the certificate in `slot`, if there is one.
+    pub fn cert(&self, slot: Slot) -> Option<&[u8]> {
+        self.certs.get(slot.get() as usize).map(Vec::as_slice)
+    }
+
+    /// Returns the number of certificates in this store.
+    pub fn len(&self) -> usize {
+        self.certs.len()
+    }
+
+    /// Returns whether this store has no certificates.
+    pub fn is_empty(&self) -> bool {
+        self.certs.is_empty()
+    }
+
+    /// Returns an iterator over the certificates in this store, in slot
+    /// order.
+    pub fn iter(&self) -> impl Iterator<Item = (Slot, &[u8])> + '_ {
+        self.certs.iter().zip(SLOTS).map(|(cert, slot)| {
+            // `from_pem()` never stores more certs than there are slots.
+            (Slot::new(slot).unwrap(), cert.as_slice())
+        })
+    }
+
+    /// Consumes this store, returning its certificates in slot order.
+    pub fn into_der(self) -> Vec<Vec<u8>> {
+        self.certs
+    }
+}
+
+/// The only PEM label that a [`PemCertStore`] accepts.
+const LABEL: &str = "CERTIFICATE";
+
+/// If `line` is a `-----<kind> <label>-----` line, returns the label.
+fn delimiter<'a>(line: &'a str, kind: &str) -> Option<&'a str> {
+    line.strip_prefix("-----")?
+        .strip_prefix(kind)?
+        .strip_prefix(' ')?
+        .strip_suffix("-----")
+}
+
+/// Decodes standard, padded base64, ignoring whitespace.
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut digits = 0;
+    let mut padding = 0;
+    for c in text.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => {
+                padding += 1;
+                continue;
+            }
+            _ => return None,
+        };
+        if padding != 0 {
+            return None;
+        }
+        digits += 1;
+        acc = (acc << 6 | value as u32) & 0xffff;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+
+    // Every four-digit group must be complete, and a trailing partial group
+    // of two or three digits must be padded out; a single digit cannot encode
+    // a whole byte.
+    let expected_padding = (4 - digits % 4) % 4;
+    if digits % 4 == 1 || padding != expected_padding {
+        return None;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const BUNDLE: &str = "\
+subject=CN = root
+-----BEGIN CERTIFICATE-----
+AAECAwQF
+BgcI
+-----END CERTIFICATE-----
+
+subject=CN = leaf
+-----BEGIN CERTIFICATE-----
+3q2+7w==
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn bundle() {
+        let store = PemCertStore::from_pem(BUNDLE).unwrap();
+        assert_eq!(store.len(), 2);
+        assert_eq!(
+            store.cert(Slot::FIRST),
+            Some(&[0, 1, 2, 3, 4, 5, 6, 7, 8][..])
+        );
+        assert_eq!(
+            store.cert(Slot::new(1).unwrap()),
+            Some(&[0xde, 0xad, 0xbe, 0xef][..])
+        );
+        assert_eq!(store.cert(Slot::new(2).unwrap()), None);
+
+        let slots =
+            store.iter().map(|(slot, _)| slot.get()).collect::<Vec<_>>();
+        assert_eq!(slots, vec![0, 1]);
+    }
+
+    #[test]
+    fn empty_bundle() {
+        let store = PemCertStore::from_pem("no certs here\n").unwrap();
+        assert!(store.is_empty());
+    }
+
+    /// Builds a PEM block with the given labels and contents.
+    fn block(begin: &str, body: &str, end: &str) -> String {
+        format!(
+            "-----BEGIN {}-----\n{}\n-----END {}-----\n",
+            begin, body, end
+        )
+    }
+
+    #[test]
+    fn malformed() {
+        let cert = |body| block(LABEL, body, LABEL);
+        let cases = vec![
+            (
+                "-----BEGIN CERTIFICATE-----\nAAAA\n".to_string(),
+                Error::Unterminated { line: 1 },
+            ),
+            (
+                format!("-----BEGIN CERTIFICATE-----\n{}", cert("AAAA")),
+                Error::Unterminated { line: 1 },
+            ),
+            (
+                "\n-----END CERTIFICATE-----\n".to_string(),
+                Error::UnexpectedEnd { line: 2 },
+            ),
+            (
+                block("PRIVATE KEY", "AAAA", "PRIVATE KEY"),
+                Error::BadLabel { line: 1 },
+            ),
+            (
+                block(LABEL, "AAAA", "X509 CRL"),
+                Error::BadLabel { line: 3 },
+            ),
+            (cert("AA*A"), Error::BadBase64 { line: 1 }),
+            (cert("AAA"), Error::BadBase64 { line: 1 }),
+            (cert("AA==AA=="), Error::BadBase64 { line: 1 }),
+            (cert(""), Error::Empty { line: 1 }),
+        ];
+        for (bundle, error) in cases {
+            let err = PemCertStore::from_pem(&bundle).unwrap_err();
+            assert_eq!(err.into_inner(), error, "{:?}", bundle);
+        }
+    }
+
+    #[test]
+    fn too_many_certs() {
+        let cert = block(LABEL, "AAAA", LABEL);
+        let full = cert.repeat(SLOTS.count());
+        assert_eq!(PemCertStore::from_pem(&full).unwrap().len(), 8);
+
+        let err = PemCertStore::from_pem(&(full + &cert)).unwrap_err();
+        assert_eq!(err.into_inner(), Error::TooManyCerts);
+    }
+}
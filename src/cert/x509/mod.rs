@@ -41,6 +41,20 @@ fn parse_algo(buf: &mut untrusted::Reader) -> Result<sig::Algo, Error> {
     }
 }
 
+/// Splits the first certificate off of `chain`, a concatenation of DER
+/// certificates, returning it along with the rest of `chain`.
+///
+/// Only the certificate's outer `SEQUENCE` header is parsed.
+pub fn split_first(chain: &[u8]) -> Result<(&[u8], &[u8]), Error> {
+    let mut buf = untrusted::Reader::new(untrusted::Input::from(chain));
+    let (tag, _) = der::any(&mut buf)?;
+    if tag != Tag::SEQUENCE {
+        return Err(Error::BadEncoding);
+    }
+    let rest = buf.read_bytes_to_end().as_slice_less_safe();
+    Ok(chain.split_at(chain.len() - rest.len()))
+}
+
 /// Parses an X.509 certificate.
 ///
 /// This function performs several aggressive checks to reject any and all
@@ -9,6 +9,7 @@
 use testutil::data;
 use testutil::data::keys;
 
+use crate::cert;
 use crate::cert::Cert;
 use crate::cert::CertFormat;
 use crate::crypto::ring;
@@ -52,6 +53,32 @@ fn explicit_key() {
     assert!(!cert.is_within_path_len_constraint(2));
 }
 
+#[test]
+fn split_chain() {
+    let mut chain = data::x509::CHAIN1.to_vec();
+    chain.extend_from_slice(data::x509::CHAIN2);
+    let certs = cert::split_der_chain(&chain)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(certs, vec![data::x509::CHAIN1, data::x509::CHAIN2]);
+
+    // A chain that stops partway through its second cert.
+    let mut certs = cert::split_der_chain(&chain[..chain.len() - 1]);
+    assert_eq!(certs.next().unwrap().unwrap(), data::x509::CHAIN1);
+    assert!(certs.next().unwrap().is_err());
+    assert!(certs.next().is_none());
+
+    // Not a cert at all.
+    let mut certs = cert::split_der_chain(&[0x04, 0x01, 0x00]);
+    assert!(matches!(
+        certs.next().unwrap().unwrap_err().into_inner(),
+        cert::Error::BadEncoding
+    ));
+    assert!(certs.next().is_none());
+
+    assert!(cert::split_der_chain(&[]).next().is_none());
+}
+
 macro_rules! table_test {
     ([$pred:ident] $($test:ident:$data:ident,)*) => {$(
         #[test]
@@ -11,6 +11,9 @@ use crate::Result;
 pub enum Error {
     /// Indicates an unspecified, internal error.
     Unspecified,
+    /// Indicates that the generator failed a health test, and its output must
+    /// not be trusted.
+    Unhealthy,
 }
 
 debug_from!(Error);
@@ -25,5 +28,20 @@ debug_from!(Error);
 pub trait Csrng {
     /// Fills `buf` with random bytes.
     fn fill(&mut self, buf: &mut [u8]) -> Result<(), Error>;
+
+    /// Runs the generator's health tests.
+    ///
+    /// Hardware entropy sources can degrade or fail outright; this function
+    /// gives them a chance to say so before their output is used. It should
+    /// return [`Error::Unhealthy`] if the generator is not fit for use, in
+    /// which case callers must fail closed rather than fall back to weaker
+    /// randomness.
+    ///
+    /// The default implementation always succeeds, which is appropriate for
+    /// generators that are not backed by hardware of their own, such as the
+    /// operating system's.
+    fn health_check(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
 }
 impl dyn Csrng {} // Ensure object-safe.
@@ -65,6 +65,10 @@ pub enum Error {
     /// operation was requested.
     Idle,
 
+    /// Indicates that the underlying hardware is in use by another
+    /// operation, and that the caller should try again later.
+    Busy,
+
     /// Indicates an unspecified, internal error.
     Unspecified,
 }
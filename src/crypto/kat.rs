@@ -0,0 +1,393 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Known-answer tests for signature verifiers.
+//!
+//! This module provides a table of known-answer tests ("KATs") for each
+//! signature algorithm Manticore uses, along with [`run_kats()`] for checking
+//! a [`sig::Ciphers`] against them. Integrators providing their own
+//! cryptography can use this to gain some confidence that it is
+//! spec-compliant.
+//!
+//! Requires the `kat` feature flag to be enabled.
+
+use crate::crypto::sig;
+use crate::crypto::sig::Algo;
+use crate::crypto::sig::PublicKeyParams;
+use crate::Result;
+
+/// A single known-answer test for a signature verifier.
+#[derive(Clone, Debug)]
+pub struct Kat {
+    /// A short description of this test.
+    pub name: &'static str,
+    /// The algorithm to verify with.
+    pub algo: Algo,
+    /// The public key to verify with.
+    pub key: PublicKeyParams<'static>,
+    /// The signed message.
+    pub message: &'static [u8],
+    /// The signature over `message`.
+    pub signature: &'static [u8],
+    /// Whether a correct verifier accepts `signature`.
+    pub valid: bool,
+}
+
+/// A failed known-answer test.
+#[derive(Copy, Clone, Debug)]
+pub struct Failure {
+    /// The test that failed.
+    pub kat: &'static Kat,
+    /// Whether the verifier accepted the signature; a test fails if this
+    /// differs from [`Kat::valid`].
+    pub accepted: bool,
+}
+
+debug_from!(Failure);
+
+/// Runs every test in `kats` against `ciphers`, stopping at the first
+/// failure.
+///
+/// A signature counts as rejected if `ciphers` refuses to provide a verifier
+/// for its key, or if verification returns any error. Verifiers that may
+/// return [`sig::Error::Busy`] should retry internally.
+pub fn run_kats(
+    ciphers: &mut dyn sig::Ciphers,
+    kats: &'static [Kat],
+) -> Result<(), Failure> {
+    for kat in kats {
+        let accepted = match ciphers.verifier(kat.algo, &kat.key) {
+            Some(verifier) => {
+                verifier.verify(&[kat.message], kat.signature).is_ok()
+            }
+            None => false,
+        };
+        if accepted != kat.valid {
+            return Err(fail!(Failure { kat, accepted }));
+        }
+    }
+    Ok(())
+}
+
+/// Known-answer tests for [`Algo::RsaPkcs1Sha256`].
+///
+/// These are drawn from NIST's FIPS 186-3 `SigVer15` vectors, as well as
+/// *ring*'s edge cases for RSA public exponents. At least one test in this
+/// table has a signature that must be rejected.
+pub const RSA_PKCS1_SHA256: &[Kat] = &[
+    Kat {
+        name: "e = 3",
+        algo: Algo::RsaPkcs1Sha256,
+        key: PublicKeyParams::Rsa {
+            modulus: EDGE_MODULUS,
+            exponent: &[0x03],
+        },
+        message: b"",
+        signature: EDGE_SIG,
+        valid: true,
+    },
+    Kat {
+        name: "e = 1, trivially forged",
+        algo: Algo::RsaPkcs1Sha256,
+        key: PublicKeyParams::Rsa {
+            modulus: EDGE_MODULUS,
+            exponent: &[0x01],
+        },
+        message: b"",
+        signature: EDGE_FORGED_SIG,
+        valid: false,
+    },
+    Kat {
+        name: "e = 3, signature too short",
+        algo: Algo::RsaPkcs1Sha256,
+        key: PublicKeyParams::Rsa {
+            modulus: EDGE_MODULUS,
+            exponent: &[0x03],
+        },
+        message: b"",
+        signature: &[0; 255],
+        valid: false,
+    },
+    Kat {
+        name: "NIST SigVer15, valid",
+        algo: Algo::RsaPkcs1Sha256,
+        key: PublicKeyParams::Rsa {
+            modulus: NIST_MODULUS,
+            exponent: &[0x49, 0xd2, 0xa1],
+        },
+        message: NIST_MSG,
+        signature: NIST_SIG,
+        valid: true,
+    },
+    Kat {
+        name: "NIST SigVer15, wrong message",
+        algo: Algo::RsaPkcs1Sha256,
+        key: PublicKeyParams::Rsa {
+            modulus: NIST_MODULUS,
+            exponent: &[0x49, 0xd2, 0xa1],
+        },
+        message: NIST_BAD_MSG,
+        signature: NIST_BAD_SIG,
+        valid: false,
+    },
+    Kat {
+        name: "NIST SigVer15, wrong exponent",
+        algo: Algo::RsaPkcs1Sha256,
+        key: PublicKeyParams::Rsa {
+            modulus: NIST_MODULUS,
+            exponent: &[0x07, 0x48, 0x5b],
+        },
+        message: NIST_OTHER_E_MSG,
+        signature: NIST_OTHER_E_SIG,
+        valid: false,
+    },
+];
+
+/// A 2048-bit modulus from *ring*'s edge-case vectors.
+const EDGE_MODULUS: &[u8] = &[
+    0xbe, 0x5b, 0x56, 0x13, 0xb7, 0x87, 0x2a, 0x0c, 0xb3, 0xe0, 0xfc, 0x39,
+    0x08, 0xd0, 0xec, 0xa9, 0xfc, 0x9c, 0x37, 0x88, 0x7d, 0x62, 0x62, 0xd3,
+    0x15, 0xc7, 0x42, 0x5f, 0x30, 0x0c, 0x56, 0x41, 0x3c, 0x18, 0xe0, 0x33,
+    0xf2, 0x29, 0x56, 0x71, 0xbb, 0x84, 0x35, 0x25, 0x89, 0x8d, 0x32, 0x17,
+    0x7e, 0xdd, 0x20, 0x65, 0x43, 0xd4, 0xfb, 0x68, 0xed, 0x72, 0x6c, 0xe7,
+    0xc3, 0xed, 0x26, 0xdb, 0xb7, 0xab, 0x39, 0xca, 0xed, 0xa2, 0xfa, 0x51,
+    0xf1, 0xf1, 0x68, 0xb1, 0x0a, 0xe7, 0x62, 0x7d, 0x25, 0xcf, 0xa5, 0x16,
+    0x60, 0xe7, 0xd5, 0x99, 0xc9, 0xf2, 0xe1, 0x68, 0x3d, 0x18, 0x43, 0x88,
+    0xca, 0xf5, 0x5c, 0x20, 0xc1, 0xa9, 0xf6, 0x91, 0xec, 0x32, 0x5f, 0x7a,
+    0x5f, 0x89, 0xbc, 0x3e, 0x9c, 0x8a, 0x5a, 0x86, 0x20, 0xab, 0xb7, 0x30,
+    0x70, 0x22, 0x6f, 0xfd, 0x24, 0xfc, 0xa5, 0x70, 0xc6, 0xc0, 0x02, 0xf4,
+    0xde, 0xff, 0xa8, 0x0e, 0x16, 0x20, 0xb1, 0x31, 0xef, 0xb1, 0x95, 0x09,
+    0x41, 0x20, 0xab, 0x9a, 0x51, 0x8f, 0x0d, 0xc9, 0x74, 0xad, 0x4e, 0xf6,
+    0x3e, 0x32, 0xea, 0x5b, 0x1f, 0x95, 0x03, 0x0d, 0x04, 0xe2, 0x43, 0xa9,
+    0x86, 0xbf, 0xf7, 0xbe, 0x7b, 0xf3, 0xe0, 0xc8, 0xd2, 0xf0, 0x3b, 0x86,
+    0x88, 0x44, 0xbe, 0xb5, 0xfd, 0x04, 0x0d, 0xb4, 0x29, 0x67, 0xd2, 0xc2,
+    0xad, 0x87, 0x38, 0x67, 0xd9, 0xfd, 0x6d, 0x26, 0xf1, 0x29, 0xb7, 0xca,
+    0x3a, 0xfc, 0x4e, 0xee, 0xe7, 0x83, 0x12, 0x7d, 0xd5, 0xa2, 0xf3, 0x82,
+    0x8e, 0xc4, 0xf1, 0x23, 0xde, 0x16, 0x87, 0x19, 0x27, 0x96, 0x3e, 0x2b,
+    0x56, 0x26, 0x11, 0x4f, 0xe9, 0x89, 0x4e, 0x1c, 0xe6, 0x36, 0x5c, 0x80,
+    0xd7, 0xe2, 0xec, 0x7b, 0x50, 0xee, 0x0d, 0x04, 0x95, 0x2f, 0x05, 0x5b,
+    0x38, 0x31, 0xe1, 0xfb,
+];
+
+/// A signature over the empty message under [`EDGE_MODULUS`] and `e = 3`.
+const EDGE_SIG: &[u8] = &[
+    0x21, 0xf1, 0xd7, 0x8d, 0x3d, 0x3d, 0x02, 0xea, 0xa3, 0x08, 0x86, 0x35,
+    0x6f, 0x49, 0x4f, 0xd4, 0xce, 0x06, 0x0e, 0x12, 0xcf, 0x4a, 0x80, 0x8c,
+    0x3a, 0x4a, 0x80, 0x11, 0x16, 0x4f, 0x94, 0x8a, 0x1b, 0x15, 0x41, 0x4f,
+    0x6f, 0xe0, 0xba, 0xfb, 0x17, 0x44, 0x68, 0xe6, 0x32, 0xb7, 0xb8, 0x43,
+    0x05, 0x9d, 0xca, 0xa2, 0xd9, 0x15, 0x02, 0x8f, 0xc7, 0x9e, 0x32, 0x52,
+    0xb7, 0x90, 0x60, 0x80, 0x13, 0x73, 0xa1, 0x6b, 0x6d, 0x26, 0xe5, 0xbc,
+    0x39, 0x9d, 0x66, 0x20, 0x13, 0x57, 0xf2, 0x84, 0xac, 0x82, 0x25, 0x44,
+    0x17, 0x6f, 0x5d, 0x7b, 0xbc, 0xc4, 0x02, 0xbe, 0x53, 0x35, 0xd2, 0x21,
+    0xde, 0xeb, 0x2c, 0x44, 0xba, 0x1c, 0xc9, 0x08, 0xe1, 0x03, 0x40, 0x05,
+    0x6b, 0x52, 0x1a, 0x0f, 0x96, 0x5b, 0x37, 0x3c, 0xc0, 0x42, 0xdd, 0x2b,
+    0x3e, 0x3b, 0xef, 0x68, 0x21, 0x3c, 0xd5, 0x26, 0xc7, 0x8f, 0x0a, 0x52,
+    0x37, 0xa7, 0xc1, 0xaa, 0x74, 0x98, 0x9d, 0x86, 0x42, 0x0d, 0x0a, 0x0c,
+    0xb6, 0x39, 0x36, 0x2e, 0x2f, 0x49, 0x18, 0x2e, 0x1c, 0x4c, 0x60, 0x3e,
+    0x7d, 0xc1, 0xb4, 0x94, 0xef, 0xbc, 0x58, 0x1c, 0xae, 0xbb, 0x03, 0xfb,
+    0x63, 0x37, 0x20, 0xb6, 0x43, 0xe7, 0x2d, 0x52, 0x4c, 0x84, 0x03, 0xe5,
+    0x00, 0xf5, 0xa7, 0xbe, 0x5c, 0xaa, 0xdf, 0x5e, 0xd0, 0xa9, 0x65, 0x3a,
+    0x80, 0x5d, 0xf9, 0x17, 0xd0, 0xda, 0xfc, 0x4e, 0x58, 0x9e, 0x26, 0x37,
+    0x3a, 0x84, 0x97, 0x0c, 0xe6, 0x90, 0x53, 0x59, 0xae, 0x98, 0x1b, 0xd3,
+    0xd5, 0x66, 0x99, 0x13, 0x2b, 0xe0, 0x4d, 0xd3, 0xce, 0x02, 0xbd, 0xaa,
+    0x39, 0x97, 0x37, 0xef, 0x79, 0xc2, 0xf3, 0x35, 0x2f, 0x4f, 0x4a, 0xb8,
+    0xac, 0x1f, 0x09, 0x54, 0x11, 0x1d, 0xfd, 0xd7, 0xfe, 0xe5, 0x65, 0x5a,
+    0xa4, 0x08, 0x77, 0x4e,
+];
+
+/// The PKCS#1 v1.5 padding of the SHA-256 digest of the empty message,
+/// which is a "valid" signature under `e = 1`.
+const EDGE_FORGED_SIG: &[u8] = &[
+    0x00, 0x01, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x00, 0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65,
+    0x03, 0x04, 0x02, 0x01, 0x05, 0x00, 0x04, 0x20, 0xe3, 0xb0, 0xc4, 0x42,
+    0x98, 0xfc, 0x1c, 0x14, 0x9a, 0xfb, 0xf4, 0xc8, 0x99, 0x6f, 0xb9, 0x24,
+    0x27, 0xae, 0x41, 0xe4, 0x64, 0x9b, 0x93, 0x4c, 0xa4, 0x95, 0x99, 0x1b,
+    0x78, 0x52, 0xb8, 0x55,
+];
+
+/// A 2048-bit modulus from NIST's `SigVer15_186-3.rsp`.
+const NIST_MODULUS: &[u8] = &[
+    0xc4, 0x7a, 0xba, 0xcc, 0x2a, 0x84, 0xd5, 0x6f, 0x36, 0x14, 0xd9, 0x2f,
+    0xd6, 0x2e, 0xd3, 0x6d, 0xdd, 0xe4, 0x59, 0x66, 0x4b, 0x93, 0x01, 0xdc,
+    0xd1, 0xd6, 0x17, 0x81, 0xcf, 0xcc, 0x02, 0x6b, 0xcb, 0x23, 0x99, 0xbe,
+    0xe7, 0xe7, 0x56, 0x81, 0xa8, 0x0b, 0x7b, 0xf5, 0x00, 0xe2, 0xd0, 0x8c,
+    0xea, 0xe1, 0xc4, 0x2e, 0xc0, 0xb7, 0x07, 0x92, 0x7f, 0x2b, 0x2f, 0xe9,
+    0x2a, 0xe8, 0x52, 0x08, 0x7d, 0x25, 0xf1, 0xd2, 0x60, 0xcc, 0x74, 0x90,
+    0x5e, 0xe5, 0xf9, 0xb2, 0x54, 0xed, 0x05, 0x49, 0x4a, 0x9f, 0xe0, 0x67,
+    0x32, 0xc3, 0x68, 0x09, 0x92, 0xdd, 0x6f, 0x0d, 0xc6, 0x34, 0x56, 0x8d,
+    0x11, 0x54, 0x2a, 0x70, 0x5f, 0x83, 0xae, 0x96, 0xd2, 0xa4, 0x97, 0x63,
+    0xd5, 0xfb, 0xb2, 0x43, 0x98, 0xed, 0xf3, 0x70, 0x2b, 0xc9, 0x4b, 0xc1,
+    0x68, 0x19, 0x01, 0x66, 0x49, 0x2b, 0x86, 0x71, 0xde, 0x87, 0x4b, 0xb9,
+    0xce, 0xcb, 0x05, 0x8c, 0x6c, 0x83, 0x44, 0xaa, 0x8c, 0x93, 0x75, 0x4d,
+    0x6e, 0xff, 0xcd, 0x44, 0xa4, 0x1e, 0xd7, 0xde, 0x0a, 0x9d, 0xcd, 0x91,
+    0x44, 0x43, 0x7f, 0x21, 0x2b, 0x18, 0x88, 0x1d, 0x04, 0x2d, 0x33, 0x1a,
+    0x46, 0x18, 0xa9, 0xe6, 0x30, 0xef, 0x9b, 0xb6, 0x63, 0x05, 0xe4, 0xfd,
+    0xf8, 0xf0, 0x39, 0x1b, 0x3b, 0x23, 0x13, 0xfe, 0x54, 0x9f, 0x01, 0x89,
+    0xff, 0x96, 0x8b, 0x92, 0xf3, 0x3c, 0x26, 0x6a, 0x4b, 0xc2, 0xcf, 0xfc,
+    0x89, 0x7d, 0x19, 0x37, 0xee, 0xb9, 0xe4, 0x06, 0xf5, 0xd0, 0xea, 0xa7,
+    0xa1, 0x47, 0x82, 0xe7, 0x6a, 0xf3, 0xfc, 0xe9, 0x8f, 0x54, 0xed, 0x23,
+    0x7b, 0x4a, 0x04, 0xa4, 0x15, 0x9a, 0x5f, 0x62, 0x50, 0xa2, 0x96, 0xa9,
+    0x02, 0x88, 0x02, 0x04, 0xe6, 0x1d, 0x89, 0x1c, 0x4d, 0xa2, 0x9f, 0x2d,
+    0x65, 0xf3, 0x4c, 0xbb,
+];
+
+/// A message, and its signature, under [`NIST_MODULUS`] and `e = 0x49d2a1`.
+const NIST_MSG: &[u8] = &[
+    0x95, 0x12, 0x3c, 0x8d, 0x1b, 0x23, 0x65, 0x40, 0xb8, 0x69, 0x76, 0xa1,
+    0x1c, 0xea, 0x31, 0xf8, 0xbd, 0x4e, 0x6c, 0x54, 0xc2, 0x35, 0x14, 0x7d,
+    0x20, 0xce, 0x72, 0x2b, 0x03, 0xa6, 0xad, 0x75, 0x6f, 0xbd, 0x91, 0x8c,
+    0x27, 0xdf, 0x8e, 0xa9, 0xce, 0x31, 0x04, 0x44, 0x4c, 0x0b, 0xbe, 0x87,
+    0x73, 0x05, 0xbc, 0x02, 0xe3, 0x55, 0x35, 0xa0, 0x2a, 0x58, 0xdc, 0xda,
+    0x30, 0x6e, 0x63, 0x2a, 0xd3, 0x0b, 0x3d, 0xc3, 0xce, 0x0b, 0xa9, 0x7f,
+    0xdf, 0x46, 0xec, 0x19, 0x29, 0x65, 0xdd, 0x9c, 0xd7, 0xf4, 0xa7, 0x1b,
+    0x02, 0xb8, 0xcb, 0xa3, 0xd4, 0x42, 0x64, 0x6e, 0xee, 0xc4, 0xaf, 0x59,
+    0x08, 0x24, 0xca, 0x98, 0xd7, 0x4f, 0xbc, 0xa9, 0x34, 0xd0, 0xb6, 0x86,
+    0x7a, 0xa1, 0x99, 0x1f, 0x30, 0x40, 0xb7, 0x07, 0xe8, 0x06, 0xde, 0x6e,
+    0x66, 0xb5, 0x93, 0x4f, 0x05, 0x50, 0x9b, 0xea,
+];
+
+const NIST_SIG: &[u8] = &[
+    0x51, 0x26, 0x5d, 0x96, 0xf1, 0x1a, 0xb3, 0x38, 0x76, 0x28, 0x91, 0xcb,
+    0x29, 0xbf, 0x3f, 0x1d, 0x2b, 0x33, 0x05, 0x10, 0x70, 0x63, 0xf5, 0xf3,
+    0x24, 0x5a, 0xf3, 0x76, 0xdf, 0xcc, 0x70, 0x27, 0xd3, 0x93, 0x65, 0xde,
+    0x70, 0xa3, 0x1d, 0xb0, 0x5e, 0x9e, 0x10, 0xeb, 0x61, 0x48, 0xcb, 0x7f,
+    0x64, 0x25, 0xf0, 0xc9, 0x3c, 0x4f, 0xb0, 0xe2, 0x29, 0x1a, 0xdb, 0xd2,
+    0x2c, 0x77, 0x65, 0x6a, 0xfc, 0x19, 0x68, 0x58, 0xa1, 0x1e, 0x1c, 0x67,
+    0x0d, 0x9e, 0xeb, 0x59, 0x26, 0x13, 0xe6, 0x9e, 0xb4, 0xf3, 0xaa, 0x50,
+    0x17, 0x30, 0x74, 0x3a, 0xc4, 0x46, 0x44, 0x86, 0xc7, 0xae, 0x68, 0xfd,
+    0x50, 0x9e, 0x89, 0x6f, 0x63, 0x88, 0x4e, 0x94, 0x24, 0xf6, 0x9c, 0x1c,
+    0x53, 0x97, 0x95, 0x9f, 0x1e, 0x52, 0xa3, 0x68, 0x66, 0x7a, 0x59, 0x8a,
+    0x1f, 0xc9, 0x01, 0x25, 0x27, 0x3d, 0x93, 0x41, 0x29, 0x5d, 0x2f, 0x8e,
+    0x1c, 0xc4, 0x96, 0x9b, 0xf2, 0x28, 0xc8, 0x60, 0xe0, 0x7a, 0x35, 0x46,
+    0xbe, 0x2e, 0xed, 0xa1, 0xcd, 0xe4, 0x8e, 0xe9, 0x4d, 0x06, 0x28, 0x01,
+    0xfe, 0x66, 0x6e, 0x4a, 0x7a, 0xe8, 0xcb, 0x9c, 0xd7, 0x92, 0x62, 0xc0,
+    0x17, 0xb0, 0x81, 0xaf, 0x87, 0x4f, 0xf0, 0x04, 0x53, 0xca, 0x43, 0xe3,
+    0x4e, 0xfd, 0xb4, 0x3f, 0xff, 0xb0, 0xbb, 0x42, 0xa4, 0xe2, 0xd3, 0x2a,
+    0x5e, 0x5c, 0xc9, 0xe8, 0x54, 0x6a, 0x22, 0x1f, 0xe9, 0x30, 0x25, 0x0e,
+    0x5f, 0x53, 0x33, 0xe0, 0xef, 0xe5, 0x8f, 0xfe, 0xbf, 0x19, 0x36, 0x9a,
+    0x3b, 0x8a, 0xe5, 0xa6, 0x7f, 0x6a, 0x04, 0x8b, 0xc9, 0xef, 0x91, 0x5b,
+    0xda, 0x25, 0x16, 0x07, 0x29, 0xb5, 0x08, 0x66, 0x7a, 0xda, 0x84, 0xa0,
+    0xc2, 0x7e, 0x7e, 0x26, 0xcf, 0x2a, 0xbc, 0xa4, 0x13, 0xe5, 0xe4, 0x69,
+    0x3f, 0x4a, 0x94, 0x05,
+];
+
+/// A message, and a signature over a different message.
+const NIST_BAD_MSG: &[u8] = &[
+    0xf8, 0x9f, 0xd2, 0xf6, 0xc4, 0x5a, 0x8b, 0x50, 0x66, 0xa6, 0x51, 0x41,
+    0x0b, 0x8e, 0x53, 0x4b, 0xfe, 0xc0, 0xd9, 0xa3, 0x6f, 0x3e, 0x2b, 0x88,
+    0x74, 0x57, 0xaf, 0xd4, 0x4d, 0xd6, 0x51, 0xd1, 0xec, 0x79, 0x27, 0x4d,
+    0xb5, 0xa4, 0x55, 0xf1, 0x82, 0x57, 0x2f, 0xce, 0xea, 0x5e, 0x9e, 0x39,
+    0xc3, 0xc7, 0xc5, 0xd9, 0xe5, 0x99, 0xe4, 0xfe, 0x31, 0xc3, 0x7c, 0x34,
+    0xd2, 0x53, 0xb4, 0x19, 0xc3, 0xe8, 0xfb, 0x6b, 0x91, 0x6a, 0xef, 0x65,
+    0x63, 0xf8, 0x7d, 0x4c, 0x37, 0x22, 0x4a, 0x45, 0x6e, 0x59, 0x52, 0x69,
+    0x8b, 0xa3, 0xd0, 0x1b, 0x38, 0x94, 0x5d, 0x99, 0x8a, 0x79, 0x5b, 0xd2,
+    0x85, 0xd6, 0x94, 0x78, 0xe3, 0x13, 0x1f, 0x55, 0x11, 0x72, 0x84, 0xe2,
+    0x7b, 0x44, 0x1f, 0x16, 0x09, 0x5d, 0xca, 0x7c, 0xe9, 0xc5, 0xb6, 0x88,
+    0x90, 0xb0, 0x9a, 0x2b, 0xfb, 0xb0, 0x10, 0xa5,
+];
+
+const NIST_BAD_SIG: &[u8] = &[
+    0xba, 0x48, 0x53, 0x87, 0x08, 0x51, 0x2d, 0x45, 0xc0, 0xed, 0xca, 0xc5,
+    0x7a, 0x9b, 0x4f, 0xb6, 0x37, 0xe9, 0x72, 0x1f, 0x72, 0x00, 0x3c, 0x60,
+    0xf1, 0x3f, 0x5c, 0x9a, 0x36, 0xc9, 0x68, 0xce, 0xf9, 0xbe, 0x8f, 0x54,
+    0x66, 0x54, 0x18, 0x14, 0x1c, 0x3d, 0x9e, 0xcc, 0x02, 0xa5, 0xbf, 0x95,
+    0x2c, 0xfc, 0x05, 0x5f, 0xb5, 0x1e, 0x18, 0x70, 0x5e, 0x9d, 0x88, 0x50,
+    0xf4, 0xe1, 0xf5, 0xa3, 0x44, 0xaf, 0x55, 0x0d, 0xe8, 0x4f, 0xfd, 0x08,
+    0x05, 0xe2, 0x7e, 0x55, 0x7f, 0x6a, 0xa5, 0x0d, 0x26, 0x45, 0x31, 0x4c,
+    0x64, 0xc1, 0xc7, 0x1a, 0xa6, 0xbb, 0x44, 0xfa, 0xf8, 0xf2, 0x9c, 0xa6,
+    0x57, 0x8e, 0x24, 0x41, 0xd4, 0x51, 0x0e, 0x36, 0x05, 0x2f, 0x46, 0x55,
+    0x1d, 0xf3, 0x41, 0xb2, 0xdc, 0xf4, 0x3f, 0x76, 0x1f, 0x08, 0xb9, 0x46,
+    0xca, 0x0b, 0x70, 0x81, 0xda, 0xdb, 0xb8, 0x8e, 0x95, 0x5e, 0x82, 0x0f,
+    0xd7, 0xf6, 0x57, 0xc4, 0xdd, 0x9f, 0x45, 0x54, 0xd1, 0x67, 0xdd, 0x7c,
+    0x9a, 0x48, 0x7e, 0xd4, 0x1c, 0xed, 0x2b, 0x40, 0x06, 0x80, 0x98, 0xde,
+    0xed, 0xc9, 0x51, 0x06, 0x0f, 0xaf, 0x7e, 0x15, 0xb1, 0xf0, 0xf8, 0x0a,
+    0xe6, 0x7f, 0xf2, 0xee, 0x28, 0xa2, 0x38, 0xd8, 0x0b, 0xf7, 0x2d, 0xd7,
+    0x1c, 0x8d, 0x95, 0xc7, 0x9b, 0xc1, 0x56, 0x11, 0x4e, 0xce, 0x8e, 0xc8,
+    0x37, 0x57, 0x3a, 0x4b, 0x66, 0x89, 0x8d, 0x45, 0xb4, 0x5a, 0x5e, 0xac,
+    0xd0, 0xb0, 0xe4, 0x14, 0x47, 0xd8, 0xfa, 0x08, 0xa3, 0x67, 0xf4, 0x37,
+    0x64, 0x5e, 0x50, 0xc9, 0x92, 0x0b, 0x88, 0xa1, 0x6b, 0xc0, 0x88, 0x01,
+    0x47, 0xac, 0xfb, 0x9a, 0x79, 0xde, 0x9e, 0x35, 0x1b, 0x3f, 0xa0, 0x0b,
+    0x3f, 0x4e, 0x9f, 0x18, 0x2f, 0x45, 0x55, 0x3d, 0xff, 0xca, 0x55, 0xe3,
+    0x93, 0xc5, 0xea, 0xb6,
+];
+
+/// A message, and a signature that does not verify under [`NIST_MODULUS`]
+/// and `e = 0x07485b`.
+const NIST_OTHER_E_MSG: &[u8] = &[
+    0x03, 0xd2, 0xf0, 0x69, 0x35, 0x17, 0xcf, 0xfb, 0x2b, 0x72, 0x4c, 0x1f,
+    0x30, 0x50, 0x2c, 0x53, 0x59, 0xc0, 0x51, 0xc1, 0xbc, 0xd8, 0x8d, 0xc1,
+    0xdd, 0x54, 0xb8, 0x9e, 0x69, 0x81, 0x00, 0x9d, 0x27, 0x5a, 0x81, 0x3b,
+    0x2b, 0xf0, 0x16, 0xb7, 0x4d, 0x0f, 0x6e, 0xd0, 0xd9, 0x1e, 0x62, 0xd0,
+    0x88, 0x47, 0x85, 0xc9, 0xaf, 0xd8, 0xfd, 0x1f, 0xb7, 0xe9, 0x92, 0x46,
+    0xcd, 0x40, 0x05, 0xcd, 0xda, 0x71, 0xa3, 0x9c, 0xb6, 0x49, 0x19, 0x7a,
+    0x99, 0x6d, 0x8a, 0xd2, 0xd2, 0x3f, 0xdf, 0xb6, 0xbb, 0x01, 0x5f, 0x24,
+    0xec, 0x3d, 0x7f, 0x88, 0xaf, 0x64, 0xfb, 0x83, 0xb4, 0xb5, 0x25, 0xeb,
+    0x06, 0x60, 0x7d, 0x13, 0x3e, 0xec, 0x83, 0x4c, 0xf7, 0xd6, 0xc9, 0xab,
+    0x81, 0x7b, 0x4c, 0x0d, 0xda, 0x37, 0x04, 0x59, 0xd9, 0xcf, 0xba, 0x05,
+    0xad, 0x0c, 0x1a, 0xdc, 0x86, 0xa9, 0x09, 0xfe,
+];
+
+const NIST_OTHER_E_SIG: &[u8] = &[
+    0x51, 0x1a, 0xbd, 0x82, 0x21, 0x8c, 0xab, 0x34, 0x49, 0x79, 0xb2, 0x88,
+    0x7b, 0x02, 0x60, 0x0d, 0x24, 0x27, 0xf1, 0xeb, 0x12, 0xac, 0x01, 0xd9,
+    0x76, 0x84, 0xc2, 0xa4, 0x43, 0xa9, 0x27, 0x28, 0x34, 0xc3, 0xf7, 0x9c,
+    0xde, 0xd0, 0x7a, 0x39, 0xdb, 0xee, 0x37, 0x70, 0xdd, 0xe8, 0x27, 0xa7,
+    0x4d, 0xc9, 0x94, 0xb1, 0x7b, 0xfd, 0x8a, 0x26, 0xd0, 0x7b, 0x23, 0x9d,
+    0x26, 0xd5, 0x8c, 0x42, 0xf7, 0x9d, 0x56, 0x02, 0x64, 0xc3, 0x1b, 0x7e,
+    0x1c, 0x3d, 0xdd, 0xef, 0x6d, 0x75, 0x56, 0xf2, 0x28, 0xc3, 0x94, 0x41,
+    0x4f, 0x4c, 0xec, 0x56, 0x1c, 0x3d, 0xa2, 0x68, 0x6a, 0x8e, 0xeb, 0xec,
+    0x77, 0x02, 0xf3, 0x28, 0x50, 0x80, 0x9a, 0x93, 0xde, 0xeb, 0x84, 0xb2,
+    0xa0, 0x2f, 0xcd, 0xba, 0x22, 0x4d, 0x2f, 0xd9, 0xef, 0xb8, 0xe0, 0x56,
+    0xe7, 0x96, 0xf4, 0x9b, 0x57, 0xd5, 0x6e, 0x9f, 0x3e, 0x90, 0xd0, 0xb4,
+    0x9b, 0x08, 0xbd, 0xee, 0x93, 0xa2, 0xe1, 0x2e, 0x67, 0x6f, 0xb4, 0xd4,
+    0xfa, 0x83, 0x8c, 0x5b, 0xd8, 0x8e, 0xda, 0x00, 0x8f, 0x1b, 0x59, 0x2a,
+    0x72, 0x46, 0x55, 0x87, 0xbe, 0x0a, 0xe1, 0x7d, 0x9b, 0x15, 0x6b, 0x90,
+    0x4f, 0x44, 0xa7, 0xe0, 0x4d, 0x3b, 0x58, 0xd2, 0x4a, 0xd6, 0x7b, 0x71,
+    0xb0, 0xf4, 0xc6, 0x99, 0xfa, 0x51, 0x63, 0x95, 0x46, 0xb6, 0x2b, 0x9f,
+    0x83, 0x59, 0x7f, 0xf0, 0x3d, 0x46, 0x5f, 0x1b, 0xb3, 0x96, 0xae, 0x15,
+    0xe9, 0x2d, 0x0e, 0x92, 0xe8, 0x56, 0x47, 0xd5, 0xdf, 0x11, 0x3e, 0x2c,
+    0x75, 0x18, 0xd0, 0xe3, 0xad, 0x2e, 0x7a, 0xa7, 0xda, 0xc7, 0x20, 0xc9,
+    0x83, 0x47, 0xaa, 0x15, 0x1e, 0x4f, 0x37, 0xfe, 0xa0, 0x81, 0xdb, 0xed,
+    0x35, 0x0c, 0xc9, 0xc9, 0x3f, 0x60, 0x6b, 0x38, 0xf2, 0x1a, 0x3e, 0x5d,
+    0xe6, 0xd1, 0x40, 0xd2,
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg(all(feature = "ring", feature = "std"))]
+    fn ring_passes() {
+        use crate::crypto::ring;
+        run_kats(&mut ring::sig::Ciphers::new(), RSA_PKCS1_SHA256).unwrap();
+    }
+
+    #[test]
+    fn catches_lax_verifier() {
+        let mut ciphers = sig::NoVerify;
+        let failure = run_kats(&mut ciphers, RSA_PKCS1_SHA256)
+            .unwrap_err()
+            .into_inner();
+        assert!(!failure.kat.valid);
+        assert!(failure.accepted);
+    }
+}
@@ -27,5 +27,10 @@ pub mod csrng;
 pub mod hash;
 pub mod sig;
 
+#[cfg(any(test, feature = "kat"))]
+pub mod kat;
+
+mod p256;
+
 #[cfg(feature = "ring")]
 pub mod ring;
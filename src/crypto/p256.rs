@@ -0,0 +1,158 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Minimal NIST P-256 field arithmetic, for validating public keys.
+//!
+//! This is only intended for checking untrusted, *public* points; it is not
+//! constant-time and must not be used with secret values.
+
+/// A 256-bit integer, as little-endian 64-bit limbs.
+type U256 = [u64; 4];
+
+/// The field prime, `2^256 - 2^224 + 2^192 + 2^96 - 1`.
+const P: U256 = [
+    0xffffffff_ffffffff,
+    0x00000000_ffffffff,
+    0x00000000_00000000,
+    0xffffffff_00000001,
+];
+
+/// The curve coefficient `b`; the coefficient `a` is `-3`.
+const B: U256 = [
+    0x3bce3c3e_27d2604b,
+    0x651d06b0_cc53b0f6,
+    0xb3ebbd55_769886bc,
+    0x5ac635d8_aa3a93e7,
+];
+
+/// Checks whether `(x, y)`, given as big-endian coordinates, is a point on
+/// the P-256 curve `y^2 = x^3 - 3x + b`.
+///
+/// Coordinates that are not fully reduced modulo the field prime are
+/// rejected.
+pub fn is_on_curve(x: &[u8; 32], y: &[u8; 32]) -> bool {
+    let x = from_be(x);
+    let y = from_be(y);
+    if !lt(&x, &P) || !lt(&y, &P) {
+        return false;
+    }
+
+    let lhs = mul(&y, &y);
+
+    let x3 = mul(&mul(&x, &x), &x);
+    let three_x = add(&add(&x, &x), &x);
+    let rhs = add(&sub(&x3, &three_x), &B);
+
+    lhs == rhs
+}
+
+fn from_be(bytes: &[u8; 32]) -> U256 {
+    let mut out = [0; 4];
+    for (limb, chunk) in out.iter_mut().rev().zip(bytes.chunks_exact(8)) {
+        let mut be = [0; 8];
+        be.copy_from_slice(chunk);
+        *limb = u64::from_be_bytes(be);
+    }
+    out
+}
+
+fn lt(a: &U256, b: &U256) -> bool {
+    a.iter().rev().lt(b.iter().rev())
+}
+
+/// Computes `a + b`, returning the carry.
+fn add_raw(a: &U256, b: &U256) -> (U256, bool) {
+    let mut out = [0; 4];
+    let mut carry = 0u128;
+    for ((o, &a), &b) in out.iter_mut().zip(a).zip(b) {
+        let sum = a as u128 + b as u128 + carry;
+        *o = sum as u64;
+        carry = sum >> 64;
+    }
+    (out, carry != 0)
+}
+
+/// Computes `a - b`, returning the borrow.
+fn sub_raw(a: &U256, b: &U256) -> (U256, bool) {
+    let mut out = [0; 4];
+    let mut borrow = false;
+    for ((o, &a), &b) in out.iter_mut().zip(a).zip(b) {
+        let (d, b1) = a.overflowing_sub(b);
+        let (d, b2) = d.overflowing_sub(borrow as u64);
+        *o = d;
+        borrow = b1 || b2;
+    }
+    (out, borrow)
+}
+
+/// Computes `a + b mod p`, for reduced `a` and `b`.
+fn add(a: &U256, b: &U256) -> U256 {
+    let (sum, carry) = add_raw(a, b);
+    if carry || !lt(&sum, &P) {
+        sub_raw(&sum, &P).0
+    } else {
+        sum
+    }
+}
+
+/// Computes `a - b mod p`, for reduced `a` and `b`.
+fn sub(a: &U256, b: &U256) -> U256 {
+    let (diff, borrow) = sub_raw(a, b);
+    if borrow {
+        add_raw(&diff, &P).0
+    } else {
+        diff
+    }
+}
+
+/// Computes `a * b mod p`, for reduced `a` and `b`, by double-and-add.
+fn mul(a: &U256, b: &U256) -> U256 {
+    let mut out = [0; 4];
+    for i in (0..256).rev() {
+        out = add(&out, &out);
+        if (b[i / 64] >> (i % 64)) & 1 == 1 {
+            out = add(&out, a);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const GX: [u8; 32] = [
+        0x6b, 0x17, 0xd1, 0xf2, 0xe1, 0x2c, 0x42, 0x47, 0xf8, 0xbc, 0xe6, 0xe5,
+        0x63, 0xa4, 0x40, 0xf2, 0x77, 0x03, 0x7d, 0x81, 0x2d, 0xeb, 0x33, 0xa0,
+        0xf4, 0xa1, 0x39, 0x45, 0xd8, 0x98, 0xc2, 0x96,
+    ];
+    const GY: [u8; 32] = [
+        0x4f, 0xe3, 0x42, 0xe2, 0xfe, 0x1a, 0x7f, 0x9b, 0x8e, 0xe7, 0xeb, 0x4a,
+        0x7c, 0x0f, 0x9e, 0x16, 0x2b, 0xce, 0x33, 0x57, 0x6b, 0x31, 0x5e, 0xce,
+        0xcb, 0xb6, 0x40, 0x68, 0x37, 0xbf, 0x51, 0xf5,
+    ];
+
+    #[test]
+    fn generator() {
+        assert!(is_on_curve(&GX, &GY));
+
+        // -G = (Gx, p - Gy) is also on the curve.
+        let neg_y = sub(&[0; 4], &from_be(&GY));
+        let mut neg_gy = [0; 32];
+        for (chunk, limb) in neg_gy.chunks_exact_mut(8).zip(neg_y.iter().rev())
+        {
+            chunk.copy_from_slice(&limb.to_be_bytes());
+        }
+        assert!(is_on_curve(&GX, &neg_gy));
+    }
+
+    #[test]
+    fn off_curve() {
+        let mut gy = GY;
+        gy[31] ^= 1;
+        assert!(!is_on_curve(&GX, &gy));
+        assert!(!is_on_curve(&[0; 32], &[0; 32]));
+        assert!(!is_on_curve(&[0xff; 32], &GY));
+    }
+}
@@ -41,4 +41,7 @@ impl csrng::Csrng for Csrng {
             .fill(buf)
             .map_err(|_| fail!(csrng::Error::Unspecified))
     }
+
+    // The operating system is responsible for the health of its own entropy
+    // sources, so the default `health_check()` is sufficient.
 }
@@ -65,6 +65,10 @@ impl sig::Verify for VerifyP256 {
             )
             .map_err(|_| fail!(sig::Error::Unspecified))
     }
+
+    fn key_bits(&self) -> Option<usize> {
+        Some(256)
+    }
 }
 
 /// A `ring`-based [`sig::Sign`] for PKCS#1.5 RSA using SHA-256.
@@ -120,6 +124,10 @@ impl sig::Sign for SignP256 {
         64 + 8
     }
 
+    fn key_bits(&self) -> Option<usize> {
+        Some(256)
+    }
+
     fn sign(
         &mut self,
         message_vec: &[&[u8]],
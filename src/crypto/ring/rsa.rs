@@ -9,6 +9,7 @@
 use ring::signature::KeyPair as _;
 use ring::signature::RsaPublicKeyComponents;
 
+use crate::crypto::hash;
 use crate::crypto::sig;
 use crate::Result;
 
@@ -33,6 +34,25 @@ impl PublicKey {
             },
         }
     }
+
+    /// Returns this key's modulus, in big-endian.
+    pub fn modulus(&self) -> &[u8] {
+        &self.key.n
+    }
+
+    /// Returns this key's exponent, in big-endian.
+    pub fn exponent(&self) -> &[u8] {
+        &self.key.e
+    }
+
+    /// Returns this key's parameters, such as for computing its
+    /// [fingerprint](sig::PublicKeyParams::fingerprint).
+    pub fn params(&self) -> sig::PublicKeyParams {
+        sig::PublicKeyParams::Rsa {
+            modulus: self.modulus(),
+            exponent: self.exponent(),
+        }
+    }
 }
 
 /// A `ring`-based [`sig::Verify`] for PKCS#1.5 RSA using SHA-256.
@@ -64,6 +84,10 @@ impl sig::Verify for Verify256 {
             .verify(scheme, &message, signature)
             .map_err(|_| fail!(sig::Error::Unspecified))
     }
+
+    fn key_bits(&self) -> Option<usize> {
+        modulus_bits(&self.key.key.n)
+    }
 }
 
 /// A `ring`-based [`sig::Sign`] for PKCS#1.5 RSA using SHA-256.
@@ -108,6 +132,15 @@ impl sig::Sign for Sign256 {
         self.keypair.public_modulus_len()
     }
 
+    fn key_bits(&self) -> Option<usize> {
+        modulus_bits(
+            self.keypair
+                .public_key()
+                .modulus()
+                .big_endian_without_leading_zero(),
+        )
+    }
+
     fn sign(
         &mut self,
         message_vec: &[&[u8]],
@@ -125,6 +158,52 @@ impl sig::Sign for Sign256 {
             .map_err(|_| fail!(sig::Error::Unspecified))?;
         Ok(self.sig_bytes())
     }
+
+    /// Creates an RSA-PSS signature.
+    ///
+    /// `ring` only supports PSS with MGF1 over the message's hash function,
+    /// and a salt as long as its digest; see [`sig::PssParams::with_hash()`].
+    fn sign_pss(
+        &mut self,
+        params: &sig::PssParams,
+        message_vec: &[&[u8]],
+        signature: &mut [u8],
+    ) -> Result<usize, sig::Error> {
+        check!(
+            *params == sig::PssParams::with_hash(params.hash),
+            sig::Error::Unsupported
+        );
+        check!(
+            self.key_bits().map(|k| params.fits(k)).unwrap_or(false),
+            sig::Error::Unsupported
+        );
+        let len = self.sig_bytes();
+        check!(signature.len() >= len, sig::Error::Unspecified);
+
+        let scheme: &dyn ring::signature::RsaEncoding = match params.hash {
+            hash::Algo::Sha256 => &ring::signature::RSA_PSS_SHA256,
+            hash::Algo::Sha384 => &ring::signature::RSA_PSS_SHA384,
+            hash::Algo::Sha512 => &ring::signature::RSA_PSS_SHA512,
+        };
+
+        let mut message = Vec::new();
+        for bytes in message_vec {
+            message.extend_from_slice(bytes);
+        }
+
+        let rng = ring::rand::SystemRandom::new();
+        self.keypair
+            .sign(scheme, &rng, &message, &mut signature[..len])
+            .map_err(|_| fail!(sig::Error::Unspecified))?;
+        Ok(len)
+    }
+}
+
+/// Computes the bit length of a big-endian modulus, which may be padded with
+/// leading zeroes.
+fn modulus_bits(n: &[u8]) -> Option<usize> {
+    let start = n.iter().position(|&b| b != 0)?;
+    Some((n.len() - start) * 8 - n[start].leading_zeros() as usize)
 }
 
 /// Generates an RSA engine and signer out of test-only data.
@@ -147,6 +226,8 @@ mod tests {
     #[cfg_attr(miri, ignore)]
     fn rsa() {
         let (mut verifier, mut signer) = from_keypair(keys::KEY1_RSA_KEYPAIR);
+        assert_eq!(verifier.key_bits(), Some(2048));
+        assert_eq!(signer.key_bits(), Some(2048));
 
         verifier
             .verify(&[misc_crypto::PLAIN_TEXT], misc_crypto::KEY1_SHA256_SIG)
@@ -161,4 +242,50 @@ mod tests {
             .verify(&[misc_crypto::PLAIN_TEXT], &generated_sig)
             .unwrap();
     }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn rsa_pss() {
+        let (verifier, mut signer) = from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let params = sig::PssParams::with_hash(hash::Algo::Sha256);
+
+        // Leave room at the end to check that only `sig_bytes()` are written.
+        let mut generated_sig = vec![0; signer.sig_bytes() + 1];
+        let len = signer
+            .sign_pss(&params, &[misc_crypto::PLAIN_TEXT], &mut generated_sig)
+            .unwrap();
+        assert_eq!(len, signer.sig_bytes());
+        assert_eq!(generated_sig[len], 0);
+
+        verifier
+            .key
+            .key
+            .verify(
+                &ring::signature::RSA_PSS_2048_8192_SHA256,
+                misc_crypto::PLAIN_TEXT,
+                &generated_sig[..len],
+            )
+            .unwrap();
+
+        let short = signer.sign_pss(
+            &params,
+            &[misc_crypto::PLAIN_TEXT],
+            &mut generated_sig[..len - 1],
+        );
+        assert_eq!(short.unwrap_err().into_inner(), sig::Error::Unspecified);
+
+        let odd_salt = sig::PssParams {
+            salt_len: 20,
+            ..params
+        };
+        let unsupported = signer.sign_pss(
+            &odd_salt,
+            &[misc_crypto::PLAIN_TEXT],
+            &mut generated_sig,
+        );
+        assert_eq!(
+            unsupported.unwrap_err().into_inner(),
+            sig::Error::Unsupported
+        );
+    }
 }
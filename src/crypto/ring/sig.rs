@@ -22,16 +22,143 @@ use crate::protocol::cerberus::capabilities;
 use crate::crypto;
 
 /// A [`sig::Ciphers`] built on top of `ring`.
+///
+/// By default, a fresh verifier is primed for every call to
+/// [`sig::Ciphers::verifier()`]. [`Ciphers::with_cache()`] instead keeps a
+/// bounded number of primed verifiers around, keyed by algorithm and public
+/// key, evicting the least recently used one when full. Since a
+/// [`sig::Verify`] cannot give up its key, handing the same verifier out
+/// again is safe.
 #[derive(Default)]
 pub struct Ciphers {
     verifier: Option<Box<dyn sig::Verify>>,
+    cache: Vec<Cached>,
+    capacity: usize,
+}
+
+/// A primed verifier, along with the key it was primed with.
+struct Cached {
+    algo: Algo,
+    key: OwnedKey,
+    verifier: Box<dyn sig::Verify>,
+}
+
+/// An owned copy of a [`PublicKeyParams`], for use as a cache key.
+#[derive(PartialEq, Eq)]
+enum OwnedKey {
+    Rsa {
+        modulus: Vec<u8>,
+        exponent: Vec<u8>,
+    },
+    Ecc {
+        curve: Curve,
+        x: Vec<u8>,
+        y: Vec<u8>,
+    },
+}
+
+impl OwnedKey {
+    fn new(key: &PublicKeyParams) -> Self {
+        match key {
+            PublicKeyParams::Rsa { modulus, exponent } => Self::Rsa {
+                modulus: modulus.to_vec(),
+                exponent: exponent.to_vec(),
+            },
+            PublicKeyParams::Ecc { curve, x, y } => Self::Ecc {
+                curve: *curve,
+                x: x.to_vec(),
+                y: y.to_vec(),
+            },
+        }
+    }
+
+    fn is(&self, key: &PublicKeyParams) -> bool {
+        match (self, key) {
+            (
+                Self::Rsa { modulus, exponent },
+                PublicKeyParams::Rsa {
+                    modulus: m,
+                    exponent: e,
+                },
+            ) => modulus == m && exponent == e,
+            (
+                Self::Ecc { curve, x, y },
+                PublicKeyParams::Ecc {
+                    curve: c,
+                    x: x2,
+                    y: y2,
+                },
+            ) => curve == c && x == x2 && y == y2,
+            _ => false,
+        }
+    }
 }
 
 impl Ciphers {
-    /// Returns a new `RingCiphers`.
+    /// Returns a new `Ciphers`.
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Returns a new `Ciphers` that caches up to `capacity` primed
+    /// verifiers.
+    ///
+    /// A `capacity` of zero disables caching, which is equivalent to
+    /// [`Ciphers::new()`].
+    pub fn with_cache(capacity: usize) -> Self {
+        Self {
+            cache: Vec::with_capacity(capacity),
+            capacity,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the number of verifiers currently cached.
+    pub fn cached(&self) -> usize {
+        self.cache.len()
+    }
+}
+
+/// Primes a new verifier for `key`, if `algo` is supported.
+fn prime(algo: Algo, key: &PublicKeyParams) -> Option<Box<dyn sig::Verify>> {
+    match (algo, key) {
+        (Algo::RsaPkcs1Sha256, PublicKeyParams::Rsa { modulus, exponent }) => {
+            let key =
+                rsa::PublicKey::new((*modulus).into(), (*exponent).into());
+            Some(Box::new(rsa::Verify256::from_public(key)))
+        }
+        (
+            Algo::EcdsaDerP256,
+            PublicKeyParams::Ecc {
+                curve: Curve::NistP256,
+                x,
+                y,
+            },
+        ) => {
+            if !Curve::NistP256.is_on_curve(x, y) {
+                return None;
+            }
+            let x: &[u8; 32] = (*x).try_into().ok()?;
+            let y: &[u8; 32] = (*y).try_into().ok()?;
+            Some(Box::new(ecdsa::VerifyP256::with_der_encoding(*x, *y)))
+        }
+        (
+            Algo::EcdsaPkcs11P256,
+            PublicKeyParams::Ecc {
+                curve: Curve::NistP256,
+                x,
+                y,
+            },
+        ) => {
+            if !Curve::NistP256.is_on_curve(x, y) {
+                return None;
+            }
+            let x: &[u8; 32] = (*x).try_into().ok()?;
+            let y: &[u8; 32] = (*y).try_into().ok()?;
+            Some(Box::new(ecdsa::VerifyP256::with_pkcs11_encoding(*x, *y)))
+        }
+        _ => None,
+    }
 }
 
 impl sig::Ciphers for Ciphers {
@@ -53,48 +180,85 @@ impl sig::Ciphers for Ciphers {
         algo: sig::Algo,
         key: &sig::PublicKeyParams,
     ) -> Option<&'a mut dyn sig::Verify> {
-        match (algo, key) {
-            (
-                Algo::RsaPkcs1Sha256,
-                PublicKeyParams::Rsa { modulus, exponent },
-            ) => {
-                let key =
-                    rsa::PublicKey::new((*modulus).into(), (*exponent).into());
-
-                self.verifier =
-                    Some(Box::new(rsa::Verify256::from_public(key)));
+        if self.capacity == 0 {
+            self.verifier = prime(algo, key);
+            return self.verifier.as_mut().map(|x| &mut **x as _);
+        }
+
+        // The cache is kept in order of use, most recent last.
+        match self
+            .cache
+            .iter()
+            .position(|c| c.algo == algo && c.key.is(key))
+        {
+            Some(idx) => {
+                let hit = self.cache.remove(idx);
+                self.cache.push(hit);
             }
-            (
-                Algo::EcdsaDerP256,
-                PublicKeyParams::Ecc {
-                    curve: Curve::NistP256,
-                    x,
-                    y,
-                },
-            ) => {
-                let x: &[u8; 32] = (*x).try_into().ok()?;
-                let y: &[u8; 32] = (*y).try_into().ok()?;
-                self.verifier = Some(Box::new(
-                    ecdsa::VerifyP256::with_der_encoding(*x, *y),
-                ));
+            None => {
+                let verifier = prime(algo, key)?;
+                if self.cache.len() == self.capacity {
+                    self.cache.remove(0);
+                }
+                self.cache.push(Cached {
+                    algo,
+                    key: OwnedKey::new(key),
+                    verifier,
+                });
             }
-            (
-                Algo::EcdsaPkcs11P256,
-                PublicKeyParams::Ecc {
-                    curve: Curve::NistP256,
-                    x,
-                    y,
-                },
-            ) => {
-                let x: &[u8; 32] = (*x).try_into().ok()?;
-                let y: &[u8; 32] = (*y).try_into().ok()?;
-                self.verifier = Some(Box::new(
-                    ecdsa::VerifyP256::with_pkcs11_encoding(*x, *y),
-                ));
-            }
-            _ => {}
         }
+        self.cache.last_mut().map(|c| &mut *c.verifier as _)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto::kat;
+    use crate::crypto::sig::Ciphers as _;
+
+    #[test]
+    fn cached_verifiers() {
+        let mut ciphers = Ciphers::with_cache(2);
+        kat::run_kats(&mut ciphers, kat::RSA_PKCS1_SHA256).unwrap();
+        assert_eq!(ciphers.cached(), 2);
+
+        // Running again must give the same answers out of a warm cache.
+        kat::run_kats(&mut ciphers, kat::RSA_PKCS1_SHA256).unwrap();
+        assert_eq!(ciphers.cached(), 2);
+
+        let kat = &kat::RSA_PKCS1_SHA256[0];
+        let unsupported = ciphers.verifier(
+            kat.algo,
+            &PublicKeyParams::Ecc {
+                curve: Curve::NistP256,
+                x: &[0; 32],
+                y: &[0; 32],
+            },
+        );
+        assert!(unsupported.is_none());
+        assert!(ciphers.verifier(kat.algo, &kat.key).is_some());
+        assert_eq!(ciphers.cached(), 2);
+    }
+
+    #[test]
+    fn uncached() {
+        let mut ciphers = Ciphers::new();
+        kat::run_kats(&mut ciphers, kat::RSA_PKCS1_SHA256).unwrap();
+        assert_eq!(ciphers.cached(), 0);
 
-        self.verifier.as_mut().map(|x| &mut **x as _)
+        // A key that cannot be primed must not hand back the verifier for
+        // the previous one.
+        let kat = &kat::RSA_PKCS1_SHA256[0];
+        assert!(ciphers.verifier(kat.algo, &kat.key).is_some());
+        let unsupported = ciphers.verifier(
+            kat.algo,
+            &PublicKeyParams::Ecc {
+                curve: Curve::NistP256,
+                x: &[0; 32],
+                y: &[0; 32],
+            },
+        );
+        assert!(unsupported.is_none());
     }
 }
@@ -4,17 +4,49 @@
 
 //! Algorithm-generic signature traits.
 
+use core::convert::TryInto as _;
+
+use crate::crypto;
+use crate::crypto::hash;
+use crate::crypto::hash::EngineExt as _;
+use crate::io;
 use crate::protocol::cerberus::capabilities;
 use crate::Result;
 
+#[cfg(feature = "arbitrary-derive")]
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+
 /// An error returned by a signature operation.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Error {
+    /// Indicates that the underlying hardware is in use by another
+    /// operation, and that the caller should try again later.
+    Busy,
+
+    /// Indicates that the requested operation, or its parameters, are not
+    /// supported by this engine.
+    Unsupported,
+
     /// Indicates an unspecified, internal error.
     Unspecified,
 }
 
-debug_from!(Error);
+impl From<hash::Error> for Error {
+    fn from(e: hash::Error) -> Self {
+        match e {
+            hash::Error::Busy => Self::Busy,
+            _ => Self::Unspecified,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(_: io::Error) -> Self {
+        Self::Unspecified
+    }
+}
+
+debug_from!(Error => hash::Error, io::Error);
 
 /// A signature-verification engine, already primed with a key.
 ///
@@ -34,9 +66,164 @@ pub trait Verify {
         message_vec: &[&[u8]],
         signature: &[u8],
     ) -> Result<(), Error>;
+
+    /// Verifies that `signature` is a valid signature for a message whose
+    /// `algo` digest is `digest`.
+    ///
+    /// This allows checking signatures over messages too large to hold in
+    /// memory at once; see [`verify_stream()`].
+    ///
+    /// Engines that cannot verify a precomputed digest return
+    /// [`Error::Unsupported`]. The default implementation always does so.
+    fn verify_prehashed(
+        &mut self,
+        algo: hash::Algo,
+        digest: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        let _ = (algo, digest, signature);
+        Err(fail!(Error::Unsupported))
+    }
+
+    /// Returns the size, in bits, of the key this engine was primed with:
+    /// the modulus length for RSA, or the curve size for ECC.
+    ///
+    /// This is intended for logging and policy decisions. The default
+    /// implementation returns `None`, indicating that the size is unknown.
+    fn key_bits(&self) -> Option<usize> {
+        None
+    }
 }
 impl dyn Verify {} // Ensure object-safe.
 
+/// Verifies that `signature` is a valid signature for the remaining contents
+/// of `message`, without buffering all of it.
+///
+/// The message is read in fixed-size chunks until `message` reports no more
+/// data, hashed with `algo` using `hasher`, and the resulting digest is
+/// checked by [`Verify::verify_prehashed()`].
+pub fn verify_stream(
+    verifier: &mut (impl Verify + ?Sized),
+    hasher: &mut (impl hash::Engine + ?Sized),
+    algo: hash::Algo,
+    signature: &[u8],
+    message: &mut (impl io::Read + ?Sized),
+) -> Result<(), Error> {
+    use crate::crypto::hash::EngineExt as _;
+
+    let mut h = hasher.new_hash(algo)?;
+    let mut chunk = [0; 256];
+    while message.remaining_data() > 0 {
+        let len = message.remaining_data().min(chunk.len());
+        message.read_bytes(&mut chunk[..len])?;
+        h.write(&chunk[..len])?;
+    }
+
+    let mut digest = [0; hash::Algo::Sha512.bytes()];
+    let digest = &mut digest[..algo.bytes()];
+    h.finish(digest)?;
+    verifier.verify_prehashed(algo, digest, signature)
+}
+
+/// Verifies `signature` against each of several candidate keys, such as the
+/// old and new keys during a key rollover, returning the index of the first
+/// one under which it is valid.
+///
+/// Every verifier is tried, in order, even after one succeeds, so that the
+/// time taken does not reveal which key matched. As such, this is O(n) in the
+/// number of verifiers.
+///
+/// If no verifier succeeds, returns [`Error::Busy`] if any of them was busy,
+/// since trying again may yield a different result, and
+/// [`Error::Unspecified`] otherwise, including if `verifiers` is empty.
+pub fn verify_any(
+    verifiers: &mut [&mut dyn Verify],
+    message_vec: &[&[u8]],
+    signature: &[u8],
+) -> Result<usize, Error> {
+    let mut matched = None;
+    let mut busy = false;
+    for (i, verifier) in verifiers.iter_mut().enumerate() {
+        match verifier.verify(message_vec, signature) {
+            Ok(()) => matched = matched.or(Some(i)),
+            Err(e) => busy |= e.into_inner() == Error::Busy,
+        }
+    }
+
+    match (matched, busy) {
+        (Some(i), _) => Ok(i),
+        (None, true) => Err(fail!(Error::Busy)),
+        (None, false) => Err(fail!(Error::Unspecified)),
+    }
+}
+
+/// A [`Verify`] that logs every call into another `Verify`, for diagnosing
+/// why signatures fail to verify.
+///
+/// Each call is logged at [`TraceLevel::Trace`] with the lengths of the
+/// message and signature, the size of the key, and the result. The key, the
+/// message, and the signature themselves are never logged. Production
+/// builds can silence these logs altogether by capping the level below
+/// `Trace` with [`set_trace_level()`].
+///
+/// [`TraceLevel::Trace`]: crate::TraceLevel::Trace
+/// [`set_trace_level()`]: crate::set_trace_level
+pub struct LoggingVerify<V> {
+    inner: V,
+}
+
+impl<V: Verify> LoggingVerify<V> {
+    /// Creates a new `LoggingVerify` that wraps `inner`.
+    pub fn new(inner: V) -> Self {
+        Self { inner }
+    }
+
+    /// Returns the wrapped verifier.
+    pub fn into_inner(self) -> V {
+        self.inner
+    }
+}
+
+impl<V: Verify> Verify for LoggingVerify<V> {
+    fn verify(
+        &mut self,
+        message_vec: &[&[u8]],
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        let result = self.inner.verify(message_vec, signature);
+        trace!(
+            "verify: message_len = {}, sig_len = {}, key_bits = {:?}: {:?}",
+            message_vec.iter().map(|m| m.len()).sum::<usize>(),
+            signature.len(),
+            self.inner.key_bits(),
+            result.as_ref().map_err(|e| e.as_ref()),
+        );
+        result
+    }
+
+    fn verify_prehashed(
+        &mut self,
+        algo: hash::Algo,
+        digest: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        let result = self.inner.verify_prehashed(algo, digest, signature);
+        trace!(
+            "verify_prehashed: algo = {:?}, sig_len = {}, key_bits = {:?}: \
+             {:?}",
+            algo,
+            signature.len(),
+            self.inner.key_bits(),
+            result.as_ref().map_err(|e| e.as_ref()),
+        );
+        result
+    }
+
+    fn key_bits(&self) -> Option<usize> {
+        self.inner.key_bits()
+    }
+}
+
 /// An signing engine, already primed with a keypair.
 ///
 /// There is no way to extract the keypair back out of a `Sign` value.
@@ -45,6 +232,13 @@ pub trait Sign {
     /// will need.
     fn sig_bytes(&self) -> usize;
 
+    /// Returns the size, in bits, of the key this engine was primed with.
+    ///
+    /// See [`Verify::key_bits()`].
+    fn key_bits(&self) -> Option<usize> {
+        None
+    }
+
     /// Creates a digital signature for `message_vec`, writing it to signature.
     ///
     /// `message_vec` is an iovec-like structure: the message is split across
@@ -58,9 +252,314 @@ pub trait Sign {
         message_vec: &[&[u8]],
         signature: &mut [u8],
     ) -> Result<usize, Error>;
+
+    /// Creates an RSA-PSS signature for `message_vec`, using the given
+    /// `params`, writing it to `signature`.
+    ///
+    /// A PSS signature for a `k`-bit modulus is always exactly `ceil(k / 8)`
+    /// bytes, which is what [`Sign::sig_bytes()`] returns. Note that when
+    /// `k % 8 == 1`, the PSS-encoded message itself is one byte shorter than
+    /// that; the signature is still left-padded out to the full modulus
+    /// length. If `signature` is shorter than `sig_bytes()`, this function
+    /// returns [`Error::Unspecified`] without signing anything.
+    ///
+    /// If the signature is created successfully, returns the number of bytes
+    /// written to `signature`.
+    ///
+    /// Engines that do not support PSS, or that do not support `params`
+    /// (including parameters that do not fit the key, per
+    /// [`PssParams::fits()`]), return [`Error::Unsupported`]. The default
+    /// implementation always does so.
+    fn sign_pss(
+        &mut self,
+        params: &PssParams,
+        message_vec: &[&[u8]],
+        signature: &mut [u8],
+    ) -> Result<usize, Error> {
+        let _ = (params, message_vec, signature);
+        Err(fail!(Error::Unsupported))
+    }
 }
 impl dyn Sign {} // Ensure object-safe.
 
+/// A type of signing key, as advertised during capabilities negotiation.
+///
+/// A device may hold keys of both types for the same identity; see
+/// [`AnySigner`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary-derive", derive(Arbitrary))]
+pub enum KeyType {
+    /// An RSA key.
+    Rsa,
+    /// An ECDSA key.
+    Ecdsa,
+}
+derive_borrowed!(KeyType);
+
+impl KeyType {
+    /// Returns whether `caps`, the result of negotiating capabilities with a
+    /// peer, allows keys of this type.
+    pub fn is_negotiated(self, caps: &capabilities::Crypto) -> bool {
+        match self {
+            Self::Rsa => caps.has_rsa,
+            Self::Ecdsa => caps.has_ecdsa,
+        }
+    }
+}
+
+/// A [`Sign`] for a key of either [`KeyType`], tagged with which one it is.
+///
+/// This allows code that picks a signer at runtime, such as in response to a
+/// peer's choice of key type, to hold RSA and ECDSA signers side by side.
+pub enum AnySigner<'a> {
+    /// A signer for an RSA key.
+    Rsa(&'a mut dyn Sign),
+    /// A signer for an ECDSA key.
+    Ecdsa(&'a mut dyn Sign),
+}
+
+impl<'a> AnySigner<'a> {
+    /// Returns the type of key this signer holds.
+    pub fn key_type(&self) -> KeyType {
+        match self {
+            Self::Rsa(_) => KeyType::Rsa,
+            Self::Ecdsa(_) => KeyType::Ecdsa,
+        }
+    }
+
+    /// Returns the wrapped signer.
+    pub fn into_inner(self) -> &'a mut dyn Sign {
+        match self {
+            Self::Rsa(s) | Self::Ecdsa(s) => s,
+        }
+    }
+
+    fn inner(&self) -> &dyn Sign {
+        match self {
+            Self::Rsa(s) | Self::Ecdsa(s) => &**s,
+        }
+    }
+
+    fn inner_mut(&mut self) -> &mut dyn Sign {
+        match self {
+            Self::Rsa(s) | Self::Ecdsa(s) => &mut **s,
+        }
+    }
+}
+
+impl Sign for AnySigner<'_> {
+    fn sig_bytes(&self) -> usize {
+        self.inner().sig_bytes()
+    }
+
+    fn key_bits(&self) -> Option<usize> {
+        self.inner().key_bits()
+    }
+
+    fn sign(
+        &mut self,
+        message_vec: &[&[u8]],
+        signature: &mut [u8],
+    ) -> Result<usize, Error> {
+        self.inner_mut().sign(message_vec, signature)
+    }
+
+    fn sign_pss(
+        &mut self,
+        params: &PssParams,
+        message_vec: &[&[u8]],
+        signature: &mut [u8],
+    ) -> Result<usize, Error> {
+        self.inner_mut().sign_pss(params, message_vec, signature)
+    }
+}
+
+/// A handle to a signing key held somewhere other than this device, such as
+/// in an HSM reached over a side channel.
+///
+/// Integrations implement this for whatever opaque handle their remote
+/// signer understands; [`RemoteSign`] then adapts it into a [`Sign`], so that
+/// the rest of Manticore need not care where the private key lives.
+pub trait RemoteKey {
+    /// Returns the public half of this key.
+    ///
+    /// Unlike the private half, this must be available locally, since it is
+    /// needed to build certificates for the key.
+    fn public_key(&self) -> PublicKeyParams;
+
+    /// Returns an upper bound for the number of bytes a signature with this
+    /// key will need.
+    fn sig_bytes(&self) -> usize;
+
+    /// Asks the remote signer to sign `message_vec`, writing the signature to
+    /// `signature`.
+    ///
+    /// See [`Sign::sign()`].
+    fn sign_remote(
+        &mut self,
+        message_vec: &[&[u8]],
+        signature: &mut [u8],
+    ) -> Result<usize, Error>;
+}
+impl dyn RemoteKey {} // Ensure object-safe.
+
+/// A [`Sign`] that dispatches every signature to a [`RemoteKey`].
+pub struct RemoteSign<K> {
+    key: K,
+}
+
+impl<K: RemoteKey> RemoteSign<K> {
+    /// Creates a new `RemoteSign` that signs with the given remote key.
+    pub fn new(key: K) -> Self {
+        Self { key }
+    }
+
+    /// Returns the handle this signer dispatches to.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns the public half of this signer's key.
+    pub fn public_key(&self) -> PublicKeyParams {
+        self.key.public_key()
+    }
+}
+
+impl<K: RemoteKey> Sign for RemoteSign<K> {
+    fn sig_bytes(&self) -> usize {
+        self.key.sig_bytes()
+    }
+
+    fn sign(
+        &mut self,
+        message_vec: &[&[u8]],
+        signature: &mut [u8],
+    ) -> Result<usize, Error> {
+        check!(signature.len() >= self.sig_bytes(), Error::Unspecified);
+        self.key.sign_remote(message_vec, signature)
+    }
+}
+
+/// A fixed-capacity buffer for holding a single signature.
+///
+/// The buffer is large enough for an RSA signature with a 4096-bit modulus,
+/// which is the largest key size Cerberus supports, and any ECDSA signature;
+/// it tracks how much of that space the signature actually uses. This allows
+/// signing without an [`Arena`](crate::mem::Arena) or the heap.
+#[derive(Clone)]
+pub struct SigBuf {
+    buf: [u8; Self::CAPACITY],
+    len: usize,
+}
+
+impl SigBuf {
+    /// The largest signature, in bytes, that a `SigBuf` can hold.
+    pub const CAPACITY: usize = 4096 / 8;
+
+    /// Creates a zeroed buffer exactly as long as an RSA signature for a
+    /// modulus of the given `strength`.
+    pub fn for_rsa(strength: capabilities::RsaKeyStrength) -> Self {
+        use capabilities::RsaKeyStrength::*;
+        let bits = match strength {
+            Bits2048 => 2048,
+            Bits3072 => 3072,
+            Bits4096 => 4096,
+        };
+        Self {
+            buf: [0; Self::CAPACITY],
+            len: bits / 8,
+        }
+    }
+
+    /// Creates a `SigBuf` holding a signature over `message_vec` by
+    /// `signer`.
+    ///
+    /// Returns [`Error::Unsupported`] if `signer` may produce signatures
+    /// longer than [`SigBuf::CAPACITY`].
+    pub fn sign(
+        signer: &mut (impl Sign + ?Sized),
+        message_vec: &[&[u8]],
+    ) -> Result<Self, Error> {
+        check!(signer.sig_bytes() <= Self::CAPACITY, Error::Unsupported);
+        let mut sig = Self {
+            buf: [0; Self::CAPACITY],
+            len: 0,
+        };
+        let len =
+            signer.sign(message_vec, &mut sig.buf[..signer.sig_bytes()])?;
+        check!(len <= Self::CAPACITY, Error::Unspecified);
+        sig.len = len;
+        Ok(sig)
+    }
+
+    /// Returns the length of the signature, in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl AsRef<[u8]> for SigBuf {
+    fn as_ref(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+impl AsMut<[u8]> for SigBuf {
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.buf[..self.len]
+    }
+}
+
+impl core::fmt::Debug for SigBuf {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_tuple("SigBuf").field(&self.as_ref()).finish()
+    }
+}
+
+/// Parameters for RSA-PSS signatures, per RFC 8017.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PssParams {
+    /// The hash function used to digest the message.
+    pub hash: hash::Algo,
+    /// The hash function used by the MGF1 mask generation function.
+    pub mgf_hash: hash::Algo,
+    /// The length of the salt, in bytes.
+    pub salt_len: usize,
+}
+
+impl PssParams {
+    /// Returns the most common parameters for `hash`: MGF1 using the same
+    /// hash function, and a salt as long as its digest.
+    pub const fn with_hash(hash: hash::Algo) -> Self {
+        Self {
+            hash,
+            mgf_hash: hash,
+            salt_len: hash.bytes(),
+        }
+    }
+
+    /// Returns whether these parameters can be used with a key whose modulus
+    /// is `modulus_bits` bits long.
+    ///
+    /// PSS encodes messages into `ceil((modulus_bits - 1) / 8)` bytes, which
+    /// must hold the digest, the salt, and two more bytes.
+    pub fn fits(&self, modulus_bits: usize) -> bool {
+        let em_len = (modulus_bits.saturating_sub(1) + 7) / 8;
+        self.hash
+            .bytes()
+            .checked_add(self.salt_len)
+            .and_then(|n| n.checked_add(2))
+            .map(|n| n <= em_len)
+            .unwrap_or(false)
+    }
+}
+
 /// Public key parameters extracted from a certificate.
 ///
 /// This must be paired with a compatible [`Algo`] (which specifies *algorithm*
@@ -100,6 +599,120 @@ impl PublicKeyParams<'_> {
         );
         ok
     }
+
+    /// Computes this key's fingerprint: the SHA-256 digest of its canonical
+    /// encoding.
+    ///
+    /// The canonical encoding is a byte identifying the key type, followed by
+    /// each of the key's components. Each component is a big-endian integer
+    /// with its leading zero bytes stripped, preceded by its length as a
+    /// big-endian `u32`:
+    /// - RSA keys are encoded as `0x01 || len(n) || n || len(e) || e`.
+    /// - EC keys are encoded as `0x02 || curve || len(x) || x || len(y) || y`,
+    ///   where `curve` is `0x01` for P-256.
+    ///
+    /// Stripping leading zeros means that a key has the same fingerprint
+    /// whether or not its components were padded, such as by DER's sign
+    /// byte.
+    pub fn fingerprint(
+        &self,
+        hasher: &mut dyn hash::Engine,
+    ) -> Result<[u8; FINGERPRINT_LEN], hash::Error> {
+        let mut h = hasher.new_hash(hash::Algo::Sha256)?;
+        match self {
+            Self::Rsa { modulus, exponent } => {
+                h.write(&[0x01])?;
+                write_component(&mut h, modulus)?;
+                write_component(&mut h, exponent)?;
+            }
+            Self::Ecc { curve, x, y } => {
+                let curve = match curve {
+                    Curve::NistP256 => 0x01,
+                };
+                h.write(&[0x02, curve])?;
+                write_component(&mut h, x)?;
+                write_component(&mut h, y)?;
+            }
+        }
+
+        let mut fingerprint = [0; FINGERPRINT_LEN];
+        h.finish(&mut fingerprint)?;
+        Ok(fingerprint)
+    }
+
+    /// Returns whether `self` and `other` are the same key, ignoring any
+    /// leading zeros in their components.
+    ///
+    /// Once their leading zeros have been stripped, components are compared
+    /// in constant time. The stripping itself is not: the key types, the
+    /// number of leading zeros in each component, and the resulting lengths
+    /// all leak through timing. This is fine for public keys, which are not
+    /// secret to begin with, but makes this unsuitable for comparing secrets.
+    pub fn eq_key(&self, other: &PublicKeyParams) -> bool {
+        match (self, other) {
+            (
+                Self::Rsa {
+                    modulus: n1,
+                    exponent: e1,
+                },
+                PublicKeyParams::Rsa {
+                    modulus: n2,
+                    exponent: e2,
+                },
+            ) => ct_eq(n1, n2) & ct_eq(e1, e2),
+            (
+                Self::Ecc {
+                    curve: c1,
+                    x: x1,
+                    y: y1,
+                },
+                PublicKeyParams::Ecc {
+                    curve: c2,
+                    x: x2,
+                    y: y2,
+                },
+            ) => (c1 == c2) & ct_eq(x1, x2) & ct_eq(y1, y2),
+            _ => false,
+        }
+    }
+}
+
+/// The length of a key fingerprint, in bytes.
+///
+/// See [`PublicKeyParams::fingerprint()`].
+pub const FINGERPRINT_LEN: usize = hash::Algo::Sha256.bytes();
+
+/// Strips the leading zero bytes off of the big-endian integer `int`.
+fn strip_zeros(int: &[u8]) -> &[u8] {
+    let start = int.iter().position(|&b| b != 0).unwrap_or(int.len());
+    &int[start..]
+}
+
+/// Hashes the big-endian integer `int` as a component of a key's canonical
+/// encoding.
+fn write_component<E: hash::Engine + ?Sized>(
+    h: &mut hash::Hasher<&mut E>,
+    int: &[u8],
+) -> Result<(), hash::Error> {
+    let int = strip_zeros(int);
+    let len: u32 = int
+        .len()
+        .try_into()
+        .map_err(|_| fail!(hash::Error::Unspecified))?;
+    h.write(&len.to_be_bytes())?;
+    h.write(int)
+}
+
+/// Compares the big-endian integers `a` and `b`.
+///
+/// Only the comparison of their stripped forms is constant-time; how long it
+/// takes to strip them depends on how many leading zeros each has.
+fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    let (a, b) = (strip_zeros(a), strip_zeros(b));
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
 }
 
 /// An elliptic curve used in e.g. ECDSA.
@@ -111,6 +724,23 @@ pub enum Curve {
     NistP256,
 }
 
+impl Curve {
+    /// Returns whether `(x, y)`, given as big-endian coordinates, is a point
+    /// on this curve.
+    ///
+    /// Untrusted points must be validated before use; operating on a point
+    /// that is not on the expected curve can leak secrets (the so-called
+    /// "invalid curve attack").
+    pub fn is_on_curve(self, x: &[u8], y: &[u8]) -> bool {
+        match self {
+            Self::NistP256 => match (x.try_into(), y.try_into()) {
+                (Ok(x), Ok(y)) => crypto::p256::is_on_curve(x, y),
+                _ => false,
+            },
+        }
+    }
+}
+
 /// A signature algorithm for a certificate subject key.
 ///
 /// Each variant of this enum captures all parameters of the algorithm.
@@ -171,3 +801,363 @@ impl Ciphers for NoVerify {
         Some(self)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A `RemoteKey` that "signs" by XOR-ing the message together.
+    struct XorKey {
+        calls: usize,
+    }
+
+    impl RemoteKey for XorKey {
+        fn public_key(&self) -> PublicKeyParams {
+            PublicKeyParams::Ecc {
+                curve: Curve::NistP256,
+                x: &[1; 32],
+                y: &[2; 32],
+            }
+        }
+
+        fn sig_bytes(&self) -> usize {
+            4
+        }
+
+        fn sign_remote(
+            &mut self,
+            message_vec: &[&[u8]],
+            signature: &mut [u8],
+        ) -> Result<usize, Error> {
+            self.calls += 1;
+            signature[..4].copy_from_slice(&[0; 4]);
+            for (i, &b) in message_vec.iter().copied().flatten().enumerate() {
+                signature[i % 4] ^= b;
+            }
+            Ok(4)
+        }
+    }
+
+    #[test]
+    fn remote_sign() {
+        let mut signer = RemoteSign::new(XorKey { calls: 0 });
+        assert!(signer.public_key().is_params_for(Algo::EcdsaDerP256));
+
+        let mut sig = [0; 4];
+        let len = signer.sign(&[&[1, 2], &[3, 4, 5]], &mut sig).unwrap();
+        assert_eq!(&sig[..len], &[1 ^ 5, 2, 3, 4]);
+        assert_eq!(signer.key().calls, 1);
+
+        let mut short = [0; 3];
+        assert!(signer.sign(&[&[1]], &mut short).is_err());
+        assert_eq!(signer.key().calls, 1);
+    }
+
+    #[test]
+    fn any_signer() {
+        use enumflags2::BitFlags;
+
+        let mut rsa = RemoteSign::new(XorKey { calls: 0 });
+        let mut ecdsa = RemoteSign::new(XorKey { calls: 0 });
+        let mut signers =
+            [AnySigner::Rsa(&mut rsa), AnySigner::Ecdsa(&mut ecdsa)];
+        assert_eq!(signers[0].key_type(), KeyType::Rsa);
+        assert_eq!(signers[1].key_type(), KeyType::Ecdsa);
+
+        let mut sig = [0; 4];
+        let signer = &mut signers[1];
+        assert_eq!(signer.sig_bytes(), 4);
+        let len = signer.sign(&[&[1, 2, 3, 4]], &mut sig).unwrap();
+        assert_eq!(&sig[..len], &[1, 2, 3, 4]);
+        assert_eq!(ecdsa.key().calls, 1);
+        assert_eq!(rsa.key().calls, 0);
+
+        let caps = capabilities::Crypto {
+            has_ecdsa: false,
+            has_ecc: false,
+            has_rsa: true,
+            has_aes: false,
+            ecc_strength: BitFlags::empty(),
+            rsa_strength: BitFlags::empty(),
+            aes_strength: BitFlags::empty(),
+            hash_algos: capabilities::HashAlgos::default(),
+        };
+        assert!(KeyType::Rsa.is_negotiated(&caps));
+        assert!(!KeyType::Ecdsa.is_negotiated(&caps));
+    }
+
+    /// A `Verify` whose "signatures" are just the message digest.
+    struct DigestVerify;
+
+    impl Verify for DigestVerify {
+        fn verify(&mut self, _: &[&[u8]], _: &[u8]) -> Result<(), Error> {
+            Err(fail!(Error::Unsupported))
+        }
+
+        fn verify_prehashed(
+            &mut self,
+            _: hash::Algo,
+            digest: &[u8],
+            signature: &[u8],
+        ) -> Result<(), Error> {
+            check!(digest == signature, Error::Unspecified);
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "ring", feature = "std"))]
+    fn stream() {
+        use crate::crypto::hash::EngineExt as _;
+        use crate::crypto::ring;
+
+        // A few megabytes of xorshift output, with an odd length so that the
+        // last chunk is short.
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let image = (0..3 * 1024 * 1024 + 17)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect::<Vec<_>>();
+
+        let mut hasher = ring::hash::Engine::new();
+        let mut sig = [0; 48];
+        hasher
+            .contiguous_hash(hash::Algo::Sha384, &image, &mut sig)
+            .unwrap();
+
+        let algo = hash::Algo::Sha384;
+        verify_stream(
+            &mut DigestVerify,
+            &mut hasher,
+            algo,
+            &sig,
+            &mut &image[..],
+        )
+        .unwrap();
+
+        let mut tampered = image.clone();
+        tampered[1024 * 1024] ^= 1;
+        assert!(verify_stream(
+            &mut DigestVerify,
+            &mut hasher,
+            algo,
+            &sig,
+            &mut &tampered[..]
+        )
+        .is_err());
+
+        // Engines without prehashed support refuse outright.
+        assert_eq!(
+            verify_stream(
+                &mut NoPrehash,
+                &mut hasher,
+                algo,
+                &sig,
+                &mut &image[..]
+            )
+            .unwrap_err()
+            .into_inner(),
+            Error::Unsupported
+        );
+    }
+
+    /// A `Verify` that only implements the required methods.
+    struct NoPrehash;
+
+    impl Verify for NoPrehash {
+        fn verify(&mut self, _: &[&[u8]], _: &[u8]) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    /// A `Verify` that accepts exactly one signature, counting its calls.
+    struct OneSig {
+        sig: &'static [u8],
+        calls: usize,
+        err: Error,
+    }
+
+    impl Verify for OneSig {
+        fn verify(&mut self, _: &[&[u8]], sig: &[u8]) -> Result<(), Error> {
+            self.calls += 1;
+            check!(sig == self.sig, self.err);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn any_key() {
+        let one = |sig| OneSig {
+            sig,
+            calls: 0,
+            err: Error::Unspecified,
+        };
+        let (mut old, mut new, mut other) =
+            (one(b"old"), one(b"new"), one(b"x"));
+
+        let mut keys: [&mut dyn Verify; 3] = [&mut old, &mut new, &mut other];
+        assert_eq!(verify_any(&mut keys, &[b"msg"], b"new").unwrap(), 1);
+        assert_eq!(verify_any(&mut keys, &[b"msg"], b"old").unwrap(), 0);
+        assert_eq!(
+            verify_any(&mut keys, &[b"msg"], b"bad")
+                .unwrap_err()
+                .into_inner(),
+            Error::Unspecified
+        );
+        // Every key is tried every time, regardless of which one matched.
+        assert_eq!((old.calls, new.calls, other.calls), (3, 3, 3));
+
+        let mut busy = OneSig {
+            err: Error::Busy,
+            ..one(b"busy")
+        };
+        let mut keys: [&mut dyn Verify; 2] = [&mut old, &mut busy];
+        assert_eq!(
+            verify_any(&mut keys, &[b"msg"], b"bad")
+                .unwrap_err()
+                .into_inner(),
+            Error::Busy
+        );
+        assert!(verify_any(&mut [], &[b"msg"], b"old").is_err());
+    }
+
+    /// A `RemoteKey` whose signatures are longer than any `SigBuf`.
+    struct HugeKey;
+
+    impl RemoteKey for HugeKey {
+        fn public_key(&self) -> PublicKeyParams {
+            PublicKeyParams::Rsa {
+                modulus: &[0xff; 1024],
+                exponent: &[1, 0, 1],
+            }
+        }
+
+        fn sig_bytes(&self) -> usize {
+            1024
+        }
+
+        fn sign_remote(
+            &mut self,
+            _: &[&[u8]],
+            signature: &mut [u8],
+        ) -> Result<usize, Error> {
+            Ok(signature.len())
+        }
+    }
+
+    #[test]
+    fn fingerprint() {
+        use crate::crypto::hash::EngineExt as _;
+        use crate::crypto::ring;
+
+        let mut hasher = ring::hash::Engine::new();
+        let key = PublicKeyParams::Rsa {
+            modulus: &[0xc3, 0x5a],
+            exponent: &[0x01, 0x00, 0x01],
+        };
+        let padded = PublicKeyParams::Rsa {
+            modulus: &[0x00, 0xc3, 0x5a],
+            exponent: &[0x00, 0x00, 0x01, 0x00, 0x01],
+        };
+        let other = PublicKeyParams::Rsa {
+            modulus: &[0xc3, 0x5b],
+            exponent: &[0x01, 0x00, 0x01],
+        };
+
+        let fp = key.fingerprint(&mut hasher).unwrap();
+        let mut want = [0; FINGERPRINT_LEN];
+        hasher
+            .contiguous_hash(
+                hash::Algo::Sha256,
+                &[
+                    0x01, // RSA.
+                    0x00, 0x00, 0x00, 0x02, 0xc3, 0x5a, // n.
+                    0x00, 0x00, 0x00, 0x03, 0x01, 0x00, 0x01, // e.
+                ],
+                &mut want,
+            )
+            .unwrap();
+        assert_eq!(fp, want);
+        assert_eq!(padded.fingerprint(&mut hasher).unwrap(), fp);
+        assert_ne!(other.fingerprint(&mut hasher).unwrap(), fp);
+
+        assert!(key.eq_key(&padded));
+        assert!(!key.eq_key(&other));
+
+        let ecc = PublicKeyParams::Ecc {
+            curve: Curve::NistP256,
+            x: &[0xc3, 0x5a],
+            y: &[0x01, 0x00, 0x01],
+        };
+        assert!(ecc.eq_key(&ecc));
+        assert!(!ecc.eq_key(&key));
+        assert_ne!(ecc.fingerprint(&mut hasher).unwrap(), fp);
+    }
+
+    #[test]
+    fn sig_buf() {
+        let mut signer = RemoteSign::new(XorKey { calls: 0 });
+        let sig = SigBuf::sign(&mut signer, &[&[1, 2], &[3, 4, 5]]).unwrap();
+        assert_eq!(sig.as_ref(), &[1 ^ 5, 2, 3, 4]);
+
+        let sig = SigBuf::for_rsa(capabilities::RsaKeyStrength::Bits3072);
+        assert_eq!(sig.len(), 384);
+
+        let mut huge = RemoteSign::new(HugeKey);
+        assert_eq!(
+            SigBuf::sign(&mut huge, &[&[1]]).unwrap_err().into_inner(),
+            Error::Unsupported
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "log")]
+    fn logging_verify() {
+        use crate::debug;
+        use crate::TraceLevel;
+        use std::cell::RefCell;
+        use std::fmt;
+        use std::string::String;
+        use std::string::ToString as _;
+        use std::vec::Vec;
+
+        std::thread_local! {
+            static CAPTURED: RefCell<Vec<String>> = RefCell::new(Vec::new());
+        }
+        fn capture(level: TraceLevel, args: fmt::Arguments) {
+            if level == TraceLevel::Trace {
+                CAPTURED.with(|c| c.borrow_mut().push(args.to_string()));
+            }
+        }
+
+        let mut verifier = LoggingVerify::new(OneSig {
+            sig: b"good",
+            calls: 0,
+            err: Error::Unspecified,
+        });
+        debug::with_trace_hook(capture, || {
+            verifier.verify(&[b"hunter", b"2"], b"good").unwrap();
+            assert!(verifier.verify(&[b"hunter2"], b"wrong!").is_err());
+        });
+        assert_eq!(verifier.into_inner().calls, 2);
+
+        let lines = CAPTURED.with(|c| c.take());
+        assert_eq!(
+            lines,
+            &[
+                "verify: message_len = 7, sig_len = 4, key_bits = None: \
+                 Ok(())",
+                "verify: message_len = 7, sig_len = 6, key_bits = None: \
+                 Err(Unspecified)",
+            ]
+        );
+        // Neither the message nor the signature make it into the log.
+        assert!(lines
+            .iter()
+            .all(|l| !l.contains("hunter") && !l.contains("wrong")));
+    }
+}
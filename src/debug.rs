@@ -10,11 +10,16 @@
 //!
 //! Manticore code *should not* call into the [`log`] crate directly outside of
 //! this module.
+//!
+//! Integrators can filter or redirect these diagnostics, independently of the
+//! [`log`] crate, with [`set_trace_level()`] and [`set_trace_hook()`].
 
 // TODO: Remove this once we start using these macros in Manticore.
 #![allow(unused)]
 
 use core::fmt;
+use core::sync::atomic::AtomicU8;
+use core::sync::atomic::Ordering;
 
 #[cfg(doc)]
 use __raw_log as log;
@@ -211,12 +216,121 @@ macro_rules! fail {
     }};
 }
 
+/// A verbosity level for Manticore's internal diagnostics.
+///
+/// Levels are ordered from least to most verbose.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TraceLevel {
+    /// Errors, such as those generated by [`fail!()`].
+    Error = 1,
+    /// Unexpected, but recoverable, conditions.
+    Warn = 2,
+    /// Routine operations, such as a request being processed.
+    Info = 3,
+    /// Fine-grained tracing.
+    Trace = 4,
+}
+
+/// A sink for Manticore's internal diagnostics.
+///
+/// See [`set_trace_hook()`].
+pub type TraceHook = fn(TraceLevel, fmt::Arguments);
+
+/// The most verbose level that is not filtered out, or zero if every
+/// diagnostic is.
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(TraceLevel::Trace as u8);
+
+/// The current `TraceHook`, or `None` for the default.
+static HOOK: spin::RwLock<Option<TraceHook>> = spin::RwLock::new(None);
+
+/// Routes Manticore's internal diagnostics to `hook`, rather than to the
+/// [`log`] crate.
+///
+/// Passing `None` restores the default, which is to log via [`log`]. This
+/// allows integrators to silence or redirect Manticore's diagnostics without
+/// tuning the global [`log`] filter.
+///
+/// If the `log` feature is disabled, all diagnostics are redacted, and the
+/// hook is never called.
+pub fn set_trace_hook(hook: Option<TraceHook>) {
+    *HOOK.write() = hook;
+}
+
+/// Sets the most verbose [`TraceLevel`] of diagnostic that Manticore emits.
+///
+/// Diagnostics more verbose than `max` are discarded before reaching either
+/// the [`log`] crate or a hook set with [`set_trace_hook()`]; passing `None`
+/// discards all of them. By default, every diagnostic is emitted.
+pub fn set_trace_level(max: Option<TraceLevel>) {
+    MAX_LEVEL.store(max.map(|l| l as u8).unwrap_or(0), Ordering::Relaxed);
+}
+
+/// Returns whether diagnostics at `level` should be emitted.
+#[doc(hidden)]
+#[inline]
+pub fn __enabled(level: TraceLevel) -> bool {
+    level as u8 <= MAX_LEVEL.load(Ordering::Relaxed)
+}
+
+/// Returns the current `TraceHook`, if one was set.
+#[doc(hidden)]
+#[inline]
+pub fn __hook() -> Option<TraceHook> {
+    *HOOK.read()
+}
+
+/// Runs `f` with `hook` installed as the trace hook, restoring the default
+/// afterwards.
+///
+/// The hook is global, so tests that install one must go through this
+/// function, which runs them one at a time.
+#[cfg(all(test, feature = "log"))]
+pub(crate) fn with_trace_hook<R>(hook: TraceHook, f: impl FnOnce() -> R) -> R {
+    use core::sync::atomic::AtomicBool;
+
+    static LOCKED: AtomicBool = AtomicBool::new(false);
+    struct Unlock;
+    impl Drop for Unlock {
+        fn drop(&mut self) {
+            set_trace_hook(None);
+            LOCKED.store(false, Ordering::Release);
+        }
+    }
+
+    while LOCKED
+        .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        std::thread::yield_now();
+    }
+    let _unlock = Unlock;
+    set_trace_hook(Some(hook));
+    f()
+}
+
+/// Logs a diagnostic at the given level, either via the current hook or via
+/// the given [`log`] macro.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_at {
+    ($level:ident, $raw:ident, $($args:tt)*) => {
+        #[cfg(feature = "log")]
+        let _ = if $crate::debug::__enabled($crate::TraceLevel::$level) {
+            match $crate::debug::__hook() {
+                Some(hook) => {
+                    hook($crate::TraceLevel::$level, format_args!($($args)*))
+                }
+                None => __raw_log::$raw!($($args)*),
+            }
+        };
+    }
+}
+
 /// Redactable version of [`log::trace!()`].
 #[macro_export]
 macro_rules! trace {
     ($($args:tt)*) => {
-        #[cfg(feature = "log")]
-        let _ = __raw_log::trace!($($args)*);
+        $crate::__log_at!(Trace, trace, $($args)*);
     }
 }
 
@@ -224,8 +338,7 @@ macro_rules! trace {
 #[macro_export]
 macro_rules! info {
     ($($args:tt)*) => {
-        #[cfg(feature = "log")]
-        let _ = __raw_log::info!($($args)*);
+        $crate::__log_at!(Info, info, $($args)*);
     }
 }
 
@@ -233,8 +346,7 @@ macro_rules! info {
 #[macro_export]
 macro_rules! warn {
     ($($args:tt)*) => {
-        #[cfg(feature = "log")]
-        let _ = __raw_log::warn!($($args)*);
+        $crate::__log_at!(Warn, warn, $($args)*);
     }
 }
 
@@ -242,8 +354,7 @@ macro_rules! warn {
 #[macro_export]
 macro_rules! error {
     ($($args:tt)*) => {
-        #[cfg(feature = "log")]
-        let _ = __raw_log::error!($($args)*);
+        $crate::__log_at!(Error, error, $($args)*);
     }
 }
 
@@ -278,3 +389,51 @@ fn init_test_logger() {
         })
         .init();
 }
+
+#[cfg(all(test, feature = "log"))]
+mod test {
+    use super::*;
+
+    use std::cell::RefCell;
+    use std::string::String;
+    use std::string::ToString as _;
+    use std::vec::Vec;
+
+    std::thread_local! {
+        // Other tests may be logging concurrently, so only capture lines
+        // logged by this test's thread.
+        static CAPTURED: RefCell<Vec<(TraceLevel, String)>> =
+            RefCell::new(Vec::new());
+    }
+
+    fn capture(level: TraceLevel, args: fmt::Arguments) {
+        CAPTURED.with(|c| c.borrow_mut().push((level, args.to_string())));
+    }
+
+    #[test]
+    fn trace_hook() {
+        with_trace_hook(capture, || {
+            info!("trace_hook: {}", 1);
+            error!("trace_hook: {}", 2);
+
+            set_trace_level(Some(TraceLevel::Error));
+            info!("trace_hook: {}", 3);
+            error!("trace_hook: {}", 4);
+
+            set_trace_level(None);
+            error!("trace_hook: {}", 5);
+
+            set_trace_level(Some(TraceLevel::Trace));
+        });
+        info!("trace_hook: {}", 6);
+
+        assert_eq!(
+            CAPTURED.with(|c| c.take()),
+            &[
+                (TraceLevel::Info, "trace_hook: 1".to_string()),
+                (TraceLevel::Error, "trace_hook: 2".to_string()),
+                (TraceLevel::Error, "trace_hook: 4".to_string()),
+            ]
+        );
+    }
+}
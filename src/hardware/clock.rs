@@ -0,0 +1,190 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Monotonic time sources.
+//!
+//! Rate limits, read budgets, and session lifetimes all need to know what time
+//! it is. Rather than depending on `std::time::Instant`, `manticore` asks a
+//! [`Clock`], so that bare-metal integrations can plug in their own tick
+//! source, and tests can control time directly with a [`MockClock`].
+
+use core::cell::Cell;
+use core::time::Duration;
+
+/// A point in time, as reported by a [`Clock`].
+///
+/// Ticks are microseconds since some arbitrary, fixed epoch, such as the time
+/// the device was last released from reset.
+pub type Ticks = u64;
+
+/// Converts a [`Duration`] into a number of [`Ticks`], saturating on
+/// overflow.
+pub fn ticks(d: Duration) -> Ticks {
+    let micros = d.as_micros();
+    if micros > Ticks::MAX as u128 {
+        return Ticks::MAX;
+    }
+    micros as Ticks
+}
+
+/// A point in time by which some piece of work should be finished.
+///
+/// Deadlines are cooperative, not preemptive: nothing interrupts work that
+/// runs past one. Instead, long-running code should check
+/// [`Deadline::has_passed()`] before starting each expensive step, and give up
+/// if it returns `true`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Deadline {
+    at: Ticks,
+}
+
+impl Deadline {
+    /// A deadline that never passes.
+    pub const NEVER: Self = Self { at: Ticks::MAX };
+
+    /// Returns a deadline `d` from the current time, according to `clock`.
+    pub fn after(clock: &dyn Clock, d: Duration) -> Self {
+        Self {
+            at: clock.now().saturating_add(ticks(d)),
+        }
+    }
+
+    /// Returns whether this deadline has passed, according to `clock`.
+    pub fn has_passed(self, clock: &dyn Clock) -> bool {
+        self != Self::NEVER && clock.now() >= self.at
+    }
+
+    /// Returns how much time is left before this deadline passes, according
+    /// to `clock`.
+    pub fn remaining(self, clock: &dyn Clock) -> Duration {
+        Duration::from_micros(self.at.saturating_sub(clock.now()))
+    }
+}
+
+/// A monotonic time source.
+pub trait Clock {
+    /// Returns the current time.
+    ///
+    /// Successive calls must never go backwards.
+    fn now(&self) -> Ticks;
+}
+impl dyn Clock {} // Ensure object-safe.
+
+/// A [`Clock`] backed by [`std::time::Instant`], whose epoch is the moment
+/// it was created.
+#[cfg(feature = "std")]
+pub struct SystemClock {
+    epoch: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl SystemClock {
+    /// Creates a new `SystemClock`, starting at zero.
+    pub fn new() -> Self {
+        Self {
+            epoch: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> Ticks {
+        ticks(self.epoch.elapsed())
+    }
+}
+
+/// A [`Clock`] that only moves when told to, for testing time-dependent
+/// behavior deterministically.
+#[derive(Default, Debug)]
+pub struct MockClock {
+    now: Cell<Ticks>,
+}
+
+impl MockClock {
+    /// Creates a new `MockClock`, starting at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the clock forward by `d`.
+    pub fn advance(&self, d: Duration) {
+        self.now.set(self.now.get().saturating_add(ticks(d)));
+    }
+
+    /// Sets the clock to `now`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `now` is earlier than the current time.
+    pub fn set(&self, now: Ticks) {
+        assert!(now >= self.now.get(), "MockClock cannot go backwards");
+        self.now.set(now);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Ticks {
+        self.now.get()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mock_clock() {
+        let clock = MockClock::new();
+        assert_eq!(clock.now(), 0);
+        clock.advance(Duration::from_millis(3));
+        assert_eq!(clock.now(), 3000);
+        clock.set(5000);
+        assert_eq!(clock.now(), 5000);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mock_clock_is_monotonic() {
+        let clock = MockClock::new();
+        clock.set(10);
+        clock.set(9);
+    }
+
+    #[test]
+    fn system_clock() {
+        let clock = SystemClock::new();
+        let a = clock.now();
+        let b = clock.now();
+        assert!(b >= a);
+    }
+
+    #[test]
+    fn deadline() {
+        let clock = MockClock::new();
+        let deadline = Deadline::after(&clock, Duration::from_millis(5));
+        assert!(!deadline.has_passed(&clock));
+        assert_eq!(deadline.remaining(&clock), Duration::from_millis(5));
+
+        clock.advance(Duration::from_millis(5));
+        assert!(deadline.has_passed(&clock));
+        assert_eq!(deadline.remaining(&clock), Duration::from_secs(0));
+
+        clock.set(Ticks::MAX);
+        assert!(!Deadline::NEVER.has_passed(&clock));
+    }
+
+    #[test]
+    fn saturating_ticks() {
+        assert_eq!(ticks(Duration::from_secs(1)), 1_000_000);
+        assert_eq!(ticks(Duration::from_secs(u64::MAX)), Ticks::MAX);
+    }
+}
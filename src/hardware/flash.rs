@@ -483,14 +483,14 @@ impl<F: Flash> Iterator for FlashIo<F> {
 }
 
 impl<F: Flash> io::Write for FlashIo<F> {
-    fn write_bytes(&mut self, buf: &[u8]) -> Result<(), io::Error> {
+    fn write_bytes(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
         check!(self.remaining_data() != 0, io::Error::BufferExhausted);
 
         self.flash
             .program(self.cursor, buf)
             .map_err(|_| fail!(io::Error::Internal))?;
         self.cursor += buf.len() as u32;
-        Ok(())
+        Ok(buf.len())
     }
 }
 
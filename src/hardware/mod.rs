@@ -11,6 +11,7 @@
 
 use core::time::Duration;
 
+pub mod clock;
 pub mod flash;
 
 /// Provides access to "chip identity" information of various types.
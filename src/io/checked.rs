@@ -0,0 +1,181 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Debug-only length accounting for [`Read`]ers.
+//!
+//! A [`Read`] promises, through [`Read::remaining_data()`], exactly how many
+//! bytes can still be read from it. If that promise is broken, or if a parser
+//! reads past the end of the payload it was handed, framing silently desyncs:
+//! the excess bytes are taken from whatever follows in the underlying stream.
+//!
+//! [`CheckedRead`] wraps a reader and asserts that neither of these happens.
+//! The checks are only performed when `debug_assertions` are enabled; in
+//! release builds, `CheckedRead` is a zero-cost passthrough.
+
+use core::alloc::Layout;
+
+use crate::io;
+use crate::io::Read;
+use crate::io::ReadZero;
+use crate::mem::Arena;
+use crate::Result;
+
+/// A [`Read`] wrapper that asserts that reads are accounted for correctly.
+///
+/// In debug builds, every successful read is checked to
+/// - request no more than the wrapped reader's `remaining_data()`,
+/// - decrease `remaining_data()` by exactly the number of bytes read, and
+/// - keep the total number of bytes read within the declared payload length.
+///
+/// Failed reads, such as those caused by truncated input, are not treated as
+/// bugs, which makes this type suitable for use while fuzzing.
+pub struct CheckedRead<R> {
+    inner: R,
+    #[cfg(debug_assertions)]
+    declared: usize,
+    #[cfg(debug_assertions)]
+    consumed: usize,
+}
+
+impl<R: Read> CheckedRead<R> {
+    /// Wraps `inner`, taking its current `remaining_data()` as the declared
+    /// payload length.
+    pub fn new(inner: R) -> Self {
+        let len = inner.remaining_data();
+        Self::with_len(inner, len)
+    }
+
+    /// Wraps `inner`, declaring that no more than `len` bytes should be read
+    /// from it.
+    pub fn with_len(inner: R, #[allow(unused)] len: usize) -> Self {
+        Self {
+            inner,
+            #[cfg(debug_assertions)]
+            declared: len,
+            #[cfg(debug_assertions)]
+            consumed: 0,
+        }
+    }
+
+    /// Unwraps this `CheckedRead`, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Performs a read of `n` bytes via `read`, checking its accounting.
+    #[inline]
+    fn checked<T>(
+        &mut self,
+        #[allow(unused)] n: usize,
+        read: impl FnOnce(&mut R) -> Result<T, io::Error>,
+    ) -> Result<T, io::Error> {
+        #[cfg(debug_assertions)]
+        let before = self.inner.remaining_data();
+
+        let val = read(&mut self.inner)?;
+
+        #[cfg(debug_assertions)]
+        {
+            let after = self.inner.remaining_data();
+            assert!(
+                n <= before,
+                "read {} bytes, but only {} were available",
+                n,
+                before
+            );
+            assert_eq!(
+                before - n,
+                after,
+                "reading {} bytes changed remaining_data() from {} to {}",
+                n,
+                before,
+                after
+            );
+
+            self.consumed += n;
+            assert!(
+                self.consumed <= self.declared,
+                "read {} bytes past the end of a {}-byte payload",
+                self.consumed - self.declared,
+                self.declared
+            );
+        }
+        Ok(val)
+    }
+}
+
+impl<R: Read> Read for CheckedRead<R> {
+    fn read_bytes(&mut self, out: &mut [u8]) -> Result<(), io::Error> {
+        self.checked(out.len(), |r| r.read_bytes(out))
+    }
+
+    fn remaining_data(&self) -> usize {
+        self.inner.remaining_data()
+    }
+}
+
+#[allow(unsafe_code)]
+unsafe impl<'a, R: ReadZero<'a>> ReadZero<'a> for CheckedRead<R> {
+    fn read_direct(
+        &mut self,
+        arena: &'a dyn Arena,
+        layout: Layout,
+    ) -> Result<&'a [u8], io::Error> {
+        self.checked(layout.size(), |r| r.read_direct(arena, layout))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mem::BumpArena;
+
+    /// A reader that under-reports how much it has consumed.
+    struct Leaky<'a>(&'a [u8]);
+    impl Read for Leaky<'_> {
+        fn read_bytes(&mut self, out: &mut [u8]) -> Result<(), io::Error> {
+            self.0.read_bytes(out)?;
+            self.0 = &self.0[self.0.len().min(1)..];
+            Ok(())
+        }
+
+        fn remaining_data(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    #[test]
+    fn passthrough() {
+        let arena = BumpArena::new([0; 8]);
+        let mut r = CheckedRead::new(&[1, 2, 3, 4, 5][..]);
+
+        let mut buf = [0; 2];
+        r.read_bytes(&mut buf).unwrap();
+        assert_eq!(buf, [1, 2]);
+        assert_eq!(
+            r.read_direct(&arena, Layout::new::<u16>()).unwrap().len(),
+            2
+        );
+        assert_eq!(r.remaining_data(), 1);
+
+        // Over-reading an honest reader is an ordinary error.
+        assert!(r.read_bytes(&mut buf).is_err());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "changed remaining_data()")]
+    fn bad_accounting() {
+        let mut r = CheckedRead::new(Leaky(&[1, 2, 3, 4]));
+        let _ = r.read_bytes(&mut [0; 1]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "past the end")]
+    fn over_read() {
+        let mut r = CheckedRead::with_len(&[1, 2, 3, 4][..], 2);
+        let _ = r.read_bytes(&mut [0; 3]);
+    }
+}
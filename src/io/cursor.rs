@@ -19,7 +19,7 @@
 //! # struct MyMessage;
 //! # impl ToWire for MyMessage {
 //! #     fn to_wire<W: Write>(&self, mut w: W) -> Result<(), wire::Error> {
-//! #         w.write_bytes(&[1, 2, 3, 4]);
+//! #         w.write_all(&[1, 2, 3, 4]);
 //! #         Ok(())
 //! #     }
 //! # }
@@ -112,11 +112,11 @@ impl<'a> Cursor<'a> {
     /// # use manticore::io::cursor::*;
     /// # let mut buf = [0; 16];
     /// let mut cursor = Cursor::new(&mut buf);
-    /// cursor.write_bytes(b"barbar")?;
+    /// cursor.write_all(b"barbar")?;
     ///
     /// let mark = cursor.consumed_len();
     /// cursor.seek(SeekPos::Rel(-5))?;
-    /// cursor.write_bytes(b"foo")?;
+    /// cursor.write_all(b"foo")?;
     /// cursor.seek(SeekPos::Abs(mark))?;
     /// assert_eq!(cursor.consumed_bytes(), b"bfooar");
     ///
@@ -143,6 +143,11 @@ impl<'a> Cursor<'a> {
         self.cursor
     }
 
+    /// Returns the number of bytes left to consume.
+    pub fn remaining_len(&self) -> usize {
+        self.buf.len() - self.cursor
+    }
+
     /// Returns the portion of the buffer which has been consumed thus far.
     pub fn consumed_bytes(&self) -> &[u8] {
         &self.buf[..self.cursor]
@@ -169,10 +174,10 @@ impl<'a> Cursor<'a> {
 }
 
 impl Write for Cursor<'_> {
-    fn write_bytes(&mut self, buf: &[u8]) -> Result<(), io::Error> {
+    fn write_bytes(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
         let dest = self.consume(buf.len())?;
         dest.copy_from_slice(buf);
-        Ok(())
+        Ok(buf.len())
     }
 }
 
@@ -191,7 +196,8 @@ mod test {
         let bytes = cursor.take_consumed_bytes();
         assert_eq!(bytes, &[0xaa, 0xff, 0xaa, 0xff]);
         assert_eq!(cursor.consumed_len(), 0);
+        assert_eq!(cursor.remaining_len(), 4);
 
-        assert!(cursor.write_bytes(&[0x55; 7]).is_err());
+        assert!(cursor.write_all(&[0x55; 7]).is_err());
     }
 }
@@ -15,6 +15,9 @@ use crate::Result;
 
 /// A little-endian integer, which can be read and written.
 ///
+/// Cerberus payloads are little-endian; this is the trait to use for almost
+/// all message fields.
+///
 /// This trait can be used for operating generically over little-endian integer
 /// I/O.
 pub trait LeInt: Sized + Copy {
@@ -35,7 +38,7 @@ impl LeInt for u8 {
 
     #[inline]
     fn write_to<W: Write>(self, mut w: W) -> Result<(), io::Error> {
-        w.write_bytes(&[self])
+        w.write_all(&[self])
     }
 }
 
@@ -55,7 +58,7 @@ impl LeInt for u16 {
 
         let mut bytes = [0; mem::size_of::<Self>()];
         byteorder::LE::write_u16(&mut bytes, self);
-        w.write_bytes(&bytes)
+        w.write_all(&bytes)
     }
 }
 
@@ -75,7 +78,7 @@ impl LeInt for u32 {
 
         let mut bytes = [0; mem::size_of::<Self>()];
         byteorder::LE::write_u32(&mut bytes, self);
-        w.write_bytes(&bytes)
+        w.write_all(&bytes)
     }
 }
 
@@ -95,6 +98,93 @@ impl LeInt for u64 {
 
         let mut bytes = [0; mem::size_of::<Self>()];
         byteorder::LE::write_u64(&mut bytes, self);
-        w.write_bytes(&bytes)
+        w.write_all(&bytes)
+    }
+}
+
+/// A big-endian (i.e., network order) integer, which can be read and
+/// written.
+///
+/// This trait can be used for operating generically over big-endian integer
+/// I/O, such as for transport headers.
+pub trait BeInt: Sized + Copy {
+    /// Reads a value of type `Self`, in big-endian order.
+    fn read_from<R: Read>(r: R) -> Result<Self, io::Error>;
+
+    /// Writes a value of type `Self`, in big-endian order.
+    fn write_to<W: Write>(self, w: W) -> Result<(), io::Error>;
+}
+
+impl BeInt for u8 {
+    #[inline]
+    fn read_from<R: Read>(mut r: R) -> Result<Self, io::Error> {
+        let mut bytes = [0; mem::size_of::<Self>()];
+        r.read_bytes(&mut bytes)?;
+        Ok(bytes[0])
+    }
+
+    #[inline]
+    fn write_to<W: Write>(self, mut w: W) -> Result<(), io::Error> {
+        w.write_all(&[self])
+    }
+}
+
+impl BeInt for u16 {
+    #[inline]
+    fn read_from<R: Read>(mut r: R) -> Result<Self, io::Error> {
+        use byteorder::ByteOrder as _;
+
+        let mut bytes = [0; mem::size_of::<Self>()];
+        r.read_bytes(&mut bytes)?;
+        Ok(byteorder::BE::read_u16(&bytes))
+    }
+
+    #[inline]
+    fn write_to<W: Write>(self, mut w: W) -> Result<(), io::Error> {
+        use byteorder::ByteOrder as _;
+
+        let mut bytes = [0; mem::size_of::<Self>()];
+        byteorder::BE::write_u16(&mut bytes, self);
+        w.write_all(&bytes)
+    }
+}
+
+impl BeInt for u32 {
+    #[inline]
+    fn read_from<R: Read>(mut r: R) -> Result<Self, io::Error> {
+        use byteorder::ByteOrder as _;
+
+        let mut bytes = [0; mem::size_of::<Self>()];
+        r.read_bytes(&mut bytes)?;
+        Ok(byteorder::BE::read_u32(&bytes))
+    }
+
+    #[inline]
+    fn write_to<W: Write>(self, mut w: W) -> Result<(), io::Error> {
+        use byteorder::ByteOrder as _;
+
+        let mut bytes = [0; mem::size_of::<Self>()];
+        byteorder::BE::write_u32(&mut bytes, self);
+        w.write_all(&bytes)
+    }
+}
+
+impl BeInt for u64 {
+    #[inline]
+    fn read_from<R: Read>(mut r: R) -> Result<Self, io::Error> {
+        use byteorder::ByteOrder as _;
+
+        let mut bytes = [0; mem::size_of::<Self>()];
+        r.read_bytes(&mut bytes)?;
+        Ok(byteorder::BE::read_u64(&bytes))
+    }
+
+    #[inline]
+    fn write_to<W: Write>(self, mut w: W) -> Result<(), io::Error> {
+        use byteorder::ByteOrder as _;
+
+        let mut bytes = [0; mem::size_of::<Self>()];
+        byteorder::BE::write_u64(&mut bytes, self);
+        w.write_all(&bytes)
     }
 }
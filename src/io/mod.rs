@@ -9,11 +9,17 @@
 //! read/write interface.
 
 pub mod bit_buf;
+pub mod checked;
 pub mod cursor;
 pub mod endian;
 pub mod read;
+#[cfg(feature = "compression")]
+pub mod rle;
+#[cfg(feature = "std")]
+pub mod std_compat;
 pub mod write;
 
+pub use checked::CheckedRead;
 pub use cursor::Cursor;
 pub use read::Read;
 pub use read::ReadInt;
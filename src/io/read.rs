@@ -14,6 +14,7 @@ use zerocopy::FromBytes;
 use zerocopy::LayoutVerified;
 
 use crate::io;
+use crate::io::endian::BeInt;
 use crate::io::endian::LeInt;
 use crate::mem::misalign_of;
 use crate::mem::Arena;
@@ -48,6 +49,12 @@ pub impl<R: Read + ?Sized> R {
     fn read_le<I: LeInt>(&mut self) -> Result<I, io::Error> {
         I::read_from(self)
     }
+
+    /// Reads a big-endian integer.
+    #[inline]
+    fn read_be<I: BeInt>(&mut self) -> Result<I, io::Error> {
+        I::read_from(self)
+    }
 }
 
 /// A [`Read`] that may, as an optimization, zero-copy read data for the
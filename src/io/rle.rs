@@ -0,0 +1,185 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A minimal run-length encoding, for compressing message payloads.
+//!
+//! The encoding is the classic "PackBits" scheme: the compressed stream is a
+//! sequence of packets, each starting with a header byte `n`:
+//! - `0x00..=0x7f`: the next `n + 1` bytes are copied verbatim.
+//! - `0x81..=0xff`: the next byte is repeated `257 - n` times.
+//! - `0x80`: a no-op, which is skipped.
+//!
+//! This scheme never expands its input by more than one byte in 128, and is
+//! very effective on the large runs of `0x00` and `0xff` padding found in
+//! manifests and flash images.
+//!
+//! This module is only available with the `compression` feature.
+
+use crate::io;
+use crate::io::Write;
+use crate::Result;
+
+/// The longest run or literal that a single packet can describe.
+const MAX_PACKET: usize = 128;
+
+/// Compresses `input`, writing the encoded packets to `w`.
+pub fn compress(mut input: &[u8], mut w: impl Write) -> Result<(), io::Error> {
+    while let Some(&first) = input.first() {
+        let run = input
+            .iter()
+            .take(MAX_PACKET)
+            .take_while(|&&b| b == first)
+            .count();
+        if run >= 2 {
+            w.write_all(&[(257 - run) as u8, first])?;
+            input = &input[run..];
+            continue;
+        }
+
+        // Extend the literal until we hit the start of a run, which will be
+        // picked up by the next packet instead.
+        let mut lit = 1;
+        while lit < input.len()
+            && lit < MAX_PACKET
+            && input.get(lit + 1) != Some(&input[lit])
+        {
+            lit += 1;
+        }
+        w.write_all(&[(lit - 1) as u8])?;
+        w.write_all(&input[..lit])?;
+        input = &input[lit..];
+    }
+    Ok(())
+}
+
+/// Computes the length of the data that `input` decompresses to, without
+/// actually decompressing it.
+///
+/// This function is useful for sizing a buffer to pass to [`decompress()`].
+pub fn decompressed_len(input: &[u8]) -> Result<usize, io::Error> {
+    let mut len = 0usize;
+    for_each_packet(input, |packet| {
+        len = len
+            .checked_add(packet.len())
+            .ok_or(io::Error::BufferExhausted)?;
+        Ok(())
+    })?;
+    Ok(len)
+}
+
+/// Decompresses `input`, writing the decoded bytes to `w`.
+///
+/// Returns [`io::Error::BufferExhausted`] if `input` is truncated.
+pub fn decompress(input: &[u8], mut w: impl Write) -> Result<(), io::Error> {
+    for_each_packet(input, |packet| match packet {
+        Packet::Literal(bytes) => w.write_all(bytes),
+        Packet::Run(byte, count) => {
+            let run = [byte; MAX_PACKET];
+            w.write_all(&run[..count])
+        }
+    })
+}
+
+/// A decoded PackBits packet.
+enum Packet<'a> {
+    Literal(&'a [u8]),
+    Run(u8, usize),
+}
+
+impl Packet<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Self::Literal(bytes) => bytes.len(),
+            Self::Run(_, count) => *count,
+        }
+    }
+}
+
+/// Calls `f` on each packet in `input`, in order.
+fn for_each_packet<'a>(
+    mut input: &'a [u8],
+    mut f: impl FnMut(Packet<'a>) -> Result<(), io::Error>,
+) -> Result<(), io::Error> {
+    while let Some((&header, rest)) = input.split_first() {
+        input = rest;
+        match header {
+            0x00..=0x7f => {
+                let len = header as usize + 1;
+                check!(input.len() >= len, io::Error::BufferExhausted);
+                f(Packet::Literal(&input[..len]))?;
+                input = &input[len..];
+            }
+            0x80 => {}
+            _ => {
+                let (&byte, rest) = input
+                    .split_first()
+                    .ok_or_else(|| fail!(io::Error::BufferExhausted))?;
+                f(Packet::Run(byte, 257 - header as usize))?;
+                input = rest;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip(data: &[u8]) -> Vec<u8> {
+        let mut compressed = Vec::new();
+        compress(data, io::write::StdWrite(&mut compressed)).unwrap();
+
+        let len = decompressed_len(&compressed).unwrap();
+        assert_eq!(len, data.len());
+
+        let mut decompressed = Vec::new();
+        decompress(&compressed, io::write::StdWrite(&mut decompressed))
+            .unwrap();
+        assert_eq!(decompressed, data);
+        compressed
+    }
+
+    #[test]
+    fn empty() {
+        assert!(round_trip(&[]).is_empty());
+    }
+
+    #[test]
+    fn literals() {
+        let data = (0..=255).collect::<Vec<u8>>();
+        let compressed = round_trip(&data);
+        assert_eq!(compressed.len(), data.len() + 2);
+    }
+
+    #[test]
+    fn mixed() {
+        round_trip(b"aaaaabcdeffffffghhi");
+        round_trip(b"abcdefgg");
+        round_trip(b"zz");
+        round_trip(b"z");
+    }
+
+    #[test]
+    fn manifest() {
+        // A manifest-like image: a small header, a table of short entries,
+        // and a large amount of erased-flash padding.
+        let mut data = Vec::new();
+        data.extend_from_slice(b"\x00\x10\x06\x70\xaa\xbb\xcc\xdd");
+        for i in 0..32u8 {
+            data.extend_from_slice(&[i, 0, 0, 0, 0x10, 0x20, 0, 0]);
+        }
+        data.resize(4096, 0xff);
+
+        let compressed = round_trip(&data);
+        assert!(compressed.len() < data.len() / 8);
+    }
+
+    #[test]
+    fn truncated() {
+        assert!(decompressed_len(&[0x03, 1, 2]).is_err());
+        assert!(decompressed_len(&[0xfe]).is_err());
+        assert_eq!(decompressed_len(&[0x80, 0xfe, 7]).unwrap(), 3);
+    }
+}
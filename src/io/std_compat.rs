@@ -0,0 +1,100 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Adapters from `manticore`'s I/O traits to [`std::io`]'s.
+//!
+//! These allow payloads read through `manticore`, such as the body of a
+//! request pulled off of a transport, to be handed to parsers written against
+//! [`std::io`].
+
+use crate::io;
+
+// This allows us to refer to types via the `manticore` prefix in the
+// doc comments below, which is useful for clarity between `std` and
+// `manticore` IO traits.
+#[cfg(doc)]
+use crate as manticore;
+
+/// Converts a [`manticore::io::Read`] into a [`std::io::Read`].
+///
+/// Since a [`manticore::io::Read`] knows how many bytes it has left, each
+/// [`std::io::Read::read()`] reads as many bytes as fit in the caller's
+/// buffer, and `0`, signaling end-of-file, once
+/// [`manticore::io::Read::remaining_data()`] hits zero.
+///
+/// Errors from the underlying reader are translated into a [`std::io::Error`]
+/// of kind [`UnexpectedEof`] for [`io::Error::BufferExhausted`], and of kind
+/// [`Other`] otherwise.
+///
+/// [`UnexpectedEof`]: std::io::ErrorKind::UnexpectedEof
+/// [`Other`]: std::io::ErrorKind::Other
+pub struct ReadAdapter<R>(pub R);
+
+impl<R: io::Read> std::io::Read for ReadAdapter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(self.0.remaining_data());
+        if len == 0 {
+            return Ok(0);
+        }
+
+        self.0.read_bytes(&mut buf[..len]).map_err(|e| {
+            std::io::Error::from(match e.into_inner() {
+                io::Error::BufferExhausted => std::io::ErrorKind::UnexpectedEof,
+                io::Error::Internal => std::io::ErrorKind::Other,
+            })
+        })?;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::BufRead as _;
+    use std::io::Read as _;
+
+    #[test]
+    fn buf_reader() {
+        let bytes: &[u8] = b"first line\nsecond line\nthe rest";
+        let mut r = std::io::BufReader::with_capacity(4, ReadAdapter(bytes));
+
+        let mut line = String::new();
+        r.read_line(&mut line).unwrap();
+        assert_eq!(line, "first line\n");
+        line.clear();
+        r.read_line(&mut line).unwrap();
+        assert_eq!(line, "second line\n");
+
+        let mut rest = Vec::new();
+        r.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"the rest");
+
+        // The underlying reader is drained, rather than read past.
+        assert_eq!(r.read(&mut [0; 4]).unwrap(), 0);
+        assert!(r.into_inner().0.is_empty());
+    }
+
+    /// A reader that claims to have more data than it does.
+    struct Liar<'a>(&'a [u8]);
+
+    impl io::Read for Liar<'_> {
+        fn read_bytes(
+            &mut self,
+            out: &mut [u8],
+        ) -> crate::Result<(), io::Error> {
+            self.0.read_bytes(out)
+        }
+
+        fn remaining_data(&self) -> usize {
+            self.0.len() + 1
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let mut buf = [0; 8];
+        let err = ReadAdapter(Liar(b"abc")).read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -7,6 +7,7 @@
 use core::mem;
 
 use crate::io;
+use crate::io::endian::BeInt;
 use crate::io::endian::LeInt;
 use crate::Result;
 
@@ -17,11 +18,41 @@ use crate::Result;
 /// more detailed errors. [`StdWrite`] provides an implementation of
 /// `Write` in terms of [`std::io::Write`].
 pub trait Write {
-    /// Attempt to write `buf` exactly to `self`.
+    /// Attempts to write `buf` to `self`, returning the number of bytes
+    /// written.
     ///
-    /// This function does not perform partial writes: it will either block
-    /// until completion or return an error.
-    fn write_bytes(&mut self, buf: &[u8]) -> Result<(), io::Error>;
+    /// This function may perform a partial write, writing only a prefix of
+    /// `buf`, such as when a non-blocking transport can only accept part of
+    /// it at the moment. Returning `Ok(0)` for a nonempty `buf` indicates
+    /// that no further progress can be made.
+    ///
+    /// Most callers want [`Write::write_all()`] instead.
+    fn write_bytes(&mut self, buf: &[u8]) -> Result<usize, io::Error>;
+
+    /// Writes all of `buf` to `self`, calling [`Write::write_bytes()`] until
+    /// every byte is written.
+    ///
+    /// Returns [`io::Error::BufferExhausted`] if `write_bytes()` stops making
+    /// progress, in which case some prefix of `buf` may have been written.
+    fn write_all(&mut self, mut buf: &[u8]) -> Result<(), io::Error> {
+        while !buf.is_empty() {
+            let len = self.write_bytes(buf)?;
+            check!(len != 0, io::Error::BufferExhausted);
+            buf = buf.get(len..).ok_or_else(|| fail!(io::Error::Internal))?;
+        }
+        Ok(())
+    }
+
+    /// Pushes any data `self` has buffered out to its destination.
+    ///
+    /// Servers call this once they have written a response, so that a sink
+    /// that streams data out as it is written does not hold onto the tail of
+    /// it. Sinks that write through immediately, or that only send anything
+    /// once the whole message is known, need not implement this; the default
+    /// implementation does nothing.
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
 
     /// Writes a little-endian integer.
     ///
@@ -36,25 +67,66 @@ pub trait Write {
     {
         val.write_to(self)
     }
+
+    /// Writes a big-endian integer.
+    ///
+    /// # Note
+    /// Do not implement this function yourself. Callers are not required to
+    /// call it in order to actually perform a write, so whether or not it is
+    /// called is an implementation detail.
+    #[inline]
+    fn write_be<I: BeInt>(&mut self, val: I) -> Result<(), io::Error>
+    where
+        Self: Sized,
+    {
+        val.write_to(self)
+    }
 }
 impl dyn Write {} // Ensure object-safety.
 
 impl<W: Write + ?Sized> Write for &'_ mut W {
     #[inline]
-    fn write_bytes(&mut self, buf: &[u8]) -> Result<(), io::Error> {
+    fn write_bytes(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
         W::write_bytes(*self, buf)
     }
+
+    #[inline]
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), io::Error> {
+        W::write_all(*self, buf)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<(), io::Error> {
+        W::flush(*self)
+    }
 }
 
 impl Write for &'_ mut [u8] {
-    fn write_bytes(&mut self, buf: &[u8]) -> Result<(), io::Error> {
+    fn write_bytes(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        // A slice never performs partial writes, so that a write that does
+        // not fit leaves it untouched.
         let n = buf.len();
         check!(self.len() >= n, io::Error::BufferExhausted);
 
         let (dest, rest) = mem::replace(self, &mut []).split_at_mut(n);
         dest.copy_from_slice(buf);
         *self = rest;
-        Ok(())
+        Ok(n)
+    }
+}
+
+/// A [`Write`] that discards everything written to it, counting the bytes
+/// instead.
+///
+/// This is useful for measuring how long a message will be before committing
+/// to writing it.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Counter(pub usize);
+
+impl Write for Counter {
+    fn write_bytes(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        self.0 = self.0.saturating_add(buf.len());
+        Ok(buf.len())
     }
 }
 
@@ -66,8 +138,9 @@ use crate as manticore;
 
 /// Converts a [`std::io::Write`] into a [`manticore::io::Write`].
 ///
-/// [`manticore::io::Write::write_bytes()`] is implemented by simply calling
-/// [`std::io::Write::write()`] repeatedly until every byte is written;
+/// [`manticore::io::Write::write_bytes()`] is implemented by calling
+/// [`std::io::Write::write()`] once, retrying only if it is interrupted, so
+/// partial writes are passed through to the caller;
 /// [`manticore::io::Write`] should be implemented directly if possible.
 ///
 /// This type is provided instead of implementing [`manticore::io::Write`]
@@ -78,20 +151,28 @@ pub struct StdWrite<W>(pub W);
 
 #[cfg(feature = "std")]
 impl<W: std::io::Write> Write for StdWrite<W> {
-    fn write_bytes(&mut self, mut buf: &[u8]) -> Result<(), io::Error> {
+    fn write_bytes(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
         use std::io::ErrorKind;
         loop {
-            if buf.is_empty() {
-                return Ok(());
-            }
             match self.0.write(buf).map_err(|e| e.kind()) {
-                Ok(len) => buf = &buf[len..],
+                Ok(len) => return Ok(len),
                 Err(ErrorKind::Interrupted) => continue,
                 // No good way to propagate this. =/
                 Err(_) => return Err(fail!(io::Error::Internal)),
             }
         }
     }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        use std::io::ErrorKind;
+        loop {
+            match self.0.flush().map_err(|e| e.kind()) {
+                Ok(()) => return Ok(()),
+                Err(ErrorKind::Interrupted) => continue,
+                Err(_) => return Err(fail!(io::Error::Internal)),
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -103,18 +184,24 @@ mod test {
     fn read_and_write_bytes() {
         let mut buf = [0; 6];
         let mut bytes = &mut buf[..];
-        bytes.write_bytes(b"Wo").unwrap();
-        bytes.write_bytes(b"r").unwrap();
+        bytes.write_all(b"Wo").unwrap();
+        bytes.write_all(b"r").unwrap();
         assert_eq!(bytes.len(), 3);
         bytes.write_le::<u16>(0x646c).unwrap();
         assert_eq!(bytes.len(), 1);
-        assert!(bytes.write_bytes(b"!!").is_err());
+        assert!(bytes.write_all(b"!!").is_err());
         bytes.write_le::<u8>(b'!').unwrap();
         assert_eq!(bytes.len(), 0);
         assert_eq!(&buf, b"World!");
 
         let mut bytes = &mut buf[..];
         assert_eq!(bytes.read_le::<u32>().unwrap(), 0x6c726f57);
+        let mut bytes = &buf[..];
+        assert_eq!(bytes.read_be::<u32>().unwrap(), 0x576f726c);
+
+        let mut bytes = &mut buf[..];
+        bytes.write_be::<u16>(0x0102).unwrap();
+        assert_eq!(&buf[..2], &[1, 2]);
     }
 
     #[test]
@@ -124,4 +211,54 @@ mod test {
         std_write.write_le::<u32>(0x04030201).unwrap();
         assert_eq!(buf, [1, 2, 3, 4]);
     }
+
+    #[test]
+    fn std_write_flush() {
+        let mut std_write = StdWrite(std::io::BufWriter::new(Vec::new()));
+        std_write.write_all(b"held").unwrap();
+        assert!(std_write.0.get_ref().is_empty());
+        std_write.flush().unwrap();
+        assert_eq!(std_write.0.get_ref(), b"held");
+    }
+
+    /// A `Write` that accepts at most `chunk` bytes at a time.
+    struct Trickle<'a> {
+        out: &'a mut [u8],
+        chunk: usize,
+        calls: usize,
+    }
+
+    impl Write for Trickle<'_> {
+        fn write_bytes(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+            self.calls += 1;
+            let len = buf.len().min(self.chunk).min(self.out.len());
+            self.out.write_bytes(&buf[..len])
+        }
+    }
+
+    #[test]
+    fn partial_writes() {
+        let mut buf = [0; 8];
+        let mut w = Trickle {
+            out: &mut buf[..],
+            chunk: 3,
+            calls: 0,
+        };
+        w.write_all(b"World").unwrap();
+        assert_eq!(w.calls, 2);
+        w.write_le::<u16>(0x646c).unwrap();
+        assert_eq!(w.write_bytes(b"!!").unwrap(), 1);
+
+        // Once the sink stops making progress, `write_all()` gives up.
+        assert!(w.write_all(b"!").is_err());
+        assert_eq!(&buf, b"Worldld!");
+    }
+
+    #[test]
+    fn counter() {
+        let mut counter = Counter::default();
+        counter.write_all(b"hello").unwrap();
+        counter.write_le::<u32>(0).unwrap();
+        assert_eq!(counter, Counter(9));
+    }
 }
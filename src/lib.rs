@@ -32,6 +32,9 @@
 //!   This feature is not intended for on-device use-cases either.
 //! - `log` (default) enables debug logging throughout manticore, via the `log`
 //!   crate. This feature can be disabled to redact all logging.
+//! - `compression` enables the [`io::rle` module], a small run-length
+//!   encoding that transports can use to compress large payloads, such as
+//!   manifests.
 //! - `serde` enables implementations of `serde`'s (de)serialization traits.
 //! - `arbitrary-derive` enables implementations of fuzz-testing-related
 //!   traits.
@@ -40,6 +43,7 @@
 //!   https://github.com/opencomputeproject/Project_Olympus/tree/master/Project_Cerberus
 //! [`protocol` module]: protocol/index.html
 //! [`crypto::ring` module]: crypto/ring/index.html
+//! [`io::rle` module]: io/rle/index.html
 
 #![allow(clippy::unusual_byte_groupings)]
 #![cfg_attr(not(feature = "std"), no_std)]
@@ -72,7 +76,11 @@ pub mod net;
 pub mod server;
 pub mod session;
 
+pub use debug::set_trace_hook;
+pub use debug::set_trace_level;
 pub use debug::Error;
+pub use debug::TraceHook;
+pub use debug::TraceLevel;
 
 /// [`Result`] type to use throughout Manticore, which incorporates the
 /// [`manticore::Error`] type.
@@ -0,0 +1,91 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The Component Firmware Manifest (CFM)
+//!
+//! A CFM is a policy document describing the firmware that components
+//! attached to a platform (rather than the platform itself) are allowed to
+//! run, and which is checked by attesting those components.
+//!
+//! CFMs are delivered to a device with the `PrepareCfm`, `UpdateCfm`, and
+//! `ActivateCfm` commands; see [`server::ManifestStore`].
+//!
+//! Currently, only the elements common to all manifests, such as
+//! [`ManifestExt::platform_id()`], can be read out of a CFM.
+//!
+//! [`server::ManifestStore`]: crate::server::ManifestStore
+//! [`ManifestExt::platform_id()`]: crate::manifest::ManifestExt::platform_id
+
+use crate::manifest::provenance;
+use crate::manifest::Container;
+use crate::manifest::Error;
+use crate::manifest::Manifest;
+use crate::manifest::ManifestType;
+use crate::manifest::Parse;
+use crate::manifest::ParsedManifest;
+use crate::manifest::ValidationTime;
+use crate::Result;
+
+wire_enum! {
+    /// A CFM element type.
+    pub enum ElementType: u8 {
+      /// A `ComponentDevice` describes one kind of component that a
+      /// particular CFM attests.
+      ComponentDevice = 0x70,
+    }
+}
+
+/// A Component Firmware Manifest.
+///
+/// This type only maintains the TOC in memory for book-keeping.
+pub struct ParsedCfm<'cfm, Provenance = provenance::Signed> {
+    container: Container<'cfm, Cfm, Provenance>,
+}
+
+/// A [`Manifest`] implementation mapping onto [`ParsedCfm`], for use in generic
+/// contexts.
+///
+/// See [`Manifest`] and [`Parse`].
+pub enum Cfm {}
+
+impl Manifest for Cfm {
+    type ElementType = ElementType;
+    const TYPE: ManifestType = ManifestType::Cfm;
+
+    fn min_version(_: ElementType) -> u8 {
+        0
+    }
+}
+
+impl<'f, P> Parse<'f, P> for Cfm {
+    type Parsed = ParsedCfm<'f, P>;
+
+    fn parse(container: Container<'f, Self, P>) -> Result<Self::Parsed, Error> {
+        Ok(ParsedCfm::new(container))
+    }
+
+    fn container(manifest: &Self::Parsed) -> &Container<'f, Self, P> {
+        &manifest.container
+    }
+
+    type Guarded = ();
+    fn validate(
+        _manifest: &Self::Parsed,
+        _when: ValidationTime,
+        _args: &Self::Guarded,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<P> ParsedManifest for ParsedCfm<'_, P> {
+    type Manifest = Cfm;
+}
+
+impl<'cfm, P> ParsedCfm<'cfm, P> {
+    /// Creates a new CFM handle using the given `Container`.
+    pub fn new(container: Container<'cfm, Cfm, P>) -> Self {
+        ParsedCfm { container }
+    }
+}
@@ -658,6 +658,66 @@ pub(crate) mod test {
         assert_eq!(toc.entries().count(), 0);
     }
 
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn rejects_tampering() {
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let (mut wrong_key, _) =
+            ring::rsa::from_keypair(keys::KEY2_RSA_KEYPAIR);
+
+        #[rustfmt::skip]
+        let pfm: owned::Pfm = from_str(r#"{
+            "version_id": 42,
+            "elements": [{ "platform_id": "blah" }]
+        }"#).unwrap();
+        let signed = pfm
+            .sign(0x0, hash::Algo::Sha256, &mut hasher, &mut signer)
+            .unwrap();
+
+        let verify = |bytes: &[u8], key: &mut ring::rsa::Verify256| {
+            manifest::parse_and_verify::<Pfm>(
+                &Ram(bytes),
+                &mut ring::hash::Engine::new(),
+                key,
+                &OutOfMemory,
+                &OutOfMemory,
+            )
+            .map(drop)
+            .map_err(|e| e.into_inner())
+        };
+        assert!(verify(&signed, &mut rsa).is_ok());
+        assert!(matches!(
+            verify(&signed, &mut wrong_key),
+            Err(manifest::Error::SigError(_)),
+        ));
+
+        // The version ID is only covered by the signature.
+        let mut bytes = signed.clone();
+        bytes[4] ^= 1;
+        assert!(matches!(
+            verify(&bytes, &mut rsa),
+            Err(manifest::Error::SigError(_)),
+        ));
+
+        // The TOC entry's length is covered by the TOC hash.
+        let mut bytes = signed.clone();
+        bytes[mem::size_of::<RawHeader>() + 6] ^= 1;
+        assert!(matches!(
+            verify(&bytes, &mut rsa),
+            Err(manifest::Error::BadTocHash(_)),
+        ));
+
+        // The trailing signature itself.
+        let mut bytes = signed;
+        *bytes.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            verify(&bytes, &mut rsa),
+            Err(manifest::Error::SigError(_)),
+        ));
+    }
+
     #[test]
     #[cfg_attr(miri, ignore)]
     fn one_element() {
@@ -8,7 +8,9 @@ use zerocopy::AsBytes;
 use zerocopy::FromBytes;
 
 use crate::crypto::hash;
+use crate::crypto::sig;
 use crate::hardware::flash::Flash;
+use crate::manifest::provenance;
 use crate::manifest::provenance::Provenance;
 use crate::manifest::Container;
 use crate::manifest::ElementType;
@@ -38,6 +40,30 @@ impl<'a, 'f, Manifest> PlatformId<'a, 'f, Manifest> {
     }
 }
 
+/// Parses a manifest of type `M` out of `flash`, verifying its signature with
+/// `sig_verify` before any of its contents are trusted.
+///
+/// Only a manifest that passes this check carries the
+/// [`provenance::Signed`] provenance, which trusted operations (such as
+/// [`Parse::validate()`]) require. The signed portion is hashed with
+/// `hasher`.
+pub fn parse_and_verify<'f, M: Parse<'f, provenance::Signed>>(
+    flash: &'f dyn Flash,
+    hasher: &mut dyn hash::Engine,
+    sig_verify: &mut dyn sig::Verify,
+    toc_arena: &'f dyn Arena,
+    verify_arena: &dyn Arena,
+) -> Result<M::Parsed, Error> {
+    let container = Container::parse_and_verify(
+        flash,
+        hasher,
+        sig_verify,
+        toc_arena,
+        verify_arena,
+    )?;
+    M::parse(container)
+}
+
 /// Helpers for working with manifests.
 #[extend::ext(name = ManifestExt)]
 pub impl<'f, P, M> M
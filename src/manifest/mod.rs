@@ -90,8 +90,10 @@ pub use container::TocEntry;
 mod generic;
 pub use generic::*;
 
+pub mod cfm;
 #[cfg(feature = "std")]
 pub mod owned;
+pub mod pcd;
 pub mod pfm;
 
 #[cfg(test)]
@@ -100,18 +102,37 @@ mod testdata;
 #[cfg(doc)]
 use crate::hardware::flash::Flash;
 
+#[cfg(feature = "arbitrary-derive")]
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+
 wire_enum! {
     /// A Cerberus manifest type.
     ///
     /// This enum represents the "magic number" `u16` value in a maniest header.
+    #[cfg_attr(feature = "arbitrary-derive", derive(Arbitrary))]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub enum ManifestType: u16 {
         /// A ["Platform Firmware Manifest"], a manifest which describes
         /// firmware that is allowed to run on a platfrom.
         ///
         /// ["Platform Firmware Manifest"]: pfm/index.html
         Pfm = 0x706d,
+
+        /// A ["Component Firmware Manifest"], a manifest which describes
+        /// firmware that is allowed to run on components attached to a
+        /// platform.
+        ///
+        /// ["Component Firmware Manifest"]: cfm/index.html
+        Cfm = 0xa592,
+
+        /// A ["Platform Configuration Data"], a manifest which describes the
+        /// topology of the platform around a RoT.
+        ///
+        /// ["Platform Configuration Data"]: pcd/index.html
+        Pcd = 0x1029,
     }
 }
+derive_borrowed!(ManifestType);
 
 /// A manifest element type.
 ///
@@ -226,6 +247,13 @@ pub enum Error {
         toc_index: usize,
     },
 
+    /// Indicates that an element declared more children than the manifest
+    /// actually contains.
+    BadCount {
+        /// The index of the bad entry.
+        toc_index: usize,
+    },
+
     /// Indicates that some assumption about a manifest's alignment (internal
     /// or overall) was violated.
     Unaligned,
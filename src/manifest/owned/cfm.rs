@@ -0,0 +1,123 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! CFM element structures.
+//!
+//! See [`owned::Cfm`](../type.Cfm.html).
+
+use crate::crypto::ring;
+use crate::manifest;
+use crate::manifest::cfm;
+use crate::manifest::owned;
+use crate::manifest::owned::EncodingError;
+use crate::manifest::provenance;
+use crate::manifest::Error;
+use crate::manifest::ManifestExt as _;
+use crate::manifest::ManifestType;
+use crate::mem::BumpArena;
+use crate::Result;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// An owned CFM element.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+#[allow(missing_docs)]
+pub enum Element {
+    PlatformId {
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::serde::bytestring")
+        )]
+        platform_id: Vec<u8>,
+    },
+}
+
+impl owned::Element for Element {
+    type ElementType = cfm::ElementType;
+    const TYPE: ManifestType = ManifestType::Cfm;
+
+    fn element_type(&self) -> manifest::ElementType<cfm::ElementType> {
+        match self {
+            Self::PlatformId { .. } => manifest::ElementType::PlatformId,
+        }
+    }
+
+    fn to_bytes(&self, padding_byte: u8) -> Result<Vec<u8>, EncodingError> {
+        match self {
+            Self::PlatformId { platform_id: id } => {
+                owned::encode_platform_id(id, padding_byte)
+            }
+        }
+    }
+}
+
+impl owned::FromUnowned for Element {
+    type Manifest = manifest::cfm::Cfm;
+
+    fn from_container(
+        container: manifest::Container<Self::Manifest, provenance::Adhoc>,
+    ) -> Result<Vec<owned::Node<Self>>, Error> {
+        let arena = BumpArena::new(vec![0; 2048]);
+        let cfm = manifest::cfm::ParsedCfm::new(container);
+        let mut h = ring::hash::Engine::new();
+        let mut nodes = Vec::new();
+
+        if let Some(id) = cfm.platform_id(&mut h, &arena)? {
+            nodes.push(owned::Node {
+                element: Element::PlatformId {
+                    platform_id: id.id_string().to_vec(),
+                },
+                hashed: id.entry().hash().is_some(),
+                children: Vec::new(),
+            })
+        }
+
+        Ok(nodes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use testutil::data::keys;
+
+    use crate::crypto::hash;
+    use crate::crypto::ring::rsa;
+    use crate::manifest::owned::Cfm;
+    use crate::manifest::Metadata;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn round_trip() {
+        let cfm = Cfm {
+            metadata: Metadata { version_id: 7 },
+            elements: vec![owned::Node {
+                element: Element::PlatformId {
+                    platform_id: b"components".to_vec(),
+                },
+                children: vec![],
+                hashed: true,
+            }],
+        };
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) = rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        let bytes = cfm
+            .sign(0x00, hash::Algo::Sha256, &mut hasher, &mut signer)
+            .unwrap();
+        assert_eq!(&bytes[2..4], &0xa592u16.to_le_bytes());
+
+        let cfm2 = owned::Container::parse(&bytes, &mut hasher, Some(&mut rsa))
+            .unwrap();
+        assert!(!cfm2.bad_signature);
+        assert!(!cfm2.bad_toc_hash);
+        assert!(cfm2.bad_hashes.is_empty());
+        assert_eq!(cfm, cfm2.container);
+    }
+}
@@ -28,6 +28,7 @@ use crate::manifest::Error;
 use crate::manifest::Manifest;
 use crate::manifest::ManifestType;
 use crate::manifest::Metadata;
+use crate::mem::misalign_of;
 use crate::mem::BumpArena;
 use crate::protocol::wire::WireEnum;
 use crate::Result;
@@ -35,6 +36,8 @@ use crate::Result;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+pub mod cfm;
+pub mod pcd;
 pub mod pfm;
 
 /// An "owned" manifest element.
@@ -83,13 +86,23 @@ pub trait FromUnowned: Element {
 /// See [`manifest::pfm`] for lazy parsing out of flash.
 pub type Pfm = Container<self::pfm::Element>;
 
+/// A heap-allocated CFM.
+///
+/// See [`manifest::cfm`] for lazy parsing out of flash.
+pub type Cfm = Container<self::cfm::Element>;
+
+/// A heap-allocated PCD.
+///
+/// See [`manifest::pcd`] for lazy parsing out of flash.
+pub type Pcd = Container<self::pcd::Element>;
+
 /// A heap-allocated Cerberus manifest, represented as a tree structure.
 ///
 /// Prefer to access this type through one of the provided type aliases,
 /// instead:
 /// - Platform Firmware Manifest: [`Pfm`](type.Pfm.html)
-/// - Component Firmware Manifest: NYI
-/// - Platform Configuration Descriptor: NYI
+/// - Component Firmware Manifest: [`Cfm`](type.Cfm.html)
+/// - Platform Configuration Data: [`Pcd`](type.Pcd.html)
 #[derive(Clone, PartialEq, Eq, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Container<E> {
@@ -421,3 +434,23 @@ impl<E: Element> Container<E> {
         Ok(bytes)
     }
 }
+
+/// Encodes a `PlatformId` element, which all manifest types share.
+fn encode_platform_id(
+    id: &[u8],
+    padding_byte: u8,
+) -> Result<Vec<u8>, EncodingError> {
+    let id_len: u8 = id
+        .len()
+        .try_into()
+        .map_err(|_| EncodingError::StringTooLong(id.to_vec()))?;
+    let mut bytes = vec![padding_byte; 4];
+    bytes[0] = id_len;
+
+    bytes.extend_from_slice(id);
+    for _ in 0..misalign_of(bytes.len(), 4) {
+        bytes.push(padding_byte);
+    }
+
+    Ok(bytes)
+}
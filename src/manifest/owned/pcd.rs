@@ -0,0 +1,310 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! PCD element structures.
+//!
+//! See [`owned::Pcd`](../type.Pcd.html).
+
+use core::convert::TryInto;
+
+use crate::crypto::ring;
+use crate::manifest;
+use crate::manifest::owned;
+use crate::manifest::owned::EncodingError;
+use crate::manifest::pcd;
+use crate::manifest::provenance;
+use crate::manifest::Error;
+use crate::manifest::ManifestExt as _;
+use crate::manifest::ManifestType;
+use crate::mem::misalign_of;
+use crate::mem::Arena as _;
+use crate::mem::BumpArena;
+use crate::Result;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// An owned PCD element.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+#[allow(missing_docs)]
+pub enum Element {
+    Rot {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::dec"))]
+        port_count: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::dec"))]
+        component_count: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+        address: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+        eid: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+        bridge_address: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+        bridge_eid: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::bin"))]
+        flags: u8,
+    },
+    Port {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::dec"))]
+        port_id: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::dec"))]
+        pulse_interval: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::dec"))]
+        spi_frequency_hz: u32,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::bin"))]
+        policy: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::bin"))]
+        flags: u8,
+    },
+    Component {
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::serde::bytestring")
+        )]
+        component_type: Vec<u8>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::dec"))]
+        bus: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+        address: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+        eid: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+        power_ctrl_reg: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::bin"))]
+        power_ctrl_mask: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::bin"))]
+        i2c_mode: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::bin"))]
+        policy: u8,
+    },
+    PlatformId {
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::serde::bytestring")
+        )]
+        platform_id: Vec<u8>,
+    },
+}
+
+impl owned::Element for Element {
+    type ElementType = pcd::ElementType;
+    const TYPE: ManifestType = ManifestType::Pcd;
+
+    fn element_type(&self) -> manifest::ElementType<pcd::ElementType> {
+        match self {
+            Self::Rot { .. } => pcd::ElementType::Rot.into(),
+            Self::Port { .. } => pcd::ElementType::Port.into(),
+            Self::Component { .. } => pcd::ElementType::Component.into(),
+            Self::PlatformId { .. } => manifest::ElementType::PlatformId,
+        }
+    }
+
+    fn to_bytes(&self, padding_byte: u8) -> Result<Vec<u8>, EncodingError> {
+        match self {
+            Self::Rot {
+                port_count,
+                component_count,
+                address,
+                eid,
+                bridge_address,
+                bridge_eid,
+                flags,
+            } => Ok(vec![
+                *flags,
+                *port_count,
+                *component_count,
+                *address,
+                *eid,
+                *bridge_address,
+                *bridge_eid,
+                padding_byte,
+            ]),
+            Self::Port {
+                port_id,
+                pulse_interval,
+                spi_frequency_hz,
+                policy,
+                flags,
+            } => {
+                let mut bytes =
+                    vec![*port_id, *flags, *policy, *pulse_interval];
+                bytes.extend_from_slice(&spi_frequency_hz.to_le_bytes());
+                Ok(bytes)
+            }
+            Self::Component {
+                component_type,
+                bus,
+                address,
+                eid,
+                power_ctrl_reg,
+                power_ctrl_mask,
+                i2c_mode,
+                policy,
+            } => {
+                let type_len: u8 =
+                    component_type.len().try_into().map_err(|_| {
+                        EncodingError::StringTooLong(component_type.clone())
+                    })?;
+                let mut bytes =
+                    vec![*policy, *power_ctrl_reg, *power_ctrl_mask, type_len];
+
+                bytes.extend_from_slice(component_type);
+                for _ in 0..misalign_of(bytes.len(), 4) {
+                    bytes.push(padding_byte);
+                }
+                bytes.extend_from_slice(&[*i2c_mode, *bus, *address, *eid]);
+
+                Ok(bytes)
+            }
+            Self::PlatformId { platform_id: id } => {
+                owned::encode_platform_id(id, padding_byte)
+            }
+        }
+    }
+}
+
+impl owned::FromUnowned for Element {
+    type Manifest = manifest::pcd::Pcd;
+
+    fn from_container(
+        container: manifest::Container<Self::Manifest, provenance::Adhoc>,
+    ) -> Result<Vec<owned::Node<Self>>, Error> {
+        let mut arena = BumpArena::new(vec![0; 2048]);
+        let pcd = manifest::pcd::ParsedPcd::new(container);
+        let mut h = ring::hash::Engine::new();
+        let mut nodes = Vec::new();
+
+        if let Some(id) = pcd.platform_id(&mut h, &arena)? {
+            nodes.push(owned::Node {
+                element: Element::PlatformId {
+                    platform_id: id.id_string().to_vec(),
+                },
+                hashed: id.entry().hash().is_some(),
+                children: Vec::new(),
+            })
+        }
+        arena.reset();
+
+        if let Some(rot) = pcd.rot(&mut h, &arena)? {
+            nodes.push(owned::Node {
+                element: Element::Rot {
+                    port_count: rot.port_count() as u8,
+                    component_count: rot.component_count() as u8,
+                    address: rot.address(),
+                    eid: rot.eid(),
+                    bridge_address: rot.bridge_address(),
+                    bridge_eid: rot.bridge_eid(),
+                    flags: rot.raw_flags(),
+                },
+                hashed: rot.entry().hash().is_some(),
+                children: Vec::new(),
+            })
+        }
+        arena.reset();
+
+        for port in pcd.ports() {
+            let port = port.read(&mut h, &arena)?;
+            nodes.push(owned::Node {
+                element: Element::Port {
+                    port_id: port.id(),
+                    pulse_interval: port.pulse_interval(),
+                    spi_frequency_hz: port.spi_frequency_hz(),
+                    policy: port.raw_policy(),
+                    flags: port.raw_flags(),
+                },
+                hashed: port.entry().hash().is_some(),
+                children: Vec::new(),
+            });
+            arena.reset();
+        }
+
+        for component in pcd.components() {
+            let component = component.read(&mut h, &arena)?;
+            let (power_ctrl_reg, power_ctrl_mask) = component.power_ctrl();
+            nodes.push(owned::Node {
+                element: Element::Component {
+                    component_type: component.component_type().to_vec(),
+                    bus: component.bus(),
+                    address: component.address(),
+                    eid: component.eid(),
+                    power_ctrl_reg,
+                    power_ctrl_mask,
+                    i2c_mode: component.raw_i2c_mode(),
+                    policy: component.raw_policy(),
+                },
+                hashed: component.entry().hash().is_some(),
+                children: Vec::new(),
+            });
+            arena.reset();
+        }
+
+        Ok(nodes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use testutil::data::keys;
+
+    use crate::crypto::hash;
+    use crate::crypto::ring::rsa;
+    use crate::manifest::owned::Pcd;
+    use crate::manifest::Metadata;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn round_trip() {
+        let pcd = Pcd {
+            metadata: Metadata { version_id: 3 },
+            elements: vec![
+                owned::Node {
+                    element: Element::Rot {
+                        port_count: 0,
+                        component_count: 1,
+                        address: 0x41,
+                        eid: 0x0b,
+                        bridge_address: 0x10,
+                        bridge_eid: 0x08,
+                        flags: 0b1,
+                    },
+                    children: vec![],
+                    hashed: true,
+                },
+                owned::Node {
+                    element: Element::Component {
+                        component_type: b"nic".to_vec(),
+                        bus: 2,
+                        address: 0x22,
+                        eid: 0x0c,
+                        power_ctrl_reg: 0x04,
+                        power_ctrl_mask: 0b10,
+                        i2c_mode: 0,
+                        policy: 1,
+                    },
+                    children: vec![],
+                    hashed: false,
+                },
+            ],
+        };
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) = rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        let bytes = pcd
+            .sign(0x00, hash::Algo::Sha256, &mut hasher, &mut signer)
+            .unwrap();
+        assert_eq!(&bytes[2..4], &0x1029u16.to_le_bytes());
+
+        let pcd2 = owned::Container::parse(&bytes, &mut hasher, Some(&mut rsa))
+            .unwrap();
+        assert!(!pcd2.bad_signature);
+        assert!(!pcd2.bad_toc_hash);
+        assert!(pcd2.bad_hashes.is_empty());
+        assert_eq!(pcd, pcd2.container);
+    }
+}
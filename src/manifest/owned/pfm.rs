@@ -202,19 +202,7 @@ impl owned::Element for Element {
                 Ok(bytes)
             }
             Self::PlatformId { platform_id: id } => {
-                let id_len: u8 = id
-                    .len()
-                    .try_into()
-                    .map_err(|_| EncodingError::StringTooLong(id.clone()))?;
-                let mut bytes = vec![padding_byte; 4];
-                bytes[0] = id_len;
-
-                bytes.extend_from_slice(&id);
-                for _ in 0..misalign_of(bytes.len(), 4) {
-                    bytes.push(padding_byte);
-                }
-
-                Ok(bytes)
+                owned::encode_platform_id(id, padding_byte)
             }
         }
     }
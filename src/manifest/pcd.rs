@@ -0,0 +1,624 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The Platform Configuration Data (PCD)
+//!
+//! A PCD is a computer-readable description of the platform topology around
+//! a RoT: the RoT's own bus addresses, the flash ports it protects, and the
+//! components it can reach over I2C, and so attest.
+//!
+//! Like a [PFM](super::pfm), a PCD is read by "random access" through its
+//! table of contents. The [`ParsedPcd`] type is the entry-point for this
+//! module.
+
+use zerocopy::AsBytes;
+use zerocopy::FromBytes;
+use zerocopy::LayoutVerified;
+
+use crate::crypto::hash;
+use crate::crypto::sig;
+use crate::hardware::flash::Flash;
+use crate::manifest;
+use crate::manifest::provenance;
+use crate::manifest::provenance::Provenance;
+use crate::manifest::Container;
+use crate::manifest::Error;
+use crate::manifest::Manifest;
+use crate::manifest::ManifestType;
+use crate::manifest::Parse;
+use crate::manifest::ParsedManifest;
+use crate::manifest::TocEntry;
+use crate::manifest::ValidationTime;
+use crate::mem::misalign_of;
+use crate::mem::Arena;
+use crate::Result;
+
+wire_enum! {
+    /// A PCD element type.
+    pub enum ElementType: u8 {
+      /// A `Rot` describes the RoT itself, including how many ports and
+      /// components the rest of the PCD describes.
+      Rot = 0x40,
+
+      /// A `Port` describes a flash port that the RoT protects.
+      Port = 0x41,
+
+      /// A `Component` describes a component attached directly to one of
+      /// the RoT's I2C buses.
+      Component = 0x43,
+    }
+}
+
+/// A Platform Configuration Data manifest.
+///
+/// This type provides functions for parsing a PCD's table of contents and
+/// using it to extract other portions of the PCD.
+///
+/// This type only maintains the TOC in memory for book-keeping.
+pub struct ParsedPcd<'pcd, Provenance = provenance::Signed> {
+    container: Container<'pcd, Pcd, Provenance>,
+}
+
+/// A [`Manifest`] implementation mapping onto [`ParsedPcd`], for use in generic
+/// contexts.
+///
+/// See [`Manifest`] and [`Parse`].
+pub enum Pcd {}
+
+impl Manifest for Pcd {
+    type ElementType = ElementType;
+    const TYPE: ManifestType = ManifestType::Pcd;
+
+    fn min_version(_: ElementType) -> u8 {
+        0
+    }
+}
+
+impl<'f, P> Parse<'f, P> for Pcd {
+    type Parsed = ParsedPcd<'f, P>;
+
+    fn parse(container: Container<'f, Self, P>) -> Result<Self::Parsed, Error> {
+        Ok(ParsedPcd::new(container))
+    }
+
+    fn container(manifest: &Self::Parsed) -> &Container<'f, Self, P> {
+        &manifest.container
+    }
+
+    type Guarded = ();
+    fn validate(
+        _manifest: &Self::Parsed,
+        _when: ValidationTime,
+        _args: &Self::Guarded,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<P> ParsedManifest for ParsedPcd<'_, P> {
+    type Manifest = Pcd;
+}
+
+impl<'pcd, P> ParsedPcd<'pcd, P> {
+    /// Creates a new PCD handle using the given `Container`.
+    pub fn new(container: Container<'pcd, Pcd, P>) -> Self {
+        ParsedPcd { container }
+    }
+}
+
+impl<'pcd> ParsedPcd<'pcd> {
+    /// Parses a PCD out of `flash`, verifying its signature before anything
+    /// else in it is trusted.
+    ///
+    /// See [`manifest::parse_and_verify()`].
+    pub fn parse_and_verify(
+        flash: &'pcd dyn Flash,
+        hasher: &mut dyn hash::Engine,
+        sig_verify: &mut dyn sig::Verify,
+        toc_arena: &'pcd dyn Arena,
+        verify_arena: &dyn Arena,
+    ) -> Result<Self, Error> {
+        manifest::parse_and_verify::<Pcd>(
+            flash,
+            hasher,
+            sig_verify,
+            toc_arena,
+            verify_arena,
+        )
+    }
+}
+
+impl<'pcd, P> ParsedPcd<'pcd, P>
+where
+    P: Provenance,
+{
+    /// Extracts the `Rot` element from this PCD.
+    ///
+    /// This function will also verify the hash of the `Rot` if one is
+    /// present, and that the PCD actually contains as many ports and
+    /// components as the `Rot` declares.
+    pub fn rot(
+        &self,
+        hasher: &mut dyn hash::Engine,
+        arena: &'pcd dyn Arena,
+    ) -> Result<Option<RotInfo<'_, 'pcd>>, Error> {
+        let entry =
+            match self.container.toc().singleton(ElementType::Rot.into()) {
+                Some(x) => x,
+                None => return Ok(None),
+            };
+
+        let (header, _) = entry.read_with_header::<RotHeader, P>(
+            self.container.flash(),
+            arena,
+            hasher,
+        )?;
+
+        check!(
+            header.port_count as usize <= self.ports().count()
+                && header.component_count as usize <= self.components().count(),
+            Error::BadCount {
+                toc_index: entry.index(),
+            }
+        );
+
+        Ok(Some(RotInfo { entry, header }))
+    }
+
+    /// Returns an iterator over the `Port` elements of this PCD.
+    ///
+    /// The returned values only contain the `Toc` information for the entry,
+    /// allowing the user to lazily select which entries to read from flash.
+    pub fn ports(&self) -> impl Iterator<Item = PortEntry<'_, 'pcd, P>> + '_ {
+        self.container
+            .toc()
+            .entries_of(ElementType::Port.into())
+            .map(move |entry| PortEntry { pcd: self, entry })
+    }
+
+    /// Returns an iterator over the `Component` elements of this PCD.
+    ///
+    /// The returned values only contain the `Toc` information for the entry,
+    /// allowing the user to lazily select which entries to read from flash.
+    pub fn components(
+        &self,
+    ) -> impl Iterator<Item = ComponentEntry<'_, 'pcd, P>> + '_ {
+        self.container
+            .toc()
+            .entries_of(ElementType::Component.into())
+            .map(move |entry| ComponentEntry { pcd: self, entry })
+    }
+}
+
+#[derive(Clone, Copy, FromBytes, AsBytes)]
+#[repr(C)]
+struct RotHeader {
+    flags: u8,
+    port_count: u8,
+    component_count: u8,
+    address: u8,
+    eid: u8,
+    bridge_address: u8,
+    bridge_eid: u8,
+    _unused: u8,
+}
+
+/// A descriptor for the RoT that a PCD configures.
+pub struct RotInfo<'a, 'pcd> {
+    entry: TocEntry<'a, 'pcd, Pcd>,
+    header: &'pcd RotHeader,
+}
+
+impl<'a, 'pcd> RotInfo<'a, 'pcd> {
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'pcd, Pcd> {
+        self.entry
+    }
+
+    /// Returns the raw encoded flags for this element.
+    pub fn raw_flags(&self) -> u8 {
+        self.header.flags
+    }
+
+    /// Returns the number of `Port`s this PCD declares.
+    pub fn port_count(&self) -> usize {
+        self.header.port_count as usize
+    }
+
+    /// Returns the number of `Component`s this PCD declares.
+    pub fn component_count(&self) -> usize {
+        self.header.component_count as usize
+    }
+
+    /// Returns the RoT's own 7-bit I2C address.
+    pub fn address(&self) -> u8 {
+        self.header.address
+    }
+
+    /// Returns the RoT's MCTP endpoint ID.
+    pub fn eid(&self) -> u8 {
+        self.header.eid
+    }
+
+    /// Returns the 7-bit I2C address of the MCTP bridge the RoT talks
+    /// through.
+    pub fn bridge_address(&self) -> u8 {
+        self.header.bridge_address
+    }
+
+    /// Returns the MCTP endpoint ID of the MCTP bridge the RoT talks
+    /// through.
+    pub fn bridge_eid(&self) -> u8 {
+        self.header.bridge_eid
+    }
+}
+
+/// A "port" element entry in a PCD's `Toc`.
+///
+/// This type allows for lazily reading the [`Port`] described by this entry,
+/// as obtained from [`ParsedPcd::ports()`].
+pub struct PortEntry<'a, 'pcd, Provenance = provenance::Signed> {
+    pcd: &'a ParsedPcd<'pcd, Provenance>,
+    entry: TocEntry<'a, 'pcd, Pcd>,
+}
+
+impl<'a, 'pcd, P> PortEntry<'a, 'pcd, P>
+where
+    P: Provenance,
+{
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'pcd, Pcd> {
+        self.entry
+    }
+
+    /// Reads the contents of this element into memory, verifying its hash
+    /// and potentially allocating it on `arena`.
+    pub fn read(
+        self,
+        hasher: &mut dyn hash::Engine,
+        arena: &'pcd dyn Arena,
+    ) -> Result<Port<'a, 'pcd>, Error> {
+        let (header, _) = self.entry.read_with_header::<PortHeader, P>(
+            self.pcd.container.flash(),
+            arena,
+            hasher,
+        )?;
+        Ok(Port {
+            entry: self.entry,
+            header,
+        })
+    }
+}
+
+#[derive(Clone, Copy, FromBytes, AsBytes)]
+#[repr(C)]
+struct PortHeader {
+    id: u8,
+    flags: u8,
+    policy: u8,
+    pulse_interval: u8,
+    spi_frequency_hz: u32,
+}
+
+/// A "port" element from a PCD, describing a flash port the RoT protects.
+///
+/// To obtain a value of this type, see [`ParsedPcd::ports()`] and
+/// [`PortEntry::read()`].
+pub struct Port<'a, 'pcd> {
+    entry: TocEntry<'a, 'pcd, Pcd>,
+    header: &'pcd PortHeader,
+}
+
+impl<'a, 'pcd> Port<'a, 'pcd> {
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'pcd, Pcd> {
+        self.entry
+    }
+
+    /// Returns this port's ID.
+    pub fn id(&self) -> u8 {
+        self.header.id
+    }
+
+    /// Returns the raw encoded flags for this element.
+    pub fn raw_flags(&self) -> u8 {
+        self.header.flags
+    }
+
+    /// Returns the raw encoded policy for this element.
+    pub fn raw_policy(&self) -> u8 {
+        self.header.policy
+    }
+
+    /// Returns the interval between reset-control pulses, in units of 10ms,
+    /// or zero if the port is not reset by pulses.
+    pub fn pulse_interval(&self) -> u8 {
+        self.header.pulse_interval
+    }
+
+    /// Returns the frequency of the port's SPI bus, in Hz.
+    pub fn spi_frequency_hz(&self) -> u32 {
+        self.header.spi_frequency_hz
+    }
+}
+
+/// A "component" element entry in a PCD's `Toc`.
+///
+/// This type allows for lazily reading the [`Component`] described by this
+/// entry, as obtained from [`ParsedPcd::components()`].
+pub struct ComponentEntry<'a, 'pcd, Provenance = provenance::Signed> {
+    pcd: &'a ParsedPcd<'pcd, Provenance>,
+    entry: TocEntry<'a, 'pcd, Pcd>,
+}
+
+impl<'a, 'pcd, P> ComponentEntry<'a, 'pcd, P>
+where
+    P: Provenance,
+{
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'pcd, Pcd> {
+        self.entry
+    }
+
+    /// Reads the contents of this element into memory, verifying its hash
+    /// and potentially allocating it on `arena`.
+    pub fn read(
+        self,
+        hasher: &mut dyn hash::Engine,
+        arena: &'pcd dyn Arena,
+    ) -> Result<Component<'a, 'pcd>, Error> {
+        #[derive(Clone, Copy, FromBytes, AsBytes)]
+        #[repr(C)]
+        struct Header {
+            policy: u8,
+            power_ctrl_reg: u8,
+            power_ctrl_mask: u8,
+            type_len: u8,
+        }
+        let (header, rest) = self.entry.read_with_header::<Header, P>(
+            self.pcd.container.flash(),
+            arena,
+            hasher,
+        )?;
+
+        let too_short = Error::TooShort {
+            toc_index: self.entry.index(),
+        };
+        check!(rest.len() >= header.type_len as usize, too_short);
+        let (component_type, rest) = rest.split_at(header.type_len as usize);
+
+        // Align back to 4-byte boundary.
+        let rest = rest
+            .get(misalign_of(component_type.len(), 4)..)
+            .ok_or(too_short)?;
+        let (bus, _) = LayoutVerified::<_, ComponentBus>::new_from_prefix(rest)
+            .ok_or(too_short)?;
+
+        Ok(Component {
+            entry: self.entry,
+            policy: header.policy,
+            power_ctrl: (header.power_ctrl_reg, header.power_ctrl_mask),
+            component_type,
+            bus: bus.into_ref(),
+        })
+    }
+}
+
+#[derive(Clone, Copy, FromBytes, AsBytes)]
+#[repr(C)]
+struct ComponentBus {
+    i2c_mode: u8,
+    bus: u8,
+    address: u8,
+    eid: u8,
+}
+
+/// A "component" element from a PCD, describing a component attached to one
+/// of the RoT's I2C buses.
+///
+/// To obtain a value of this type, see [`ParsedPcd::components()`] and
+/// [`ComponentEntry::read()`].
+pub struct Component<'a, 'pcd> {
+    entry: TocEntry<'a, 'pcd, Pcd>,
+    policy: u8,
+    power_ctrl: (u8, u8),
+    component_type: &'pcd [u8],
+    bus: &'pcd ComponentBus,
+}
+
+impl<'a, 'pcd> Component<'a, 'pcd> {
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'pcd, Pcd> {
+        self.entry
+    }
+
+    /// Returns the raw encoded attestation policy for this component.
+    pub fn raw_policy(&self) -> u8 {
+        self.policy
+    }
+
+    /// Returns the register and bitmask that control this component's
+    /// power, as a pair.
+    pub fn power_ctrl(&self) -> (u8, u8) {
+        self.power_ctrl
+    }
+
+    /// Returns the type string for this component, which identifies it in
+    /// a CFM.
+    pub fn component_type(&self) -> &'pcd [u8] {
+        self.component_type
+    }
+
+    /// Returns the raw encoded I2C mode of the bus this component is on.
+    pub fn raw_i2c_mode(&self) -> u8 {
+        self.bus.i2c_mode
+    }
+
+    /// Returns the index of the I2C bus this component is on.
+    pub fn bus(&self) -> u8 {
+        self.bus.bus
+    }
+
+    /// Returns this component's 7-bit I2C address.
+    pub fn address(&self) -> u8 {
+        self.bus.address
+    }
+
+    /// Returns this component's MCTP endpoint ID.
+    pub fn eid(&self) -> u8 {
+        self.bus.eid
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use serde_json::from_str;
+    use testutil::data::keys;
+
+    use crate::crypto::ring;
+    use crate::hardware::flash::Ram;
+    use crate::manifest::owned;
+    use crate::mem::BumpArena;
+    use crate::mem::OutOfMemory;
+
+    fn sign(json: &str) -> Vec<u8> {
+        let mut hasher = ring::hash::Engine::new();
+        let (_, mut signer) = ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let pcd: owned::Pcd = from_str(json).unwrap();
+        pcd.sign(0x0, hash::Algo::Sha256, &mut hasher, &mut signer)
+            .unwrap()
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn components() {
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, _) = ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        #[rustfmt::skip]
+        let bytes = Ram(sign(r#"{
+            "version_id": 1,
+            "elements": [
+                {
+                    "port_count": 1,
+                    "component_count": 2,
+                    "address": "0x41",
+                    "eid": "0x0b",
+                    "bridge_address": "0x10",
+                    "bridge_eid": "0x08",
+                    "flags": "0b0"
+                },
+                {
+                    "port_id": 0,
+                    "pulse_interval": 0,
+                    "spi_frequency_hz": 33000000,
+                    "policy": "0b1",
+                    "flags": "0b0"
+                },
+                {
+                    "component_type": "nic",
+                    "bus": 1,
+                    "address": "0x22",
+                    "eid": "0x0c",
+                    "power_ctrl_reg": "0x00",
+                    "power_ctrl_mask": "0b0",
+                    "i2c_mode": "0b0",
+                    "policy": "0b0"
+                },
+                {
+                    "component_type": "accelerator",
+                    "bus": 3,
+                    "address": "0x30",
+                    "eid": "0x0d",
+                    "power_ctrl_reg": "0x00",
+                    "power_ctrl_mask": "0b0",
+                    "i2c_mode": "0b0",
+                    "policy": "0b0"
+                }
+            ]
+        }"#));
+
+        let pcd = ParsedPcd::parse_and_verify(
+            &bytes,
+            &mut hasher,
+            &mut rsa,
+            &OutOfMemory,
+            &OutOfMemory,
+        )
+        .unwrap();
+
+        let rot = pcd.rot(&mut hasher, &OutOfMemory).unwrap().unwrap();
+        assert_eq!(rot.address(), 0x41);
+        assert_eq!(rot.component_count(), 2);
+
+        let ports = pcd
+            .ports()
+            .map(|p| p.read(&mut hasher, &OutOfMemory).unwrap())
+            .map(|p| (p.id(), p.spi_frequency_hz()))
+            .collect::<Vec<_>>();
+        assert_eq!(ports, &[(0, 33_000_000)]);
+
+        let arena = BumpArena::new([0; 64]);
+        let components = pcd
+            .components()
+            .map(|c| c.read(&mut hasher, &arena).unwrap())
+            .map(|c| (c.component_type(), c.bus(), c.address(), c.eid()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            components,
+            &[
+                (&b"nic"[..], 1, 0x22, 0x0c),
+                (&b"accelerator"[..], 3, 0x30, 0x0d),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn component_count_overflow() {
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, _) = ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        #[rustfmt::skip]
+        let bytes = Ram(sign(r#"{
+            "version_id": 1,
+            "elements": [
+                {
+                    "port_count": 0,
+                    "component_count": 200,
+                    "address": "0x41",
+                    "eid": "0x0b",
+                    "bridge_address": "0x10",
+                    "bridge_eid": "0x08",
+                    "flags": "0b0"
+                },
+                {
+                    "component_type": "nic",
+                    "bus": 1,
+                    "address": "0x22",
+                    "eid": "0x0c",
+                    "power_ctrl_reg": "0x00",
+                    "power_ctrl_mask": "0b0",
+                    "i2c_mode": "0b0",
+                    "policy": "0b0"
+                }
+            ]
+        }"#));
+
+        let pcd = ParsedPcd::parse_and_verify(
+            &bytes,
+            &mut hasher,
+            &mut rsa,
+            &OutOfMemory,
+            &OutOfMemory,
+        )
+        .unwrap();
+        assert!(matches!(
+            pcd.rot(&mut hasher, &OutOfMemory),
+            Err(e) if matches!(e.into_inner(), Error::BadCount { toc_index: 0 }),
+        ));
+    }
+}
@@ -26,7 +26,10 @@ use zerocopy::FromBytes;
 use zerocopy::LayoutVerified;
 
 use crate::crypto::hash;
+use crate::crypto::sig;
+use crate::hardware::flash::Flash;
 use crate::hardware::flash::Region;
+use crate::manifest;
 use crate::manifest::provenance;
 use crate::manifest::provenance::Provenance;
 use crate::manifest::Container;
@@ -117,6 +120,28 @@ impl<'pfm, P> ParsedPfm<'pfm, P> {
     }
 }
 
+impl<'pfm> ParsedPfm<'pfm> {
+    /// Parses a PFM out of `flash`, verifying its signature before anything
+    /// else in it is trusted.
+    ///
+    /// See [`manifest::parse_and_verify()`].
+    pub fn parse_and_verify(
+        flash: &'pfm dyn Flash,
+        hasher: &mut dyn hash::Engine,
+        sig_verify: &mut dyn sig::Verify,
+        toc_arena: &'pfm dyn Arena,
+        verify_arena: &dyn Arena,
+    ) -> Result<Self, Error> {
+        manifest::parse_and_verify::<Pfm>(
+            flash,
+            hasher,
+            sig_verify,
+            toc_arena,
+            verify_arena,
+        )
+    }
+}
+
 impl<'pfm, P> ParsedPfm<'pfm, P>
 where
     P: Provenance,
@@ -720,6 +745,46 @@ mod test {
         assert_eq!(pfm.allowable_fws().count(), 0);
     }
 
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn tampered() {
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        #[rustfmt::skip]
+        let pfm: owned::Pfm = from_str(r#"{
+            "version_id": 42,
+            "elements": [{ "platform_id": "my pfm" }]
+        }"#).unwrap();
+        let mut bytes = pfm
+            .sign(0x0, hash::Algo::Sha256, &mut hasher, &mut signer)
+            .unwrap();
+
+        assert!(ParsedPfm::parse_and_verify(
+            &Ram(&bytes[..]),
+            &mut hasher,
+            &mut rsa,
+            &OutOfMemory,
+            &OutOfMemory,
+        )
+        .is_ok());
+
+        // Flip a bit in the version ID, which is covered by the signature
+        // but not by any TOC hash.
+        bytes[4] ^= 1;
+        assert!(matches!(
+            ParsedPfm::parse_and_verify(
+                &Ram(&bytes[..]),
+                &mut hasher,
+                &mut rsa,
+                &OutOfMemory,
+                &OutOfMemory,
+            ),
+            Err(e) if matches!(e.into_inner(), Error::SigError(_)),
+        ));
+    }
+
     #[test]
     #[cfg_attr(miri, ignore)]
     fn platform_id() {
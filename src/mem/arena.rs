@@ -118,9 +118,81 @@ pub unsafe trait Arena {
     /// assert!(arena.alloc_raw(Layout::new::<[u8; 64]>()).is_ok());
     /// ```
     fn reset(&mut self);
+
+    /// Returns a mark recording how much of this arena has been allocated
+    /// so far, which can later be passed to [`Arena::truncate_to()`].
+    ///
+    /// Arenas that do not support LIFO reclamation may return any value; the
+    /// default implementation does exactly that.
+    fn mark(&self) -> ArenaMark {
+        ArenaMark {
+            offset: 0,
+            padding: 0,
+        }
+    }
+
+    /// Rolls this arena back to the state recorded by `mark`, freeing every
+    /// allocation made since [`Arena::mark()`] returned it.
+    ///
+    /// This allows a parser to speculatively allocate, and then give back the
+    /// memory if it turns out to be unnecessary, without needing to reset the
+    /// whole arena. Allocations made *before* the mark remain valid.
+    ///
+    /// This function need not actually do anything; the default
+    /// implementation is a no-op.
+    ///
+    /// # Safety
+    ///
+    /// Calling this function invalidates every slice that was returned by
+    /// this arena after `mark` was created: the caller must ensure that all
+    /// such slices are unreachable, since they may be handed out again by
+    /// subsequent allocations.
+    ///
+    /// If `mark` was obtained from a different arena, or from before the last
+    /// call to [`Arena::reset()`], there is no telling which slices it
+    /// invalidates, so the caller must instead ensure that every slice
+    /// returned since the last reset is unreachable.
+    unsafe fn truncate_to(&self, #[allow(unused)] mark: ArenaMark) {}
+
+    /// Returns how many bytes of this arena are currently allocated,
+    /// including any padding inserted for alignment.
+    ///
+    /// Arenas that do not track usage may return zero; the default
+    /// implementation does exactly that.
+    fn used(&self) -> usize {
+        0
+    }
+
+    /// Returns the largest value [`Arena::used()`] has ever reached, across
+    /// calls to [`Arena::reset()`].
+    ///
+    /// This is useful for sizing arenas against real workloads. Arenas that
+    /// do not track usage may return zero; the default implementation does
+    /// exactly that.
+    fn high_water(&self) -> usize {
+        0
+    }
+
+    /// Returns how many of the bytes counted by [`Arena::used()`] are padding,
+    /// skipped over to align an allocation rather than handed out.
+    ///
+    /// Arenas that do not track usage may return zero; the default
+    /// implementation does exactly that.
+    fn padding_used(&self) -> usize {
+        0
+    }
 }
 impl dyn Arena {} // Ensure object-safety.
 
+/// A snapshot of an [`Arena`]'s allocation state.
+///
+/// See [`Arena::mark()`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ArenaMark {
+    offset: usize,
+    padding: usize,
+}
+
 /// Convenience functions for arenas, exposed as a trait.
 ///
 /// Note that this trait is implemened for `&impl Arena`, which is the reason
@@ -233,6 +305,9 @@ pub struct BumpArena<B: Buf> {
     // Invariant: cursor <= buf_len. This invariant is assumed when performing
     // unsafe operations.
     cursor: Cell<usize>,
+    high_water: Cell<usize>,
+    // The number of bytes below `cursor` that are alignment padding.
+    padding: Cell<usize>,
 }
 
 impl<B: Buf> BumpArena<B> {
@@ -249,6 +324,8 @@ impl<B: Buf> BumpArena<B> {
             buf,
             _ph: PhantomData,
             cursor: Cell::new(0),
+            high_water: Cell::new(0),
+            padding: Cell::new(0),
         }
     }
 
@@ -307,12 +384,13 @@ impl<'arena> BumpArenaRef<'arena> {
         Ok(slice)
     }
 
-    /// Aligns the internal buffer to the given alignment.
+    /// Aligns the internal buffer to the given alignment, returning the number
+    /// of padding bytes skipped to do so.
     ///
     /// # Panics
     ///
     /// `align` must be a power of two.
-    fn align_to(self, align: usize) -> Result<(), OutOfMemory> {
+    fn align_to(self, align: usize) -> Result<usize, OutOfMemory> {
         assert!(align.is_power_of_two());
 
         // SAFE: see the safety notes in alloc_raw().
@@ -325,7 +403,7 @@ impl<'arena> BumpArenaRef<'arena> {
         let misalignment = aligned - current_addr;
 
         self.alloc_inner(misalignment)?;
-        Ok(())
+        Ok(misalignment)
     }
 }
 
@@ -338,8 +416,12 @@ unsafe impl<B: Buf> Arena for BumpArena<B> {
         }
 
         let a = self.as_ref();
-        a.align_to(layout.align())?;
-        a.alloc_inner(layout.size())
+        let padding = a.align_to(layout.align())?;
+        self.padding.set(self.padding.get() + padding);
+        let buf = a.alloc_inner(layout.size())?;
+        self.high_water
+            .set(self.high_water.get().max(self.cursor.get()));
+        Ok(buf)
     }
 
     // NOTE: because this function takes `self` by unique reference, no mutable
@@ -349,7 +431,37 @@ unsafe impl<B: Buf> Arena for BumpArena<B> {
     // Thus, followup calls to alloc cannot create aliases, since there are no
     // outstanding poitners to alias.
     fn reset(&mut self) {
-        self.cursor.set(0)
+        self.cursor.set(0);
+        self.padding.set(0);
+    }
+
+    fn mark(&self) -> ArenaMark {
+        ArenaMark {
+            offset: self.cursor.get(),
+            padding: self.padding.get(),
+        }
+    }
+
+    // NOTE: the cursor is only ever allowed to move backwards here, which
+    // maintains the `cursor <= buf_len` invariant even if `mark` came from
+    // somewhere else.
+    unsafe fn truncate_to(&self, mark: ArenaMark) {
+        if mark.offset < self.cursor.get() {
+            self.cursor.set(mark.offset);
+            self.padding.set(self.padding.get().min(mark.padding));
+        }
+    }
+
+    fn used(&self) -> usize {
+        self.cursor.get()
+    }
+
+    fn high_water(&self) -> usize {
+        self.high_water.get()
+    }
+
+    fn padding_used(&self) -> usize {
+        self.padding.get()
     }
 }
 
@@ -581,4 +693,75 @@ mod test {
         assert_eq!(buf.len(), 0);
         assert_eq!(buf.as_ptr() as usize % 4, 0);
     }
+
+    #[test]
+    fn bump_truncate() {
+        let arena = BumpArena::<[u8; 64]>::new([0; 64]);
+
+        let kept = arena.alloc_slice::<u8>(16).unwrap();
+        kept.copy_from_slice(&[0xaa; 16]);
+
+        let mark = arena.mark();
+        let speculative = arena.alloc_slice::<u8>(48).unwrap();
+        speculative[0] = 0x55;
+        assert!(arena.alloc_slice::<u8>(1).is_err());
+
+        // SAFE: `speculative` is not used past this point.
+        unsafe { arena.truncate_to(mark) };
+        let buf = arena.alloc_slice::<u8>(48).unwrap();
+        assert_eq!(buf.len(), 48);
+        assert_eq!(kept, &[0xaa; 16]);
+    }
+
+    #[test]
+    fn bump_truncate_stale_mark() {
+        let mut arena = BumpArena::<[u8; 64]>::new([0; 64]);
+
+        arena.alloc_slice::<u8>(32).unwrap();
+        let mark = arena.mark();
+        arena.reset();
+
+        // A mark from before a reset must never move the cursor forwards.
+        arena.alloc_slice::<u8>(8).unwrap();
+        // SAFE: the only slice allocated since the reset was discarded above.
+        unsafe { arena.truncate_to(mark) };
+        assert_eq!(arena.alloc_slice::<u8>(56).unwrap().len(), 56);
+    }
+
+    #[test]
+    fn bump_usage() {
+        let mut arena = BumpArena::<[u8; 64]>::new([0; 64]);
+        assert_eq!((arena.used(), arena.high_water()), (0, 0));
+
+        arena.alloc_slice::<u8>(40).unwrap();
+        assert_eq!((arena.used(), arena.high_water()), (40, 40));
+
+        arena.reset();
+        arena.alloc_slice::<u8>(8).unwrap();
+        assert_eq!((arena.used(), arena.high_water()), (8, 40));
+    }
+
+    #[test]
+    fn bump_padding() {
+        let mut arena = BumpArena::<[u8; 256]>::new([0; 256]);
+        let layout = Layout::from_size_align(1, 64).unwrap();
+
+        // The padding ahead of the first allocation depends on where the
+        // buffer happens to live, but the padding after it does not.
+        arena.alloc_raw(layout).unwrap();
+        let first = arena.padding_used();
+        assert!(first < 64);
+
+        let mark = arena.mark();
+        arena.alloc_raw(layout).unwrap();
+        assert_eq!(arena.padding_used(), first + 63);
+        assert_eq!(arena.used(), arena.padding_used() + 2);
+
+        // SAFE: nothing allocated since `mark` is used past this point.
+        unsafe { arena.truncate_to(mark) };
+        assert_eq!(arena.padding_used(), first);
+
+        arena.reset();
+        assert_eq!(arena.padding_used(), 0);
+    }
 }
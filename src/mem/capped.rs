@@ -0,0 +1,170 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Arenas that limit how many allocations they hand out.
+
+#![allow(unsafe_code)]
+
+use core::alloc::Layout;
+use core::cell::Cell;
+
+use crate::mem::Arena;
+use crate::mem::ArenaMark;
+use crate::mem::OutOfMemory;
+use crate::Result;
+
+/// The default value of [`CappedArena::max_allocs()`].
+///
+/// This is far more than any single Cerberus message needs.
+pub const DEFAULT_MAX_ALLOCS: usize = 64;
+
+/// An [`Arena`] that fails every allocation past a fixed count, until it is
+/// reset.
+///
+/// Bounding the size of a message does not bound how many allocations parsing
+/// it makes: a message of deeply nested or many tiny pieces can stay under the
+/// size limit while making the parser allocate for each one. Wrapping the
+/// arena that requests are parsed into with a `CappedArena`, and resetting it
+/// between requests, turns such a message into an [`OutOfMemory`] error.
+///
+/// Zero-sized allocations are never counted, since they must always succeed.
+/// Allocations freed with [`Arena::truncate_to()`] still count.
+///
+/// # Examples
+/// ```
+/// # use manticore::mem::*;
+/// let mut arena = CappedArena::with_limit(BumpArena::new([0; 64]), 2);
+///
+/// assert!(arena.alloc::<u8>().is_ok());
+/// assert!(arena.alloc::<u8>().is_ok());
+/// assert!(arena.alloc::<u8>().is_err());
+/// assert_eq!(arena.allocs(), 2);
+///
+/// arena.reset();
+/// assert!(arena.alloc::<u8>().is_ok());
+/// # Ok::<(), manticore::Error<OutOfMemory>>(())
+/// ```
+pub struct CappedArena<A> {
+    inner: A,
+    max_allocs: usize,
+    allocs: Cell<usize>,
+}
+
+impl<A: Arena> CappedArena<A> {
+    /// Wraps `inner`, allowing [`DEFAULT_MAX_ALLOCS`] allocations between
+    /// resets.
+    pub fn new(inner: A) -> Self {
+        Self::with_limit(inner, DEFAULT_MAX_ALLOCS)
+    }
+
+    /// Wraps `inner`, allowing `max_allocs` allocations between resets.
+    pub fn with_limit(inner: A, max_allocs: usize) -> Self {
+        Self {
+            inner,
+            max_allocs,
+            allocs: Cell::new(0),
+        }
+    }
+
+    /// Returns the number of allocations allowed between resets.
+    pub fn max_allocs(&self) -> usize {
+        self.max_allocs
+    }
+
+    /// Returns the number of allocations made since the last reset.
+    pub fn allocs(&self) -> usize {
+        self.allocs.get()
+    }
+
+    /// Returns the wrapped arena.
+    pub fn into_inner(self) -> A {
+        self.inner
+    }
+}
+
+unsafe impl<A: Arena> Arena for CappedArena<A> {
+    fn alloc_raw(&self, layout: Layout) -> Result<&mut [u8], OutOfMemory> {
+        if layout.size() == 0 {
+            return self.inner.alloc_raw(layout);
+        }
+
+        let allocs = self.allocs.get();
+        if allocs >= self.max_allocs {
+            warn!("exceeded the limit of {} allocations", self.max_allocs);
+            return Err(fail!(OutOfMemory));
+        }
+        let buf = self.inner.alloc_raw(layout)?;
+        self.allocs.set(allocs + 1);
+        Ok(buf)
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.allocs.set(0);
+    }
+
+    fn mark(&self) -> ArenaMark {
+        self.inner.mark()
+    }
+
+    unsafe fn truncate_to(&self, mark: ArenaMark) {
+        self.inner.truncate_to(mark)
+    }
+
+    fn used(&self) -> usize {
+        self.inner.used()
+    }
+
+    fn high_water(&self) -> usize {
+        self.inner.high_water()
+    }
+
+    fn padding_used(&self) -> usize {
+        self.inner.padding_used()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mem::ArenaExt as _;
+    use crate::mem::BumpArena;
+    use crate::protocol::cerberus::get_digests::GetDigestsResponse;
+    use crate::protocol::wire;
+    use crate::protocol::wire::FromWire as _;
+
+    #[test]
+    fn caps_allocations() {
+        let mut arena = CappedArena::with_limit(BumpArena::new([0; 64]), 3);
+        for _ in 0..3 {
+            assert!(arena.alloc_slice::<u8>(4).is_ok());
+        }
+        assert!(arena.alloc_slice::<u8>(4).is_err());
+        assert!(arena.alloc_slice::<u8>(0).is_ok());
+        assert_eq!(arena.allocs(), 3);
+        assert_eq!(arena.used(), 12);
+
+        // A failed allocation in the inner arena does not count.
+        arena.reset();
+        assert!(arena.alloc_slice::<u8>(128).is_err());
+        assert_eq!(arena.allocs(), 0);
+    }
+
+    #[test]
+    fn message_over_cap() {
+        // One digest, which `GetDigests` allocates space for.
+        let resp = [&[0x01, 0x01][..], &[0x55; 32]].concat();
+
+        let arena = CappedArena::with_limit(BumpArena::new([0; 64]), 0);
+        let err =
+            GetDigestsResponse::from_wire(&mut &resp[..], &arena).unwrap_err();
+        assert_eq!(err.into_inner(), wire::Error::OutOfMemory);
+
+        let arena = CappedArena::with_limit(BumpArena::new([0; 64]), 1);
+        let digests = GetDigestsResponse::from_wire(&mut &resp[..], &arena)
+            .unwrap()
+            .digests;
+        assert_eq!(digests, &[[0x55; 32]]);
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Arenas made up of several backing regions.
+
+#![allow(unsafe_code)]
+
+use core::alloc::Layout;
+use core::cell::Cell;
+
+use crate::mem::Arena;
+use crate::mem::OutOfMemory;
+use crate::Result;
+
+/// An [`Arena`] that allocates from a list of backing arenas, moving on to
+/// the next one whenever the current one fills up.
+///
+/// This makes it possible to serve the occasional large message without
+/// sizing every arena for the worst case: a small first region handles the
+/// common case, and later regions are only touched when it runs out.
+///
+/// A single allocation never spans regions, so an allocation larger than any
+/// one region still fails. Regions that have been moved past are not revisited
+/// until the chain is reset.
+///
+/// [`Arena::mark()`] and [`Arena::truncate_to()`] are not supported, and use
+/// the default no-op implementations.
+///
+/// # Examples
+/// ```
+/// # use manticore::mem::*;
+/// let mut regions = [BumpArena::new(vec![0; 32]), BumpArena::new(vec![0; 64])];
+/// let mut arena = ChainedArena::new(&mut regions);
+///
+/// assert!(arena.alloc_slice::<u8>(24).is_ok());
+/// assert!(arena.alloc_slice::<u8>(48).is_ok());
+/// assert!(arena.alloc_slice::<u8>(96).is_err());
+/// assert_eq!(arena.used(), 72);
+///
+/// arena.reset();
+/// assert_eq!(arena.used(), 0);
+/// # Ok::<(), manticore::Error<OutOfMemory>>(())
+/// ```
+pub struct ChainedArena<'r, A> {
+    regions: &'r mut [A],
+    // Invariant: current <= regions.len().
+    current: Cell<usize>,
+}
+
+impl<'r, A: Arena> ChainedArena<'r, A> {
+    /// Creates a new `ChainedArena` that allocates from each of `regions`, in
+    /// order.
+    pub fn new(regions: &'r mut [A]) -> Self {
+        Self {
+            regions,
+            current: Cell::new(0),
+        }
+    }
+}
+
+unsafe impl<A: Arena> Arena for ChainedArena<'_, A> {
+    fn alloc_raw(&self, layout: Layout) -> Result<&mut [u8], OutOfMemory> {
+        if layout.size() == 0 {
+            // Forward to OutOfMemory, which will always succeed on zero-length
+            // allocations, even if there are no regions at all.
+            return OutOfMemory.alloc_raw(layout);
+        }
+
+        for (i, region) in self.regions.iter().enumerate() {
+            if i < self.current.get() {
+                continue;
+            }
+            if let Ok(buf) = region.alloc_raw(layout) {
+                self.current.set(i);
+                return Ok(buf);
+            }
+        }
+        Err(fail!(OutOfMemory))
+    }
+
+    // NOTE: as with `BumpArena`, taking `self` by unique reference means that
+    // no slice handed out by any of the regions can still be reachable.
+    fn reset(&mut self) {
+        for region in self.regions.iter_mut() {
+            region.reset();
+        }
+        self.current.set(0);
+    }
+
+    fn used(&self) -> usize {
+        self.regions.iter().map(|r| r.used()).sum()
+    }
+
+    fn high_water(&self) -> usize {
+        self.regions.iter().map(|r| r.high_water()).sum()
+    }
+
+    fn padding_used(&self) -> usize {
+        self.regions.iter().map(|r| r.padding_used()).sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mem::ArenaExt as _;
+    use crate::mem::BumpArena;
+
+    #[test]
+    fn spills_over() {
+        let mut regions =
+            [BumpArena::new(vec![0; 16]), BumpArena::new(vec![0; 32])];
+        let mut arena = ChainedArena::new(&mut regions);
+
+        let a = arena.alloc_slice::<u8>(12).unwrap();
+        let b = arena.alloc_slice::<u8>(12).unwrap();
+        a.copy_from_slice(&[0xaa; 12]);
+        b.copy_from_slice(&[0xbb; 12]);
+        assert_eq!(a, &[0xaa; 12]);
+
+        // Too big for any one region, even though there's enough space in
+        // total.
+        assert!(arena.alloc_slice::<u8>(40).is_err());
+        assert!(arena.alloc_slice::<u8>(0).is_ok());
+        assert_eq!(arena.used(), 24);
+
+        arena.reset();
+        assert_eq!(arena.used(), 0);
+        assert_eq!(arena.high_water(), 24);
+        assert_eq!(arena.alloc_slice::<u8>(16).unwrap().len(), 16);
+    }
+
+    #[test]
+    fn no_regions() {
+        let mut regions: [BumpArena<[u8; 1]>; 0] = [];
+        let arena = ChainedArena::new(&mut regions);
+        assert!(arena.alloc_slice::<u8>(1).is_err());
+        assert!(arena.alloc_slice::<u8>(0).is_ok());
+    }
+}
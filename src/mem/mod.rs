@@ -8,6 +8,19 @@
 mod arena;
 pub use arena::*;
 
+mod capped;
+pub use capped::CappedArena;
+pub use capped::DEFAULT_MAX_ALLOCS;
+
+mod chain;
+pub use chain::ChainedArena;
+
+mod pool;
+pub use pool::*;
+
+mod vec;
+pub use vec::ArenaVec;
+
 /// Aligns the given address to the alignment for the given type.
 ///
 /// `align` must be a power of two; otherwise, the returned value
@@ -0,0 +1,279 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Bounded pools of arenas.
+//!
+//! A server handling many connections at once typically gives each one its
+//! own arena; left unchecked, total memory use then grows with the number of
+//! connections. An [`ArenaPool`] bounds it instead, by handing out at most a
+//! fixed number of fixed-size arenas at a time.
+//!
+//! [`ArenaPool`] requires the `std` feature flag to be enabled.
+
+// The in-use counter is waited on with a `Condvar`, so it cannot be an
+// atomic.
+#![allow(clippy::mutex_atomic)]
+
+#[cfg(feature = "std")]
+use core::ops::Deref;
+#[cfg(feature = "std")]
+use core::ops::DerefMut;
+
+#[cfg(feature = "std")]
+use std::sync::Arc;
+#[cfg(feature = "std")]
+use std::sync::Condvar;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+#[cfg(feature = "std")]
+use std::sync::MutexGuard;
+
+#[cfg(feature = "std")]
+use crate::mem::BumpArena;
+#[cfg(feature = "std")]
+use crate::Result;
+
+/// What [`ArenaPool::take()`] should do when every arena is in use.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WhenExhausted {
+    /// Block until another arena is returned to the pool.
+    Block,
+    /// Fail immediately with [`PoolExhausted`].
+    Reject,
+}
+
+/// An error returned by [`ArenaPool::take()`] when every arena in the pool is
+/// in use.
+///
+/// This converts into a "busy" protocol error, so that the peer knows to
+/// retry later.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PoolExhausted;
+
+/// A bounded pool of fixed-size [`BumpArena`]s.
+///
+/// Cloning an `ArenaPool` produces a new handle to the same pool, which can
+/// be sent to another thread.
+///
+/// # Examples
+/// ```
+/// # use manticore::mem::*;
+/// let pool = ArenaPool::new(1, 128, WhenExhausted::Reject);
+///
+/// let arena = pool.take()?;
+/// assert!(arena.alloc_slice::<u8>(128).is_ok());
+/// assert!(pool.take().is_err());
+///
+/// drop(arena);
+/// assert!(pool.take().is_ok());
+/// # Ok::<(), manticore::Error<PoolExhausted>>(())
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone)]
+pub struct ArenaPool {
+    shared: Arc<Shared>,
+    arena_size: usize,
+    when_exhausted: WhenExhausted,
+}
+
+/// State shared by all handles to an [`ArenaPool`].
+#[cfg(feature = "std")]
+struct Shared {
+    capacity: usize,
+    in_use: Mutex<usize>,
+    returned: Condvar,
+}
+
+#[cfg(feature = "std")]
+impl Shared {
+    fn lock(&self) -> MutexGuard<usize> {
+        // The counter is always consistent, even if a thread panicked while
+        // holding the lock.
+        self.in_use.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(feature = "std")]
+impl ArenaPool {
+    /// Creates a new pool of `count` arenas, each `arena_size` bytes long.
+    ///
+    /// The pool never uses more than `count * arena_size` bytes of arena
+    /// memory at a time. Arenas are allocated when taken, and freed when
+    /// returned.
+    pub fn new(
+        count: usize,
+        arena_size: usize,
+        when_exhausted: WhenExhausted,
+    ) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                capacity: count,
+                in_use: Mutex::new(0),
+                returned: Condvar::new(),
+            }),
+            arena_size,
+            when_exhausted,
+        }
+    }
+
+    /// Takes an arena out of this pool; it is returned when dropped.
+    ///
+    /// If every arena is in use, this function either blocks or fails with
+    /// [`PoolExhausted`], according to the pool's [`WhenExhausted`] policy.
+    pub fn take(&self) -> Result<PooledArena, PoolExhausted> {
+        let mut in_use = self.shared.lock();
+        while *in_use >= self.shared.capacity {
+            check!(self.when_exhausted == WhenExhausted::Block, PoolExhausted);
+            in_use = self
+                .shared
+                .returned
+                .wait(in_use)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        *in_use += 1;
+        drop(in_use);
+
+        Ok(PooledArena {
+            arena: BumpArena::new(vec![0; self.arena_size]),
+            shared: Arc::clone(&self.shared),
+        })
+    }
+
+    /// Returns the number of arenas currently taken out of this pool.
+    pub fn in_use(&self) -> usize {
+        *self.shared.lock()
+    }
+}
+
+/// An arena taken out of an [`ArenaPool`].
+///
+/// Dropping this value frees the arena and returns its slot to the pool.
+#[cfg(feature = "std")]
+pub struct PooledArena {
+    arena: BumpArena<Vec<u8>>,
+    shared: Arc<Shared>,
+}
+
+#[cfg(feature = "std")]
+impl Deref for PooledArena {
+    type Target = BumpArena<Vec<u8>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.arena
+    }
+}
+
+#[cfg(feature = "std")]
+impl DerefMut for PooledArena {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.arena
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for PooledArena {
+    fn drop(&mut self) {
+        *self.shared.lock() -= 1;
+        self.shared.returned.notify_one();
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::mem::Arena as _;
+    use crate::mem::ArenaExt as _;
+
+    #[test]
+    fn reject() {
+        let pool = ArenaPool::new(2, 64, WhenExhausted::Reject);
+        let a = pool.take().unwrap();
+        let b = pool.take().unwrap();
+        assert_eq!(pool.in_use(), 2);
+        assert_eq!(pool.take().err().unwrap().into_inner(), PoolExhausted);
+
+        drop(a);
+        assert_eq!(pool.in_use(), 1);
+        let mut c = pool.take().unwrap();
+        assert!(c.alloc_slice::<u8>(64).is_ok());
+        assert!(c.alloc_slice::<u8>(1).is_err());
+        c.reset();
+        assert!(c.alloc_slice::<u8>(1).is_ok());
+        drop((b, c));
+        assert_eq!(pool.in_use(), 0);
+    }
+
+    #[test]
+    fn block() {
+        let pool = ArenaPool::new(1, 64, WhenExhausted::Block);
+        let arena = pool.take().unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let waiter = {
+            let pool = pool.clone();
+            thread::spawn(move || {
+                let arena = pool.take();
+                tx.send(arena.is_ok()).unwrap();
+            })
+        };
+        assert!(rx.recv_timeout(Duration::from_millis(20)).is_err());
+
+        drop(arena);
+        assert!(rx.recv().unwrap());
+        waiter.join().unwrap();
+        assert_eq!(pool.in_use(), 0);
+    }
+
+    #[test]
+    fn stress() {
+        const CAPACITY: usize = 4;
+        const THREADS: usize = 16;
+        const ROUNDS: usize = 200;
+
+        for &policy in &[WhenExhausted::Block, WhenExhausted::Reject] {
+            let pool = ArenaPool::new(CAPACITY, 256, policy);
+            let peak = Arc::new(AtomicUsize::new(0));
+            let rejected = Arc::new(AtomicUsize::new(0));
+
+            let threads = (0..THREADS)
+                .map(|_| {
+                    let pool = pool.clone();
+                    let peak = Arc::clone(&peak);
+                    let rejected = Arc::clone(&rejected);
+                    thread::spawn(move || {
+                        for _ in 0..ROUNDS {
+                            let arena = match pool.take() {
+                                Ok(arena) => arena,
+                                Err(_) => {
+                                    rejected.fetch_add(1, Ordering::Relaxed);
+                                    thread::yield_now();
+                                    continue;
+                                }
+                            };
+                            peak.fetch_max(pool.in_use(), Ordering::Relaxed);
+                            arena.alloc_slice::<u8>(256).unwrap();
+                            thread::yield_now();
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            for t in threads {
+                t.join().unwrap();
+            }
+
+            assert_eq!(pool.in_use(), 0);
+            assert!(peak.load(Ordering::Relaxed) <= CAPACITY);
+            if policy == WhenExhausted::Block {
+                assert_eq!(rejected.load(Ordering::Relaxed), 0);
+            }
+        }
+    }
+}
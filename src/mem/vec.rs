@@ -0,0 +1,182 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A growable, arena-backed byte buffer.
+
+use crate::io;
+use crate::mem::Arena;
+use crate::mem::ArenaExt as _;
+use crate::mem::OutOfMemory;
+use crate::Result;
+
+/// The capacity of the first chunk an [`ArenaVec`] allocates.
+const MIN_CAPACITY: usize = 64;
+
+/// A growable byte buffer, like a `Vec<u8>`, that allocates out of an
+/// [`Arena`] rather than the global allocator.
+///
+/// This type is intended for buffering a message whose length is not known
+/// up front, such as a response that must be prefixed by its length, on
+/// targets without `alloc`.
+///
+/// When the buffer runs out of capacity, it allocates a new chunk twice as
+/// large out of the arena and copies its contents over. Because arenas cannot
+/// free individual allocations, outgrown chunks are not reclaimed until the
+/// arena is reset; in the worst case, an `ArenaVec` of length `n` uses up to
+/// `4 * n` bytes of the arena. Callers that know the final length should use
+/// [`ArenaVec::with_capacity()`].
+///
+/// If the arena is exhausted, writes fail with [`OutOfMemory`] (or
+/// [`io::Error::BufferExhausted`], via [`io::Write`]), and the contents of the
+/// buffer are left unchanged.
+pub struct ArenaVec<'a> {
+    arena: &'a dyn Arena,
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> ArenaVec<'a> {
+    /// Creates a new, empty `ArenaVec`. This function does not allocate.
+    pub fn new(arena: &'a dyn Arena) -> Self {
+        Self {
+            arena,
+            buf: &mut [],
+            len: 0,
+        }
+    }
+
+    /// Creates a new, empty `ArenaVec` with room for `capacity` bytes.
+    pub fn with_capacity(
+        arena: &'a dyn Arena,
+        capacity: usize,
+    ) -> Result<Self, OutOfMemory> {
+        Ok(Self {
+            arena,
+            buf: arena.alloc_slice(capacity)?,
+            len: 0,
+        })
+    }
+
+    /// Returns the number of bytes in this buffer.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether this buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bytes this buffer can hold without allocating.
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Returns the contents of this buffer.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Returns the contents of this buffer, mutably.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.buf[..self.len]
+    }
+
+    /// Consumes this buffer, returning its contents with the lifetime of the
+    /// arena.
+    pub fn into_slice(self) -> &'a mut [u8] {
+        &mut self.buf[..self.len]
+    }
+
+    /// Empties this buffer, keeping its capacity.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Appends `bytes` to the end of this buffer, growing it if necessary.
+    pub fn extend_from_slice(
+        &mut self,
+        bytes: &[u8],
+    ) -> Result<(), OutOfMemory> {
+        let new_len = self
+            .len
+            .checked_add(bytes.len())
+            .ok_or_else(|| fail!(OutOfMemory))?;
+        if new_len > self.capacity() {
+            self.grow(new_len)?;
+        }
+
+        self.buf[self.len..new_len].copy_from_slice(bytes);
+        self.len = new_len;
+        Ok(())
+    }
+
+    /// Reallocates this buffer so that it can hold at least `min_capacity`
+    /// bytes.
+    fn grow(&mut self, min_capacity: usize) -> Result<(), OutOfMemory> {
+        let capacity = self
+            .capacity()
+            .saturating_mul(2)
+            .max(min_capacity)
+            .max(MIN_CAPACITY);
+        let buf = self.arena.alloc_slice::<u8>(capacity)?;
+        buf[..self.len].copy_from_slice(&self.buf[..self.len]);
+        self.buf = buf;
+        Ok(())
+    }
+}
+
+impl io::Write for ArenaVec<'_> {
+    fn write_bytes(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        self.extend_from_slice(buf)
+            .map_err(|_| fail!(io::Error::BufferExhausted))?;
+        Ok(buf.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::io::Write as _;
+    use crate::mem::BumpArena;
+
+    #[test]
+    fn grows() {
+        let arena = BumpArena::new([0; 1024]);
+        let mut vec = ArenaVec::new(&arena);
+        assert!(vec.is_empty());
+        assert_eq!(vec.capacity(), 0);
+
+        for i in 0..100u8 {
+            vec.write_all(&[i, i]).unwrap();
+        }
+        assert_eq!(vec.len(), 200);
+        assert!(vec.capacity() >= 200);
+        assert!(vec
+            .as_slice()
+            .chunks(2)
+            .enumerate()
+            .all(|(i, c)| { c == [i as u8, i as u8] }));
+    }
+
+    #[test]
+    fn with_capacity() {
+        let arena = BumpArena::new([0; 16]);
+        let mut vec = ArenaVec::with_capacity(&arena, 16).unwrap();
+        vec.write_all(b"sixteen bytes!!!").unwrap();
+        assert_eq!(vec.into_slice(), b"sixteen bytes!!!");
+    }
+
+    #[test]
+    fn exhausted() {
+        let arena = BumpArena::new([0; 96]);
+        let mut vec = ArenaVec::new(&arena);
+        vec.write_all(&[0xaa; 64]).unwrap();
+
+        // Growing to 128 bytes does not fit, so the write must fail, leaving
+        // the buffer untouched.
+        assert!(vec.write_all(&[0xbb; 1]).is_err());
+        assert_eq!(vec.as_slice(), &[0xaa; 64][..]);
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Device-originated requests.
+//!
+//! See [`ClientPort`] for detailed information.
+
+use crate::io::ReadZero;
+use crate::net;
+use crate::Result;
+
+#[cfg(doc)]
+use crate::net::host::HostPort;
+
+/// Represents a physical port that a device can use to send requests of its
+/// own, acting as a client rather than a server.
+///
+/// This is the complement of [`HostPort`]: where a `HostPort` receives a
+/// request and then replies to it, a `ClientPort` sends a request and then
+/// receives the reply. A device that both challenges and responds to
+/// challenges, such as for mutual attestation, can drive one of each over the
+/// same transport.
+///
+/// This trait is parametrized over the lifetime of the response, `'resp`, and
+/// over the [`net::Header`] type it is responsible for encoding.
+/// ```
+/// # use manticore::Result;
+/// # use manticore::net::{*, client::*};
+/// fn send_request<'resp, Header>(
+///     port: &mut impl ClientPort<'resp, Header>,
+///     header: Header,
+///     message: &[u8],
+/// ) -> Result<(), Error> {
+///     let resp = port.send(header, message)?;
+///     let header = resp.header()?;
+///     let payload = resp.payload()?;
+///     // At this point, `header` and the bytes in `payload` can be used to
+///     // parse the response.
+///     Ok(())
+/// }
+/// ```
+///
+/// This trait is object-safe.
+///
+/// # Implementing this trait
+///
+/// As with [`HostPort`], an implementation is responsible for all transport
+/// details: framing, packetization, and so on. A response that is not fully
+/// read before the next call to `send()` must be discarded, so that it does
+/// not corrupt the next one.
+pub trait ClientPort<'resp, Header> {
+    /// Sends a request with the given `header` and `payload`, and blocks
+    /// until the peer replies to it.
+    ///
+    /// On success, returns a [`ClientResponse`] for reading the reply.
+    fn send(
+        &mut self,
+        header: Header,
+        payload: &[u8],
+    ) -> Result<&mut dyn ClientResponse<'resp, Header>, net::Error>;
+}
+impl<H> dyn ClientPort<'_, H> {} // Ensure object-safety.
+
+/// Provides the "response" half of a transaction originated by a device.
+///
+/// See [`ClientPort`] for more information.
+pub trait ClientResponse<'resp, Header> {
+    /// Returns the header sent by the peer for this response.
+    fn header(&self) -> Result<Header, net::Error>;
+
+    /// Returns the raw byte stream for the payload of the response.
+    fn payload(&mut self) -> Result<&mut dyn ReadZero<'resp>, net::Error>;
+}
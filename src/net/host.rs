@@ -6,12 +6,20 @@
 //!
 //! See [`HostPort`] for detailed information.
 
+use crate::io;
 use crate::io::Cursor;
 use crate::io::ReadZero;
 use crate::io::Write;
+use crate::mem::Arena;
+use crate::mem::ArenaExt as _;
 use crate::net;
+use crate::protocol::wire;
+use crate::protocol::wire::ToWire;
 use crate::Result;
 
+#[cfg(doc)]
+use crate::protocol::cerberus;
+
 /// Represents a physical port that can be used to interact with host devices.
 ///
 /// This trait provides a generic mechanism for receiving and responding to
@@ -46,7 +54,7 @@ use crate::Result;
 ///     let sink = resp.sink()?;
 ///     // Now, the computed response can be written to `sink`:
 /// #   let resp_message = [1, 2, 3];
-///     sink.write_bytes(&resp_message);
+///     sink.write_all(&resp_message);
 ///
 ///     // Finally, we finish the reply by calling `finish()`. This is
 ///     // necessary to signal to the port that we don't plan to add any
@@ -113,6 +121,69 @@ pub trait HostRequest<'req, Header> {
     /// This function should not be called after calling `reply()`.
     fn payload(&mut self) -> Result<&mut dyn ReadZero<'req>, net::Error>;
 
+    /// Returns the length of the payload of the request, as declared by the
+    /// transport, if it is known.
+    ///
+    /// This function may be called before `payload()`, so that a caller can
+    /// reject an obviously mis-sized request without parsing it. Once the
+    /// payload has been partially read, this returns the number of bytes that
+    /// remain.
+    ///
+    /// Returns `None` if the transport does not know the length ahead of time,
+    /// or if `reply()` has already been called. The default implementation
+    /// always returns `None`.
+    fn payload_len(&self) -> Option<usize> {
+        None
+    }
+
+    /// Reads the rest of the payload of the request into `arena`, returning
+    /// it as a single slice.
+    ///
+    /// This is an alternative to [`HostRequest::payload()`] for parsers that
+    /// need the whole payload at once, such as for checksumming or
+    /// decompression. Implementations that bound the size of a payload should
+    /// reject an oversized one here, too.
+    ///
+    /// The returned slice lives as long as `arena`, not as long as this
+    /// request, so an implementation cannot lend out its own receive buffer,
+    /// which the next call to [`HostPort::receive()`] may overwrite. The most
+    /// it can do is read the payload into `arena` without copying it anywhere
+    /// else first.
+    ///
+    /// This function should not be called after calling `reply()`.
+    fn payload_bytes(
+        &mut self,
+        arena: &'req dyn Arena,
+    ) -> Result<&'req [u8], net::Error> {
+        let r = self.payload()?;
+        let bytes = arena
+            .alloc_slice::<u8>(r.remaining_data())
+            .map_err(|_| fail!(net::Error::Io(io::Error::BufferExhausted)))?;
+        r.read_bytes(bytes)?;
+        Ok(bytes)
+    }
+
+    /// Returns the largest reply payload, in bytes, that can be sent in
+    /// response to this request, if it is known.
+    ///
+    /// Servers use this to replace a response that would not fit with an
+    /// error, rather than failing partway through writing it. The default
+    /// implementation always returns `None`.
+    fn reply_capacity(&self) -> Option<usize> {
+        None
+    }
+
+    /// Returns whether the transport marked this request as encrypted, such
+    /// as with the "crypt" bit of a Cerberus header.
+    ///
+    /// The payload is still returned exactly as it arrived: decrypting it is
+    /// up to the server, which owns the session the key belongs to; see
+    /// [`session`](crate::session) for the layout of an encrypted message.
+    /// The default implementation always returns `false`.
+    fn is_encrypted(&self) -> bool {
+        false
+    }
+
     /// Replies to this request..
     ///
     /// Calling this function performs sufficient transport-level operations to
@@ -124,19 +195,61 @@ pub trait HostRequest<'req, Header> {
     ) -> Result<&mut dyn HostResponse<'req>, net::Error>;
 }
 
+/// Convenience functions for [`HostRequest`]s.
+///
+/// This trait is implemented for every [`HostRequest`], including trait
+/// objects; implementers do not need to implement it themselves.
+pub trait HostRequestExt<'req, Header>: HostRequest<'req, Header> {
+    /// Replies to this request with an error message, such as a
+    /// [`cerberus::Error`].
+    ///
+    /// This function builds the reply header with
+    /// [`net::Header::reply_with_error()`], serializes `error` into the reply,
+    /// and finishes it, so that every error response has the same shape on
+    /// the wire.
+    fn reply_error<E: ToWire>(&mut self, error: E) -> Result<(), net::Error>
+    where
+        Header: net::Header,
+    {
+        let header = self.header()?.reply_with_error();
+        let reply = self.reply(header)?;
+        let sink = reply.sink()?;
+        error
+            .to_wire(&mut *sink)
+            .map_err(|e| match e.into_inner() {
+                wire::Error::Io(e) => fail!(net::Error::Io(e)),
+                _ => fail!(net::Error::Io(io::Error::Internal)),
+            })?;
+        sink.flush()?;
+        reply.finish()
+    }
+}
+
+impl<'req, Header, R> HostRequestExt<'req, Header> for R where
+    R: HostRequest<'req, Header> + ?Sized
+{
+}
+
 /// Provides the "reponse" half of a transaction with a host.
 ///
 /// See [`HostPort`] for more information.
 pub trait HostResponse<'req> {
     /// Returns the raw byte stream for building the payload of the response.
     ///
-    /// This function should not be called after calling `finish()`.
+    /// Servers [flush](Write::flush) this stream once the payload is written,
+    /// before calling `finish()`. This function should not be called after
+    /// calling `finish()`.
     fn sink(&mut self) -> Result<&mut dyn Write, net::Error>;
 
     /// Indicates that all payload data has been written.
     ///
     /// Callers should remember to call this function; failing to do so may
     /// result in a response not being sent properly.
+    ///
+    /// This function must only be called on a response started with
+    /// [`HostRequest::reply()`]; even an empty response must be started
+    /// before it is finished. Implementations should return
+    /// [`net::Error::NoResponseStarted`] otherwise.
     fn finish(&mut self) -> Result<(), net::Error>;
 }
 
@@ -280,6 +393,14 @@ impl<'req, 'buf: 'req, Header: Copy> HostRequest<'req, Header>
         Ok(&mut self.rx)
     }
 
+    fn payload_len(&self) -> Option<usize> {
+        self.rx_header.map(|_| self.rx.len())
+    }
+
+    fn reply_capacity(&self) -> Option<usize> {
+        Some(self.tx.remaining_len())
+    }
+
     fn reply(
         &mut self,
         header: Header,
@@ -299,7 +420,113 @@ impl<'req, 'buf: 'req, Header: Copy> HostResponse<'req>
     }
 
     fn finish(&mut self) -> Result<(), net::Error> {
+        check!(self.tx_header.is_some(), net::Error::NoResponseStarted);
         self.finished = true;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mem::BumpArena;
+    use crate::protocol::cerberus;
+    use crate::protocol::wire::FromWire as _;
+
+    #[test]
+    fn reply_error() {
+        let mut buf = [0; 64];
+        let mut host = InMemHost::new(&mut buf);
+        host.request(
+            net::CerberusHeader {
+                command: cerberus::CommandType::FirmwareVersion,
+            },
+            &[0],
+        );
+
+        let req = host.receive().unwrap();
+        req.reply_error(cerberus::Error::Busy).unwrap();
+
+        let (header, mut resp) = host.response().unwrap();
+        assert_eq!(header.command, cerberus::CommandType::Error);
+
+        let arena = BumpArena::new([0; 64]);
+        let err = cerberus::Error::from_wire(&mut resp, &arena).unwrap();
+        assert_eq!(err, cerberus::Error::Busy);
+        assert!(resp.is_empty());
+    }
+
+    #[test]
+    fn finish_without_reply() {
+        let header = net::CerberusHeader {
+            command: cerberus::CommandType::FirmwareVersion,
+        };
+        let mut buf = [0; 64];
+        let mut host = InMemHost::new(&mut buf);
+        host.request(header, &[0]);
+
+        host.receive().unwrap();
+        let err = HostResponse::finish(&mut host.0).unwrap_err();
+        assert_eq!(err.into_inner(), net::Error::NoResponseStarted);
+        assert!(host.response().is_none());
+
+        // The request is still there to reply to.
+        let resp = host.receive().unwrap().reply(header).unwrap();
+        resp.finish().unwrap();
+        assert_eq!(host.response().unwrap().0, header);
+    }
+
+    #[test]
+    fn payload_len() {
+        let mut buf = [0; 64];
+        let mut host = InMemHost::new(&mut buf);
+        host.request(
+            net::CerberusHeader {
+                command: cerberus::CommandType::FirmwareVersion,
+            },
+            &[0, 1, 2],
+        );
+
+        let req = host.receive().unwrap();
+        assert_eq!(req.payload_len(), Some(3));
+        req.payload().unwrap().read_bytes(&mut [0; 2]).unwrap();
+        assert_eq!(req.payload_len(), Some(1));
+
+        req.reply(net::CerberusHeader {
+            command: cerberus::CommandType::FirmwareVersion,
+        })
+        .unwrap();
+        assert_eq!(req.payload_len(), None);
+    }
+
+    #[test]
+    fn payload_bytes() {
+        let arena = BumpArena::new([0; 4]);
+        let mut buf = [0; 64];
+        let mut host = InMemHost::new(&mut buf);
+        host.request(
+            net::CerberusHeader {
+                command: cerberus::CommandType::FirmwareVersion,
+            },
+            &[0, 1, 2, 3],
+        );
+
+        let req = host.receive().unwrap();
+        req.payload().unwrap().read_bytes(&mut [0; 1]).unwrap();
+        assert_eq!(req.payload_bytes(&arena).unwrap(), &[1, 2, 3]);
+        assert_eq!(req.payload_len(), Some(0));
+        assert!(req.payload_bytes(&arena).unwrap().is_empty());
+
+        host.request(
+            net::CerberusHeader {
+                command: cerberus::CommandType::FirmwareVersion,
+            },
+            &[0, 1, 2, 3],
+        );
+        let req = host.receive().unwrap();
+        assert_eq!(
+            req.payload_bytes(&arena).unwrap_err().into_inner(),
+            net::Error::Io(io::Error::BufferExhausted)
+        );
+    }
+}
@@ -0,0 +1,349 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! MCTP packetization.
+//!
+//! MCTP splits each message into packets, each of which carries a four-byte
+//! transport header followed by at most one "transmission unit" of payload.
+//! Every endpoint must accept the 64-byte [`BASELINE_TRANSMISSION_UNIT`]; larger
+//! units may be negotiated through the `DeviceCapabilities` exchange, which
+//! is separate from the limit on the size of a whole message.
+//!
+//! [`Packetizer`] fragments a message into packets no larger than the
+//! negotiated unit, without copying it. Once a message has been reassembled,
+//! [`decode()`] splits it into its message type, body, and integrity-check
+//! tag, rejecting messages whose IC bit disagrees with whether they carry a
+//! tag.
+
+use crate::protocol::cerberus::capabilities::Networking;
+use crate::protocol::integrity;
+use crate::Result;
+
+/// An error returned by MCTP framing.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Error {
+    /// Indicates that a transmission unit smaller than
+    /// [`BASELINE_TRANSMISSION_UNIT`] was requested.
+    UnitTooSmall,
+    /// Indicates that a message's IC bit was set but it carried no
+    /// integrity-check tag, or that it carried a tag without its IC bit set.
+    IcMismatch,
+    /// Indicates that a message was too short to hold its body, or had
+    /// trailing bytes that could not be an integrity-check tag.
+    BadLength,
+}
+
+debug_from!(Error);
+
+/// The transmission unit, in bytes, that every MCTP endpoint must support.
+pub const BASELINE_TRANSMISSION_UNIT: usize = 64;
+
+/// The MCTP header version this module speaks.
+const HEADER_VERSION: u8 = 0x01;
+
+/// Returns the largest transmission unit that both `ours` and `theirs` can
+/// handle, according to their advertised capabilities.
+///
+/// Returns [`Error::UnitTooSmall`] if either side advertises a packet size
+/// below the baseline.
+pub fn negotiate_packet_size(
+    ours: &Networking,
+    theirs: &Networking,
+) -> Result<usize, Error> {
+    let size = ours.max_packet_size.min(theirs.max_packet_size) as usize;
+    check!(size >= BASELINE_TRANSMISSION_UNIT, Error::UnitTooSmall);
+    Ok(size)
+}
+
+/// An MCTP transport header.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TransportHeader {
+    /// The endpoint ID of the packet's destination.
+    pub dest: u8,
+    /// The endpoint ID of the packet's source.
+    pub src: u8,
+    /// Whether this is the first packet of a message.
+    pub som: bool,
+    /// Whether this is the last packet of a message.
+    pub eom: bool,
+    /// The packet's sequence number, modulo four.
+    pub seq: u8,
+    /// Whether the message tag was allocated by this packet's source.
+    pub tag_owner: bool,
+    /// The message tag, which ties together packets of the same message.
+    pub tag: u8,
+}
+
+impl TransportHeader {
+    /// The length of an encoded header, in bytes.
+    pub const LEN: usize = 4;
+
+    /// Encodes this header into its wire format.
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let flags = (self.som as u8) << 7
+            | (self.eom as u8) << 6
+            | (self.seq & 0b11) << 4
+            | (self.tag_owner as u8) << 3
+            | (self.tag & 0b111);
+        [HEADER_VERSION, self.dest, self.src, flags]
+    }
+}
+
+/// A single MCTP packet, borrowing its payload from the message it was cut
+/// from.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Packet<'a> {
+    /// The packet's transport header.
+    pub header: TransportHeader,
+    /// The packet's payload, which is never longer than the transmission unit
+    /// of the [`Packetizer`] that produced it.
+    pub payload: &'a [u8],
+}
+
+/// An iterator that fragments a message into MCTP [`Packet`]s.
+///
+/// The first packet has its SOM bit set, the last has its EOM bit set, and
+/// sequence numbers count up from zero. An empty message is sent as a single
+/// empty packet.
+pub struct Packetizer<'a> {
+    message: &'a [u8],
+    unit: usize,
+    template: TransportHeader,
+    seq: u8,
+    started: bool,
+    done: bool,
+}
+
+impl<'a> Packetizer<'a> {
+    /// Creates a new `Packetizer` that cuts `message` into packets of at most
+    /// `max_packet_size` payload bytes.
+    ///
+    /// `template` provides the addressing and tag fields of each packet; its
+    /// SOM, EOM, and sequence fields are ignored.
+    ///
+    /// Returns [`Error::UnitTooSmall`] if `max_packet_size` is below
+    /// [`BASELINE_TRANSMISSION_UNIT`].
+    pub fn new(
+        message: &'a [u8],
+        max_packet_size: usize,
+        template: TransportHeader,
+    ) -> Result<Self, Error> {
+        check!(
+            max_packet_size >= BASELINE_TRANSMISSION_UNIT,
+            Error::UnitTooSmall
+        );
+        Ok(Self {
+            message,
+            unit: max_packet_size,
+            template,
+            seq: 0,
+            started: false,
+            done: false,
+        })
+    }
+
+    /// Returns the largest payload any packet from this `Packetizer` will
+    /// carry.
+    pub fn max_packet_size(&self) -> usize {
+        self.unit
+    }
+}
+
+impl<'a> Iterator for Packetizer<'a> {
+    type Item = Packet<'a>;
+
+    fn next(&mut self) -> Option<Packet<'a>> {
+        if self.done {
+            return None;
+        }
+
+        let len = self.message.len().min(self.unit);
+        let (payload, rest) = self.message.split_at(len);
+        let header = TransportHeader {
+            som: !self.started,
+            eom: rest.is_empty(),
+            seq: self.seq,
+            ..self.template
+        };
+
+        self.message = rest;
+        self.seq = (self.seq + 1) & 0b11;
+        self.started = true;
+        self.done = rest.is_empty();
+        Some(Packet { header, payload })
+    }
+}
+
+/// A reassembled MCTP message, split into its parts.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Message<'a> {
+    /// The message type, without the [`integrity::IC_BIT`].
+    pub message_type: u8,
+    /// The message body, which follows the message-type byte.
+    pub body: &'a [u8],
+    /// The integrity-check tag at the end of the message, if it has one.
+    ///
+    /// Checking the tag against `body` is left to the caller, such as with
+    /// [`integrity::verify()`].
+    pub tag: Option<[u8; integrity::TAG_LEN]>,
+}
+
+/// Decodes `message`, a reassembled MCTP message whose body is `body_len`
+/// bytes long.
+///
+/// `body_len` must come from whatever delimits the body independently of the
+/// message-type byte, such as a length field in the protocol header. Any bytes
+/// past the body must form an integrity-check tag, and must be present exactly
+/// when the IC bit is set.
+///
+/// Returns [`Error::IcMismatch`] if the IC bit disagrees with the presence of
+/// a tag, and [`Error::BadLength`] if `message` does not have room for its
+/// body or has trailing bytes that are not a tag.
+pub fn decode(message: &[u8], body_len: usize) -> Result<Message, Error> {
+    let (&ty, rest) = message.split_first().ok_or(Error::BadLength)?;
+    check!(rest.len() >= body_len, Error::BadLength);
+    let (body, trailer) = rest.split_at(body_len);
+
+    let ic = ty & integrity::IC_BIT != 0;
+    let tag = match trailer.len() {
+        0 => None,
+        integrity::TAG_LEN => {
+            let mut tag = [0; integrity::TAG_LEN];
+            tag.copy_from_slice(trailer);
+            Some(tag)
+        }
+        _ => return Err(fail!(Error::BadLength)),
+    };
+    check!(ic == tag.is_some(), Error::IcMismatch);
+
+    Ok(Message {
+        message_type: ty & !integrity::IC_BIT,
+        body,
+        tag,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEMPLATE: TransportHeader = TransportHeader {
+        dest: 0x10,
+        src: 0x20,
+        som: false,
+        eom: false,
+        seq: 0,
+        tag_owner: true,
+        tag: 5,
+    };
+
+    #[test]
+    fn fragments_at_baseline() {
+        let message = (0..200).map(|i| i as u8).collect::<Vec<_>>();
+        let packets =
+            Packetizer::new(&message, BASELINE_TRANSMISSION_UNIT, TEMPLATE)
+                .unwrap()
+                .collect::<Vec<_>>();
+
+        let lens = packets.iter().map(|p| p.payload.len()).collect::<Vec<_>>();
+        assert_eq!(lens, &[64, 64, 64, 8]);
+        let flags = packets
+            .iter()
+            .map(|p| (p.header.som, p.header.eom, p.header.seq))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            flags,
+            &[
+                (true, false, 0),
+                (false, false, 1),
+                (false, false, 2),
+                (false, true, 3)
+            ]
+        );
+        assert_eq!(packets[0].header.to_bytes(), [0x01, 0x10, 0x20, 0x8d]);
+        assert_eq!(packets[3].header.to_bytes(), [0x01, 0x10, 0x20, 0x7d]);
+
+        let joined = packets
+            .iter()
+            .flat_map(|p| p.payload.iter().copied())
+            .collect::<Vec<_>>();
+        assert_eq!(joined, message);
+    }
+
+    #[test]
+    fn sequence_wraps() {
+        let message = [0; 64 * 6];
+        let seqs = Packetizer::new(&message, 64, TEMPLATE)
+            .unwrap()
+            .map(|p| p.header.seq)
+            .collect::<Vec<_>>();
+        assert_eq!(seqs, &[0, 1, 2, 3, 0, 1]);
+    }
+
+    #[test]
+    fn empty_message() {
+        let packets = Packetizer::new(&[], 64, TEMPLATE)
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(packets.len(), 1);
+        assert!(packets[0].header.som && packets[0].header.eom);
+        assert!(packets[0].payload.is_empty());
+    }
+
+    #[test]
+    fn decode_consistent() {
+        let msg = decode(&[0x7e, 0x01, 0x02], 2).unwrap();
+        assert_eq!(msg.message_type, 0x7e);
+        assert_eq!(msg.body, &[0x01, 0x02]);
+        assert_eq!(msg.tag, None);
+
+        let tag = integrity::compute(&[0x01, 0x02]);
+        let packet = [0xfe, 0x01, 0x02, tag[0]];
+        let msg = decode(&packet, 2).unwrap();
+        assert_eq!(msg.message_type, 0x7e);
+        assert_eq!(msg.body, &[0x01, 0x02]);
+        assert_eq!(msg.tag, Some(tag));
+    }
+
+    #[test]
+    fn decode_ic_without_tag() {
+        let err = decode(&[0xfe, 0x01, 0x02], 2).unwrap_err();
+        assert_eq!(err.into_inner(), Error::IcMismatch);
+    }
+
+    #[test]
+    fn decode_tag_without_ic() {
+        let tag = integrity::compute(&[0x01, 0x02]);
+        let err = decode(&[0x7e, 0x01, 0x02, tag[0]], 2).unwrap_err();
+        assert_eq!(err.into_inner(), Error::IcMismatch);
+    }
+
+    #[test]
+    fn decode_bad_length() {
+        for &(message, body_len) in &[
+            (&[][..], 0),
+            (&[0x7e, 0x01][..], 2),
+            (&[0xfe, 0x01, 0x02, 0x03][..], 1),
+        ] {
+            let err = decode(message, body_len).unwrap_err();
+            assert_eq!(err.into_inner(), Error::BadLength);
+        }
+    }
+
+    #[test]
+    fn rejects_small_units() {
+        assert!(Packetizer::new(&[0; 8], 63, TEMPLATE).is_err());
+
+        let net = |max_packet_size| Networking {
+            max_message_size: 1024,
+            max_packet_size,
+            mode: crate::protocol::cerberus::capabilities::RotMode::Platform,
+            roles: enumflags2::BitFlags::empty(),
+            has_compression: false,
+            has_streaming: false,
+        };
+        assert_eq!(negotiate_packet_size(&net(256), &net(128)).unwrap(), 128);
+        assert!(negotiate_packet_size(&net(256), &net(32)).is_err());
+    }
+}
@@ -13,14 +13,22 @@
 //! device over a SPI line, it should tie up all the necessary implementation
 //! details into a [`HostPort`] implementation.
 
-use core::fmt::Debug;
+use core::fmt;
 
 use crate::io;
 use crate::protocol::cerberus;
+use crate::protocol::integrity;
 use crate::protocol::spdm;
+use crate::protocol::wire::WireEnum;
+use crate::Result;
 
+pub mod client;
 pub mod device;
 pub mod host;
+pub mod mctp;
+
+#[cfg(feature = "std")]
+pub mod record;
 
 #[cfg(doc)]
 use host::HostPort;
@@ -40,8 +48,27 @@ pub enum Error {
     /// Indicates that some operation was done out of order, such as attempting
     /// to reference part of the request once a reply has begun.
     OutOfOrder,
+    /// Indicates that a response was finished before it was started, that is,
+    /// that [`HostResponse::finish()`] was called without first calling
+    /// [`HostRequest::reply()`].
+    ///
+    /// [`HostResponse::finish()`]: host::HostResponse::finish
+    /// [`HostRequest::reply()`]: host::HostRequest::reply
+    NoResponseStarted,
     /// The operation timed out.
     Timeout,
+    /// Indicates that the port was shut down, and will not produce any
+    /// further requests.
+    Shutdown,
+    /// Indicates that a message was tagged with a [`MessageType`] other than
+    /// the one for the protocol being spoken, such as a PLDM message arriving
+    /// at a Cerberus port.
+    UnsupportedMessageType,
+    /// Indicates that a message's integrity-check tag did not match its
+    /// contents.
+    ///
+    /// See [`integrity`].
+    BadIntegrity,
 }
 
 impl From<io::Error> for Error {
@@ -52,10 +79,84 @@ impl From<io::Error> for Error {
 
 debug_from!(Error => io::Error);
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error: {:?}", e),
+            Self::BadHeader => write!(f, "bad message header"),
+            Self::Disconnected => write!(f, "connection disconnected"),
+            Self::OutOfOrder => write!(f, "operation out of order"),
+            Self::NoResponseStarted => {
+                write!(f, "response finished before it was started")
+            }
+            Self::Timeout => write!(f, "operation timed out"),
+            Self::Shutdown => write!(f, "port shut down"),
+            Self::UnsupportedMessageType => {
+                write!(f, "unsupported message type")
+            }
+            Self::BadIntegrity => write!(f, "integrity check failed"),
+        }
+    }
+}
+
+wire_enum! {
+    /// An MCTP message type.
+    ///
+    /// Transports that multiplex several protocols over one endpoint, like
+    /// MCTP, prefix each message with one of these bytes to tell them apart.
+    /// A [`HostPort`] for such a transport should check this byte against
+    /// [`Header::MESSAGE_TYPE`] before parsing the rest of the header, such as
+    /// with [`check_message_type()`].
+    ///
+    /// Only the message types relevant to Manticore are listed here.
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum MessageType: u8 {
+        /// MCTP control messages.
+        Control = 0x00,
+        /// Platform Level Data Model messages.
+        Pldm = 0x01,
+        /// SPDM messages.
+        Spdm = 0x05,
+        /// PCI vendor-defined messages, which is what Cerberus messages are
+        /// sent as.
+        VendorDefinedPci = 0x7e,
+    }
+}
+
+/// Checks that `byte`, a message-type byte read off of the wire, matches the
+/// [`MessageType`] of the protocol spoken by `H`.
+///
+/// The [`integrity::IC_BIT`] is not part of the message type; this function
+/// returns whether it was set, in which case the message ends in an
+/// integrity-check tag.
+///
+/// Returns [`Error::UnsupportedMessageType`] if it does not, including if
+/// `byte` is not a known message type at all.
+pub fn check_message_type<H: Header>(byte: u8) -> Result<bool, Error> {
+    let ty = MessageType::from_wire_value(byte & !integrity::IC_BIT);
+    if ty != Some(H::MESSAGE_TYPE) {
+        return Err(fail!(
+            Error::UnsupportedMessageType,
+            "expected message type {:?}, got {:#04x} ({:?})",
+            H::MESSAGE_TYPE,
+            byte,
+            ty,
+        ));
+    }
+    Ok(byte & integrity::IC_BIT != 0)
+}
+
 /// A header type, which represents a protocol over the wire.
-pub trait Header: Copy + Debug {
+pub trait Header: Copy + fmt::Debug {
+    /// The [`MessageType`] that messages with this header are sent as, on
+    /// transports that distinguish between protocols.
+    const MESSAGE_TYPE: MessageType;
+
     /// The command type enum associated with this header.
-    type CommandType: Copy + Debug + Eq;
+    ///
+    /// This must be `'static`, so that timers for it may be borrowed for
+    /// shorter lifetimes than a request.
+    type CommandType: Copy + fmt::Debug + Eq + 'static;
 
     /// Returns the [`Self::CommandType`] contained within `self`.
     fn command(&self) -> Self::CommandType;
@@ -85,6 +186,7 @@ pub struct CerberusHeader {
 }
 
 impl Header for CerberusHeader {
+    const MESSAGE_TYPE: MessageType = MessageType::VendorDefinedPci;
     type CommandType = cerberus::CommandType;
 
     fn command(&self) -> cerberus::CommandType {
@@ -124,6 +226,7 @@ pub struct SpdmHeader {
 }
 
 impl Header for SpdmHeader {
+    const MESSAGE_TYPE: MessageType = MessageType::Spdm;
     type CommandType = spdm::CommandType;
 
     fn command(&self) -> spdm::CommandType {
@@ -0,0 +1,408 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Transcripts of host traffic, for golden testing.
+//!
+//! [`RecordingHostPort`] sits between a server and a [`HostPort`], recording
+//! the exact bytes of every request and reply into a [`Transcript`]. A test
+//! can then compare the transcript of a sequence of commands against a golden
+//! one, catching changes in behavior that per-command tests would miss.
+
+use std::fmt;
+
+use crate::io;
+use crate::io::write::StdWrite;
+use crate::io::Read;
+use crate::io::ReadZero;
+use crate::io::Write;
+use crate::net;
+use crate::net::host::HostPort;
+use crate::net::host::HostRequest;
+use crate::net::host::HostResponse;
+use crate::Result;
+
+/// A single request, along with the reply to it, in a [`Transcript`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Exchange<H> {
+    /// The header of the request.
+    pub request: H,
+    /// The full payload of the request.
+    pub request_payload: Vec<u8>,
+    /// The header of the reply, or `None` if the request was never replied
+    /// to.
+    pub reply: Option<H>,
+    /// The payload of the reply.
+    pub reply_payload: Vec<u8>,
+}
+
+/// A record of the traffic that passed through a [`RecordingHostPort`].
+///
+/// A transcript serializes, with [`fmt::Display`], to a line-oriented text
+/// format suitable for embedding in a test as a golden value. Each request is
+/// a line starting with `>`, and each reply a line starting with `<`, followed
+/// by the header's `Debug` representation and the payload in hex:
+/// ```text
+/// > CerberusHeader { command: FirmwareVersion } 00
+/// < CerberusHeader { command: FirmwareVersion } 6d637470
+/// ```
+/// A request that was never replied to is followed by `< (no reply)`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Transcript<H> {
+    exchanges: Vec<Exchange<H>>,
+}
+
+impl<H> Default for Transcript<H> {
+    fn default() -> Self {
+        Self {
+            exchanges: Vec::new(),
+        }
+    }
+}
+
+impl<H> Transcript<H> {
+    /// Creates a new, empty `Transcript`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the exchanges recorded so far, in order.
+    pub fn exchanges(&self) -> &[Exchange<H>] {
+        &self.exchanges
+    }
+
+    /// Discards all recorded exchanges.
+    pub fn clear(&mut self) {
+        self.exchanges.clear()
+    }
+}
+
+impl<H: fmt::Debug> Transcript<H> {
+    /// Compares this transcript against `golden`, a transcript in the text
+    /// format described above.
+    ///
+    /// Leading and trailing whitespace on each line, and blank lines, are
+    /// ignored, so that `golden` may be an indented string literal. Returns a
+    /// description of the first line that differs, or `None` if there is no
+    /// such line.
+    pub fn diff(&self, golden: &str) -> Option<String> {
+        fn lines(s: &str) -> Vec<&str> {
+            s.lines().map(str::trim).filter(|l| !l.is_empty()).collect()
+        }
+        let actual = self.to_string();
+        let (actual, golden) = (lines(&actual), lines(golden));
+
+        let len = actual.len().max(golden.len());
+        (0..len).find_map(|i| {
+            let (a, g) = (actual.get(i), golden.get(i));
+            if a == g {
+                return None;
+            }
+            Some(format!(
+                "line {}: expected {}, got {}",
+                i + 1,
+                g.map_or("end of transcript".into(), |l| format!("`{}`", l)),
+                a.map_or("end of transcript".into(), |l| format!("`{}`", l)),
+            ))
+        })
+    }
+}
+
+impl<H: fmt::Debug> fmt::Display for Transcript<H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn line(
+            f: &mut fmt::Formatter,
+            dir: char,
+            header: &dyn fmt::Debug,
+            payload: &[u8],
+        ) -> fmt::Result {
+            write!(f, "{} {:?}", dir, header)?;
+            if !payload.is_empty() {
+                write!(f, " ")?;
+                for b in payload {
+                    write!(f, "{:02x}", b)?;
+                }
+            }
+            writeln!(f)
+        }
+
+        for ex in &self.exchanges {
+            line(f, '>', &ex.request, &ex.request_payload)?;
+            match &ex.reply {
+                Some(reply) => line(f, '<', reply, &ex.reply_payload)?,
+                None => writeln!(f, "< (no reply)")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A [`HostPort`] that records a single exchange with another port into a
+/// [`Transcript`].
+///
+/// A `RecordingHostPort` borrows the port it wraps, and only serves one
+/// request: further calls to `receive()` return
+/// [`net::Error::Disconnected`]. Servers only receive one request at a time,
+/// so a new `RecordingHostPort` should be created for each one:
+/// ```
+/// # use manticore::io::Write as _;
+/// # use manticore::net::{self, host::*, record::*};
+/// # use manticore::protocol::cerberus::CommandType;
+/// let mut buf = [0; 64];
+/// let mut host = InMemHost::new(&mut buf);
+/// let mut transcript = Transcript::new();
+///
+/// let header = net::CerberusHeader {
+///     command: CommandType::FirmwareVersion,
+/// };
+/// host.request(header, &[0]);
+/// {
+///     let mut port = RecordingHostPort::new(&mut host, &mut transcript);
+///     // Hand `port` off to a server, like any other `HostPort`.
+///     let req = port.receive()?;
+///     let header = req.header()?;
+///     let reply = req.reply(header)?;
+///     reply.sink()?.write_all(b"mctp")?;
+///     reply.finish()?;
+/// }
+///
+/// assert_eq!(transcript.diff("
+///     > CerberusHeader { command: FirmwareVersion } 00
+///     < CerberusHeader { command: FirmwareVersion } 6d637470
+/// "), None);
+/// # Ok::<(), manticore::Error<manticore::net::Error>>(())
+/// ```
+///
+/// To capture the request exactly, its entire payload is read from the
+/// wrapped port upon `receive()`; likewise, the reply is buffered, and only
+/// handed to the wrapped port upon `finish()`.
+pub struct RecordingHostPort<'p, 'req, H>(Inner<'p, 'req, H>);
+
+/// The actual guts of a `RecordingHostPort`; see [`net::host::InMemHost`]
+/// for why this is a separate type.
+struct Inner<'p, 'req, H> {
+    port: Option<&'p mut dyn HostPort<'req, H>>,
+    request: Option<&'p mut dyn HostRequest<'req, H>>,
+    transcript: &'p mut Transcript<H>,
+    // The header of the request being recorded, until it is replied to.
+    header: Option<H>,
+    rx: Buffered,
+    reply: Option<H>,
+    tx: StdWrite<Vec<u8>>,
+}
+
+impl<'p, 'req, H> RecordingHostPort<'p, 'req, H> {
+    /// Creates a new `RecordingHostPort` that serves a request from `port`,
+    /// recording it into `transcript`.
+    pub fn new(
+        port: &'p mut dyn HostPort<'req, H>,
+        transcript: &'p mut Transcript<H>,
+    ) -> Self {
+        Self(Inner {
+            port: Some(port),
+            request: None,
+            transcript,
+            header: None,
+            rx: Buffered::default(),
+            reply: None,
+            tx: StdWrite(Vec::new()),
+        })
+    }
+}
+
+impl<'p, 'req, H: Copy> HostPort<'req, H> for RecordingHostPort<'p, 'req, H> {
+    fn receive(&mut self) -> Result<&mut dyn HostRequest<'req, H>, net::Error> {
+        let inner = &mut self.0;
+        let port = inner
+            .port
+            .take()
+            .ok_or_else(|| fail!(net::Error::Disconnected))?;
+
+        let request = port.receive()?;
+        let header = request.header()?;
+        let r = request.payload()?;
+        let mut bytes = vec![0; r.remaining_data()];
+        r.read_bytes(&mut bytes)?;
+
+        inner.request = Some(request);
+        inner.header = Some(header);
+        inner.rx = Buffered { bytes, pos: 0 };
+        Ok(inner)
+    }
+}
+
+impl<'p, 'req, H: Copy> HostRequest<'req, H> for Inner<'p, 'req, H> {
+    fn header(&self) -> Result<H, net::Error> {
+        check!(self.reply.is_none(), net::Error::OutOfOrder);
+        self.header.ok_or_else(|| fail!(net::Error::Disconnected))
+    }
+
+    fn payload(&mut self) -> Result<&mut dyn ReadZero<'req>, net::Error> {
+        check!(self.header.is_some(), net::Error::Disconnected);
+        check!(self.reply.is_none(), net::Error::OutOfOrder);
+        Ok(&mut self.rx)
+    }
+
+    fn payload_len(&self) -> Option<usize> {
+        self.header.as_ref()?;
+        if self.reply.is_some() {
+            return None;
+        }
+        Some(self.rx.remaining_data())
+    }
+
+    fn reply_capacity(&self) -> Option<usize> {
+        self.request.as_ref()?.reply_capacity()
+    }
+
+    fn reply(
+        &mut self,
+        header: H,
+    ) -> Result<&mut dyn HostResponse<'req>, net::Error> {
+        check!(self.header.is_some(), net::Error::Disconnected);
+        check!(self.reply.is_none(), net::Error::OutOfOrder);
+        self.reply = Some(header);
+        Ok(self)
+    }
+}
+
+impl<'p, 'req, H: Copy> HostResponse<'req> for Inner<'p, 'req, H> {
+    fn sink(&mut self) -> Result<&mut dyn Write, net::Error> {
+        check!(self.header.is_some(), net::Error::Disconnected);
+        check!(self.reply.is_some(), net::Error::OutOfOrder);
+        Ok(&mut self.tx)
+    }
+
+    fn finish(&mut self) -> Result<(), net::Error> {
+        let (header, reply) = match (self.header, self.reply) {
+            (Some(header), Some(reply)) => (header, reply),
+            (Some(_), None) => {
+                return Err(fail!(net::Error::NoResponseStarted))
+            }
+            _ => return Err(fail!(net::Error::OutOfOrder)),
+        };
+        self.header = None;
+        let payload = core::mem::take(&mut self.tx.0);
+        self.transcript.exchanges.push(Exchange {
+            request: header,
+            request_payload: core::mem::take(&mut self.rx.bytes),
+            reply: Some(reply),
+            reply_payload: payload.clone(),
+        });
+
+        let request = self
+            .request
+            .take()
+            .ok_or_else(|| fail!(net::Error::Disconnected))?;
+        let resp = request.reply(reply)?;
+        resp.sink()?.write_all(&payload)?;
+        resp.finish()
+    }
+}
+
+impl<H> Drop for Inner<'_, '_, H> {
+    fn drop(&mut self) {
+        // Record requests that were dropped on the ground, too, since whether
+        // a server replies at all is part of its behavior.
+        if let Some(header) = self.header.take() {
+            self.transcript.exchanges.push(Exchange {
+                request: header,
+                request_payload: core::mem::take(&mut self.rx.bytes),
+                reply: None,
+                reply_payload: Vec::new(),
+            });
+        }
+    }
+}
+
+/// A request payload, buffered in its entirety.
+#[derive(Default)]
+struct Buffered {
+    bytes: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for Buffered {
+    fn read_bytes(&mut self, out: &mut [u8]) -> Result<(), io::Error> {
+        check!(
+            self.remaining_data() >= out.len(),
+            io::Error::BufferExhausted
+        );
+        out.copy_from_slice(&self.bytes[self.pos..self.pos + out.len()]);
+        self.pos += out.len();
+        Ok(())
+    }
+
+    fn remaining_data(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+}
+#[allow(unsafe_code)]
+unsafe impl ReadZero<'_> for Buffered {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::net::host::HostRequestExt as _;
+    use crate::net::host::InMemHost;
+    use crate::protocol::cerberus;
+    use crate::protocol::cerberus::CommandType;
+
+    fn header(command: CommandType) -> net::CerberusHeader {
+        net::CerberusHeader { command }
+    }
+
+    #[test]
+    fn records_session() {
+        let mut buf = [0; 64];
+        let mut host = InMemHost::new(&mut buf);
+        let mut transcript = Transcript::new();
+
+        host.request(header(CommandType::FirmwareVersion), &[0]);
+        {
+            let mut port = RecordingHostPort::new(&mut host, &mut transcript);
+            let req = port.receive().unwrap();
+            // Only part of the payload is read, but all of it is recorded.
+            assert_eq!(req.payload_len(), Some(1));
+            let reply =
+                req.reply(header(CommandType::FirmwareVersion)).unwrap();
+            reply.sink().unwrap().write_all(&[0xab, 0xcd]).unwrap();
+            reply.finish().unwrap();
+
+            // A recorder only serves one request.
+            assert!(port.receive().is_err());
+        }
+        let (h, resp) = host.response().unwrap();
+        assert_eq!(h.command, CommandType::FirmwareVersion);
+        assert_eq!(resp, &[0xab, 0xcd]);
+
+        host.request(header(CommandType::DeviceId), &[]);
+        RecordingHostPort::new(&mut host, &mut transcript)
+            .receive()
+            .unwrap()
+            .reply_error(cerberus::Error::Busy)
+            .unwrap();
+
+        host.request(header(CommandType::DeviceInfo), &[7]);
+        RecordingHostPort::new(&mut host, &mut transcript)
+            .receive()
+            .unwrap();
+
+        assert_eq!(transcript.exchanges().len(), 3);
+        let golden = "
+            > CerberusHeader { command: FirmwareVersion } 00
+            < CerberusHeader { command: FirmwareVersion } abcd
+            > CerberusHeader { command: DeviceId }
+            < CerberusHeader { command: Error } 0300000000
+            > CerberusHeader { command: DeviceInfo } 07
+            < (no reply)
+        ";
+        assert_eq!(transcript.diff(golden), None);
+        assert_eq!(
+            transcript.diff(&golden.replace("abcd", "abce")).unwrap(),
+            "line 2: expected `< CerberusHeader { command: FirmwareVersion } \
+             abce`, got `< CerberusHeader { command: FirmwareVersion } abcd`"
+        );
+        assert!(transcript.diff("").unwrap().contains("expected end"));
+    }
+}
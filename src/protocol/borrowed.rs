@@ -32,6 +32,8 @@ pub(crate) mod sealed {
 #[cfg(feature = "std")]
 mod cfg_gated {
     use super::sealed::Sealed;
+    use crate::protocol::wire::Be;
+    use crate::protocol::wire::Le;
 
     /// A type which can be built by borrowing from another, `'static` type.
     ///
@@ -91,6 +93,33 @@ mod cfg_gated {
         }
     }
 
+    impl<I: 'static + Clone> Sealed for Le<I> {}
+    impl<'a, I: 'static + Clone> Borrowed<'a> for Le<I> {
+        type Static = Self;
+        #[inline]
+        fn borrow(x: &'a Self) -> Self {
+            x.clone()
+        }
+    }
+
+    impl<I: 'static + Clone> Sealed for Be<I> {}
+    impl<'a, I: 'static + Clone> Borrowed<'a> for Be<I> {
+        type Static = Self;
+        #[inline]
+        fn borrow(x: &'a Self) -> Self {
+            x.clone()
+        }
+    }
+
+    impl<'a, T: Borrowed<'a>> Sealed for Option<T> {}
+    impl<'a, T: Borrowed<'a>> Borrowed<'a> for Option<T> {
+        type Static = Option<T::Static>;
+        #[inline]
+        fn borrow(x: &'a Self::Static) -> Self {
+            x.as_ref().map(T::borrow)
+        }
+    }
+
     impl<'a, T: Borrowed<'a>, U: Borrowed<'a>> Sealed for (T, U) {}
     impl<'a, T: Borrowed<'a>, U: Borrowed<'a>> Borrowed<'a> for (T, U) {
         type Static = (T::Static, U::Static);
@@ -319,4 +348,5 @@ macro_rules! derive_borrowed {
 derive_borrowed! {
     u8, u16, u32, u64, u128, usize,
     i8, i16, i32, i64, i128, isize,
+    bool,
 }
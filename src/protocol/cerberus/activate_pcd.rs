@@ -0,0 +1,55 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `ActivatePcd` request and response.
+//!
+//! This module provides a Cerberus command for replacing a device's active
+//! Platform Configuration Data manifest with the one most recently sent with
+//! [`UpdatePcd`](super::UpdatePcd), once its signature has been verified.
+
+use crate::protocol::cerberus::CommandType;
+
+protocol_struct! {
+    /// A command for activating a PCD.
+    type ActivatePcd;
+    const TYPE: CommandType = ActivatePcd;
+
+    struct Request {}
+
+    fn Request::from_wire(_, _) {
+        Ok(Self {})
+    }
+
+    fn Request::to_wire(&self, _w) {
+        Ok(())
+    }
+
+    struct Response {}
+
+    fn Response::from_wire(_, _) {
+        Ok(Self {})
+    }
+
+    fn Response::to_wire(&self, _w) {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[],
+            json: "{}",
+            value: ActivatePcdRequest {},
+        },
+        response_round_trip: {
+            bytes: &[],
+            json: "{}",
+            value: ActivatePcdResponse {},
+        },
+    }
+}
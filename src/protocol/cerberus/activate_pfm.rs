@@ -0,0 +1,63 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `ActivatePfm` request and response.
+//!
+//! This module provides a Cerberus command for replacing a device's active
+//! Platform Firmware Manifest with the one most recently sent with
+//! [`UpdatePfm`](super::UpdatePfm), once its signature has been verified.
+
+use crate::io::ReadInt as _;
+use crate::protocol::cerberus::CommandType;
+
+protocol_struct! {
+    /// A command for activating a PFM.
+    type ActivatePfm;
+    const TYPE: CommandType = ActivatePfm;
+
+    struct Request {
+        /// The port whose PFM is being replaced.
+        pub port_id: u8,
+    }
+
+    fn Request::from_wire(r, _) {
+        let port_id = r.read_le::<u8>()?;
+        Ok(Self { port_id })
+    }
+
+    fn Request::to_wire(&self, w) {
+        w.write_le(self.port_id)?;
+        Ok(())
+    }
+
+    struct Response {}
+
+    fn Response::from_wire(_, _) {
+        Ok(Self {})
+    }
+
+    fn Response::to_wire(&self, _w) {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x02],
+            json: r#"{
+                "port_id": 2
+            }"#,
+            value: ActivatePfmRequest { port_id: 2 },
+        },
+        response_round_trip: {
+            bytes: &[],
+            json: "{}",
+            value: ActivatePfmResponse {},
+        },
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `BeginVerify` request and response.
+//!
+//! This module provides a Cerberus command for starting a resumable
+//! verification of a firmware image. The image is too large to send in one
+//! message, and hashing all of it could take longer than a device may spend
+//! on any single request, so verification is spread out over several:
+//! 1. `BeginVerify` opens a verification, returning a handle for it.
+//! 2. [`VerifyChunk`](super::VerifyChunk) streams the image through the
+//!    device's hasher, one chunk at a time.
+//! 3. [`FinishVerify`](super::FinishVerify) closes the verification,
+//!    returning the verdict.
+//!
+//! Note that this command is a Manticore extension.
+
+use crate::io::read::ReadZeroExt as _;
+use crate::io::ReadInt as _;
+use crate::protocol::cerberus::CommandType;
+
+protocol_struct! {
+    /// A command for starting a firmware verification.
+    type BeginVerify;
+    const TYPE: CommandType = BeginVerify;
+
+    struct Request<'wire> {
+        /// The total length of the image that will follow, in bytes.
+        pub image_len: u32,
+        /// The SHA-256 digest the image is expected to have.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub expected: &'wire [u8; 32],
+    }
+
+    fn Request::from_wire(r, arena) {
+        let image_len = r.read_le::<u32>()?;
+        let expected = r.read_object::<[u8; 32]>(arena)?;
+        Ok(Self { image_len, expected })
+    }
+
+    fn Request::to_wire(&self, w) {
+        w.write_le(self.image_len)?;
+        w.write_all(self.expected)?;
+        Ok(())
+    }
+
+    struct Response {
+        /// A handle for the new verification, to be passed to subsequent
+        /// `VerifyChunk` and `FinishVerify` requests.
+        pub handle: u32,
+    }
+
+    fn Response::from_wire(r, _) {
+        let handle = r.read_le::<u32>()?;
+        Ok(Self { handle })
+    }
+
+    fn Response::to_wire(&self, w) {
+        w.write_le(self.handle)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[
+                0x00, 0x00, 0x01, 0x00,
+
+                0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+                0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+                0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+                0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+            ],
+            json: r#"{
+                "image_len": 65536,
+                "expected": "1111111111111111111111111111111111111111111111111111111111111111"
+            }"#,
+            value: BeginVerifyRequest {
+                image_len: 0x10000,
+                expected: &[0x11; 32],
+            },
+        },
+        response_round_trip: {
+            bytes: &[0x2a, 0x00, 0x00, 0x00],
+            json: r#"{
+                "handle": 42
+            }"#,
+            value: BeginVerifyResponse { handle: 42 },
+        },
+    }
+}
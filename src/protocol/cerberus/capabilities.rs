@@ -13,6 +13,7 @@ use core::time::Duration;
 use enumflags2::bitflags;
 use enumflags2::BitFlags;
 
+use crate::crypto::hash;
 use crate::io::bit_buf::BitBuf;
 use crate::io::ReadInt as _;
 use crate::io::ReadZero;
@@ -134,6 +135,29 @@ pub enum Security {
     Confidentiality = 0b100,
 }
 
+impl Security {
+    /// Returns the security capabilities a device has, given that it supports
+    /// exactly the commands for which `handles` returns true.
+    ///
+    /// [`Security::Confidentiality`] is never included, since it depends on
+    /// AES support rather than on any particular command.
+    pub fn for_commands(
+        handles: impl Fn(CommandType) -> bool,
+    ) -> BitFlags<Self> {
+        let mut security = BitFlags::empty();
+        if handles(CommandType::GetDigests)
+            && handles(CommandType::GetCert)
+            && handles(CommandType::Challenge)
+        {
+            security |= Self::Authentication;
+        }
+        if handles(CommandType::KeyExchange) {
+            security |= Self::HashAndKdf;
+        }
+        security
+    }
+}
+
 /// Represents a supported elliptic curve cryptography key strength.
 #[bitflags]
 #[repr(u8)]
@@ -172,6 +196,192 @@ pub enum AesKeyStrength {
     Bits256 = 0b010,
 }
 
+/// A set of supported hash algorithms.
+///
+/// This is a `manticore` extension, which occupies the five bits of the sixth
+/// capabilities byte that Cerberus marks as reserved. Bits that do not name a
+/// [`hash::Algo`] are preserved as-is, so that the capabilities of a peer that
+/// knows of more algorithms round-trip unchanged.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "u8", into = "u8")
+)]
+pub struct HashAlgos(u8);
+
+impl HashAlgos {
+    /// The number of bits a `HashAlgos` occupies on the wire.
+    pub const BITS: usize = 5;
+
+    /// Returns the empty set.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Creates a set from its wire representation, including any bits that do
+    /// not name a known algorithm.
+    ///
+    /// Returns `None` if `bits` does not fit in [`HashAlgos::BITS`] bits.
+    pub const fn from_bits(bits: u8) -> Option<Self> {
+        if bits >> Self::BITS != 0 {
+            return None;
+        }
+        Some(Self(bits))
+    }
+
+    /// Returns this set's wire representation.
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Returns the set of algorithms that `engine` supports.
+    pub fn supported_by(engine: &mut (impl hash::Engine + ?Sized)) -> Self {
+        let mut set = Self::empty();
+        for &algo in Self::KNOWN {
+            if engine.supports(algo) {
+                set.insert(algo);
+            }
+        }
+        set
+    }
+
+    /// Returns whether `algo` is in this set.
+    pub fn contains(self, algo: hash::Algo) -> bool {
+        self.0 & Self::bit(algo) != 0
+    }
+
+    /// Adds `algo` to this set.
+    pub fn insert(&mut self, algo: hash::Algo) {
+        self.0 |= Self::bit(algo);
+    }
+
+    /// Returns the algorithms in both `self` and `other`.
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// Returns an iterator over the known algorithms in this set.
+    pub fn iter(self) -> impl Iterator<Item = hash::Algo> {
+        Self::KNOWN
+            .iter()
+            .copied()
+            .filter(move |&a| self.contains(a))
+    }
+
+    /// Every algorithm with an assigned bit.
+    const KNOWN: &'static [hash::Algo] =
+        &[hash::Algo::Sha256, hash::Algo::Sha384, hash::Algo::Sha512];
+
+    fn bit(algo: hash::Algo) -> u8 {
+        match algo {
+            hash::Algo::Sha256 => 0b001,
+            hash::Algo::Sha384 => 0b010,
+            hash::Algo::Sha512 => 0b100,
+        }
+    }
+}
+
+impl From<hash::Algo> for HashAlgos {
+    fn from(algo: hash::Algo) -> Self {
+        Self(Self::bit(algo))
+    }
+}
+
+/// An error returned when converting a `u8` with bits beyond
+/// [`HashAlgos::BITS`] into a [`HashAlgos`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct InvalidHashAlgos(pub u8);
+
+impl core::fmt::Display for InvalidHashAlgos {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "hash algorithm set {:#b} is too wide", self.0)
+    }
+}
+
+impl core::convert::TryFrom<u8> for HashAlgos {
+    type Error = InvalidHashAlgos;
+    fn try_from(bits: u8) -> core::result::Result<Self, InvalidHashAlgos> {
+        Self::from_bits(bits).ok_or(InvalidHashAlgos(bits))
+    }
+}
+
+impl From<HashAlgos> for u8 {
+    fn from(set: HashAlgos) -> u8 {
+        set.0
+    }
+}
+
+/// The revision of the Cerberus protocol that a device speaks.
+///
+/// This is a `manticore` extension, which occupies three of the reserved bits
+/// of the eighth capabilities byte. A peer that does not know about protocol
+/// versions leaves those bits zero, and so advertises
+/// [`ProtocolVersion::CURRENT`]: the message layouts that `manticore`
+/// implements. Later revisions, should the layout of any message change, are
+/// numbered upwards from there.
+///
+/// A device only proceeds with a peer whose version it accepts, rather than
+/// risk misparsing messages laid out differently than it expects.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "u8", into = "u8")
+)]
+pub struct ProtocolVersion(u8);
+
+impl ProtocolVersion {
+    /// The number of bits a `ProtocolVersion` occupies on the wire.
+    pub const BITS: usize = 3;
+
+    /// The version that `manticore` speaks, which is also the one spoken by
+    /// peers that predate version negotiation.
+    pub const CURRENT: Self = Self(0);
+
+    /// Creates a version from its wire representation.
+    ///
+    /// Returns `None` if `version` does not fit in [`ProtocolVersion::BITS`]
+    /// bits.
+    pub const fn new(version: u8) -> Option<Self> {
+        if version >> Self::BITS != 0 {
+            return None;
+        }
+        Some(Self(version))
+    }
+
+    /// Returns this version's wire representation.
+    pub const fn get(self) -> u8 {
+        self.0
+    }
+}
+
+/// An error returned when converting a `u8` with bits beyond
+/// [`ProtocolVersion::BITS`] into a [`ProtocolVersion`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct InvalidProtocolVersion(pub u8);
+
+impl core::fmt::Display for InvalidProtocolVersion {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "protocol version {} is out of range", self.0)
+    }
+}
+
+impl core::convert::TryFrom<u8> for ProtocolVersion {
+    type Error = InvalidProtocolVersion;
+    fn try_from(
+        version: u8,
+    ) -> core::result::Result<Self, InvalidProtocolVersion> {
+        Self::new(version).ok_or(InvalidProtocolVersion(version))
+    }
+}
+
+impl From<ProtocolVersion> for u8 {
+    fn from(version: ProtocolVersion) -> u8 {
+        version.0
+    }
+}
+
 /// Network-related capabilities for a device.
 ///
 /// A value of this type needs to be provided to `manticore` by an integration,
@@ -192,8 +402,9 @@ pub struct Networking {
     /// be packetized). It is typically derived from the size of underlying
     /// buffers in, say, a SPI or I2C hardware IP.
     ///
-    /// (Note: the packetization strategy is not provided by `manticore`
-    /// itself.)
+    /// For MCTP transports, see [`net::mctp::negotiate_packet_size()`].
+    ///
+    /// [`net::mctp::negotiate_packet_size()`]: ../../../net/mctp/fn.negotiate_packet_size.html
     pub max_packet_size: u16,
 
     /// The type of RoT this device is.
@@ -201,6 +412,27 @@ pub struct Networking {
     /// Valid "bus roles" of this device: is a host, a target, or both?
     #[cfg_attr(feature = "serde", serde(with = "crate::serde::bitflags"))]
     pub roles: BitFlags<BusRole>,
+
+    /// Whether this device can exchange run-length compressed payloads.
+    ///
+    /// This is a `manticore` extension, which occupies a bit that Cerberus
+    /// marks as reserved. A transport should only compress messages once both
+    /// sides of a session have advertised this capability; see the
+    /// [`io::rle`] module.
+    ///
+    /// [`io::rle`]: ../../../io/rle/index.html
+    pub has_compression: bool,
+
+    /// Whether this device can send responses without a length prefix, when
+    /// their length is implied by their type.
+    ///
+    /// This is a `manticore` extension, which occupies a bit that Cerberus
+    /// marks as reserved. It is only meaningful for transports whose framing
+    /// carries a length prefix, which a device must otherwise compute by
+    /// buffering the whole response before sending any of it. A transport
+    /// should only omit length prefixes once both sides of a session have
+    /// advertised this capability.
+    pub has_streaming: bool,
 }
 
 /// Cryptographic device capabilities.
@@ -225,6 +457,9 @@ pub struct Crypto {
     /// AES key strengths supported by this device.
     #[cfg_attr(feature = "serde", serde(with = "crate::serde::bitflags"))]
     pub aes_strength: BitFlags<AesKeyStrength>,
+
+    /// Hash algorithms supported by this device.
+    pub hash_algos: HashAlgos,
 }
 
 /// A description of device capabilities.
@@ -259,6 +494,12 @@ pub struct Capabilities {
 
     /// Cryptographic capabilities supported by this device.
     pub crypto: Crypto,
+
+    /// The protocol version this device speaks.
+    ///
+    /// In a response, this is the version that the device agreed to speak
+    /// with the requester.
+    pub protocol_version: ProtocolVersion,
 }
 
 /// Constants relevant to parsing `Capabilities`.
@@ -281,35 +522,40 @@ impl<'wire> FromWire<'wire> for Capabilities {
         let max_message_size = r.read_le::<u16>()?;
         let max_packet_size = r.read_le::<u16>()?;
 
-        // The fifth byte contains the security capabilities, a reserved bit,
-        // the bus role, and the RoT mode, in that order.
+        // The fifth byte contains the security capabilities, a reserved bit
+        // (used by manticore for the compression bit), the bus role, and the
+        // RoT mode, in that order.
         let mut byte_five = BitBuf::from_bits(r.read_le::<u8>()?);
         let mode_bits = byte_five.read_bits(MODE_SIZE)?;
         let bus_bits = byte_five.read_bits(BUS_SIZE)?;
-        let _ = byte_five.read_bits(1)?;
+        let has_compression = byte_five.read_bit()?;
         let security_bits = byte_five.read_bits(SEC_SIZE)?;
 
         let mode = RotMode::from_wire_value(mode_bits)
             .ok_or(wire::Error::OutOfRange)?;
         let roles = BitFlags::<BusRole>::from_bits(bus_bits)
             .map_err(|_| wire::Error::OutOfRange)?;
-        let networking = Networking {
+        let mut networking = Networking {
             max_message_size,
             max_packet_size,
             mode,
             roles,
+            has_compression,
+            // This bit lives in the eighth byte; see below.
+            has_streaming: false,
         };
 
         let security = BitFlags::<Security>::from_bits(security_bits)
             .map_err(|_| wire::Error::OutOfRange)?;
 
-        // The sixth byte consists of five reserved bits, and the PFM, policy,
-        // and firmware protection bits.
+        // The sixth byte consists of five reserved bits (used by manticore for
+        // the hash algorithms), and the PFM, policy, and firmware protection
+        // bits.
         let mut byte_six = BitBuf::from_bits(r.read_le::<u8>()?);
         let has_pfm_support = byte_six.read_bit()?;
         let has_policy_support = byte_six.read_bit()?;
         let has_firmware_protection = byte_six.read_bit()?;
-        let _ = byte_six.read_bits(5)?;
+        let hash_algos = HashAlgos(byte_six.read_bits(HashAlgos::BITS)?);
 
         // The seventh byte consists of the rsa strength, the ecc strength, and
         // the ecdsa and rsa bits.
@@ -324,11 +570,14 @@ impl<'wire> FromWire<'wire> for Capabilities {
         let ecc_strength = BitFlags::<EccKeyStrength>::from_bits(ecc_bits)
             .map_err(|_| wire::Error::OutOfRange)?;
 
-        // The eighth byte consists of the aes strength, four reserved bits,
+        // The eighth byte consists of the aes strength, four reserved bits
+        // (used by manticore for the streaming bit and the protocol version),
         // and the ecc bit.
         let mut byte_eight = BitBuf::from_bits(r.read_le::<u8>()?);
         let has_ecc = byte_eight.read_bit()?;
-        let _ = byte_eight.read_bits(4)?;
+        networking.has_streaming = byte_eight.read_bit()?;
+        let protocol_version =
+            ProtocolVersion(byte_eight.read_bits(ProtocolVersion::BITS)?);
         let aes_bits = byte_eight.read_bits(AES_SIZE)?;
 
         let aes_strength = BitFlags::<AesKeyStrength>::from_bits(aes_bits)
@@ -351,7 +600,10 @@ impl<'wire> FromWire<'wire> for Capabilities {
                 ecc_strength,
                 rsa_strength,
                 aes_strength,
+                hash_algos,
             },
+
+            protocol_version,
         })
     }
 }
@@ -367,7 +619,7 @@ impl ToWire for Capabilities {
         fifth_byte
             .write_bits(MODE_SIZE, self.networking.mode.to_wire_value())?;
         fifth_byte.write_bits(BUS_SIZE, self.networking.roles.bits())?;
-        fifth_byte.write_zero_bits(1)?;
+        fifth_byte.write_bit(self.networking.has_compression)?;
         fifth_byte.write_bits(SEC_SIZE, self.security.bits())?;
         w.write_le(fifth_byte.bits())?;
 
@@ -375,7 +627,8 @@ impl ToWire for Capabilities {
         sixth_byte.write_bit(self.has_pfm_support)?;
         sixth_byte.write_bit(self.has_policy_support)?;
         sixth_byte.write_bit(self.has_firmware_protection)?;
-        sixth_byte.write_zero_bits(5)?;
+        sixth_byte
+            .write_bits(HashAlgos::BITS, self.crypto.hash_algos.bits())?;
         w.write_le(sixth_byte.bits())?;
 
         let mut seventh_byte = BitBuf::new();
@@ -387,7 +640,9 @@ impl ToWire for Capabilities {
 
         let mut eighth_byte = BitBuf::new();
         eighth_byte.write_bit(self.crypto.has_ecc)?;
-        eighth_byte.write_zero_bits(4)?;
+        eighth_byte.write_bit(self.networking.has_streaming)?;
+        eighth_byte
+            .write_bits(ProtocolVersion::BITS, self.protocol_version.get())?;
         eighth_byte.write_bits(AES_SIZE, self.crypto.aes_strength.bits())?;
         w.write_le(eighth_byte.bits())?;
 
@@ -410,6 +665,8 @@ impl Arbitrary for Capabilities {
                 max_packet_size: u.arbitrary()?,
                 mode: u.arbitrary()?,
                 roles: arbitrary_bitflags(u)?,
+                has_compression: u.arbitrary()?,
+                has_streaming: u.arbitrary()?,
             },
             security: arbitrary_bitflags(u)?,
             has_pfm_support: u.arbitrary()?,
@@ -423,7 +680,9 @@ impl Arbitrary for Capabilities {
                 ecc_strength: arbitrary_bitflags(u)?,
                 rsa_strength: arbitrary_bitflags(u)?,
                 aes_strength: arbitrary_bitflags(u)?,
+                hash_algos: HashAlgos(u.int_in_range(0..=0b11111)?),
             },
+            protocol_version: ProtocolVersion(u.int_in_range(0..=0b111)?),
         })
     }
 }
@@ -461,9 +720,9 @@ mod test {
                 0x00, 0x01,  // Message size.
                 0x80, 0x00,  // Packet size.
                 0b01_11_0_011,  // PA-RoT, Host + Target, KDF + Auth
-                0b1_0_0_00000,  // PFM support.
+                0b1_0_0_10011,  // PFM support; SHA-256, SHA-384, and bit 4.
                 0b1_0_000_001,  // RSA-2048 only.
-                0b0_0000_011,  // AES-128 and -256
+                0b0_0_000_011,  // AES-128 and -256
             ],
             json: r#"{
                 "capabilities": {
@@ -471,7 +730,9 @@ mod test {
                         "max_message_size": 256,
                         "max_packet_size": 128,
                         "mode": "Platform",
-                        "roles": ["Host", "Target"]
+                        "roles": ["Host", "Target"],
+                        "has_compression": false,
+                        "has_streaming": false
                     },
                     "security": ["HashAndKdf", "Authentication"],
                     "has_pfm_support": true,
@@ -484,8 +745,10 @@ mod test {
                         "has_aes": false,
                         "ecc_strength": [],
                         "rsa_strength": ["Bits2048"],
-                        "aes_strength": ["Bits128", "Bits256"]
-                    }
+                        "aes_strength": ["Bits128", "Bits256"],
+                        "hash_algos": 19
+                    },
+                    "protocol_version": 0
                 }
             }"#,
             value: DeviceCapabilitiesRequest {
@@ -495,6 +758,8 @@ mod test {
                         max_packet_size: 0x80,
                         mode: RotMode::Platform,
                         roles: BusRole::Host | BusRole::Target,
+                        has_compression: false,
+                        has_streaming: false,
                     },
                     security: Security::HashAndKdf | Security::Authentication,
                     has_pfm_support: true,
@@ -508,7 +773,9 @@ mod test {
                         ecc_strength: BitFlags::<EccKeyStrength>::empty(),
                         rsa_strength: RsaKeyStrength::Bits2048.into(),
                         aes_strength: AesKeyStrength::Bits128 | AesKeyStrength::Bits256,
+                        hash_algos: HashAlgos::from_bits(0b10011).unwrap(),
                     },
+                    protocol_version: ProtocolVersion::CURRENT,
                 },
             },
         },
@@ -517,9 +784,9 @@ mod test {
                 0x00, 0x01,  // Message size.
                 0x80, 0x00,  // Packet size.
                 0b01_11_0_011,  // PA-RoT, Host + Target, KDF + Auth
-                0b1_0_0_00000,  // PFM support.
+                0b1_0_0_10011,  // PFM support; SHA-256, SHA-384, and bit 4.
                 0b1_0_000_001,  // RSA-2048 only.
-                0b0_0000_011,  // AES-128 and -256
+                0b0_1_010_011,  // Streaming; version 2; AES-128 and -256
                 50,  // 500ms normal timeout.
                 2,  // 200ms crypto timeout.
             ],
@@ -529,7 +796,9 @@ mod test {
                         "max_message_size": 256,
                         "max_packet_size": 128,
                         "mode": "Platform",
-                        "roles": ["Host", "Target"]
+                        "roles": ["Host", "Target"],
+                        "has_compression": false,
+                        "has_streaming": true
                     },
                     "security": ["HashAndKdf", "Authentication"],
                     "has_pfm_support": true,
@@ -542,8 +811,10 @@ mod test {
                         "has_aes": false,
                         "ecc_strength": [],
                         "rsa_strength": ["Bits2048"],
-                        "aes_strength": ["Bits128", "Bits256"]
-                    }
+                        "aes_strength": ["Bits128", "Bits256"],
+                        "hash_algos": 19
+                    },
+                    "protocol_version": 2
                 },
                 "timeouts": {
                     "regular": { "secs": 0, "nanos": 500000000 },
@@ -557,6 +828,8 @@ mod test {
                         max_packet_size: 0x80,
                         mode: RotMode::Platform,
                         roles: BusRole::Host | BusRole::Target,
+                        has_compression: false,
+                        has_streaming: true,
                     },
                     security: Security::HashAndKdf | Security::Authentication,
                     has_pfm_support: true,
@@ -570,7 +843,9 @@ mod test {
                         ecc_strength: BitFlags::<EccKeyStrength>::empty(),
                         rsa_strength: RsaKeyStrength::Bits2048.into(),
                         aes_strength: AesKeyStrength::Bits128 | AesKeyStrength::Bits256,
+                        hash_algos: HashAlgos::from_bits(0b10011).unwrap(),
                     },
+                    protocol_version: ProtocolVersion::new(2).unwrap(),
                 },
                 timeouts: Timeouts {
                     regular: Duration::from_millis(500),
@@ -579,4 +854,38 @@ mod test {
             },
         },
     }
+
+    #[test]
+    fn hash_algos() {
+        let set = HashAlgos::from_bits(0b10011).unwrap();
+        assert!(set.contains(hash::Algo::Sha256));
+        assert!(set.contains(hash::Algo::Sha384));
+        assert!(!set.contains(hash::Algo::Sha512));
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            &[hash::Algo::Sha256, hash::Algo::Sha384]
+        );
+        assert_eq!(set.intersection(hash::Algo::Sha384.into()).bits(), 0b00010);
+        assert_eq!(HashAlgos::from_bits(0b100000), None);
+    }
+
+    #[test]
+    fn protocol_version() {
+        assert_eq!(ProtocolVersion::default(), ProtocolVersion::CURRENT);
+        assert_eq!(ProtocolVersion::new(7).map(ProtocolVersion::get), Some(7));
+        assert_eq!(ProtocolVersion::new(8), None);
+    }
+
+    #[test]
+    fn security_for_commands() {
+        assert_eq!(Security::for_commands(|_| false), BitFlags::empty());
+        assert_eq!(
+            Security::for_commands(|_| true),
+            Security::Authentication | Security::HashAndKdf
+        );
+        assert_eq!(
+            Security::for_commands(|c| c != CommandType::GetCert),
+            Security::HashAndKdf
+        );
+    }
 }
@@ -5,9 +5,40 @@
 //! `Challenge` request and response.
 //!
 //! This module provides the Cerberus challenge command.
+//!
+//! # Signed timestamps
+//!
+//! As a `manticore` extension, a requester may ask for the response to carry
+//! a timestamp, by setting the low bit of the request's reserved byte. A
+//! device that supports this sets the low bit of the first reserved byte of
+//! the response, and appends the current [`Ticks`] of its [`Clock`], as a
+//! little-endian `u64`, after PMR0. Since the timestamp comes before the
+//! signature, it is signed along with the rest of the response.
+//!
+//! The timestamp is only as trustworthy as the device's `Clock`: ticks count
+//! from an arbitrary epoch, such as the last reset, so they can only be
+//! compared with other timestamps from the same device, and a device whose
+//! clock an attacker can rewind offers no freshness guarantee at all. A
+//! device that does not support timestamps ignores the request bit, and
+//! responds without one.
+//!
+//! # Key type selection
+//!
+//! As another `manticore` extension, a requester talking to a device with
+//! both RSA and ECDSA keys may pick which one signs the response, using the
+//! two bits above the timestamp bit of the request's reserved byte: `0b01`
+//! for RSA and `0b10` for ECDSA. Both sides should only pick a key type that
+//! they both advertised in `DeviceCapabilities`; see
+//! [`KeyType::is_negotiated()`]. If both bits are clear, the device signs
+//! with its default key for the slot, as in plain Cerberus.
+//!
+//! [`Clock`]: crate::hardware::clock::Clock
+//! [`KeyType::is_negotiated()`]: crate::crypto::sig::KeyType::is_negotiated
 
 use core::convert::TryInto as _;
 
+use crate::crypto::sig;
+use crate::hardware::clock::Ticks;
 use crate::io::read::ReadZeroExt as _;
 use crate::io::ReadInt as _;
 use crate::io::ReadZero;
@@ -17,6 +48,8 @@ use crate::protocol::cerberus::CommandType;
 use crate::protocol::wire;
 use crate::protocol::wire::FromWire;
 use crate::protocol::wire::ToWire;
+use crate::protocol::Nonce;
+use crate::protocol::Slot;
 use crate::Result;
 
 protocol_struct! {
@@ -26,28 +59,45 @@ protocol_struct! {
 
     struct Request<'wire> {
         /// The slot number of the chain to read from.
-        pub slot: u8,
+        pub slot: Slot,
         /// A requester-chosen random nonce.
-        #[cfg_attr(feature = "serde", serde(
-            serialize_with = "crate::serde::se_hexstring",
-        ))]
-        #[@static(cfg_attr(feature = "serde", serde(
-            deserialize_with = "crate::serde::de_hexstring",
-        )))]
-        pub nonce: &'wire [u8; 32],
+        pub nonce: &'wire Nonce,
+        /// Whether the requester would like a signed timestamp in the
+        /// response.
+        ///
+        /// This is a `manticore` extension; see the
+        /// [module documentation](crate::protocol::cerberus::challenge).
+        pub want_timestamp: bool,
+        /// The type of key the requester would like the response signed
+        /// with, if it has a preference.
+        ///
+        /// This is a `manticore` extension; see the
+        /// [module documentation](crate::protocol::cerberus::challenge).
+        pub key_type: Option<sig::KeyType>,
     }
 
     fn Request::from_wire(r, arena) {
-        let slot = r.read_le()?;
-        let _: u8 = r.read_le()?;
-        let nonce = r.read_object::<[u8; 32]>(arena)?;
-        Ok(Self { slot, nonce })
+        let slot = Slot::from_wire(r, arena)?;
+        let flags: u8 = r.read_le()?;
+        let nonce = r.read_object::<Nonce>(arena)?;
+        let key_type = match (flags & KEY_TYPE_MASK) >> KEY_TYPE_SHIFT {
+            0b00 => None,
+            0b01 => Some(sig::KeyType::Rsa),
+            0b10 => Some(sig::KeyType::Ecdsa),
+            _ => return Err(fail!(wire::Error::OutOfRange)),
+        };
+        Ok(Self {
+            slot,
+            nonce,
+            want_timestamp: flags & TIMESTAMP_BIT != 0,
+            key_type,
+        })
     }
 
     fn Request::to_wire(&self, w) {
-        w.write_le(self.slot)?;
-        w.write_le(0u8)?;
-        w.write_bytes(self.nonce)?;
+        self.slot.to_wire(&mut w)?;
+        w.write_le(self.flags())?;
+        self.nonce.to_wire(&mut w)?;
         Ok(())
     }
 
@@ -77,11 +127,57 @@ protocol_struct! {
 
     fn Response::to_wire(&self, w) {
         self.tbs.to_wire(&mut w)?;
-        w.write_bytes(self.signature)?;
+        w.write_all(self.signature)?;
         Ok(())
     }
 }
 
+/// The bit in a request's reserved byte, and in the first of a response's
+/// reserved bytes, that signals a timestamp.
+const TIMESTAMP_BIT: u8 = 0b1;
+
+/// The bits in a request's reserved byte that select a key type.
+const KEY_TYPE_MASK: u8 = 0b110;
+const KEY_TYPE_SHIFT: u32 = 1;
+
+impl ChallengeRequest<'_> {
+    /// Returns the byte between the slot and the nonce.
+    fn flags(&self) -> u8 {
+        let key_type = match self.key_type {
+            None => 0b00,
+            Some(sig::KeyType::Rsa) => 0b01,
+            Some(sig::KeyType::Ecdsa) => 0b10,
+        };
+        let timestamp = if self.want_timestamp {
+            TIMESTAMP_BIT
+        } else {
+            0
+        };
+        (key_type << KEY_TYPE_SHIFT) | timestamp
+    }
+}
+
+impl ChallengeResponse<'_> {
+    /// Checks that this response was signed by `verifier`'s key, in reply to
+    /// `req`.
+    ///
+    /// This covers the whole response up to the signature, including the
+    /// timestamp, if there is one.
+    pub fn verify(
+        &self,
+        req: &ChallengeRequest,
+        verifier: &mut (impl sig::Verify + ?Sized),
+    ) -> Result<(), sig::Error> {
+        let head = [req.slot.get(), req.flags()];
+        self.tbs.as_iovec_with(|[a, b, c, d, e]| {
+            verifier.verify(
+                &[&head[..], req.nonce.as_ref(), a, b, c, d, e],
+                self.signature,
+            )
+        })
+    }
+}
+
 #[cfg(feature = "arbitrary-derive")]
 use libfuzzer_sys::arbitrary::{self, Arbitrary};
 
@@ -97,7 +193,7 @@ derive_borrowed! {
     )]
     pub struct ChallengeResponseTbs<'wire> {
         /// The slot number of the chain to read from.
-        pub slot: u8,
+        pub slot: Slot,
         /// The "certificate slot mask" (Cerberus does not elaborate further).
         ///
         /// Manticore ignores this value.
@@ -107,13 +203,7 @@ derive_borrowed! {
         /// Manticore ignores this value.
         pub protocol_range: (u8, u8),
         /// A responder-chosen random nonce.
-        #[cfg_attr(feature = "serde", serde(
-            serialize_with = "crate::serde::se_hexstring",
-        ))]
-        #[@static(cfg_attr(feature = "serde", serde(
-            deserialize_with = "crate::serde::de_hexstring",
-        )))]
-        pub nonce: &'wire [u8; 32],
+        pub nonce: &'wire Nonce,
         /// The number of "components" used to generate PMR0.
         pub pmr0_components: u8,
         /// The value of the PMR0 measurement.
@@ -124,6 +214,12 @@ derive_borrowed! {
             deserialize_with = "crate::serde::de_hexstring",
         )))]
         pub pmr0: &'wire [u8],
+        /// The device's [`Ticks`] when it built this response, if the
+        /// requester asked for them and the device supports timestamps.
+        ///
+        /// This is a `manticore` extension; see the
+        /// [module documentation](crate::protocol::cerberus::challenge).
+        pub timestamp: Option<Ticks>,
     }
 }
 
@@ -134,23 +230,34 @@ impl ChallengeResponseTbs<'_> {
     /// challenge response without needless allocation.
     pub(crate) fn as_iovec_with<R>(
         &self,
-        f: impl FnOnce([&[u8]; 4]) -> R,
+        f: impl FnOnce([&[u8]; 5]) -> R,
     ) -> R {
+        let timestamp = self.timestamp.map(Ticks::to_le_bytes);
         f([
             &[
-                self.slot,
+                self.slot.get(),
                 self.slot_mask,
                 self.protocol_range.0,
                 self.protocol_range.1,
                 // The two reserved bytes.
-                0,
+                self.flags(),
                 0,
             ],
-            self.nonce,
+            self.nonce.as_ref(),
             &[self.pmr0_components, self.pmr0.len() as u8],
             self.pmr0,
+            timestamp.as_ref().map_or(&[][..], |t| &t[..]),
         ])
     }
+
+    /// Returns the first reserved byte.
+    fn flags(&self) -> u8 {
+        if self.timestamp.is_some() {
+            TIMESTAMP_BIT
+        } else {
+            0
+        }
+    }
 }
 
 impl<'wire> FromWire<'wire> for ChallengeResponseTbs<'wire> {
@@ -158,18 +265,25 @@ impl<'wire> FromWire<'wire> for ChallengeResponseTbs<'wire> {
         r: &mut R,
         arena: &'wire dyn Arena,
     ) -> Result<Self, wire::Error> {
-        let slot = r.read_le()?;
+        let slot = Slot::from_wire(r, arena)?;
         let slot_mask = r.read_le()?;
         let min_version = r.read_le()?;
         let max_version = r.read_le()?;
-        let _: u16 = r.read_le()?;
+        let flags: u8 = r.read_le()?;
+        let _: u8 = r.read_le()?;
 
-        let nonce = r.read_object::<[u8; 32]>(arena)?;
+        let nonce = r.read_object::<Nonce>(arena)?;
 
         let pmr0_components = r.read_le()?;
         let pmr0_len = r.read_le::<u8>()?;
         let pmr0 = r.read_slice::<u8>(pmr0_len as usize, arena)?;
 
+        let timestamp = if flags & TIMESTAMP_BIT != 0 {
+            Some(r.read_le::<Ticks>()?)
+        } else {
+            None
+        };
+
         Ok(Self {
             slot,
             slot_mask,
@@ -177,18 +291,20 @@ impl<'wire> FromWire<'wire> for ChallengeResponseTbs<'wire> {
             nonce,
             pmr0_components,
             pmr0,
+            timestamp,
         })
     }
 }
 
 impl ToWire for ChallengeResponseTbs<'_> {
     fn to_wire<W: Write>(&self, mut w: W) -> Result<(), wire::Error> {
-        w.write_le(self.slot)?;
+        self.slot.to_wire(&mut w)?;
         w.write_le(self.slot_mask)?;
         w.write_le(self.protocol_range.0)?;
         w.write_le(self.protocol_range.1)?;
-        w.write_le(0u16)?;
-        w.write_bytes(self.nonce)?;
+        w.write_le(self.flags())?;
+        w.write_le(0u8)?;
+        self.nonce.to_wire(&mut w)?;
         w.write_le(self.pmr0_components)?;
         w.write_le::<u8>(
             self.pmr0
@@ -196,7 +312,10 @@ impl ToWire for ChallengeResponseTbs<'_> {
                 .try_into()
                 .map_err(|_| wire::Error::OutOfRange)?,
         )?;
-        w.write_bytes(self.pmr0)?;
+        w.write_all(self.pmr0)?;
+        if let Some(timestamp) = self.timestamp {
+            w.write_le(timestamp)?;
+        }
         Ok(())
     }
 }
@@ -218,11 +337,61 @@ mod test {
             ],
             json: r#"{
                 "slot": 1,
-                "nonce": "7777777777777777777777777777777777777777777777777777777777777777"
+                "nonce": "7777777777777777777777777777777777777777777777777777777777777777",
+                "want_timestamp": false,
+                "key_type": null
+            }"#,
+            value: ChallengeRequest {
+                slot: Slot::new(1).unwrap(),
+                nonce: &Nonce([0x77; 32]),
+                want_timestamp: false,
+                key_type: None,
+            },
+        },
+        timestamp_request_round_trip: {
+            bytes: &[
+                0x01, 0x01,  // Slot #, timestamp bit.
+
+                // Nonce.
+                0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77,
+                0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77,
+                0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77,
+                0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77,
+            ],
+            json: r#"{
+                "slot": 1,
+                "nonce": "7777777777777777777777777777777777777777777777777777777777777777",
+                "want_timestamp": true,
+                "key_type": null
+            }"#,
+            value: ChallengeRequest {
+                slot: Slot::new(1).unwrap(),
+                nonce: &Nonce([0x77; 32]),
+                want_timestamp: true,
+                key_type: None,
+            },
+        },
+        key_type_request_round_trip: {
+            bytes: &[
+                0x01, 0x05,  // Slot #, ECDSA key type, timestamp bit.
+
+                // Nonce.
+                0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77,
+                0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77,
+                0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77,
+                0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77,
+            ],
+            json: r#"{
+                "slot": 1,
+                "nonce": "7777777777777777777777777777777777777777777777777777777777777777",
+                "want_timestamp": true,
+                "key_type": "Ecdsa"
             }"#,
             value: ChallengeRequest {
-                slot: 1,
-                nonce: &[0x77; 32],
+                slot: Slot::new(1).unwrap(),
+                nonce: &Nonce([0x77; 32]),
+                want_timestamp: true,
+                key_type: Some(sig::KeyType::Ecdsa),
             },
         },
         response_round_trup: {
@@ -249,19 +418,202 @@ mod test {
                 "nonce": "dddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddd",
                 "pmr0_components": 10,
                 "pmr0": "706d7230",
+                "timestamp": null,
+                "signature": "6563647361"
+            }"#,
+            value: ChallengeResponse {
+                tbs: ChallengeResponseTbs {
+                    slot: Slot::new(1).unwrap(),
+                    slot_mask: 255,
+                    protocol_range: (5, 7),
+                    nonce: &Nonce([0xdd; 32]),
+                    pmr0_components: 10,
+                    pmr0: b"pmr0",
+                    timestamp: None,
+                },
+                signature: b"ecdsa",
+            },
+        },
+        timestamp_response_round_trip: {
+            bytes: &[
+                0x01, 0xff, 0x05, 0x07,  // Slot #, slot mask, min, max.
+                0x01, 0x00,              // Timestamp bit, reserved.
+
+                // Nonce.
+                0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd,
+                0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd,
+                0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd,
+                0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd,
+
+                0x0a, 0x04,  // Component #, PMR len.
+                b'p', b'm', b'r', b'0',
+
+                // Timestamp.
+                0x40, 0xe2, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+
+                // Signature.
+                b'e', b'c', b'd', b's', b'a',
+            ],
+            json: r#"{
+                "slot": 1,
+                "slot_mask": 255,
+                "protocol_range": [5, 7],
+                "nonce": "dddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddd",
+                "pmr0_components": 10,
+                "pmr0": "706d7230",
+                "timestamp": 123456,
                 "signature": "6563647361"
             }"#,
             value: ChallengeResponse {
                 tbs: ChallengeResponseTbs {
-                    slot: 1,
+                    slot: Slot::new(1).unwrap(),
                     slot_mask: 255,
                     protocol_range: (5, 7),
-                    nonce: &[0xdd; 32],
+                    nonce: &Nonce([0xdd; 32]),
                     pmr0_components: 10,
                     pmr0: b"pmr0",
+                    timestamp: Some(123456),
                 },
                 signature: b"ecdsa",
             },
         },
     }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn signed_timestamp() {
+        use crate::crypto::ring;
+        use crate::crypto::sig::Sign as _;
+        use testutil::data::keys;
+
+        let (mut verifier, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        let req = ChallengeRequest {
+            slot: Slot::FIRST,
+            nonce: &Nonce([0x77; 32]),
+            want_timestamp: true,
+            key_type: None,
+        };
+        let mut req_buf = [0; 34];
+        req.to_wire(&mut req_buf[..]).unwrap();
+
+        let mut tbs = ChallengeResponseTbs {
+            slot: Slot::FIRST,
+            slot_mask: 0,
+            protocol_range: (0, 0),
+            nonce: &Nonce([0xdd; 32]),
+            pmr0_components: 0,
+            pmr0: b"pmr0",
+            timestamp: Some(123456),
+        };
+        let mut signature = vec![0; signer.sig_bytes()];
+        let sig_len = tbs
+            .as_iovec_with(|[a, b, c, d, e]| {
+                signer.sign(&[&req_buf[..], a, b, c, d, e], &mut signature)
+            })
+            .unwrap();
+        signature.truncate(sig_len);
+
+        let resp = ChallengeResponse {
+            tbs,
+            signature: &signature,
+        };
+        resp.verify(&req, &mut verifier).unwrap();
+
+        // Rewinding the timestamp invalidates the signature, as does
+        // stripping it.
+        tbs.timestamp = Some(123455);
+        let resp = ChallengeResponse {
+            tbs,
+            signature: &signature,
+        };
+        assert!(resp.verify(&req, &mut verifier).is_err());
+
+        tbs.timestamp = None;
+        let resp = ChallengeResponse {
+            tbs,
+            signature: &signature,
+        };
+        assert!(resp.verify(&req, &mut verifier).is_err());
+    }
+
+    #[test]
+    fn reserved_key_type() {
+        let mut bytes = [0x77; 34];
+        bytes[0] = 0x01;
+        bytes[1] = 0x06;
+        let arena = crate::mem::BumpArena::new([0; 64]);
+        assert!(ChallengeRequest::from_wire(&mut &bytes[..], &arena).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn signed_with_each_key_type() {
+        use crate::crypto::ring;
+        use crate::crypto::sig::Sign as _;
+        use testutil::data::keys;
+
+        let (mut rsa_verifier, mut rsa_signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let mut ecdsa_signer =
+            ring::ecdsa::SignP256::with_der_encoding_from_pkcs8(
+                keys::KEY1_ECDSA_P256_KEYPAIR,
+            )
+            .unwrap();
+        let mut ecdsa_verifier = ring::ecdsa::VerifyP256::with_der_encoding(
+            *keys::KEY1_ECDSA_P256_X,
+            *keys::KEY1_ECDSA_P256_Y,
+        );
+
+        // Each key type's verifier is at the same index as its signer.
+        let mut signers = [
+            sig::AnySigner::Rsa(&mut rsa_signer),
+            sig::AnySigner::Ecdsa(&mut ecdsa_signer),
+        ];
+        let verifiers: [&mut dyn sig::Verify; 2] =
+            [&mut rsa_verifier, &mut ecdsa_verifier];
+        for (i, signer) in signers.iter_mut().enumerate() {
+            let req = ChallengeRequest {
+                slot: Slot::FIRST,
+                nonce: &Nonce([0x77; 32]),
+                want_timestamp: false,
+                key_type: Some(signer.key_type()),
+            };
+            let mut req_buf = [0; 34];
+            req.to_wire(&mut req_buf[..]).unwrap();
+
+            let tbs = ChallengeResponseTbs {
+                slot: Slot::FIRST,
+                slot_mask: 0,
+                protocol_range: (0, 0),
+                nonce: &Nonce([0xdd; 32]),
+                pmr0_components: 0,
+                pmr0: b"pmr0",
+                timestamp: None,
+            };
+            let mut signature = vec![0; signer.sig_bytes()];
+            let sig_len = tbs
+                .as_iovec_with(|[a, b, c, d, e]| {
+                    signer.sign(&[&req_buf[..], a, b, c, d, e], &mut signature)
+                })
+                .unwrap();
+            signature.truncate(sig_len);
+            let resp = ChallengeResponse {
+                tbs,
+                signature: &signature,
+            };
+
+            // Only the verifier for the selected key type accepts the
+            // response, and only for the request that selected it.
+            resp.verify(&req, &mut *verifiers[i]).unwrap();
+            assert!(resp.verify(&req, &mut *verifiers[1 - i]).is_err());
+
+            let unselected = ChallengeRequest {
+                key_type: None,
+                ..req
+            };
+            assert!(resp.verify(&unselected, &mut *verifiers[i]).is_err());
+        }
+    }
 }
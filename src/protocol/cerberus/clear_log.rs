@@ -0,0 +1,177 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `ClearLog` request and response.
+//!
+//! This module provides a Cerberus command for erasing one of a device's
+//! logs. Because this destroys evidence, servers only honor it within an
+//! established session, and it is authorized in the same way as
+//! [`ResetConfig`](super::ResetConfig): a request without an [`AuthToken`] is
+//! answered with a fresh nonce, and the log is only cleared once that nonce
+//! comes back signed by a key the device trusts.
+
+use crate::io::read::ReadZeroExt as _;
+use crate::protocol::cerberus::get_log::LogType;
+use crate::protocol::cerberus::reset_config::AuthToken;
+use crate::protocol::cerberus::CommandType;
+use crate::protocol::wire::WireEnum as _;
+use crate::protocol::Nonce;
+
+protocol_struct! {
+    /// A command for clearing a log.
+    type ClearLog;
+    const TYPE: CommandType = ClearLog;
+
+    struct Request<'wire> {
+        /// The log to clear.
+        pub log_type: LogType,
+        /// Proof that the requester may clear this log.
+        ///
+        /// If absent, the device does not clear anything, and instead replies
+        /// with a nonce to sign.
+        pub authorization: Option<AuthToken<'wire>>,
+    }
+
+    fn Request::from_wire(r, arena) {
+        let log_type = LogType::from_wire(r, arena)?;
+        let authorization = if r.remaining_data() > 0 {
+            Some(AuthToken::from_wire(r, arena)?)
+        } else {
+            None
+        };
+        Ok(Self { log_type, authorization })
+    }
+
+    fn Request::to_wire(&self, w) {
+        self.log_type.to_wire(&mut w)?;
+        if let Some(token) = &self.authorization {
+            token.to_wire(&mut w)?;
+        }
+        Ok(())
+    }
+
+    struct Response<'wire> {
+        /// A nonce that must be signed to authorize clearing the log, if the
+        /// request carried no authorization.
+        ///
+        /// This is empty if the log was cleared.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub nonce: &'wire [u8],
+    }
+
+    fn Response::from_wire(r, arena) {
+        let nonce = r.read_slice::<u8>(r.remaining_data(), arena)?;
+        Ok(Self { nonce })
+    }
+
+    fn Response::to_wire(&self, w) {
+        w.write_all(self.nonce)?;
+        Ok(())
+    }
+}
+
+/// Runs `f` with the message that an [`AuthToken`] for clearing the log of
+/// type `log_type` signs, as an iovec.
+///
+/// The message leads with the `ClearLog` command byte, so that a signature
+/// over it cannot also pass for a `ResetConfig` authorization.
+pub fn with_signed_message<R>(
+    log_type: LogType,
+    nonce: &Nonce,
+    f: impl FnOnce(&[&[u8]]) -> R,
+) -> R {
+    f(&[
+        &[
+            CommandType::ClearLog.to_wire_value(),
+            log_type.to_wire_value(),
+        ],
+        nonce.as_ref(),
+    ])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x01],
+            json: r#"{
+                "log_type": "Debug",
+                "authorization": null
+            }"#,
+            value: ClearLogRequest {
+                log_type: LogType::Debug,
+                authorization: None,
+            },
+        },
+        authorized_request_round_trip: {
+            bytes: &[
+                0x01,  // Debug.
+
+                // Nonce.
+                0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+                0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+                0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+                0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+
+                // Signature.
+                b'r', b's', b'a',
+            ],
+            json: r#"{
+                "log_type": "Debug",
+                "authorization": {
+                    "nonce": "5555555555555555555555555555555555555555555555555555555555555555",
+                    "signature": "727361"
+                }
+            }"#,
+            value: ClearLogRequest {
+                log_type: LogType::Debug,
+                authorization: Some(AuthToken {
+                    nonce: &Nonce([0x55; 32]),
+                    signature: b"rsa",
+                }),
+            },
+        },
+        response_round_trip: {
+            bytes: &[0xaa; 32],
+            json: r#"{
+                "nonce": "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"
+            }"#,
+            value: ClearLogResponse {
+                nonce: &[0xaa; 32],
+            },
+        },
+        empty_response_round_trip: {
+            bytes: &[],
+            json: r#"{
+                "nonce": ""
+            }"#,
+            value: ClearLogResponse {
+                nonce: &[],
+            },
+        },
+    }
+
+    #[test]
+    fn rejects_unknown_log_type() {
+        use crate::protocol::wire;
+        use crate::protocol::wire::FromWire as _;
+
+        for &byte in &[0x00, 0x04, 0xff] {
+            let mut bytes: &[u8] = &[byte];
+            let err = ClearLogRequest::from_wire(
+                &mut bytes,
+                &crate::mem::OutOfMemory,
+            )
+            .unwrap_err();
+            assert_eq!(err.into_inner(), wire::Error::OutOfRange);
+        }
+    }
+}
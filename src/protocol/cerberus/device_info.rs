@@ -58,7 +58,7 @@ protocol_struct! {
     }
 
     fn Response::to_wire(&self, w) {
-        w.write_bytes(self.info)?;
+        w.write_all(self.info)?;
         Ok(())
     }
 }
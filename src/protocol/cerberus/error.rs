@@ -8,32 +8,89 @@ use core::convert::TryFrom;
 use core::convert::TryInto;
 
 use crate::crypto;
+use crate::io;
 use crate::io::ReadInt as _;
 use crate::io::ReadZero;
 use crate::io::Write;
+use crate::manifest;
 use crate::mem::Arena;
 use crate::mem::OutOfMemory;
+use crate::mem::PoolExhausted;
 use crate::protocol::cerberus::CommandType;
 use crate::protocol::wire;
 use crate::protocol::wire::FromWire;
 use crate::protocol::wire::ToWire;
+use crate::protocol::wire::WireEnum as _;
 use crate::protocol::Message;
+use crate::server;
 use crate::session;
 use crate::Result;
 
 #[cfg(doc)]
 use crate::protocol;
 
+wire_enum! {
+    /// A Cerberus completion code, which leads every message of type
+    /// [`CommandType::Error`].
+    ///
+    /// Despite the name of the message that carries it, a completion code
+    /// may indicate success; see [`Ack`].
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum CompletionCode: u8 {
+        /// The request was executed successfully.
+        NoError = 0x00,
+        /// The request was not valid.
+        InvalidRequest = 0x01,
+        /// The device is busy; see [`Error::Busy`].
+        Busy = 0x03,
+        /// An unspecified error, whose meaning is given by the accompanying
+        /// data; see [`Error::Unspecified`].
+        Unspecified = 0x04,
+        /// The request's integrity check did not match.
+        InvalidChecksum = 0x05,
+        /// The request arrived out of order.
+        OutOfOrder = 0x06,
+        /// The request could not be authenticated.
+        Authentication = 0x07,
+        /// The request's sequence number was outside the expected window.
+        OutOfSequenceWindow = 0x08,
+        /// A packet had an invalid length.
+        InvalidPacketLength = 0x09,
+        /// The request was longer than the device could accept.
+        MessageOverflow = 0x0a,
+    }
+}
+
 /// An uninterpreted Cerberus Error.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RawError {
     /// What kind of error this is.
+    ///
+    /// See [`RawError::completion_code()`].
     pub code: u8,
     /// A fixed array of "extra data" that can come with an error code.
     pub data: [u8; 4],
 }
 
+impl RawError {
+    /// Returns this error's completion code, or `None` if it is not one that
+    /// `manticore` knows about.
+    pub fn completion_code(&self) -> Option<CompletionCode> {
+        CompletionCode::from_wire_value(self.code)
+    }
+
+    /// Returns whether this "error" actually indicates success.
+    ///
+    /// Only a [`CompletionCode::NoError`] with no extra data is a success;
+    /// anything else must be treated as a failure, even when it answers a
+    /// request whose response would otherwise be an [`Ack`].
+    pub fn is_success(&self) -> bool {
+        self.completion_code() == Some(CompletionCode::NoError)
+            && self.data == [0; 4]
+    }
+}
+
 impl<'wire> FromWire<'wire> for RawError {
     fn from_wire<R: ReadZero<'wire> + ?Sized>(
         r: &mut R,
@@ -50,7 +107,7 @@ impl<'wire> FromWire<'wire> for RawError {
 impl ToWire for RawError {
     fn to_wire<W: Write>(&self, mut w: W) -> Result<(), wire::Error> {
         w.write_le(self.code)?;
-        w.write_bytes(&self.data[..])?;
+        w.write_all(&self.data[..])?;
         Ok(())
     }
 }
@@ -89,7 +146,7 @@ impl ToWire for Ack {
 impl From<Ack> for RawError {
     fn from(_: Ack) -> RawError {
         RawError {
-            code: 0,
+            code: CompletionCode::NoError.to_wire_value(),
             data: [0; 4],
         }
     }
@@ -98,12 +155,10 @@ impl From<Ack> for RawError {
 impl TryFrom<RawError> for Ack {
     type Error = wire::Error;
     fn try_from(e: RawError) -> core::result::Result<Ack, wire::Error> {
-        match e {
-            RawError {
-                code: 0,
-                data: [0, 0, 0, 0],
-            } => Ok(Ack),
-            _ => Err(wire::Error::OutOfRange),
+        if e.is_success() {
+            Ok(Ack)
+        } else {
+            Err(wire::Error::OutOfRange)
         }
     }
 }
@@ -118,6 +173,9 @@ impl TryFrom<RawError> for Ack {
 pub enum Error {
     /// Indicates that the device is "busy", usually meaning that other
     /// commands are being serviced.
+    ///
+    /// Clients should retry the request after a short delay. Cryptography
+    /// errors that indicate busy hardware are folded into this error.
     Busy,
 
     /// Indicates that resources were exhausted during processing of a
@@ -140,7 +198,8 @@ pub enum Error {
     /// Indicates that some kind of internal error occured; this likely
     /// indicates a bug in the implementation.
     ///
-    /// All `manticore::crypto` errors get folded into this error by default.
+    /// All other `manticore::crypto` errors get folded into this error by
+    /// default.
     ///
     /// This is a Manticore-specific error.
     Internal,
@@ -150,6 +209,45 @@ pub enum Error {
     /// This is a Manticore-specific error.
     UnknownChain,
 
+    /// The requester-provided nonce was rejected, usually because it was
+    /// used in a recent request.
+    ///
+    /// This is a Manticore-specific error.
+    StaleNonce,
+
+    /// A requester-provided public key was malformed or, in the case of an
+    /// elliptic curve key, not a point on the expected curve.
+    ///
+    /// This is a Manticore-specific error.
+    InvalidPublicKey,
+
+    /// The request's command type is not supported by this device.
+    ///
+    /// This is a Manticore-specific error.
+    UnsupportedCommand,
+
+    /// The request may only be made within an established session.
+    ///
+    /// This is a Manticore-specific error.
+    SessionRequired,
+
+    /// The request requires authorization that was missing or invalid.
+    ///
+    /// This is a Manticore-specific error.
+    Unauthorized,
+
+    /// The requester speaks a protocol version that this device does not
+    /// accept.
+    ///
+    /// This is a Manticore-specific error.
+    UnsupportedVersion,
+
+    /// The requester asked for a signature with a type of key that this
+    /// device does not hold.
+    ///
+    /// This is a Manticore-specific error.
+    UnsupportedKeyType,
+
     /// Indicates an unspecified, vendor-defined error, which may include
     /// extra unformatted data.
     Unspecified([u8; 4]),
@@ -158,6 +256,18 @@ pub enum Error {
     Unknown(RawError),
 }
 
+impl Error {
+    /// Returns the completion code this error is sent with, or `None` if it
+    /// is an [`Error::Unknown`] with a code `manticore` does not know about.
+    pub fn completion_code(&self) -> Option<CompletionCode> {
+        match self {
+            Self::Busy => Some(CompletionCode::Busy),
+            Self::Unknown(e) => e.completion_code(),
+            _ => Some(CompletionCode::Unspecified),
+        }
+    }
+}
+
 impl Message<'_> for Error {
     type CommandType = CommandType;
     const TYPE: CommandType = CommandType::Error;
@@ -182,6 +292,13 @@ impl<'wire> FromWire<'wire> for Error {
                 3 => Ok(Self::OutOfRange),
                 4 => Ok(Self::Internal),
                 5 => Ok(Self::UnknownChain),
+                6 => Ok(Self::StaleNonce),
+                7 => Ok(Self::InvalidPublicKey),
+                8 => Ok(Self::UnsupportedCommand),
+                9 => Ok(Self::SessionRequired),
+                10 => Ok(Self::Unauthorized),
+                11 => Ok(Self::UnsupportedVersion),
+                12 => Ok(Self::UnsupportedKeyType),
                 _ => Err(fail!(wire::Error::OutOfRange)),
             },
             RawError { code: 4, data } => Ok(Self::Unspecified(data)),
@@ -217,6 +334,34 @@ impl ToWire for Error {
                 code: 4,
                 data: [5, 0, 0, 0],
             },
+            Self::StaleNonce => RawError {
+                code: 4,
+                data: [6, 0, 0, 0],
+            },
+            Self::InvalidPublicKey => RawError {
+                code: 4,
+                data: [7, 0, 0, 0],
+            },
+            Self::UnsupportedCommand => RawError {
+                code: 4,
+                data: [8, 0, 0, 0],
+            },
+            Self::SessionRequired => RawError {
+                code: 4,
+                data: [9, 0, 0, 0],
+            },
+            Self::Unauthorized => RawError {
+                code: 4,
+                data: [10, 0, 0, 0],
+            },
+            Self::UnsupportedVersion => RawError {
+                code: 4,
+                data: [11, 0, 0, 0],
+            },
+            Self::UnsupportedKeyType => RawError {
+                code: 4,
+                data: [12, 0, 0, 0],
+            },
             Self::Unspecified(data) => RawError {
                 code: 4,
                 data: *data,
@@ -234,28 +379,108 @@ impl From<OutOfMemory> for Error {
     }
 }
 
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        match e {
+            io::Error::BufferExhausted => Self::ResourceLimit,
+            io::Error::Internal => Self::Internal,
+        }
+    }
+}
+
+impl From<PoolExhausted> for Error {
+    fn from(_: PoolExhausted) -> Self {
+        Self::Busy
+    }
+}
+
 impl From<crypto::csrng::Error> for Error {
-    fn from(_: crypto::csrng::Error) -> Self {
-        Self::Internal
+    fn from(e: crypto::csrng::Error) -> Self {
+        match e {
+            crypto::csrng::Error::Unhealthy => Self::Busy,
+            _ => Self::Internal,
+        }
     }
 }
 
 impl From<crypto::hash::Error> for Error {
-    fn from(_: crypto::hash::Error) -> Self {
-        Self::Internal
+    fn from(e: crypto::hash::Error) -> Self {
+        match e {
+            crypto::hash::Error::Busy => Self::Busy,
+            _ => Self::Internal,
+        }
     }
 }
 
 impl From<crypto::sig::Error> for Error {
-    fn from(_: crypto::sig::Error) -> Self {
-        Self::Internal
+    fn from(e: crypto::sig::Error) -> Self {
+        match e {
+            crypto::sig::Error::Busy => Self::Busy,
+            _ => Self::Internal,
+        }
+    }
+}
+
+/// A manifest that fails to parse or verify is blamed on whoever sent it.
+impl From<manifest::Error> for Error {
+    fn from(e: manifest::Error) -> Self {
+        match e {
+            manifest::Error::OutOfMemory => Self::ResourceLimit,
+            manifest::Error::SigError(_) => Self::Unauthorized,
+            _ => Self::Malformed,
+        }
+    }
+}
+
+impl From<server::nonce::Error> for Error {
+    fn from(e: server::nonce::Error) -> Self {
+        match e {
+            server::nonce::Error::Csrng(e) => e.into(),
+        }
     }
 }
 
 impl From<session::Error> for Error {
-    fn from(_: session::Error) -> Self {
-        Self::Internal
+    fn from(e: session::Error) -> Self {
+        match e {
+            session::Error::InvalidPublicKey => Self::InvalidPublicKey,
+            session::Error::Unauthenticated => Self::Unauthorized,
+            _ => Self::Internal,
+        }
     }
 }
 
-debug_from!(Error => OutOfMemory, crypto::csrng::Error, crypto::hash::Error, crypto::sig::Error, session::Error);
+debug_from!(Error => OutOfMemory, io::Error, PoolExhausted, crypto::csrng::Error, crypto::hash::Error, crypto::sig::Error, manifest::Error, server::nonce::Error, session::Error);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn completion_codes() {
+        let ack = RawError::from(Ack);
+        assert_eq!(ack.completion_code(), Some(CompletionCode::NoError));
+        assert!(ack.is_success());
+
+        // A success code with extra data attached is not a success.
+        let partial = RawError {
+            code: 0x00,
+            data: [1, 0, 0, 0],
+        };
+        assert!(!partial.is_success());
+        assert!(Ack::try_from(partial).is_err());
+
+        let unknown = RawError {
+            code: 0x7e,
+            data: [0; 4],
+        };
+        assert_eq!(unknown.completion_code(), None);
+        assert_eq!(Error::Unknown(unknown).completion_code(), None);
+
+        assert_eq!(Error::Busy.completion_code(), Some(CompletionCode::Busy));
+        assert_eq!(
+            Error::Unauthorized.completion_code(),
+            Some(CompletionCode::Unspecified)
+        );
+    }
+}
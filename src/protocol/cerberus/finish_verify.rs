@@ -0,0 +1,86 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `FinishVerify` request and response.
+//!
+//! This module provides a Cerberus command for closing a verification
+//! started with [`BeginVerify`](super::BeginVerify), and learning whether
+//! the image matched its expected digest.
+//!
+//! Note that this command is a Manticore extension.
+
+use crate::io::ReadInt as _;
+use crate::protocol::cerberus::CommandType;
+
+protocol_struct! {
+    /// A command for finishing a firmware verification.
+    type FinishVerify;
+    const TYPE: CommandType = FinishVerify;
+
+    struct Request {
+        /// The handle returned by `BeginVerify`.
+        pub handle: u32,
+    }
+
+    fn Request::from_wire(r, _) {
+        let handle = r.read_le::<u32>()?;
+        Ok(Self { handle })
+    }
+
+    fn Request::to_wire(&self, w) {
+        w.write_le(self.handle)?;
+        Ok(())
+    }
+
+    struct Response {
+        /// Whether the image's digest matched the expected one.
+        pub verified: bool,
+    }
+
+    fn Response::from_wire(r, _) {
+        let verified = wire::one_of("verified", r.read_le::<u8>()?, &[0, 1])?;
+        Ok(Self { verified: verified == 1 })
+    }
+
+    fn Response::to_wire(&self, w) {
+        w.write_le(self.verified as u8)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::protocol::wire;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x2a, 0x00, 0x00, 0x00],
+            json: r#"{
+                "handle": 42
+            }"#,
+            value: FinishVerifyRequest { handle: 42 },
+        },
+        response_round_trip: {
+            bytes: &[0x01],
+            json: r#"{
+                "verified": true
+            }"#,
+            value: FinishVerifyResponse { verified: true },
+        },
+    }
+
+    #[test]
+    fn rejects_bad_verdict() {
+        use crate::protocol::wire::FromWire as _;
+
+        let mut bytes: &[u8] = &[0x02];
+        let err = FinishVerifyResponse::from_wire(
+            &mut bytes,
+            &crate::mem::OutOfMemory,
+        )
+        .unwrap_err();
+        assert_eq!(err.into_inner(), wire::Error::Validation("verified"));
+    }
+}
@@ -51,7 +51,7 @@ protocol_struct! {
     }
 
     fn Response::to_wire(&self, w) {
-        w.write_bytes(self.version)?;
+        w.write_all(self.version)?;
         Ok(())
     }
 }
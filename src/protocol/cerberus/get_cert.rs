@@ -7,8 +7,8 @@
 //! This module provides a Cerberus command for requesting certificates.
 
 use crate::io::ReadInt as _;
-use crate::mem::ArenaExt as _;
 use crate::protocol::cerberus::CommandType;
+use crate::protocol::Slot;
 
 protocol_struct! {
     /// A command for requesting a chunk of a certificate.
@@ -17,7 +17,7 @@ protocol_struct! {
 
     struct Request {
         /// The slot number of the chain to read from.
-        pub slot: u8,
+        pub slot: Slot,
         /// The number of the cert to request, indexed from the root.
         pub cert_number: u8,
         /// The offset in bytes from the start of the certificate to read from.
@@ -26,8 +26,8 @@ protocol_struct! {
         pub len: u16,
     }
 
-    fn Request::from_wire(r, _) {
-        let slot = r.read_le()?;
+    fn Request::from_wire(r, arena) {
+        let slot = Slot::from_wire(r, arena)?;
         let cert_number = r.read_le()?;
         let offset = r.read_le()?;
         let len = r.read_le()?;
@@ -40,7 +40,7 @@ protocol_struct! {
     }
 
     fn Request::to_wire(&self, w) {
-        w.write_le(self.slot)?;
+        self.slot.to_wire(&mut w)?;
         w.write_le(self.cert_number)?;
         w.write_le(self.offset)?;
         w.write_le(self.len)?;
@@ -49,7 +49,7 @@ protocol_struct! {
 
     struct Response<'wire> {
         /// The slot number of the chain to read from.
-        pub slot: u8,
+        pub slot: Slot,
         /// The number of the cert to request, indexed from the root.
         pub cert_number: u8,
         /// The data read from the certificate.
@@ -63,12 +63,10 @@ protocol_struct! {
     }
 
     fn Response::from_wire(r, arena) {
-        let slot = r.read_le()?;
+        let slot = Slot::from_wire(r, arena)?;
         let cert_number = r.read_le()?;
 
-        let data_len = r.remaining_data();
-        let data = arena.alloc_slice::<u8>(data_len)?;
-        r.read_bytes(data)?;
+        let data = wire::read_to_end(r, arena)?;
         Ok(Self {
             slot,
             cert_number,
@@ -77,9 +75,9 @@ protocol_struct! {
     }
 
     fn Response::to_wire(&self, w) {
-        w.write_le(self.slot)?;
+        self.slot.to_wire(&mut w)?;
         w.write_le(self.cert_number)?;
-        w.write_bytes(self.data)?;
+        w.write_all(self.data)?;
         Ok(())
     }
 }
@@ -98,7 +96,7 @@ mod test {
                 "len": 255
             }"#,
             value: GetCertRequest {
-                slot: 1,
+                slot: Slot::new(1).unwrap(),
                 cert_number: 2,
                 offset: 257,
                 len: 255,
@@ -112,10 +110,22 @@ mod test {
                 "data": "782e353039"
             }"#,
             value: GetCertResponse {
-                slot: 1,
+                slot: Slot::new(1).unwrap(),
                 cert_number: 2,
                 data: b"x.509",
             },
         },
     }
+
+    #[test]
+    fn rejects_bad_slot() {
+        use crate::protocol::wire;
+        use crate::protocol::wire::FromWire as _;
+
+        let mut bytes: &[u8] = &[0x08, 0x02, 0x01, 0x01, 0xff, 0x00];
+        let err =
+            GetCertRequest::from_wire(&mut bytes, &crate::mem::OutOfMemory)
+                .unwrap_err();
+        assert_eq!(err.into_inner(), wire::Error::Validation("slot"));
+    }
 }
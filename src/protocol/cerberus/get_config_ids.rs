@@ -0,0 +1,184 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `GetConfigIds` request and response.
+//!
+//! This module provides a Cerberus command for asking which version of a
+//! manifest a device has active, such as the CFM most recently activated
+//! with [`ActivateCfm`](super::ActivateCfm).
+
+use crate::io::read::ReadZeroExt as _;
+use crate::io::ReadInt as _;
+use crate::io::ReadZero;
+use crate::io::Write;
+use crate::manifest::ManifestType;
+use crate::mem::Arena;
+use crate::protocol::cerberus::CommandType;
+use crate::protocol::wire;
+use crate::protocol::wire::FromWire;
+use crate::protocol::wire::ToWire;
+use crate::Result;
+
+protocol_struct! {
+    /// A command for requesting the identity of an active manifest.
+    type GetConfigIds;
+    const TYPE: CommandType = GetConfigIds;
+
+    struct Request {
+        /// The type of manifest to describe.
+        pub manifest_type: ManifestType,
+    }
+
+    fn Request::from_wire(r, arena) {
+        let manifest_type = ManifestType::from_wire(r, arena)?;
+        Ok(Self { manifest_type })
+    }
+
+    fn Request::to_wire(&self, w) {
+        self.manifest_type.to_wire(&mut w)?;
+        Ok(())
+    }
+
+    struct Response<'wire> {
+        /// The type of manifest described, echoed from the request.
+        pub manifest_type: ManifestType,
+        /// The active manifest of that type, or `None` if no such manifest
+        /// is active.
+        pub active: Option<ConfigId<'wire>>,
+    }
+
+    fn Response::from_wire(r, arena) {
+        let manifest_type = ManifestType::from_wire(r, arena)?;
+        let active = match r.read_le::<u8>()? {
+            0 => None,
+            1 => Some(ConfigId::from_wire(r, arena)?),
+            _ => return Err(fail!(wire::Error::OutOfRange)),
+        };
+        Ok(Self { manifest_type, active })
+    }
+
+    fn Response::to_wire(&self, w) {
+        self.manifest_type.to_wire(&mut w)?;
+        match &self.active {
+            Some(id) => {
+                w.write_le(1u8)?;
+                id.to_wire(&mut w)?;
+            }
+            None => w.write_le(0u8)?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "arbitrary-derive")]
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+
+derive_borrowed! {
+    /// The identity of an active manifest.
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    #[@static(
+        derive(Clone, PartialEq, Eq, Debug),
+        cfg_attr(feature = "serde", derive(serde::Deserialize)),
+        cfg_attr(feature = "arbitrary-derive", derive(Arbitrary)),
+    )]
+    pub struct ConfigId<'wire> {
+        /// The manifest's version ID, from its header.
+        pub version_id: u32,
+        /// The SHA-256 digest of the entire manifest, including its
+        /// signature.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub digest: &'wire [u8; 32],
+    }
+}
+
+impl<'wire> FromWire<'wire> for ConfigId<'wire> {
+    fn from_wire<R: ReadZero<'wire> + ?Sized>(
+        r: &mut R,
+        arena: &'wire dyn Arena,
+    ) -> Result<Self, wire::Error> {
+        let version_id = r.read_le::<u32>()?;
+        let digest = r.read_object::<[u8; 32]>(arena)?;
+        Ok(Self { version_id, digest })
+    }
+}
+
+impl ToWire for ConfigId<'_> {
+    fn to_wire<W: Write>(&self, mut w: W) -> Result<(), wire::Error> {
+        w.write_le(self.version_id)?;
+        w.write_all(self.digest)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x92, 0xa5],
+            json: r#"{
+                "manifest_type": "Cfm"
+            }"#,
+            value: GetConfigIdsRequest {
+                manifest_type: ManifestType::Cfm,
+            },
+        },
+        response_round_trip: {
+            bytes: &[
+                0x92, 0xa5,  // Cfm.
+                0x01,        // Active.
+                0x2a, 0x00, 0x00, 0x00,  // Version ID.
+
+                // Digest.
+                0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77,
+                0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77,
+                0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77,
+                0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77,
+            ],
+            json: r#"{
+                "manifest_type": "Cfm",
+                "active": {
+                    "version_id": 42,
+                    "digest": "7777777777777777777777777777777777777777777777777777777777777777"
+                }
+            }"#,
+            value: GetConfigIdsResponse {
+                manifest_type: ManifestType::Cfm,
+                active: Some(ConfigId {
+                    version_id: 42,
+                    digest: &[0x77; 32],
+                }),
+            },
+        },
+        inactive_response_round_trip: {
+            bytes: &[0x6d, 0x70, 0x00],
+            json: r#"{
+                "manifest_type": "Pfm",
+                "active": null
+            }"#,
+            value: GetConfigIdsResponse {
+                manifest_type: ManifestType::Pfm,
+                active: None,
+            },
+        },
+    }
+
+    #[test]
+    fn rejects_bad_active_flag() {
+        let mut bytes: &[u8] = &[0x92, 0xa5, 0x02];
+        let err = GetConfigIdsResponse::from_wire(
+            &mut bytes,
+            &crate::mem::OutOfMemory,
+        )
+        .unwrap_err();
+        assert_eq!(err.into_inner(), wire::Error::OutOfRange);
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `GetDeviceState` request and response.
+//!
+//! This module provides a Cerberus command for querying the runtime state of
+//! the RoT itself, such as whether it is running normally or recovering. It
+//! is intended for tooling that polls a RoT's health.
+
+use crate::io::ReadInt as _;
+use crate::protocol::cerberus::CommandType;
+use crate::protocol::wire::WireEnum as _;
+
+protocol_struct! {
+    /// A command for requesting the device's runtime state.
+    type GetDeviceState;
+    const TYPE: CommandType = GetDeviceState;
+
+    struct Request {}
+
+    fn Request::from_wire(_, _) {
+        Ok(Self {})
+    }
+
+    fn Request::to_wire(&self, _w) {
+        Ok(())
+    }
+
+    struct Response {
+        /// The device's current state.
+        pub state: State,
+    }
+
+    fn Response::from_wire(r, _) {
+        let byte = r.read_le::<u8>()?;
+        let state = State::from_wire_value(byte).unwrap_or(State::Reserved);
+        Ok(Self { state })
+    }
+
+    fn Response::to_wire(&self, w) {
+        w.write_le(self.state.to_wire_value())?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "arbitrary-derive")]
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+
+wire_enum! {
+    /// A RoT's runtime state.
+    ///
+    /// States this version of `manticore` does not know about are parsed as
+    /// [`State::Reserved`], so that newer devices can still be polled.
+    #[cfg_attr(feature = "arbitrary-derive", derive(Arbitrary))]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum State: u8 {
+        /// The device is running its normal firmware.
+        Running = 0x00,
+        /// The device is running a recovery image.
+        Recovery = 0x01,
+        /// The device is applying a firmware update.
+        Updating = 0x02,
+        /// The device has been unsealed, such as for debugging, and its
+        /// attestations should not be trusted.
+        Unsealed = 0x03,
+        /// A state not known to this version of `manticore`.
+        Reserved = 0xff,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[],
+            json: "{}",
+            value: GetDeviceStateRequest {},
+        },
+        response_round_trip: {
+            bytes: &[0x01],
+            json: r#"{
+                "state": "Recovery"
+            }"#,
+            value: GetDeviceStateResponse {
+                state: State::Recovery,
+            },
+        },
+    }
+
+    #[test]
+    fn unknown_state_is_reserved() {
+        use crate::protocol::wire::FromWire as _;
+
+        let mut bytes: &[u8] = &[0x42];
+        let resp = GetDeviceStateResponse::from_wire(
+            &mut bytes,
+            &crate::mem::OutOfMemory,
+        )
+        .unwrap();
+        assert_eq!(resp.state, State::Reserved);
+    }
+}
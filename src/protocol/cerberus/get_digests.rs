@@ -15,6 +15,7 @@ use crate::crypto::hash;
 use crate::io::ReadInt as _;
 use crate::mem::ArenaExt as _;
 use crate::protocol::cerberus::CommandType;
+use crate::protocol::Slot;
 
 protocol_struct! {
     /// A command for requesting certificate hashes.
@@ -23,21 +24,21 @@ protocol_struct! {
 
     struct Request {
         /// The slot number of the chain to read from.
-        pub slot: u8,
+        pub slot: Slot,
         /// The key exchange algorithm to eventually use.
         ///
         /// Manticore currently ignores this field.
         pub key_exchange: KeyExchangeAlgo,
     }
 
-    fn Request::from_wire(r, a) {
-        let slot = r.read_le()?;
-        let key_exchange = KeyExchangeAlgo::from_wire(r, a)?;
+    fn Request::from_wire(r, arena) {
+        let slot = Slot::from_wire(r, arena)?;
+        let key_exchange = KeyExchangeAlgo::from_wire(r, arena)?;
         Ok(Self { slot, key_exchange })
     }
 
     fn Request::to_wire(&self, w) {
-        w.write_le(self.slot)?;
+        self.slot.to_wire(&mut w)?;
         self.key_exchange.to_wire(w)?;
         Ok(())
     }
@@ -72,7 +73,7 @@ protocol_struct! {
             .try_into()
             .map_err(|_| wire::Error::OutOfRange)?;
         w.write_le(digests_len)?;
-        w.write_bytes(self.digests.as_bytes())?;
+        w.write_all(self.digests.as_bytes())?;
         Ok(())
     }
 }
@@ -104,7 +105,7 @@ mod test {
                 "key_exchange": "None"
             }"#,
             value: GetDigestsRequest {
-                slot: 1,
+                slot: Slot::new(1).unwrap(),
                 key_exchange: KeyExchangeAlgo::None,
             },
         },
@@ -115,7 +116,7 @@ mod test {
                 "key_exchange": "Ecdh"
             }"#,
             value: GetDigestsRequest {
-                slot: 5,
+                slot: Slot::new(5).unwrap(),
                 key_exchange: KeyExchangeAlgo::Ecdh,
             },
         },
@@ -0,0 +1,117 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `GetLog` request and response.
+//!
+//! This module provides a Cerberus command for reading one of a device's
+//! logs, such as its attestation event log, in chunks.
+
+use crate::io::ReadInt as _;
+use crate::protocol::cerberus::CommandType;
+
+protocol_struct! {
+    /// A command for requesting a chunk of a log.
+    type GetLog;
+    const TYPE: CommandType = GetLog;
+
+    struct Request {
+        /// The log to read from.
+        pub log_type: LogType,
+        /// The offset in bytes from the start of the log to read from.
+        pub offset: u32,
+    }
+
+    fn Request::from_wire(r, a) {
+        let log_type = LogType::from_wire(r, a)?;
+        let offset = r.read_le()?;
+        Ok(Self { log_type, offset })
+    }
+
+    fn Request::to_wire(&self, w) {
+        self.log_type.to_wire(&mut w)?;
+        w.write_le(self.offset)?;
+        Ok(())
+    }
+
+    struct Response<'wire> {
+        /// The data read from the log.
+        ///
+        /// This is as much of the log as fits in a single message; an empty
+        /// chunk indicates that `offset` is at or past the end of the log.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub data: &'wire [u8],
+    }
+
+    fn Response::from_wire(r, arena) {
+        let data = wire::read_to_end(r, arena)?;
+        Ok(Self { data })
+    }
+
+    fn Response::to_wire(&self, w) {
+        w.write_all(self.data)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "arbitrary-derive")]
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+
+wire_enum! {
+    /// A log type, i.e., which of a device's logs is being read.
+    #[cfg_attr(feature = "arbitrary-derive", derive(Arbitrary))]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum LogType: u8 {
+        /// The device's debug log.
+        Debug = 0x01,
+        /// The device's attestation log, in TCG event log format.
+        ///
+        /// See [`TcgEventLog`](crate::server::TcgEventLog).
+        Attestation = 0x02,
+        /// The device's tamper log.
+        Tamper = 0x03,
+    }
+}
+derive_borrowed!(LogType);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x02, 0x00, 0x01, 0x00, 0x00],
+            json: r#"{
+                "log_type": "Attestation",
+                "offset": 256
+            }"#,
+            value: GetLogRequest {
+                log_type: LogType::Attestation,
+                offset: 256,
+            },
+        },
+        response_round_trip: {
+            bytes: b"some log",
+            json: r#"{
+                "data": "736f6d65206c6f67"
+            }"#,
+            value: GetLogResponse {
+                data: b"some log",
+            },
+        },
+        empty_response_round_trip: {
+            bytes: &[],
+            json: r#"{
+                "data": ""
+            }"#,
+            value: GetLogResponse {
+                data: &[],
+            },
+        },
+    }
+}
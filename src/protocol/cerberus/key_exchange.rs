@@ -108,7 +108,7 @@ protocol_struct! {
                     hash::Algo::Sha384 => w.write_le::<u8>(0b01)?,
                     hash::Algo::Sha512 => w.write_le::<u8>(0b10)?,
                 }
-                w.write_bytes(pk_req)?;
+                w.write_all(pk_req)?;
             }
             Self::PairedKeyHmac { key_len, key_hmac } => {
                 RequestType::PairedKeyHmac.to_wire(&mut w)?;
@@ -116,11 +116,11 @@ protocol_struct! {
                     .try_into()
                     .map_err(|_| wire::Error::OutOfRange)?;
                 w.write_le(key_len)?;
-                w.write_bytes(key_hmac)?;
+                w.write_all(key_hmac)?;
             }
             Self::DestroySession { session_hmac } => {
                 RequestType::DestroySession.to_wire(&mut w)?;
-                w.write_bytes(session_hmac)?;
+                w.write_all(session_hmac)?;
             }
         }
 
@@ -202,16 +202,16 @@ protocol_struct! {
                     .try_into()
                     .map_err(|_| wire::Error::OutOfRange)?;
                 w.write_le(pk_len)?;
-                w.write_bytes(pk_resp)?;
+                w.write_all(pk_resp)?;
 
                 let sig_len: u16 = signature
                     .len()
                     .try_into()
                     .map_err(|_| wire::Error::OutOfRange)?;
                 w.write_le(sig_len)?;
-                w.write_bytes(signature)?;
+                w.write_all(signature)?;
 
-                w.write_bytes(alias_cert_hmac)?;
+                w.write_all(alias_cert_hmac)?;
                 Ok(())
             }
             Self::PairedKeyHmac => RequestType::PairedKeyHmac.to_wire(&mut w),
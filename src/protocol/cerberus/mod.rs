@@ -25,24 +25,86 @@ pub use get_digests::GetDigests;
 pub mod get_cert;
 pub use get_cert::GetCert;
 
+pub mod get_log;
+pub use get_log::GetLog;
+
+pub mod clear_log;
+pub use clear_log::ClearLog;
+
+pub mod get_device_state;
+pub use get_device_state::GetDeviceState;
+
 pub mod get_host_state;
 pub use get_host_state::GetHostState;
 
+pub mod set_host_state;
+pub use set_host_state::SetHostState;
+
 pub mod challenge;
 pub use challenge::Challenge;
 
 pub mod key_exchange;
 pub use key_exchange::KeyExchange;
 
+pub mod reset_config;
+pub use reset_config::ResetConfig;
+
+pub mod prepare_pfm;
+pub use prepare_pfm::PreparePfm;
+
+pub mod update_pfm;
+pub use update_pfm::UpdatePfm;
+
+pub mod activate_pfm;
+pub use activate_pfm::ActivatePfm;
+
+pub mod prepare_cfm;
+pub use prepare_cfm::PrepareCfm;
+
+pub mod update_cfm;
+pub use update_cfm::UpdateCfm;
+
+pub mod activate_cfm;
+pub use activate_cfm::ActivateCfm;
+
+pub mod prepare_pcd;
+pub use prepare_pcd::PreparePcd;
+
+pub mod update_pcd;
+pub use update_pcd::UpdatePcd;
+
+pub mod activate_pcd;
+pub use activate_pcd::ActivatePcd;
+
+pub mod get_config_ids;
+pub use get_config_ids::GetConfigIds;
+
 pub mod reset_counter;
 pub use reset_counter::ResetCounter;
 
+pub mod unseal;
+pub use unseal::Unseal;
+
+pub mod begin_verify;
+pub use begin_verify::BeginVerify;
+
+pub mod verify_chunk;
+pub use verify_chunk::VerifyChunk;
+
+pub mod finish_verify;
+pub use finish_verify::FinishVerify;
+
 pub mod request_counter;
 pub use request_counter::RequestCounter;
 
+pub mod vendor_command;
+pub use vendor_command::VendorCommand;
+
 mod error;
 pub use error::*;
 
+pub use crate::protocol::SLOTS;
+
 wire_enum! {
     /// A Cerberus command type.
     ///
@@ -90,11 +152,67 @@ wire_enum! {
         ///
         /// See [`GetHostState`].
         GetHostState = 0x40,
+        /// A request for a chunk of one of the device's logs.
+        ///
+        /// See [`GetLog`].
+        GetLog = 0x50,
+        /// A request to clear one of the device's logs.
+        ///
+        /// See [`ClearLog`].
+        ClearLog = 0x51,
+        /// A request to reset part of the device's configuration.
+        ///
+        /// See [`ResetConfig`].
+        ResetConfig = 0x6a,
+        /// A request to begin sending a new PFM.
+        ///
+        /// See [`PreparePfm`].
+        PreparePfm = 0x5b,
+        /// A request carrying part of a new PFM.
+        ///
+        /// See [`UpdatePfm`].
+        UpdatePfm = 0x5c,
+        /// A request to activate a new PFM.
+        ///
+        /// See [`ActivatePfm`].
+        ActivatePfm = 0x5d,
+        /// A request to begin sending a new CFM.
+        ///
+        /// See [`PrepareCfm`].
+        PrepareCfm = 0x5f,
+        /// A request carrying part of a new CFM.
+        ///
+        /// See [`UpdateCfm`].
+        UpdateCfm = 0x60,
+        /// A request to activate a new CFM.
+        ///
+        /// See [`ActivateCfm`].
+        ActivateCfm = 0x61,
+        /// A request to begin sending a new PCD.
+        ///
+        /// See [`PreparePcd`].
+        PreparePcd = 0x64,
+        /// A request carrying part of a new PCD.
+        ///
+        /// See [`UpdatePcd`].
+        UpdatePcd = 0x65,
+        /// A request to activate a new PCD.
+        ///
+        /// See [`ActivatePcd`].
+        ActivatePcd = 0x66,
+        /// A request for the identity of an active manifest.
+        ///
+        /// See [`GetConfigIds`].
+        GetConfigIds = 0x70,
         /// A request for the number of times the device has been reset since
         /// POR.
         ///
         /// See [`ResetCounter`].
         ResetCounter = 0x87,
+        /// A request to release a key sealed to the device's measurements.
+        ///
+        /// See [`Unseal`].
+        Unseal = 0x89,
         /// A request for the uptime of the device since last reset.
         ///
         /// Note that this command is a Manticore extension.
@@ -108,6 +226,41 @@ wire_enum! {
         ///
         /// See [`RequestCounter`].
         RequestCounter = 0xa1,
+        /// A request for the runtime state of the device.
+        ///
+        /// Note that this command is a Manticore extension.
+        ///
+        /// See [`GetDeviceState`].
+        GetDeviceState = 0xa2,
+        /// A request to begin verifying a firmware image.
+        ///
+        /// Note that this command is a Manticore extension.
+        ///
+        /// See [`BeginVerify`].
+        BeginVerify = 0xa3,
+        /// A request carrying part of a firmware image being verified.
+        ///
+        /// Note that this command is a Manticore extension.
+        ///
+        /// See [`VerifyChunk`].
+        VerifyChunk = 0xa4,
+        /// A request for the result of a firmware verification.
+        ///
+        /// Note that this command is a Manticore extension.
+        ///
+        /// See [`FinishVerify`].
+        FinishVerify = 0xa5,
+        /// A request to hold the host processor in reset, or release it.
+        ///
+        /// Note that this command is a Manticore extension.
+        ///
+        /// See [`SetHostState`].
+        SetHostState = 0xa6,
+        /// A vendor-defined message, identified by a vendor ID and a
+        /// vendor-specific subcommand.
+        ///
+        /// See [`VendorCommand`].
+        VendorCommand = 0xf0,
     }
 }
 
@@ -115,25 +268,79 @@ impl CommandType {
     /// Returns `true` when `self` represents a `manticore` extension to the
     /// protocol.
     pub fn is_manticore_extension(self) -> bool {
-        matches!(self, Self::DeviceUptime)
+        matches!(
+            self,
+            Self::DeviceUptime
+                | Self::GetDeviceState
+                | Self::BeginVerify
+                | Self::VerifyChunk
+                | Self::FinishVerify
+                | Self::SetHostState
+        )
+    }
+
+    /// Returns `true` when handling a `self` request twice has the same
+    /// effect as handling it once.
+    ///
+    /// A client may blindly retry an idempotent request, such as after a
+    /// [`Error::Busy`], whether or not the first attempt was handled. Retrying
+    /// any other request risks applying it twice: an `UpdateCfm` retried
+    /// after its reply was lost would append its chunk a second time.
+    pub fn is_idempotent(self) -> bool {
+        // This match is deliberately exhaustive, so that new commands must be
+        // classified.
+        match self {
+            Self::Error
+            | Self::FirmwareVersion
+            | Self::DeviceCapabilities
+            | Self::DeviceId
+            | Self::DeviceInfo
+            | Self::GetDigests
+            | Self::GetCert
+            | Self::GetHostState
+            | Self::GetLog
+            | Self::GetConfigIds
+            | Self::ResetCounter
+            | Self::DeviceUptime
+            | Self::RequestCounter
+            | Self::GetDeviceState => true,
+
+            // These produce fresh nonces, or consume the ones they are given.
+            Self::Challenge | Self::KeyExchange | Self::Unseal => false,
+
+            Self::ClearLog
+            | Self::ResetConfig
+            | Self::PreparePfm
+            | Self::UpdatePfm
+            | Self::ActivatePfm
+            | Self::PrepareCfm
+            | Self::UpdateCfm
+            | Self::ActivateCfm
+            | Self::PreparePcd
+            | Self::UpdatePcd
+            | Self::ActivatePcd
+            | Self::BeginVerify
+            | Self::VerifyChunk
+            | Self::FinishVerify => false,
+
+            // Setting the same state twice is harmless, but it still
+            // changes what the host is doing.
+            Self::SetHostState => false,
+
+            // Vendor commands are opaque, so assume the worst.
+            Self::VendorCommand => false,
+        }
     }
 }
 
+/// Converts a command byte into a [`CommandType`], mapping bytes that are not
+/// a known command to [`CommandType::Error`].
+///
+/// Servers have no handler for `Error` requests, so they answer these with
+/// an "unsupported command" error, rather than treating them as bad framing.
 impl From<u8> for CommandType {
     fn from(num: u8) -> CommandType {
-        match num {
-            0x01 => CommandType::FirmwareVersion,
-            0x02 => CommandType::DeviceCapabilities,
-            0x03 => CommandType::DeviceId,
-            0x04 => CommandType::DeviceInfo,
-            0x81 => CommandType::GetDigests,
-            0x82 => CommandType::GetCert,
-            0x83 => CommandType::Challenge,
-            0x40 => CommandType::GetHostState,
-            0x87 => CommandType::ResetCounter,
-            0xa0 => CommandType::DeviceUptime,
-            0xa1 => CommandType::RequestCounter,
-            _ => CommandType::Error,
-        }
+        use crate::protocol::wire::WireEnum as _;
+        CommandType::from_wire_value(num).unwrap_or(CommandType::Error)
     }
 }
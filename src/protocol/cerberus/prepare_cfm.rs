@@ -0,0 +1,65 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `PrepareCfm` request and response.
+//!
+//! This module provides a Cerberus command for starting the transfer of a
+//! new Component Firmware Manifest to a device. The manifest itself is then
+//! sent in chunks with [`UpdateCfm`](super::UpdateCfm).
+
+use crate::io::ReadInt as _;
+use crate::protocol::cerberus::CommandType;
+
+protocol_struct! {
+    /// A command for preparing to receive a CFM.
+    type PrepareCfm;
+    const TYPE: CommandType = PrepareCfm;
+
+    struct Request {
+        /// The total length of the CFM that will follow, in bytes.
+        pub total_len: u32,
+    }
+
+    fn Request::from_wire(r, _) {
+        let total_len = r.read_le::<u32>()?;
+        Ok(Self { total_len })
+    }
+
+    fn Request::to_wire(&self, w) {
+        w.write_le(self.total_len)?;
+        Ok(())
+    }
+
+    struct Response {}
+
+    fn Response::from_wire(_, _) {
+        Ok(Self {})
+    }
+
+    fn Response::to_wire(&self, _w) {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x00, 0x04, 0x00, 0x00],
+            json: r#"{
+                "total_len": 1024
+            }"#,
+            value: PrepareCfmRequest {
+                total_len: 1024,
+            },
+        },
+        response_round_trip: {
+            bytes: &[],
+            json: "{}",
+            value: PrepareCfmResponse {},
+        },
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `PreparePfm` request and response.
+//!
+//! This module provides a Cerberus command for starting the transfer of a
+//! new Platform Firmware Manifest to a device. The manifest itself is then
+//! sent in chunks with [`UpdatePfm`](super::UpdatePfm).
+
+use crate::io::ReadInt as _;
+use crate::protocol::cerberus::CommandType;
+
+protocol_struct! {
+    /// A command for preparing to receive a PFM.
+    type PreparePfm;
+    const TYPE: CommandType = PreparePfm;
+
+    struct Request {
+        /// The port whose PFM is being replaced.
+        pub port_id: u8,
+        /// The total length of the PFM that will follow, in bytes.
+        pub total_len: u32,
+    }
+
+    fn Request::from_wire(r, _) {
+        let port_id = r.read_le::<u8>()?;
+        let total_len = r.read_le::<u32>()?;
+        Ok(Self { port_id, total_len })
+    }
+
+    fn Request::to_wire(&self, w) {
+        w.write_le(self.port_id)?;
+        w.write_le(self.total_len)?;
+        Ok(())
+    }
+
+    struct Response {}
+
+    fn Response::from_wire(_, _) {
+        Ok(Self {})
+    }
+
+    fn Response::to_wire(&self, _w) {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x01, 0x00, 0x04, 0x00, 0x00],
+            json: r#"{
+                "port_id": 1,
+                "total_len": 1024
+            }"#,
+            value: PreparePfmRequest {
+                port_id: 1,
+                total_len: 1024,
+            },
+        },
+        response_round_trip: {
+            bytes: &[],
+            json: "{}",
+            value: PreparePfmResponse {},
+        },
+    }
+}
@@ -0,0 +1,225 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `ResetConfig` request and response.
+//!
+//! This module provides a Cerberus command for restoring some part of a
+//! device's configuration to its defaults. Because this is destructive, it
+//! requires authorization: a request without an [`AuthToken`] is answered
+//! with a fresh nonce, and the reset is only performed once that nonce comes
+//! back signed by a key the device trusts.
+
+use crate::io::read::ReadZeroExt as _;
+use crate::io::ReadZero;
+use crate::io::Write;
+use crate::mem::Arena;
+use crate::protocol::cerberus::CommandType;
+use crate::protocol::wire;
+use crate::protocol::wire::FromWire;
+use crate::protocol::wire::ToWire;
+use crate::protocol::wire::WireEnum as _;
+use crate::protocol::Nonce;
+use crate::Result;
+
+protocol_struct! {
+    /// A command for resetting device configuration.
+    type ResetConfig;
+    const TYPE: CommandType = ResetConfig;
+
+    struct Request<'wire> {
+        /// The kind of reset to perform.
+        pub kind: ResetKind,
+        /// Proof that the requester may perform this reset.
+        ///
+        /// If absent, the device does not reset anything, and instead replies
+        /// with a nonce to sign.
+        pub authorization: Option<AuthToken<'wire>>,
+    }
+
+    fn Request::from_wire(r, arena) {
+        let kind = ResetKind::from_wire(r, arena)?;
+        let authorization = if r.remaining_data() > 0 {
+            Some(AuthToken::from_wire(r, arena)?)
+        } else {
+            None
+        };
+        Ok(Self { kind, authorization })
+    }
+
+    fn Request::to_wire(&self, w) {
+        self.kind.to_wire(&mut w)?;
+        if let Some(token) = &self.authorization {
+            token.to_wire(&mut w)?;
+        }
+        Ok(())
+    }
+
+    struct Response<'wire> {
+        /// A nonce that must be signed to authorize the reset, if the request
+        /// carried no authorization.
+        ///
+        /// This is empty if the reset was performed.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub nonce: &'wire [u8],
+    }
+
+    fn Response::from_wire(r, arena) {
+        let nonce = r.read_slice::<u8>(r.remaining_data(), arena)?;
+        Ok(Self { nonce })
+    }
+
+    fn Response::to_wire(&self, w) {
+        w.write_all(self.nonce)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "arbitrary-derive")]
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+
+wire_enum! {
+    /// A kind of configuration reset.
+    #[cfg_attr(feature = "arbitrary-derive", derive(Arbitrary))]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum ResetKind: u8 {
+        /// Erase all platform firmware manifests, reverting the device to
+        /// bypass mode.
+        Bypass = 0x00,
+        /// Restore the device's factory-default configuration.
+        FactoryDefault = 0x01,
+        /// Clear any platform-specific configuration.
+        PlatformConfig = 0x02,
+        /// Clear a detected intrusion.
+        Intrusion = 0x03,
+        /// Erase all component firmware manifests.
+        ComponentManifests = 0x04,
+    }
+}
+derive_borrowed!(ResetKind);
+
+derive_borrowed! {
+    /// An authorization for a [`ResetConfig`] request, or for another
+    /// destructive request, such as [`ClearLog`](super::ClearLog).
+    ///
+    /// For a `ResetConfig`, the signature is over the [`ResetKind`] byte
+    /// followed by the nonce; see [`with_signed_message()`].
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    #[@static(
+        derive(Clone, PartialEq, Eq, Debug),
+        cfg_attr(feature = "serde", derive(serde::Deserialize)),
+        cfg_attr(feature = "arbitrary-derive", derive(Arbitrary)),
+    )]
+    pub struct AuthToken<'wire> {
+        /// The nonce the device handed out for this reset.
+        pub nonce: &'wire Nonce,
+        /// A signature over the reset kind and `nonce`.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub signature: &'wire [u8],
+    }
+}
+
+/// Runs `f` with the message that an [`AuthToken`] for a reset of `kind`
+/// signs, as an iovec.
+pub fn with_signed_message<R>(
+    kind: ResetKind,
+    nonce: &Nonce,
+    f: impl FnOnce(&[&[u8]]) -> R,
+) -> R {
+    f(&[&[kind.to_wire_value()], nonce.as_ref()])
+}
+
+impl<'wire> FromWire<'wire> for AuthToken<'wire> {
+    fn from_wire<R: ReadZero<'wire> + ?Sized>(
+        r: &mut R,
+        arena: &'wire dyn Arena,
+    ) -> Result<Self, wire::Error> {
+        let nonce = r.read_object::<Nonce>(arena)?;
+        let signature = r.read_slice::<u8>(r.remaining_data(), arena)?;
+        Ok(Self { nonce, signature })
+    }
+}
+
+impl ToWire for AuthToken<'_> {
+    fn to_wire<W: Write>(&self, mut w: W) -> Result<(), wire::Error> {
+        self.nonce.to_wire(&mut w)?;
+        w.write_all(self.signature)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x01],
+            json: r#"{
+                "kind": "FactoryDefault",
+                "authorization": null
+            }"#,
+            value: ResetConfigRequest {
+                kind: ResetKind::FactoryDefault,
+                authorization: None,
+            },
+        },
+        authorized_request_round_trip: {
+            bytes: &[
+                0x03,  // Intrusion.
+
+                // Nonce.
+                0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+                0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+                0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+                0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+
+                // Signature.
+                b'r', b's', b'a',
+            ],
+            json: r#"{
+                "kind": "Intrusion",
+                "authorization": {
+                    "nonce": "5555555555555555555555555555555555555555555555555555555555555555",
+                    "signature": "727361"
+                }
+            }"#,
+            value: ResetConfigRequest {
+                kind: ResetKind::Intrusion,
+                authorization: Some(AuthToken {
+                    nonce: &Nonce([0x55; 32]),
+                    signature: b"rsa",
+                }),
+            },
+        },
+        response_round_trip: {
+            bytes: &[0xaa; 32],
+            json: r#"{
+                "nonce": "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"
+            }"#,
+            value: ResetConfigResponse {
+                nonce: &[0xaa; 32],
+            },
+        },
+        empty_response_round_trip: {
+            bytes: &[],
+            json: r#"{
+                "nonce": ""
+            }"#,
+            value: ResetConfigResponse {
+                nonce: &[],
+            },
+        },
+    }
+}
@@ -0,0 +1,189 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `SetHostState` request and response.
+//!
+//! This module provides a Cerberus command for holding the host processor
+//! protected by Cerberus in reset, such as while recovering its firmware, and
+//! for releasing it again. The resulting state can be read back with
+//! [`GetHostState`](super::GetHostState).
+//!
+//! Since a host held in reset is offline, this command is authorized in the
+//! same way as [`ResetConfig`](super::ResetConfig): a request without an
+//! [`AuthToken`] is answered with a fresh nonce, and the host's state is only
+//! changed once that nonce comes back signed by a key the device trusts.
+//!
+//! Note that this command is a Manticore extension.
+
+use crate::io::read::ReadZeroExt as _;
+use crate::io::ReadInt as _;
+use crate::protocol::cerberus::reset_config::AuthToken;
+use crate::protocol::cerberus::CommandType;
+use crate::protocol::wire::WireEnum as _;
+use crate::protocol::Nonce;
+
+protocol_struct! {
+    /// A command for holding the host in reset, or releasing it.
+    type SetHostState;
+    const TYPE: CommandType = SetHostState;
+
+    struct Request<'wire> {
+        /// The port of the host whose reset state is being set.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hex",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_radix",
+        )))]
+        pub port_id: u8,
+        /// Whether the host should be held in reset, rather than released.
+        pub hold_in_reset: bool,
+        /// Proof that the requester may change the host's state.
+        ///
+        /// If absent, the device does not change anything, and instead
+        /// replies with a nonce to sign.
+        pub authorization: Option<AuthToken<'wire>>,
+    }
+
+    fn Request::from_wire(r, arena) {
+        let port_id = r.read_le()?;
+        let hold_in_reset =
+            wire::one_of("hold_in_reset", r.read_le::<u8>()?, &[0, 1])?;
+        let authorization = if r.remaining_data() > 0 {
+            Some(AuthToken::from_wire(r, arena)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            port_id,
+            hold_in_reset: hold_in_reset == 1,
+            authorization,
+        })
+    }
+
+    fn Request::to_wire(&self, w) {
+        w.write_le(self.port_id)?;
+        w.write_le(self.hold_in_reset as u8)?;
+        if let Some(token) = &self.authorization {
+            token.to_wire(&mut w)?;
+        }
+        Ok(())
+    }
+
+    struct Response<'wire> {
+        /// A nonce that must be signed to authorize the change, if the
+        /// request carried no authorization.
+        ///
+        /// This is empty if the host's state was changed.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub nonce: &'wire [u8],
+    }
+
+    fn Response::from_wire(r, arena) {
+        let nonce = r.read_slice::<u8>(r.remaining_data(), arena)?;
+        Ok(Self { nonce })
+    }
+
+    fn Response::to_wire(&self, w) {
+        w.write_all(self.nonce)?;
+        Ok(())
+    }
+}
+
+/// Runs `f` with the message that an [`AuthToken`] for setting the state of
+/// the host on `port_id` signs, as an iovec.
+///
+/// The message leads with the `SetHostState` command byte, so that a
+/// signature over it cannot also pass for another command's authorization,
+/// and covers `hold_in_reset`, so that it cannot be replayed to do the
+/// opposite.
+pub fn with_signed_message<R>(
+    port_id: u8,
+    hold_in_reset: bool,
+    nonce: &Nonce,
+    f: impl FnOnce(&[&[u8]]) -> R,
+) -> R {
+    f(&[
+        &[
+            CommandType::SetHostState.to_wire_value(),
+            port_id,
+            hold_in_reset as u8,
+        ],
+        nonce.as_ref(),
+    ])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x00, 0x01],
+            json: r#"{
+                "port_id": "0x00",
+                "hold_in_reset": true,
+                "authorization": null
+            }"#,
+            value: SetHostStateRequest {
+                port_id: 0,
+                hold_in_reset: true,
+                authorization: None,
+            },
+        },
+        authorized_request_round_trip: {
+            bytes: &[
+                0x00, 0x00,  // Port 0, released.
+
+                // Nonce.
+                0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+                0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+                0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+                0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+
+                // Signature.
+                b'r', b's', b'a',
+            ],
+            json: r#"{
+                "port_id": "0x00",
+                "hold_in_reset": false,
+                "authorization": {
+                    "nonce": "5555555555555555555555555555555555555555555555555555555555555555",
+                    "signature": "727361"
+                }
+            }"#,
+            value: SetHostStateRequest {
+                port_id: 0,
+                hold_in_reset: false,
+                authorization: Some(AuthToken {
+                    nonce: &Nonce([0x55; 32]),
+                    signature: b"rsa",
+                }),
+            },
+        },
+        response_round_trip: {
+            bytes: &[0xaa; 32],
+            json: r#"{
+                "nonce": "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"
+            }"#,
+            value: SetHostStateResponse {
+                nonce: &[0xaa; 32],
+            },
+        },
+        empty_response_round_trip: {
+            bytes: &[],
+            json: r#"{
+                "nonce": ""
+            }"#,
+            value: SetHostStateResponse {
+                nonce: &[],
+            },
+        },
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `Unseal` request and response.
+//!
+//! This module provides a Cerberus command for releasing a key that was
+//! sealed to a set of measurements. The request carries a [`Policy`], naming
+//! PCRs and the values they must hold, along with the encrypted key. The
+//! device only decrypts the key if its measurements satisfy the policy, and
+//! only releases it within an established session: the request must arrive
+//! encrypted, and the released key is itself encrypted with the session's
+//! AES key.
+//!
+//! Unlike Cerberus's "Unseal Message" command, which is polled for its result,
+//! this command is answered synchronously.
+
+use core::convert::TryInto as _;
+
+use zerocopy::AsBytes as _;
+
+use crate::crypto::hash;
+use crate::io::ReadInt as _;
+use crate::io::ReadZero;
+use crate::io::Write;
+use crate::mem::Arena;
+use crate::mem::ArenaExt as _;
+use crate::protocol::cerberus::CommandType;
+use crate::protocol::wire;
+use crate::protocol::wire::FromWire;
+use crate::protocol::wire::ToWire;
+use crate::Result;
+
+/// The length of a PCR value.
+const DIGEST_LEN: usize = hash::Algo::Sha256.bytes();
+
+protocol_struct! {
+    /// A command for releasing a sealed key.
+    type Unseal;
+    const TYPE: CommandType = Unseal;
+
+    struct Request<'wire> {
+        /// The measurements the key was sealed to.
+        pub policy: Policy<'wire>,
+        /// The sealed key, encrypted with a device-specific key.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub blob: &'wire [u8],
+    }
+
+    fn Request::from_wire(r, arena) {
+        let policy = Policy::from_wire(r, arena)?;
+        let blob = wire::read_to_end(r, arena)?;
+        Ok(Self { policy, blob })
+    }
+
+    fn Request::to_wire(&self, w) {
+        self.policy.to_wire(&mut w)?;
+        w.write_all(self.blob)?;
+        Ok(())
+    }
+
+    struct Response<'wire> {
+        /// The released key, as an encrypted message under the current
+        /// session; see [`session`](crate::session) for its layout.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub key: &'wire [u8],
+    }
+
+    fn Response::from_wire(r, arena) {
+        let key = wire::read_to_end(r, arena)?;
+        Ok(Self { key })
+    }
+
+    fn Response::to_wire(&self, w) {
+        w.write_all(self.key)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "arbitrary-derive")]
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+
+derive_borrowed! {
+    /// A sealing policy: a selection of PCRs, and the value each one must
+    /// hold for a key sealed to this policy to be released.
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    #[@static(
+        derive(Clone, PartialEq, Eq, Debug),
+        cfg_attr(feature = "serde", derive(serde::Deserialize)),
+        cfg_attr(feature = "arbitrary-derive", derive(Arbitrary)),
+    )]
+    pub struct Policy<'wire> {
+        /// The selected PCRs, as a bitmask: bit `i` selects PCR `i`.
+        pub pcr_mask: u32,
+        /// The expected value of each selected PCR, in ascending order of PCR
+        /// index.
+        ///
+        /// There must be exactly as many of these as there are bits set in
+        /// `pcr_mask`.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstrings",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstrings",
+        )))]
+        pub expected: &'wire [[u8; DIGEST_LEN]],
+    }
+}
+
+impl<'wire> Policy<'wire> {
+    /// Returns each PCR index this policy selects, along with the value it
+    /// expects that PCR to hold.
+    pub fn pcrs(
+        &self,
+    ) -> impl Iterator<Item = (usize, &'wire [u8; DIGEST_LEN])> {
+        let mask = self.pcr_mask;
+        (0..32)
+            .filter(move |i| mask & (1 << i) != 0)
+            .zip(self.expected.iter())
+    }
+}
+
+impl<'wire> FromWire<'wire> for Policy<'wire> {
+    fn from_wire<R: ReadZero<'wire> + ?Sized>(
+        r: &mut R,
+        arena: &'wire dyn Arena,
+    ) -> Result<Self, wire::Error> {
+        let pcr_mask = r.read_le::<u32>()?;
+        let expected = arena
+            .alloc_slice::<[u8; DIGEST_LEN]>(pcr_mask.count_ones() as usize)?;
+        r.read_bytes(expected.as_bytes_mut())?;
+        Ok(Self { pcr_mask, expected })
+    }
+}
+
+impl ToWire for Policy<'_> {
+    fn to_wire<W: Write>(&self, mut w: W) -> Result<(), wire::Error> {
+        let count: u32 = self
+            .expected
+            .len()
+            .try_into()
+            .map_err(|_| wire::Error::OutOfRange)?;
+        check!(count == self.pcr_mask.count_ones(), wire::Error::OutOfRange);
+
+        w.write_le(self.pcr_mask)?;
+        w.write_all(self.expected.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[
+                0x05, 0x00, 0x00, 0x00,  // PCRs 0 and 2.
+
+                // PCR 0.
+                0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+                0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+                0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+                0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+
+                // PCR 2.
+                0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22,
+                0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22,
+                0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22,
+                0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22,
+
+                // Blob.
+                0xb1, 0x0b,
+            ],
+            json: r#"{
+                "policy": {
+                    "pcr_mask": 5,
+                    "expected": [
+                        "1111111111111111111111111111111111111111111111111111111111111111",
+                        "2222222222222222222222222222222222222222222222222222222222222222"
+                    ]
+                },
+                "blob": "b10b"
+            }"#,
+            value: UnsealRequest {
+                policy: Policy {
+                    pcr_mask: 0b101,
+                    expected: &[[0x11; 32], [0x22; 32]],
+                },
+                blob: &[0xb1, 0x0b],
+            },
+        },
+        response_round_trip: {
+            bytes: b"key",
+            json: r#"{
+                "key": "6b6579"
+            }"#,
+            value: UnsealResponse { key: b"key" },
+        },
+    }
+
+    #[test]
+    fn policy_pcrs() {
+        let policy = Policy {
+            pcr_mask: 0x8000_0005,
+            expected: &[[0x11; 32], [0x22; 32], [0x33; 32]],
+        };
+        let pcrs = policy.pcrs().map(|(i, v)| (i, v[0])).collect::<Vec<_>>();
+        assert_eq!(pcrs, &[(0, 0x11), (2, 0x22), (31, 0x33)]);
+    }
+
+    #[test]
+    fn policy_length_mismatch() {
+        let policy = Policy {
+            pcr_mask: 0b11,
+            expected: &[[0x11; 32]],
+        };
+        let mut buf = [0; 64];
+        let err = policy.to_wire(&mut buf[..]).unwrap_err();
+        assert_eq!(err.into_inner(), wire::Error::OutOfRange);
+    }
+}
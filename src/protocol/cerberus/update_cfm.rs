@@ -0,0 +1,81 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `UpdateCfm` request and response.
+//!
+//! This module provides a Cerberus command for sending the next chunk of a
+//! Component Firmware Manifest whose transfer was started with
+//! [`PrepareCfm`](super::PrepareCfm).
+
+use crate::io::read::ReadZeroExt as _;
+use crate::io::ReadInt as _;
+use crate::protocol::cerberus::CommandType;
+
+protocol_struct! {
+    /// A command for sending part of a CFM.
+    type UpdateCfm;
+    const TYPE: CommandType = UpdateCfm;
+
+    struct Request<'wire> {
+        /// The offset of this chunk within the CFM.
+        ///
+        /// Chunks must be sent in order; this is used to detect chunks that
+        /// were lost or repeated.
+        pub offset: u32,
+        /// The next chunk of the CFM.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub data: &'wire [u8],
+    }
+
+    fn Request::from_wire(r, arena) {
+        let offset = r.read_le::<u32>()?;
+        let data = r.read_slice::<u8>(r.remaining_data(), arena)?;
+        Ok(Self { offset, data })
+    }
+
+    fn Request::to_wire(&self, w) {
+        w.write_le(self.offset)?;
+        w.write_all(self.data)?;
+        Ok(())
+    }
+
+    struct Response {}
+
+    fn Response::from_wire(_, _) {
+        Ok(Self {})
+    }
+
+    fn Response::to_wire(&self, _w) {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: b"\x00\x01\x00\x00manifest",
+            json: r#"{
+                "offset": 256,
+                "data": "6d616e6966657374"
+            }"#,
+            value: UpdateCfmRequest {
+                offset: 256,
+                data: b"manifest",
+            },
+        },
+        response_round_trip: {
+            bytes: &[],
+            json: "{}",
+            value: UpdateCfmResponse {},
+        },
+    }
+}
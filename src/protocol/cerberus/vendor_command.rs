@@ -0,0 +1,127 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `VendorCommand` request and response.
+//!
+//! This module provides a Cerberus command for carrying vendor-defined
+//! messages. Manticore does not interpret their payloads; servers route them
+//! to a [`VendorHandler`] registered for the message's vendor ID.
+//!
+//! [`VendorHandler`]: crate::server::VendorHandler
+
+use crate::io::ReadInt as _;
+use crate::mem::ArenaExt as _;
+use crate::protocol::cerberus::CommandType;
+
+protocol_struct! {
+    /// A command for sending a vendor-defined message.
+    type VendorCommand;
+    const TYPE: CommandType = VendorCommand;
+
+    struct Request<'wire> {
+        /// The vendor that defines this message.
+        pub vendor_id: u16,
+        /// The vendor-defined command within `vendor_id`'s namespace.
+        pub subcommand: u8,
+        /// The opaque payload of the message.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub payload: &'wire [u8],
+    }
+
+    fn Request::from_wire(r, arena) {
+        let vendor_id = r.read_le()?;
+        let subcommand = r.read_le()?;
+
+        let payload_len = r.remaining_data();
+        let payload = arena.alloc_slice::<u8>(payload_len)?;
+        r.read_bytes(payload)?;
+        Ok(Self {
+            vendor_id,
+            subcommand,
+            payload,
+        })
+    }
+
+    fn Request::to_wire(&self, w) {
+        w.write_le(self.vendor_id)?;
+        w.write_le(self.subcommand)?;
+        w.write_all(self.payload)?;
+        Ok(())
+    }
+
+    struct Response<'wire> {
+        /// The vendor that defines this message.
+        pub vendor_id: u16,
+        /// The vendor-defined command being responded to.
+        pub subcommand: u8,
+        /// The opaque payload of the response.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub payload: &'wire [u8],
+    }
+
+    fn Response::from_wire(r, arena) {
+        let vendor_id = r.read_le()?;
+        let subcommand = r.read_le()?;
+
+        let payload_len = r.remaining_data();
+        let payload = arena.alloc_slice::<u8>(payload_len)?;
+        r.read_bytes(payload)?;
+        Ok(Self {
+            vendor_id,
+            subcommand,
+            payload,
+        })
+    }
+
+    fn Response::to_wire(&self, w) {
+        w.write_le(self.vendor_id)?;
+        w.write_le(self.subcommand)?;
+        w.write_all(self.payload)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x14, 0x14, 0x02, 0xaa, 0xbb],
+            json: r#"{
+                "vendor_id": 5140,
+                "subcommand": 2,
+                "payload": "aabb"
+            }"#,
+            value: VendorCommandRequest {
+                vendor_id: 0x1414,
+                subcommand: 2,
+                payload: &[0xaa, 0xbb],
+            },
+        },
+        response_round_trip: {
+            bytes: &[0x14, 0x14, 0x02],
+            json: r#"{
+                "vendor_id": 5140,
+                "subcommand": 2,
+                "payload": ""
+            }"#,
+            value: VendorCommandResponse {
+                vendor_id: 0x1414,
+                subcommand: 2,
+                payload: &[],
+            },
+        },
+    }
+}
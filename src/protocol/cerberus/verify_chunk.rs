@@ -0,0 +1,93 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `VerifyChunk` request and response.
+//!
+//! This module provides a Cerberus command for streaming the next chunk of
+//! a firmware image into a verification started with
+//! [`BeginVerify`](super::BeginVerify).
+//!
+//! Note that this command is a Manticore extension.
+
+use crate::io::read::ReadZeroExt as _;
+use crate::io::ReadInt as _;
+use crate::protocol::cerberus::CommandType;
+
+protocol_struct! {
+    /// A command for sending part of an image being verified.
+    type VerifyChunk;
+    const TYPE: CommandType = VerifyChunk;
+
+    struct Request<'wire> {
+        /// The handle returned by `BeginVerify`.
+        pub handle: u32,
+        /// The offset of this chunk within the image.
+        ///
+        /// Chunks must be sent in order; this is used to detect chunks that
+        /// were lost or repeated.
+        pub offset: u32,
+        /// The next chunk of the image.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub data: &'wire [u8],
+    }
+
+    fn Request::from_wire(r, arena) {
+        let handle = r.read_le::<u32>()?;
+        let offset = r.read_le::<u32>()?;
+        let data = r.read_slice::<u8>(r.remaining_data(), arena)?;
+        Ok(Self { handle, offset, data })
+    }
+
+    fn Request::to_wire(&self, w) {
+        w.write_le(self.handle)?;
+        w.write_le(self.offset)?;
+        w.write_all(self.data)?;
+        Ok(())
+    }
+
+    struct Response {}
+
+    fn Response::from_wire(_, _) {
+        Ok(Self {})
+    }
+
+    fn Response::to_wire(&self, _w) {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[
+                0x2a, 0x00, 0x00, 0x00,
+                0x00, 0x01, 0x00, 0x00,
+                b'f', b'w',
+            ],
+            json: r#"{
+                "handle": 42,
+                "offset": 256,
+                "data": "6677"
+            }"#,
+            value: VerifyChunkRequest {
+                handle: 42,
+                offset: 256,
+                data: b"fw",
+            },
+        },
+        response_round_trip: {
+            bytes: &[],
+            json: "{}",
+            value: VerifyChunkResponse {},
+        },
+    }
+}
@@ -0,0 +1,268 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Debugging aids for protocol messages.
+//!
+//! When a message fails to parse, a raw hexdump is of limited use. The
+//! functions in this module instead parse the message, recording every read
+//! the parser performs, and produce a hexdump grouped by those reads; each
+//! line roughly corresponds to one field. If parsing fails, the fields that
+//! were recognized are still annotated, followed by the bytes the parser did
+//! not get to and the error it produced.
+//!
+//! For example, a truncated `GetCert` request might be annotated as follows:
+//! ```text
+//! manticore::protocol::cerberus::get_cert::GetCertRequest (4 bytes)
+//!   0000: 00
+//!   0001: 00
+//!   0002: 00 01
+//! error: Io(BufferExhausted)
+//! ```
+//!
+//! Requires the `std` feature flag to be enabled.
+
+use core::alloc::Layout;
+use core::any::type_name;
+use core::fmt::Write as _;
+use core::ops::Range;
+
+use crate::io;
+use crate::io::Read;
+use crate::io::ReadZero;
+use crate::mem::Arena;
+use crate::mem::BumpArena;
+use crate::protocol::cerberus;
+use crate::protocol::wire;
+use crate::protocol::wire::FromWire;
+use crate::protocol::Command;
+use crate::protocol::Req;
+use crate::protocol::Resp;
+use crate::Result;
+
+/// The number of bytes to print per line of a long field.
+const BYTES_PER_LINE: usize = 16;
+
+/// Annotates `bytes` as the payload of a `C` request, if `is_request` is set,
+/// or of a `C` response otherwise.
+pub fn annotate<C>(is_request: bool, bytes: &[u8]) -> String
+where
+    C: for<'a> Command<'a>,
+{
+    let arena = scratch_arena(bytes);
+    let mut r = Recorder::new(bytes);
+    if is_request {
+        let result = Req::<C>::from_wire(&mut r, &arena).map(drop);
+        r.render(type_name::<Req<C>>(), result)
+    } else {
+        let result = Resp::<C>::from_wire(&mut r, &arena).map(drop);
+        r.render(type_name::<Resp<C>>(), result)
+    }
+}
+
+/// Annotates `bytes` as the payload of a Cerberus message with the given
+/// `command` type.
+///
+/// `is_request` selects between a command's request and response; it is
+/// ignored for [`cerberus::CommandType::Error`].
+pub fn annotate_cerberus(
+    command: cerberus::CommandType,
+    is_request: bool,
+    bytes: &[u8],
+) -> String {
+    use cerberus::CommandType as Ty;
+    match command {
+        Ty::Error => {
+            let arena = scratch_arena(bytes);
+            let mut r = Recorder::new(bytes);
+            let result = cerberus::Error::from_wire(&mut r, &arena).map(drop);
+            r.render(type_name::<cerberus::Error>(), result)
+        }
+        Ty::FirmwareVersion => {
+            annotate::<cerberus::FirmwareVersion>(is_request, bytes)
+        }
+        Ty::DeviceCapabilities => {
+            annotate::<cerberus::DeviceCapabilities>(is_request, bytes)
+        }
+        Ty::DeviceId => annotate::<cerberus::DeviceId>(is_request, bytes),
+        Ty::DeviceInfo => annotate::<cerberus::DeviceInfo>(is_request, bytes),
+        Ty::GetDigests => annotate::<cerberus::GetDigests>(is_request, bytes),
+        Ty::GetCert => annotate::<cerberus::GetCert>(is_request, bytes),
+        Ty::Challenge => annotate::<cerberus::Challenge>(is_request, bytes),
+        Ty::KeyExchange => annotate::<cerberus::KeyExchange>(is_request, bytes),
+        Ty::GetHostState => {
+            annotate::<cerberus::GetHostState>(is_request, bytes)
+        }
+        Ty::GetLog => annotate::<cerberus::GetLog>(is_request, bytes),
+        Ty::ClearLog => annotate::<cerberus::ClearLog>(is_request, bytes),
+        Ty::GetDeviceState => {
+            annotate::<cerberus::GetDeviceState>(is_request, bytes)
+        }
+        Ty::ResetConfig => annotate::<cerberus::ResetConfig>(is_request, bytes),
+        Ty::PreparePfm => annotate::<cerberus::PreparePfm>(is_request, bytes),
+        Ty::UpdatePfm => annotate::<cerberus::UpdatePfm>(is_request, bytes),
+        Ty::ActivatePfm => annotate::<cerberus::ActivatePfm>(is_request, bytes),
+        Ty::PrepareCfm => annotate::<cerberus::PrepareCfm>(is_request, bytes),
+        Ty::UpdateCfm => annotate::<cerberus::UpdateCfm>(is_request, bytes),
+        Ty::ActivateCfm => annotate::<cerberus::ActivateCfm>(is_request, bytes),
+        Ty::PreparePcd => annotate::<cerberus::PreparePcd>(is_request, bytes),
+        Ty::UpdatePcd => annotate::<cerberus::UpdatePcd>(is_request, bytes),
+        Ty::ActivatePcd => annotate::<cerberus::ActivatePcd>(is_request, bytes),
+        Ty::GetConfigIds => {
+            annotate::<cerberus::GetConfigIds>(is_request, bytes)
+        }
+        Ty::ResetCounter => {
+            annotate::<cerberus::ResetCounter>(is_request, bytes)
+        }
+        Ty::Unseal => annotate::<cerberus::Unseal>(is_request, bytes),
+        Ty::DeviceUptime => {
+            annotate::<cerberus::DeviceUptime>(is_request, bytes)
+        }
+        Ty::RequestCounter => {
+            annotate::<cerberus::RequestCounter>(is_request, bytes)
+        }
+        Ty::BeginVerify => annotate::<cerberus::BeginVerify>(is_request, bytes),
+        Ty::VerifyChunk => annotate::<cerberus::VerifyChunk>(is_request, bytes),
+        Ty::FinishVerify => {
+            annotate::<cerberus::FinishVerify>(is_request, bytes)
+        }
+        Ty::SetHostState => {
+            annotate::<cerberus::SetHostState>(is_request, bytes)
+        }
+        Ty::VendorCommand => {
+            annotate::<cerberus::VendorCommand>(is_request, bytes)
+        }
+    }
+}
+
+/// Creates an arena large enough to parse any reasonable message out of
+/// `bytes`.
+fn scratch_arena(bytes: &[u8]) -> BumpArena<Vec<u8>> {
+    BumpArena::new(vec![0; 4 * bytes.len() + 1024])
+}
+
+/// A reader over a byte slice that records the extent of every read.
+struct Recorder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    fields: Vec<Range<usize>>,
+}
+
+impl<'a> Recorder<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            pos: 0,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Records a read of `len` bytes at the current position.
+    fn advance(&mut self, len: usize) {
+        // Zero-length reads are not interesting.
+        if len > 0 {
+            self.fields.push(self.pos..self.pos + len);
+        }
+        self.pos += len;
+    }
+
+    /// Renders the recorded fields, for a message named `name`.
+    fn render(&self, name: &str, result: Result<(), wire::Error>) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "{} ({} bytes)", name, self.bytes.len());
+
+        let unparsed = self.pos..self.bytes.len();
+        for field in self.fields.iter().chain(Some(&unparsed)) {
+            let bytes = &self.bytes[field.clone()];
+            for (i, line) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+                let _ =
+                    write!(out, "  {:04x}:", field.start + i * BYTES_PER_LINE);
+                for b in line {
+                    let _ = write!(out, " {:02x}", b);
+                }
+                if field == &unparsed && i == 0 {
+                    let _ = write!(out, " <unparsed>");
+                }
+                out.push('\n');
+            }
+        }
+
+        if let Err(e) = result {
+            let _ = writeln!(out, "error: {:?}", e.into_inner());
+        }
+        out
+    }
+}
+
+impl Read for Recorder<'_> {
+    fn read_bytes(&mut self, out: &mut [u8]) -> Result<(), io::Error> {
+        let mut rest = &self.bytes[self.pos..];
+        rest.read_bytes(out)?;
+        self.advance(out.len());
+        Ok(())
+    }
+
+    fn remaining_data(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+}
+
+#[allow(unsafe_code)]
+unsafe impl<'a, 'b: 'a> ReadZero<'a> for Recorder<'b> {
+    fn read_direct(
+        &mut self,
+        arena: &'a dyn Arena,
+        layout: Layout,
+    ) -> Result<&'a [u8], io::Error> {
+        let mut rest = &self.bytes[self.pos..];
+        let out = rest.read_direct(arena, layout)?;
+        self.advance(out.len());
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn annotates_fields() {
+        let out = annotate_cerberus(
+            cerberus::CommandType::GetCert,
+            true,
+            &[0x00, 0x01, 0x02, 0x00, 0x04, 0x00],
+        );
+        let lines = out.lines().collect::<Vec<_>>();
+        assert!(lines[0].ends_with("GetCertRequest (6 bytes)"), "{}", out);
+        assert_eq!(
+            &lines[1..],
+            &["  0000: 00", "  0001: 01", "  0002: 02 00", "  0004: 04 00"],
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn partial() {
+        let out =
+            annotate_cerberus(cerberus::CommandType::GetCert, true, &[0, 0, 1]);
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines[1], "  0000: 00", "{}", out);
+        assert_eq!(lines[2], "  0001: 00", "{}", out);
+        assert_eq!(lines[3], "  0002: 01 <unparsed>", "{}", out);
+        assert!(lines[4].starts_with("error: "), "{}", out);
+    }
+
+    #[test]
+    fn long_field() {
+        let out = annotate_cerberus(
+            cerberus::CommandType::FirmwareVersion,
+            false,
+            &[0xab; 32],
+        );
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3, "{}", out);
+        assert!(lines[1].starts_with("  0000: ab ab"), "{}", out);
+        assert!(lines[2].starts_with("  0010: ab ab"), "{}", out);
+    }
+}
@@ -0,0 +1,449 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Golden wire-byte fixtures for every implemented message.
+//!
+//! Each fixture is hand-assembled from the relevant specification, and is
+//! written as hex so that it reads like a packet capture. Multi-byte fields
+//! use asymmetric values, so that a byte-order mistake in either direction
+//! shows up as a mismatch.
+//!
+//! The per-message `round_trip_test!`s cover JSON as well; these exist to pin
+//! down the wire format in one place.
+
+use core::time::Duration;
+
+use crate::io::Cursor;
+use crate::manifest::ManifestType;
+use crate::mem::BumpArena;
+use crate::protocol::cerberus;
+use crate::protocol::cerberus::capabilities;
+use crate::protocol::cerberus::capabilities::*;
+use crate::protocol::cerberus::*;
+use crate::protocol::spdm;
+use crate::protocol::testing::hex;
+use crate::protocol::wire::FromWire as _;
+use crate::protocol::wire::ToWire as _;
+use crate::protocol::Nonce;
+use crate::protocol::Req;
+use crate::protocol::Resp;
+use crate::protocol::Slot;
+
+/// Generates a test for each fixture, which checks that the value serializes
+/// to exactly the fixture's bytes, and that those bytes parse back into the
+/// value.
+///
+/// Fixtures for structs are written as `name: Type { fields } => [hex]`;
+/// anything else is written as `name: Type = value => [hex]`.
+macro_rules! golden {
+    () => {};
+    (@test $name:ident, $ty:ty, { $($value:tt)* }, [$($hex:literal),*]) => {
+        #[test]
+        fn $name() {
+            let fixture: &[&str] = &[$($hex),*];
+            let want = hex::decode(&fixture.join(" "));
+            let value: $ty = { $($value)* };
+
+            let mut buf = vec![0; 4096];
+            let mut cursor = Cursor::new(&mut buf);
+            value.to_wire(&mut cursor).expect("serialization failed");
+            pretty_assertions::assert_eq!(hex::encode(cursor.consumed_bytes()), hex::encode(&want));
+
+            let arena = BumpArena::new(vec![0; 4096]);
+            let mut r = want.as_slice();
+            let parsed = <$ty>::from_wire(&mut r, &arena).expect("parse failed");
+            assert!(r.is_empty(), "expected bytes to be fully read");
+            pretty_assertions::assert_eq!(parsed, value);
+        }
+    };
+    ($name:ident: $ty:path { $($body:tt)* } => [$($hex:literal),* $(,)?], $($rest:tt)*) => {
+        golden!(@test $name, $ty, { $ty { $($body)* } }, [$($hex),*]);
+        golden!($($rest)*);
+    };
+    ($name:ident: $ty:ty = $value:expr => [$($hex:literal),* $(,)?], $($rest:tt)*) => {
+        golden!(@test $name, $ty, { $value }, [$($hex),*]);
+        golden!($($rest)*);
+    };
+}
+
+/// Capabilities with asymmetric message and packet sizes.
+const CAPABILITIES: Capabilities = Capabilities {
+    networking: Networking {
+        max_message_size: 0x0234,
+        max_packet_size: 0x0180,
+        mode: RotMode::Platform,
+        roles: enumflags2::make_bitflags!(BusRole::{Host | Target}),
+        has_compression: false,
+        has_streaming: false,
+    },
+    security: enumflags2::make_bitflags!(Security::{HashAndKdf | Authentication}),
+    has_pfm_support: true,
+    has_policy_support: false,
+    has_firmware_protection: false,
+    crypto: Crypto {
+        has_ecdsa: false,
+        has_ecc: false,
+        has_rsa: true,
+        has_aes: false,
+        ecc_strength: enumflags2::make_bitflags!(EccKeyStrength::{}),
+        rsa_strength: enumflags2::make_bitflags!(RsaKeyStrength::{Bits2048}),
+        aes_strength: enumflags2::make_bitflags!(AesKeyStrength::{Bits128 | Bits256}),
+        hash_algos: HashAlgos::empty(),
+    },
+    protocol_version: ProtocolVersion::CURRENT,
+};
+
+golden! {
+    firmware_version_req: Req::<cerberus::FirmwareVersion> { index: 5 } => [
+        "05",
+    ],
+    firmware_version_resp: Resp::<cerberus::FirmwareVersion> { version: &[0x76; 32] } => [
+        "76767676767676767676767676767676",
+        "76767676767676767676767676767676",
+    ],
+    device_capabilities_req: Req::<cerberus::DeviceCapabilities> { capabilities: CAPABILITIES } => [
+        "3402 8001 73 80 81 03",
+    ],
+    device_capabilities_resp: Resp::<cerberus::DeviceCapabilities> {
+        capabilities: CAPABILITIES,
+        timeouts: capabilities::Timeouts {
+            regular: Duration::from_millis(120),
+            crypto: Duration::from_millis(1500),
+        },
+    } => [
+        "3402 8001 73 80 81 03",
+        "0c 0f",
+    ],
+    device_id_req: Req::<cerberus::DeviceId> {} => [],
+    device_id_resp: Resp::<cerberus::DeviceId> {
+        id: device_id::DeviceIdentifier {
+            vendor_id: 0x1234,
+            device_id: 0x5678,
+            subsys_vendor_id: 0x9abc,
+            subsys_id: 0xdef0,
+        },
+    } => [
+        "3412 7856 bc9a f0de",
+    ],
+    device_info_req: Req::<cerberus::DeviceInfo> {
+        index: device_info::InfoIndex::UniqueChipIndex,
+    } => [
+        "00",
+    ],
+    device_info_resp: Resp::<cerberus::DeviceInfo> { info: b"uid" } => [
+        "756964",
+    ],
+    device_uptime_req: Req::<cerberus::DeviceUptime> { port_id: 2 } => [
+        "02",
+    ],
+    device_uptime_resp: Resp::<cerberus::DeviceUptime> {
+        uptime: Duration::from_micros(0x0102_0304),
+    } => [
+        "04030201",
+    ],
+    get_digests_req: Req::<cerberus::GetDigests> {
+        slot: Slot::new(1).unwrap(),
+        key_exchange: get_digests::KeyExchangeAlgo::Ecdh,
+    } => [
+        "01 01",
+    ],
+    get_digests_resp: Resp::<cerberus::GetDigests> { digests: &[[0xaa; 32]] } => [
+        "01 01",
+        "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+        "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+    ],
+    get_cert_req: Req::<cerberus::GetCert> {
+        slot: Slot::new(1).unwrap(),
+        cert_number: 2,
+        offset: 0x0102,
+        len: 0x0304,
+    } => [
+        "01 02 0201 0403",
+    ],
+    get_cert_resp: Resp::<cerberus::GetCert> {
+        slot: Slot::new(1).unwrap(),
+        cert_number: 2,
+        data: b"der",
+    } => [
+        "01 02 646572",
+    ],
+    challenge_req: Req::<cerberus::Challenge> {
+        slot: Slot::new(0).unwrap(),
+        nonce: &Nonce([0x77; 32]),
+        want_timestamp: false,
+        key_type: None,
+    } => [
+        "00 00",
+        "77777777777777777777777777777777",
+        "77777777777777777777777777777777",
+    ],
+    challenge_resp: Resp::<cerberus::Challenge> {
+        tbs: challenge::ChallengeResponseTbs {
+            slot: Slot::new(0).unwrap(),
+            slot_mask: 0x01,
+            protocol_range: (1, 1),
+            nonce: &Nonce([0xdd; 32]),
+            pmr0_components: 1,
+            pmr0: b"pmr0",
+            timestamp: None,
+        },
+        signature: b"sig",
+    } => [
+        "00 01 01 01 0000",
+        "dddddddddddddddddddddddddddddddd",
+        "dddddddddddddddddddddddddddddddd",
+        "01 04 706d7230",
+        "736967",
+    ],
+    key_exchange_req: Req<cerberus::KeyExchange> = Req::<cerberus::KeyExchange>::PairedKeyHmac {
+        key_len: 0x0102,
+        key_hmac: b"hmac",
+    } => [
+        "01 0201 686d6163",
+    ],
+    key_exchange_resp: Resp<cerberus::KeyExchange> = Resp::<cerberus::KeyExchange>::SessionKey {
+        pk_resp: b"pk",
+        signature: b"sig",
+        alias_cert_hmac: b"mac",
+    } => [
+        "00 0200 706b 0300 736967 6d6163",
+    ],
+    get_host_state_req: Req::<cerberus::GetHostState> { port_id: 0 } => [
+        "00",
+    ],
+    get_host_state_resp: Resp::<cerberus::GetHostState> {
+        host_reset_state: get_host_state::HostResetState::HostInReset,
+    } => [
+        "01",
+    ],
+    get_log_req: Req::<cerberus::GetLog> {
+        log_type: get_log::LogType::Debug,
+        offset: 0x0102_0304,
+    } => [
+        "01 04030201",
+    ],
+    get_log_resp: Resp::<cerberus::GetLog> { data: b"log" } => [
+        "6c6f67",
+    ],
+    clear_log_req: Req::<cerberus::ClearLog> {
+        log_type: get_log::LogType::Tamper,
+        authorization: Some(reset_config::AuthToken {
+            nonce: &Nonce([0x11; 32]),
+            signature: b"sig",
+        }),
+    } => [
+        "03",
+        "11111111111111111111111111111111",
+        "11111111111111111111111111111111",
+        "736967",
+    ],
+    clear_log_resp: Resp::<cerberus::ClearLog> { nonce: &[] } => [],
+    get_device_state_req: Req::<cerberus::GetDeviceState> {} => [],
+    get_device_state_resp: Resp::<cerberus::GetDeviceState> {
+        state: get_device_state::State::Unsealed,
+    } => [
+        "03",
+    ],
+    reset_config_req: Req::<cerberus::ResetConfig> {
+        kind: reset_config::ResetKind::PlatformConfig,
+        authorization: Some(reset_config::AuthToken {
+            nonce: &Nonce([0x11; 32]),
+            signature: b"sig",
+        }),
+    } => [
+        "02",
+        "11111111111111111111111111111111",
+        "11111111111111111111111111111111",
+        "736967",
+    ],
+    reset_config_resp: Resp::<cerberus::ResetConfig> { nonce: &[] } => [],
+    prepare_pfm_req: Req::<cerberus::PreparePfm> {
+        port_id: 1,
+        total_len: 0x1234,
+    } => [
+        "01",
+        "34120000",
+    ],
+    prepare_pfm_resp: Resp::<cerberus::PreparePfm> {} => [],
+    update_pfm_req: Req::<cerberus::UpdatePfm> {
+        port_id: 1,
+        offset: 0x40,
+        data: b"pfm",
+    } => [
+        "01",
+        "40000000",
+        "70666d",
+    ],
+    update_pfm_resp: Resp::<cerberus::UpdatePfm> {} => [],
+    activate_pfm_req: Req::<cerberus::ActivatePfm> { port_id: 1 } => ["01"],
+    activate_pfm_resp: Resp::<cerberus::ActivatePfm> {} => [],
+    prepare_cfm_req: Req::<cerberus::PrepareCfm> {
+        total_len: 0x1234,
+    } => [
+        "34120000",
+    ],
+    prepare_cfm_resp: Resp::<cerberus::PrepareCfm> {} => [],
+    update_cfm_req: Req::<cerberus::UpdateCfm> {
+        offset: 0x40,
+        data: b"cfm",
+    } => [
+        "40000000",
+        "63666d",
+    ],
+    update_cfm_resp: Resp::<cerberus::UpdateCfm> {} => [],
+    activate_cfm_req: Req::<cerberus::ActivateCfm> {} => [],
+    activate_cfm_resp: Resp::<cerberus::ActivateCfm> {} => [],
+    prepare_pcd_req: Req::<cerberus::PreparePcd> {
+        total_len: 0x1234,
+    } => [
+        "34120000",
+    ],
+    prepare_pcd_resp: Resp::<cerberus::PreparePcd> {} => [],
+    update_pcd_req: Req::<cerberus::UpdatePcd> {
+        offset: 0x40,
+        data: b"pcd",
+    } => [
+        "40000000",
+        "706364",
+    ],
+    update_pcd_resp: Resp::<cerberus::UpdatePcd> {} => [],
+    activate_pcd_req: Req::<cerberus::ActivatePcd> {} => [],
+    activate_pcd_resp: Resp::<cerberus::ActivatePcd> {} => [],
+    get_config_ids_req: Req::<cerberus::GetConfigIds> {
+        manifest_type: ManifestType::Pfm,
+    } => [
+        "6d70",
+    ],
+    get_config_ids_resp: Resp::<cerberus::GetConfigIds> {
+        manifest_type: ManifestType::Cfm,
+        active: Some(get_config_ids::ConfigId {
+            version_id: 0x01020304,
+            digest: &[0x22; 32],
+        }),
+    } => [
+        "92a5 01 04030201",
+        "22222222222222222222222222222222",
+        "22222222222222222222222222222222",
+    ],
+    reset_counter_req: Req::<cerberus::ResetCounter> {
+        reset_type: reset_counter::ResetType::External,
+        port_id: 0,
+    } => [
+        "01 00",
+    ],
+    reset_counter_resp: Resp::<cerberus::ResetCounter> { count: 0x0102 } => [
+        "0201",
+    ],
+    request_counter_req: Req::<cerberus::RequestCounter> {} => [],
+    request_counter_resp: Resp::<cerberus::RequestCounter> {
+        ok_count: 0x0102,
+        err_count: 0x0304,
+    } => [
+        "0201 0403",
+    ],
+    unseal_req: Req::<cerberus::Unseal> {
+        policy: unseal::Policy {
+            pcr_mask: 0x0000_0102,
+            expected: &[[0x11; 32], [0x22; 32]],
+        },
+        blob: b"blob",
+    } => [
+        "02010000",
+        "1111111111111111111111111111111111111111111111111111111111111111",
+        "2222222222222222222222222222222222222222222222222222222222222222",
+        "626c6f62",
+    ],
+    unseal_resp: Resp::<cerberus::Unseal> { key: b"key" } => [
+        "6b6579",
+    ],
+    begin_verify_req: Req::<cerberus::BeginVerify> {
+        image_len: 0x0001_0203,
+        expected: &[0x11; 32],
+    } => [
+        "03020100",
+        "1111111111111111111111111111111111111111111111111111111111111111",
+    ],
+    begin_verify_resp: Resp::<cerberus::BeginVerify> { handle: 0x0102_0304 } => [
+        "04030201",
+    ],
+    verify_chunk_req: Req::<cerberus::VerifyChunk> {
+        handle: 0x0102_0304,
+        offset: 0x0506,
+        data: b"fw",
+    } => [
+        "04030201 06050000 6677",
+    ],
+    verify_chunk_resp: Resp::<cerberus::VerifyChunk> {} => [],
+    finish_verify_req: Req::<cerberus::FinishVerify> { handle: 0x0102_0304 } => [
+        "04030201",
+    ],
+    finish_verify_resp: Resp::<cerberus::FinishVerify> { verified: true } => [
+        "01",
+    ],
+    set_host_state_req: Req::<cerberus::SetHostState> {
+        port_id: 0,
+        hold_in_reset: true,
+        authorization: Some(reset_config::AuthToken {
+            nonce: &Nonce([0x22; 32]),
+            signature: b"sig",
+        }),
+    } => [
+        "00",
+        "01",
+        "22222222222222222222222222222222",
+        "22222222222222222222222222222222",
+        "736967",
+    ],
+    set_host_state_resp: Resp::<cerberus::SetHostState> { nonce: &[] } => [],
+    vendor_command_req: Req::<cerberus::VendorCommand> {
+        vendor_id: 0x1234,
+        subcommand: 5,
+        payload: b"hi",
+    } => [
+        "3412 05 6869",
+    ],
+    vendor_command_resp: Resp::<cerberus::VendorCommand> {
+        vendor_id: 0x1234,
+        subcommand: 5,
+        payload: b"hi",
+    } => [
+        "3412 05 6869",
+    ],
+    cerberus_busy: cerberus::Error = cerberus::Error::Busy => [
+        "03 00000000",
+    ],
+    cerberus_unspecified: cerberus::Error = cerberus::Error::Unspecified([1, 2, 3, 4]) => [
+        "04 01020304",
+    ],
+    get_version_req: Req::<spdm::GetVersion> {} => [
+        "0000",
+    ],
+    get_version_resp: Resp::<spdm::GetVersion> {
+        versions: &[spdm::ExtendedVersion::MANTICORE],
+    } => [
+        "0000 00 01 0012",
+    ],
+    get_caps_req: Req::<spdm::GetCaps> {
+        crypto_timeout: Duration::from_micros(1 << 12),
+        caps: spdm::get_caps::Caps::manticore(),
+        max_packet_size: 0x0234,
+        max_message_size: 0x0102_0304,
+    } => [
+        "000000 0c 0000",
+        "76220400",
+        "34020000",
+        "04030201",
+    ],
+    get_caps_resp: Resp::<spdm::GetCaps> {
+        crypto_timeout: Duration::from_micros(1 << 12),
+        caps: spdm::get_caps::Caps::manticore(),
+        max_packet_size: 0x0234,
+        max_message_size: 0x0102_0304,
+    } => [
+        "000000 0c 0000",
+        "76220400",
+        "34020000",
+        "04030201",
+    ],
+}
@@ -0,0 +1,88 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Cerberus message integrity checks.
+//!
+//! When a Cerberus message is sent over MCTP, the most significant bit of its
+//! message-type byte, the [`IC_BIT`], indicates that the message carries an
+//! integrity check. The check is a [`TAG_LEN`]-byte tag appended to the end
+//! of the message, computed over the payload that precedes it.
+//!
+//! Cerberus uses the same CRC-8 as SMBus packet error checking: polynomial
+//! `x^8 + x^2 + x + 1`, with a zero initial value and no reflection.
+//!
+//! Both clients and servers should go through [`compute()`] and [`verify()`],
+//! so that the two ends agree on the algorithm.
+
+/// The bit of an MCTP message-type byte that indicates that the message
+/// carries an integrity check.
+pub const IC_BIT: u8 = 0x80;
+
+/// The length of an integrity-check tag, in bytes.
+pub const TAG_LEN: usize = 1;
+
+/// The CRC-8 generator polynomial, minus its leading term.
+const POLY: u8 = 0x07;
+
+/// Computes the integrity-check tag for `bytes`.
+pub fn compute(bytes: &[u8]) -> [u8; TAG_LEN] {
+    let mut crc = 0u8;
+    for &byte in bytes {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ POLY
+            } else {
+                crc << 1
+            };
+        }
+    }
+    [crc]
+}
+
+/// Checks that `tag` is the integrity-check tag for `bytes`.
+pub fn verify(bytes: &[u8], tag: &[u8; TAG_LEN]) -> bool {
+    compute(bytes) == *tag
+}
+
+/// Splits the trailing integrity-check tag off of `message`.
+///
+/// Returns `None` if `message` is too short to carry a tag.
+pub fn split_tag(message: &[u8]) -> Option<(&[u8], [u8; TAG_LEN])> {
+    let payload_len = message.len().checked_sub(TAG_LEN)?;
+    let (payload, tag) = message.split_at(payload_len);
+    let mut out = [0; TAG_LEN];
+    out.copy_from_slice(tag);
+    Some((payload, out))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_value() {
+        // The standard CRC-8/SMBUS check value.
+        assert_eq!(compute(b"123456789"), [0xf4]);
+        assert_eq!(compute(&[]), [0x00]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let payload = [0x7e, 0x14, 0x01, 0x00, 0xff, 0x42];
+        let tag = compute(&payload);
+        assert!(verify(&payload, &tag));
+
+        let mut message = payload.to_vec();
+        message.extend_from_slice(&tag);
+        let (body, split) = split_tag(&message).unwrap();
+        assert_eq!(body, &payload);
+        assert!(verify(body, &split));
+
+        let mut corrupted = payload;
+        corrupted[3] ^= 0x10;
+        assert!(!verify(&corrupted, &tag));
+        assert!(split_tag(&[]).is_none());
+    }
+}
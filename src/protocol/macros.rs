@@ -37,9 +37,11 @@ macro_rules! round_trip_test {
 
             let arena = $crate::mem::BumpArena::new(vec![0u8; 4096]);
 
-            let mut bytes_reader: &[u8] = $bytes;
+            let bytes_reader: &[u8] = $bytes;
+            let mut bytes_reader = $crate::io::CheckedRead::new(bytes_reader);
             let from_wire = $ty::from_wire(&mut bytes_reader, &arena).unwrap();
 
+            let bytes_reader = bytes_reader.into_inner();
             assert!(bytes_reader.is_empty(), "expected bytes to be fully read");
             pretty_assertions::assert_eq!(from_wire, $ty$(::$variant)? $({ $($field: $field_val,)* })?);
         }
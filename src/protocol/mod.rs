@@ -53,8 +53,27 @@ pub mod template;
 pub mod wire;
 
 pub mod cerberus;
+pub mod integrity;
 pub mod spdm;
 
+mod nonce;
+pub use nonce::Nonce;
+pub use nonce::NONCE_LEN;
+
+mod slot;
+pub use slot::InvalidSlot;
+pub use slot::Slot;
+pub use slot::SLOTS;
+
+#[cfg(feature = "std")]
+pub mod debug;
+
+#[cfg(feature = "std")]
+pub mod testing;
+
+#[cfg(test)]
+mod golden;
+
 /// A Manticore command.
 ///
 /// A Manticore command is identified by two types, each of which has a
@@ -0,0 +1,152 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Challenge nonces.
+
+use zerocopy::AsBytes;
+use zerocopy::FromBytes;
+
+use crate::crypto::csrng;
+use crate::crypto::csrng::Csrng;
+use crate::io::ReadZero;
+use crate::io::Write;
+use crate::mem::Arena;
+use crate::protocol::wire;
+use crate::protocol::wire::FromWire;
+use crate::protocol::wire::ToWire;
+use crate::Result;
+
+#[cfg(feature = "arbitrary-derive")]
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+
+/// The length of a [`Nonce`].
+///
+/// This is the length of nonces used throughout Cerberus.
+pub const NONCE_LEN: usize = 32;
+
+/// A random nonce, such as the ones exchanged by `Challenge`.
+///
+/// Comparing two `Nonce`s takes the same amount of time no matter where they
+/// differ, so anti-replay checks do not leak how close a guess was.
+#[derive(Copy, Clone, Eq, Debug, FromBytes, AsBytes)]
+#[repr(transparent)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
+#[cfg_attr(all(feature = "serde", feature = "std"), derive(serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary-derive", derive(Arbitrary))]
+pub struct Nonce(
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serde::se_hexstring")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "std"),
+        serde(deserialize_with = "crate::serde::de_hexstring")
+    )]
+    pub [u8; NONCE_LEN],
+);
+derive_borrowed!(Nonce);
+
+impl Nonce {
+    /// Draws a fresh nonce from `rng`, after checking that it is healthy.
+    pub fn random(
+        rng: &mut (impl Csrng + ?Sized),
+    ) -> Result<Self, csrng::Error> {
+        rng.health_check()?;
+        let mut nonce = Self([0; NONCE_LEN]);
+        rng.fill(&mut nonce.0)?;
+        Ok(nonce)
+    }
+}
+
+impl PartialEq for Nonce {
+    fn eq(&self, other: &Self) -> bool {
+        self.0
+            .iter()
+            .zip(&other.0)
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+    }
+}
+
+impl AsRef<[u8]> for Nonce {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<'wire> FromWire<'wire> for Nonce {
+    fn from_wire<R: ReadZero<'wire> + ?Sized>(
+        r: &mut R,
+        _: &'wire dyn Arena,
+    ) -> Result<Self, wire::Error> {
+        let mut nonce = Self([0; NONCE_LEN]);
+        r.read_bytes(&mut nonce.0)?;
+        Ok(nonce)
+    }
+}
+
+impl ToWire for Nonce {
+    fn to_wire<W: Write>(&self, mut w: W) -> Result<(), wire::Error> {
+        w.write_all(&self.0)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A `Csrng` that fills buffers with a fixed byte.
+    struct Fixed(u8, bool);
+
+    impl Csrng for Fixed {
+        fn fill(&mut self, buf: &mut [u8]) -> Result<(), csrng::Error> {
+            for b in buf {
+                *b = self.0;
+            }
+            Ok(())
+        }
+
+        fn health_check(&mut self) -> Result<(), csrng::Error> {
+            check!(self.1, csrng::Error::Unhealthy);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn random() {
+        let nonce = Nonce::random(&mut Fixed(0x42, true)).unwrap();
+        assert_eq!(nonce, Nonce([0x42; NONCE_LEN]));
+
+        let err = Nonce::random(&mut Fixed(0x42, false)).unwrap_err();
+        assert_eq!(err.into_inner(), csrng::Error::Unhealthy);
+    }
+
+    #[test]
+    fn equality() {
+        let mut nonce = Nonce([0x11; NONCE_LEN]);
+        assert_eq!(nonce, Nonce([0x11; NONCE_LEN]));
+        nonce.0[NONCE_LEN - 1] ^= 1;
+        assert_ne!(nonce, Nonce([0x11; NONCE_LEN]));
+        nonce.0[0] ^= 1;
+        assert_ne!(nonce, Nonce([0x11; NONCE_LEN]));
+    }
+
+    #[test]
+    fn wire() {
+        let arena = crate::mem::OutOfMemory;
+        let bytes = [0x77; NONCE_LEN + 1];
+        let mut r = &bytes[..];
+        let nonce = Nonce::from_wire(&mut r, &arena).unwrap();
+        assert_eq!(nonce, Nonce([0x77; NONCE_LEN]));
+        assert_eq!(r.len(), 1);
+
+        let mut r = &bytes[..NONCE_LEN - 1];
+        assert!(Nonce::from_wire(&mut r, &arena).is_err());
+
+        let mut out = [0; NONCE_LEN];
+        nonce.to_wire(&mut out[..]).unwrap();
+        assert_eq!(out, [0x77; NONCE_LEN]);
+    }
+}
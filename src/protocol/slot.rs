@@ -0,0 +1,131 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Certificate chain slot numbers.
+
+use core::convert::TryFrom;
+use core::fmt;
+use core::ops::RangeInclusive;
+
+use crate::io::ReadInt as _;
+use crate::io::ReadZero;
+use crate::io::Write;
+use crate::mem::Arena;
+use crate::protocol::wire;
+use crate::protocol::wire::FromWire;
+use crate::protocol::wire::ToWire;
+use crate::Result;
+
+/// The range of valid certificate chain slot numbers.
+pub const SLOTS: RangeInclusive<u8> = 0..=7;
+
+/// A certificate chain slot number, which is always within [`SLOTS`].
+///
+/// Messages that carry a slot number reject values outside of that range when
+/// parsed, so a `Slot` taken from a message can be used without further
+/// checks.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "u8", into = "u8")
+)]
+pub struct Slot(u8);
+derive_borrowed!(Slot);
+
+impl Slot {
+    /// The first valid slot.
+    pub const FIRST: Self = Self(*SLOTS.start());
+
+    /// Creates a new `Slot`, if `slot` is within [`SLOTS`].
+    pub const fn new(slot: u8) -> Option<Self> {
+        if slot < *SLOTS.start() || slot > *SLOTS.end() {
+            return None;
+        }
+        Some(Self(slot))
+    }
+
+    /// Returns the slot number.
+    pub const fn get(self) -> u8 {
+        self.0
+    }
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// An error returned when converting a `u8` outside of [`SLOTS`] into a
+/// [`Slot`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct InvalidSlot(pub u8);
+
+impl fmt::Display for InvalidSlot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "slot {} is out of range {:?}", self.0, SLOTS)
+    }
+}
+
+impl TryFrom<u8> for Slot {
+    type Error = InvalidSlot;
+    fn try_from(slot: u8) -> core::result::Result<Self, InvalidSlot> {
+        Self::new(slot).ok_or(InvalidSlot(slot))
+    }
+}
+
+impl From<Slot> for u8 {
+    fn from(slot: Slot) -> u8 {
+        slot.0
+    }
+}
+
+impl<'wire> FromWire<'wire> for Slot {
+    fn from_wire<R: ReadZero<'wire> + ?Sized>(
+        r: &mut R,
+        _: &'wire dyn Arena,
+    ) -> Result<Self, wire::Error> {
+        let slot = wire::in_range("slot", r.read_le::<u8>()?, SLOTS)?;
+        Ok(Self(slot))
+    }
+}
+
+impl ToWire for Slot {
+    fn to_wire<W: Write>(&self, mut w: W) -> Result<(), wire::Error> {
+        w.write_le(self.0)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "arbitrary-derive")]
+impl libfuzzer_sys::arbitrary::Arbitrary for Slot {
+    fn arbitrary(
+        u: &mut libfuzzer_sys::arbitrary::Unstructured,
+    ) -> libfuzzer_sys::arbitrary::Result<Self> {
+        Ok(Self(u.int_in_range(SLOTS)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checked() {
+        assert_eq!(Slot::new(7).map(Slot::get), Some(7));
+        assert_eq!(Slot::new(8), None);
+        assert_eq!(Slot::try_from(9), Err(InvalidSlot(9)));
+
+        let arena = crate::mem::OutOfMemory;
+        let mut bytes: &[u8] = &[0x03, 0x08];
+        assert_eq!(Slot::from_wire(&mut bytes, &arena), Ok(Slot(3)));
+        assert_eq!(
+            Slot::from_wire(&mut bytes, &arena)
+                .unwrap_err()
+                .into_inner(),
+            wire::Error::Validation("slot")
+        );
+    }
+}
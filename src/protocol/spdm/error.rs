@@ -5,12 +5,14 @@
 //! Error definitions for SPDM messages.
 
 use crate::crypto;
+use crate::io;
 use crate::io::read::ReadZeroExt as _;
 use crate::io::ReadInt as _;
 use crate::io::ReadZero;
 use crate::io::Write;
 use crate::mem::Arena;
 use crate::mem::OutOfMemory;
+use crate::mem::PoolExhausted;
 use crate::protocol::spdm::CommandType;
 use crate::protocol::wire;
 use crate::protocol::wire::FromWire;
@@ -54,7 +56,7 @@ impl ToWire for RawError<'_> {
     fn to_wire<W: Write>(&self, mut w: W) -> Result<(), wire::Error> {
         w.write_le(self.code)?;
         w.write_le(self.data)?;
-        w.write_bytes(self.extra)?;
+        w.write_all(self.extra)?;
         Ok(())
     }
 }
@@ -244,6 +246,21 @@ impl From<OutOfMemory> for Error<'_> {
     }
 }
 
+impl From<io::Error> for Error<'_> {
+    fn from(e: io::Error) -> Self {
+        match e {
+            io::Error::BufferExhausted => Self::ResponseTooLarge,
+            io::Error::Internal => Self::Unspecified,
+        }
+    }
+}
+
+impl From<PoolExhausted> for Error<'_> {
+    fn from(_: PoolExhausted) -> Self {
+        Self::Busy
+    }
+}
+
 impl From<crypto::csrng::Error> for Error<'_> {
     fn from(_: crypto::csrng::Error) -> Self {
         Self::Unspecified
@@ -268,4 +285,4 @@ impl From<session::Error> for Error<'_> {
     }
 }
 
-debug_from!(Error<'wire> => OutOfMemory, crypto::csrng::Error, crypto::hash::Error, crypto::sig::Error, session::Error);
+debug_from!(Error<'wire> => OutOfMemory, io::Error, PoolExhausted, crypto::csrng::Error, crypto::hash::Error, crypto::sig::Error, session::Error);
@@ -218,6 +218,33 @@ impl Caps {
             | Self::Heartbeat
             | Self::AliasCert
     }
+
+    /// Returns the capabilities a SPDM server has, given that it supports
+    /// exactly the commands for which `handles` returns true.
+    ///
+    /// Servers should advertise these rather than a fixed set, so that
+    /// advertised and actual capabilities cannot drift apart.
+    pub fn for_commands(
+        handles: impl Fn(CommandType) -> bool,
+    ) -> enumflags2::BitFlags<Self> {
+        let mut caps = BitFlags::empty();
+        if handles(CommandType::GetDigests) && handles(CommandType::GetCert) {
+            caps |= Self::Certs | Self::AliasCert;
+        }
+        if handles(CommandType::Challenge) {
+            caps |= Self::Challenge;
+        }
+        if handles(CommandType::GetMeasurements) {
+            caps |= Self::SignedMeasurements | Self::FreshMeasurements;
+        }
+        if handles(CommandType::KeyExchange) && handles(CommandType::Finish) {
+            caps |= Self::KeyExchange | Self::SessionEncryption;
+        }
+        if handles(CommandType::Heartbeat) {
+            caps |= Self::Heartbeat;
+        }
+        caps
+    }
 }
 
 #[cfg(test)]
@@ -286,4 +313,14 @@ mod test {
             },
         },
     }
+
+    #[test]
+    fn caps_for_commands() {
+        assert_eq!(Caps::for_commands(|_| true), Caps::manticore());
+        assert_eq!(Caps::for_commands(|_| false), BitFlags::empty());
+        assert_eq!(
+            Caps::for_commands(|c| c == CommandType::Challenge),
+            Caps::Challenge
+        );
+    }
 }
@@ -49,7 +49,7 @@ protocol_struct! {
     fn Response::to_wire(&self, w) {
         spdm::write_zeros(&mut w, 3)?;
         w.write_le::<u8>(self.versions.len().try_into().map_err(|_| wire::Error::OutOfRange)?)?;
-        w.write_bytes(self.versions.as_bytes())?;
+        w.write_all(self.versions.as_bytes())?;
         Ok(())
     }
 }
@@ -0,0 +1,73 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Hex encoding for test fixtures.
+//!
+//! Messages are much easier to check against a specification when written
+//! as hex, grouped by field, than as arrays of byte literals:
+//! ```
+//! # use manticore::protocol::testing::hex;
+//! let bytes = hex::decode("01 0302 ff");
+//! assert_eq!(bytes, [0x01, 0x03, 0x02, 0xff]);
+//! assert_eq!(hex::encode(&bytes), "010302ff");
+//! ```
+//!
+//! These functions are intended for tests, and panic on malformed input
+//! rather than returning an error.
+
+/// Decodes `hex` into bytes, ignoring any whitespace.
+///
+/// # Panics
+///
+/// Panics if `hex` contains anything other than hex digits and whitespace,
+/// or an odd number of hex digits.
+pub fn decode(hex: &str) -> Vec<u8> {
+    let digits = hex
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c.to_digit(16) {
+            Some(d) => d as u8,
+            None => panic!("non-hex character {:?} in {:?}", c, hex),
+        })
+        .collect::<Vec<_>>();
+    assert!(
+        digits.len() % 2 == 0,
+        "odd number of hex digits in {:?}",
+        hex
+    );
+    digits
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair[1])
+        .collect()
+}
+
+/// Encodes `bytes` as lowercase hex, with no separators.
+pub fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let bytes = decode(" 00 01\n\tAbCd ff ");
+        assert_eq!(bytes, [0x00, 0x01, 0xab, 0xcd, 0xff]);
+        assert_eq!(encode(&bytes), "0001abcdff");
+        assert!(decode("").is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "odd number of hex digits")]
+    fn odd_length() {
+        decode("01 2");
+    }
+
+    #[test]
+    #[should_panic(expected = "non-hex character")]
+    fn non_hex() {
+        decode("0x12");
+    }
+}
@@ -0,0 +1,9 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers for writing protocol tests.
+//!
+//! Requires the `std` feature flag to be enabled.
+
+pub mod hex;
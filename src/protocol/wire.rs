@@ -11,15 +11,24 @@
 
 use core::fmt;
 use core::hash::Hash;
+use core::ops::RangeBounds;
 
 use crate::io;
+use crate::io::endian::BeInt;
 use crate::io::endian::LeInt;
+use crate::io::read::ReadZeroExt as _;
 use crate::io::ReadZero;
 use crate::io::Write;
 use crate::mem::Arena;
 use crate::mem::OutOfMemory;
 use crate::Result;
 
+use zerocopy::AsBytes;
+use zerocopy::FromBytes;
+
+#[cfg(feature = "arbitrary-derive")]
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+
 /// A type which can be deserialized from the Cerberus wire format.
 ///
 /// The lifetime `'wire` indicates that the type can be deserialized from a
@@ -45,6 +54,10 @@ pub enum Error {
     /// Indicates that some field within the request was outside of its
     /// valid range.
     OutOfRange,
+
+    /// Indicates that a field failed validation, such as by [`in_range()`]
+    /// or [`one_of()`]; contains the name of the offending field.
+    Validation(&'static str),
 }
 
 impl From<io::Error> for Error {
@@ -61,10 +74,179 @@ impl From<OutOfMemory> for Error {
 
 debug_from!(Error => io::Error, OutOfMemory);
 
+/// Checks that `value`, parsed from the field named `field`, lies within
+/// `range`.
+///
+/// This function is intended to be used within [`FromWire`] implementations,
+/// so that invalid values are rejected at parse time, rather than by every
+/// consumer of the parsed message.
+///
+/// ```
+/// # use manticore::protocol::wire;
+/// assert!(wire::in_range("slot", 3u8, 0..=7).is_ok());
+/// assert!(wire::in_range("slot", 8u8, 0..=7).is_err());
+/// ```
+pub fn in_range<T: PartialOrd>(
+    field: &'static str,
+    value: T,
+    range: impl RangeBounds<T>,
+) -> Result<T, Error> {
+    check!(range.contains(&value), Error::Validation(field));
+    Ok(value)
+}
+
+/// Checks that `value`, parsed from the field named `field`, is one of
+/// `allowed`.
+///
+/// See [`in_range()`].
+pub fn one_of<T: PartialEq>(
+    field: &'static str,
+    value: T,
+    allowed: &[T],
+) -> Result<T, Error> {
+    check!(allowed.contains(&value), Error::Validation(field));
+    Ok(value)
+}
+
+/// Reads a `T` out of `r` if `present` is set, or nothing at all if it is not.
+///
+/// This is intended for fields whose presence is announced by an earlier
+/// part of the message, such as a flag bit:
+///
+/// ```
+/// # use manticore::protocol::wire;
+/// # use manticore::io::ReadInt as _;
+/// # use manticore::mem::OutOfMemory;
+/// let mut r: &[u8] = &[0b1, 0x42, 0x01];
+/// let flags = r.read_le::<u8>().unwrap();
+/// let field =
+///     wire::read_if::<wire::Le<u16>, _>(flags & 1 != 0, &mut r, &OutOfMemory);
+/// assert_eq!(field.unwrap(), Some(wire::Le(0x0142)));
+/// ```
+pub fn read_if<'wire, T, R>(
+    present: bool,
+    r: &mut R,
+    arena: &'wire dyn Arena,
+) -> Result<Option<T>, Error>
+where
+    T: FromWire<'wire>,
+    R: ReadZero<'wire> + ?Sized,
+{
+    if !present {
+        return Ok(None);
+    }
+    T::from_wire(r, arena).map(Some)
+}
+
+/// Writes `value`, the field named `field`, to `w` if it is `Some`.
+///
+/// `present` is whatever announces the field to readers, such as a flag bit
+/// written earlier; see [`read_if()`]. If it disagrees with `value`, the
+/// message could not be parsed back correctly, so this returns
+/// [`Error::Validation`] without writing anything.
+pub fn write_if<T: ToWire, W: Write>(
+    field: &'static str,
+    present: bool,
+    value: Option<&T>,
+    w: W,
+) -> Result<(), Error> {
+    check!(present == value.is_some(), Error::Validation(field));
+    match value {
+        Some(value) => value.to_wire(w),
+        None => Ok(()),
+    }
+}
+
+/// Reads a fixed-layout `T` out of `r`, without copying if possible.
+///
+/// The returned reference is always suitably aligned for `T`: if the bytes in
+/// `r` are not, they are copied into an allocation from `arena` with
+/// `T`'s alignment. This allows [`FromWire`] implementations for fixed-layout
+/// messages to alias a `#[repr(C)]` struct directly over the wire bytes.
+///
+/// The [`FromBytes`] and [`AsBytes`] bounds guarantee that `T` is plain old
+/// data, for which any bit pattern is valid.
+///
+/// # Endianness
+///
+/// The fields of `T` are interpreted in the host's native byte order, whereas
+/// Cerberus is little-endian. This function should only be used with types
+/// whose fields are byte-order-agnostic, such as byte arrays or `u8`s, or
+/// that explicitly convert their fields, such as with `u32::from_le()`.
+///
+/// ```
+/// # use manticore::protocol::wire;
+/// # use manticore::mem::BumpArena;
+/// #[derive(Copy, Clone, zerocopy::FromBytes, zerocopy::AsBytes)]
+/// #[repr(C)]
+/// struct Header {
+///     magic: [u8; 2],
+///     version: u8,
+///     flags: u8,
+/// }
+///
+/// let arena = BumpArena::new([0; 8]);
+/// let header: &Header =
+///     wire::read_struct(&mut &[0xce, 0x7b, 1, 0][..], &arena).unwrap();
+/// assert_eq!(header.magic, [0xce, 0x7b]);
+/// assert_eq!(header.version, 1);
+/// ```
+pub fn read_struct<'a, T, R>(
+    r: &mut R,
+    arena: &'a dyn Arena,
+) -> Result<&'a T, Error>
+where
+    T: FromBytes + AsBytes + Copy,
+    R: ReadZero<'a> + ?Sized,
+{
+    Ok(r.read_object::<T>(arena)?)
+}
+
+/// Reads all of the data remaining in `r`, for use as the final field of a
+/// message whose length is implied by the end of the message, rather than by
+/// a count field.
+///
+/// The bytes are aliased rather than copied if possible. If nothing remains,
+/// this returns an empty slice without touching `arena`.
+///
+/// ```
+/// # use manticore::protocol::wire;
+/// # use manticore::mem::BumpArena;
+/// let arena = BumpArena::new([0; 8]);
+/// let mut r: &[u8] = &[0x01, 0x02, 0x03];
+/// assert_eq!(wire::read_to_end(&mut r, &arena).unwrap(), &[1, 2, 3]);
+/// assert!(wire::read_to_end(&mut r, &arena).unwrap().is_empty());
+/// ```
+pub fn read_to_end<'a, R>(
+    r: &mut R,
+    arena: &'a dyn Arena,
+) -> Result<&'a [u8], Error>
+where
+    R: ReadZero<'a> + ?Sized,
+{
+    let len = r.remaining_data();
+    if len == 0 {
+        return Ok(&[]);
+    }
+    Ok(r.read_slice::<u8>(len, arena)?)
+}
+
 /// A type which can be serialized into the Cerberus wire format.
 pub trait ToWire: Sized {
     /// Serializes `self` into `w`.
     fn to_wire<W: Write>(&self, w: W) -> Result<(), Error>;
+
+    /// Returns the number of bytes that [`ToWire::to_wire()`] would write,
+    /// without writing them anywhere.
+    ///
+    /// This is useful for sizing a buffer exactly before serializing into it,
+    /// or for sending a length prefix ahead of a message that is written out
+    /// as it is serialized.
+    fn wire_len(&self) -> Result<usize, Error> {
+        let mut len = io::write::Counter::default();
+        self.to_wire(&mut len)?;
+        Ok(len.0)
+    }
 }
 
 /// Represents a C-like enum that can be converted to and from a wire
@@ -136,6 +318,146 @@ where
     }
 }
 
+/// A [`WireEnum`] wrapper that accepts values `E` does not recognize.
+///
+/// A field of type `E` rejects unknown wire values with
+/// [`Error::OutOfRange`]. For fields that the Cerberus specification may
+/// extend in the future, this is often too strict: a newer peer may send a
+/// value that is currently reserved. Declaring such a field as `Lenient<E>`
+/// instead preserves unknown values as [`Lenient::Reserved`], so that they can
+/// be ignored (and serialized back verbatim) rather than failing the whole
+/// message.
+///
+/// Whether to be lenient is decided per field, by choosing the field's type.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Lenient<E: WireEnum>
+where
+    E::Wire: Copy + Eq + fmt::Debug,
+{
+    /// A value that `E` recognizes.
+    Known(E),
+    /// A value that `E` does not recognize, such as one from a range reserved
+    /// for future use.
+    Reserved(E::Wire),
+}
+
+impl<E: WireEnum> Lenient<E>
+where
+    E::Wire: Copy + Eq + fmt::Debug,
+{
+    /// Returns the recognized value, if there is one.
+    pub fn known(self) -> Option<E> {
+        match self {
+            Self::Known(e) => Some(e),
+            Self::Reserved(_) => None,
+        }
+    }
+}
+
+impl<E: WireEnum> From<E> for Lenient<E>
+where
+    E::Wire: Copy + Eq + fmt::Debug,
+{
+    fn from(e: E) -> Self {
+        Self::Known(e)
+    }
+}
+
+impl<E: WireEnum> WireEnum for Lenient<E>
+where
+    E::Wire: Copy + Eq + fmt::Debug,
+{
+    type Wire = E::Wire;
+
+    fn to_wire_value(self) -> Self::Wire {
+        match self {
+            Self::Known(e) => e.to_wire_value(),
+            Self::Reserved(wire) => wire,
+        }
+    }
+
+    fn from_wire_value(wire: Self::Wire) -> Option<Self> {
+        Some(match E::from_wire_value(wire) {
+            Some(e) => Self::Known(e),
+            None => Self::Reserved(wire),
+        })
+    }
+}
+
+/// An integer that is encoded little-endian on the wire.
+///
+/// Cerberus payloads are little-endian, and plain integer fields are always
+/// read as such. Wrapping a field in `Le` makes that explicit in the message
+/// definition, which is useful next to fields of the opposite order; see
+/// [`Be`].
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+#[cfg_attr(feature = "arbitrary-derive", derive(Arbitrary))]
+pub struct Le<I>(pub I);
+
+impl<I> From<I> for Le<I> {
+    fn from(i: I) -> Self {
+        Self(i)
+    }
+}
+
+impl<'wire, I: LeInt> FromWire<'wire> for Le<I> {
+    fn from_wire<R: ReadZero<'wire> + ?Sized>(
+        r: &mut R,
+        _: &'wire dyn Arena,
+    ) -> Result<Self, Error> {
+        Ok(Self(I::read_from(r)?))
+    }
+}
+
+impl<I: LeInt> ToWire for Le<I> {
+    fn to_wire<W: Write>(&self, mut w: W) -> Result<(), Error> {
+        self.0.write_to(&mut w)?;
+        Ok(())
+    }
+}
+
+/// An integer that is encoded big-endian (i.e., in network order) on the
+/// wire.
+///
+/// Transport headers, such as MCTP's, are big-endian, unlike the Cerberus
+/// payloads they carry. Declaring such a field as `Be<u16>` rather than `u16`
+/// makes it impossible to accidentally read it in the wrong order.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+#[cfg_attr(feature = "arbitrary-derive", derive(Arbitrary))]
+pub struct Be<I>(pub I);
+
+impl<I> From<I> for Be<I> {
+    fn from(i: I) -> Self {
+        Self(i)
+    }
+}
+
+impl<'wire, I: BeInt> FromWire<'wire> for Be<I> {
+    fn from_wire<R: ReadZero<'wire> + ?Sized>(
+        r: &mut R,
+        _: &'wire dyn Arena,
+    ) -> Result<Self, Error> {
+        Ok(Self(I::read_from(r)?))
+    }
+}
+
+impl<I: BeInt> ToWire for Be<I> {
+    fn to_wire<W: Write>(&self, mut w: W) -> Result<(), Error> {
+        self.0.write_to(&mut w)?;
+        Ok(())
+    }
+}
+
 /// A deserialization-from-string error.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub struct WireEnumFromStrError;
@@ -256,4 +578,191 @@ mod test {
         assert_eq!(DemoEnum::First.to_string(), "First");
         assert_eq!(DemoEnum::Second.to_string(), "Second");
     }
+
+    #[test]
+    fn validators() {
+        use super::*;
+
+        assert_eq!(in_range("x", 5, 0..=7).unwrap(), 5);
+        assert_eq!(in_range("x", 7, ..8).unwrap(), 7);
+        assert_eq!(
+            in_range("x", 8, 0..=7).unwrap_err().into_inner(),
+            Error::Validation("x")
+        );
+
+        assert_eq!(one_of("y", 2, &[1, 2, 4]).unwrap(), 2);
+        assert_eq!(
+            one_of("y", 3, &[1, 2, 4]).unwrap_err().into_inner(),
+            Error::Validation("y")
+        );
+    }
+
+    #[test]
+    fn presence_flagged() {
+        use super::*;
+        let arena = crate::mem::OutOfMemory;
+
+        let mut bytes: &[u8] = &[0x02, 0x7f];
+        let absent = read_if::<DemoEnum, _>(false, &mut bytes, &arena);
+        assert_eq!(absent.unwrap(), None);
+        let present = read_if::<DemoEnum, _>(true, &mut bytes, &arena);
+        assert_eq!(present.unwrap(), Some(DemoEnum::Second));
+        assert_eq!(bytes, &[0x7f]);
+
+        let mut buf = [0; 2];
+        let mut w = &mut buf[..];
+        write_if("x", true, Some(&DemoEnum::First), &mut w).unwrap();
+        write_if::<DemoEnum, _>("x", false, None, &mut w).unwrap();
+        assert_eq!(w.len(), 1);
+        assert_eq!(buf[0], 0x01);
+
+        let mut w = &mut buf[..];
+        assert_eq!(
+            write_if("x", false, Some(&DemoEnum::First), &mut w)
+                .unwrap_err()
+                .into_inner(),
+            Error::Validation("x")
+        );
+        assert_eq!(
+            write_if::<DemoEnum, _>("x", true, None, &mut w)
+                .unwrap_err()
+                .into_inner(),
+            Error::Validation("x")
+        );
+        assert_eq!(w.len(), 2);
+    }
+
+    #[test]
+    fn read_struct() {
+        use crate::mem::BumpArena;
+
+        #[derive(Copy, Clone, zerocopy::FromBytes, zerocopy::AsBytes)]
+        #[repr(C)]
+        struct Words {
+            words: [u32; 2],
+        }
+
+        // Force the payload to be misaligned, so that it must be copied.
+        let bytes = [0xffu8; 16];
+        let misaligned = if bytes.as_ptr() as usize % 4 == 0 {
+            &bytes[1..9]
+        } else {
+            &bytes[4 - bytes.as_ptr() as usize % 4 + 1..][..8]
+        };
+
+        let arena = BumpArena::new([0; 16]);
+        let mut r = misaligned;
+        let words: &Words = super::read_struct(&mut r, &arena).unwrap();
+        assert_eq!(words.words, [u32::MAX; 2]);
+        assert_eq!(words as *const Words as usize % 4, 0);
+        assert!(r.is_empty());
+
+        let arena = crate::mem::OutOfMemory;
+        let mut r = misaligned;
+        assert!(super::read_struct::<Words, _>(&mut r, &arena).is_err());
+    }
+
+    #[test]
+    fn read_to_end() {
+        use crate::io::Read as _;
+
+        let arena = crate::mem::BumpArena::new([0; 8]);
+        let mut r: &[u8] = &[0x01, 0x02, 0x03, 0x04];
+        r.read_bytes(&mut [0]).unwrap();
+        let rest = super::read_to_end(&mut r, &arena);
+        assert_eq!(rest.unwrap(), &[2, 3, 4]);
+
+        // An exhausted reader yields an empty slice without allocating,
+        // rather than an error.
+        let empty = super::read_to_end(&mut r, &crate::mem::OutOfMemory);
+        assert!(empty.unwrap().is_empty());
+    }
+
+    #[test]
+    fn lenient() {
+        use super::*;
+        let arena = crate::mem::OutOfMemory;
+
+        let mut bytes: &[u8] = &[0x02, 0x7f];
+        assert!(DemoEnum::from_wire(&mut bytes, &arena).is_ok());
+        assert!(DemoEnum::from_wire(&mut bytes, &arena).is_err());
+
+        let mut bytes: &[u8] = &[0x02, 0x7f];
+        let second = Lenient::<DemoEnum>::from_wire(&mut bytes, &arena);
+        assert_eq!(second.unwrap(), Lenient::Known(DemoEnum::Second));
+        let reserved = Lenient::<DemoEnum>::from_wire(&mut bytes, &arena);
+        assert_eq!(reserved.unwrap(), Lenient::Reserved(0x7f));
+        assert_eq!(reserved.unwrap().known(), None);
+
+        let mut buf = [0; 1];
+        reserved.unwrap().to_wire(&mut buf[..]).unwrap();
+        assert_eq!(buf, [0x7f]);
+    }
+
+    #[test]
+    fn wire_len() {
+        use super::*;
+        use crate::protocol::cerberus::get_digests::GetDigestsResponse;
+
+        let digests = [[0x55; 32]; 3];
+        for n in 0..=digests.len() {
+            let resp = GetDigestsResponse {
+                digests: &digests[..n],
+            };
+            let mut buf = [0; 128];
+            let mut w = &mut buf[..];
+            resp.to_wire(&mut w).unwrap();
+            let written = 128 - w.len();
+            assert_eq!(resp.wire_len().unwrap(), written);
+        }
+    }
+
+    #[test]
+    fn endianness() {
+        use super::*;
+        let arena = crate::mem::OutOfMemory;
+
+        let mut bytes: &[u8] = &[0x01, 0x02, 0x01, 0x02];
+        assert_eq!(Le::<u16>::from_wire(&mut bytes, &arena), Ok(Le(0x0201)));
+        assert_eq!(Be::<u16>::from_wire(&mut bytes, &arena), Ok(Be(0x0102)));
+        assert!(Be::<u16>::from_wire(&mut bytes, &arena).is_err());
+
+        let mut buf = [0; 8];
+        let mut w = &mut buf[..];
+        Le(0x01020304u32).to_wire(&mut w).unwrap();
+        Be(0x01020304u32).to_wire(&mut w).unwrap();
+        assert_eq!(buf, [4, 3, 2, 1, 1, 2, 3, 4]);
+    }
+
+    derive_borrowed! {
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        #[@static(derive(Clone, PartialEq, Eq, Debug, serde::Deserialize))]
+        pub struct MixedEndian<'a> {
+            pub net_order: super::Be<u16>,
+            pub host_order: super::Le<u16>,
+            pub payload: &'a [u8],
+        }
+    }
+
+    #[test]
+    fn endianness_borrowed() {
+        use crate::protocol::borrowed::*;
+
+        let json = r#"{
+            "net_order": 258,
+            "host_order": 258,
+            "payload": [1, 2]
+        }"#;
+        let owned =
+            serde_json::from_str::<AsStatic<MixedEndian>>(json).unwrap();
+        let borrowed: MixedEndian = Borrowed::borrow(&owned);
+        assert_eq!(
+            borrowed,
+            MixedEndian {
+                net_order: super::Be(0x0102),
+                host_order: super::Le(0x0102),
+                payload: &[1, 2],
+            }
+        );
+    }
 }
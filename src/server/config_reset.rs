@@ -0,0 +1,27 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Configuration resets, performed by `ResetConfig`.
+//!
+//! Servers only call into a [`ConfigReset`] once a request has been
+//! authorized by a signature from the key returned by
+//! [`ConfigReset::authority()`].
+
+use crate::crypto::sig;
+use crate::protocol::cerberus;
+use crate::protocol::cerberus::reset_config::ResetKind;
+use crate::Result;
+
+/// A device's resettable configuration.
+pub trait ConfigReset {
+    /// Returns a verifier primed with the key that authorizes resets of
+    /// `kind`, or `None` if this device does not support such a reset.
+    fn authority(&mut self, kind: ResetKind) -> Option<&mut dyn sig::Verify>;
+
+    /// Performs a reset of `kind`.
+    ///
+    /// This is only called once the request has been authorized.
+    fn reset(&mut self, kind: ResetKind) -> Result<(), cerberus::Error>;
+}
+impl dyn ConfigReset {} // Ensure object-safety.
@@ -0,0 +1,23 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The runtime state reported by `GetDeviceState`.
+//!
+//! A device whose state never changes can use a fixed [`State`] directly,
+//! since it is itself a [`DeviceState`].
+
+use crate::protocol::cerberus::get_device_state::State;
+
+/// A source for the device's current runtime [`State`].
+pub trait DeviceState {
+    /// Returns the device's current state.
+    fn state(&self) -> State;
+}
+impl dyn DeviceState {} // Ensure object-safety.
+
+impl DeviceState for State {
+    fn state(&self) -> State {
+        *self
+    }
+}
@@ -0,0 +1,266 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Type-erased request handlers.
+//!
+//! The handler framework used by the servers in this module picks a handler at
+//! compile time, which requires every command to be known up-front. A server
+//! that instead wants to decide at runtime which commands it serves, such as
+//! a router that handlers are registered with one by one, can store them as
+//! `dyn ErasedHandler`s, which all have the same type regardless of the
+//! [`Command`] they handle.
+//!
+//! [`register()`] turns a handler function for a particular `Command` into an
+//! [`ErasedHandler`], which parses the request and serializes the reply
+//! itself.
+//!
+//! [`Command`]: crate::protocol::Command
+
+use core::marker::PhantomData;
+
+use crate::io;
+use crate::mem::Arena;
+use crate::net;
+use crate::protocol;
+use crate::protocol::wire::FromWire as _;
+use crate::protocol::wire::ToWire as _;
+use crate::protocol::Message as _;
+use crate::protocol::Req;
+use crate::protocol::Resp;
+use crate::server::Error;
+use crate::server::ParseOptions;
+use crate::Result;
+
+/// A handler for requests of a single command type, whose request and
+/// response types have been erased.
+///
+/// Requests are parsed with the lifetime `'req`, as with the rest of the
+/// handler framework.
+///
+/// See the [module documentation](self) for more information.
+pub trait ErasedHandler<'req, Header: net::Header> {
+    /// Returns the command type of the requests this handler accepts.
+    fn command(&self) -> Header::CommandType;
+
+    /// Parses `payload` as a request according to `opts`, handles it, and
+    /// writes the payload of the reply to `sink`.
+    ///
+    /// Returns the command type to send the reply with: either that of the
+    /// response, or that of the command's error message if the handler
+    /// failed. Errors returned by this function, such as for a request that
+    /// does not parse, have not been written to `sink`.
+    fn handle(
+        &self,
+        payload: &'req [u8],
+        arena: &'req dyn Arena,
+        opts: ParseOptions,
+        sink: &mut dyn io::Write,
+    ) -> Result<Header::CommandType, Error<Header>>;
+}
+impl<H: net::Header> dyn ErasedHandler<'_, H> {} // Ensure object-safety.
+
+/// Erases `handler`, a function that handles requests for the command `C`,
+/// into an [`ErasedHandler`].
+///
+/// `handler` is passed the parsed request along with the arena it was parsed
+/// into, which the response may also be allocated in.
+///
+/// This function should be called as `register::<Command, _>(...)`, since
+/// the handler's type can't be named when it is a closure.
+pub fn register<'req, C, F>(handler: F) -> TypedHandler<C, F>
+where
+    C: protocol::Command<'req>,
+    F: Fn(
+        Req<'req, C>,
+        &'req dyn Arena,
+    ) -> Result<Resp<'req, C>, protocol::Error<'req, C>>,
+{
+    TypedHandler {
+        handler,
+        _ph: PhantomData,
+    }
+}
+
+/// An [`ErasedHandler`] for the command `C`, returned by [`register()`].
+pub struct TypedHandler<C, F> {
+    handler: F,
+    _ph: PhantomData<fn() -> C>,
+}
+
+impl<'req, H, C, F> ErasedHandler<'req, H> for TypedHandler<C, F>
+where
+    H: net::Header,
+    C: protocol::Command<'req, CommandType = H::CommandType>,
+    F: Fn(
+        Req<'req, C>,
+        &'req dyn Arena,
+    ) -> Result<Resp<'req, C>, protocol::Error<'req, C>>,
+{
+    fn command(&self) -> H::CommandType {
+        Req::<'req, C>::TYPE
+    }
+
+    fn handle(
+        &self,
+        payload: &'req [u8],
+        arena: &'req dyn Arena,
+        opts: ParseOptions,
+        sink: &mut dyn io::Write,
+    ) -> Result<H::CommandType, Error<H>> {
+        let mut r = payload;
+        let req = Req::<'req, C>::from_wire(&mut r, arena)?;
+        check!(
+            opts.allow_trailing || r.is_empty(),
+            Error::ReqTooLong(r.len())
+        );
+
+        match (self.handler)(req, arena) {
+            Ok(resp) => {
+                resp.to_wire(sink)?;
+                Ok(Resp::<'req, C>::TYPE)
+            }
+            Err(err) => {
+                err.into_inner().to_wire(sink)?;
+                Ok(protocol::Error::<'req, C>::TYPE)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::time::Duration;
+
+    use crate::mem::BumpArena;
+    use crate::net::CerberusHeader;
+    use crate::protocol::cerberus;
+    use crate::protocol::cerberus::CommandType;
+    use crate::protocol::wire::FromWire;
+
+    const VERSION: &[u8; 32] = &[7; 32];
+
+    /// Looks up the handler for `command` in `handlers` and runs it on
+    /// `payload`, returning the reply's command type and payload.
+    fn dispatch<'a, 'out>(
+        handlers: &[&dyn ErasedHandler<'a, CerberusHeader>],
+        command: CommandType,
+        payload: &'a [u8],
+        arena: &'a dyn Arena,
+        opts: ParseOptions,
+        out: &'out mut [u8],
+    ) -> Result<(CommandType, &'out [u8]), Error<CerberusHeader>> {
+        let handler = handlers
+            .iter()
+            .find(|h| h.command() == command)
+            .ok_or_else(|| fail!(Error::UnhandledCommand(command)))?;
+
+        let mut sink = &mut *out;
+        let reply = handler.handle(payload, arena, opts, &mut sink)?;
+        let remaining = sink.len();
+        let written = out.len() - remaining;
+        Ok((reply, &out[..written]))
+    }
+
+    #[test]
+    fn two_commands() {
+        let fw = register::<cerberus::FirmwareVersion, _>(|req, _| {
+            check!(req.index == 0, cerberus::Error::OutOfRange);
+            Ok(Resp::<cerberus::FirmwareVersion> { version: VERSION })
+        });
+        let uptime = register::<cerberus::DeviceUptime, _>(|_, _| {
+            Ok(Resp::<cerberus::DeviceUptime> {
+                uptime: Duration::from_micros(1234),
+            })
+        });
+        let handlers: [&dyn ErasedHandler<CerberusHeader>; 2] = [&fw, &uptime];
+
+        let arena = BumpArena::new([0; 64]);
+        let mut out = [0; 64];
+        let (reply, mut bytes) = dispatch(
+            &handlers,
+            CommandType::FirmwareVersion,
+            &[0],
+            &arena,
+            ParseOptions::default(),
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(reply, CommandType::FirmwareVersion);
+        let resp =
+            Resp::<cerberus::FirmwareVersion>::from_wire(&mut bytes, &arena)
+                .unwrap();
+        assert_eq!(resp.version, VERSION);
+
+        let mut out = [0; 64];
+        let (reply, mut bytes) = dispatch(
+            &handlers,
+            CommandType::DeviceUptime,
+            &[0],
+            &arena,
+            ParseOptions::default(),
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(reply, CommandType::DeviceUptime);
+        let resp =
+            Resp::<cerberus::DeviceUptime>::from_wire(&mut bytes, &arena)
+                .unwrap();
+        assert_eq!(resp.uptime, Duration::from_micros(1234));
+
+        // Handler errors are serialized as the reply.
+        let mut out = [0; 64];
+        let (reply, _) = dispatch(
+            &handlers,
+            CommandType::FirmwareVersion,
+            &[1],
+            &arena,
+            ParseOptions::default(),
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(reply, CommandType::Error);
+
+        // Trailing bytes are skipped, unless parsing strictly.
+        let mut out = [0; 64];
+        let (reply, _) = dispatch(
+            &handlers,
+            CommandType::FirmwareVersion,
+            &[0, 0],
+            &arena,
+            ParseOptions::default(),
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(reply, CommandType::FirmwareVersion);
+
+        // Malformed requests are not serialized.
+        let mut out = [0; 64];
+        let err = dispatch(
+            &handlers,
+            CommandType::FirmwareVersion,
+            &[0, 0],
+            &arena,
+            ParseOptions::STRICT,
+            &mut out,
+        )
+        .unwrap_err();
+        assert_eq!(err.into_inner(), Error::ReqTooLong(1));
+
+        let mut out = [0; 64];
+        let err = dispatch(
+            &handlers,
+            CommandType::DeviceId,
+            &[],
+            &arena,
+            ParseOptions::default(),
+            &mut out,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.into_inner(),
+            Error::UnhandledCommand(CommandType::DeviceId)
+        );
+    }
+}
@@ -0,0 +1,328 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! TCG-style measurement event logs.
+//!
+//! Attestation verifiers generally want more than raw PCR values: they want
+//! the sequence of events that produced them, so that they can check each
+//! measurement against a policy and then replay the log to confirm the final
+//! PCRs. [`TcgEventLog`] records such a sequence in the "crypto agile" format
+//! from the TCG PC Client Platform Firmware Profile, which standard tooling
+//! (such as `tpm2_eventlog`) can parse.
+//!
+//! Only SHA-256 digests are supported.
+//!
+//! A serialized log can be served to clients with the
+//! [`GetLog`](crate::protocol::cerberus::GetLog) command.
+
+use crate::crypto::hash;
+use crate::crypto::hash::EngineExt as _;
+use crate::io::Cursor;
+use crate::io::Write as _;
+use crate::Result;
+
+/// The number of PCRs a [`TcgEventLog`] tracks.
+pub const PCR_COUNT: usize = 24;
+
+/// The length of a SHA-256 digest.
+const DIGEST_LEN: usize = hash::Algo::Sha256.bytes();
+
+/// The TCG algorithm ID for SHA-256.
+const TPM_ALG_SHA256: u16 = 0x000b;
+
+/// An error returned by a [`TcgEventLog`] operation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Indicates that the log's buffer is too small for the new event.
+    OutOfSpace,
+    /// Indicates that an event referenced a PCR index of at least
+    /// [`PCR_COUNT`].
+    BadPcr,
+    /// Indicates that hashing failed.
+    Hash(hash::Error),
+}
+
+impl From<hash::Error> for Error {
+    fn from(e: hash::Error) -> Self {
+        Self::Hash(e)
+    }
+}
+
+debug_from!(Error => hash::Error);
+
+/// A TCG event type, identifying what kind of thing an event measures.
+///
+/// The associated constants are the types defined by the PC Client Platform
+/// Firmware Profile that are meaningful outside of a PC; any other value may
+/// be used as well.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EventType(pub u32);
+
+impl EventType {
+    /// `EV_POST_CODE`: a measurement of early boot code, such as a ROM
+    /// extension.
+    pub const POST_CODE: Self = Self(0x01);
+    /// `EV_NO_ACTION`: an informational event that is not extended into any
+    /// PCR.
+    pub const NO_ACTION: Self = Self(0x03);
+    /// `EV_SEPARATOR`: marks the boundary between pre-OS and OS measurements
+    /// in a PCR.
+    pub const SEPARATOR: Self = Self(0x04);
+    /// `EV_ACTION`: a measurement of a fixed string describing an action
+    /// taken during boot.
+    pub const ACTION: Self = Self(0x05);
+    /// `EV_EVENT_TAG`: a tagged, vendor-defined event.
+    pub const EVENT_TAG: Self = Self(0x06);
+    /// `EV_S_CRTM_CONTENTS`: a measurement of the static root of trust for
+    /// measurement.
+    pub const S_CRTM_CONTENTS: Self = Self(0x07);
+    /// `EV_S_CRTM_VERSION`: the version of the static root of trust for
+    /// measurement.
+    pub const S_CRTM_VERSION: Self = Self(0x08);
+    /// `EV_CPU_MICROCODE`: a measurement of a CPU microcode update.
+    pub const CPU_MICROCODE: Self = Self(0x09);
+    /// `EV_PLATFORM_CONFIG_FLAGS`: a measurement of platform configuration
+    /// flags.
+    pub const PLATFORM_CONFIG_FLAGS: Self = Self(0x0a);
+    /// `EV_COMPACT_HASH`: a measurement of a small piece of data, which is
+    /// recorded as the event data.
+    pub const COMPACT_HASH: Self = Self(0x0c);
+    /// `EV_IPL`: a measurement of the initial program loader.
+    pub const IPL: Self = Self(0x0d);
+    /// `EV_NONHOST_CODE`: a measurement of code running on a device other
+    /// than the host, such as a management controller.
+    pub const NONHOST_CODE: Self = Self(0x0f);
+    /// `EV_NONHOST_CONFIG`: a measurement of the configuration of a non-host
+    /// device.
+    pub const NONHOST_CONFIG: Self = Self(0x10);
+    /// `EV_NONHOST_INFO`: identifying information about a non-host device.
+    pub const NONHOST_INFO: Self = Self(0x11);
+}
+
+/// A TCG event log, serialized into a caller-provided buffer as events are
+/// appended.
+///
+/// Along with the log itself, this type tracks the aggregate value of each
+/// PCR: appending an event with digest `d` to PCR `i` sets it to
+/// `SHA-256(pcr[i] || d)`, just as a TPM would. Events of type
+/// [`EventType::NO_ACTION`] are recorded, but not extended into any PCR.
+///
+/// The first event in the log is always the `Spec ID Event03` header, which
+/// declares SHA-256 as the log's only digest algorithm.
+pub struct TcgEventLog<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+    events: usize,
+    pcrs: [[u8; DIGEST_LEN]; PCR_COUNT],
+}
+
+impl<'a> TcgEventLog<'a> {
+    /// Creates a new, empty log, backed by `buf`.
+    ///
+    /// Returns [`Error::OutOfSpace`] if `buf` is too small for the log's
+    /// header.
+    pub fn new(buf: &'a mut [u8]) -> Result<Self, Error> {
+        let mut log = Self {
+            buf,
+            len: 0,
+            events: 0,
+            pcrs: [[0; DIGEST_LEN]; PCR_COUNT],
+        };
+        log.write_spec_id()?;
+        Ok(log)
+    }
+
+    /// Appends an event whose digest is the SHA-256 hash of `event_data`.
+    pub fn append(
+        &mut self,
+        hasher: &mut dyn hash::Engine,
+        pcr: u32,
+        event_type: EventType,
+        event_data: &[u8],
+    ) -> Result<(), Error> {
+        let mut digest = [0; DIGEST_LEN];
+        hasher.contiguous_hash(hash::Algo::Sha256, event_data, &mut digest)?;
+        self.append_digest(hasher, pcr, event_type, &digest, event_data)
+    }
+
+    /// Appends an event with a precomputed `digest`.
+    ///
+    /// This is useful when the measured data is not `event_data` itself,
+    /// such as when measuring a firmware image but logging only its name.
+    pub fn append_digest(
+        &mut self,
+        hasher: &mut dyn hash::Engine,
+        pcr: u32,
+        event_type: EventType,
+        digest: &[u8; DIGEST_LEN],
+        event_data: &[u8],
+    ) -> Result<(), Error> {
+        let index = pcr as usize;
+        check!(index < PCR_COUNT, Error::BadPcr);
+
+        // Compute the new PCR value first, so that a hashing failure leaves
+        // the log unchanged.
+        let mut new_pcr = self.pcrs[index];
+        if event_type != EventType::NO_ACTION {
+            let mut h = hasher.new_hash(hash::Algo::Sha256)?;
+            h.write(&self.pcrs[index])?;
+            h.write(digest)?;
+            h.finish(&mut new_pcr)?;
+        }
+
+        self.write_event(|w| {
+            w.write_le(pcr)?;
+            w.write_le(event_type.0)?;
+            w.write_le(1u32)?; // TPML_DIGEST_VALUES.count
+            w.write_le(TPM_ALG_SHA256)?;
+            w.write_all(digest)?;
+            w.write_le(event_data.len() as u32)?;
+            w.write_all(event_data)
+        })?;
+        self.pcrs[index] = new_pcr;
+        self.events += 1;
+        Ok(())
+    }
+
+    /// Returns the aggregate value of PCR `index`, or `None` if it is out of
+    /// range.
+    pub fn pcr(&self, index: usize) -> Option<&[u8; DIGEST_LEN]> {
+        self.pcrs.get(index)
+    }
+
+    /// Returns the number of events in this log, not counting the header.
+    pub fn event_count(&self) -> usize {
+        self.events
+    }
+
+    /// Returns the serialized log.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Writes the `TCG_PCClientPCREvent` that opens every crypto-agile log.
+    fn write_spec_id(&mut self) -> Result<(), Error> {
+        const SIGNATURE: &[u8; 16] = b"Spec ID Event03\0";
+        const SPEC_ID_LEN: u32 = 33;
+
+        self.write_event(|w| {
+            w.write_le(0u32)?; // pcrIndex
+            w.write_le(EventType::NO_ACTION.0)?;
+            w.write_all(&[0; 20])?; // SHA-1 digest
+            w.write_le(SPEC_ID_LEN)?;
+
+            w.write_all(SIGNATURE)?;
+            w.write_le(0u32)?; // platformClass
+            w.write_le(0u8)?; // specVersionMinor
+            w.write_le(2u8)?; // specVersionMajor
+            w.write_le(2u8)?; // specErrata
+            w.write_le(2u8)?; // uintnSize: UINT64
+            w.write_le(1u32)?; // numberOfAlgorithms
+            w.write_le(TPM_ALG_SHA256)?;
+            w.write_le(DIGEST_LEN as u16)?;
+            w.write_le(0u8) // vendorInfoSize
+        })
+    }
+
+    /// Writes a single record to the end of the log, leaving the log
+    /// unchanged if it does not fit.
+    fn write_event(
+        &mut self,
+        f: impl FnOnce(&mut Cursor) -> Result<(), crate::io::Error>,
+    ) -> Result<(), Error> {
+        let mut cursor = Cursor::new(&mut self.buf[self.len..]);
+        f(&mut cursor).map_err(|_| fail!(Error::OutOfSpace))?;
+        self.len += cursor.consumed_len();
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "ring"))]
+mod test {
+    use super::*;
+
+    use crate::crypto::ring;
+
+    fn sha256(data: &[&[u8]]) -> [u8; DIGEST_LEN] {
+        let mut hasher = ring::hash::Engine::new();
+        let mut h = hasher.new_hash(hash::Algo::Sha256).unwrap();
+        for d in data {
+            h.write(d).unwrap();
+        }
+        let mut out = [0; DIGEST_LEN];
+        h.finish(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn header() {
+        let mut buf = [0; 128];
+        let log = TcgEventLog::new(&mut buf).unwrap();
+        assert_eq!(log.event_count(), 0);
+
+        let bytes = log.as_bytes();
+        assert_eq!(bytes.len(), 32 + 33);
+        assert_eq!(&bytes[..8], &[0, 0, 0, 0, 3, 0, 0, 0]);
+        assert_eq!(&bytes[28..32], &[33, 0, 0, 0]);
+        assert_eq!(&bytes[32..48], b"Spec ID Event03\0");
+        assert_eq!(&bytes[56..60], &[1, 0, 0, 0]);
+        assert_eq!(&bytes[60..64], &[0x0b, 0x00, 32, 0]);
+    }
+
+    #[test]
+    fn append() {
+        let mut hasher = ring::hash::Engine::new();
+        let mut buf = [0; 512];
+        let mut log = TcgEventLog::new(&mut buf).unwrap();
+        let header_len = log.as_bytes().len();
+
+        log.append(&mut hasher, 1, EventType::POST_CODE, b"bootloader")
+            .unwrap();
+        log.append(&mut hasher, 1, EventType::SEPARATOR, &[0; 4])
+            .unwrap();
+        log.append(&mut hasher, 2, EventType::NO_ACTION, b"note")
+            .unwrap();
+        assert_eq!(log.event_count(), 3);
+
+        let d1 = sha256(&[b"bootloader"]);
+        let d2 = sha256(&[&[0; 4]]);
+        let pcr1 = sha256(&[&sha256(&[&[0; 32], &d1]), &d2]);
+        assert_eq!(log.pcr(1), Some(&pcr1));
+        assert_eq!(log.pcr(2), Some(&[0; 32]));
+        assert_eq!(log.pcr(PCR_COUNT), None);
+
+        let event = &log.as_bytes()[header_len..];
+        assert_eq!(&event[..4], &[1, 0, 0, 0]);
+        assert_eq!(&event[4..8], &[1, 0, 0, 0]);
+        assert_eq!(&event[8..12], &[1, 0, 0, 0]);
+        assert_eq!(&event[12..14], &[0x0b, 0x00]);
+        assert_eq!(&event[14..46], &d1);
+        assert_eq!(&event[46..50], &[10, 0, 0, 0]);
+        assert_eq!(&event[50..60], b"bootloader");
+    }
+
+    #[test]
+    fn out_of_space() {
+        let mut hasher = ring::hash::Engine::new();
+        assert_eq!(
+            TcgEventLog::new(&mut [0; 64]).err().unwrap().into_inner(),
+            Error::OutOfSpace
+        );
+
+        let mut buf = [0; 128];
+        let mut log = TcgEventLog::new(&mut buf).unwrap();
+        let len = log.as_bytes().len();
+        let err = log
+            .append(&mut hasher, 0, EventType::ACTION, &[0; 64])
+            .unwrap_err();
+        assert_eq!(err.into_inner(), Error::OutOfSpace);
+        assert_eq!(log.as_bytes().len(), len);
+        assert_eq!(log.pcr(0), Some(&[0; 32]));
+
+        let err = log
+            .append(&mut hasher, PCR_COUNT as u32, EventType::ACTION, &[])
+            .unwrap_err();
+        assert_eq!(err.into_inner(), Error::BadPcr);
+    }
+}
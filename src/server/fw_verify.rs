@@ -0,0 +1,327 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Resumable firmware verification, performed by `BeginVerify`,
+//! `VerifyChunk`, and `FinishVerify`.
+//!
+//! Each verification in progress occupies a [`Slot`], whose hash engine
+//! holds the running digest of the image between requests. An engine can
+//! only hash one thing at a time, so the number of slots given to
+//! [`Verifications::new()`] bounds how many verifications may be in progress
+//! at once. A verification that goes without a chunk for longer than the idle
+//! timeout is abandoned, freeing its slot for another.
+
+use core::time::Duration;
+
+use crate::crypto::hash;
+use crate::hardware::clock;
+use crate::hardware::clock::Clock;
+use crate::hardware::clock::Ticks;
+use crate::protocol::cerberus;
+use crate::Result;
+
+/// The length of the digests images are checked against.
+const DIGEST_LEN: usize = hash::Algo::Sha256.bytes();
+
+/// A hash engine, along with the verification it is streaming, if any.
+pub struct Slot<'a> {
+    engine: &'a mut dyn hash::Engine,
+    stream: Option<Stream>,
+}
+
+impl<'a> Slot<'a> {
+    /// Creates a new, idle slot, which streams images through `engine`.
+    ///
+    /// `engine` should be dedicated to this slot, since other users of it
+    /// would clobber the digest it holds between requests.
+    pub fn new(engine: &'a mut dyn hash::Engine) -> Self {
+        Self {
+            engine,
+            stream: None,
+        }
+    }
+
+    /// Returns whether this slot is streaming the verification `handle`.
+    fn holds(&self, handle: u32) -> bool {
+        matches!(&self.stream, Some(s) if s.handle == handle)
+    }
+}
+
+/// The state of a verification in progress.
+struct Stream {
+    handle: u32,
+    len: usize,
+    received: usize,
+    expected: [u8; DIGEST_LEN],
+    last_active: Ticks,
+}
+
+/// The firmware verifications in progress on a device.
+pub struct Verifications<'a> {
+    slots: &'a mut [Slot<'a>],
+    idle_timeout: Duration,
+    next_handle: u32,
+}
+
+impl<'a> Verifications<'a> {
+    /// Creates a new `Verifications`, which can hold as many verifications at
+    /// once as there are `slots`.
+    ///
+    /// A verification that receives no chunks for `idle_timeout` is
+    /// abandoned.
+    pub fn new(slots: &'a mut [Slot<'a>], idle_timeout: Duration) -> Self {
+        Self {
+            slots,
+            idle_timeout,
+            next_handle: 1,
+        }
+    }
+
+    /// Returns how many verifications are in progress.
+    pub fn in_progress(&mut self, clock: &dyn Clock) -> usize {
+        self.expire(clock);
+        self.slots.iter().filter(|s| s.stream.is_some()).count()
+    }
+
+    /// Starts verifying an image of `len` bytes against the SHA-256 digest
+    /// `expected`, returning a handle for the new verification.
+    ///
+    /// Returns [`cerberus::Error::ResourceLimit`] if every slot is in use.
+    pub fn begin(
+        &mut self,
+        clock: &dyn Clock,
+        len: usize,
+        expected: &[u8; DIGEST_LEN],
+    ) -> Result<u32, cerberus::Error> {
+        self.expire(clock);
+        let idx = self
+            .slots
+            .iter()
+            .position(|s| s.stream.is_none())
+            .ok_or(cerberus::Error::ResourceLimit)?;
+        let handle = self.fresh_handle();
+
+        let slot = &mut self.slots[idx];
+        slot.engine.start_raw(hash::Algo::Sha256, None)?;
+        slot.stream = Some(Stream {
+            handle,
+            len,
+            received: 0,
+            expected: *expected,
+            last_active: clock.now(),
+        });
+        Ok(handle)
+    }
+
+    /// Streams the chunk `data`, found at `offset` within the image, into the
+    /// verification `handle`.
+    ///
+    /// Chunks must arrive in order; a chunk that does not pick up where the
+    /// last one left off, or that runs past the end of the image, is rejected
+    /// with [`cerberus::Error::OutOfRange`], and the verification carries on
+    /// as though it had never been sent.
+    pub fn feed(
+        &mut self,
+        clock: &dyn Clock,
+        handle: u32,
+        offset: usize,
+        data: &[u8],
+    ) -> Result<(), cerberus::Error> {
+        let slot = self.find(clock, handle)?;
+        let stream = match &mut slot.stream {
+            Some(s) => s,
+            None => return Err(fail!(cerberus::Error::OutOfRange)),
+        };
+
+        check!(offset == stream.received, cerberus::Error::OutOfRange);
+        let end = offset
+            .checked_add(data.len())
+            .filter(|&end| end <= stream.len)
+            .ok_or(cerberus::Error::OutOfRange)?;
+
+        if let Err(e) = slot.engine.write_raw(data) {
+            // The engine's state is unknown now, so there is no resuming.
+            slot.stream = None;
+            return Err(e.into());
+        }
+        stream.received = end;
+        stream.last_active = clock.now();
+        Ok(())
+    }
+
+    /// Ends the verification `handle`, returning whether the image matched
+    /// its expected digest.
+    ///
+    /// An image that was not received in full does not match. Either way,
+    /// `handle` may not be used again afterwards.
+    pub fn finish(
+        &mut self,
+        clock: &dyn Clock,
+        handle: u32,
+    ) -> Result<bool, cerberus::Error> {
+        let slot = self.find(clock, handle)?;
+        let stream = match slot.stream.take() {
+            Some(s) => s,
+            None => return Err(fail!(cerberus::Error::OutOfRange)),
+        };
+
+        let mut digest = [0; DIGEST_LEN];
+        slot.engine.finish_raw(&mut digest)?;
+        Ok(stream.received == stream.len && digest == stream.expected)
+    }
+
+    /// Finds the slot streaming the verification `handle`.
+    fn find(
+        &mut self,
+        clock: &dyn Clock,
+        handle: u32,
+    ) -> Result<&mut Slot<'a>, cerberus::Error> {
+        self.expire(clock);
+        self.slots
+            .iter_mut()
+            .find(|s| s.holds(handle))
+            .ok_or_else(|| fail!(cerberus::Error::OutOfRange))
+    }
+
+    /// Abandons every verification that has been idle for too long.
+    fn expire(&mut self, clock: &dyn Clock) {
+        let now = clock.now();
+        let timeout = clock::ticks(self.idle_timeout);
+        for slot in self.slots.iter_mut() {
+            let idle = match &slot.stream {
+                Some(s) => now.saturating_sub(s.last_active) >= timeout,
+                None => false,
+            };
+            if idle {
+                slot.stream = None;
+            }
+        }
+    }
+
+    /// Picks a handle that is neither zero nor in use.
+    fn fresh_handle(&mut self) -> u32 {
+        loop {
+            let handle = self.next_handle;
+            self.next_handle = handle.wrapping_add(1);
+            if handle != 0 && !self.slots.iter().any(|s| s.holds(handle)) {
+                return handle;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::crypto::hash::EngineExt as _;
+    use crate::crypto::ring;
+    use crate::hardware::clock::MockClock;
+
+    const IMAGE: &[u8] = b"a firmware image, in several chunks";
+
+    fn digest(data: &[u8]) -> [u8; DIGEST_LEN] {
+        let mut out = [0; DIGEST_LEN];
+        ring::hash::Engine::new()
+            .contiguous_hash(hash::Algo::Sha256, data, &mut out)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn chunked() {
+        let clock = MockClock::new();
+        let mut engine = ring::hash::Engine::new();
+        let mut slots = [Slot::new(&mut engine)];
+        let mut v = Verifications::new(&mut slots, Duration::from_secs(1));
+
+        let expected = digest(IMAGE);
+        let h = v.begin(&clock, IMAGE.len(), &expected).unwrap();
+        for (i, chunk) in IMAGE.chunks(8).enumerate() {
+            v.feed(&clock, h, i * 8, chunk).unwrap();
+        }
+        assert!(v.finish(&clock, h).unwrap());
+        assert_eq!(v.in_progress(&clock), 0);
+
+        // The handle is gone once finished.
+        let err = v.finish(&clock, h).unwrap_err();
+        assert_eq!(err.into_inner(), cerberus::Error::OutOfRange);
+    }
+
+    #[test]
+    fn mismatch() {
+        let clock = MockClock::new();
+        let mut engine = ring::hash::Engine::new();
+        let mut slots = [Slot::new(&mut engine)];
+        let mut v = Verifications::new(&mut slots, Duration::from_secs(1));
+
+        let h = v.begin(&clock, IMAGE.len(), &[0x55; 32]).unwrap();
+        v.feed(&clock, h, 0, IMAGE).unwrap();
+        assert!(!v.finish(&clock, h).unwrap());
+
+        // Incomplete images don't verify, even if their prefix does.
+        let expected = digest(&IMAGE[..4]);
+        let h = v.begin(&clock, IMAGE.len(), &expected).unwrap();
+        v.feed(&clock, h, 0, &IMAGE[..4]).unwrap();
+        assert!(!v.finish(&clock, h).unwrap());
+    }
+
+    #[test]
+    fn out_of_order() {
+        let clock = MockClock::new();
+        let mut engine = ring::hash::Engine::new();
+        let mut slots = [Slot::new(&mut engine)];
+        let mut v = Verifications::new(&mut slots, Duration::from_secs(1));
+
+        let expected = digest(IMAGE);
+        let h = v.begin(&clock, IMAGE.len(), &expected).unwrap();
+        v.feed(&clock, h, 0, &IMAGE[..8]).unwrap();
+
+        // Skipped, repeated, and overlong chunks are all refused...
+        for &(offset, chunk) in &[(16, &IMAGE[16..]), (0, &IMAGE[..8])] {
+            let err = v.feed(&clock, h, offset, chunk).unwrap_err();
+            assert_eq!(err.into_inner(), cerberus::Error::OutOfRange);
+        }
+        let err = v.feed(&clock, h, 8, IMAGE).unwrap_err();
+        assert_eq!(err.into_inner(), cerberus::Error::OutOfRange);
+
+        // ...without disturbing the verification.
+        v.feed(&clock, h, 8, &IMAGE[8..]).unwrap();
+        assert!(v.finish(&clock, h).unwrap());
+    }
+
+    #[test]
+    fn bounded() {
+        let clock = MockClock::new();
+        let mut e0 = ring::hash::Engine::new();
+        let mut e1 = ring::hash::Engine::new();
+        let mut slots = [Slot::new(&mut e0), Slot::new(&mut e1)];
+        let mut v = Verifications::new(&mut slots, Duration::from_secs(1));
+
+        let expected = digest(IMAGE);
+        let h0 = v.begin(&clock, IMAGE.len(), &expected).unwrap();
+        let h1 = v.begin(&clock, IMAGE.len(), &expected).unwrap();
+        assert_ne!(h0, h1);
+        let err = v.begin(&clock, IMAGE.len(), &expected).unwrap_err();
+        assert_eq!(err.into_inner(), cerberus::Error::ResourceLimit);
+
+        // Streams are independent of each other.
+        v.feed(&clock, h1, 0, IMAGE).unwrap();
+        v.feed(&clock, h0, 0, &IMAGE[..1]).unwrap();
+        assert!(v.finish(&clock, h1).unwrap());
+
+        // Keep h0 alive past the point where it would otherwise have expired.
+        clock.advance(Duration::from_millis(600));
+        v.feed(&clock, h0, 1, &IMAGE[1..2]).unwrap();
+        clock.advance(Duration::from_millis(600));
+        assert_eq!(v.in_progress(&clock), 1);
+
+        // Once idle for long enough, it is abandoned.
+        clock.advance(Duration::from_millis(400));
+        assert_eq!(v.in_progress(&clock), 0);
+        let err = v.feed(&clock, h0, 2, &IMAGE[2..]).unwrap_err();
+        assert_eq!(err.into_inner(), cerberus::Error::OutOfRange);
+        v.begin(&clock, IMAGE.len(), &expected).unwrap();
+    }
+}
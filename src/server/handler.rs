@@ -35,8 +35,20 @@
 //!   server context into the closure.
 //! - The closure executes, which returns
 //!   `Result<MyCommand::Resp, protocol::Error<MyCommand::Error>>`.
-//! - The resulting response or error is sent using `resp`.
-//! - If no handler is chosen, an error is returned.
+//! - The resulting response or error is sent using `resp`. If the port knows
+//!   that the response will not fit in its reply, the peer is sent the
+//!   error that `io::Error::BufferExhausted` converts into instead.
+//! - If no handler is chosen, an error is returned. [`HandlerMethods::run_or_reject()`]
+//!   additionally replies to the request with an "unsupported command" error.
+//!   [`HandlerMethods::run_gated()`] also gives the caller a chance to refuse
+//!   a supported request, such as for rate limiting, before it is parsed.
+//!
+//! Handlers can find out which command types the whole `Handler` has handlers
+//! for through [`Context::registry`]; this is useful for advertising
+//! capabilities truthfully.
+//!
+//! A `Handler` given a [`Timer`] through [`Handler::with_timer()`] reports how
+//! long each [`Phase`] of handling the request took.
 //!
 //! This module is not part of `manticore`'s API.
 //!
@@ -67,18 +79,25 @@
 //! is irrelevant, because no two handlers can meaningfully have the same
 //! command type.
 
+use core::fmt;
 use core::marker::PhantomData;
 
+use crate::hardware::clock::Clock;
+use crate::hardware::clock::Ticks;
+use crate::io;
 use crate::mem::Arena;
 use crate::mem::ArenaExt as _;
 use crate::net;
+use crate::net::host::HostRequestExt as _;
 use crate::protocol;
 use crate::protocol::wire;
 use crate::protocol::wire::FromWire;
-use crate::protocol::wire::ToWire as _;
+use crate::protocol::wire::ToWire;
 use crate::protocol::Message;
 use crate::protocol::Req;
 use crate::protocol::Resp;
+use crate::server::timing::Phase;
+use crate::server::timing::Timer;
 use crate::Result;
 
 /// A `*`-importable prelude that pulls in only the names that are necessary
@@ -86,6 +105,7 @@ use crate::Result;
 pub mod prelude {
     pub use super::Handler;
     pub use super::HandlerMethods;
+    pub use super::Registry;
 }
 
 /// An error returned by a request handler.
@@ -100,6 +120,12 @@ where
     /// Represents a failure during marshalling.
     Wire(wire::Error),
 
+    /// Represents a failure during marshalling, along with a breadcrumb
+    /// recording where in a request it happened.
+    ///
+    /// See [`Error::with_context()`].
+    InContext(wire::Error, Breadcrumb<Header::CommandType>),
+
     /// Indicates that a request message was too long: after successful parse
     /// of a header and a body, we still had unread bytes remaining, indicating
     /// a message decoding problem.
@@ -110,6 +136,19 @@ where
     /// Indicates that a request could not be handled, because no handler was
     /// provided for it.
     UnhandledCommand(Header::CommandType),
+
+    /// Indicates that a request was refused before reaching its handler, such
+    /// as by a rate limiter. The peer has already been sent an error reply.
+    Refused(Header::CommandType),
+
+    /// Indicates that a peer replied to a request with a message that was
+    /// neither the expected response nor an error.
+    UnexpectedResponse {
+        /// The command type of the expected response.
+        expected: Header::CommandType,
+        /// The command type the peer actually replied with.
+        got: Header::CommandType,
+    },
 }
 
 impl<H: net::Header> From<wire::Error> for Error<H> {
@@ -126,6 +165,83 @@ impl<H: net::Header> From<net::Error> for Error<H> {
 
 debug_from!(Error<H: net::Header> => wire::Error, net::Error);
 
+impl<H: net::Header> Error<H> {
+    /// Attaches a breadcrumb to this error, recording that it happened while
+    /// parsing `command`.
+    ///
+    /// If `field` is `None` and the error names its own field, as
+    /// [`wire::Error::Validation`] does, that name is used instead. Errors
+    /// other than marshalling failures are returned unchanged, as are errors
+    /// that already carry a breadcrumb.
+    pub fn with_context(
+        self,
+        command: H::CommandType,
+        field: Option<&'static str>,
+    ) -> Self {
+        match self {
+            Self::Wire(e) => {
+                let field = field.or(match e {
+                    wire::Error::Validation(f) => Some(f),
+                    _ => None,
+                });
+                Self::InContext(e, Breadcrumb { command, field })
+            }
+            e => e,
+        }
+    }
+
+    /// Returns the breadcrumb attached to this error, if there is one.
+    pub fn context(&self) -> Option<&Breadcrumb<H::CommandType>> {
+        match self {
+            Self::InContext(_, b) => Some(b),
+            _ => None,
+        }
+    }
+}
+
+impl<H: net::Header> fmt::Display for Error<H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Network(e) => write!(f, "network error: {}", e),
+            Self::Wire(e) => write!(f, "encoding error: {:?}", e),
+            Self::InContext(e, b) => {
+                write!(f, "encoding error: {:?} parsing {}", e, b)
+            }
+            Self::ReqTooLong(n) => {
+                write!(f, "request too long: {} unread bytes", n)
+            }
+            Self::UnhandledCommand(c) => {
+                write!(f, "no handler for command {:?}", c)
+            }
+            Self::Refused(c) => write!(f, "refused command {:?}", c),
+            Self::UnexpectedResponse { expected, got } => write!(
+                f,
+                "unexpected response: expected {:?}, got {:?}",
+                expected, got
+            ),
+        }
+    }
+}
+
+/// A record of where in a request an [`Error`] happened.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Breadcrumb<CommandType> {
+    /// The command being parsed.
+    pub command: CommandType,
+    /// The field being parsed, if known.
+    pub field: Option<&'static str>,
+}
+
+impl<C: fmt::Debug> fmt::Display for Breadcrumb<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.command)?;
+        if let Some(field) = self.field {
+            write!(f, ".{}", field)?;
+        }
+        Ok(())
+    }
+}
+
 /// A request handler builder.
 ///
 /// See the module documentation for more information.
@@ -133,14 +249,108 @@ debug_from!(Error<H: net::Header> => wire::Error, net::Error);
 /// Note: the type parameter on this type is only necessary to make type
 /// inference work out. It can be left off, but rustc will complain about
 /// missing type annotations.
-pub struct Handler<Server, Header> {
+pub struct Handler<'t, Server, Header>
+where
+    Header: net::Header,
+{
+    opts: ParseOptions,
+    timing: Option<Timing<'t, Header::CommandType>>,
     _ph: PhantomData<fn(Server, Header)>,
 }
 
-impl<Server, Header> Handler<Server, Header> {
+/// A clock, and a timer to report the phases it measures to.
+type Timing<'t, C> = (&'t dyn Clock, &'t mut dyn Timer<C>);
+
+impl<'t, Server, Header> Handler<'t, Server, Header>
+where
+    Header: net::Header,
+{
     /// Creates a new, default `Handler`.
     pub fn new() -> Self {
-        Self { _ph: PhantomData }
+        Self {
+            opts: ParseOptions::default(),
+            timing: None,
+            _ph: PhantomData,
+        }
+    }
+
+    /// Sets the [`ParseOptions`] used when decoding requests.
+    pub fn with_parse_options(self, opts: ParseOptions) -> Self {
+        Self { opts, ..self }
+    }
+
+    /// Reports how long each [`Phase`] of handling a request takes to
+    /// `timer`, as measured by `clock`.
+    ///
+    /// If `timer` is `None`, `clock` is never read.
+    pub fn with_timer(
+        self,
+        clock: &'t dyn Clock,
+        timer: Option<&'t mut dyn Timer<Header::CommandType>>,
+    ) -> Self {
+        Self {
+            timing: timer.map(|t| (clock, t)),
+            ..self
+        }
+    }
+}
+
+/// Measures the phases of handling a single request.
+///
+/// Without a [`Timer`] to report to, this does nothing, and never reads the
+/// clock.
+struct Stopwatch<'t, C> {
+    timing: Option<Timing<'t, C>>,
+    command: C,
+    last: Ticks,
+}
+
+impl<'t, C: Copy> Stopwatch<'t, C> {
+    /// Starts timing the first phase of handling a `command` request.
+    fn start(timing: Option<Timing<'t, C>>, command: C) -> Self {
+        let last = timing.as_ref().map(|(clock, _)| clock.now()).unwrap_or(0);
+        Self {
+            timing,
+            command,
+            last,
+        }
+    }
+
+    /// Records that `phase` has just ended, and starts timing the next one.
+    fn lap(&mut self, phase: Phase) {
+        if let Some((clock, timer)) = &mut self.timing {
+            let now = clock.now();
+            timer.record(self.command, phase, now.saturating_sub(self.last));
+            self.last = now;
+        }
+    }
+}
+
+/// Options for how a [`Handler`] decodes incoming requests.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Whether to accept requests with bytes left over after the request
+    /// message has been parsed.
+    ///
+    /// Newer peers may append fields that this server does not know about
+    /// yet; when this is set, those bytes are skipped. Otherwise, such a
+    /// request is rejected with [`Error::ReqTooLong`].
+    pub allow_trailing: bool,
+}
+
+impl ParseOptions {
+    /// Options that reject anything but an exact encoding; useful for
+    /// conformance testing.
+    pub const STRICT: Self = Self {
+        allow_trailing: false,
+    };
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            allow_trailing: true,
+        }
     }
 }
 
@@ -165,13 +375,34 @@ mod sealed {
 }
 
 /// Context for a request, i.e., all relevant variables for handling a request.
-pub struct Context<'req, Buf, Req, Server> {
+pub struct Context<'req, Buf, Req, Server, CommandType> {
     pub req_buf: Buf,
     pub req: Req,
     pub server: Server,
     pub arena: &'req dyn Arena,
+    pub registry: Registry<CommandType>,
+    pub encrypted: bool,
+}
+
+/// The set of command types that a [`Handler`] has handlers for.
+pub struct Registry<CommandType> {
+    handles: fn(CommandType) -> bool,
+}
+
+impl<CommandType> Registry<CommandType> {
+    /// Returns whether there is a handler for `command`.
+    pub fn handles(&self, command: CommandType) -> bool {
+        (self.handles)(command)
+    }
 }
 
+impl<CommandType> Clone for Registry<CommandType> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<CommandType> Copy for Registry<CommandType> {}
+
 /// The core trait that makes handler building possible.
 ///
 /// The lifetime `'req` represents the lifetime of the request. This lifetime
@@ -223,7 +454,7 @@ where
         // kludge.
         for<'c> C: protocol::Command<'c>,
         F: FnOnce(
-            Context<'req, (), Req<'req, C>, Server>,
+            Context<'req, (), Req<'req, C>, Server, Header::CommandType>,
         ) -> Result<Resp<'out, C>, protocol::Error<'out, C>>,
         'srv: 'out,
         'req: 'out,
@@ -241,7 +472,7 @@ where
         // See above for an explanation of these bounds.
         for<'c> C: protocol::Command<'c>,
         F: FnOnce(
-            Context<'req, &'req [u8], Req<'req, C>, Server>,
+            Context<'req, &'req [u8], Req<'req, C>, Server, Header::CommandType>,
         ) -> Result<Resp<'out, C>, protocol::Error<'out, C>>,
         'srv: 'out,
         'req: 'out,
@@ -253,6 +484,26 @@ where
         }
     }
 
+    /// Returns whether this `Handler` has a handler for `command`.
+    #[doc(hidden)]
+    fn handles(command: Header::CommandType) -> bool;
+
+    /// Returns the [`ParseOptions`] this `Handler` decodes requests with.
+    #[doc(hidden)]
+    fn parse_options(&self) -> ParseOptions;
+
+    /// Takes the clock and [`Timer`] this `Handler` reports phases to, if it
+    /// has one.
+    #[doc(hidden)]
+    fn take_timing(&mut self) -> Option<Timing<'srv, Header::CommandType>>;
+
+    /// Returns the [`Registry`] of commands this `Handler` has handlers for.
+    fn registry() -> Registry<Header::CommandType> {
+        Registry {
+            handles: Self::handles,
+        }
+    }
+
     /// The "real" run function.
     #[doc(hidden)]
     fn run_with_header(
@@ -261,6 +512,7 @@ where
         header: Header,
         request: &mut dyn net::host::HostRequest<'req, Header>,
         arena: &'req dyn Arena,
+        registry: Registry<Header::CommandType>,
     ) -> Result<(), Error<Header>>;
 
     /// Executes a `Handler` with the given context.
@@ -275,7 +527,63 @@ where
     ) -> Result<(), Error<Header>> {
         let request = host_port.receive()?;
         let header = request.header()?;
-        self.run_with_header(server, header, request, arena)
+        self.run_with_header(server, header, request, arena, Self::registry())
+    }
+
+    /// Like [`HandlerMethods::run()`], but if there is no handler for the
+    /// request, replies to it with `unsupported(command)` before returning
+    /// an error.
+    #[inline]
+    fn run_or_reject<E: ToWire>(
+        self,
+        server: Server,
+        host_port: &mut dyn net::host::HostPort<'req, Header>,
+        arena: &'req dyn Arena,
+        unsupported: impl FnOnce(Header::CommandType) -> E,
+    ) -> Result<(), Error<Header>> {
+        self.run_gated(server, host_port, arena, unsupported, |_, _| None)
+    }
+
+    /// Like [`HandlerMethods::run_or_reject()`], but before dispatching a
+    /// request, consults `gate` with the server and the request's command
+    /// type. If `gate` returns an error, the request is replied to with it,
+    /// and [`Error::Refused`] is returned.
+    #[inline]
+    fn run_gated<E: ToWire>(
+        self,
+        server: Server,
+        host_port: &mut dyn net::host::HostPort<'req, Header>,
+        arena: &'req dyn Arena,
+        unsupported: impl FnOnce(Header::CommandType) -> E,
+        gate: impl FnOnce(&mut Server, Header::CommandType) -> Option<E>,
+    ) -> Result<(), Error<Header>> {
+        let request = host_port.receive()?;
+        self.run_received(server, request, arena, unsupported, gate)
+    }
+
+    /// Like [`HandlerMethods::run_gated()`], but for a `request` that the
+    /// caller has already received, such as so that it can prepare for
+    /// handling it once it has arrived.
+    #[inline]
+    fn run_received<E: ToWire>(
+        self,
+        mut server: Server,
+        request: &mut dyn net::host::HostRequest<'req, Header>,
+        arena: &'req dyn Arena,
+        unsupported: impl FnOnce(Header::CommandType) -> E,
+        gate: impl FnOnce(&mut Server, Header::CommandType) -> Option<E>,
+    ) -> Result<(), Error<Header>> {
+        let header = request.header()?;
+        let command = header.command();
+        if !Self::handles(command) {
+            request.reply_error(unsupported(command))?;
+            return Err(fail!(Error::UnhandledCommand(command)));
+        }
+        if let Some(err) = gate(&mut server, command) {
+            request.reply_error(err)?;
+            return Err(fail!(Error::Refused(command)));
+        }
+        self.run_with_header(server, header, request, arena, Self::registry())
     }
 }
 
@@ -286,6 +594,7 @@ impl<Prev, Command, F, const B: bool> Cons<Prev, Command, F, B> {
         request: &mut dyn net::host::HostRequest<'_, Header>,
         ctx: Ctx,
         original_header: Header,
+        mut watch: Stopwatch<'_, Header::CommandType>,
     ) -> Result<(), Error<Header>>
     where
         Command:
@@ -297,23 +606,55 @@ impl<Prev, Command, F, const B: bool> Cons<Prev, Command, F, B> {
             protocol::Error<'out, Command>,
         >,
         Header: net::Header,
+        protocol::Error<'out, Command>: From<io::Error>,
     {
-        match (self.handler)(ctx) {
-            Ok(msg) => {
-                let reply = request.reply(
-                    original_header.reply_with(Resp::<'out, Command>::TYPE),
-                )?;
-                msg.to_wire(reply.sink()?)?;
-                reply.finish()?;
-                Ok(())
-            }
-            Err(err) => {
-                let reply =
-                    request.reply(original_header.reply_with_error())?;
-                err.into_inner().to_wire(reply.sink()?)?;
-                reply.finish()?;
-                Ok(())
+        let result = (self.handler)(ctx);
+        watch.lap(Phase::Handle);
+        send_reply::<Command, Header>(request, original_header, result)?;
+        watch.lap(Phase::Serialize);
+        Ok(())
+    }
+}
+
+/// Sends `result`, the outcome of handling a request, as the reply to
+/// `request`.
+fn send_reply<'out, Command, Header>(
+    request: &mut dyn net::host::HostRequest<'_, Header>,
+    original_header: Header,
+    result: Result<Resp<'out, Command>, protocol::Error<'out, Command>>,
+) -> Result<(), Error<Header>>
+where
+    Command: for<'c> protocol::Command<'c, CommandType = Header::CommandType>,
+    Header: net::Header,
+    protocol::Error<'out, Command>: From<io::Error>,
+{
+    match result {
+        Ok(msg) => {
+            // Measure the response before committing to it, so that one
+            // that won't fit can be replaced with an error the peer can
+            // make sense of.
+            if let Some(cap) = request.reply_capacity() {
+                if msg.wire_len()? > cap {
+                    let err = protocol::Error::<'out, Command>::from(
+                        io::Error::BufferExhausted,
+                    );
+                    request.reply_error(err)?;
+                    return Ok(());
+                }
             }
+
+            let reply = request.reply(
+                original_header.reply_with(Resp::<'out, Command>::TYPE),
+            )?;
+            let sink = reply.sink()?;
+            msg.to_wire(&mut *sink)?;
+            sink.flush().map_err(|e| e.cast::<net::Error>())?;
+            reply.finish()?;
+            Ok(())
+        }
+        Err(err) => {
+            request.reply_error(err.into_inner())?;
+            Ok(())
         }
     }
 }
@@ -328,33 +669,63 @@ where
     Prev: HandlerMethods<'req, 'srv, Server, Header>,
     Command: for<'c> protocol::Command<'c, CommandType = Header::CommandType>,
     F: FnOnce(
-        Context<'req, (), Req<'req, Command>, Server>,
+        Context<'req, (), Req<'req, Command>, Server, Header::CommandType>,
     )
         -> Result<Resp<'out, Command>, protocol::Error<'out, Command>>,
+    protocol::Error<'out, Command>: From<io::Error>,
 {
+    #[inline]
+    fn handles(command: Header::CommandType) -> bool {
+        command == Req::<'req, Command>::TYPE || Prev::handles(command)
+    }
+
+    #[inline]
+    fn parse_options(&self) -> ParseOptions {
+        self.prev.parse_options()
+    }
+
+    #[inline]
+    fn take_timing(&mut self) -> Option<Timing<'srv, Header::CommandType>> {
+        self.prev.take_timing()
+    }
+
     #[inline]
     fn run_with_header(
-        self,
+        mut self,
         server: Server,
         header: Header,
         request: &mut dyn net::host::HostRequest<'req, Header>,
         arena: &'req dyn Arena,
+        registry: Registry<Header::CommandType>,
     ) -> Result<(), Error<Header>> {
         if header.command() != Req::<'req, Command>::TYPE {
             // Recurse into the next handler case. Note that this cannot be
             // `run`, since that would re-parse the header incorrectly.
-            return self.prev.run_with_header(server, header, request, arena);
+            return self
+                .prev
+                .run_with_header(server, header, request, arena, registry);
         }
 
-        let req = FromWire::from_wire(request.payload()?, arena)?;
+        let mut watch = Stopwatch::start(self.take_timing(), header.command());
+        let opts = self.parse_options();
+        let req = {
+            let r = request.payload()?;
+            let req = FromWire::from_wire(&mut *r, arena)
+                .map_err(|e| in_context(e, header.command()))?;
+            skip_trailing(r, opts)?;
+            req
+        };
+        watch.lap(Phase::Parse);
 
         let ctx = Context {
             req_buf: (),
             req,
             server,
             arena,
+            registry,
+            encrypted: request.is_encrypted(),
         };
-        self.run_inner(request, ctx, header)
+        self.run_inner(request, ctx, header, watch)
     }
 }
 
@@ -368,26 +739,52 @@ where
     Prev: HandlerMethods<'req, 'srv, Server, Header>,
     Command: for<'c> protocol::Command<'c, CommandType = Header::CommandType>,
     F: FnOnce(
-        Context<'req, &'req [u8], Req<'req, Command>, Server>,
+        Context<
+            'req,
+            &'req [u8],
+            Req<'req, Command>,
+            Server,
+            Header::CommandType,
+        >,
     )
         -> Result<Resp<'out, Command>, protocol::Error<'out, Command>>,
+    protocol::Error<'out, Command>: From<io::Error>,
 {
+    #[inline]
+    fn handles(command: Header::CommandType) -> bool {
+        command == Req::<'req, Command>::TYPE || Prev::handles(command)
+    }
+
+    #[inline]
+    fn parse_options(&self) -> ParseOptions {
+        self.prev.parse_options()
+    }
+
+    #[inline]
+    fn take_timing(&mut self) -> Option<Timing<'srv, Header::CommandType>> {
+        self.prev.take_timing()
+    }
+
     #[inline]
     fn run_with_header(
-        self,
+        mut self,
         server: Server,
         header: Header,
         request: &mut dyn net::host::HostRequest<'req, Header>,
         arena: &'req dyn Arena,
+        registry: Registry<Header::CommandType>,
     ) -> Result<(), Error<Header>> {
         if header.command() != Req::<'req, Command>::TYPE {
             // Recurse into the next handler case. Note that this cannot be
             // `run`, since that would re-parse the header incorrectly.
-            return self.prev.run_with_header(server, header, request, arena);
+            return self
+                .prev
+                .run_with_header(server, header, request, arena, registry);
         }
 
         // Buffer the entire request payload; from_wire below will zero-copy
         // read it.
+        let mut watch = Stopwatch::start(self.take_timing(), header.command());
         let r = request.payload()?;
         let req_buf = arena
             .alloc_slice::<u8>(r.remaining_data())
@@ -399,23 +796,49 @@ where
         // argument becomes an rvalue. Thus, `from_wire` does not mutate the
         // original `req_buf` that gets passed to `run_inner()`.
         let req_buf: &'req [u8] = req_buf;
-        let req = FromWire::from_wire(&mut { req_buf }, arena)?;
+        let mut rest = req_buf;
+        let req = FromWire::from_wire(&mut rest, arena)
+            .map_err(|e| in_context(e, header.command()))?;
+        check!(
+            self.parse_options().allow_trailing || rest.is_empty(),
+            Error::ReqTooLong(rest.len())
+        );
+        watch.lap(Phase::Parse);
 
         let ctx = Context {
             req_buf,
             req,
             server,
             arena,
+            registry,
+            encrypted: request.is_encrypted(),
         };
-        self.run_inner(request, ctx, header)
+        self.run_inner(request, ctx, header, watch)
     }
 }
 
-impl<'req, 'srv, Server: 'srv, Header>
-    HandlerMethods<'req, 'srv, Server, Header> for Handler<Server, Header>
+impl<'req, 'srv, 't: 'srv, Server: 'srv, Header>
+    HandlerMethods<'req, 'srv, Server, Header> for Handler<'t, Server, Header>
 where
     Header: net::Header,
 {
+    #[inline]
+    fn handles(_: Header::CommandType) -> bool {
+        false
+    }
+
+    #[inline]
+    fn parse_options(&self) -> ParseOptions {
+        self.opts
+    }
+
+    #[inline]
+    fn take_timing(&mut self) -> Option<Timing<'srv, Header::CommandType>> {
+        let (clock, timer) = self.timing.take()?;
+        let timer: &'srv mut dyn Timer<Header::CommandType> = timer;
+        Some((clock, timer))
+    }
+
     #[inline]
     fn run_with_header(
         self,
@@ -423,13 +846,53 @@ where
         header: Header,
         _: &mut dyn net::host::HostRequest<'req, Header>,
         _: &'req dyn Arena,
+        _: Registry<Header::CommandType>,
     ) -> Result<(), Error<Header>> {
         Err(fail!(Error::UnhandledCommand(header.command())))
     }
 }
 
+/// Deals with any bytes left in `r` after a request has been parsed out of it,
+/// according to `opts`.
+///
+/// Tolerated bytes are read and discarded, so that the transport sees the
+/// whole request as consumed.
+fn skip_trailing<'a, R, H>(
+    r: &mut R,
+    opts: ParseOptions,
+) -> Result<(), Error<H>>
+where
+    R: io::ReadZero<'a> + ?Sized,
+    H: net::Header,
+{
+    let extra = r.remaining_data();
+    if extra == 0 {
+        return Ok(());
+    }
+    check!(opts.allow_trailing, Error::ReqTooLong(extra));
+
+    let mut scratch = [0; 32];
+    while r.remaining_data() > 0 {
+        let len = r.remaining_data().min(scratch.len());
+        r.read_bytes(&mut scratch[..len])
+            .map_err(|e| wire::Error::from(e.into_inner()))?;
+    }
+    Ok(())
+}
+
+/// Converts an error from parsing a request for `command` into a handler
+/// error that records as much.
+fn in_context<H: net::Header>(
+    e: crate::Error<wire::Error>,
+    command: H::CommandType,
+) -> crate::Error<Error<H>> {
+    let mut e = e.cast::<Error<H>>();
+    *e.as_mut() = e.as_ref().with_context(command, None);
+    e
+}
+
 impl<P, C, F, const B: bool> sealed::Sealed for Cons<P, C, F, B> {}
-impl<S, H> sealed::Sealed for Handler<S, H> {}
+impl<S, H: net::Header> sealed::Sealed for Handler<'_, S, H> {}
 
 #[cfg(test)]
 mod test {
@@ -442,7 +905,7 @@ mod test {
     const VERSION1: &[u8; 32] = &[2; 32];
     const VERSION2: &[u8; 32] = &[5; 32];
 
-    type Handler<S> = super::Handler<S, net::CerberusHeader>;
+    type Handler<'t, S> = super::Handler<'t, S, net::CerberusHeader>;
 
     fn simulate_request<
         'a,
@@ -666,4 +1129,214 @@ mod test {
         let version = resp.unwrap().version;
         assert!(version == VERSION1 || version == VERSION2);
     }
+
+    #[test]
+    fn registry() {
+        fn registry_of<
+            'a,
+            H: HandlerMethods<'a, 'a, (), net::CerberusHeader>,
+        >(
+            _: &H,
+        ) -> Registry<CommandType> {
+            H::registry()
+        }
+
+        let handler = Handler::<()>::new()
+            .handle::<cerberus::FirmwareVersion, _>(|_| {
+                panic!("handler should not be called")
+            })
+            .handle::<cerberus::DeviceId, _>(|_| {
+                panic!("handler should not be called")
+            });
+        let registry = registry_of(&handler);
+        assert!(registry.handles(CommandType::FirmwareVersion));
+        assert!(registry.handles(CommandType::DeviceId));
+        assert!(!registry.handles(CommandType::Challenge));
+
+        let registry = registry_of(&Handler::<()>::new());
+        assert!(!registry.handles(CommandType::FirmwareVersion));
+    }
+
+    #[test]
+    fn run_or_reject() {
+        let handler =
+            Handler::<()>::new().handle::<cerberus::DeviceId, _>(|_| {
+                panic!("called the wrong handler")
+            });
+
+        let mut scratch = [0; 64];
+        let mut port = net::host::InMemHost::new(&mut scratch);
+        port.request(
+            net::CerberusHeader {
+                command: CommandType::FirmwareVersion,
+            },
+            &[0],
+        );
+
+        let arena = BumpArena::new([0; 64]);
+        let err = handler
+            .run_or_reject((), &mut port, &arena, |_| {
+                cerberus::Error::UnsupportedCommand
+            })
+            .unwrap_err();
+        assert_eq!(
+            err.into_inner(),
+            Error::UnhandledCommand(CommandType::FirmwareVersion)
+        );
+
+        let (header, mut resp) = port.response().unwrap();
+        assert_eq!(header.command, CommandType::Error);
+        let err = cerberus::Error::from_wire(&mut resp, &arena).unwrap();
+        assert_eq!(err, cerberus::Error::UnsupportedCommand);
+    }
+
+    #[test]
+    fn timing() {
+        use crate::hardware::clock::MockClock;
+        use core::time::Duration;
+
+        let clock = MockClock::new();
+        let mut laps = Vec::new();
+        let mut timer = |command: CommandType, phase: Phase, elapsed: Ticks| {
+            laps.push((command, phase, elapsed))
+        };
+
+        let handler = Handler::<()>::new()
+            .with_timer(&clock, Some(&mut timer))
+            .handle::<cerberus::FirmwareVersion, _>(|_| {
+                clock.advance(Duration::from_micros(5));
+                Ok(Resp::<cerberus::FirmwareVersion> { version: VERSION1 })
+            });
+
+        let mut scratch = [0; 64];
+        let mut port = net::host::InMemHost::new(&mut scratch);
+        port.request(
+            net::CerberusHeader {
+                command: CommandType::FirmwareVersion,
+            },
+            &[0],
+        );
+
+        let arena = BumpArena::new([0; 64]);
+        handler.run((), &mut port, &arena).unwrap();
+        assert_eq!(
+            laps,
+            &[
+                (CommandType::FirmwareVersion, Phase::Parse, 0),
+                (CommandType::FirmwareVersion, Phase::Handle, 5),
+                (CommandType::FirmwareVersion, Phase::Serialize, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn response_too_large() {
+        let handler = Handler::<()>::new()
+            .handle::<cerberus::FirmwareVersion, _>(|_| {
+                Ok(Resp::<cerberus::FirmwareVersion> { version: VERSION1 })
+            });
+
+        let mut scratch = [0; 16];
+        let mut port = net::host::InMemHost::new(&mut scratch);
+        port.request(
+            net::CerberusHeader {
+                command: CommandType::FirmwareVersion,
+            },
+            &[0],
+        );
+
+        let arena = BumpArena::new([0; 64]);
+        handler.run((), &mut port, &arena).unwrap();
+
+        let (header, mut resp) = port.response().unwrap();
+        assert_eq!(header.command, CommandType::Error);
+        let err = cerberus::Error::from_wire(&mut resp, &arena).unwrap();
+        assert_eq!(err, cerberus::Error::ResourceLimit);
+    }
+
+    #[test]
+    fn display() {
+        let e = Error::<net::CerberusHeader>::Network(net::Error::Disconnected);
+        assert_eq!(e.to_string(), "network error: connection disconnected");
+
+        let e = Error::<net::CerberusHeader>::UnhandledCommand(
+            CommandType::FirmwareVersion,
+        );
+        assert_eq!(e.to_string(), "no handler for command FirmwareVersion");
+
+        let e = Error::<net::CerberusHeader>::Wire(wire::Error::Io(
+            io::Error::BufferExhausted,
+        ))
+        .with_context(CommandType::GetCert, Some("length"));
+        assert_eq!(
+            e.to_string(),
+            "encoding error: Io(BufferExhausted) parsing GetCert.length"
+        );
+    }
+
+    #[test]
+    fn parse_error_context() {
+        let handler = Handler::<()>::new()
+            .handle::<cerberus::FirmwareVersion, _>(|_| {
+                panic!("handler should not be called")
+            });
+
+        let mut scratch = [0; 64];
+        let mut port = net::host::InMemHost::new(&mut scratch);
+        port.request(
+            net::CerberusHeader {
+                command: CommandType::FirmwareVersion,
+            },
+            &[],
+        );
+
+        let arena = BumpArena::new([0; 64]);
+        let err = handler.run((), &mut port, &arena).unwrap_err();
+        let err = err.into_inner();
+        assert_eq!(
+            err.context(),
+            Some(&Breadcrumb {
+                command: CommandType::FirmwareVersion,
+                field: None,
+            })
+        );
+
+        // Already-located errors keep their breadcrumb, and validation
+        // failures supply their own field.
+        let e =
+            Error::<net::CerberusHeader>::Wire(wire::Error::Validation("slot"))
+                .with_context(CommandType::GetCert, None);
+        assert_eq!(e.context().unwrap().field, Some("slot"));
+        assert_eq!(e.with_context(CommandType::DeviceId, None), e);
+    }
+
+    #[test]
+    fn trailing_fields() {
+        let run = |opts| {
+            let handler = Handler::<()>::new()
+                .with_parse_options(opts)
+                .handle::<cerberus::FirmwareVersion, _>(|ctx| {
+                assert_eq!(ctx.req.index, 3);
+                Ok(Resp::<cerberus::FirmwareVersion> { version: VERSION1 })
+            });
+
+            let mut scratch = [0; 128];
+            let mut port = net::host::InMemHost::new(&mut scratch);
+            port.request(
+                net::CerberusHeader {
+                    command: CommandType::FirmwareVersion,
+                },
+                &[3, 0xaa, 0xbb],
+            );
+
+            let arena = BumpArena::new([0; 64]);
+            handler
+                .run((), &mut port, &arena)
+                .map(|_| port.response().is_some())
+                .map_err(|e| e.into_inner())
+        };
+
+        assert_eq!(run(ParseOptions::default()), Ok(true));
+        assert_eq!(run(ParseOptions::STRICT), Err(Error::ReqTooLong(2)));
+    }
 }
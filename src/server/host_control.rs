@@ -0,0 +1,37 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Control over the host processor's reset, exposed by `GetHostState` and
+//! `SetHostState`.
+//!
+//! Anyone may read a [`HostControl`]'s state, but servers only change it on
+//! behalf of a peer that has established an encrypted session, and that
+//! holds the key returned by [`HostControl::authority()`].
+
+use crate::crypto::sig;
+use crate::protocol::cerberus;
+use crate::protocol::cerberus::get_host_state::HostResetState;
+use crate::Result;
+
+/// The RoT's hold over the host processor's reset line.
+pub trait HostControl {
+    /// Returns the host's current reset state.
+    fn state(&self) -> HostResetState;
+
+    /// Holds the host in reset.
+    fn assert_reset(&mut self) -> Result<(), cerberus::Error>;
+
+    /// Releases the host from reset.
+    fn deassert_reset(&mut self) -> Result<(), cerberus::Error>;
+
+    /// Returns a verifier primed with the key that authorizes changing the
+    /// host's reset state.
+    ///
+    /// The default implementation returns `None`, so that the host's state
+    /// may only be read.
+    fn authority(&mut self) -> Option<&mut dyn sig::Verify> {
+        None
+    }
+}
+impl dyn HostControl {} // Ensure object-safety.
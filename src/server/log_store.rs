@@ -0,0 +1,73 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Storage for the logs served by `GetLog` and erased by `ClearLog`.
+//!
+//! Servers read logs through a [`LogStore`], so that integrations can keep
+//! each kind of log wherever is convenient, such as in RAM or in flash.
+//!
+//! A [`TcgEventLog`] is itself a [`LogStore`] that holds only an attestation
+//! log.
+
+use crate::crypto::sig;
+use crate::protocol::cerberus;
+use crate::protocol::cerberus::get_log::LogType;
+use crate::server::TcgEventLog;
+use crate::Result;
+
+/// A collection of a device's logs, by [`LogType`].
+pub trait LogStore {
+    /// Returns the current contents of the log of type `log`, or `None` if
+    /// this device does not keep such a log.
+    fn read(&self, log: LogType) -> Option<&[u8]>;
+
+    /// Erases the contents of the log of type `log`.
+    ///
+    /// This is only called once the request has been authorized by a
+    /// signature from the key returned by [`LogStore::authority()`].
+    ///
+    /// Returns [`cerberus::Error::OutOfRange`] if this device does not keep
+    /// such a log, or if it cannot be cleared.
+    fn clear(&mut self, log: LogType) -> Result<(), cerberus::Error>;
+
+    /// Returns a verifier primed with the key that authorizes clearing the
+    /// log of type `log`.
+    ///
+    /// The default implementation returns `None`, so that no log may be
+    /// cleared.
+    fn authority(
+        &mut self,
+        #[allow(unused)] log: LogType,
+    ) -> Option<&mut dyn sig::Verify> {
+        None
+    }
+
+    /// Returns the current value of PCR `index`, as described by the
+    /// attestation log, or `None` if there is no such PCR.
+    ///
+    /// The default implementation reports no PCRs at all.
+    fn pcr(&self, #[allow(unused)] index: usize) -> Option<&[u8; 32]> {
+        None
+    }
+}
+impl dyn LogStore {} // Ensure object-safety.
+
+/// The attestation log cannot be cleared, since the PCRs it describes would
+/// no longer match it.
+impl LogStore for TcgEventLog<'_> {
+    fn read(&self, log: LogType) -> Option<&[u8]> {
+        match log {
+            LogType::Attestation => Some(self.as_bytes()),
+            _ => None,
+        }
+    }
+
+    fn clear(&mut self, _: LogType) -> Result<(), cerberus::Error> {
+        Err(fail!(cerberus::Error::OutOfRange))
+    }
+
+    fn pcr(&self, index: usize) -> Option<&[u8; 32]> {
+        TcgEventLog::pcr(self, index)
+    }
+}
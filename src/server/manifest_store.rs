@@ -0,0 +1,55 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Storage for manifests delivered by the `Prepare`, `Update`, and `Activate`
+//! commands for PFMs, CFMs, and PCDs.
+//!
+//! A manifest passes through three states in a [`ManifestStore`]:
+//! 1. It is *received* in chunks, into the buffer set aside by
+//!    [`ManifestStore::prepare()`].
+//! 2. Once all of it has arrived, and only if its signature checks out, the
+//!    server *stages* it with [`ManifestStore::stage()`].
+//! 3. A later [`ManifestStore::activate()`] makes the staged manifest the
+//!    *active* one, replacing whatever was active before.
+//!
+//! The store itself does not verify anything; it is the server's
+//! responsibility to never stage a manifest that has not been verified.
+
+use crate::protocol::cerberus;
+use crate::Result;
+
+/// A device's received, staged, and active manifests of a single type.
+pub trait ManifestStore {
+    /// Discards any manifest being received or staged, and sets aside a
+    /// buffer to receive a new manifest of `len` bytes into.
+    ///
+    /// Returns [`cerberus::Error::ResourceLimit`] if this store cannot hold
+    /// a manifest that long.
+    fn prepare(&mut self, len: usize) -> Result<(), cerberus::Error>;
+
+    /// Returns the buffer set aside by the last call to
+    /// [`ManifestStore::prepare()`], or `None` if there is no manifest being
+    /// received.
+    fn receiving(&mut self) -> Option<&mut [u8]>;
+
+    /// Stages the manifest in [`ManifestStore::receiving()`], replacing any
+    /// previously staged manifest.
+    ///
+    /// This is only called once the manifest has been received in full, and
+    /// its signature verified.
+    fn stage(&mut self) -> Result<(), cerberus::Error>;
+
+    /// Returns the staged manifest, if there is one.
+    fn staged(&self) -> Option<&[u8]>;
+
+    /// Makes the staged manifest active, replacing the active one.
+    ///
+    /// Returns [`cerberus::Error::OutOfRange`] if there is no staged
+    /// manifest.
+    fn activate(&mut self) -> Result<(), cerberus::Error>;
+
+    /// Returns the active manifest, if there is one.
+    fn active(&self) -> Option<&[u8]>;
+}
+impl dyn ManifestStore {} // Ensure object-safety.
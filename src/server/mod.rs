@@ -10,6 +10,51 @@
 //! TODO: description of how to use a server.
 
 mod handler;
+pub use handler::Breadcrumb;
 pub use handler::Error;
+pub use handler::ParseOptions;
+
+pub mod config_reset;
+pub use config_reset::ConfigReset;
+
+pub mod device_state;
+pub use device_state::DeviceState;
+
+pub mod erased;
+pub use erased::ErasedHandler;
+
+pub mod event_log;
+pub use event_log::TcgEventLog;
+
+pub mod fw_verify;
+pub use fw_verify::Verifications;
+
+pub mod host_control;
+pub use host_control::HostControl;
+
+pub mod log_store;
+pub use log_store::LogStore;
+
+pub mod manifest_store;
+pub use manifest_store::ManifestStore;
+
+pub mod nonce;
+pub use nonce::NonceSource;
+pub use nonce::NonceValidator;
 
 pub mod pa_rot;
+
+#[cfg(feature = "std")]
+pub mod provisioning;
+
+pub mod rate_limit;
+pub use rate_limit::RateLimiter;
+
+pub mod timing;
+pub use timing::Timer;
+
+pub mod unseal;
+pub use unseal::Unsealer;
+
+pub mod vendor;
+pub use vendor::VendorHandler;
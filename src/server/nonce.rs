@@ -0,0 +1,258 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Generation and anti-replay checks for challenge nonces.
+//!
+//! A `Challenge` is only meaningful if its nonce is fresh: if a server signs
+//! the same nonce twice, an attacker that recorded the first response can
+//! replay it. [`NonceValidator`] is consulted by servers before signing
+//! anything over a requester-provided nonce.
+//!
+//! Conversely, when a device is the one issuing a challenge, it draws its
+//! nonces from a [`NonceSource`]. Every [`Csrng`] is a `NonceSource`.
+
+use crate::crypto::csrng;
+use crate::crypto::csrng::Csrng;
+use crate::protocol::Nonce;
+use crate::Result;
+
+pub use crate::protocol::NONCE_LEN;
+
+/// An error returned by a [`NonceSource`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Error {
+    /// Indicates that the underlying random number generator failed.
+    Csrng(csrng::Error),
+}
+
+impl From<csrng::Error> for Error {
+    fn from(e: csrng::Error) -> Self {
+        Self::Csrng(e)
+    }
+}
+
+debug_from!(Error => csrng::Error);
+
+/// A source of fresh nonces, for challenges this device issues.
+pub trait NonceSource {
+    /// Returns a fresh nonce.
+    fn fresh(&mut self) -> Result<Nonce, Error>;
+}
+impl dyn NonceSource {} // Ensure object-safety.
+
+/// Nonces drawn directly from a random number generator.
+impl<R: Csrng + ?Sized> NonceSource for R {
+    fn fresh(&mut self) -> Result<Nonce, Error> {
+        Ok(Nonce::random(self)?)
+    }
+}
+
+/// A policy for deciding whether a requester-provided nonce is fresh.
+pub trait NonceValidator {
+    /// Checks whether `nonce` is acceptable, recording it as seen if so.
+    ///
+    /// Returns `false` if `nonce` should be rejected, such as because it was
+    /// seen recently.
+    fn accept(&mut self, nonce: &Nonce) -> bool;
+
+    /// Starts a new anti-replay window.
+    ///
+    /// Servers should call this periodically, such as on a timer or after
+    /// some number of challenges, so that long-lived validators do not
+    /// accumulate state indefinitely. Whether nonces seen before the rotation
+    /// are accepted again afterwards is up to the validator's policy.
+    ///
+    /// The default implementation does nothing.
+    fn rotate(&mut self) {}
+}
+impl dyn NonceValidator {} // Ensure object-safety.
+
+/// A [`NonceValidator`] that rejects any of the `N` most recently seen nonces.
+///
+/// This type uses a fixed amount of memory: once `N` nonces have been seen,
+/// the oldest one is forgotten to make room for the next one.
+///
+/// On [`NonceValidator::rotate()`], all but a configurable number of the most
+/// recent nonces are forgotten; by default, none are kept.
+pub struct RecentNonces<const N: usize> {
+    seen: [Nonce; N],
+    len: usize,
+    next: usize,
+    retain: usize,
+}
+
+impl<const N: usize> RecentNonces<N> {
+    /// Creates a new `RecentNonces` that has not seen any nonces.
+    pub fn new() -> Self {
+        Self::with_retain(0)
+    }
+
+    /// Creates a new `RecentNonces` that keeps the `retain` most recently
+    /// seen nonces across calls to [`NonceValidator::rotate()`].
+    pub fn with_retain(retain: usize) -> Self {
+        Self {
+            seen: [Nonce([0; NONCE_LEN]); N],
+            len: 0,
+            next: 0,
+            retain,
+        }
+    }
+
+    /// Returns the number of nonces currently being tracked.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether no nonces are currently being tracked.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<const N: usize> Default for RecentNonces<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> NonceValidator for RecentNonces<N> {
+    fn accept(&mut self, nonce: &Nonce) -> bool {
+        if self.seen[..self.len].iter().any(|seen| seen == nonce) {
+            return false;
+        }
+        if N == 0 {
+            return true;
+        }
+
+        self.seen[self.next] = *nonce;
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+        true
+    }
+
+    fn rotate(&mut self) {
+        let keep = self.retain.min(self.len);
+        if keep == 0 {
+            self.len = 0;
+            self.next = 0;
+            return;
+        }
+
+        // Move the `keep` most recent nonces to the front, oldest first.
+        let mut kept = [Nonce([0; NONCE_LEN]); N];
+        for (i, slot) in kept[..keep].iter_mut().enumerate() {
+            *slot = self.seen[(self.next + N - keep + i) % N];
+        }
+        self.seen = kept;
+        self.len = keep;
+        self.next = keep % N;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A `Csrng` that counts up from zero.
+    struct Counter(u8);
+
+    impl Csrng for Counter {
+        fn fill(&mut self, buf: &mut [u8]) -> Result<(), csrng::Error> {
+            for b in buf {
+                *b = self.0;
+                self.0 = self.0.wrapping_add(1);
+            }
+            Ok(())
+        }
+    }
+
+    /// A `Csrng` whose health tests always fail.
+    struct Broken;
+
+    impl Csrng for Broken {
+        fn fill(&mut self, buf: &mut [u8]) -> Result<(), csrng::Error> {
+            for b in buf {
+                *b = 0;
+            }
+            Ok(())
+        }
+
+        fn health_check(&mut self) -> Result<(), csrng::Error> {
+            Err(fail!(csrng::Error::Unhealthy))
+        }
+    }
+
+    #[test]
+    fn unhealthy_csrng() {
+        assert_eq!(
+            Broken.fresh().unwrap_err().into_inner(),
+            Error::Csrng(csrng::Error::Unhealthy)
+        );
+    }
+
+    #[test]
+    fn csrng_source() {
+        let mut rng = Counter(0);
+        let nonce = rng.fresh().unwrap();
+        assert_eq!(nonce.0[0], 0);
+        assert_eq!(nonce.0[31], 31);
+        assert_eq!(rng.0, 32);
+
+        // Fresh nonces pass validation.
+        let mut nonces = RecentNonces::<2>::new();
+        assert!(nonces.accept(&nonce));
+        assert!(nonces.accept(&rng.fresh().unwrap()));
+    }
+
+    #[test]
+    fn rejects_replay() {
+        let mut nonces = RecentNonces::<4>::new();
+        assert!(nonces.accept(&Nonce([1; 32])));
+        assert!(nonces.accept(&Nonce([2; 32])));
+        assert!(!nonces.accept(&Nonce([1; 32])));
+        assert!(!nonces.accept(&Nonce([2; 32])));
+        assert!(nonces.accept(&Nonce([3; 32])));
+    }
+
+    #[test]
+    fn forgets_oldest() {
+        let mut nonces = RecentNonces::<2>::new();
+        assert!(nonces.accept(&Nonce([1; 32])));
+        assert!(nonces.accept(&Nonce([2; 32])));
+        assert!(nonces.accept(&Nonce([3; 32])));
+        assert!(!nonces.accept(&Nonce([2; 32])));
+        assert!(nonces.accept(&Nonce([1; 32])));
+    }
+
+    #[test]
+    fn rotate_forgets() {
+        let mut nonces = RecentNonces::<4>::new();
+        assert!(nonces.accept(&Nonce([1; 32])));
+        assert!(nonces.accept(&Nonce([2; 32])));
+
+        nonces.rotate();
+        assert!(nonces.is_empty());
+        assert!(nonces.accept(&Nonce([1; 32])));
+        assert!(!nonces.accept(&Nonce([1; 32])));
+    }
+
+    #[test]
+    fn rotate_retains() {
+        let mut nonces = RecentNonces::<3>::with_retain(2);
+        for i in 1..=4 {
+            assert!(nonces.accept(&Nonce([i; 32])));
+        }
+
+        nonces.rotate();
+        assert_eq!(nonces.len(), 2);
+        assert!(!nonces.accept(&Nonce([4; 32])));
+        assert!(!nonces.accept(&Nonce([3; 32])));
+        assert!(nonces.accept(&Nonce([2; 32])));
+
+        // The retained nonces are still forgotten oldest-first.
+        assert!(nonces.accept(&Nonce([5; 32])));
+        assert!(nonces.accept(&Nonce([3; 32])));
+        assert!(!nonces.accept(&Nonce([2; 32])));
+    }
+}
@@ -7,22 +7,55 @@
 //! This module provides structures for serving responses to a host making
 //! requests to a PA-RoT.
 
+use core::time::Duration;
+
 use crate::cert;
 use crate::crypto::csrng;
 use crate::crypto::hash;
 use crate::crypto::hash::EngineExt as _;
 use crate::crypto::sig;
 use crate::hardware;
+use crate::hardware::clock;
+use crate::hardware::clock::Clock;
+use crate::hardware::clock::Deadline;
+use crate::hardware::clock::Ticks;
+use crate::hardware::flash::Ram;
+use crate::io::ReadZero;
+use crate::manifest;
+use crate::manifest::cfm::Cfm;
+use crate::manifest::pcd::Pcd;
+use crate::manifest::pfm::Pfm;
+use crate::manifest::ManifestType;
 use crate::mem::Arena;
 use crate::mem::ArenaExt as _;
+use crate::mem::ArenaVec;
 use crate::net;
+use crate::net::host::HostRequest;
+use crate::net::host::HostRequestExt as _;
+use crate::net::host::HostResponse;
 use crate::net::CerberusHeader;
 use crate::net::SpdmHeader;
 use crate::protocol::cerberus;
 use crate::protocol::spdm;
+use crate::protocol::Nonce;
 use crate::protocol::Req;
 use crate::protocol::Resp;
+use crate::protocol::Slot;
+use crate::server::ConfigReset;
+use crate::server::DeviceState;
 use crate::server::Error;
+use crate::server::HostControl;
+use crate::server::LogStore;
+use crate::server::ManifestStore;
+use crate::server::NonceSource as _;
+use crate::server::NonceValidator;
+use crate::server::ParseOptions;
+use crate::server::RateLimiter;
+use crate::server::Timer;
+use crate::server::Unsealer;
+use crate::server::VendorHandler;
+use crate::server::Verifications;
+use crate::session;
 use crate::session::Session;
 use crate::Result;
 
@@ -35,6 +68,14 @@ pub struct Options<'a> {
     /// A handle for looking up reset-related information for the current
     /// device.
     pub reset: &'a dyn hardware::Reset,
+    /// The time source for rate limits and session expiry.
+    pub clock: &'a dyn Clock,
+    /// Whether to include a signed timestamp from [`Options::clock`] in
+    /// `Challenge` responses, for requesters that ask for one.
+    ///
+    /// This should only be enabled if `clock` cannot be rewound or paused by
+    /// an attacker; see [`cerberus::challenge`] for more information.
+    pub signed_timestamps: bool,
 
     /// A handle to a hashing engine.
     pub hasher: &'a mut dyn hash::Engine,
@@ -47,6 +88,27 @@ pub struct Options<'a> {
 
     /// The session manager.
     pub session: &'a mut dyn Session,
+    /// How long a session may remain active after its key exchange.
+    ///
+    /// Once this has elapsed, the session is destroyed before the next request
+    /// is processed. If `None`, sessions never expire.
+    pub session_lifetime: Option<Duration>,
+    /// The anti-replay policy for challenge nonces.
+    pub nonces: &'a mut dyn NonceValidator,
+    /// Handlers for vendor-defined messages, looked up by vendor ID.
+    ///
+    /// `VendorCommand`s whose vendor ID matches none of these are rejected
+    /// as unsupported.
+    pub vendor: &'a mut [&'a mut dyn VendorHandler],
+    /// The rate-limiting policy, if any.
+    ///
+    /// Requests that this refuses are rejected as busy before being parsed.
+    pub rate_limiter: Option<&'a mut dyn RateLimiter>,
+    /// A sink for how long each phase of handling a Cerberus request takes,
+    /// as measured by [`Options::clock`], if any.
+    ///
+    /// SPDM requests are not timed.
+    pub timer: Option<&'a mut dyn Timer>,
 
     /// The value of PMR0.
     ///
@@ -54,13 +116,76 @@ pub struct Options<'a> {
     /// trait.
     pub pmr0: &'a [u8],
 
+    /// The device's logs, served by `GetLog` and erased by `ClearLog`.
+    pub logs: &'a mut dyn LogStore,
+    /// The device's runtime state, served by `GetDeviceState`.
+    pub device_state: &'a dyn DeviceState,
+    /// The device's resettable configuration, if `ResetConfig` is
+    /// supported.
+    pub config_reset: Option<&'a mut dyn ConfigReset>,
+    /// The host's reset line, if `GetHostState` and `SetHostState` are
+    /// supported.
+    ///
+    /// Only peers within an encrypted session that hold the key returned by
+    /// [`HostControl::authority()`] may change the host's state. This device
+    /// only protects a single port, port 0.
+    pub host_control: Option<&'a mut dyn HostControl>,
+    /// The device's PFMs, if `PreparePfm`, `UpdatePfm`, and `ActivatePfm`
+    /// are supported.
+    ///
+    /// This device only protects a single port, port 0.
+    pub pfm_store: Option<&'a mut dyn ManifestStore>,
+    /// The key that PFMs must be signed with, if they are supported.
+    ///
+    /// A PFM is only staged once its signature has been verified with this.
+    pub pfm_authority: Option<&'a mut dyn sig::Verify>,
+    /// The device's CFMs, if `PrepareCfm`, `UpdateCfm`, and `ActivateCfm`
+    /// are supported.
+    pub cfm_store: Option<&'a mut dyn ManifestStore>,
+    /// The key that CFMs must be signed with, if they are supported.
+    ///
+    /// A CFM is only staged once its signature has been verified with this.
+    pub cfm_authority: Option<&'a mut dyn sig::Verify>,
+    /// The device's PCDs, if `PreparePcd`, `UpdatePcd`, and `ActivatePcd`
+    /// are supported.
+    pub pcd_store: Option<&'a mut dyn ManifestStore>,
+    /// The key that PCDs must be signed with, if they are supported.
+    ///
+    /// A PCD is only staged once its signature has been verified with this.
+    pub pcd_authority: Option<&'a mut dyn sig::Verify>,
+    /// The device's sealed-key engine, if `Unseal` is supported.
+    ///
+    /// Keys are only released to peers within a session, and only if
+    /// the PCRs in [`Options::logs`] satisfy the request's policy.
+    pub unsealer: Option<&'a mut dyn Unsealer>,
+    /// The device's firmware verifications in progress, if `BeginVerify`,
+    /// `VerifyChunk`, and `FinishVerify` are supported.
+    pub fw_verify: Option<Verifications<'a>>,
+
     /// This device's silicon identifier.
     pub device_id: cerberus::device_id::DeviceIdentifier,
     /// Integration-provided description of the device's networking
     /// capabilities.
     pub networking: cerberus::capabilities::Networking,
+    /// The protocol versions this device accepts from peers during
+    /// capabilities negotiation.
+    ///
+    /// A peer advertising any other version is refused with
+    /// [`cerberus::Error::UnsupportedVersion`], as are its requests until it
+    /// negotiates an accepted version. A peer that never negotiates is taken
+    /// to speak [`ProtocolVersion::CURRENT`], and so is only served if that
+    /// version is accepted. Most integrations should accept only
+    /// [`ProtocolVersion::CURRENT`].
+    ///
+    /// [`ProtocolVersion::CURRENT`]: cerberus::capabilities::ProtocolVersion::CURRENT
+    pub protocol_versions: &'a [cerberus::capabilities::ProtocolVersion],
     /// Integration-provided "acceptable timeout" lengths.
     pub timeouts: cerberus::capabilities::Timeouts,
+    /// How strictly to decode incoming requests.
+    ///
+    /// The default tolerates unknown trailing fields; conformance tests
+    /// should use [`ParseOptions::STRICT`].
+    pub parse_options: ParseOptions,
 }
 
 /// A PA-RoT, or "Platform Root of Trust", server.
@@ -85,19 +210,122 @@ pub struct PaRot<'a> {
     ///
     /// Note that this is *only* changed when the most recent `GetDigests`
     /// indicated a forthcoming key exchange.
-    current_cert_slot: Option<u8>,
+    current_cert_slot: Option<Slot>,
+
+    /// The time at which the current session was established, if there is
+    /// one.
+    session_start: Option<Ticks>,
+
+    /// The protocol version the peer speaks, as accepted during capabilities
+    /// negotiation.
+    ///
+    /// This is `None` if the peer's version was refused, or if it has not
+    /// negotiated and [`ProtocolVersion::CURRENT`] is not accepted; no
+    /// requests but `DeviceCapabilities` are served until it is set.
+    ///
+    /// [`ProtocolVersion::CURRENT`]: cerberus::capabilities::ProtocolVersion::CURRENT
+    protocol_version: Option<cerberus::capabilities::ProtocolVersion>,
+
+    /// The nonce most recently handed out by a command that requires
+    /// authorization, such as `ResetConfig`, along with that command; the
+    /// next authorized request for it must sign the nonce.
+    ///
+    /// Only one nonce is outstanding at a time, and it is discarded after one
+    /// use.
+    auth_nonce: Option<(cerberus::CommandType, Nonce)>,
+
+    /// How many bytes of the manifest of each type being received have
+    /// arrived so far.
+    pfm_received: usize,
+    cfm_received: usize,
+    pcd_received: usize,
+
+    /// When the response to the request being processed is due, based on the
+    /// crypto timeout advertised in [`Options::timeouts`]; this is reset by
+    /// [`PaRot::process_request()`] as soon as a request is received.
+    ///
+    /// Handlers that perform several expensive operations should call
+    /// [`check_deadline()`] before each one, so that a slow engine
+    /// produces a busy error the peer can retry on, rather than a reply that
+    /// arrives after the peer has given up. The check must come before any
+    /// state is changed, since a busy request is expected to be retried.
+    deadline: Deadline,
+}
+
+/// A manifest store, along with what is needed to receive a manifest into it.
+struct Staging<'s> {
+    store: &'s mut dyn ManifestStore,
+    authority: &'s mut dyn sig::Verify,
+    hasher: &'s mut dyn hash::Engine,
+    received: &'s mut usize,
 }
 
 impl<'a> PaRot<'a> {
     /// Create a new `PaRot` with the given `Options`.
     pub fn new(opts: Options<'a>) -> Self {
+        use cerberus::capabilities::ProtocolVersion;
+        let protocol_version = Some(ProtocolVersion::CURRENT)
+            .filter(|v| opts.protocol_versions.contains(v));
         Self {
             opts,
             ok_count: 0,
             err_count: 0,
             key_exchange: None,
             current_cert_slot: None,
+            session_start: None,
+            protocol_version,
+            auth_nonce: None,
+            pfm_received: 0,
+            cfm_received: 0,
+            pcd_received: 0,
+            deadline: Deadline::NEVER,
+        }
+    }
+
+    /// Destroys the current session if it has outlived
+    /// [`Options::session_lifetime`].
+    fn expire_session(&mut self) {
+        let (start, lifetime) =
+            match (self.session_start, self.opts.session_lifetime) {
+                (Some(start), Some(lifetime)) => (start, lifetime),
+                _ => return,
+            };
+        let now = self.opts.clock.now();
+        if now.saturating_sub(start) < clock::ticks(lifetime) {
+            return;
         }
+
+        info!("session expired; destroying it");
+        let _ = self.opts.session.destroy_session();
+        self.session_start = None;
+    }
+
+    /// Returns the protocol version that requests are currently being served
+    /// at, if any; see [`Options::protocol_versions`].
+    pub fn protocol_version(
+        &self,
+    ) -> Option<cerberus::capabilities::ProtocolVersion> {
+        self.protocol_version
+    }
+
+    /// Starts a new `Challenge` anti-replay window.
+    ///
+    /// Integrations should call this on a timer, or after some number of
+    /// challenges; see [`NonceValidator::rotate()`].
+    pub fn rotate_nonces(&mut self) {
+        self.opts.nonces.rotate();
+    }
+
+    /// Runs the health tests of this server's random number generator.
+    ///
+    /// Integrations should call this at startup, before serving any requests,
+    /// and periodically thereafter; see [`csrng::Csrng::health_check()`].
+    /// Regardless, the generator is checked each time this server needs fresh
+    /// randomness, whether for a nonce, a key exchange, or an encrypted reply,
+    /// and requests that need it are answered with [`cerberus::Error::Busy`]
+    /// while it is unhealthy.
+    pub fn check_rng_health(&mut self) -> Result<(), csrng::Error> {
+        self.opts.csrng.health_check()
     }
 
     /// Process a single incoming request.
@@ -106,14 +334,67 @@ impl<'a> PaRot<'a> {
         host_port: &mut dyn net::host::HostPort<'req, CerberusHeader>,
         arena: &'req dyn Arena,
     ) -> Result<(), Error<CerberusHeader>> {
+        let request = match host_port.receive() {
+            Ok(request) => request,
+            Err(e) => {
+                self.err_count += 1;
+                return Err(e.into());
+            }
+        };
+        // Now that there is a request to serve, drop the session if it has
+        // run out, so that the request cannot make use of it.
+        self.expire_session();
+        // The reply is due a crypto timeout after the request arrived.
+        self.deadline =
+            Deadline::after(self.opts.clock, self.opts.timeouts.crypto);
+
+        // An encrypted request is decrypted up front, so that every handler
+        // parses its payload the same way; handlers that only accept
+        // encrypted requests check `Context::encrypted`.
+        let mut decrypted;
+        let request: &mut dyn HostRequest<'req, CerberusHeader> =
+            if !request.is_encrypted() {
+                request
+            } else {
+                let result = match request.payload() {
+                    Ok(r) => self.decrypt_payload(r, arena),
+                    Err(e) => {
+                        self.err_count += 1;
+                        return Err(e.into());
+                    }
+                };
+                match result {
+                    Ok(payload) => {
+                        decrypted = Decrypted {
+                            inner: request,
+                            payload,
+                        };
+                        &mut decrypted
+                    }
+                    Err(e) => {
+                        self.err_count += 1;
+                        let command = request.header()?.command;
+                        request.reply_error(e.into_inner())?;
+                        return Err(fail!(Error::Refused(command)));
+                    }
+                }
+            };
+
+        // The timer is lent to the handler for the duration of the request,
+        // since the handler also needs `self`.
+        let mut lent_timer = self.opts.timer.take();
+        let timer = lent_timer.as_mut().map(|t| &mut **t as &mut dyn Timer);
+
         // Style note: when defining a new handler, if it is more than a
         // handful of lines long, define it out-of-line instead.
         let result = Handler::<&mut Self, CerberusHeader>::new()
+            .with_parse_options(self.opts.parse_options)
+            .with_timer(self.opts.clock, timer)
             .handle::<cerberus::FirmwareVersion, _>(|ctx| {
                 ctx.server.handle_fw_version(&ctx.req)
             })
             .handle::<cerberus::DeviceCapabilities, _>(|ctx| {
-                ctx.server.handle_capabilities(&ctx.req)
+                ctx.server.handle_capabilities(&ctx.req, ctx.registry)
             })
             .handle::<cerberus::DeviceId, _>(|ctx| {
                 Ok(Resp::<cerberus::DeviceId> {
@@ -166,7 +447,158 @@ impl<'a> PaRot<'a> {
                     err_count: ctx.server.err_count,
                 })
             })
-            .run(self, host_port, arena);
+            .handle::<cerberus::GetLog, _>(|ctx| {
+                ctx.server.handle_log(&ctx.req)
+            })
+            .handle::<cerberus::ClearLog, _>(|ctx| {
+                ctx.server.handle_clear_log(ctx.arena, &ctx.req)
+            })
+            .handle::<cerberus::ResetConfig, _>(|ctx| {
+                ctx.server.handle_reset_config(ctx.arena, &ctx.req)
+            })
+            .handle::<cerberus::GetHostState, _>(|ctx| {
+                // NOTE: Currently, we only handle port 0, the "self" port.
+                check!(ctx.req.port_id == 0, cerberus::Error::OutOfRange);
+                let host = ctx
+                    .server
+                    .opts
+                    .host_control
+                    .as_ref()
+                    .ok_or(cerberus::Error::UnsupportedCommand)?;
+                Ok(Resp::<cerberus::GetHostState> {
+                    host_reset_state: host.state(),
+                })
+            })
+            .handle::<cerberus::SetHostState, _>(|ctx| {
+                ctx.server.handle_set_host_state(ctx.arena, &ctx.req)
+            })
+            .handle::<cerberus::PreparePfm, _>(|ctx| {
+                // NOTE: Currently, we only handle port 0, the "self" port.
+                check!(ctx.req.port_id == 0, cerberus::Error::OutOfRange);
+                ctx.server.handle_prepare_manifest(
+                    ManifestType::Pfm,
+                    ctx.req.total_len,
+                )?;
+                Ok(Resp::<cerberus::PreparePfm> {})
+            })
+            .handle::<cerberus::UpdatePfm, _>(|ctx| {
+                check!(ctx.req.port_id == 0, cerberus::Error::OutOfRange);
+                ctx.server.handle_update_manifest(
+                    ManifestType::Pfm,
+                    ctx.arena,
+                    ctx.req.offset as usize,
+                    ctx.req.data,
+                )?;
+                Ok(Resp::<cerberus::UpdatePfm> {})
+            })
+            .handle::<cerberus::ActivatePfm, _>(|ctx| {
+                check!(ctx.req.port_id == 0, cerberus::Error::OutOfRange);
+                ctx.server.handle_activate_manifest(ManifestType::Pfm)?;
+                Ok(Resp::<cerberus::ActivatePfm> {})
+            })
+            .handle::<cerberus::PrepareCfm, _>(|ctx| {
+                ctx.server.handle_prepare_manifest(
+                    ManifestType::Cfm,
+                    ctx.req.total_len,
+                )?;
+                Ok(Resp::<cerberus::PrepareCfm> {})
+            })
+            .handle::<cerberus::UpdateCfm, _>(|ctx| {
+                ctx.server.handle_update_manifest(
+                    ManifestType::Cfm,
+                    ctx.arena,
+                    ctx.req.offset as usize,
+                    ctx.req.data,
+                )?;
+                Ok(Resp::<cerberus::UpdateCfm> {})
+            })
+            .handle::<cerberus::ActivateCfm, _>(|ctx| {
+                ctx.server.handle_activate_manifest(ManifestType::Cfm)?;
+                Ok(Resp::<cerberus::ActivateCfm> {})
+            })
+            .handle::<cerberus::PreparePcd, _>(|ctx| {
+                ctx.server.handle_prepare_manifest(
+                    ManifestType::Pcd,
+                    ctx.req.total_len,
+                )?;
+                Ok(Resp::<cerberus::PreparePcd> {})
+            })
+            .handle::<cerberus::UpdatePcd, _>(|ctx| {
+                ctx.server.handle_update_manifest(
+                    ManifestType::Pcd,
+                    ctx.arena,
+                    ctx.req.offset as usize,
+                    ctx.req.data,
+                )?;
+                Ok(Resp::<cerberus::UpdatePcd> {})
+            })
+            .handle::<cerberus::ActivatePcd, _>(|ctx| {
+                ctx.server.handle_activate_manifest(ManifestType::Pcd)?;
+                Ok(Resp::<cerberus::ActivatePcd> {})
+            })
+            .handle::<cerberus::GetConfigIds, _>(|ctx| {
+                ctx.server.handle_config_ids(ctx.arena, &ctx.req)
+            })
+            .handle::<cerberus::GetDeviceState, _>(|ctx| {
+                Ok(Resp::<cerberus::GetDeviceState> {
+                    state: ctx.server.opts.device_state.state(),
+                })
+            })
+            .handle::<cerberus::Unseal, _>(|ctx| {
+                ctx.server.handle_unseal(ctx.arena, &ctx.req, ctx.encrypted)
+            })
+            .handle::<cerberus::BeginVerify, _>(|ctx| {
+                let (verifications, clock) = ctx.server.fw_verify()?;
+                let handle = verifications.begin(
+                    clock,
+                    ctx.req.image_len as usize,
+                    ctx.req.expected,
+                )?;
+                Ok(Resp::<cerberus::BeginVerify> { handle })
+            })
+            .handle::<cerberus::VerifyChunk, _>(|ctx| {
+                let (verifications, clock) = ctx.server.fw_verify()?;
+                verifications.feed(
+                    clock,
+                    ctx.req.handle,
+                    ctx.req.offset as usize,
+                    ctx.req.data,
+                )?;
+                Ok(Resp::<cerberus::VerifyChunk> {})
+            })
+            .handle::<cerberus::FinishVerify, _>(|ctx| {
+                let (verifications, clock) = ctx.server.fw_verify()?;
+                let verified = verifications.finish(clock, ctx.req.handle)?;
+                Ok(Resp::<cerberus::FinishVerify> { verified })
+            })
+            .handle::<cerberus::VendorCommand, _>(|ctx| {
+                ctx.server.handle_vendor(ctx.arena, &ctx.req)
+            })
+            .run_received(
+                self,
+                request,
+                arena,
+                |_| cerberus::Error::UnsupportedCommand,
+                |server, command| {
+                    // A peer whose version was refused must renegotiate
+                    // before anything else; it is not rate limited, since
+                    // nothing it asks for is served.
+                    if server.protocol_version.is_none()
+                        && command != cerberus::CommandType::DeviceCapabilities
+                    {
+                        return Some(cerberus::Error::UnsupportedVersion);
+                    }
+
+                    let now = server.opts.clock.now();
+                    let limiter = server.opts.rate_limiter.as_mut()?;
+                    if limiter.admit(command, now) {
+                        return None;
+                    }
+                    Some(cerberus::Error::Busy)
+                },
+            );
+
+        self.opts.timer = lent_timer;
 
         match result {
             Ok(_) => self.ok_count += 1,
@@ -175,6 +607,89 @@ impl<'a> PaRot<'a> {
         result
     }
 
+    /// Processes `n` requests, one after the other, resetting `arena` after
+    /// each one.
+    ///
+    /// A request that fails to be processed, or even received, still counts
+    /// towards `n`; its error is logged and counted like any other failed
+    /// request, since the peer has already been replied to or has gone away.
+    /// Only [`net::Error::Shutdown`] is returned, as soon as `host_port` is
+    /// shut down.
+    pub fn serve_n<P, A>(
+        &mut self,
+        host_port: &mut P,
+        arena: &mut A,
+        n: usize,
+    ) -> Result<(), Error<CerberusHeader>>
+    where
+        P: for<'req> net::host::HostPort<'req, CerberusHeader> + ?Sized,
+        A: Arena,
+    {
+        for _ in 0..n {
+            self.serve_one(host_port, arena)?;
+        }
+        Ok(())
+    }
+
+    /// Processes requests until `done` returns `true` for the command type of
+    /// one of them, resetting `arena` after each one.
+    ///
+    /// Errors are handled as in [`PaRot::serve_n()`]; `done` is not consulted
+    /// when no request could be received at all.
+    pub fn serve_until<P, A>(
+        &mut self,
+        host_port: &mut P,
+        arena: &mut A,
+        mut done: impl FnMut(cerberus::CommandType) -> bool,
+    ) -> Result<(), Error<CerberusHeader>>
+    where
+        P: for<'req> net::host::HostPort<'req, CerberusHeader> + ?Sized,
+        A: Arena,
+    {
+        loop {
+            if let Some(command) = self.serve_one(host_port, arena)? {
+                if done(command) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Processes a single request and resets `arena`, returning the request's
+    /// command type, if one was received.
+    ///
+    /// Errors other than a shutdown have already been counted by
+    /// [`PaRot::process_request()`], and are only logged.
+    fn serve_one<P, A>(
+        &mut self,
+        host_port: &mut P,
+        arena: &mut A,
+    ) -> Result<Option<cerberus::CommandType>, Error<CerberusHeader>>
+    where
+        P: for<'req> net::host::HostPort<'req, CerberusHeader> + ?Sized,
+        A: Arena,
+    {
+        let mut port = PeekHeader {
+            port: host_port,
+            header: None,
+        };
+        let result = self.process_request(&mut port, &*arena);
+        arena.reset();
+
+        let command = port.header.map(|h| h.command);
+        match result {
+            Ok(()) => Ok(command),
+            Err(e) => {
+                // No more requests will arrive on a port that has shut down.
+                if matches!(e.as_ref(), Error::Network(net::Error::Shutdown)) {
+                    return Err(e);
+                }
+                warn!("failed to process request ({:?}): {}", command, e);
+                Ok(command)
+            }
+        }
+    }
+
     fn handle_fw_version(
         &mut self,
         req: &Req<cerberus::FirmwareVersion>,
@@ -196,24 +711,38 @@ impl<'a> PaRot<'a> {
     fn handle_capabilities(
         &mut self,
         req: &Req<cerberus::DeviceCapabilities>,
+        registry: Registry<cerberus::CommandType>,
     ) -> Result<Resp<cerberus::DeviceCapabilities>, cerberus::Error> {
         use cerberus::capabilities::*;
         use enumflags2::BitFlags;
+        let protocol_version = req.capabilities.protocol_version;
+        // A refused peer loses whatever version it negotiated before.
+        let accepted = self.opts.protocol_versions.contains(&protocol_version);
+        self.protocol_version = Some(protocol_version).filter(|_| accepted);
+        check!(accepted, cerberus::Error::UnsupportedVersion);
+
         let mut crypto = req.capabilities.crypto;
 
         self.opts.ciphers.negotiate(&mut crypto);
         crypto.has_aes = false;
         crypto.aes_strength = BitFlags::<AesKeyStrength>::empty();
+        crypto.hash_algos = HashAlgos::supported_by(self.opts.hasher);
+
+        let mut networking = self.opts.networking;
+        networking.has_compression &=
+            req.capabilities.networking.has_compression;
+        networking.has_streaming &= req.capabilities.networking.has_streaming;
 
         let capabilities = Capabilities {
-            networking: self.opts.networking,
-            security: BitFlags::<Security>::empty(),
+            networking,
+            security: Security::for_commands(|c| registry.handles(c)),
 
             has_pfm_support: false,
             has_policy_support: false,
             has_firmware_protection: false,
 
             crypto,
+            protocol_version,
         };
 
         Ok(Resp::<cerberus::DeviceCapabilities> {
@@ -230,7 +759,8 @@ impl<'a> PaRot<'a> {
         let digests_len = self
             .opts
             .trust_chain
-            .chain_len(req.slot)
+            .bounded_chain_len(req.slot)
+            .map_err(|_| cerberus::Error::ResourceLimit)?
             .ok_or(cerberus::Error::UnknownChain)?
             .get();
         let digests = arena
@@ -259,7 +789,8 @@ impl<'a> PaRot<'a> {
         let cert = self
             .opts
             .trust_chain
-            .cert(req.slot, req.cert_number as usize)
+            .bounded_cert(req.slot, req.cert_number as usize)
+            .map_err(|_| cerberus::Error::OutOfRange)?
             .ok_or(cerberus::Error::UnknownChain)?;
 
         let start = cert.raw().len().min(req.offset as usize);
@@ -280,13 +811,33 @@ impl<'a> PaRot<'a> {
         req: &Req<cerberus::Challenge>,
         req_buf: &[u8],
     ) -> Result<Resp<'req, cerberus::Challenge>, cerberus::Error> {
-        let signer = self
-            .opts
-            .trust_chain
-            .signer(req.slot)
-            .ok_or(cerberus::Error::UnknownChain)?;
-        let nonce = arena.alloc::<[u8; 32]>()?;
-        self.opts.csrng.fill(nonce)?;
+        let trust_chain = &mut *self.opts.trust_chain;
+        let signer = match req.key_type {
+            None => trust_chain
+                .signer(req.slot)
+                .ok_or(cerberus::Error::UnknownChain)?,
+            Some(key_type) => {
+                check!(
+                    trust_chain.chain_len(req.slot).is_some(),
+                    cerberus::Error::UnknownChain
+                );
+                trust_chain
+                    .signer_for(req.slot, key_type)
+                    .ok_or(cerberus::Error::UnsupportedKeyType)?
+                    .into_inner()
+            }
+        };
+
+        let nonce = arena.alloc::<Nonce>()?;
+        *nonce = self.opts.csrng.fresh()?;
+
+        // Only record the peer's nonce once we're committed to signing over
+        // it, so that a request that comes back busy can be retried as-is.
+        check_deadline(self.deadline, self.opts.clock)?;
+        check!(
+            self.opts.nonces.accept(req.nonce),
+            cerberus::Error::StaleNonce
+        );
 
         let tbs = cerberus::challenge::ChallengeResponseTbs {
             slot: req.slot,
@@ -295,11 +846,17 @@ impl<'a> PaRot<'a> {
             nonce,
             pmr0_components: 0,
             pmr0: self.opts.pmr0,
+            timestamp: if req.want_timestamp && self.opts.signed_timestamps {
+                Some(self.opts.clock.now())
+            } else {
+                None
+            },
         };
 
+        info!("signing challenge: key_bits = {:?}", signer.key_bits());
         let signature = arena.alloc_slice::<u8>(signer.sig_bytes())?;
-        let sig_len = tbs.as_iovec_with(|[a, b, c, d]| {
-            signer.sign(&[req_buf, a, b, c, d], signature)
+        let sig_len = tbs.as_iovec_with(|[a, b, c, d, e]| {
+            signer.sign(&[req_buf, a, b, c, d, e], signature)
         })?;
         let signature = &signature[..sig_len];
 
@@ -333,11 +890,24 @@ impl<'a> PaRot<'a> {
                     .signer(slot)
                     .ok_or(cerberus::Error::UnknownChain)?;
 
+                // Reject invalid points before generating any key material.
+                check!(
+                    self.opts.session.is_on_curve(pk_req),
+                    cerberus::Error::InvalidPublicKey
+                );
+
+                // Give up before touching the session, so that a busy reply
+                // leaves the current one, if any, intact.
+                check_deadline(self.deadline, self.opts.clock)?;
+                // The ephemeral key and the signature below are only as good
+                // as the device's entropy; fail closed if it is unhealthy.
+                self.opts.csrng.health_check()?;
                 let pk_resp =
                     arena.alloc_slice(self.opts.session.ephemeral_bytes())?;
                 let key_len = self.opts.session.begin_ecdh(pk_resp)?;
                 let pk_resp = &pk_resp[..key_len];
                 self.opts.session.finish_ecdh(*hmac_algorithm, pk_req)?;
+                self.session_start = Some(self.opts.clock.now());
 
                 let signature = arena.alloc_slice(signer.sig_bytes())?;
                 signer.sign(&[pk_req, pk_resp], signature)?;
@@ -345,7 +915,8 @@ impl<'a> PaRot<'a> {
                 let chain_len = self
                     .opts
                     .trust_chain
-                    .chain_len(slot)
+                    .bounded_chain_len(slot)
+                    .map_err(|_| cerberus::Error::ResourceLimit)?
                     .ok_or(cerberus::Error::OutOfRange)?
                     .get();
                 let alias_cert = self
@@ -377,6 +948,457 @@ impl<'a> PaRot<'a> {
         }
     }
 
+    fn handle_log(
+        &mut self,
+        req: &Req<cerberus::GetLog>,
+    ) -> Result<Resp<cerberus::GetLog>, cerberus::Error> {
+        let log = self
+            .opts
+            .logs
+            .read(req.log_type)
+            .ok_or(cerberus::Error::OutOfRange)?;
+        let start = log.len().min(req.offset as usize);
+        let end =
+            log.len().min(start.saturating_add(
+                self.opts.networking.max_message_size as usize,
+            ));
+        Ok(Resp::<cerberus::GetLog> {
+            data: &log[start..end],
+        })
+    }
+
+    fn handle_clear_log<'req>(
+        &mut self,
+        arena: &'req dyn Arena,
+        req: &Req<cerberus::ClearLog>,
+    ) -> Result<Resp<'req, cerberus::ClearLog>, cerberus::Error> {
+        use cerberus::clear_log::with_signed_message;
+
+        // A session alone does not authorize anything, since any peer may
+        // establish one. Clearing a log destroys evidence, so the peer must
+        // also hold the key that `LogStore::authority()` trusts for that log,
+        // which it proves by signing a nonce of ours.
+        check!(
+            self.opts.session.aes_key().is_some(),
+            cerberus::Error::SessionRequired
+        );
+        let token = match &req.authorization {
+            Some(token) => token,
+            None => {
+                let nonce = self
+                    .issue_auth_nonce(cerberus::CommandType::ClearLog, arena)?;
+                return Ok(Resp::<cerberus::ClearLog> { nonce: &nonce.0 });
+            }
+        };
+
+        self.redeem_auth_nonce(cerberus::CommandType::ClearLog, token.nonce)?;
+        let verifier = self
+            .opts
+            .logs
+            .authority(req.log_type)
+            .ok_or(cerberus::Error::OutOfRange)?;
+        let verified = with_signed_message(req.log_type, token.nonce, |msg| {
+            verifier.verify(msg, token.signature)
+        });
+        check!(verified.is_ok(), cerberus::Error::Unauthorized);
+
+        self.opts.logs.clear(req.log_type)?;
+        Ok(Resp::<cerberus::ClearLog> { nonce: &[] })
+    }
+
+    fn handle_set_host_state<'req>(
+        &mut self,
+        arena: &'req dyn Arena,
+        req: &Req<cerberus::SetHostState>,
+    ) -> Result<Resp<'req, cerberus::SetHostState>, cerberus::Error> {
+        use cerberus::set_host_state::with_signed_message;
+
+        check!(req.port_id == 0, cerberus::Error::OutOfRange);
+        check!(
+            self.opts.host_control.is_some(),
+            cerberus::Error::UnsupportedCommand
+        );
+
+        // Holding the host in reset takes it offline, and releasing it early
+        // may let it boot firmware that is still being recovered, so either
+        // direction needs the session *and* a signature from the key that
+        // `HostControl::authority()` trusts. The signature covers the
+        // requested state, so a nonce signed for one direction cannot be
+        // spent on the other.
+        check!(
+            self.opts.session.aes_key().is_some(),
+            cerberus::Error::SessionRequired
+        );
+        let token = match &req.authorization {
+            Some(token) => token,
+            None => {
+                let nonce = self.issue_auth_nonce(
+                    cerberus::CommandType::SetHostState,
+                    arena,
+                )?;
+                return Ok(Resp::<cerberus::SetHostState> { nonce: &nonce.0 });
+            }
+        };
+
+        self.redeem_auth_nonce(
+            cerberus::CommandType::SetHostState,
+            token.nonce,
+        )?;
+        let host = self
+            .opts
+            .host_control
+            .as_mut()
+            .ok_or(cerberus::Error::UnsupportedCommand)?;
+        let verifier = host.authority().ok_or(cerberus::Error::Unauthorized)?;
+        let verified = with_signed_message(
+            req.port_id,
+            req.hold_in_reset,
+            token.nonce,
+            |msg| verifier.verify(msg, token.signature),
+        );
+        check!(verified.is_ok(), cerberus::Error::Unauthorized);
+
+        if req.hold_in_reset {
+            host.assert_reset()?;
+        } else {
+            host.deassert_reset()?;
+        }
+        Ok(Resp::<cerberus::SetHostState> { nonce: &[] })
+    }
+
+    fn handle_reset_config<'req>(
+        &mut self,
+        arena: &'req dyn Arena,
+        req: &Req<cerberus::ResetConfig>,
+    ) -> Result<Resp<'req, cerberus::ResetConfig>, cerberus::Error> {
+        use cerberus::reset_config::with_signed_message;
+
+        check!(
+            self.opts.config_reset.is_some(),
+            cerberus::Error::UnsupportedCommand
+        );
+        let token = match &req.authorization {
+            Some(token) => token,
+            None => {
+                let nonce = self.issue_auth_nonce(
+                    cerberus::CommandType::ResetConfig,
+                    arena,
+                )?;
+                return Ok(Resp::<cerberus::ResetConfig> { nonce: &nonce.0 });
+            }
+        };
+
+        self.redeem_auth_nonce(
+            cerberus::CommandType::ResetConfig,
+            token.nonce,
+        )?;
+        let config = self
+            .opts
+            .config_reset
+            .as_mut()
+            .ok_or(cerberus::Error::UnsupportedCommand)?;
+        let verifier = config
+            .authority(req.kind)
+            .ok_or(cerberus::Error::OutOfRange)?;
+        let verified = with_signed_message(req.kind, token.nonce, |msg| {
+            verifier.verify(msg, token.signature)
+        });
+        check!(verified.is_ok(), cerberus::Error::Unauthorized);
+
+        config.reset(req.kind)?;
+        Ok(Resp::<cerberus::ResetConfig> { nonce: &[] })
+    }
+
+    /// Hands out a fresh nonce, which the next authorized request for
+    /// `command` must sign.
+    ///
+    /// This replaces any nonce handed out before, for any command.
+    fn issue_auth_nonce<'req>(
+        &mut self,
+        command: cerberus::CommandType,
+        arena: &'req dyn Arena,
+    ) -> Result<&'req Nonce, cerberus::Error> {
+        let nonce = arena.alloc::<Nonce>()?;
+        *nonce = self.opts.csrng.fresh()?;
+        self.auth_nonce = Some((command, *nonce));
+        Ok(nonce)
+    }
+
+    /// Checks that `nonce` is the one most recently handed out for `command`.
+    ///
+    /// Each nonce may only be used once, whether or not the signature over
+    /// it checks out.
+    fn redeem_auth_nonce(
+        &mut self,
+        command: cerberus::CommandType,
+        nonce: &Nonce,
+    ) -> Result<(), cerberus::Error> {
+        let expected = self.auth_nonce.take();
+        check!(
+            expected == Some((command, *nonce)),
+            cerberus::Error::Unauthorized
+        );
+        Ok(())
+    }
+
+    /// Returns everything needed to receive a manifest of type `ty`, if
+    /// manifests of that type are supported.
+    fn staging(
+        &mut self,
+        ty: ManifestType,
+    ) -> Result<Staging<'_>, cerberus::Error> {
+        let (store, authority, received) = match ty {
+            ManifestType::Pfm => (
+                &mut self.opts.pfm_store,
+                &mut self.opts.pfm_authority,
+                &mut self.pfm_received,
+            ),
+            ManifestType::Cfm => (
+                &mut self.opts.cfm_store,
+                &mut self.opts.cfm_authority,
+                &mut self.cfm_received,
+            ),
+            ManifestType::Pcd => (
+                &mut self.opts.pcd_store,
+                &mut self.opts.pcd_authority,
+                &mut self.pcd_received,
+            ),
+        };
+        match (store, authority) {
+            (Some(store), Some(authority)) => Ok(Staging {
+                store: *store,
+                authority: *authority,
+                hasher: &mut *self.opts.hasher,
+                received,
+            }),
+            _ => Err(fail!(cerberus::Error::UnsupportedCommand)),
+        }
+    }
+
+    fn handle_prepare_manifest(
+        &mut self,
+        ty: ManifestType,
+        total_len: u32,
+    ) -> Result<(), cerberus::Error> {
+        let staging = self.staging(ty)?;
+        staging.store.prepare(total_len as usize)?;
+        *staging.received = 0;
+        Ok(())
+    }
+
+    /// Writes `data` at `offset` into the manifest of type `ty` being
+    /// received, staging it once it is complete.
+    ///
+    /// Chunks must arrive in order, so `offset` must be exactly the number of
+    /// bytes received so far. A chunk sent when no manifest is being
+    /// received, that is lost, repeated or out of order, or that would run
+    /// past the length given to `Prepare`, is rejected with
+    /// [`cerberus::Error::OutOfRange`].
+    fn handle_update_manifest(
+        &mut self,
+        ty: ManifestType,
+        arena: &dyn Arena,
+        offset: usize,
+        data: &[u8],
+    ) -> Result<(), cerberus::Error> {
+        let Staging {
+            store,
+            authority,
+            hasher,
+            received,
+        } = self.staging(ty)?;
+
+        let buf = store.receiving().ok_or(cerberus::Error::OutOfRange)?;
+        check!(offset == *received, cerberus::Error::OutOfRange);
+        let end = received
+            .checked_add(data.len())
+            .ok_or(cerberus::Error::OutOfRange)?;
+        let chunk = buf
+            .get_mut(*received..end)
+            .ok_or(cerberus::Error::OutOfRange)?;
+        chunk.copy_from_slice(data);
+        *received = end;
+        if end < buf.len() {
+            return Ok(());
+        }
+
+        // The whole manifest is here; it may only be staged if it was
+        // signed by the authority for its type.
+        let flash = Ram(&*buf);
+        match ty {
+            ManifestType::Pfm => {
+                manifest::parse_and_verify::<Pfm>(
+                    &flash, hasher, authority, arena, arena,
+                )?;
+            }
+            ManifestType::Cfm => {
+                manifest::parse_and_verify::<Cfm>(
+                    &flash, hasher, authority, arena, arena,
+                )?;
+            }
+            ManifestType::Pcd => {
+                manifest::parse_and_verify::<Pcd>(
+                    &flash, hasher, authority, arena, arena,
+                )?;
+            }
+        }
+
+        store.stage()?;
+        Ok(())
+    }
+
+    fn handle_activate_manifest(
+        &mut self,
+        ty: ManifestType,
+    ) -> Result<(), cerberus::Error> {
+        self.staging(ty)?.store.activate()
+    }
+
+    fn handle_config_ids<'req>(
+        &mut self,
+        arena: &'req dyn Arena,
+        req: &Req<cerberus::GetConfigIds>,
+    ) -> Result<Resp<'req, cerberus::GetConfigIds>, cerberus::Error> {
+        use cerberus::get_config_ids::ConfigId;
+
+        let store = match req.manifest_type {
+            ManifestType::Pfm => self.opts.pfm_store.as_deref(),
+            ManifestType::Cfm => self.opts.cfm_store.as_deref(),
+            ManifestType::Pcd => self.opts.pcd_store.as_deref(),
+        }
+        .ok_or(cerberus::Error::OutOfRange)?;
+
+        let bytes = match store.active() {
+            Some(bytes) => bytes,
+            None => {
+                return Ok(Resp::<cerberus::GetConfigIds> {
+                    manifest_type: req.manifest_type,
+                    active: None,
+                })
+            }
+        };
+
+        // The active manifest was verified before it was staged.
+        let flash = Ram(bytes);
+        let version_id = match req.manifest_type {
+            ManifestType::Pfm => {
+                manifest::Container::<Pfm, _>::parse(&flash, arena)?
+                    .metadata()
+                    .version_id
+            }
+            ManifestType::Cfm => {
+                manifest::Container::<Cfm, _>::parse(&flash, arena)?
+                    .metadata()
+                    .version_id
+            }
+            ManifestType::Pcd => {
+                manifest::Container::<Pcd, _>::parse(&flash, arena)?
+                    .metadata()
+                    .version_id
+            }
+        };
+        let digest = arena.alloc::<[u8; 32]>()?;
+        self.opts
+            .hasher
+            .contiguous_hash(hash::Algo::Sha256, bytes, digest)?;
+
+        Ok(Resp::<cerberus::GetConfigIds> {
+            manifest_type: req.manifest_type,
+            active: Some(ConfigId { version_id, digest }),
+        })
+    }
+
+    fn handle_unseal<'req>(
+        &mut self,
+        arena: &'req dyn Arena,
+        req: &Req<cerberus::Unseal>,
+        encrypted: bool,
+    ) -> Result<Resp<'req, cerberus::Unseal>, cerberus::Error> {
+        let unsealer = self
+            .opts
+            .unsealer
+            .as_mut()
+            .ok_or(cerberus::Error::UnsupportedCommand)?;
+        // A released key must never be readable off the wire, so both the
+        // request and the key in the reply must be encrypted under the
+        // session; a request sent in the clear may have been tampered with.
+        check!(
+            self.opts.session.aes_key().is_some(),
+            cerberus::Error::SessionRequired
+        );
+        check!(encrypted, cerberus::Error::Unauthorized);
+
+        for (index, expected) in req.policy.pcrs() {
+            let actual = self
+                .opts
+                .logs
+                .pcr(index)
+                .ok_or(cerberus::Error::OutOfRange)?;
+            check!(actual == expected, cerberus::Error::Unauthorized);
+        }
+
+        let key = arena.alloc_slice::<u8>(
+            unsealer.max_key_len() + session::AES_TAG_LEN + session::AES_IV_LEN,
+        )?;
+        let key_len = unsealer.unseal(&req.policy, req.blob, key)?;
+
+        let mut iv = [0; session::AES_IV_LEN];
+        self.opts.csrng.health_check()?;
+        self.opts.csrng.fill(&mut iv)?;
+        let len =
+            session::seal_message(&mut *self.opts.session, &iv, key, key_len)?;
+        Ok(Resp::<cerberus::Unseal> { key: &key[..len] })
+    }
+
+    /// Reads the rest of an encrypted request `payload` into `arena`, and
+    /// decrypts it with the current session.
+    fn decrypt_payload<'req, R: ReadZero<'req> + ?Sized>(
+        &mut self,
+        payload: &mut R,
+        arena: &'req dyn Arena,
+    ) -> Result<&'req [u8], cerberus::Error> {
+        check!(
+            self.opts.session.aes_key().is_some(),
+            cerberus::Error::SessionRequired
+        );
+        let message = arena.alloc_slice::<u8>(payload.remaining_data())?;
+        payload.read_bytes(message)?;
+        let plaintext =
+            session::open_message(&mut *self.opts.session, message)?;
+        Ok(plaintext)
+    }
+
+    fn fw_verify(
+        &mut self,
+    ) -> Result<(&mut Verifications<'a>, &'a dyn Clock), cerberus::Error> {
+        let clock = self.opts.clock;
+        match &mut self.opts.fw_verify {
+            Some(verifications) => Ok((verifications, clock)),
+            None => Err(fail!(cerberus::Error::UnsupportedCommand)),
+        }
+    }
+
+    fn handle_vendor<'req>(
+        &mut self,
+        arena: &'req dyn Arena,
+        req: &Req<cerberus::VendorCommand>,
+    ) -> Result<Resp<'req, cerberus::VendorCommand>, cerberus::Error> {
+        let handler = self
+            .opts
+            .vendor
+            .iter_mut()
+            .find(|h| h.vendor_id() == req.vendor_id)
+            .ok_or(cerberus::Error::UnsupportedCommand)?;
+
+        let mut out = ArenaVec::new(arena);
+        handler.handle(req.subcommand, req.payload, &mut out)?;
+        Ok(Resp::<cerberus::VendorCommand> {
+            vendor_id: req.vendor_id,
+            subcommand: req.subcommand,
+            payload: out.into_slice(),
+        })
+    }
+
     /// Process a single incoming SPDM request.
     pub fn process_spdm_request<'req>(
         &mut self,
@@ -386,6 +1408,7 @@ impl<'a> PaRot<'a> {
         // Style note: when defining a new handler, if it is more than a
         // handful of lines long, define it out-of-line instead.
         let result = Handler::<&mut Self, SpdmHeader>::new()
+            .with_parse_options(self.opts.parse_options)
             .handle::<spdm::GetVersion, _>(|_| {
                 Ok(Resp::<spdm::GetVersion> {
                     versions: &[spdm::ExtendedVersion::MANTICORE],
@@ -394,7 +1417,9 @@ impl<'a> PaRot<'a> {
             .handle::<spdm::GetCaps, _>(|ctx| {
                 Ok(Resp::<spdm::GetCaps> {
                     crypto_timeout: ctx.server.opts.timeouts.crypto,
-                    caps: spdm::get_caps::Caps::manticore(),
+                    caps: spdm::get_caps::Caps::for_commands(|c| {
+                        ctx.registry.handles(c)
+                    }),
                     max_packet_size: ctx.server.opts.networking.max_packet_size
                         as u32,
                     max_message_size: ctx
@@ -405,7 +1430,9 @@ impl<'a> PaRot<'a> {
                         as u32,
                 })
             })
-            .run(self, host_port, arena);
+            .run_or_reject(self, host_port, arena, |command| {
+                spdm::Error::Unsupported { command }
+            });
 
         match result {
             Ok(_) => self.ok_count += 1,
@@ -414,3 +1441,71 @@ impl<'a> PaRot<'a> {
         result
     }
 }
+
+/// A [`net::host::HostPort`] that remembers the header of the last request
+/// received through it.
+struct PeekHeader<'p, P: ?Sized, H> {
+    port: &'p mut P,
+    header: Option<H>,
+}
+
+impl<'req, P, H> net::host::HostPort<'req, H> for PeekHeader<'_, P, H>
+where
+    P: net::host::HostPort<'req, H> + ?Sized,
+    H: Copy,
+{
+    fn receive(
+        &mut self,
+    ) -> Result<&mut dyn net::host::HostRequest<'req, H>, net::Error> {
+        let request = self.port.receive()?;
+        self.header = request.header().ok();
+        Ok(request)
+    }
+}
+
+/// An encrypted request, whose payload has been decrypted into an arena.
+struct Decrypted<'a, 'req> {
+    inner: &'a mut dyn HostRequest<'req, CerberusHeader>,
+    payload: &'req [u8],
+}
+
+impl<'req> HostRequest<'req, CerberusHeader> for Decrypted<'_, 'req> {
+    fn header(&self) -> Result<CerberusHeader, net::Error> {
+        self.inner.header()
+    }
+
+    fn payload(&mut self) -> Result<&mut dyn ReadZero<'req>, net::Error> {
+        Ok(&mut self.payload)
+    }
+
+    fn payload_len(&self) -> Option<usize> {
+        Some(self.payload.len())
+    }
+
+    fn reply_capacity(&self) -> Option<usize> {
+        self.inner.reply_capacity()
+    }
+
+    fn is_encrypted(&self) -> bool {
+        true
+    }
+
+    fn reply(
+        &mut self,
+        header: CerberusHeader,
+    ) -> Result<&mut dyn HostResponse<'req>, net::Error> {
+        self.inner.reply(header)
+    }
+}
+
+/// Returns a busy error if `deadline` has already passed.
+///
+/// This check is cooperative: it cannot interrupt an operation that is
+/// already underway.
+fn check_deadline(
+    deadline: Deadline,
+    clock: &dyn Clock,
+) -> Result<(), cerberus::Error> {
+    check!(!deadline.has_passed(clock), cerberus::Error::Busy);
+    Ok(())
+}
@@ -0,0 +1,458 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Device identity provisioning.
+//!
+//! On boot, a Cerberus RoT derives an alias key for the firmware it is about
+//! to run, and certifies it with its device key, DICE-style. The resulting
+//! alias certificate is issued by the subject of the device certificate, so
+//! that it chains to it.
+//!
+//! [`gen_alias_cert()`] builds such a certificate in the X.509 profile that
+//! [`Cert::parse()`] accepts: the alias key may only be used for digital
+//! signatures, and is not a CA.
+
+use crate::cert::Cert;
+use crate::cert::CertFormat;
+use crate::crypto::hash;
+use crate::crypto::sig;
+use crate::Result;
+
+/// DER-encoded OIDs used in alias certificates.
+mod oid {
+    /// `sha256WithRSAEncryption`, 1.2.840.113549.1.1.11.
+    pub const RSA_PKCS1_SHA256: &[u8] =
+        &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+    /// `rsaEncryption`, 1.2.840.113549.1.1.1.
+    pub const RSA_ENCRYPTION: &[u8] =
+        &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+    /// `id-sha256`, 2.16.840.1.101.3.4.2.1.
+    pub const SHA256: &[u8] =
+        &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+    /// `id-at-commonName`, 2.5.4.3.
+    pub const COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+    /// `id-ce-keyUsage`, 2.5.29.15.
+    pub const KEY_USAGE: &[u8] = &[0x55, 0x1d, 0x0f];
+    /// `id-ce-basicConstraints`, 2.5.29.19.
+    pub const BASIC_CONSTRAINTS: &[u8] = &[0x55, 0x1d, 0x13];
+    /// `tcg-dice-TcbInfo`, 2.23.133.5.4.1.
+    pub const TCG_DICE_TCB_INFO: &[u8] = &[0x67, 0x81, 0x05, 0x05, 0x04, 0x01];
+}
+
+/// DER tags used in alias certificates.
+mod tag {
+    pub const BOOLEAN: u8 = 0x01;
+    pub const INTEGER: u8 = 0x02;
+    pub const BIT_STRING: u8 = 0x03;
+    pub const OCTET_STRING: u8 = 0x04;
+    pub const NULL: u8 = 0x05;
+    pub const OID: u8 = 0x06;
+    pub const UTF8_STRING: u8 = 0x0c;
+    pub const UTC_TIME: u8 = 0x17;
+    pub const GENERALIZED_TIME: u8 = 0x18;
+    pub const SEQUENCE: u8 = 0x30;
+    pub const SET: u8 = 0x31;
+
+    /// Returns the tag for the constructed, context-specific field `n`.
+    pub const fn context(n: u8) -> u8 {
+        0xa0 | n
+    }
+}
+
+/// The maximum length of a certificate serial number, per RFC 5280.
+const MAX_SERIAL_LEN: usize = 20;
+
+/// The length of a GeneralizedTime, in the `YYYYMMDDHHMMSSZ` form RFC 5280
+/// requires.
+const TIME_LEN: usize = 15;
+
+/// The range of years that RFC 5280 requires be encoded as a UTCTime, rather
+/// than a GeneralizedTime.
+const UTC_YEARS: core::ops::Range<u32> = 1950..2050;
+
+/// An error returned by [`gen_alias_cert()`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Indicates that the device certificate was not an X.509 certificate
+    /// with an RSA key, or that the alias key was not an RSA key.
+    UnsupportedKey,
+    /// Indicates that one of the [`AliasFields`] was malformed.
+    BadField,
+    /// Indicates that signing the certificate failed.
+    Sig(sig::Error),
+}
+
+impl From<sig::Error> for Error {
+    fn from(e: sig::Error) -> Self {
+        Self::Sig(e)
+    }
+}
+
+debug_from!(Error => sig::Error);
+
+/// The fields of an alias certificate that are not derived from the keys
+/// involved.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AliasFields<'a> {
+    /// The certificate's serial number, in big-endian.
+    ///
+    /// This must be nonzero, and at most 20 bytes long once leading zeros are
+    /// stripped. Its first nonzero byte must not have its top bit set, since
+    /// [`Cert::parse()`] rejects serial numbers that need a leading zero to be
+    /// positive.
+    pub serial: &'a [u8],
+    /// The common name to give the certificate's subject.
+    pub common_name: &'a str,
+    /// The start of the certificate's validity period, as a GeneralizedTime
+    /// of the form `YYYYMMDDHHMMSSZ`.
+    ///
+    /// The year must be 1950 or later. Times before 2050 are encoded as a
+    /// UTCTime instead, as RFC 5280 requires.
+    pub not_before: &'a str,
+    /// The end of the certificate's validity period, in the same form as
+    /// `not_before`.
+    pub not_after: &'a str,
+    /// The SHA-256 digest of the firmware the alias key was derived for, if
+    /// it should be recorded in a TCG DICE `TcbInfo` extension.
+    pub fwid: Option<&'a [u8; hash::Algo::Sha256.bytes()]>,
+}
+
+/// Generates an alias certificate for `alias_pub`, issued by the subject of
+/// `device_cert`.
+///
+/// `signer` must be primed with the device key, that is, the private key
+/// for `device_cert`'s subject key; it is used to produce an RSA PKCS#1 v1.5
+/// signature with SHA-256.
+///
+/// Returns the DER-encoded certificate.
+pub fn gen_alias_cert(
+    signer: &mut dyn sig::Sign,
+    device_cert: &Cert,
+    alias_pub: &sig::PublicKeyParams,
+    fields: &AliasFields,
+) -> Result<Vec<u8>, Error> {
+    check!(
+        device_cert.format() == CertFormat::RiotX509,
+        Error::UnsupportedKey
+    );
+    check!(
+        matches!(device_cert.subject_key(), sig::PublicKeyParams::Rsa { .. }),
+        Error::UnsupportedKey
+    );
+    let spki = match alias_pub {
+        sig::PublicKeyParams::Rsa { modulus, exponent } => tlv(
+            tag::SEQUENCE,
+            &[
+                &rsa_algo(oid::RSA_ENCRYPTION),
+                &bit_string(&tlv(
+                    tag::SEQUENCE,
+                    &[&integer(modulus), &integer(exponent)],
+                )),
+            ],
+        ),
+        _ => return Err(fail!(Error::UnsupportedKey)),
+    };
+
+    let serial = strip_zeros(fields.serial);
+    check!(
+        !serial.is_empty()
+            && serial.len() <= MAX_SERIAL_LEN
+            && serial[0] & 0x80 == 0,
+        Error::BadField
+    );
+    for time in &[fields.not_before, fields.not_after] {
+        let (digits, z) =
+            time.as_bytes().split_at(time.len().saturating_sub(1));
+        check!(
+            time.len() == TIME_LEN
+                && z == b"Z"
+                && digits.iter().all(u8::is_ascii_digit)
+                && year_of(time) >= UTC_YEARS.start,
+            Error::BadField
+        );
+    }
+
+    let sig_algo = rsa_algo(oid::RSA_PKCS1_SHA256);
+    let tbs = tlv(
+        tag::SEQUENCE,
+        &[
+            // Version: v3.
+            &tlv(tag::context(0), &[&integer(&[2])]),
+            &integer(serial),
+            &sig_algo,
+            &tlv(tag::SEQUENCE, &[device_cert.subject().as_bytes()]),
+            &tlv(
+                tag::SEQUENCE,
+                &[
+                    &validity_time(fields.not_before),
+                    &validity_time(fields.not_after),
+                ],
+            ),
+            &common_name(fields.common_name),
+            &spki,
+            &tlv(tag::context(3), &[&extensions(fields)]),
+        ],
+    );
+
+    let mut sig = vec![0; signer.sig_bytes()];
+    let sig_len = signer.sign(&[&tbs], &mut sig)?;
+    sig.truncate(sig_len);
+
+    Ok(tlv(tag::SEQUENCE, &[&tbs, &sig_algo, &bit_string(&sig)]))
+}
+
+/// Returns the year of a validated `YYYYMMDDHHMMSSZ` time.
+fn year_of(time: &str) -> u32 {
+    time.bytes()
+        .take(4)
+        .fold(0, |year, digit| year * 10 + u32::from(digit - b'0'))
+}
+
+/// Encodes a validated `YYYYMMDDHHMMSSZ` time as a `Time`, which is a UTCTime
+/// through 2049 and a GeneralizedTime thereafter.
+fn validity_time(time: &str) -> Vec<u8> {
+    if UTC_YEARS.contains(&year_of(time)) {
+        // UTCTime drops the century: `YYMMDDHHMMSSZ`.
+        tlv(tag::UTC_TIME, &[time[2..].as_bytes()])
+    } else {
+        tlv(tag::GENERALIZED_TIME, &[time.as_bytes()])
+    }
+}
+
+/// Builds the extensions of an alias certificate.
+fn extensions(fields: &AliasFields) -> Vec<u8> {
+    let mut extns = vec![
+        // digitalSignature, and nothing else. This is bit zero of the BIT
+        // STRING, so the other seven bits of its only byte are unused.
+        extension(oid::KEY_USAGE, true, &tlv(tag::BIT_STRING, &[&[7, 0x80]])),
+        // An empty basicConstraints means "not a CA".
+        extension(oid::BASIC_CONSTRAINTS, true, &tlv(tag::SEQUENCE, &[])),
+    ];
+    if let Some(fwid) = fields.fwid {
+        // DiceTcbInfo ::= SEQUENCE { ..., fwids [6] IMPLICIT FWIDLIST, ... }
+        // FWID ::= SEQUENCE { hashAlg OBJECT IDENTIFIER, digest OCTET STRING }
+        let fwid = tlv(
+            tag::SEQUENCE,
+            &[
+                &tlv(tag::OID, &[oid::SHA256]),
+                &tlv(tag::OCTET_STRING, &[fwid]),
+            ],
+        );
+        let tcb_info = tlv(tag::SEQUENCE, &[&tlv(tag::context(6), &[&fwid])]);
+        extns.push(extension(oid::TCG_DICE_TCB_INFO, false, &tcb_info));
+    }
+
+    let extns = extns.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    tlv(tag::SEQUENCE, &extns)
+}
+
+/// Encodes an X.509 `Extension`, with the DER-encoded value `value`.
+fn extension(oid: &[u8], critical: bool, value: &[u8]) -> Vec<u8> {
+    let oid = tlv(tag::OID, &[oid]);
+    let value = tlv(tag::OCTET_STRING, &[value]);
+    if critical {
+        tlv(
+            tag::SEQUENCE,
+            &[&oid, &tlv(tag::BOOLEAN, &[&[0xff]]), &value],
+        )
+    } else {
+        tlv(tag::SEQUENCE, &[&oid, &value])
+    }
+}
+
+/// Encodes a `Name` consisting of a single common name.
+fn common_name(cn: &str) -> Vec<u8> {
+    let atv = tlv(
+        tag::SEQUENCE,
+        &[
+            &tlv(tag::OID, &[oid::COMMON_NAME]),
+            &tlv(tag::UTF8_STRING, &[cn.as_bytes()]),
+        ],
+    );
+    tlv(tag::SEQUENCE, &[&tlv(tag::SET, &[&atv])])
+}
+
+/// Encodes an RSA `AlgorithmIdentifier`, whose parameters are always `NULL`.
+fn rsa_algo(oid: &[u8]) -> Vec<u8> {
+    tlv(
+        tag::SEQUENCE,
+        &[&tlv(tag::OID, &[oid]), &tlv(tag::NULL, &[])],
+    )
+}
+
+/// Encodes a `BIT STRING` with no unused bits.
+fn bit_string(bytes: &[u8]) -> Vec<u8> {
+    tlv(tag::BIT_STRING, &[&[0], bytes])
+}
+
+/// Encodes the big-endian, unsigned integer `be` as an `INTEGER`.
+fn integer(be: &[u8]) -> Vec<u8> {
+    let be = strip_zeros(be);
+    match be.first() {
+        None => tlv(tag::INTEGER, &[&[0]]),
+        // A set top bit would make the integer negative; DER disambiguates
+        // with a leading zero.
+        Some(&b) if b & 0x80 != 0 => tlv(tag::INTEGER, &[&[0], be]),
+        Some(_) => tlv(tag::INTEGER, &[be]),
+    }
+}
+
+/// Strips the leading zeros off of a big-endian integer.
+fn strip_zeros(be: &[u8]) -> &[u8] {
+    let start = be.iter().position(|&b| b != 0).unwrap_or(be.len());
+    &be[start..]
+}
+
+/// Encodes a tag-length-value triple, whose value is the concatenation of
+/// `parts`.
+fn tlv(tag: u8, parts: &[&[u8]]) -> Vec<u8> {
+    let len = parts.iter().map(|p| p.len()).sum::<usize>();
+    let mut out = Vec::with_capacity(len + 6);
+    out.push(tag);
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let len_bytes = len.to_be_bytes();
+        let len_bytes = strip_zeros(&len_bytes);
+        out.push(0x80 | len_bytes.len() as u8);
+        out.extend_from_slice(len_bytes);
+    }
+    for part in parts {
+        out.extend_from_slice(part);
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use testutil::data::keys;
+    use testutil::data::x509;
+
+    use crate::crypto::ring;
+
+    fn fields() -> AliasFields<'static> {
+        AliasFields {
+            serial: &[0x00, 0x1a, 0x11, 0xa5],
+            common_name: "Manticore Alias",
+            not_before: "20230101000000Z",
+            not_after: "99991231235959Z",
+            fwid: Some(&[0x5a; 32]),
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn chains_to_device() {
+        let mut ciphers = ring::sig::Ciphers::new();
+        let device_cert = Cert::parse(
+            x509::SELF_SIGNED,
+            CertFormat::RiotX509,
+            None,
+            &mut ciphers,
+        )
+        .unwrap();
+        let device_key = device_cert.subject_key().clone();
+
+        let mut signer =
+            ring::rsa::Sign256::from_pkcs8(keys::KEY1_RSA_KEYPAIR).unwrap();
+        let alias_pub = sig::PublicKeyParams::Rsa {
+            modulus: keys::KEY2_RSA_MOD,
+            exponent: keys::KEY2_RSA_EXP,
+        };
+
+        let raw =
+            gen_alias_cert(&mut signer, &device_cert, &alias_pub, &fields())
+                .unwrap();
+        let alias_cert = Cert::parse(
+            &raw,
+            CertFormat::RiotX509,
+            Some(&device_key),
+            &mut ciphers,
+        )
+        .unwrap();
+
+        // 2023 is encoded as a UTCTime; 9999 can only be a GeneralizedTime.
+        let contains =
+            |needle: &[u8]| raw.windows(needle.len()).any(|w| w == needle);
+        assert!(contains(b"\x17\x0d230101000000Z"));
+        assert!(contains(b"\x18\x0f99991231235959Z"));
+
+        assert_eq!(alias_cert.issuer(), device_cert.subject());
+        assert!(alias_cert.subject_key().eq_key(&alias_pub));
+        assert!(!alias_cert.supports_cert_signing());
+        assert_eq!(alias_cert.is_ca_cert(), Some(false));
+
+        // The certificate does not verify under any other key.
+        let other_key = sig::PublicKeyParams::Rsa {
+            modulus: keys::KEY3_RSA_MOD,
+            exponent: keys::KEY3_RSA_EXP,
+        };
+        assert!(Cert::parse(
+            &raw,
+            CertFormat::RiotX509,
+            Some(&other_key),
+            &mut ciphers
+        )
+        .is_err());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn bad_fields() {
+        let mut ciphers = ring::sig::Ciphers::new();
+        let device_cert = Cert::parse(
+            x509::SELF_SIGNED,
+            CertFormat::RiotX509,
+            None,
+            &mut ciphers,
+        )
+        .unwrap();
+        let mut signer =
+            ring::rsa::Sign256::from_pkcs8(keys::KEY1_RSA_KEYPAIR).unwrap();
+        let alias_pub = device_cert.subject_key().clone();
+
+        let bad = [
+            AliasFields {
+                serial: &[0, 0],
+                ..fields()
+            },
+            AliasFields {
+                serial: &[0xff; 21],
+                ..fields()
+            },
+            AliasFields {
+                serial: &[0x00, 0x9a],
+                ..fields()
+            },
+            AliasFields {
+                not_before: "19491231235959Z",
+                ..fields()
+            },
+            AliasFields {
+                not_before: "2023-01-01",
+                ..fields()
+            },
+            AliasFields {
+                not_after: "99991231235959",
+                ..fields()
+            },
+        ];
+        for f in &bad {
+            let err = gen_alias_cert(&mut signer, &device_cert, &alias_pub, f)
+                .unwrap_err();
+            assert_eq!(err.into_inner(), Error::BadField);
+        }
+
+        let ecc = sig::PublicKeyParams::Ecc {
+            curve: sig::Curve::NistP256,
+            x: &[1; 32],
+            y: &[2; 32],
+        };
+        let err = gen_alias_cert(&mut signer, &device_cert, &ecc, &fields())
+            .unwrap_err();
+        assert_eq!(err.into_inner(), Error::UnsupportedKey);
+    }
+}
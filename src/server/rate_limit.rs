@@ -0,0 +1,181 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Rate limiting for incoming requests.
+//!
+//! Some commands, such as `Challenge` and `KeyExchange`, are expensive to
+//! serve, and a peer that sends them in a tight loop can starve everyone
+//! else. Servers consult a [`RateLimiter`] with the command type of each
+//! request before dispatching it, and reply with a "busy" error to any
+//! request it refuses.
+
+use core::time::Duration;
+
+use crate::hardware::clock;
+use crate::hardware::clock::Ticks;
+use crate::protocol::cerberus::CommandType;
+
+/// A policy for deciding whether to serve a request.
+///
+/// Implementations that know which peer a request came from, such as by
+/// inspecting the transport, can use that to apply per-peer limits.
+pub trait RateLimiter {
+    /// Checks whether a request for `command` should be served, recording it
+    /// if so.
+    ///
+    /// `now` is the current time, as reported by the server's
+    /// [`Clock`](clock::Clock).
+    ///
+    /// Returns `false` if the request should be refused as busy.
+    fn admit(&mut self, command: CommandType, now: Ticks) -> bool;
+}
+impl dyn RateLimiter {} // Ensure object-safety.
+
+/// A limit on how often a single command type may be served.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Limit {
+    /// The command type this limit applies to.
+    pub command: CommandType,
+    /// The number of requests that may be served back-to-back.
+    pub burst: u32,
+    /// How long it takes to earn back the right to serve one more request.
+    pub period: Duration,
+}
+
+/// A [`RateLimiter`] that keeps a token bucket for each of `N` command types.
+///
+/// Each bucket starts out full, holding [`Limit::burst`] tokens, and gains a
+/// token every [`Limit::period`], up to that many. Serving a request takes a
+/// token from its command's bucket; requests for command types without a
+/// [`Limit`] are always served.
+pub struct TokenBuckets<const N: usize> {
+    limits: [Limit; N],
+    buckets: [Bucket; N],
+}
+
+#[derive(Copy, Clone)]
+struct Bucket {
+    tokens: u32,
+    /// The time at which `tokens` was last brought up to date.
+    last: Option<Ticks>,
+}
+
+impl<const N: usize> TokenBuckets<N> {
+    /// Creates a new `TokenBuckets` that enforces `limits`.
+    ///
+    /// If more than one limit names the same command type, the first one is
+    /// used.
+    pub fn new(limits: [Limit; N]) -> Self {
+        let mut buckets = [Bucket {
+            tokens: 0,
+            last: None,
+        }; N];
+        for (bucket, limit) in buckets.iter_mut().zip(limits.iter()) {
+            bucket.tokens = limit.burst;
+        }
+        Self { limits, buckets }
+    }
+
+    /// Returns the number of requests for `command` that may currently be
+    /// served back-to-back, or `None` if `command` is not limited.
+    pub fn tokens(&self, command: CommandType) -> Option<u32> {
+        let i = self.limits.iter().position(|l| l.command == command)?;
+        Some(self.buckets[i].tokens)
+    }
+}
+
+impl Bucket {
+    /// Adds any tokens earned under `limit` since the last refill.
+    fn refill(&mut self, limit: &Limit, now: Ticks) {
+        let last = match self.last {
+            Some(last) if self.tokens < limit.burst => last,
+            _ => {
+                self.last = Some(now);
+                return;
+            }
+        };
+
+        let elapsed = now.saturating_sub(last);
+        let period = clock::ticks(limit.period).max(1);
+        let earned = elapsed / period;
+        let missing = (limit.burst - self.tokens) as u64;
+        if earned >= missing {
+            self.tokens = limit.burst;
+            self.last = Some(now);
+        } else {
+            // Only count whole periods, so that partial progress towards the
+            // next token is not lost.
+            self.tokens += earned as u32;
+            self.last = Some(last + period * earned);
+        }
+    }
+}
+
+impl<const N: usize> RateLimiter for TokenBuckets<N> {
+    fn admit(&mut self, command: CommandType, now: Ticks) -> bool {
+        let i = match self.limits.iter().position(|l| l.command == command) {
+            Some(i) => i,
+            None => return true,
+        };
+
+        let bucket = &mut self.buckets[i];
+        bucket.refill(&self.limits[i], now);
+        if bucket.tokens == 0 {
+            return false;
+        }
+        bucket.tokens -= 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ms(ms: u64) -> Ticks {
+        clock::ticks(Duration::from_millis(ms))
+    }
+
+    #[test]
+    fn burst_then_refill() {
+        let mut limiter = TokenBuckets::new([Limit {
+            command: CommandType::Challenge,
+            burst: 2,
+            period: Duration::from_millis(100),
+        }]);
+
+        assert!(limiter.admit(CommandType::Challenge, ms(0)));
+        assert!(limiter.admit(CommandType::Challenge, ms(10)));
+        assert!(!limiter.admit(CommandType::Challenge, ms(20)));
+        assert_eq!(limiter.tokens(CommandType::Challenge), Some(0));
+
+        // The first token was taken at t = 0, so the next one is earned at
+        // t = 100.
+        assert!(!limiter.admit(CommandType::Challenge, ms(99)));
+        assert!(limiter.admit(CommandType::Challenge, ms(150)));
+        assert!(!limiter.admit(CommandType::Challenge, ms(180)));
+        assert!(limiter.admit(CommandType::Challenge, ms(200)));
+
+        // A long pause only refills up to the burst size.
+        assert!(limiter.admit(CommandType::Challenge, ms(10_000)));
+        assert!(limiter.admit(CommandType::Challenge, ms(10_000)));
+        assert!(!limiter.admit(CommandType::Challenge, ms(10_000)));
+    }
+
+    #[test]
+    fn unlimited_commands() {
+        let mut limiter = TokenBuckets::new([Limit {
+            command: CommandType::KeyExchange,
+            burst: 0,
+            period: Duration::from_millis(100),
+        }]);
+
+        assert!(!limiter.admit(CommandType::KeyExchange, ms(0)));
+        assert!(!limiter.admit(CommandType::KeyExchange, ms(1000)));
+        assert_eq!(limiter.tokens(CommandType::DeviceId), None);
+        for _ in 0..100 {
+            assert!(limiter.admit(CommandType::DeviceId, ms(0)));
+        }
+    }
+}
@@ -0,0 +1,47 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Per-phase timing of request handling.
+//!
+//! Handling a request happens in three [`Phase`]s: parsing it, running its
+//! handler, and serializing the reply. A server that is given a [`Timer`]
+//! reads its [`Clock`](crate::hardware::clock::Clock) at each boundary
+//! between phases, and reports how long each one took, keyed by the request's
+//! command type. This makes it possible to find slow commands without an
+//! external profiler.
+//!
+//! Servers without a `Timer` never read the clock for this.
+
+use crate::hardware::clock::Ticks;
+use crate::protocol::cerberus::CommandType;
+
+/// A phase of handling a request.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// Reading the request's payload and parsing it.
+    Parse,
+    /// Running the request's handler.
+    Handle,
+    /// Serializing and sending the reply, which may be an error.
+    Serialize,
+}
+
+/// A sink for per-phase timings.
+///
+/// `C` is the command type that timings are keyed by; it defaults to Cerberus
+/// command types. Any `FnMut(C, Phase, Ticks)` closure is a `Timer`.
+pub trait Timer<C = CommandType> {
+    /// Records that `phase` of handling a `command` request took `elapsed`.
+    fn record(&mut self, command: C, phase: Phase, elapsed: Ticks);
+}
+impl<C> dyn Timer<C> {} // Ensure object-safety.
+
+impl<C, F> Timer<C> for F
+where
+    F: FnMut(C, Phase, Ticks),
+{
+    fn record(&mut self, command: C, phase: Phase, elapsed: Ticks) {
+        self(command, phase, elapsed)
+    }
+}
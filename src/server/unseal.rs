@@ -0,0 +1,36 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Sealed-key release, performed by `Unseal`.
+//!
+//! Servers only call into an [`Unsealer`] once the device's measurements, as
+//! reported by [`LogStore::pcr()`], have been found to satisfy the request's
+//! [`Policy`].
+//!
+//! [`LogStore::pcr()`]: crate::server::LogStore::pcr
+
+use crate::protocol::cerberus;
+use crate::protocol::cerberus::unseal::Policy;
+use crate::Result;
+
+/// A device's engine for decrypting sealed keys.
+pub trait Unsealer {
+    /// Returns the length of the longest key [`Unsealer::unseal()`] may
+    /// release.
+    fn max_key_len(&self) -> usize;
+
+    /// Decrypts `blob`, writing the key sealed inside it to `key` and
+    /// returning its length.
+    ///
+    /// Implementations must check that `blob` was sealed to exactly `policy`,
+    /// such as by authenticating the policy along with the ciphertext, so that
+    /// a key cannot be released by pairing its blob with a weaker policy.
+    fn unseal(
+        &mut self,
+        policy: &Policy,
+        blob: &[u8],
+        key: &mut [u8],
+    ) -> Result<usize, cerberus::Error>;
+}
+impl dyn Unsealer {} // Ensure object-safety.
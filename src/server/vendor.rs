@@ -0,0 +1,34 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Handlers for vendor-defined messages.
+//!
+//! Manticore does not interpret the payload of a [`VendorCommand`]; instead,
+//! servers look up a [`VendorHandler`] by the command's vendor ID, and pass
+//! it the raw payload. Commands for unregistered vendor IDs are rejected as
+//! unsupported.
+//!
+//! [`VendorCommand`]: crate::protocol::cerberus::VendorCommand
+
+use crate::io;
+use crate::protocol::cerberus;
+use crate::Result;
+
+/// A handler for vendor-defined messages with a particular vendor ID.
+pub trait VendorHandler {
+    /// Returns the vendor ID whose messages this handler accepts.
+    fn vendor_id(&self) -> u16;
+
+    /// Handles a single vendor-defined message.
+    ///
+    /// `subcommand` and `payload` are taken verbatim from the request; the
+    /// payload of the response should be written to `out`.
+    fn handle(
+        &mut self,
+        subcommand: u8,
+        payload: &[u8],
+        out: &mut dyn io::Write,
+    ) -> Result<(), cerberus::Error>;
+}
+impl dyn VendorHandler {} // Ensure object-safety.
@@ -22,9 +22,24 @@
 //! use the P-256 curve. Implementations of [`Session`] must use this exact
 //! algorithm.
 //!
+//! # Encryption
+//!
+//! Messages within a session are encrypted with AES-256-GCM, using the
+//! session's AES key. An encrypted message consists of the ciphertext,
+//! followed by the [`AES_TAG_LEN`]-byte authentication tag and the
+//! [`AES_IV_LEN`]-byte IV it was encrypted with:
+//!
+//! ```text
+//! encrypted_message := ciphertext || tag || iv
+//! ```
+//!
+//! Both ends should go through [`seal_message()`] and [`open_message()`],
+//! so that they agree on this layout.
+//!
 //! [SP 800-108]: https://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-108.pdf
 
 use crate::crypto::hash;
+use crate::protocol::Nonce;
 use crate::Result;
 
 #[cfg(doc)]
@@ -42,6 +57,12 @@ pub enum Error {
     /// For example, [`Session::finish_ecdh()`] can only be called after
     /// [`Session::begin_ecdh()`] is called.
     BadStateTransition,
+    /// Indicates that the peer's public key was malformed, or was not a point
+    /// on the expected curve.
+    InvalidPublicKey,
+    /// Indicates that an encrypted message was malformed, or failed
+    /// authentication.
+    Unauthenticated,
     /// Indicates an unspecified, internal error.
     Unspecified,
 }
@@ -51,6 +72,12 @@ debug_from!(Error);
 /// A secret key returned by a [`Session`].
 pub type Key = [u8; 256 / 8];
 
+/// The length of an AES-GCM IV, in bytes.
+pub const AES_IV_LEN: usize = 12;
+
+/// The length of an AES-GCM authentication tag, in bytes.
+pub const AES_TAG_LEN: usize = 16;
+
 /// A manager for a Cerberus session, usable by either the host (the client)
 /// or the device (the server).
 ///
@@ -111,8 +138,8 @@ pub trait Session {
     /// This function destroys any prior existing session.
     fn create_session(
         &mut self,
-        req_nonce: &[u8],
-        resp_nonce: &[u8],
+        req_nonce: &Nonce,
+        resp_nonce: &Nonce,
     ) -> Result<(), Error>;
 
     /// Destroys a session without creating a new one.
@@ -122,6 +149,14 @@ pub trait Session {
     /// [`Self::begin_ecdh()`].
     fn ephemeral_bytes(&self) -> usize;
 
+    /// Returns whether `their_key` is a well-formed public key for the curve
+    /// used by this session's ECDH agreement.
+    ///
+    /// `their_key` must be an ECC key using the DER encoding. This check must
+    /// verify that the key describes a point on the curve: computing a shared
+    /// secret with an off-curve point can leak the private key.
+    fn is_on_curve(&self, their_key: &[u8]) -> bool;
+
     /// Begins an ECDH agreement.
     ///
     /// A fresh public key of length at most [`Session::ephemeral_bytes()`] is
@@ -138,7 +173,9 @@ pub trait Session {
     /// `their_key` should contain the public key from the response to an
     /// appropriate [`cerberus::key_exchange`] request.
     ///
-    /// `their_key` must be an ECC key using the DER encoding.
+    /// `their_key` must be an ECC key using the DER encoding. If it fails
+    /// [`Session::is_on_curve()`], this function must return
+    /// [`Error::InvalidPublicKey`].
     fn finish_ecdh(
         &mut self,
         hmac_algorithm: hash::Algo,
@@ -150,4 +187,70 @@ pub trait Session {
 
     /// Returns the current session's HMAC key, if a session exists.
     fn hmac_key(&self) -> Option<(hash::Algo, &Key)>;
+
+    /// Encrypts `data` in place with the current session's AES-GCM key,
+    /// returning the authentication tag.
+    ///
+    /// `iv` must never be used twice within the same session. Returns
+    /// [`Error::BadStateTransition`] if there is no active session.
+    fn encrypt(
+        &mut self,
+        iv: &[u8; AES_IV_LEN],
+        data: &mut [u8],
+    ) -> Result<[u8; AES_TAG_LEN], Error>;
+
+    /// Decrypts `data` in place with the current session's AES-GCM key,
+    /// checking it against `tag`.
+    ///
+    /// If `tag` does not match, this function must return
+    /// [`Error::Unauthenticated`], and the contents of `data` are
+    /// unspecified. Returns [`Error::BadStateTransition`] if there is no
+    /// active session.
+    fn decrypt(
+        &mut self,
+        iv: &[u8; AES_IV_LEN],
+        data: &mut [u8],
+        tag: &[u8; AES_TAG_LEN],
+    ) -> Result<(), Error>;
+}
+
+/// Encrypts the first `len` bytes of `message` in place, and appends the tag
+/// and `iv`, producing an encrypted message.
+///
+/// `message` must have room for [`AES_TAG_LEN`] + [`AES_IV_LEN`] bytes past
+/// `len`. Returns the length of the encrypted message.
+pub fn seal_message(
+    session: &mut (impl Session + ?Sized),
+    iv: &[u8; AES_IV_LEN],
+    message: &mut [u8],
+    len: usize,
+) -> Result<usize, Error> {
+    let total = len + AES_TAG_LEN + AES_IV_LEN;
+    let message = message.get_mut(..total).ok_or(Error::Unspecified)?;
+    let (data, trailer) = message.split_at_mut(len);
+    let tag = session.encrypt(iv, data)?;
+    trailer[..AES_TAG_LEN].copy_from_slice(&tag);
+    trailer[AES_TAG_LEN..].copy_from_slice(iv);
+    Ok(total)
+}
+
+/// Decrypts an encrypted message in place, returning the plaintext.
+///
+/// Returns [`Error::Unauthenticated`] if `message` is too short to be an
+/// encrypted message, or if it fails authentication.
+pub fn open_message<'a>(
+    session: &mut (impl Session + ?Sized),
+    message: &'a mut [u8],
+) -> Result<&'a mut [u8], Error> {
+    let len = message
+        .len()
+        .checked_sub(AES_TAG_LEN + AES_IV_LEN)
+        .ok_or(Error::Unauthenticated)?;
+    let (data, trailer) = message.split_at_mut(len);
+    let mut tag = [0; AES_TAG_LEN];
+    tag.copy_from_slice(&trailer[..AES_TAG_LEN]);
+    let mut iv = [0; AES_IV_LEN];
+    iv.copy_from_slice(&trailer[AES_TAG_LEN..]);
+    session.decrypt(&iv, data, &tag)?;
+    Ok(data)
 }
@@ -8,10 +8,13 @@
 
 use core::mem;
 
+use ring::aead;
 use ring::agreement as ecdh;
 use ring::hmac;
 
 use crate::crypto::hash;
+use crate::crypto::sig::Curve;
+use crate::protocol::Nonce;
 use crate::session;
 use crate::Result;
 
@@ -22,8 +25,8 @@ pub struct Session {
 }
 
 struct Connection {
-    req_nonce: Box<[u8]>,
-    resp_nonce: Box<[u8]>,
+    req_nonce: Nonce,
+    resp_nonce: Nonce,
     keys: Keys,
 }
 
@@ -56,12 +59,12 @@ impl Default for Session {
 impl session::Session for Session {
     fn create_session(
         &mut self,
-        req_nonce: &[u8],
-        resp_nonce: &[u8],
+        req_nonce: &Nonce,
+        resp_nonce: &Nonce,
     ) -> Result<(), session::Error> {
         self.conn = Some(Connection {
-            req_nonce: req_nonce.into(),
-            resp_nonce: resp_nonce.into(),
+            req_nonce: *req_nonce,
+            resp_nonce: *resp_nonce,
             keys: Keys::None,
         });
         Ok(())
@@ -77,6 +80,17 @@ impl session::Session for Session {
         64 + 1
     }
 
+    fn is_on_curve(&self, their_key: &[u8]) -> bool {
+        // Only uncompressed points are supported: 0x04 || x || y.
+        match their_key {
+            [4, point @ ..] if point.len() == 64 => {
+                let (x, y) = point.split_at(32);
+                Curve::NistP256.is_on_curve(x, y)
+            }
+            _ => false,
+        }
+    }
+
     fn begin_ecdh(
         &mut self,
         our_key: &mut [u8],
@@ -106,6 +120,7 @@ impl session::Session for Session {
         hmac_algorithm: hash::Algo,
         their_key: &[u8],
     ) -> Result<(), session::Error> {
+        let on_curve = self.is_on_curve(their_key);
         let conn = self
             .conn
             .as_mut()
@@ -115,6 +130,9 @@ impl session::Session for Session {
             Keys::Ecdh(our_key) => our_key,
             _ => return Err(fail!(session::Error::BadStateTransition)),
         };
+        if !on_curve {
+            return Err(fail!(session::Error::InvalidPublicKey));
+        }
         let their_key =
             ecdh::UnparsedPublicKey::new(&ecdh::ECDH_P256, their_key);
 
@@ -125,13 +143,13 @@ impl session::Session for Session {
             |material| {
                 let aes_key = sp800_108_hmac256(
                     material,
-                    &conn.req_nonce,
-                    &conn.resp_nonce,
+                    conn.req_nonce.as_ref(),
+                    conn.resp_nonce.as_ref(),
                 );
                 let hmac_key = sp800_108_hmac256(
                     material,
-                    &conn.resp_nonce,
-                    &conn.req_nonce,
+                    conn.resp_nonce.as_ref(),
+                    conn.req_nonce.as_ref(),
                 );
                 Ok(Keys::Session {
                     aes_key,
@@ -162,6 +180,58 @@ impl session::Session for Session {
             _ => None,
         }
     }
+
+    fn encrypt(
+        &mut self,
+        iv: &[u8; session::AES_IV_LEN],
+        data: &mut [u8],
+    ) -> Result<[u8; session::AES_TAG_LEN], session::Error> {
+        let tag = self
+            .aead_key()?
+            .seal_in_place_separate_tag(
+                aead::Nonce::assume_unique_for_key(*iv),
+                aead::Aad::empty(),
+                data,
+            )
+            .map_err(|_| session::Error::Unspecified)?;
+
+        let mut out = [0; session::AES_TAG_LEN];
+        out.copy_from_slice(tag.as_ref());
+        Ok(out)
+    }
+
+    fn decrypt(
+        &mut self,
+        iv: &[u8; session::AES_IV_LEN],
+        data: &mut [u8],
+        tag: &[u8; session::AES_TAG_LEN],
+    ) -> Result<(), session::Error> {
+        // ring wants the tag right after the ciphertext, so it gets checked
+        // against a copy instead.
+        let mut ciphertext = data.to_vec();
+        ciphertext.extend_from_slice(tag);
+        let plaintext = self
+            .aead_key()?
+            .open_in_place(
+                aead::Nonce::assume_unique_for_key(*iv),
+                aead::Aad::empty(),
+                &mut ciphertext,
+            )
+            .map_err(|_| session::Error::Unauthenticated)?;
+        data.copy_from_slice(plaintext);
+        Ok(())
+    }
+}
+
+impl Session {
+    /// Returns the AES-GCM key for the current session.
+    fn aead_key(&self) -> Result<aead::LessSafeKey, session::Error> {
+        let key = session::Session::aes_key(self)
+            .ok_or(session::Error::BadStateTransition)?;
+        let key = aead::UnboundKey::new(&aead::AES_256_GCM, key)
+            .map_err(|_| session::Error::Unspecified)?;
+        Ok(aead::LessSafeKey::new(key))
+    }
 }
 
 /// Computes an SP 800-108 KDF with the Cerberus parametrization.
@@ -191,8 +261,8 @@ mod test {
         let mut host = Session::new();
         let mut device = Session::new();
 
-        let req_nonce = [0x5eu8; 32];
-        let resp_nonce = [0x7au8; 32];
+        let req_nonce = Nonce([0x5e; 32]);
+        let resp_nonce = Nonce([0x7a; 32]);
 
         host.create_session(&req_nonce, &resp_nonce).unwrap();
         device.create_session(&req_nonce, &resp_nonce).unwrap();
@@ -211,4 +281,77 @@ mod test {
         assert_eq!(host.aes_key(), device.aes_key());
         assert_eq!(host.hmac_key(), device.hmac_key());
     }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn off_curve() {
+        let mut host = Session::new();
+        let mut device = Session::new();
+        let (req_nonce, resp_nonce) = (Nonce([1; 32]), Nonce([2; 32]));
+        host.create_session(&req_nonce, &resp_nonce).unwrap();
+        device.create_session(&req_nonce, &resp_nonce).unwrap();
+
+        let mut hkey = vec![0; host.ephemeral_bytes()];
+        let key_len = host.begin_ecdh(&mut hkey).unwrap();
+        let hkey = &mut hkey[..key_len];
+        assert!(device.is_on_curve(hkey));
+
+        // Perturbing the y-coordinate moves the point off the curve.
+        hkey[key_len - 1] ^= 1;
+        assert!(!device.is_on_curve(hkey));
+
+        let mut dkey = vec![0; device.ephemeral_bytes()];
+        device.begin_ecdh(&mut dkey).unwrap();
+        assert_eq!(
+            device
+                .finish_ecdh(hash::Algo::Sha256, hkey)
+                .unwrap_err()
+                .into_inner(),
+            session::Error::InvalidPublicKey
+        );
+        assert!(device.hmac_key().is_none());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn encryption() {
+        let mut host = Session::new();
+        let mut device = Session::new();
+        let (req_nonce, resp_nonce) = (Nonce([3; 32]), Nonce([4; 32]));
+        host.create_session(&req_nonce, &resp_nonce).unwrap();
+        device.create_session(&req_nonce, &resp_nonce).unwrap();
+
+        let iv = [0x11; session::AES_IV_LEN];
+        let mut message = [0; 5 + session::AES_TAG_LEN + session::AES_IV_LEN];
+        message[..5].copy_from_slice(b"hello");
+        // Without a session, there is no key to encrypt with.
+        assert!(session::seal_message(&mut host, &iv, &mut message, 5).is_err());
+
+        let mut hkey = vec![0; host.ephemeral_bytes()];
+        let hlen = host.begin_ecdh(&mut hkey).unwrap();
+        let mut dkey = vec![0; device.ephemeral_bytes()];
+        let dlen = device.begin_ecdh(&mut dkey).unwrap();
+        device
+            .finish_ecdh(hash::Algo::Sha256, &hkey[..hlen])
+            .unwrap();
+        host.finish_ecdh(hash::Algo::Sha256, &dkey[..dlen]).unwrap();
+
+        let len =
+            session::seal_message(&mut host, &iv, &mut message, 5).unwrap();
+        assert_eq!(len, message.len());
+        assert_ne!(&message[..5], b"hello");
+
+        let mut tampered = message;
+        tampered[0] ^= 1;
+        assert_eq!(
+            session::open_message(&mut device, &mut tampered)
+                .unwrap_err()
+                .into_inner(),
+            session::Error::Unauthenticated
+        );
+        assert!(session::open_message(&mut device, &mut [0; 4]).is_err());
+
+        let plaintext = session::open_message(&mut device, &mut message);
+        assert_eq!(&*plaintext.unwrap(), b"hello");
+    }
 }
@@ -83,21 +83,24 @@ impl Manifest {
                 let mut read_buf = Vec::new();
                 check!(r.read_to_end(&mut read_buf), "failed to read file");
                 let manifest = match manifest {
-                    ManifestType::Pfm => {
-                        let pfm: owned::Pfm = check!(
-                            serde_json::from_slice(&read_buf),
-                            "failed to parse PFM"
-                        );
-                        check!(
-                            pfm.sign(
-                                0x00,
-                                hash::Algo::Sha256,
-                                &mut hasher,
-                                &mut signer
-                            ),
-                            "failed to sign PFM"
-                        )
-                    }
+                    ManifestType::Pfm => sign::<owned::pfm::Element>(
+                        &read_buf,
+                        &mut hasher,
+                        &mut signer,
+                        "PFM",
+                    ),
+                    ManifestType::Cfm => sign::<owned::cfm::Element>(
+                        &read_buf,
+                        &mut hasher,
+                        &mut signer,
+                        "CFM",
+                    ),
+                    ManifestType::Pcd => sign::<owned::pcd::Element>(
+                        &read_buf,
+                        &mut hasher,
+                        &mut signer,
+                        "PCD",
+                    ),
                 };
 
                 check!(w.write_all(&manifest), "failed to write manifest");
@@ -133,38 +136,30 @@ impl Manifest {
                 let manifest_type = check!(r.read_le::<u16>(), "input len < 4");
 
                 match ManifestType::from_wire_value(manifest_type) {
-                    Some(ManifestType::Pfm) => {
-                        let parse = check!(
-                            owned::Pfm::parse(
-                                &read_buf,
-                                &mut hasher,
-                                engine.as_mut()
-                            ),
-                            "failed to parse PFM"
-                        );
-
-                        if parse.bad_signature {
-                            eprintln!("warning: signature verification failed");
-                        }
-                        if parse.bad_toc_hash {
-                            eprintln!("warning: TOC hash verification failed");
-                        }
-                        for idx in parse.bad_hashes {
-                            eprintln!(
-                                "warning: bad hash for toc entry {}",
-                                idx
-                            );
-                        }
-
-                        let r = match pretty {
-                            true => serde_json::to_writer_pretty(
-                                w,
-                                &parse.container,
-                            ),
-                            false => serde_json::to_writer(w, &parse.container),
-                        };
-                        check!(r, "failed to serialize PFM");
-                    }
+                    Some(ManifestType::Pfm) => show::<owned::pfm::Element>(
+                        &read_buf,
+                        &mut hasher,
+                        engine.as_mut(),
+                        pretty,
+                        w,
+                        "PFM",
+                    ),
+                    Some(ManifestType::Cfm) => show::<owned::cfm::Element>(
+                        &read_buf,
+                        &mut hasher,
+                        engine.as_mut(),
+                        pretty,
+                        w,
+                        "CFM",
+                    ),
+                    Some(ManifestType::Pcd) => show::<owned::pcd::Element>(
+                        &read_buf,
+                        &mut hasher,
+                        engine.as_mut(),
+                        pretty,
+                        w,
+                        "PCD",
+                    ),
                     None => {
                         check!(
                             Err(format!(
@@ -179,3 +174,56 @@ impl Manifest {
         }
     }
 }
+
+/// Parses a JSON-encoded manifest out of `json`, and signs it.
+fn sign<E>(
+    json: &[u8],
+    hasher: &mut ring::hash::Engine,
+    signer: &mut ring::rsa::Sign256,
+    name: &str,
+) -> Vec<u8>
+where
+    E: owned::Element + serde::de::DeserializeOwned,
+{
+    let manifest: owned::Container<E> =
+        check!(serde_json::from_slice(json), "failed to parse {}", name);
+    check!(
+        manifest.sign(0x00, hash::Algo::Sha256, hasher, signer),
+        "failed to sign {}",
+        name
+    )
+}
+
+/// Parses a signed manifest out of `bytes`, and writes it to `w` as JSON.
+fn show<E>(
+    bytes: &[u8],
+    hasher: &mut ring::hash::Engine,
+    verifier: Option<&mut ring::rsa::Verify256>,
+    pretty: bool,
+    w: Box<dyn Write>,
+    name: &str,
+) where
+    E: owned::FromUnowned + serde::Serialize,
+{
+    let parse = check!(
+        owned::Container::<E>::parse(bytes, hasher, verifier),
+        "failed to parse {}",
+        name
+    );
+
+    if parse.bad_signature {
+        eprintln!("warning: signature verification failed");
+    }
+    if parse.bad_toc_hash {
+        eprintln!("warning: TOC hash verification failed");
+    }
+    for idx in parse.bad_hashes {
+        eprintln!("warning: bad hash for toc entry {}", idx);
+    }
+
+    let r = match pretty {
+        true => serde_json::to_writer_pretty(w, &parse.container),
+        false => serde_json::to_writer(w, &parse.container),
+    };
+    check!(r, "failed to serialize {}", name);
+}
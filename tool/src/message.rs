@@ -34,10 +34,30 @@ macro_rules! proto_match {
             cerberus::GetDigests,
             cerberus::GetCert,
             cerberus::GetHostState,
+            cerberus::GetLog,
+            cerberus::ClearLog,
+            cerberus::GetDeviceState,
             cerberus::Challenge,
             cerberus::KeyExchange,
+            cerberus::ResetConfig,
+            cerberus::PreparePfm,
+            cerberus::UpdatePfm,
+            cerberus::ActivatePfm,
+            cerberus::PrepareCfm,
+            cerberus::UpdateCfm,
+            cerberus::ActivateCfm,
+            cerberus::PreparePcd,
+            cerberus::UpdatePcd,
+            cerberus::ActivatePcd,
+            cerberus::GetConfigIds,
             cerberus::ResetCounter,
             cerberus::RequestCounter,
+            cerberus::Unseal,
+            cerberus::BeginVerify,
+            cerberus::VerifyChunk,
+            cerberus::FinishVerify,
+            cerberus::SetHostState,
+            cerberus::VendorCommand,
         })
     };
     (($cmd:expr, $is_req:expr, $mty:ident, $expr:expr) in {$($t:ty,)*}) => {